@@ -486,6 +486,22 @@ print(json.dumps({
 }
 
 fn configure(interpreter_config: &InterpreterConfig) -> Result<String, String> {
+    if interpreter_config.version.major == 2 {
+        // fail PYTHON_SYS_EXECUTABLE=python2 cargo ...
+        // This has to be checked before anything else: the generated wrappers rely on Python 3
+        // only features like keyword-only arguments, so a Python 2 build would be silently wrong.
+        let interpreter = if interpreter_config.executable.is_empty() {
+            "The configured interpreter".to_string()
+        } else {
+            format!("`{}`", interpreter_config.executable)
+        };
+        return Err(format!(
+            "Python 2 is not supported. {} is Python {}; set PYTHON_SYS_EXECUTABLE to a \
+             Python 3.{} or newer interpreter",
+            interpreter, interpreter_config.version, PY3_MIN_MINOR
+        ));
+    }
+
     if let Some(minor) = interpreter_config.version.minor {
         if minor < PY3_MIN_MINOR {
             return Err(format!(
@@ -515,11 +531,6 @@ fn configure(interpreter_config: &InterpreterConfig) -> Result<String, String> {
         flags += "CFG_PyPy";
     };
 
-    if interpreter_config.version.major == 2 {
-        // fail PYTHON_SYS_EXECUTABLE=python2 cargo ...
-        return Err("Python 2 is not supported".to_string());
-    }
-
     if env::var_os("CARGO_FEATURE_ABI3").is_some() {
         println!("cargo:rustc-cfg=Py_LIMITED_API");
     }
@@ -532,13 +543,82 @@ fn configure(interpreter_config: &InterpreterConfig) -> Result<String, String> {
     }
     println!("cargo:rustc-cfg=Py_3");
 
+    // Exposed at runtime by `pyo3::build_info`
+    let abiflags = interpreter_config
+        .ld_version
+        .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    println!(
+        "cargo:rustc-env=PYO3_PYTHON_VERSION={}",
+        interpreter_config.version
+    );
+    println!(
+        "cargo:rustc-env=PYO3_PYTHON_IMPLEMENTATION={:?}",
+        interpreter_config.version.implementation
+    );
+    println!("cargo:rustc-env=PYO3_PYTHON_ABIFLAGS={}", abiflags);
+
     Ok(flags)
 }
 
+/// Exposes the enabled features to `pyo3::build_info::features`. Cargo passes them to build
+/// scripts as `CARGO_FEATURE_<NAME>`, upper-cased and with `-` replaced by `_`, so the names are
+/// read from Cargo.toml: the keys of `[features]` and the optional dependencies, which are either
+/// inline tables in `[dependencies]` or `[dependencies.name]` tables, like in the manifest of a
+/// published crate.
+fn emit_features() -> Result<(), String> {
+    let manifest = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.toml");
+    let manifest = std::fs::read_to_string(&manifest)
+        .map_err(|e| format!("Failed to read {}: {}", manifest.display(), e))?;
+    let header = Regex::new(r"^\[\s*([^\]]*?)\s*\]").unwrap();
+    let key = Regex::new(r"^([A-Za-z0-9_-]+)\s*=\s*(.*)$").unwrap();
+    let optional = Regex::new(r"[{,]\s*optional\s*=\s*true\b").unwrap();
+
+    let mut section = String::new();
+    let mut names = Vec::new();
+    for line in manifest.lines().map(str::trim) {
+        if let Some(captures) = header.captures(line) {
+            section = captures[1].to_string();
+            continue;
+        }
+        let captures = match key.captures(line) {
+            Some(captures) => captures,
+            None => continue,
+        };
+        let (name, value) = (&captures[1], &captures[2]);
+        if (section == "features" && name != "default")
+            || (section == "dependencies" && optional.is_match(value))
+        {
+            names.push(name.to_string());
+        } else if section.starts_with("dependencies.") && name == "optional" && value == "true" {
+            names.push(section["dependencies.".len()..].to_string());
+        }
+    }
+
+    let mut features: Vec<String> = names
+        .into_iter()
+        .filter(|name| {
+            let var = format!("CARGO_FEATURE_{}", name.to_uppercase().replace('-', "_"));
+            env::var_os(var).is_some()
+        })
+        .collect();
+    features.sort();
+    features.dedup();
+    println!("cargo:rustc-env=PYO3_FEATURES={}", features.join(","));
+    Ok(())
+}
+
 fn check_rustc_version() {
+    // Without specialization, any recent compiler works
+    if env::var_os("CARGO_FEATURE_STABLE").is_some() {
+        return;
+    }
+
     let channel = Channel::read().expect("Failed to determine rustc channel");
     if !channel.supports_features() {
-        panic!("Error: pyo3 requires a nightly or dev version of Rust.");
+        panic!(
+            "Error: pyo3 requires a nightly or dev version of Rust, \
+             or the `stable` feature to build with a stable compiler."
+        );
     }
 
     let actual_version = Version::read().expect("Failed to determine the rustc version");
@@ -643,6 +723,8 @@ fn main() -> Result<(), String> {
         // TODO: Find out how we can set -undefined dynamic_lookup here (if this is possible)
     }
 
+    emit_features()?;
+
     let env_vars = ["LD_LIBRARY_PATH", "PATH", "PYTHON_SYS_EXECUTABLE", "LIB"];
 
     for var in env_vars.iter() {
@@ -11,6 +11,9 @@ function Invoke-Call
 }
 
 Invoke-Call { cargo test --verbose --features="num-bigint num-complex" }
+Invoke-Call { cargo test --verbose --features="serde pickle-serde" }
+Invoke-Call { pip install numpy }
+Invoke-Call { cargo test --verbose --features="numpy" }
 
 foreach ($example in Get-ChildItem -dir "examples")
 {
@@ -3,7 +3,10 @@ set -ex
 
 # run `cargo test` only if testing against cpython.
 if ! [[ $FEATURES == *"pypy"* ]]; then
-  cargo test --features "$FEATURES num-bigint num-complex"
+  pip install numpy
+  cargo test --features "$FEATURES num-bigint num-complex stubs convert-trace rayon serde pickle-serde gil-metrics debug-gil-checks log numpy"
+  cargo test --features "$FEATURES serde serde_json" --test test_serde
+  cargo test --features "$FEATURES compact-codegen"
   ( cd pyo3-derive-backend; cargo test )
 else
   # check that pypy at least builds
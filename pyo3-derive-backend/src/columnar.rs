@@ -0,0 +1,78 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//! Code generation for `#[derive(Columnar)]`
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::ext::IdentExt;
+
+/// Implements `Columnar` for a struct with named fields, with one column per field
+pub fn build_columnar(ast: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(ref fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "#[derive(Columnar)] can only be used with structs with named fields",
+            ))
+        }
+    };
+
+    let ident = &ast.ident;
+    let mut generics = ast.generics.clone();
+    if !generics.params.is_empty() {
+        // The fields may use the type parameters, so require the conversions for their types
+        let where_clause = generics.make_where_clause();
+        for field in fields.iter() {
+            let ty = &field.ty;
+            where_clause.predicates.push(syn::parse_quote! {
+                #ty: pyo3::ToPyObject + for<'a> pyo3::FromPyObject<'a>
+            });
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let field_idents: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let names: Vec<_> = field_idents
+        .iter()
+        .map(|f| syn::LitStr::new(&f.unraw().to_string(), f.span()))
+        .collect();
+    let columns: Vec<_> = (0..field_idents.len())
+        .map(|i| syn::Ident::new(&format!("column{}", i), Span::call_site()))
+        .collect();
+
+    Ok(quote! {
+        impl #impl_generics pyo3::columnar::Columnar for #ident #ty_generics #where_clause {
+            const COLUMNS: &'static [&'static str] = &[#(#names),*];
+
+            fn to_columns<'p>(
+                py: pyo3::Python<'p>,
+                rows: &[Self],
+            ) -> pyo3::PyResult<&'p pyo3::types::PyDict> {
+                let columns = pyo3::types::PyDict::new(py);
+                #(
+                    columns.set_item(
+                        #names,
+                        pyo3::types::PyList::new(py, rows.iter().map(|row| &row.#field_idents)),
+                    )?;
+                )*
+                Ok(columns)
+            }
+
+            fn from_columns(columns: &pyo3::types::PyDict) -> pyo3::PyResult<Vec<Self>> {
+                let mut len = None;
+                #(
+                    let mut #columns =
+                        pyo3::columnar::extract_column(columns, #names, &mut len)?.into_iter();
+                )*
+                Ok((0..len.unwrap_or(0))
+                    .map(|_| #ident {
+                        #(#field_idents: #columns.next().unwrap()),*
+                    })
+                    .collect())
+            }
+        }
+    })
+}
@@ -205,14 +205,14 @@ pub const DESCR: Proto = Proto {
             name: "__set__",
             arg1: "Inst",
             arg2: "Value",
-            pyres: true,
+            pyres: false,
             proto: "pyo3::class::descr::PyDescrSetProtocol",
         },
         MethodProto::Binary {
-            name: "__det__",
+            name: "__delete__",
             arg: "Inst",
             pyres: false,
-            proto: "pyo3::class::descr::PyDescrDelProtocol",
+            proto: "pyo3::class::descr::PyDescrDeleteProtocol",
         },
         MethodProto::Binary {
             name: "__set_name__",
@@ -500,10 +500,9 @@ pub const NUM: Proto = Proto {
             pyres: true,
             proto: "pyo3::class::number::PyNumberRDivmodProtocol",
         },
-        MethodProto::Ternary {
+        MethodProto::Binary {
             name: "__rpow__",
-            arg1: "Other",
-            arg2: "Modulo",
+            arg: "Other",
             pyres: true,
             proto: "pyo3::class::number::PyNumberRPowProtocol",
         },
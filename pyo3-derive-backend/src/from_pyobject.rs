@@ -0,0 +1,185 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//! Code generation for `#[derive(FromPyObject)]`
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::ext::IdentExt;
+
+/// Where the value of a named field is read from
+enum FieldSource {
+    Attribute(syn::LitStr),
+    Item(syn::LitStr),
+}
+
+/// Implements `FromPyObject` for a struct, reading its fields from the object, or for an enum,
+/// trying its variants in order
+pub fn build_from_pyobject(ast: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &ast.ident;
+    let body = match ast.data {
+        syn::Data::Struct(ref data) => {
+            let path = quote! { #ident };
+            let build = build_fields(&path, &data.fields)?;
+            quote! { #build }
+        }
+        syn::Data::Enum(ref data) => {
+            let enum_name = syn::LitStr::new(&ident.unraw().to_string(), ident.span());
+            let mut variants = Vec::new();
+            for variant in data.variants.iter() {
+                let variant_ident = &variant.ident;
+                let variant_name =
+                    syn::LitStr::new(&variant_ident.unraw().to_string(), variant_ident.span());
+                let path = quote! { #ident::#variant_ident };
+                let build = build_fields(&path, &variant.fields)?;
+                variants.push(quote! {
+                    match (|| -> pyo3::PyResult<Self> { #build })() {
+                        Ok(value) => return Ok(value),
+                        Err(err) => errors.push((#variant_name, err)),
+                    }
+                });
+            }
+            quote! {
+                let mut errors = Vec::new();
+                #(#variants)*
+                Err(pyo3::derive_utils::failed_to_extract_enum(obj, #enum_name, errors))
+            }
+        }
+        syn::Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "#[derive(FromPyObject)] can only be used with structs and enums",
+            ))
+        }
+    };
+
+    // The lifetime of the source object, the lifetime of the type if it has one
+    let mut generics = ast.generics.clone();
+    let lifetime = match generics.lifetimes().next() {
+        Some(def) => def.lifetime.clone(),
+        None => {
+            let lifetime = syn::Lifetime::new("'source", Span::call_site());
+            generics.params.insert(
+                0,
+                syn::GenericParam::Lifetime(syn::LifetimeDef::new(lifetime.clone())),
+            );
+            lifetime
+        }
+    };
+    let type_params: Vec<_> = generics.type_params().map(|p| p.ident.clone()).collect();
+    if !type_params.is_empty() {
+        let where_clause = generics.make_where_clause();
+        for param in type_params.iter() {
+            where_clause.predicates.push(syn::parse_quote! {
+                #param: pyo3::FromPyObject<#lifetime>
+            });
+        }
+    }
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics pyo3::FromPyObject<#lifetime> for #ident #ty_generics #where_clause {
+            fn extract(obj: &#lifetime pyo3::types::PyAny) -> pyo3::PyResult<Self> {
+                #body
+            }
+        }
+    })
+}
+
+/// Builds the expression extracting `path` with `fields` from `obj`: named fields from attributes
+/// or items, a single unnamed field from `obj` itself and several from a tuple
+fn build_fields(path: &TokenStream, fields: &syn::Fields) -> syn::Result<TokenStream> {
+    match fields {
+        syn::Fields::Named(ref fields) => {
+            let mut values = Vec::new();
+            for field in fields.named.iter() {
+                let ident = field.ident.as_ref().unwrap().clone();
+                let ty = &field.ty;
+                let value = match parse_field_source(field)? {
+                    FieldSource::Attribute(name) => {
+                        quote! { pyo3::ObjectProtocol::getattr(obj, #name)? }
+                    }
+                    FieldSource::Item(key) => {
+                        quote! { pyo3::ObjectProtocol::get_item(obj, #key)? }
+                    }
+                };
+                values.push(quote! {
+                    #ident: <#ty as pyo3::FromPyObject>::extract(#value)?
+                });
+            }
+            Ok(quote! { Ok(#path { #(#values),* }) })
+        }
+        syn::Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+            let ty = &fields.unnamed[0].ty;
+            Ok(quote! { Ok(#path(<#ty as pyo3::FromPyObject>::extract(obj)?)) })
+        }
+        syn::Fields::Unnamed(ref fields) => {
+            let types = fields.unnamed.iter().map(|f| &f.ty);
+            let values: Vec<_> = (0..fields.unnamed.len())
+                .map(|i| syn::Ident::new(&format!("field{}", i), Span::call_site()))
+                .collect();
+            Ok(quote! {{
+                let (#(#values),*): (#(#types),*) = pyo3::FromPyObject::extract(obj)?;
+                Ok(#path(#(#values),*))
+            }})
+        }
+        syn::Fields::Unit => Err(syn::Error::new_spanned(
+            path,
+            "#[derive(FromPyObject)] requires fields to extract",
+        )),
+    }
+}
+
+/// Parses `#[pyo3(attribute)]`, `#[pyo3(item)]` and their forms with a name, like
+/// `#[pyo3(item("key"))]`. Fields are read from the attribute of the same name by default.
+fn parse_field_source(field: &syn::Field) -> syn::Result<FieldSource> {
+    let ident = field.ident.as_ref().unwrap().unraw();
+    let default_name = syn::LitStr::new(&ident.to_string(), ident.span());
+    let mut source = None;
+    for attr in field.attrs.iter() {
+        let list = match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) if list.path.is_ident("pyo3") => list,
+            _ => continue,
+        };
+        for meta in list.nested.iter() {
+            let (path, name) = match meta {
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) => (path, default_name.clone()),
+                syn::NestedMeta::Meta(syn::Meta::List(list)) if list.nested.len() == 1 => {
+                    match list.nested.first() {
+                        Some(syn::NestedMeta::Lit(syn::Lit::Str(name))) => {
+                            (&list.path, name.clone())
+                        }
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                &list.nested,
+                                "expected the name as a string literal",
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "expected `attribute` or `item`",
+                    ))
+                }
+            };
+            if source.is_some() {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "only one of `attribute` and `item` can be used",
+                ));
+            }
+            source = Some(if path.is_ident("attribute") {
+                FieldSource::Attribute(name)
+            } else if path.is_ident("item") {
+                FieldSource::Item(name)
+            } else {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    "expected `attribute` or `item`",
+                ));
+            });
+        }
+    }
+    Ok(source.unwrap_or(FieldSource::Attribute(default_name)))
+}
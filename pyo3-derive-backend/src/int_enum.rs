@@ -0,0 +1,103 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//! Code generation for `#[derive(IntEnum)]`
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+
+const REPRS: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "usize",
+];
+
+/// Implements `IntEnum`, the conversions to and from Python and `TryFrom` of the `#[repr]` type
+/// for a fieldless enum
+pub fn build_int_enum(ast: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    let variants = match ast.data {
+        syn::Data::Enum(ref data) => &data.variants,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "#[derive(IntEnum)] can only be used with enums",
+            ))
+        }
+    };
+    for variant in variants.iter() {
+        if variant.fields != syn::Fields::Unit {
+            return Err(syn::Error::new_spanned(
+                &variant.fields,
+                "#[derive(IntEnum)] requires variants without fields",
+            ));
+        }
+    }
+    if !ast.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &ast.generics,
+            "#[derive(IntEnum)] can't be used with generic enums",
+        ));
+    }
+    let repr = int_repr(ast)?;
+
+    let ident = &ast.ident;
+    let name = syn::LitStr::new(&ident.unraw().to_string(), ident.span());
+    let variant_idents: Vec<_> = variants.iter().map(|v| &v.ident).collect();
+    let variant_names: Vec<_> = variant_idents
+        .iter()
+        .map(|v| syn::LitStr::new(&v.unraw().to_string(), v.span()))
+        .collect();
+
+    Ok(quote! {
+        impl pyo3::int_enum::IntEnum for #ident {
+            const NAME: &'static str = #name;
+            const VARIANTS: &'static [(&'static str, i128, Self)] = &[
+                #((#variant_names, #ident::#variant_idents as i128, #ident::#variant_idents)),*
+            ];
+        }
+
+        impl std::convert::TryFrom<#repr> for #ident {
+            type Error = pyo3::int_enum::InvalidValue;
+
+            fn try_from(value: #repr) -> Result<Self, Self::Error> {
+                pyo3::int_enum::from_value(value as i128)
+            }
+        }
+
+        impl pyo3::ToPyObject for #ident {
+            fn to_object(&self, py: pyo3::Python) -> pyo3::PyObject {
+                (*self as #repr).to_object(py)
+            }
+        }
+
+        impl pyo3::IntoPy<pyo3::PyObject> for #ident {
+            fn into_py(self, py: pyo3::Python) -> pyo3::PyObject {
+                (self as #repr).into_py(py)
+            }
+        }
+
+        impl<'source> pyo3::FromPyObject<'source> for #ident {
+            fn extract(obj: &'source pyo3::types::PyAny) -> pyo3::PyResult<Self> {
+                pyo3::int_enum::extract(obj)
+            }
+        }
+    })
+}
+
+/// The integer type of the `#[repr]` attribute
+fn int_repr(ast: &syn::DeriveInput) -> syn::Result<syn::Ident> {
+    for attr in ast.attrs.iter().filter(|attr| attr.path.is_ident("repr")) {
+        if let syn::Meta::List(list) = attr.parse_meta()? {
+            for nested in list.nested.iter() {
+                if let syn::NestedMeta::Meta(syn::Meta::Path(path)) = nested {
+                    if let Some(ident) = path.get_ident() {
+                        if REPRS.contains(&ident.to_string().as_str()) {
+                            return Ok(ident.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Err(syn::Error::new_spanned(
+        &ast.ident,
+        "#[derive(IntEnum)] requires an integer representation like #[repr(u8)]",
+    ))
+}
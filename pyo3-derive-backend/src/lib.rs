@@ -3,19 +3,28 @@
 
 #![recursion_limit = "1024"]
 
+mod columnar;
 mod defs;
+mod from_pyobject;
 mod func;
+mod int_enum;
 mod method;
 mod module;
+mod pickle;
 mod pyclass;
 mod pyfunction;
 mod pyimpl;
 mod pymethod;
 mod pyproto;
+mod stubs;
 mod utils;
 
-pub use module::{add_fn_to_module, process_functions_in_module, py_init};
-pub use pyclass::{build_py_class, PyClassArgs};
+pub use columnar::build_columnar;
+pub use from_pyobject::build_from_pyobject;
+pub use int_enum::build_int_enum;
+pub use module::{add_fn_to_module, process_functions_in_module, py_init, PyModuleArgs};
+pub use pickle::build_pickle;
+pub use pyclass::{build_py_class, build_py_enum, PyClassArgs};
 pub use pyfunction::{build_py_function, PyFunctionAttr};
 pub use pyimpl::{build_py_methods, impl_methods};
 pub use pyproto::build_py_proto;
@@ -3,7 +3,7 @@
 use crate::pyfunction::Argument;
 use crate::pyfunction::{parse_name_attribute, PyFunctionAttr};
 use crate::utils;
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use quote::ToTokens;
 use syn::ext::IdentExt;
@@ -24,14 +24,30 @@ pub struct FnArg<'a> {
 pub enum FnType {
     Getter,
     Setter,
+    Deleter,
     Fn,
     FnNew,
     FnCall,
     FnClass,
     FnStatic,
+    ClassAttribute,
     PySelf(syn::TypeReference),
 }
 
+/// The calling convention of the C function wrapping a function or method, i.e. the `ml_flags` of
+/// its `PyMethodDef`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CallingConvention {
+    /// `METH_NOARGS`
+    NoArgs,
+    /// `METH_O`, which takes one positional argument
+    O,
+    /// `METH_FASTCALL | METH_KEYWORDS`, or `METH_VARARGS | METH_KEYWORDS` on Pythons without it
+    Fast,
+    /// `METH_VARARGS | METH_KEYWORDS`
+    Varargs,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct FnSpec<'a> {
     pub tp: FnType,
@@ -40,6 +56,8 @@ pub struct FnSpec<'a> {
     // Wrapped python name. This should not have any leading r#.
     // r# can be removed by syn::ext::IdentExt::unraw()
     pub python_name: syn::Ident,
+    // Whether the method takes `&mut self`, which needs a mutable borrow of the object
+    pub mut_self: bool,
     pub attrs: Vec<Argument>,
     pub args: Vec<FnArg<'a>>,
     pub output: syn::Type,
@@ -68,11 +86,13 @@ impl<'a> FnSpec<'a> {
         } = parse_method_attributes(meth_attrs, allow_custom_name)?;
 
         let mut has_self = false;
+        let mut mut_self = false;
         let mut arguments = Vec::new();
         for input in sig.inputs.iter() {
             match input {
-                syn::FnArg::Receiver(_) => {
+                syn::FnArg::Receiver(recv) => {
                     has_self = true;
+                    mut_self = recv.mutability.is_some();
                 }
                 syn::FnArg::Typed(syn::PatType {
                     ref pat, ref ty, ..
@@ -114,6 +134,13 @@ impl<'a> FnSpec<'a> {
 
         let ty = get_return_info(&sig.output);
 
+        if fn_type == FnType::ClassAttribute && (has_self || !arguments.is_empty()) {
+            return Err(syn::Error::new_spanned(
+                &sig.inputs,
+                "#[classattr] can only be used on functions without arguments",
+            ));
+        }
+
         if fn_type == FnType::Fn && !has_self {
             if arguments.is_empty() {
                 return Err(syn::Error::new_spanned(
@@ -128,12 +155,13 @@ impl<'a> FnSpec<'a> {
             fn_type = FnType::PySelf(tp);
         }
 
-        // "Tweak" getter / setter names: strip off set_ and get_ if needed
-        if let FnType::Getter | FnType::Setter = &fn_type {
+        // "Tweak" getter / setter / deleter names: strip off get_, set_ and del_ if needed
+        if let FnType::Getter | FnType::Setter | FnType::Deleter = &fn_type {
             if python_name.is_none() {
                 let prefix = match &fn_type {
                     FnType::Getter => "get_",
                     FnType::Setter => "set_",
+                    FnType::Deleter => "del_",
                     _ => unreachable!(),
                 };
 
@@ -159,28 +187,47 @@ impl<'a> FnSpec<'a> {
 
         let text_signature = match &fn_type {
             FnType::Fn | FnType::PySelf(_) | FnType::FnClass | FnType::FnStatic => {
-                utils::parse_text_signature_attrs(&mut *meth_attrs, name)?
+                utils::parse_text_signature_attrs(&mut *meth_attrs, &python_name)?
             }
             FnType::FnNew => parse_erroneous_text_signature(
                 "text_signature not allowed on __new__; if you want to add a signature on \
                  __new__, put it on the struct definition instead",
             )?,
-            FnType::FnCall | FnType::Getter | FnType::Setter => {
+            FnType::FnCall
+            | FnType::Getter
+            | FnType::Setter
+            | FnType::Deleter
+            | FnType::ClassAttribute => {
                 parse_erroneous_text_signature("text_signature not allowed with this attribute")?
             }
         };
 
-        let doc = utils::get_doc(&meth_attrs, text_signature, true)?;
-
-        Ok(FnSpec {
+        let mut spec = FnSpec {
             tp: fn_type,
             name,
             python_name,
+            mut_self,
             attrs: fn_attrs,
             args: arguments,
             output: ty,
-            doc,
-        })
+            doc: syn::LitStr::new("", Span::call_site()),
+        };
+
+        let text_signature = text_signature.or_else(|| match spec.tp {
+            _ if utils::has_doc_signature(meth_attrs) => None,
+            FnType::Fn | FnType::PySelf(_) => spec.default_text_signature(Some("$self")),
+            FnType::FnClass => spec.default_text_signature(Some("$cls")),
+            FnType::FnStatic => spec.default_text_signature(None),
+            FnType::FnNew
+            | FnType::FnCall
+            | FnType::Getter
+            | FnType::Setter
+            | FnType::Deleter
+            | FnType::ClassAttribute => None,
+        });
+        spec.doc = utils::get_doc(&meth_attrs, text_signature, true)?;
+
+        Ok(spec)
     }
 
     pub fn is_args(&self, name: &syn::Ident) -> bool {
@@ -254,6 +301,156 @@ impl<'a> FnSpec<'a> {
         }
         false
     }
+
+    /// Picks the cheapest calling convention for the arguments. Functions without arguments use
+    /// `METH_NOARGS`. `METH_O` can't be called with keywords, so it is only used for dunder methods
+    /// with one argument, which Python calls with a positional argument. Functions with `*args` or
+    /// `**kwargs` use `METH_VARARGS`, which already passes them as a tuple and a dict.
+    pub fn calling_convention(&self) -> CallingConvention {
+        match self.tp {
+            FnType::Fn | FnType::PySelf(_) | FnType::FnClass | FnType::FnStatic => {}
+            FnType::FnNew
+            | FnType::FnCall
+            | FnType::Getter
+            | FnType::Setter
+            | FnType::Deleter
+            | FnType::ClassAttribute => return CallingConvention::Varargs,
+        }
+        let python_name = self.python_name.unraw().to_string();
+        if python_name == "__call__" {
+            // `tp_call` always takes a tuple and a dict
+            return CallingConvention::Varargs;
+        }
+        let args: Vec<&FnArg> = self.args.iter().filter(|arg| !arg.py).collect();
+        if args
+            .iter()
+            .any(|arg| self.is_args(arg.name) || self.is_kwargs(arg.name))
+        {
+            return CallingConvention::Varargs;
+        }
+        match args.as_slice() {
+            [] => CallingConvention::NoArgs,
+            [arg]
+                if python_name.starts_with("__")
+                    && python_name.ends_with("__")
+                    && arg.optional.is_none()
+                    && self.default_value(arg.name).is_none() =>
+            {
+                CallingConvention::O
+            }
+            _ => CallingConvention::Fast,
+        }
+    }
+
+    /// Builds the `__text_signature__` of a function or method without a `#[text_signature]`
+    /// attribute from its arguments, so that it is shown by `help()` and `inspect.signature`.
+    /// `receiver` is the name of the first argument of methods, e.g. `$self`. Returns `None` if
+    /// the arguments can't be written as a Python signature.
+    pub fn default_text_signature(&self, receiver: Option<&str>) -> Option<syn::LitStr> {
+        let params = self.python_params(receiver, None)?;
+        Some(syn::LitStr::new(
+            &format!("{}({})", self.python_name.unraw(), params.join(", ")),
+            Span::call_site(),
+        ))
+    }
+
+    /// Writes the arguments as the parameters of a Python signature, starting with `receiver`.
+    /// With `annotate`, the parameters are annotated with the Python type of the arguments and
+    /// `*args` and `**kwargs` with `Any`. Returns `None` if the arguments can't be written as a
+    /// Python signature.
+    pub fn python_params(
+        &self,
+        receiver: Option<&str>,
+        annotate: Option<&dyn Fn(&syn::Type) -> String>,
+    ) -> Option<Vec<String>> {
+        let mut positional = Vec::new();
+        let mut keyword_only = Vec::new();
+        let mut varargs = None;
+        let mut kwargs = None;
+        let mut has_optional = false;
+
+        for arg in self.args.iter() {
+            let name = arg.name.unraw().to_string();
+            if arg.py {
+                continue;
+            } else if self.is_args(arg.name) {
+                varargs = Some(name);
+                continue;
+            } else if self.is_kwargs(arg.name) {
+                kwargs = Some(name);
+                continue;
+            }
+
+            let name = match annotate {
+                Some(annotate) => format!("{}: {}", name, annotate(arg.ty)),
+                None => name,
+            };
+            let default = match self.default_value(arg.name) {
+                Some(value) => Some(python_default(value)),
+                None if arg.optional.is_some() => Some("None".to_string()),
+                None => None,
+            };
+            if self.is_kw_only(arg.name) {
+                keyword_only.push((name, default));
+            } else {
+                // Python doesn't allow a required argument after an optional one
+                if default.is_none() && has_optional {
+                    return None;
+                }
+                has_optional |= default.is_some();
+                positional.push((name, default));
+            }
+        }
+
+        // PEP 8 puts spaces around the `=` only for annotated parameters
+        let equals = if annotate.is_some() { " = " } else { "=" };
+        let format = |(name, default): (String, Option<String>)| match default {
+            Some(default) => format!("{}{}{}", name, equals, default),
+            None => name,
+        };
+        let variadic = |prefix: &str, name: String| match annotate {
+            Some(_) => format!("{}{}: Any", prefix, name),
+            None => format!("{}{}", prefix, name),
+        };
+        let mut params: Vec<String> = receiver.map(str::to_string).into_iter().collect();
+        params.extend(positional.into_iter().map(format));
+        if self.calling_convention() == CallingConvention::O {
+            params.push("/".to_string());
+        }
+        if let Some(varargs) = varargs {
+            params.push(variadic("*", varargs));
+        } else if !keyword_only.is_empty() {
+            params.push("*".to_string());
+        }
+        params.extend(keyword_only.into_iter().map(format));
+        if let Some(kwargs) = kwargs {
+            params.push(variadic("**", kwargs));
+        }
+        Some(params)
+    }
+}
+
+/// Writes the default value of an argument as a Python literal, or as `...` if it isn't one.
+fn python_default(value: TokenStream) -> String {
+    match syn::parse2::<syn::Expr>(value) {
+        Ok(syn::Expr::Lit(syn::ExprLit { lit, .. })) => match lit {
+            syn::Lit::Int(int) => int.base10_digits().to_string(),
+            syn::Lit::Float(float) => float.base10_digits().to_string(),
+            syn::Lit::Bool(boolean) if boolean.value => "True".to_string(),
+            syn::Lit::Bool(_) => "False".to_string(),
+            _ => "...".to_string(),
+        },
+        Ok(syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        })) => match python_default(expr.into_token_stream()).as_str() {
+            "..." => "...".to_string(),
+            positive => format!("-{}", positive),
+        },
+        Ok(syn::Expr::Path(ref path)) if path.path.is_ident("None") => "None".to_string(),
+        _ => "...".to_string(),
+    }
 }
 
 pub fn is_ref(name: &syn::Ident, ty: &syn::Type) -> bool {
@@ -374,24 +571,25 @@ fn parse_method_attributes(
                     res = Some(FnType::FnClass)
                 } else if name.is_ident("staticmethod") {
                     res = Some(FnType::FnStatic)
-                } else if name.is_ident("setter") || name.is_ident("getter") {
+                } else if name.is_ident("classattr") {
+                    res = Some(FnType::ClassAttribute)
+                } else if name.is_ident("setter")
+                    || name.is_ident("getter")
+                    || name.is_ident("deleter")
+                {
                     if let syn::AttrStyle::Inner(_) = attr.style {
                         return Err(syn::Error::new_spanned(
                             attr,
-                            "Inner style attribute is not supported for setter and getter",
+                            "Inner style attribute is not supported for setter, getter and deleter",
                         ));
                     }
                     if res != None {
                         return Err(syn::Error::new_spanned(
                             attr,
-                            "setter/getter attribute can not be used mutiple times",
+                            "setter/getter/deleter attribute can not be used mutiple times",
                         ));
                     }
-                    if name.is_ident("setter") {
-                        res = Some(FnType::Setter)
-                    } else {
-                        res = Some(FnType::Getter)
-                    }
+                    res = Some(property_fn_type(name))
                 } else {
                     new_attrs.push(attr.clone())
                 }
@@ -410,31 +608,30 @@ fn parse_method_attributes(
                     ));
                 } else if path.is_ident("call") {
                     res = Some(FnType::FnCall)
-                } else if path.is_ident("setter") || path.is_ident("getter") {
+                } else if path.is_ident("setter")
+                    || path.is_ident("getter")
+                    || path.is_ident("deleter")
+                {
                     if let syn::AttrStyle::Inner(_) = attr.style {
                         return Err(syn::Error::new_spanned(
                             attr,
-                            "Inner style attribute is not supported for setter and getter",
+                            "Inner style attribute is not supported for setter, getter and deleter",
                         ));
                     }
                     if res != None {
                         return Err(syn::Error::new_spanned(
                             attr,
-                            "setter/getter attribute can not be used mutiple times",
+                            "setter/getter/deleter attribute can not be used mutiple times",
                         ));
                     }
                     if nested.len() != 1 {
                         return Err(syn::Error::new_spanned(
                             attr,
-                            "setter/getter requires one value",
+                            "setter/getter/deleter requires one value",
                         ));
                     }
 
-                    res = if path.is_ident("setter") {
-                        Some(FnType::Setter)
-                    } else {
-                        Some(FnType::Getter)
-                    };
+                    res = Some(property_fn_type(path));
 
                     property_name = match nested.first().unwrap() {
                         syn::NestedMeta::Meta(syn::Meta::Path(ref w)) if w.segments.len() == 1 => {
@@ -445,7 +642,7 @@ fn parse_method_attributes(
                             _ => {
                                 return Err(syn::Error::new_spanned(
                                     lit,
-                                    "setter/getter attribute requires str value",
+                                    "setter/getter/deleter attribute requires str value",
                                 ))
                             }
                         },
@@ -484,6 +681,17 @@ fn parse_method_attributes(
     })
 }
 
+/// The type of a method with `#[getter]`, `#[setter]` or `#[deleter]`
+fn property_fn_type(path: &syn::Path) -> FnType {
+    if path.is_ident("getter") {
+        FnType::Getter
+    } else if path.is_ident("setter") {
+        FnType::Setter
+    } else {
+        FnType::Deleter
+    }
+}
+
 fn parse_method_name_attribute(
     ty: &FnType,
     attrs: &mut Vec<syn::Attribute>,
@@ -494,7 +702,7 @@ fn parse_method_name_attribute(
     // Reject some invalid combinations
     if let Some(name) = &name {
         match ty {
-            FnType::FnNew | FnType::FnCall | FnType::Getter | FnType::Setter => {
+            FnType::FnNew | FnType::FnCall | FnType::Getter | FnType::Setter | FnType::Deleter => {
                 return Err(syn::Error::new_spanned(
                     name,
                     "name not allowed with this attribute",
@@ -508,7 +716,7 @@ fn parse_method_name_attribute(
     Ok(match ty {
         FnType::FnNew => Some(syn::Ident::new("__new__", proc_macro2::Span::call_site())),
         FnType::FnCall => Some(syn::Ident::new("__call__", proc_macro2::Span::call_site())),
-        FnType::Getter | FnType::Setter => property_name,
+        FnType::Getter | FnType::Setter | FnType::Deleter => property_name,
         _ => name,
     })
 }
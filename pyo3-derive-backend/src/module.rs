@@ -6,15 +6,133 @@ use crate::pyfunction;
 use crate::pyfunction::PyFunctionAttr;
 use crate::pymethod;
 use crate::pymethod::get_arg_names;
+use crate::stubs;
 use crate::utils;
 use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote};
-use syn::Ident;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, Expr, Ident, Token};
+
+/// The parsed arguments of the pymodule macro
+pub struct PyModuleArgs {
+    pub name: Option<Ident>,
+    pub state: syn::Type,
+    pub multi_phase: bool,
+    /// Run after the module function for every new module object
+    pub on_load: Option<syn::Path>,
+    /// Run when a module object is deallocated, before its state is dropped
+    pub on_unload: Option<syn::Path>,
+}
+
+impl Parse for PyModuleArgs {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut slf = PyModuleArgs {
+            name: None,
+            state: parse_quote! { () },
+            multi_phase: false,
+            on_load: None,
+            on_unload: None,
+        };
+
+        let vars = Punctuated::<Expr, Token![,]>::parse_terminated(input)?;
+        for expr in vars {
+            match expr {
+                syn::Expr::Path(ref exp) if exp.path.is_ident("multi_phase") => {
+                    slf.multi_phase = true;
+                }
+                syn::Expr::Path(ref exp)
+                    if exp.path.get_ident().is_some() && slf.name.is_none() =>
+                {
+                    slf.name = exp.path.get_ident().cloned();
+                }
+                syn::Expr::Assign(ref assign) => match (&*assign.left, &*assign.right) {
+                    (syn::Expr::Path(ref key), syn::Expr::Path(ref value))
+                        if key.path.is_ident("state") =>
+                    {
+                        slf.state = syn::Type::Path(syn::TypePath {
+                            qself: None,
+                            path: value.path.clone(),
+                        });
+                    }
+                    (syn::Expr::Path(ref key), syn::Expr::Path(ref value))
+                        if key.path.is_ident("on_load") =>
+                    {
+                        slf.on_load = Some(value.path.clone());
+                    }
+                    (syn::Expr::Path(ref key), syn::Expr::Path(ref value))
+                        if key.path.is_ident("on_unload") =>
+                    {
+                        slf.on_unload = Some(value.path.clone());
+                    }
+                    _ => return Err(syn::Error::new_spanned(assign, "could not parse argument")),
+                },
+                _ => return Err(syn::Error::new_spanned(expr, "Could not parse arguments")),
+            }
+        }
+        Ok(slf)
+    }
+}
 
 /// Generates the function that is called by the python interpreter to initialize the native
 /// module
-pub fn py_init(fnname: &Ident, name: &Ident, doc: syn::LitStr) -> TokenStream {
+pub fn py_init(fnname: &Ident, name: &Ident, args: &PyModuleArgs, doc: syn::LitStr) -> TokenStream {
     let cb_name = Ident::new(&format!("PyInit_{}", name), Span::call_site());
+    let state = &args.state;
+
+    let initializer = match &args.on_load {
+        Some(on_load) => quote! {
+            |py: pyo3::Python, module: &pyo3::types::PyModule| -> pyo3::PyResult<()> {
+                #fnname(py, module)?;
+                #on_load(py, module)
+            }
+        },
+        None => quote! { #fnname },
+    };
+    let (free_fn, free) = match &args.on_unload {
+        Some(on_unload) => (
+            quote! {
+                unsafe extern "C" fn free(module: *mut std::os::raw::c_void) {
+                    pyo3::derive_utils::free_module::<#state>(module, #on_unload)
+                }
+            },
+            quote! { Some(free) },
+        ),
+        None => (quote! {}, quote! { None }),
+    };
+
+    let body = if args.multi_phase {
+        quote! {
+            static mut SLOTS: [pyo3::ffi::PyModuleDef_Slot; 2] = [pyo3::ffi::PyModuleDef_Slot {
+                slot: 0,
+                value: std::ptr::null_mut(),
+            }; 2];
+            unsafe extern "C" fn exec(module: *mut pyo3::ffi::PyObject) -> std::os::raw::c_int {
+                pyo3::derive_utils::exec_module::<#state>(module, #initializer)
+            }
+            #free_fn
+            pyo3::derive_utils::make_module_def::<#state>(
+                &mut MODULE_DEF,
+                &mut SLOTS,
+                concat!(stringify!(#name), "\0"),
+                #doc,
+                #free,
+                exec,
+            )
+        }
+    } else {
+        quote! {
+            #free_fn
+            pyo3::derive_utils::make_module::<#state>(
+                &mut MODULE_DEF,
+                concat!(stringify!(#name), "\0"),
+                #doc,
+                #free,
+                #initializer,
+            )
+        }
+    };
 
     quote! {
         #[no_mangle]
@@ -22,14 +140,16 @@ pub fn py_init(fnname: &Ident, name: &Ident, doc: syn::LitStr) -> TokenStream {
         /// This autogenerated function is called by the python interpreter when importing
         /// the module.
         pub unsafe extern "C" fn #cb_name() -> *mut pyo3::ffi::PyObject {
-            pyo3::derive_utils::make_module(concat!(stringify!(#name), "\0"), #doc, #fnname)
+            static mut MODULE_DEF: pyo3::ffi::PyModuleDef = pyo3::ffi::PyModuleDef_INIT;
+            #body
         }
     }
 }
 
-/// Finds and takes care of the #[pyfn(...)] in `#[pymodule]`
-pub fn process_functions_in_module(func: &mut syn::ItemFn) {
+/// Finds and takes care of the #[pyfn(...)] and #[pyconst] in `#[pymodule]`
+pub fn process_functions_in_module(func: &mut syn::ItemFn) -> syn::Result<()> {
     let mut stmts: Vec<syn::Stmt> = Vec::new();
+    let module_arg = module_arg(&func.sig);
 
     for stmt in func.block.stmts.iter_mut() {
         if let syn::Stmt::Item(syn::Item::Fn(ref mut func)) = stmt {
@@ -41,16 +161,130 @@ pub fn process_functions_in_module(func: &mut syn::ItemFn) {
                 let item: syn::ItemFn = syn::parse_quote! {
                     fn block_wrapper() {
                         #function_to_python
-                        #module_name.add_wrapped(&#function_wrapper_ident)?;
+                        #module_name.add_function(&#function_wrapper_ident)?;
                     }
                 };
                 stmts.extend(item.block.stmts.into_iter());
             }
         };
+        let constant = match stmt {
+            syn::Stmt::Item(syn::Item::Const(ref mut item)) => {
+                extract_pyconst_attr(&mut item.attrs, &item.ident, &item.ty)?
+            }
+            syn::Stmt::Item(syn::Item::Static(ref mut item)) => {
+                extract_pyconst_attr(&mut item.attrs, &item.ident, &item.ty)?
+            }
+            _ => None,
+        };
         stmts.push(stmt.clone());
+        if let Some(constant) = constant {
+            stmts.push(add_const_to_module(constant, module_arg)?);
+        }
     }
 
     func.block.stmts = stmts;
+    Ok(())
+}
+
+/// A constant or static marked with `#[pyconst]`
+struct PyConst {
+    attr: syn::Attribute,
+    module: Option<syn::Path>,
+    python_name: syn::LitStr,
+    value: TokenStream,
+}
+
+/// Removes the `#[pyconst]`, `#[pyconst(m)]`, `#[pyconst("name")]` or `#[pyconst(m, "name")]`
+/// attribute of a const or static item
+fn extract_pyconst_attr(
+    attrs: &mut Vec<syn::Attribute>,
+    ident: &Ident,
+    ty: &syn::Type,
+) -> syn::Result<Option<PyConst>> {
+    let position = match attrs.iter().position(|attr| attr.path.is_ident("pyconst")) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+    let attr = attrs.remove(position);
+
+    let mut module = None;
+    let mut python_name = syn::LitStr::new(&ident.unraw().to_string(), ident.span());
+    match attr.parse_meta()? {
+        syn::Meta::Path(_) => {}
+        syn::Meta::List(list) => {
+            let mut nested = list.nested.iter().peekable();
+            if let Some(syn::NestedMeta::Meta(syn::Meta::Path(path))) = nested.peek() {
+                module = Some(path.clone());
+                nested.next();
+            }
+            if let Some(syn::NestedMeta::Lit(syn::Lit::Str(name))) = nested.peek() {
+                python_name = name.clone();
+                nested.next();
+            }
+            if let Some(unexpected) = nested.next() {
+                return Err(syn::Error::new_spanned(
+                    unexpected,
+                    "expected #[pyconst], #[pyconst(module)] or #[pyconst(module, \"name\")]",
+                ));
+            }
+        }
+        meta => {
+            return Err(syn::Error::new_spanned(
+                meta,
+                "expected #[pyconst], #[pyconst(module)] or #[pyconst(module, \"name\")]",
+            ))
+        }
+    }
+
+    // Arrays aren't converted to Python, but slices are converted to lists
+    let value = match ty {
+        syn::Type::Array(_) => quote! { &#ident[..] },
+        _ => quote! { #ident },
+    };
+    Ok(Some(PyConst {
+        attr,
+        module,
+        python_name,
+        value,
+    }))
+}
+
+/// The name of the `&PyModule` parameter of the module's initialization function
+fn module_arg(sig: &syn::Signature) -> Option<&Ident> {
+    sig.inputs.iter().find_map(|input| match input {
+        syn::FnArg::Typed(syn::PatType { pat, ty, .. }) => match (&**pat, &**ty) {
+            (syn::Pat::Ident(pat), syn::Type::Reference(reference)) => match &*reference.elem {
+                syn::Type::Path(path)
+                    if path.path.segments.last().map(|s| s.ident == "PyModule") == Some(true) =>
+                {
+                    Some(&pat.ident)
+                }
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Generates the statement adding a `#[pyconst]` to the module
+fn add_const_to_module(constant: PyConst, module_arg: Option<&Ident>) -> syn::Result<syn::Stmt> {
+    let module = match (constant.module, module_arg) {
+        (Some(module), _) => quote! { #module },
+        (None, Some(module)) => quote! { #module },
+        (None, None) => {
+            return Err(syn::Error::new_spanned(
+                constant.attr,
+                "can't find the `&PyModule` argument, name it with #[pyconst(module)]",
+            ))
+        }
+    };
+    let PyConst {
+        python_name, value, ..
+    } = constant;
+    Ok(syn::parse_quote! {
+        #module.add(#python_name, #value)?;
+    })
 }
 
 /// Transforms a rust fn arg parsed with syn into a method::FnArg
@@ -151,21 +385,29 @@ pub fn add_fn_to_module(
         Ok(text_signature) => text_signature,
         Err(err) => return err.to_compile_error(),
     };
-    let doc = match utils::get_doc(&func.attrs, text_signature, true) {
-        Ok(doc) => doc,
-        Err(err) => return err.to_compile_error(),
-    };
-
     let function_wrapper_ident = function_wrapper_ident(&func.sig.ident);
 
-    let spec = method::FnSpec {
+    let mut spec = method::FnSpec {
         tp: method::FnType::Fn,
         name: &function_wrapper_ident,
         python_name,
+        mut_self: false,
         attrs: pyfn_attrs,
         args: arguments,
         output: ty,
-        doc,
+        doc: syn::LitStr::new("", Span::call_site()),
+    };
+
+    let text_signature = text_signature.or_else(|| {
+        if utils::has_doc_signature(&func.attrs) {
+            None
+        } else {
+            spec.default_text_signature(None)
+        }
+    });
+    spec.doc = match utils::get_doc(&func.attrs, text_signature, true) {
+        Ok(doc) => doc,
+        Err(err) => return err.to_compile_error(),
     };
 
     let doc = &spec.doc;
@@ -173,61 +415,41 @@ pub fn add_fn_to_module(
     let python_name = &spec.python_name;
 
     let wrapper = function_c_wrapper(&func.sig.ident, &spec);
+    let stub = stubs::function_stub(&spec);
+    let (meth, flags) = pymethod::impl_method_type(&spec);
 
     let tokens = quote! {
         fn #function_wrapper_ident(py: pyo3::Python) -> pyo3::PyObject {
             #wrapper
+            #stub
 
             let _def = pyo3::class::PyMethodDef {
                 ml_name: stringify!(#python_name),
-                ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
-                ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS,
+                ml_meth: #meth,
+                ml_flags: #flags,
                 ml_doc: #doc,
             };
 
-            let function = unsafe {
-                pyo3::PyObject::from_owned_ptr_or_panic(
-                    py,
-                    pyo3::ffi::PyCFunction_New(
-                        Box::into_raw(Box::new(_def.as_method_def())),
-                        ::std::ptr::null_mut()
-                    )
-                )
-            };
-
-            function
+            pyo3::derive_utils::make_function(py, _def)
         }
     };
 
     tokens
 }
 
-/// Generate static function wrapper (PyCFunction, PyCFunctionWithKeywords)
+/// Generate static function wrapper (PyCFunction, PyCFunctionWithKeywords,
+/// _PyCFunctionFastWithKeywords)
 fn function_c_wrapper(name: &Ident, spec: &method::FnSpec<'_>) -> TokenStream {
     let names: Vec<Ident> = get_arg_names(&spec);
     let cb = quote! {
         #name(#(#names),*)
     };
 
-    let body = pymethod::impl_arg_params(spec, cb);
-
-    quote! {
-        unsafe extern "C" fn __wrap(
-            _slf: *mut pyo3::ffi::PyObject,
-            _args: *mut pyo3::ffi::PyObject,
-            _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
-        {
-            const _LOCATION: &'static str = concat!(stringify!(#name), "()");
-
-            let _py = pyo3::Python::assume_gil_acquired();
-            let _pool = pyo3::GILPool::new(_py);
-            let _args = _py.from_borrowed_ptr::<pyo3::types::PyTuple>(_args);
-            let _kwargs: Option<&pyo3::types::PyDict> = _py.from_borrowed_ptr_or_opt(_kwargs);
-
-            #body
-
-            pyo3::callback::cb_convert(
-                pyo3::callback::PyObjectCallbackConverter, _py, _result)
-        }
-    }
+    pymethod::impl_wrap_convention(
+        spec,
+        quote! { concat!(stringify!(#name), "()") },
+        quote! { _slf },
+        quote! {},
+        cb,
+    )
 }
@@ -0,0 +1,52 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//! Code generation for `#[derive(Pickle)]`
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Adds `__getstate__`, `__setstate__` and `__reduce__` methods serializing a `#[pyclass]` with
+/// serde, and the classmethod `__reduce__` uses to restore it.
+pub fn build_pickle(ast: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    if let syn::Data::Union(_) = ast.data {
+        return Err(syn::Error::new_spanned(
+            &ast.ident,
+            "#[derive(Pickle)] can't be used with unions",
+        ));
+    }
+    if !ast.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &ast.generics,
+            "#[derive(Pickle)] can't be used with generic types",
+        ));
+    }
+    let ident = &ast.ident;
+
+    Ok(quote! {
+        #[pyo3::proc_macro::pymethods]
+        impl #ident {
+            fn __getstate__(&self, py: pyo3::Python) -> pyo3::PyResult<pyo3::PyObject> {
+                pyo3::pickle::dump_state(py, self)
+            }
+
+            fn __setstate__(&mut self, state: &pyo3::types::PyAny) -> pyo3::PyResult<()> {
+                *self = pyo3::pickle::load_state(state)?;
+                Ok(())
+            }
+
+            fn __reduce__(
+                slf: &pyo3::PyClassShell<Self>,
+                py: pyo3::Python,
+            ) -> pyo3::PyResult<pyo3::PyObject> {
+                pyo3::pickle::reduce_state(py, slf)
+            }
+
+            #[classmethod]
+            fn _pyo3_unpickle(
+                _cls: &pyo3::types::PyType,
+                state: &pyo3::types::PyAny,
+            ) -> pyo3::PyResult<Self> {
+                pyo3::pickle::load_state(state)
+            }
+        }
+    })
+}
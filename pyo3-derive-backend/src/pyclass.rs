@@ -2,8 +2,10 @@
 
 use crate::method::FnType;
 use crate::pymethod::{
-    impl_py_getter_def, impl_py_setter_def, impl_wrap_getter, impl_wrap_setter, PropertyType,
+    impl_py_class_attribute, impl_py_getter_def, impl_py_setter_def, impl_wrap_getter,
+    impl_wrap_setter, PropertyType,
 };
+use crate::stubs;
 use crate::utils;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
@@ -15,11 +17,18 @@ use syn::{parse_quote, Expr, Token};
 /// The parsed arguments of the pyclass macro
 pub struct PyClassArgs {
     pub freelist: Option<syn::Expr>,
-    pub name: Option<syn::Expr>,
+    pub name: Option<syn::Ident>,
     pub flags: Vec<syn::Expr>,
     pub base: syn::TypePath,
     pub has_extends: bool,
     pub module: Option<syn::LitStr>,
+    pub eq: bool,
+    pub ord: bool,
+    pub hash: bool,
+    pub debug: bool,
+    pub display: bool,
+    pub resource: bool,
+    pub customize: Option<syn::ExprPath>,
 }
 
 impl Parse for PyClassArgs {
@@ -45,6 +54,13 @@ impl Default for PyClassArgs {
             flags: vec![parse_quote! { 0 }],
             base: parse_quote! { pyo3::types::PyAny },
             has_extends: false,
+            eq: false,
+            ord: false,
+            hash: false,
+            debug: false,
+            display: false,
+            resource: false,
+            customize: None,
         }
     }
 }
@@ -78,12 +94,19 @@ impl PyClassArgs {
             }
             "name" => match *assign.right {
                 syn::Expr::Path(ref exp) if exp.path.segments.len() == 1 => {
-                    self.name = Some(exp.clone().into());
+                    self.name = Some(exp.path.segments.first().unwrap().ident.unraw());
+                }
+                // A string can be a Rust keyword, like `name = "type"`
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(ref lit),
+                    ..
+                }) if is_python_identifier(&lit.value()) => {
+                    self.name = Some(syn::Ident::new(&lit.value(), lit.span()));
                 }
                 _ => {
                     return Err(syn::Error::new_spanned(
                         *assign.right.clone(),
-                        "Wrong 'name' format",
+                        "Wrong 'name' format, expected an identifier or a string like \"Name\"",
                     ));
                 }
             },
@@ -102,17 +125,28 @@ impl PyClassArgs {
                     ));
                 }
             },
+            "customize" => match *assign.right {
+                syn::Expr::Path(ref exp) => {
+                    self.customize = Some(exp.clone());
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        *assign.right.clone(),
+                        "Wrong format for customize",
+                    ));
+                }
+            },
             "module" => match *assign.right {
                 syn::Expr::Lit(syn::ExprLit {
                     lit: syn::Lit::Str(ref lit),
                     ..
-                }) => {
+                }) if lit.value().split('.').all(is_python_identifier) => {
                     self.module = Some(lit.clone());
                 }
                 _ => {
                     return Err(syn::Error::new_spanned(
                         *assign.right.clone(),
-                        "Wrong format for module",
+                        "Wrong format for module, expected a string like \"package.module\"",
                     ));
                 }
             },
@@ -143,6 +177,31 @@ impl PyClassArgs {
             "dict" => {
                 parse_quote! {pyo3::type_flags::DICT}
             }
+            // Slots derived from the Rust traits, these are not type flags
+            "eq" => {
+                self.eq = true;
+                return Ok(());
+            }
+            "ord" => {
+                self.ord = true;
+                return Ok(());
+            }
+            "hash" => {
+                self.hash = true;
+                return Ok(());
+            }
+            "debug" => {
+                self.debug = true;
+                return Ok(());
+            }
+            "display" => {
+                self.display = true;
+                return Ok(());
+            }
+            "resource" => {
+                self.resource = true;
+                return Ok(());
+            }
             _ => {
                 return Err(syn::Error::new_spanned(
                     exp.path.clone(),
@@ -179,7 +238,77 @@ pub fn build_py_class(class: &mut syn::ItemStruct, attr: &PyClassArgs) -> syn::R
         ));
     }
 
-    impl_class(&class.ident, &attr, doc, descriptors)
+    impl_class(&class.ident, &attr, doc, descriptors, false)
+}
+
+/// Builds a class for a fieldless enum, with a class attribute for each variant, which converts
+/// to and from ints like `#[derive(IntEnum)]`
+pub fn build_py_enum(enum_: &mut syn::ItemEnum, attr: &PyClassArgs) -> syn::Result<TokenStream> {
+    let python_name = get_class_python_name(&enum_.ident, attr);
+    let text_signature = utils::parse_text_signature_attrs(&mut enum_.attrs, &python_name)?;
+    let doc = utils::get_doc(&enum_.attrs, text_signature, true)?;
+
+    if !enum_.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &enum_.generics,
+            "#[pyclass] cannot have generic parameters",
+        ));
+    }
+    if attr.has_extends {
+        return Err(syn::Error::new_spanned(
+            &enum_.ident,
+            "#[pyclass] enums cannot extend other classes",
+        ));
+    }
+    for variant in enum_.variants.iter() {
+        if variant.fields != syn::Fields::Unit {
+            return Err(syn::Error::new_spanned(
+                &variant.fields,
+                "#[pyclass] can only be used with enums without fields",
+            ));
+        }
+    }
+
+    let cls = &enum_.ident;
+    let ty: syn::Type = parse_quote! { #cls };
+    let name = python_name.to_string();
+    let variant_idents: Vec<_> = enum_.variants.iter().map(|v| v.ident.unraw()).collect();
+    let variant_names: Vec<_> = variant_idents.iter().map(|v| v.to_string()).collect();
+    let variants = enum_.variants.iter().map(|v| &v.ident);
+    let class_attributes = enum_.variants.iter().map(|v| {
+        let ident = &v.ident;
+        impl_py_class_attribute(&v.ident.unraw(), quote! { #cls::#ident })
+    });
+    let stubs = variant_names
+        .iter()
+        .map(|name| stubs::class_attribute_stub(&ty, name, &ty));
+    let class = impl_class(cls, attr, doc, Vec::new(), true)?;
+
+    Ok(quote! {
+        #class
+
+        impl pyo3::int_enum::IntEnum for #cls {
+            const NAME: &'static str = #name;
+            const VARIANTS: &'static [(&'static str, i128, Self)] = &[
+                #((#variant_names, #cls::#variants as i128, #cls::#variants)),*
+            ];
+        }
+
+        impl<'source> pyo3::FromPyObject<'source> for #cls {
+            fn extract(obj: &'source pyo3::types::PyAny) -> pyo3::PyResult<Self> {
+                pyo3::int_enum::extract_class(obj)
+            }
+        }
+
+        #(#stubs)*
+
+        pyo3::inventory::submit! {
+            #![crate = pyo3] {
+                type ClsInventory = <#cls as pyo3::class::methods::PyMethodsInventoryDispatch>::InventoryType;
+                <ClsInventory as pyo3::class::methods::PyMethodsInventory>::new(&[#(#class_attributes),*])
+            }
+        }
+    })
 }
 
 /// Parses `#[pyo3(get, set)]`
@@ -249,10 +378,22 @@ fn impl_inventory(cls: &syn::Ident) -> TokenStream {
     }
 }
 
-fn get_class_python_name(cls: &syn::Ident, attr: &PyClassArgs) -> TokenStream {
+fn get_class_python_name(cls: &syn::Ident, attr: &PyClassArgs) -> syn::Ident {
     match &attr.name {
-        Some(name) => quote! { #name },
-        None => quote! { #cls },
+        Some(name) => name.clone(),
+        None => cls.unraw(),
+    }
+}
+
+/// Whether `name` can be the name of a class or a part of the dotted path of a module. Only ASCII
+/// names are accepted, as the name of a class is also used as a Rust identifier.
+fn is_python_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
     }
 }
 
@@ -261,6 +402,7 @@ fn impl_class(
     attr: &PyClassArgs,
     doc: syn::LitStr,
     descriptors: Vec<(syn::Field, Vec<FnType>)>,
+    is_enum: bool,
 ) -> syn::Result<TokenStream> {
     let cls_name = get_class_python_name(cls, attr).to_string();
 
@@ -326,6 +468,110 @@ fn impl_class(
     } else {
         quote! { type Dict = pyo3::pyclass_slots::PyClassDummySlot; }
     };
+    let derived_richcmp = if is_enum {
+        quote! {
+            fn derived_richcmp() -> Option<pyo3::ffi::richcmpfunc> {
+                Some(pyo3::int_enum::richcmp::<Self>())
+            }
+        }
+    } else if attr.ord {
+        quote! {
+            fn derived_richcmp() -> Option<pyo3::ffi::richcmpfunc> {
+                Some(pyo3::class::basic::richcmp_from_partial_ord::<Self>())
+            }
+        }
+    } else if attr.eq {
+        quote! {
+            fn derived_richcmp() -> Option<pyo3::ffi::richcmpfunc> {
+                Some(pyo3::class::basic::richcmp_from_partial_eq::<Self>())
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let derived_hash = if is_enum {
+        quote! {
+            fn derived_hash() -> Option<pyo3::ffi::hashfunc> {
+                Some(pyo3::int_enum::hash::<Self>())
+            }
+        }
+    } else if attr.hash {
+        quote! {
+            fn derived_hash() -> Option<pyo3::ffi::hashfunc> {
+                Some(pyo3::class::basic::hash_from_rust_hash::<Self>())
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let derived_repr = if attr.debug {
+        quote! {
+            fn derived_repr() -> Option<pyo3::ffi::reprfunc> {
+                Some(pyo3::class::basic::repr_from_debug::<Self>())
+            }
+        }
+    } else if is_enum {
+        quote! {
+            fn derived_repr() -> Option<pyo3::ffi::reprfunc> {
+                Some(pyo3::int_enum::repr::<Self>())
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let derived_str = if attr.display {
+        quote! {
+            fn derived_str() -> Option<pyo3::ffi::reprfunc> {
+                Some(pyo3::class::basic::str_from_display::<Self>())
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let derived_index = if is_enum {
+        quote! {
+            fn derived_index() -> Option<pyo3::ffi::unaryfunc> {
+                Some(pyo3::int_enum::index::<Self>())
+            }
+        }
+    } else {
+        quote! {}
+    };
+    // close(), closed, __enter__ and __exit__ are added with the methods of the class
+    let (derived_finalize, resource_methods) = if attr.resource {
+        let ty: syn::Type = parse_quote! { #cls };
+        let stubs = stubs::resource_stubs(&ty);
+        (
+            quote! {
+                fn derived_finalize() -> Option<pyo3::ffi::destructor> {
+                    Some(pyo3::resource::finalize::<Self>())
+                }
+            },
+            quote! {
+                #stubs
+
+                pyo3::inventory::submit! {
+                    #![crate = pyo3] {
+                        type ClsInventory = <#cls as pyo3::class::methods::PyMethodsInventoryDispatch>::InventoryType;
+                        <ClsInventory as pyo3::class::methods::PyMethodsInventory>::new(
+                            <#cls as pyo3::resource::PyResourceMethods>::METHODS
+                        )
+                    }
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+    let customize = if let Some(customize) = &attr.customize {
+        quote! {
+            fn customize_type_object(type_object: &mut pyo3::ffi::PyTypeObject) {
+                #customize(type_object)
+            }
+        }
+    } else {
+        quote! {}
+    };
     let module = if let Some(m) = &attr.module {
         quote! { Some(#m) }
     } else {
@@ -358,6 +604,17 @@ fn impl_class(
         quote! { 0 }
     };
 
+    // Enums are extracted from ints too, by the `FromPyObject` of `build_py_enum`
+    let extract_cloned = if !is_enum {
+        quote! {
+            impl pyo3::conversion::FromPyObjectImpl for #cls {
+                type Impl = pyo3::conversion::extract_impl::Cloned;
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     // If #cls is not extended type, we allow Self->PyObject conversion
     let into_pyobject = if !attr.has_extends {
         quote! {
@@ -377,6 +634,7 @@ fn impl_class(
             type BaseType = #base;
             type ConcreteLayout = pyo3::pyclass::PyClassShell<Self>;
             type Initializer = pyo3::pyclass_init::PyClassInitializer<Self>;
+            type AsRefTarget = pyo3::pyclass::PyClassShell<Self>;
 
             const NAME: &'static str = #cls_name;
             const MODULE: Option<&'static str> = #module;
@@ -394,19 +652,16 @@ fn impl_class(
         impl pyo3::PyClass for #cls {
             #dict
             #weakref
+            #derived_richcmp
+            #derived_hash
+            #derived_repr
+            #derived_str
+            #derived_index
+            #derived_finalize
+            #customize
         }
 
-        impl pyo3::conversion::FromPyObjectImpl for #cls {
-            type Impl = pyo3::conversion::extract_impl::Cloned;
-        }
-
-        impl pyo3::conversion::FromPyObjectImpl for &'_ #cls {
-            type Impl = pyo3::conversion::extract_impl::Reference;
-        }
-
-        impl pyo3::conversion::FromPyObjectImpl for &'_ mut #cls {
-            type Impl = pyo3::conversion::extract_impl::MutReference;
-        }
+        #extract_cloned
 
         #into_pyobject
 
@@ -416,6 +671,7 @@ fn impl_class(
 
         #gc_impl
 
+        #resource_methods
     })
 }
 
@@ -423,6 +679,13 @@ fn impl_descriptors(
     cls: &syn::Type,
     descriptors: Vec<(syn::Field, Vec<FnType>)>,
 ) -> syn::Result<TokenStream> {
+    let stubs: Vec<TokenStream> = descriptors
+        .iter()
+        .flat_map(|(field, fns)| {
+            fns.iter()
+                .map(move |desc| stubs::field_stub(cls, field, desc))
+        })
+        .collect();
     let py_methods: Vec<TokenStream> = descriptors
         .iter()
         .flat_map(|&(ref field, ref fns)| {
@@ -451,6 +714,7 @@ fn impl_descriptors(
         .collect::<syn::Result<_>>()?;
 
     Ok(quote! {
+        #(#stubs)*
 
         pyo3::inventory::submit! {
             #![crate = pyo3] {
@@ -23,13 +23,23 @@ pub fn build_py_methods(ast: &mut syn::ItemImpl) -> syn::Result<TokenStream> {
 pub fn impl_methods(ty: &syn::Type, impls: &mut Vec<syn::ImplItem>) -> syn::Result<TokenStream> {
     // get method names in impl block
     let mut methods = Vec::new();
+    let mut stubs = Vec::new();
     for iimpl in impls.iter_mut() {
         if let syn::ImplItem::Method(ref mut meth) = iimpl {
-            methods.push(pymethod::gen_py_method(ty, &mut meth.sig, &mut meth.attrs)?);
+            let (method, stub) = pymethod::gen_py_method(ty, &mut meth.sig, &mut meth.attrs)?;
+            methods.push(method);
+            stubs.push(stub);
+        } else if let syn::ImplItem::Const(ref mut konst) = iimpl {
+            if let Some((method, stub)) = pymethod::gen_py_const(ty, konst)? {
+                methods.push(method);
+                stubs.push(stub);
+            }
         }
     }
 
     Ok(quote! {
+        #(#stubs)*
+
        pyo3::inventory::submit! {
             #![crate = pyo3] {
                 type TyInventory = <#ty as pyo3::class::methods::PyMethodsInventoryDispatch>::InventoryType;
@@ -1,5 +1,7 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
-use crate::method::{FnArg, FnSpec, FnType};
+use crate::method::{CallingConvention, FnArg, FnSpec, FnType};
+use crate::pyfunction::parse_name_attribute;
+use crate::stubs;
 use crate::utils;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
@@ -10,21 +12,30 @@ pub enum PropertyType<'a> {
     Function(&'a FnSpec<'a>),
 }
 
+/// Generates the method definition of a method in `#[pymethods]` and the registration of its
+/// stub signature
 pub fn gen_py_method(
     cls: &syn::Type,
     sig: &mut syn::Signature,
     meth_attrs: &mut Vec<syn::Attribute>,
-) -> syn::Result<TokenStream> {
+) -> syn::Result<(TokenStream, TokenStream)> {
     check_generic(sig)?;
     let spec = FnSpec::parse(sig, &mut *meth_attrs, true)?;
+    check_context_manager(&spec)?;
 
-    Ok(match spec.tp {
-        FnType::Fn => impl_py_method_def(&spec, &impl_wrap(cls, &spec, true)),
+    let def = match spec.tp {
+        FnType::Fn if spec.python_name == "__repr__" || spec.python_name == "__str__" => {
+            impl_py_method_def_repr(&spec, &impl_wrap_repr(cls, &spec)?)
+        }
+        FnType::Fn if spec.python_name == "__call__" => {
+            impl_py_method_def_call(&spec, &impl_wrap(cls, &spec))
+        }
+        FnType::Fn => impl_py_method_def(&spec, &impl_wrap(cls, &spec)),
         FnType::PySelf(ref self_ty) => {
-            impl_py_method_def(&spec, &impl_wrap_pyslf(cls, &spec, self_ty, true))
+            impl_py_method_def(&spec, &impl_wrap_pyslf(cls, &spec, self_ty))
         }
         FnType::FnNew => impl_py_method_def_new(&spec, &impl_wrap_new(cls, &spec)),
-        FnType::FnCall => impl_py_method_def_call(&spec, &impl_wrap(cls, &spec, false)),
+        FnType::FnCall => impl_py_method_def_call(&spec, &impl_wrap(cls, &spec)),
         FnType::FnClass => impl_py_method_def_class(&spec, &impl_wrap_class(cls, &spec)),
         FnType::FnStatic => impl_py_method_def_static(&spec, &impl_wrap_static(cls, &spec)),
         FnType::Getter => impl_py_getter_def(
@@ -37,7 +48,58 @@ pub fn gen_py_method(
             &spec.doc,
             &impl_wrap_setter(cls, PropertyType::Function(&spec))?,
         ),
-    })
+        FnType::Deleter => impl_py_deleter_def(
+            &spec.python_name,
+            &spec.doc,
+            &impl_wrap_deleter(cls, &spec)?,
+        ),
+        FnType::ClassAttribute => {
+            let name = spec.name;
+            impl_py_class_attribute(&spec.python_name, quote! { #cls::#name() })
+        }
+    };
+    Ok((def, stubs::member_stub(cls, &spec)))
+}
+
+/// Generates the definition and the stub of an associated constant with `#[classattr]`, or
+/// `None` for other constants
+pub fn gen_py_const(
+    cls: &syn::Type,
+    konst: &mut syn::ImplItemConst,
+) -> syn::Result<Option<(TokenStream, TokenStream)>> {
+    let len = konst.attrs.len();
+    konst.attrs.retain(|attr| !attr.path.is_ident("classattr"));
+    if konst.attrs.len() == len {
+        return Ok(None);
+    }
+    let python_name =
+        parse_name_attribute(&mut konst.attrs)?.unwrap_or_else(|| konst.ident.unraw());
+    let name = &konst.ident;
+    let def = impl_py_class_attribute(&python_name, quote! { #cls::#name });
+    let stub = stubs::class_attribute_stub(cls, &python_name.to_string(), &konst.ty);
+    Ok(Some((def, stub)))
+}
+
+/// `__enter__` and `__exit__` are looked up by the `with` statement, so check that they can be
+/// called with the arguments it passes
+fn check_context_manager(spec: &FnSpec) -> syn::Result<()> {
+    if spec.tp != FnType::Fn {
+        return Ok(());
+    }
+    let (_, args) = split_off_python_arg(&spec.args);
+    if spec.python_name == "__enter__" && !args.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &args[0].ty,
+            "__enter__ can not have arguments",
+        ));
+    }
+    if spec.python_name == "__exit__" && args.len() != 3 {
+        return Err(syn::Error::new_spanned(
+            spec.name,
+            "__exit__ must have three arguments: the exception type, value and traceback",
+        ));
+    }
+    Ok(())
 }
 
 fn check_generic(sig: &syn::Signature) -> syn::Result<()> {
@@ -56,18 +118,17 @@ fn check_generic(sig: &syn::Signature) -> syn::Result<()> {
     Ok(())
 }
 
-/// Generate function wrapper (PyCFunction, PyCFunctionWithKeywords)
-pub fn impl_wrap(cls: &syn::Type, spec: &FnSpec<'_>, noargs: bool) -> TokenStream {
+/// Generate function wrapper (PyCFunction, PyCFunctionWithKeywords, _PyCFunctionFastWithKeywords)
+pub fn impl_wrap(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
     let body = impl_call(cls, &spec);
-    let slf = impl_self(&quote! { &mut #cls });
-    impl_wrap_common(cls, spec, noargs, slf, body)
+    let slf = impl_borrow_self(cls, spec.mut_self, quote! { ::std::ptr::null_mut() });
+    impl_wrap_common(cls, spec, slf, body)
 }
 
 pub fn impl_wrap_pyslf(
     cls: &syn::Type,
     spec: &FnSpec<'_>,
     self_ty: &syn::TypeReference,
-    noargs: bool,
 ) -> TokenStream {
     let names = get_arg_names(spec);
     let name = &spec.name;
@@ -75,68 +136,293 @@ pub fn impl_wrap_pyslf(
         #cls::#name(_slf, #(#names),*)
     };
     let slf = impl_self(self_ty);
-    impl_wrap_common(cls, spec, noargs, slf, body)
+    impl_wrap_common(cls, spec, slf, body)
 }
 
 fn impl_wrap_common(
     cls: &syn::Type,
     spec: &FnSpec<'_>,
-    noargs: bool,
     slf: TokenStream,
     body: TokenStream,
 ) -> TokenStream {
     let python_name = &spec.python_name;
-    if spec.args.is_empty() && noargs {
-        quote! {
+    impl_wrap_convention(
+        spec,
+        quote! { concat!(stringify!(#cls), ".", stringify!(#python_name), "()") },
+        quote! { _slf },
+        slf,
+        body,
+    )
+}
+
+/// Generates the C function `__wrap` with the signature of the calling convention of `spec`,
+/// which extracts the arguments and runs `body`. `slf` is the name of its first parameter, which
+/// `init` can convert to the receiver. The `Fast` convention also gets `__wrap_args`, which is
+/// used on Pythons without `METH_FASTCALL`.
+pub fn impl_wrap_convention(
+    spec: &FnSpec<'_>,
+    location: TokenStream,
+    slf: TokenStream,
+    init: TokenStream,
+    body: TokenStream,
+) -> TokenStream {
+    let prelude = quote! {
+        const _LOCATION: &'static str = #location;
+        let _py = pyo3::Python::assume_gil_acquired();
+        let _pool = pyo3::GILPool::new(_py);
+        #init
+    };
+    let into_result = quote! { pyo3::derive_utils::IntoPyResult::into_py_result };
+    let body = impl_wrap_iterator(&spec.output, body);
+    let mut option_pos = 0;
+    let param_conversion: Vec<TokenStream> = spec
+        .args
+        .iter()
+        .enumerate()
+        .map(|(idx, arg)| impl_arg_param(arg, spec, idx, &mut option_pos))
+        .collect();
+
+    match spec.calling_convention() {
+        CallingConvention::NoArgs => quote! {
             unsafe extern "C" fn __wrap(
-                _slf: *mut pyo3::ffi::PyObject,
+                #slf: *mut pyo3::ffi::PyObject,
                 _args: *mut pyo3::ffi::PyObject,
             ) -> *mut pyo3::ffi::PyObject
             {
-                const _LOCATION: &'static str = concat!(
-                    stringify!(#cls), ".", stringify!(#python_name), "()");
-                let _py = pyo3::Python::assume_gil_acquired();
-                let _pool = pyo3::GILPool::new(_py);
-                #slf
-                let _result = {
-                    pyo3::derive_utils::IntoPyResult::into_py_result(#body)
-                };
+                #prelude
+                #(#param_conversion)*
+                let _result = #into_result(#body);
 
                 pyo3::callback::cb_convert(
                     pyo3::callback::PyObjectCallbackConverter, _py, _result)
             }
-        }
-    } else {
-        let body = impl_arg_params(&spec, body);
-
-        quote! {
+        },
+        CallingConvention::O => quote! {
             unsafe extern "C" fn __wrap(
-                _slf: *mut pyo3::ffi::PyObject,
-                _args: *mut pyo3::ffi::PyObject,
-                _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
+                #slf: *mut pyo3::ffi::PyObject,
+                _arg: *mut pyo3::ffi::PyObject,
+            ) -> *mut pyo3::ffi::PyObject
             {
-                const _LOCATION: &'static str = concat!(
-                    stringify!(#cls), ".", stringify!(#python_name), "()");
-                let _py = pyo3::Python::assume_gil_acquired();
-                let _pool = pyo3::GILPool::new(_py);
-                #slf
-                let _args = _py.from_borrowed_ptr::<pyo3::types::PyTuple>(_args);
-                let _kwargs: Option<&pyo3::types::PyDict> = _py.from_borrowed_ptr_or_opt(_kwargs);
+                #prelude
+                let output = [Some(_py.from_borrowed_ptr::<pyo3::types::PyAny>(_arg))];
 
-                #body
+                let _result = (|| {
+                    #(#param_conversion)*
+                    #into_result(#body)
+                })();
 
                 pyo3::callback::cb_convert(
                     pyo3::callback::PyObjectCallbackConverter, _py, _result)
             }
+        },
+        CallingConvention::Fast if cfg!(feature = "compact-codegen") => {
+            let params = impl_param_descriptions(spec);
+            let num_normal_params = params.len();
+            let accept_args = bool_to_ident(spec.accept_args());
+            quote! {
+                unsafe extern "C" fn __wrap(
+                    #slf: *mut pyo3::ffi::PyObject,
+                    _args: *const *mut pyo3::ffi::PyObject,
+                    _nargs: pyo3::ffi::Py_ssize_t,
+                    _kwnames: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
+                {
+                    static PARAMS: pyo3::argparse::FunctionParams =
+                        pyo3::argparse::FunctionParams::new(&[#(#params),*]);
+
+                    unsafe fn __body<'p>(
+                        _py: pyo3::Python<'p>,
+                        #slf: *mut pyo3::ffi::PyObject,
+                        output: &[Option<&'p pyo3::types::PyAny>]) -> *mut pyo3::ffi::PyObject
+                    {
+                        use pyo3::ObjectProtocol;
+                        #init
+                        let _result = (|| {
+                            #(#param_conversion)*
+                            #into_result(#body)
+                        })();
+
+                        pyo3::callback::cb_convert(
+                            pyo3::callback::PyObjectCallbackConverter, _py, _result)
+                    }
+
+                    pyo3::argparse::wrap_fast::<[Option<&pyo3::types::PyAny>; #num_normal_params]>(
+                        #location, &PARAMS, #accept_args, __body, #slf, _args, _nargs, _kwnames)
+                }
+
+                unsafe extern "C" fn __wrap_args(
+                    #slf: *mut pyo3::ffi::PyObject,
+                    _args: *mut pyo3::ffi::PyObject,
+                    _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
+                {
+                    pyo3::argparse::call_fast(__wrap, #slf, _args, _kwargs)
+                }
+            }
         }
+        CallingConvention::Varargs
+            if cfg!(feature = "compact-codegen") && !spec.args.is_empty() =>
+        {
+            let params = impl_param_descriptions(spec);
+            let num_normal_params = params.len();
+            let accept_args = bool_to_ident(spec.accept_args());
+            let accept_kwargs = bool_to_ident(spec.accept_kwargs());
+            quote! {
+                unsafe extern "C" fn __wrap(
+                    #slf: *mut pyo3::ffi::PyObject,
+                    _args: *mut pyo3::ffi::PyObject,
+                    _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
+                {
+                    static PARAMS: pyo3::argparse::FunctionParams =
+                        pyo3::argparse::FunctionParams::new(&[#(#params),*]);
+
+                    unsafe fn __body<'p>(
+                        _py: pyo3::Python<'p>,
+                        #slf: *mut pyo3::ffi::PyObject,
+                        output: &[Option<&'p pyo3::types::PyAny>],
+                        _args: &'p pyo3::types::PyTuple,
+                        _kwargs: Option<&'p pyo3::types::PyDict>) -> *mut pyo3::ffi::PyObject
+                    {
+                        use pyo3::ObjectProtocol;
+                        #init
+                        let _result = (|| {
+                            #(#param_conversion)*
+                            #into_result(#body)
+                        })();
+
+                        pyo3::callback::cb_convert(
+                            pyo3::callback::PyObjectCallbackConverter, _py, _result)
+                    }
+
+                    pyo3::argparse::wrap_varargs::<[Option<&pyo3::types::PyAny>; #num_normal_params]>(
+                        #location,
+                        &PARAMS,
+                        #accept_args,
+                        #accept_kwargs,
+                        __body,
+                        #slf,
+                        _args,
+                        _kwargs,
+                    )
+                }
+            }
+        }
+        CallingConvention::Fast => {
+            let params = impl_param_descriptions(spec);
+            let num_normal_params = params.len();
+            let accept_args = bool_to_ident(spec.accept_args());
+            quote! {
+                unsafe extern "C" fn __wrap(
+                    #slf: *mut pyo3::ffi::PyObject,
+                    _args: *const *mut pyo3::ffi::PyObject,
+                    _nargs: pyo3::ffi::Py_ssize_t,
+                    _kwnames: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
+                {
+                    #prelude
+                    static PARAMS: pyo3::argparse::FunctionParams =
+                        pyo3::argparse::FunctionParams::new(&[#(#params),*]);
+                    let mut output = [None; #num_normal_params];
+
+                    let _result = (|| {
+                        pyo3::argparse::parse_fast_args(
+                            _py,
+                            Some(_LOCATION),
+                            &PARAMS,
+                            _args,
+                            _nargs,
+                            _kwnames,
+                            #accept_args,
+                            &mut output
+                        )?;
+
+                        #(#param_conversion)*
+
+                        #into_result(#body)
+                    })();
+
+                    pyo3::callback::cb_convert(
+                        pyo3::callback::PyObjectCallbackConverter, _py, _result)
+                }
+
+                unsafe extern "C" fn __wrap_args(
+                    #slf: *mut pyo3::ffi::PyObject,
+                    _args: *mut pyo3::ffi::PyObject,
+                    _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
+                {
+                    pyo3::argparse::call_fast(__wrap, #slf, _args, _kwargs)
+                }
+            }
+        }
+        CallingConvention::Varargs => {
+            let body = impl_arg_params(&spec, body);
+            quote! {
+                #[allow(unused_mut)]
+                unsafe extern "C" fn __wrap(
+                    #slf: *mut pyo3::ffi::PyObject,
+                    _args: *mut pyo3::ffi::PyObject,
+                    _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
+                {
+                    #prelude
+                    let _args = _py.from_borrowed_ptr::<pyo3::types::PyTuple>(_args);
+                    let _kwargs: Option<&pyo3::types::PyDict> = _py.from_borrowed_ptr_or_opt(_kwargs);
+
+                    #body
+
+                    pyo3::callback::cb_convert(
+                        pyo3::callback::PyObjectCallbackConverter, _py, _result)
+                }
+            }
+        }
+    }
+}
+
+/// Wraps the result of a function returning `impl Iterator` in a `PyIter`, which converts the
+/// items lazily, as the iterator itself can't implement `IntoPy`
+fn impl_wrap_iterator(output: &syn::Type, body: TokenStream) -> TokenStream {
+    if utils::impl_iterator_item(output).is_some() {
+        quote! { pyo3::pyiter::PyIter::new(#body) }
+    } else if utils::result_ok_type(output)
+        .and_then(utils::impl_iterator_item)
+        .is_some()
+    {
+        quote! { (#body).map(pyo3::pyiter::PyIter::new) }
+    } else {
+        body
     }
 }
 
+/// Generate wrapper for `__repr__` and `__str__` (reprfunc)
+fn impl_wrap_repr(cls: &syn::Type, spec: &FnSpec<'_>) -> syn::Result<TokenStream> {
+    if !spec.args.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &spec.name,
+            format!("{} can not have arguments", spec.python_name),
+        ));
+    }
+    let python_name = &spec.python_name;
+    let body = impl_call(cls, &spec);
+    let slf = impl_borrow_self(cls, spec.mut_self, quote! { ::std::ptr::null_mut() });
+
+    Ok(quote! {
+        unsafe extern "C" fn __wrap(
+            _slf: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
+        {
+            const _LOCATION: &'static str = concat!(stringify!(#cls),".",stringify!(#python_name),"()");
+            let _py = pyo3::Python::assume_gil_acquired();
+            let _pool = pyo3::GILPool::new(_py);
+            #slf
+            let _result = pyo3::derive_utils::IntoPyResult::into_py_result(#body);
+
+            pyo3::callback::cb_convert(
+                pyo3::callback::PyObjectCallbackConverter, _py, _result)
+        }
+    })
+}
+
 /// Generate function wrapper for protocol method (PyCFunction, PyCFunctionWithKeywords)
 pub fn impl_proto_wrap(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
     let python_name = &spec.python_name;
     let cb = impl_call(cls, &spec);
     let body = impl_arg_params(&spec, cb);
+    let slf = impl_borrow_self(cls, spec.mut_self, quote! { ::std::ptr::null_mut() });
 
     quote! {
         #[allow(unused_mut)]
@@ -148,7 +434,7 @@ pub fn impl_proto_wrap(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
             const _LOCATION: &'static str = concat!(stringify!(#cls),".",stringify!(#python_name),"()");
             let _py = pyo3::Python::assume_gil_acquired();
             let _pool = pyo3::GILPool::new(_py);
-            let _slf = _py.mut_from_borrowed_ptr::<#cls>(_slf);
+            #slf
             let _args = _py.from_borrowed_ptr::<pyo3::types::PyTuple>(_args);
             let _kwargs: Option<&pyo3::types::PyDict> = _py.from_borrowed_ptr_or_opt(_kwargs);
 
@@ -197,65 +483,38 @@ pub fn impl_wrap_new(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
     }
 }
 
-/// Generate class method wrapper (PyCFunction, PyCFunctionWithKeywords)
+/// Generate class method wrapper (PyCFunction, PyCFunctionWithKeywords, _PyCFunctionFastWithKeywords)
 pub fn impl_wrap_class(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
     let name = &spec.name;
     let python_name = &spec.python_name;
     let names: Vec<syn::Ident> = get_arg_names(&spec);
     let cb = quote! { #cls::#name(&_cls, #(#names),*) };
 
-    let body = impl_arg_params(spec, cb);
-
-    quote! {
-        #[allow(unused_mut)]
-        unsafe extern "C" fn __wrap(
-            _cls: *mut pyo3::ffi::PyObject,
-            _args: *mut pyo3::ffi::PyObject,
-            _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
-        {
-            const _LOCATION: &'static str = concat!(stringify!(#cls),".",stringify!(#python_name),"()");
-            let _py = pyo3::Python::assume_gil_acquired();
-            let _pool = pyo3::GILPool::new(_py);
+    impl_wrap_convention(
+        spec,
+        quote! { concat!(stringify!(#cls), ".", stringify!(#python_name), "()") },
+        quote! { _cls },
+        quote! {
             let _cls = pyo3::types::PyType::from_type_ptr(_py, _cls as *mut pyo3::ffi::PyTypeObject);
-            let _args = _py.from_borrowed_ptr::<pyo3::types::PyTuple>(_args);
-            let _kwargs: Option<&pyo3::types::PyDict> = _py.from_borrowed_ptr_or_opt(_kwargs);
-
-            #body
-
-            pyo3::callback::cb_convert(
-                pyo3::callback::PyObjectCallbackConverter, _py, _result)
-        }
-    }
+        },
+        cb,
+    )
 }
 
-/// Generate static method wrapper (PyCFunction, PyCFunctionWithKeywords)
+/// Generate static method wrapper (PyCFunction, PyCFunctionWithKeywords, _PyCFunctionFastWithKeywords)
 pub fn impl_wrap_static(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
     let name = &spec.name;
     let python_name = &spec.python_name;
     let names: Vec<syn::Ident> = get_arg_names(&spec);
     let cb = quote! { #cls::#name(#(#names),*) };
 
-    let body = impl_arg_params(spec, cb);
-
-    quote! {
-        #[allow(unused_mut)]
-        unsafe extern "C" fn __wrap(
-            _slf: *mut pyo3::ffi::PyObject,
-            _args: *mut pyo3::ffi::PyObject,
-            _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
-        {
-            const _LOCATION: &'static str = concat!(stringify!(#cls),".",stringify!(#python_name),"()");
-            let _py = pyo3::Python::assume_gil_acquired();
-            let _pool = pyo3::GILPool::new(_py);
-            let _args = _py.from_borrowed_ptr::<pyo3::types::PyTuple>(_args);
-            let _kwargs: Option<&pyo3::types::PyDict> = _py.from_borrowed_ptr_or_opt(_kwargs);
-
-            #body
-
-            pyo3::callback::cb_convert(
-                pyo3::callback::PyObjectCallbackConverter, _py, _result)
-        }
-    }
+    impl_wrap_convention(
+        spec,
+        quote! { concat!(stringify!(#cls), ".", stringify!(#python_name), "()") },
+        quote! { _slf },
+        quote! {},
+        cb,
+    )
 }
 
 fn impl_call_getter(spec: &FnSpec) -> syn::Result<TokenStream> {
@@ -282,7 +541,7 @@ pub(crate) fn impl_wrap_getter(
     cls: &syn::Type,
     property_type: PropertyType,
 ) -> syn::Result<TokenStream> {
-    let (python_name, getter_impl) = match property_type {
+    let (python_name, getter_impl, mut_self) = match property_type {
         PropertyType::Descriptor(field) => {
             let name = field.ident.as_ref().unwrap();
             (
@@ -291,10 +550,16 @@ pub(crate) fn impl_wrap_getter(
                     use pyo3::derive_utils::GetPropertyValue;
                     (&_slf.#name).get_property_value(_py)
                 }),
+                false,
             )
         }
-        PropertyType::Function(spec) => (spec.python_name.clone(), impl_call_getter(&spec)?),
+        PropertyType::Function(spec) => (
+            spec.python_name.clone(),
+            impl_call_getter(&spec)?,
+            spec.mut_self,
+        ),
     };
+    let slf = impl_borrow_self(cls, mut_self, quote! { ::std::ptr::null_mut() });
 
     Ok(quote! {
         unsafe extern "C" fn __wrap(
@@ -304,19 +569,12 @@ pub(crate) fn impl_wrap_getter(
 
             let _py = pyo3::Python::assume_gil_acquired();
             let _pool = pyo3::GILPool::new(_py);
-            let _slf = _py.mut_from_borrowed_ptr::<#cls>(_slf);
+            #slf
 
-            let result = pyo3::derive_utils::IntoPyResult::into_py_result(#getter_impl);
+            let _result = pyo3::derive_utils::IntoPyResult::into_py_result(#getter_impl);
 
-            match result {
-                Ok(val) => {
-                    pyo3::IntoPyPointer::into_ptr(pyo3::IntoPy::<PyObject>::into_py(val, _py))
-                }
-                Err(e) => {
-                    e.restore(_py);
-                    ::std::ptr::null_mut()
-                }
-            }
+            pyo3::callback::cb_convert(
+                pyo3::callback::PyObjectCallbackConverter, _py, _result)
         }
     })
 }
@@ -351,13 +609,18 @@ pub(crate) fn impl_wrap_setter(
     cls: &syn::Type,
     property_type: PropertyType,
 ) -> syn::Result<TokenStream> {
-    let (python_name, setter_impl) = match property_type {
+    let (python_name, setter_impl, mut_self) = match property_type {
         PropertyType::Descriptor(field) => {
             let name = field.ident.as_ref().unwrap();
-            (name.unraw(), quote!({ _slf.#name = _val; Ok(()) }))
+            (name.unraw(), quote!({ _slf.#name = _val; Ok(()) }), true)
         }
-        PropertyType::Function(spec) => (spec.python_name.clone(), impl_call_setter(&spec)?),
+        PropertyType::Function(spec) => (
+            spec.python_name.clone(),
+            impl_call_setter(&spec)?,
+            spec.mut_self,
+        ),
     };
+    let slf = impl_borrow_self(cls, mut_self, quote! { -1 });
 
     Ok(quote! {
         #[allow(unused_mut)]
@@ -368,22 +631,59 @@ pub(crate) fn impl_wrap_setter(
             const _LOCATION: &'static str = concat!(stringify!(#cls),".",stringify!(#python_name),"()");
             let _py = pyo3::Python::assume_gil_acquired();
             let _pool = pyo3::GILPool::new(_py);
-            let _slf = _py.mut_from_borrowed_ptr::<#cls>(_slf);
-            let _value = _py.from_borrowed_ptr(_value);
+            if _value.is_null() {
+                pyo3::callback::restore_err(
+                    _py, pyo3::exceptions::AttributeError::py_err("can't delete attribute"));
+                return -1;
+            }
+            #slf
+            let _value: &pyo3::types::PyAny = _py.from_borrowed_ptr(_value);
 
-            let _result = match pyo3::FromPyObject::extract(_value) {
+            let _result = match pyo3::ObjectProtocol::extract(_value) {
                 Ok(_val) => {
                     #setter_impl
                 }
                 Err(e) => Err(e)
             };
-            match _result {
-                Ok(_) => 0,
-                Err(e) => {
-                    e.restore(_py);
-                    -1
-                }
-            }
+
+            pyo3::callback::cb_convert(pyo3::callback::UnitCallbackConverter, _py, _result)
+        }
+    })
+}
+
+/// Generate a function wrapper called `__wrap` for a property deleter, which is called like a
+/// setter without a value
+fn impl_wrap_deleter(cls: &syn::Type, spec: &FnSpec) -> syn::Result<TokenStream> {
+    let (py_arg, args) = split_off_python_arg(&spec.args);
+    if !args.is_empty() {
+        return Err(syn::Error::new_spanned(
+            args[0].ty,
+            "Deleter function can only have one argument of type pyo3::Python",
+        ));
+    }
+
+    let name = &spec.name;
+    let python_name = &spec.python_name;
+    let fncall = if py_arg.is_some() {
+        quote! { _slf.#name(_py) }
+    } else {
+        quote! { _slf.#name() }
+    };
+    let slf = impl_borrow_self(cls, spec.mut_self, quote! { -1 });
+
+    Ok(quote! {
+        unsafe extern "C" fn __wrap(
+            _slf: *mut pyo3::ffi::PyObject,
+            _: *mut pyo3::ffi::PyObject, _: *mut ::std::os::raw::c_void) -> pyo3::libc::c_int
+        {
+            const _LOCATION: &'static str = concat!(stringify!(#cls),".",stringify!(#python_name),"()");
+            let _py = pyo3::Python::assume_gil_acquired();
+            let _pool = pyo3::GILPool::new(_py);
+            #slf
+
+            let _result = pyo3::derive_utils::IntoPyResult::into_py_result(#fncall);
+
+            pyo3::callback::cb_convert(pyo3::callback::UnitCallbackConverter, _py, _result)
         }
     })
 }
@@ -401,6 +701,28 @@ fn impl_call(_cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
     quote! { _slf.#fname(#(#names),*) }
 }
 
+/// Borrows the object for a method taking `&self` or `&mut self`. Python code can call back into
+/// the same object while a method runs, so a conflicting borrow raises a `RuntimeError` and
+/// returns `on_err` instead of creating aliasing references.
+fn impl_borrow_self(cls: &syn::Type, mut_self: bool, on_err: TokenStream) -> TokenStream {
+    let borrow = if mut_self {
+        quote! { try_borrow_mut }
+    } else {
+        quote! { try_borrow }
+    };
+    quote! {
+        let _cell: &pyo3::PyClassShell<#cls> = pyo3::FromPyPointer::from_borrowed_ptr(_py, _slf);
+        #[allow(unused_mut)]
+        let mut _slf = match _cell.#borrow() {
+            Ok(_slf) => _slf,
+            Err(e) => {
+                pyo3::callback::restore_err(_py, e);
+                return #on_err;
+            }
+        };
+    }
+}
+
 fn impl_self<T: quote::ToTokens>(self_ty: &T) -> TokenStream {
     quote! {
         let _slf: #self_ty = pyo3::FromPyPointer::from_borrowed_ptr(_py, _slf);
@@ -416,15 +738,8 @@ fn bool_to_ident(condition: bool) -> syn::Ident {
     }
 }
 
-fn impl_arg_params_(spec: &FnSpec<'_>, body: TokenStream, into_result: TokenStream) -> TokenStream {
-    if spec.args.is_empty() {
-        return quote! {
-            let _result = {
-                #into_result (#body)
-            };
-        };
-    }
-
+/// The `ParamDescription`s of the arguments which aren't `py`, `*args` or `**kwargs`
+fn impl_param_descriptions(spec: &FnSpec<'_>) -> Vec<TokenStream> {
     let mut params = Vec::new();
 
     for arg in spec.args.iter() {
@@ -436,13 +751,26 @@ fn impl_arg_params_(spec: &FnSpec<'_>, body: TokenStream, into_result: TokenStre
         let opt = bool_to_ident(arg.optional.is_some() || spec.default_value(&arg.name).is_some());
 
         params.push(quote! {
-            pyo3::derive_utils::ParamDescription {
+            pyo3::argparse::ParamDescription {
                 name: stringify!(#name),
                 is_optional: #opt,
                 kw_only: #kwonly
             }
         });
     }
+    params
+}
+
+fn impl_arg_params_(spec: &FnSpec<'_>, body: TokenStream, into_result: TokenStream) -> TokenStream {
+    if spec.args.is_empty() {
+        return quote! {
+            let _result = {
+                #into_result (#body)
+            };
+        };
+    }
+
+    let params = impl_param_descriptions(spec);
 
     let mut param_conversion = Vec::new();
     let mut option_pos = 0;
@@ -456,9 +784,8 @@ fn impl_arg_params_(spec: &FnSpec<'_>, body: TokenStream, into_result: TokenStre
     // create array of arguments, and then parse
     quote! {
         use pyo3::ObjectProtocol;
-        const PARAMS: &'static [pyo3::derive_utils::ParamDescription] = &[
-            #(#params),*
-        ];
+        static PARAMS: pyo3::argparse::FunctionParams =
+            pyo3::argparse::FunctionParams::new(&[#(#params),*]);
 
         let mut output = [None; #num_normal_params];
         let mut _args = _args;
@@ -466,9 +793,9 @@ fn impl_arg_params_(spec: &FnSpec<'_>, body: TokenStream, into_result: TokenStre
 
         // Workaround to use the question mark operator without rewriting everything
         let _result = (|| {
-            let (_args, _kwargs) = pyo3::derive_utils::parse_fn_args(
+            let (_args, _kwargs) = pyo3::argparse::parse_fn_args(
                 Some(_LOCATION),
-                PARAMS,
+                &PARAMS,
                 _args,
                 _kwargs,
                 #accept_args,
@@ -512,7 +839,7 @@ fn impl_arg_param(
 
     if spec.is_args(&name) {
         return quote! {
-            let #arg_name = <#ty as pyo3::FromPyObject>::extract(_args.as_ref())?;
+            let #arg_name: #ty = pyo3::ObjectProtocol::extract(_args)?;
         };
     } else if spec.is_kwargs(&name) {
         return quote! {
@@ -532,27 +859,15 @@ fn impl_arg_param(
             quote! { None }
         };
         quote! {
-            let #arg_name = match #arg_value.as_ref() {
-                Some(_obj) => {
-                    if _obj.is_none() {
-                        #default
-                    } else {
-                        Some(_obj.extract()?)
-                    }
-                },
+            let #arg_name = match pyo3::argparse::provided(#arg_value) {
+                Some(_obj) => Some(_obj.extract()?),
                 None => #default
             };
         }
     } else if let Some(default) = spec.default_value(name) {
         quote! {
-            let #arg_name = match #arg_value.as_ref() {
-                Some(_obj) => {
-                    if _obj.is_none() {
-                        #default
-                    } else {
-                        _obj.extract()?
-                    }
-                },
+            let #arg_name = match pyo3::argparse::provided(#arg_value) {
+                Some(_obj) => _obj.extract()?,
                 None => #default
             };
         }
@@ -563,35 +878,44 @@ fn impl_arg_param(
     }
 }
 
+/// The `ml_meth` and `ml_flags` of the `PyMethodDef` of a wrapper generated by
+/// `impl_wrap_convention`
+pub fn impl_method_type(spec: &FnSpec) -> (TokenStream, TokenStream) {
+    match spec.calling_convention() {
+        CallingConvention::NoArgs => (
+            quote! { pyo3::class::PyMethodType::PyCFunction(__wrap) },
+            quote! { pyo3::ffi::METH_NOARGS },
+        ),
+        CallingConvention::O => (
+            quote! { pyo3::class::PyMethodType::PyCFunction(__wrap) },
+            quote! { pyo3::ffi::METH_O },
+        ),
+        CallingConvention::Fast => (
+            quote! { pyo3::class::PyMethodType::PyCFunctionFastWithKeywords(__wrap, __wrap_args) },
+            quote! { pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS },
+        ),
+        CallingConvention::Varargs => (
+            quote! { pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap) },
+            quote! { pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS },
+        ),
+    }
+}
+
 pub fn impl_py_method_def(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
     let python_name = &spec.python_name;
     let doc = &spec.doc;
-    if spec.args.is_empty() {
-        quote! {
-            pyo3::class::PyMethodDefType::Method({
-                #wrapper
-
-                pyo3::class::PyMethodDef {
-                    ml_name: stringify!(#python_name),
-                    ml_meth: pyo3::class::PyMethodType::PyCFunction(__wrap),
-                    ml_flags: pyo3::ffi::METH_NOARGS,
-                    ml_doc: #doc,
-                }
-            })
-        }
-    } else {
-        quote! {
-            pyo3::class::PyMethodDefType::Method({
-                #wrapper
-
-                pyo3::class::PyMethodDef {
-                    ml_name: stringify!(#python_name),
-                    ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
-                    ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS,
-                    ml_doc: #doc,
-                }
-            })
-        }
+    let (meth, flags) = impl_method_type(spec);
+    quote! {
+        pyo3::class::PyMethodDefType::Method({
+            #wrapper
+
+            pyo3::class::PyMethodDef {
+                ml_name: stringify!(#python_name),
+                ml_meth: #meth,
+                ml_flags: #flags,
+                ml_doc: #doc,
+            }
+        })
     }
 }
 
@@ -615,15 +939,15 @@ pub fn impl_py_method_def_new(spec: &FnSpec, wrapper: &TokenStream) -> TokenStre
 pub fn impl_py_method_def_class(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
     let python_name = &spec.python_name;
     let doc = &spec.doc;
+    let (meth, flags) = impl_method_type(spec);
     quote! {
         pyo3::class::PyMethodDefType::Class({
             #wrapper
 
             pyo3::class::PyMethodDef {
                 ml_name: stringify!(#python_name),
-                ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
-                ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS |
-                pyo3::ffi::METH_CLASS,
+                ml_meth: #meth,
+                ml_flags: #flags | pyo3::ffi::METH_CLASS,
                 ml_doc: #doc,
             }
         })
@@ -633,20 +957,38 @@ pub fn impl_py_method_def_class(spec: &FnSpec, wrapper: &TokenStream) -> TokenSt
 pub fn impl_py_method_def_static(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
     let python_name = &spec.python_name;
     let doc = &spec.doc;
+    let (meth, flags) = impl_method_type(spec);
     quote! {
         pyo3::class::PyMethodDefType::Static({
             #wrapper
 
             pyo3::class::PyMethodDef {
                 ml_name: stringify!(#python_name),
-                ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
-                ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS | pyo3::ffi::METH_STATIC,
+                ml_meth: #meth,
+                ml_flags: #flags | pyo3::ffi::METH_STATIC,
                 ml_doc: #doc,
             }
         })
     }
 }
 
+/// Generates the definition of a `#[classattr]`, whose value is the result of `value`
+pub fn impl_py_class_attribute(python_name: &syn::Ident, value: TokenStream) -> TokenStream {
+    quote! {
+        pyo3::class::PyMethodDefType::ClassAttribute({
+            fn __wrap(_py: pyo3::Python) -> pyo3::PyResult<pyo3::PyObject> {
+                pyo3::derive_utils::IntoPyResult::into_py_result(#value)
+                    .map(|value| pyo3::IntoPy::into_py(value, _py))
+            }
+
+            pyo3::class::PyClassAttributeDef {
+                name: stringify!(#python_name),
+                meth: __wrap,
+            }
+        })
+    }
+}
+
 pub fn impl_py_method_def_call(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
     let python_name = &spec.python_name;
     let doc = &spec.doc;
@@ -664,6 +1006,21 @@ pub fn impl_py_method_def_call(spec: &FnSpec, wrapper: &TokenStream) -> TokenStr
     }
 }
 
+fn impl_py_method_def_repr(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
+    let variant = if spec.python_name == "__repr__" {
+        quote! { Repr }
+    } else {
+        quote! { Str }
+    };
+    quote! {
+        pyo3::class::PyMethodDefType::#variant({
+            #wrapper
+
+            __wrap
+        })
+    }
+}
+
 pub(crate) fn impl_py_setter_def(
     python_name: &syn::Ident,
     doc: &syn::LitStr,
@@ -682,6 +1039,24 @@ pub(crate) fn impl_py_setter_def(
     }
 }
 
+fn impl_py_deleter_def(
+    python_name: &syn::Ident,
+    doc: &syn::LitStr,
+    wrapper: &TokenStream,
+) -> TokenStream {
+    quote! {
+        pyo3::class::PyMethodDefType::Deleter({
+            #wrapper
+
+            pyo3::class::PyDeleterDef {
+                name: stringify!(#python_name),
+                meth: __wrap,
+                doc: #doc,
+            }
+        })
+    }
+}
+
 pub(crate) fn impl_py_getter_def(
     python_name: &syn::Ident,
     doc: &syn::LitStr,
@@ -0,0 +1,244 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//! Code generation for the `stubs` feature, which records typed signatures for `.pyi` files
+
+use crate::method::{CallingConvention, FnSpec, FnType};
+use crate::utils;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+
+/// Registers the typed signature of a `#[pyfunction]`, for each C function implementing it
+pub fn function_stub(spec: &FnSpec) -> TokenStream {
+    let signature = signature(spec, None, None, None);
+    let wrappers = match spec.calling_convention() {
+        // Which one is used depends on the Python version
+        CallingConvention::Fast => vec![quote!(__wrap), quote!(__wrap_args)],
+        _ => vec![quote!(__wrap)],
+    };
+    quote! {
+        #(
+            pyo3::__pyo3_stub! {
+                pyo3::stubs::FunctionStub {
+                    function: #wrappers as usize,
+                    signature: #signature,
+                }
+            }
+        )*
+    }
+}
+
+/// Registers the typed signature of a method, property or constructor of `cls`
+pub fn member_stub(cls: &syn::Type, spec: &FnSpec) -> TokenStream {
+    let python_name = spec.python_name.unraw().to_string();
+    let (kind, name, receiver, returns) = match spec.tp {
+        FnType::Fn | FnType::PySelf(_) => (quote!(Method), python_name, Some("self"), None),
+        FnType::FnCall => (quote!(Method), "__call__".to_string(), Some("self"), None),
+        FnType::FnNew => (
+            quote!(Method),
+            "__init__".to_string(),
+            Some("self"),
+            Some("None"),
+        ),
+        FnType::FnClass => (quote!(ClassMethod), python_name, Some("cls"), None),
+        FnType::FnStatic => (quote!(StaticMethod), python_name, None, None),
+        FnType::Getter => (quote!(Getter), python_name, Some("self"), None),
+        FnType::Setter => (quote!(Setter), python_name, Some("self"), Some("None")),
+        FnType::Deleter => (quote!(Deleter), python_name, Some("self"), Some("None")),
+        FnType::ClassAttribute => return class_attribute_stub(cls, &python_name, &spec.output),
+    };
+    let signature = signature(spec, receiver, Some(cls), returns);
+    member(cls, kind, &name, &signature)
+}
+
+/// Registers the typed signature of the getter or setter of a `#[pyo3(get, set)]` field
+pub fn field_stub(cls: &syn::Type, field: &syn::Field, tp: &FnType) -> TokenStream {
+    let name = field.ident.as_ref().unwrap().unraw().to_string();
+    let ty = python_type(&field.ty, Some(cls));
+    match tp {
+        FnType::Getter => member(cls, quote!(Getter), &name, &format!("(self) -> {}", ty)),
+        FnType::Setter => member(
+            cls,
+            quote!(Setter),
+            &name,
+            &format!("(self, value: {}) -> None", ty),
+        ),
+        _ => unreachable!(),
+    }
+}
+
+/// Registers the type of a `#[classattr]`
+pub fn class_attribute_stub(cls: &syn::Type, name: &str, ty: &syn::Type) -> TokenStream {
+    member(
+        cls,
+        quote!(ClassAttribute),
+        name,
+        &python_type(ty, Some(cls)),
+    )
+}
+
+/// The stubs of the members generated by `#[pyclass(resource)]`
+pub fn resource_stubs(cls: &syn::Type) -> TokenStream {
+    let class = python_type(cls, None);
+    let stubs = vec![
+        member(cls, quote!(Method), "close", "(self) -> None"),
+        member(cls, quote!(Getter), "closed", "(self) -> bool"),
+        member(
+            cls,
+            quote!(Method),
+            "__enter__",
+            &format!("(self) -> {}", class),
+        ),
+        member(
+            cls,
+            quote!(Method),
+            "__exit__",
+            "(self, *args: Any) -> None",
+        ),
+    ];
+    quote! { #(#stubs)* }
+}
+
+fn member(cls: &syn::Type, kind: TokenStream, name: &str, signature: &str) -> TokenStream {
+    quote! {
+        pyo3::__pyo3_stub! {
+            pyo3::stubs::MemberStub {
+                owner: <#cls as pyo3::type_object::PyTypeInfo>::type_object,
+                kind: pyo3::stubs::MemberKind::#kind,
+                name: #name,
+                signature: #signature,
+            }
+        }
+    }
+}
+
+/// Writes the annotated parameters and return type of `spec`, e.g. `(a: int) -> str`
+fn signature(
+    spec: &FnSpec,
+    receiver: Option<&str>,
+    cls: Option<&syn::Type>,
+    returns: Option<&str>,
+) -> String {
+    let annotate = |ty: &syn::Type| python_type(ty, cls);
+    let params = spec
+        .python_params(receiver, Some(&annotate))
+        .unwrap_or_else(|| {
+            // e.g. a required argument after an optional one, which Python can't express
+            let mut params: Vec<String> = receiver.map(str::to_string).into_iter().collect();
+            params.push("*args: Any".to_string());
+            params.push("**kwargs: Any".to_string());
+            params
+        });
+    let returns = returns
+        .map(str::to_string)
+        .unwrap_or_else(|| python_type(&spec.output, cls));
+    format!("({}) -> {}", params.join(", "), returns)
+}
+
+/// The Python annotation for the Rust type `ty`, following the conversions pyo3 implements.
+///
+/// Types without a Python equivalent are written with their Rust name, which is the name of the
+/// class for `#[pyclass]`es. `Self` is replaced with `cls`.
+pub fn python_type(ty: &syn::Type, cls: Option<&syn::Type>) -> String {
+    let path = match ty {
+        syn::Type::Reference(reference) => return python_type(&reference.elem, cls),
+        syn::Type::Paren(paren) => return python_type(&paren.elem, cls),
+        syn::Type::Group(group) => return python_type(&group.elem, cls),
+        syn::Type::Slice(slice) => return format!("List[{}]", python_type(&slice.elem, cls)),
+        syn::Type::Array(array) => return format!("List[{}]", python_type(&array.elem, cls)),
+        // The return type of functions without one
+        syn::Type::Infer(_) => return "None".to_string(),
+        syn::Type::Tuple(tuple) if tuple.elems.is_empty() => return "None".to_string(),
+        syn::Type::Tuple(tuple) => {
+            let elems: Vec<String> = tuple.elems.iter().map(|t| python_type(t, cls)).collect();
+            return format!("Tuple[{}]", elems.join(", "));
+        }
+        syn::Type::ImplTrait(_) => {
+            return match utils::impl_iterator_item(ty) {
+                Some(item) => format!("Iterator[{}]", python_type(item, cls)),
+                None => "Any".to_string(),
+            }
+        }
+        syn::Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return "Any".to_string(),
+    };
+
+    let segment = path.segments.last().unwrap();
+    let args: Vec<&syn::Type> = match segment.arguments {
+        syn::PathArguments::AngleBracketed(ref args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let arg = |i: usize| {
+        args.get(i)
+            .map_or_else(|| "Any".to_string(), |ty| python_type(ty, cls))
+    };
+
+    let name = segment.ident.unraw().to_string();
+    match name.as_str() {
+        "Self" => cls.map_or_else(|| "Any".to_string(), |cls| python_type(cls, None)),
+        "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64" | "u64" | "i128" | "u128" | "isize"
+        | "usize" | "BigInt" | "BigUint" | "PyLong" => "int".to_string(),
+        "f32" | "f64" | "PyFloat" => "float".to_string(),
+        "bool" | "PyBool" => "bool".to_string(),
+        "str" | "String" | "char" | "PyString" => "str".to_string(),
+        "Complex" | "PyComplex" => "complex".to_string(),
+        "PyBytes" => "bytes".to_string(),
+        "PyByteArray" => "bytearray".to_string(),
+        "PyDict" => "dict".to_string(),
+        "PyList" => "list".to_string(),
+        "PyTuple" => "tuple".to_string(),
+        "PySet" => "set".to_string(),
+        "PyFrozenSet" => "frozenset".to_string(),
+        "PySlice" => "slice".to_string(),
+        "PyType" => "type".to_string(),
+        "PyIter" => "Iterator[Any]".to_string(),
+        "Vec" => format!("List[{}]", arg(0)),
+        "HashSet" | "BTreeSet" => format!("Set[{}]", arg(0)),
+        "HashMap" | "BTreeMap" => format!("Dict[{}, {}]", arg(0), arg(1)),
+        "Option" => format!("Optional[{}]", arg(0)),
+        // Wrappers that convert like their content
+        "PyResult" | "Result" | "Box" | "Rc" | "Arc" | "Cow" | "Py" | "PyRef" | "PyRefMut"
+        | "PyClassShell" => arg(0),
+        "PyObject" | "PyAny" | "PyModule" | "PySequence" | "PyMapping" | "PyIterator" => {
+            "Any".to_string()
+        }
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::python_type;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_python_type() {
+        let cls: syn::Type = parse_quote!(Point);
+        let check = |ty: syn::Type, expected: &str| {
+            assert_eq!(python_type(&ty, Some(&cls)), expected);
+        };
+        check(parse_quote!(u8), "int");
+        check(parse_quote!(&str), "str");
+        check(parse_quote!(PyResult<()>), "None");
+        check(parse_quote!(Option<Vec<f64>>), "Optional[List[float]]");
+        check(
+            parse_quote!(HashMap<String, (i32, bool)>),
+            "Dict[str, Tuple[int, bool]]",
+        );
+        check(parse_quote!(&[Cow<'a, str>]), "List[str]");
+        check(parse_quote!(PyRef<Self>), "Point");
+        check(parse_quote!(Py<Other>), "Other");
+        check(parse_quote!(&PyAny), "Any");
+        check(parse_quote!(fn()), "Any");
+        check(
+            parse_quote!(impl Iterator<Item = (u8, String)> + Send),
+            "Iterator[Tuple[int, str]]",
+        );
+    }
+}
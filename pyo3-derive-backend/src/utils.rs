@@ -21,6 +21,53 @@ pub fn if_type_is_python(ty: &syn::Type) -> bool {
     }
 }
 
+/// The `Item` of `impl Iterator<Item = T>`, or of another trait ending in `Iterator`, e.g.
+/// `impl ExactSizeIterator<Item = T> + Send`.
+pub fn impl_iterator_item(ty: &syn::Type) -> Option<&syn::Type> {
+    let bounds = match ty {
+        syn::Type::ImplTrait(impl_trait) => &impl_trait.bounds,
+        _ => return None,
+    };
+    bounds.iter().find_map(|bound| {
+        let segment = match bound {
+            syn::TypeParamBound::Trait(bound) => bound.path.segments.last()?,
+            _ => return None,
+        };
+        if !segment.ident.to_string().ends_with("Iterator") {
+            return None;
+        }
+        match &segment.arguments {
+            syn::PathArguments::AngleBracketed(args) => {
+                args.args.iter().find_map(|arg| match arg {
+                    syn::GenericArgument::Binding(binding) if binding.ident == "Item" => {
+                        Some(&binding.ty)
+                    }
+                    _ => None,
+                })
+            }
+            _ => None,
+        }
+    })
+}
+
+/// The `T` of `PyResult<T>` or `Result<T, E>`.
+pub fn result_ok_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let segment = match ty {
+        syn::Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "PyResult" && segment.ident != "Result" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 pub fn is_text_signature_attr(attr: &syn::Attribute) -> bool {
     attr.path.is_ident("text_signature")
 }
@@ -93,6 +140,23 @@ pub fn parse_text_signature_attrs<T: Display + quote::ToTokens + ?Sized>(
     Ok(text_signature)
 }
 
+/// Whether the doc comments start with a signature followed by a `--` line, the format CPython
+/// uses for `__text_signature__`.
+pub fn has_doc_signature(attrs: &[syn::Attribute]) -> bool {
+    let mut lines = attrs.iter().filter_map(|attr| match attr.parse_meta() {
+        Ok(syn::Meta::NameValue(syn::MetaNameValue {
+            ref path,
+            lit: syn::Lit::Str(ref litstr),
+            ..
+        })) if path.is_ident("doc") => Some(litstr.value()),
+        _ => None,
+    });
+    lines
+        .next()
+        .map_or(false, |line| line.trim_end().ends_with(')'))
+        && lines.next().map_or(false, |line| line.trim() == "--")
+}
+
 // FIXME(althonos): not sure the docstring formatting is on par here.
 pub fn get_doc(
     attrs: &[syn::Attribute],
@@ -5,11 +5,12 @@
 extern crate proc_macro;
 use proc_macro::TokenStream;
 use pyo3_derive_backend::{
-    build_py_class, build_py_function, build_py_methods, build_py_proto, get_doc,
-    process_functions_in_module, py_init, PyClassArgs, PyFunctionAttr,
+    build_columnar, build_from_pyobject, build_int_enum, build_pickle, build_py_class,
+    build_py_enum, build_py_function, build_py_methods, build_py_proto, get_doc,
+    process_functions_in_module, py_init, PyClassArgs, PyFunctionAttr, PyModuleArgs,
 };
 use quote::quote;
-use syn::parse_macro_input;
+use syn::{parse_macro_input, parse_quote};
 
 /// Internally, this proc macro create a new c function called `PyInit_{my_module}`
 /// that then calls the init function you provided
@@ -17,20 +18,19 @@ use syn::parse_macro_input;
 pub fn pymodule(attr: TokenStream, input: TokenStream) -> TokenStream {
     let mut ast = parse_macro_input!(input as syn::ItemFn);
 
-    let modname = if attr.is_empty() {
-        ast.sig.ident.clone()
-    } else {
-        parse_macro_input!(attr as syn::Ident)
-    };
+    let args = parse_macro_input!(attr as PyModuleArgs);
+    let modname = args.name.clone().unwrap_or_else(|| ast.sig.ident.clone());
 
-    process_functions_in_module(&mut ast);
+    if let Err(err) = process_functions_in_module(&mut ast) {
+        return err.to_compile_error().into();
+    }
 
-    let doc = match get_doc(&ast.attrs, None, false) {
+    let doc = match get_doc(&ast.attrs, None, true) {
         Ok(doc) => doc,
         Err(err) => return err.to_compile_error().into(),
     };
 
-    let expanded = py_init(&ast.sig.ident, &modname, doc);
+    let expanded = py_init(&ast.sig.ident, &modname, &args, doc);
 
     quote!(
         #ast
@@ -42,6 +42,14 @@ pub fn pymodule(attr: TokenStream, input: TokenStream) -> TokenStream {
 #[proc_macro_attribute]
 pub fn pyproto(_: TokenStream, input: TokenStream) -> TokenStream {
     let mut ast = parse_macro_input!(input as syn::ItemImpl);
+    if cfg!(feature = "stable") {
+        return syn::Error::new_spanned(
+            ast.impl_token,
+            "#[pyproto] requires specialization and is not available with the `stable` feature",
+        )
+        .to_compile_error()
+        .into();
+    }
     let expanded = build_py_proto(&mut ast).unwrap_or_else(|e| e.to_compile_error());
 
     quote!(
@@ -53,9 +61,30 @@ pub fn pyproto(_: TokenStream, input: TokenStream) -> TokenStream {
 
 #[proc_macro_attribute]
 pub fn pyclass(attr: TokenStream, input: TokenStream) -> TokenStream {
-    let mut ast = parse_macro_input!(input as syn::ItemStruct);
+    let mut ast = parse_macro_input!(input as syn::Item);
     let args = parse_macro_input!(attr as PyClassArgs);
-    let expanded = build_py_class(&mut ast, &args).unwrap_or_else(|e| e.to_compile_error());
+    let expanded = match ast {
+        syn::Item::Struct(ref mut class) => {
+            if cfg!(feature = "stable") {
+                let gc: syn::Expr = parse_quote! { pyo3::type_flags::GC };
+                if args.flags.contains(&gc) {
+                    return syn::Error::new_spanned(
+                        &class.ident,
+                        "#[pyclass(gc)] requires #[pyproto] and is not available with the `stable` feature",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+            }
+            build_py_class(class, &args)
+        }
+        syn::Item::Enum(ref mut enum_) => build_py_enum(enum_, &args),
+        _ => Err(syn::Error::new_spanned(
+            &ast,
+            "#[pyclass] can only be used with C-style structs and fieldless enums",
+        )),
+    }
+    .unwrap_or_else(|e| e.to_compile_error());
 
     quote!(
         #ast
@@ -89,3 +118,43 @@ pub fn pyfunction(attr: TokenStream, input: TokenStream) -> TokenStream {
     )
     .into()
 }
+
+/// Implements `pyo3::columnar::Columnar` for a struct with named fields.
+#[proc_macro_derive(Columnar)]
+pub fn columnar(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+    build_columnar(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Implements `FromPyObject` for a struct, reading named fields from attributes or with
+/// `#[pyo3(item)]` from items of the object, or for an enum, trying its variants in order.
+#[proc_macro_derive(FromPyObject, attributes(pyo3))]
+pub fn from_pyobject(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+    build_from_pyobject(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Implements `pyo3::int_enum::IntEnum` and the conversions to and from Python ints for a
+/// fieldless enum with an integer `#[repr]`.
+#[proc_macro_derive(IntEnum)]
+pub fn int_enum(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+    build_int_enum(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Adds `__getstate__`, `__setstate__` and `__reduce__` to a `#[pyclass]` implementing serde's
+/// `Serialize` and `Deserialize`, so it can be pickled. Requires the `pickle-serde` feature of
+/// pyo3.
+#[proc_macro_derive(Pickle)]
+pub fn pickle(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+    build_pickle(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
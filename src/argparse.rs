@@ -0,0 +1,441 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//
+// based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
+
+//! Matching of the arguments of a call to the parameters of a wrapped function.
+//!
+//! The wrappers generated by `#[pyfunction]`, `#[pymethods]` and `py_class!` describe their
+//! parameters with a static `FunctionParams` table and call `parse_fn_args` or `parse_fast_args`,
+//! which fill one slot per parameter. These functions aren't generic, so their code is shared by
+//! all wrappers, which only extract each slot to the type of its parameter.
+
+use crate::callback::restore_err;
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::TypeError;
+use crate::instance::PyNativeType;
+use crate::types::{PyAny, PyDict, PyTuple};
+use crate::{ffi, GILPool, IntoPy, ObjectProtocol, PyObject, Python};
+use crate::{AsPyPointer, AsPyRef};
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::os::raw::c_char;
+use std::ptr;
+
+/// Description of a python parameter; used for `parse_args()`.
+#[derive(Debug)]
+pub struct ParamDescription {
+    /// The name of the parameter.
+    pub name: &'static str,
+    /// Whether the parameter is optional.
+    pub is_optional: bool,
+    /// Whether the parameter is optional.
+    pub kw_only: bool,
+}
+
+macro_rules! raise_arg_error {
+    ($fname: expr, $s: expr $(,$arg:expr)*) => (return Err(arg_error(
+        $fname, format_args!($s $(,$arg)*)
+    )))
+}
+
+/// Creates the `TypeError` for arguments not matching the parameters of `fname`, out of line
+/// so that the message is only formatted when parsing fails.
+#[cold]
+#[inline(never)]
+fn arg_error(fname: Option<&str>, msg: fmt::Arguments) -> PyErr {
+    TypeError::py_err(format!("{} {}", fname.unwrap_or("function"), msg))
+}
+
+/// The parameters of a function, with their names interned as Python strings on the first call.
+///
+/// Keyword arguments are usually passed with interned strings, e.g. by calls in Python code, so
+/// they can be matched to the parameters by comparing pointers, and looking them up in a dict
+/// doesn't need to create strings.
+pub struct FunctionParams {
+    params: &'static [ParamDescription],
+    // Only accessed with the GIL held
+    names: UnsafeCell<Option<Box<[PyObject]>>>,
+}
+
+unsafe impl Sync for FunctionParams {}
+
+impl FunctionParams {
+    pub const fn new(params: &'static [ParamDescription]) -> Self {
+        FunctionParams {
+            params,
+            names: UnsafeCell::new(None),
+        }
+    }
+
+    /// The interned names of the parameters, in the same order.
+    fn names(&self, py: Python) -> &[PyObject] {
+        unsafe {
+            if let Some(names) = &*self.names.get() {
+                return names;
+            }
+            let names = self
+                .params
+                .iter()
+                .map(|p| {
+                    let mut name = ffi::PyUnicode_FromStringAndSize(
+                        p.name.as_ptr() as *const c_char,
+                        p.name.len() as ffi::Py_ssize_t,
+                    );
+                    ffi::PyUnicode_InternInPlace(&mut name);
+                    PyObject::from_owned_ptr_or_panic(py, name)
+                })
+                .collect();
+            // Interning doesn't run Python code, so nothing borrows the names yet
+            *self.names.get() = Some(names);
+            (*self.names.get()).as_ref().unwrap()
+        }
+    }
+}
+
+/// Parse argument list
+///
+/// * fname:  Name of the current function
+/// * params: Declared parameters of the function
+/// * args:   Positional arguments
+/// * kwargs: Keyword arguments
+/// * output: Output array that receives the arguments.
+///           Must have same length as `params` and must be initialized to `None`.
+pub fn parse_fn_args<'p>(
+    fname: Option<&str>,
+    params: &FunctionParams,
+    args: &'p PyTuple,
+    kwargs: Option<&'p PyDict>,
+    accept_args: bool,
+    accept_kwargs: bool,
+    output: &mut [Option<&'p PyAny>],
+) -> PyResult<(&'p PyTuple, Option<&'p PyDict>)> {
+    let nargs = args.len();
+    let mut used_args = 0;
+    macro_rules! raise_error {
+        ($($arg:tt)*) => (raise_arg_error!(fname, $($arg)*))
+    }
+    // Copy kwargs not to modify it
+    let kwargs = match kwargs {
+        Some(k) => Some(k.copy()?),
+        None => None,
+    };
+    let names = params.names(args.py());
+    // Iterate through the parameters and assign values to output:
+    for (i, ((p, name), out)) in params.params.iter().zip(names).zip(output).enumerate() {
+        *out = match kwargs.and_then(|d| d.get_item(name)) {
+            Some(kwarg) => {
+                if i < nargs {
+                    raise_error!("got multiple values for argument: {}", p.name)
+                }
+                kwargs.as_ref().unwrap().del_item(name).unwrap();
+                Some(kwarg)
+            }
+            None => {
+                if p.kw_only {
+                    if !p.is_optional {
+                        raise_error!("missing required keyword-only argument: {}", p.name)
+                    }
+                    None
+                } else if i < nargs {
+                    used_args += 1;
+                    Some(args.get_item(i))
+                } else {
+                    if !p.is_optional {
+                        raise_error!("missing required positional argument: {}", p.name)
+                    }
+                    None
+                }
+            }
+        }
+    }
+    let is_kwargs_empty = kwargs.as_ref().map_or(true, |dict| dict.is_empty());
+    // Raise an error when we get an unknown key
+    if !accept_kwargs && !is_kwargs_empty {
+        let (key, _) = kwargs.unwrap().iter().next().unwrap();
+        raise_error!("got an unexpected keyword argument: {}", key)
+    }
+    // Raise an error when we get too many positional args
+    if !accept_args && used_args < nargs {
+        raise_error!(
+            "takes at most {} positional argument{} ({} given)",
+            used_args,
+            if used_args == 1 { "" } else { "s" },
+            nargs
+        )
+    }
+    // Adjust the remaining args
+    let args = if accept_args {
+        let py = args.py();
+        let slice = args.slice(used_args as isize, nargs as isize).into_py(py);
+        py.checked_cast_as(slice).unwrap()
+    } else {
+        args
+    };
+    let kwargs = if accept_kwargs && is_kwargs_empty {
+        None
+    } else {
+        kwargs
+    };
+    Ok((args, kwargs))
+}
+
+/// Parse the arguments of a `METH_FASTCALL | METH_KEYWORDS` call like `parse_fn_args`, for
+/// functions without `*args` and `**kwargs`
+///
+/// * args:    `nargs` positional arguments, followed by the values of the keyword arguments
+/// * kwnames: The names of the keyword arguments, or null
+/// * accept_args: Whether surplus positional arguments are ignored
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn parse_fast_args<'p>(
+    py: Python<'p>,
+    fname: Option<&str>,
+    params: &FunctionParams,
+    args: *const *mut ffi::PyObject,
+    nargs: ffi::Py_ssize_t,
+    kwnames: *mut ffi::PyObject,
+    accept_args: bool,
+    output: &mut [Option<&'p PyAny>],
+) -> PyResult<()> {
+    macro_rules! raise_error {
+        ($($arg:tt)*) => (raise_arg_error!(fname, $($arg)*))
+    }
+    let nargs = nargs as usize;
+    let kwnames: &[PyObject] = match py.from_borrowed_ptr_or_opt::<PyTuple>(kwnames) {
+        Some(kwnames) => kwnames.as_slice(),
+        None => &[],
+    };
+    let arg = |i: usize| py.from_borrowed_ptr::<PyAny>(*args.add(i));
+    let same_name = |a: &PyObject, b: &PyObject| {
+        a.as_ptr() == b.as_ptr() || ffi::PyUnicode_Compare(a.as_ptr(), b.as_ptr()) == 0
+    };
+    let kwarg = |name: &PyObject| {
+        // Compare all pointers first, as the keywords are usually interned
+        let i = kwnames
+            .iter()
+            .position(|k| k.as_ptr() == name.as_ptr())
+            .or_else(|| kwnames.iter().position(|k| same_name(k, name)))?;
+        Some(arg(nargs + i))
+    };
+
+    let names = params.names(py);
+    let mut used_args = 0;
+    let mut used_kwargs = 0;
+    for (i, ((p, name), out)) in params.params.iter().zip(names).zip(output).enumerate() {
+        *out = match kwarg(name) {
+            Some(kwarg) => {
+                if i < nargs {
+                    raise_error!("got multiple values for argument: {}", p.name)
+                }
+                used_kwargs += 1;
+                Some(kwarg)
+            }
+            None => {
+                if p.kw_only {
+                    if !p.is_optional {
+                        raise_error!("missing required keyword-only argument: {}", p.name)
+                    }
+                    None
+                } else if i < nargs {
+                    used_args += 1;
+                    Some(arg(i))
+                } else {
+                    if !p.is_optional {
+                        raise_error!("missing required positional argument: {}", p.name)
+                    }
+                    None
+                }
+            }
+        }
+    }
+    if used_kwargs < kwnames.len() {
+        let unknown = kwnames
+            .iter()
+            .find(|k| !names.iter().any(|name| same_name(k, name)))
+            .unwrap();
+        raise_error!("got an unexpected keyword argument: {}", unknown.as_ref(py))
+    }
+    if !accept_args && used_args < nargs {
+        raise_error!(
+            "takes at most {} positional argument{} ({} given)",
+            used_args,
+            if used_args == 1 { "" } else { "s" },
+            nargs
+        )
+    }
+    Ok(())
+}
+
+/// Calls the `METH_FASTCALL | METH_KEYWORDS` function `meth` with the tuple and dict of a
+/// `METH_VARARGS | METH_KEYWORDS` call, for Pythons without `METH_FASTCALL`.
+pub unsafe fn call_fast(
+    meth: ffi::_PyCFunctionFastWithKeywords,
+    slf: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+    kwargs: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = GILPool::new(py);
+    let args: &PyTuple = py.from_borrowed_ptr(args);
+    let kwargs: Option<&PyDict> = py.from_borrowed_ptr_or_opt(kwargs);
+    let mut stack: Vec<*mut ffi::PyObject> = args.iter().map(AsPyPointer::as_ptr).collect();
+    let kwnames = match kwargs {
+        Some(kwargs) if !kwargs.is_empty() => {
+            let (names, values): (Vec<&PyAny>, Vec<&PyAny>) = kwargs.iter().unzip();
+            stack.extend(values.into_iter().map(AsPyPointer::as_ptr));
+            PyTuple::new(py, names).as_ptr()
+        }
+        _ => ptr::null_mut(),
+    };
+    meth(slf, stack.as_ptr(), args.len() as ffi::Py_ssize_t, kwnames)
+}
+
+/// The part of a `METH_FASTCALL | METH_KEYWORDS` wrapper generated with the `compact-codegen`
+/// feature which is specific to the function: it extracts the arguments from the slots filled by
+/// `parse_fast_args`, calls the function and converts its result.
+pub type FastBody =
+    for<'p> unsafe fn(Python<'p>, *mut ffi::PyObject, &[Option<&'p PyAny>]) -> *mut ffi::PyObject;
+
+/// Like `FastBody`, for `METH_VARARGS | METH_KEYWORDS` wrappers, which also get the remaining
+/// `*args` and `**kwargs`.
+pub type VarargsBody = for<'p> unsafe fn(
+    Python<'p>,
+    *mut ffi::PyObject,
+    &[Option<&'p PyAny>],
+    &'p PyTuple,
+    Option<&'p PyDict>,
+) -> *mut ffi::PyObject;
+
+/// A `METH_FASTCALL | METH_KEYWORDS` wrapper generated with the `compact-codegen` feature, which
+/// parses the arguments and runs `body`.
+///
+/// `S` is the array of slots, e.g. `[Option<&PyAny>; 2]`, so this is only compiled once for all
+/// functions with the same number of parameters.
+#[allow(clippy::too_many_arguments)]
+#[inline(never)]
+pub unsafe fn wrap_fast<'p, S>(
+    location: &str,
+    params: &FunctionParams,
+    accept_args: bool,
+    body: FastBody,
+    slf: *mut ffi::PyObject,
+    args: *const *mut ffi::PyObject,
+    nargs: ffi::Py_ssize_t,
+    kwnames: *mut ffi::PyObject,
+) -> *mut ffi::PyObject
+where
+    S: Default + AsMut<[Option<&'p PyAny>]>,
+{
+    let py = Python::assume_gil_acquired();
+    let _pool = GILPool::new(py);
+    let mut output = S::default();
+    let output = output.as_mut();
+    match parse_fast_args(
+        py,
+        Some(location),
+        params,
+        args,
+        nargs,
+        kwnames,
+        accept_args,
+        output,
+    ) {
+        Ok(()) => body(py, slf, output),
+        Err(e) => {
+            restore_err(py, e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Like `wrap_fast`, for `METH_VARARGS | METH_KEYWORDS` wrappers.
+#[allow(clippy::too_many_arguments)]
+#[inline(never)]
+pub unsafe fn wrap_varargs<'p, S>(
+    location: &str,
+    params: &FunctionParams,
+    accept_args: bool,
+    accept_kwargs: bool,
+    body: VarargsBody,
+    slf: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+    kwargs: *mut ffi::PyObject,
+) -> *mut ffi::PyObject
+where
+    S: Default + AsMut<[Option<&'p PyAny>]>,
+{
+    let py = Python::assume_gil_acquired();
+    let _pool = GILPool::new(py);
+    let args: &PyTuple = py.from_borrowed_ptr(args);
+    let kwargs: Option<&PyDict> = py.from_borrowed_ptr_or_opt(kwargs);
+    let mut output = S::default();
+    let output = output.as_mut();
+    match parse_fn_args(
+        Some(location),
+        params,
+        args,
+        kwargs,
+        accept_args,
+        accept_kwargs,
+        output,
+    ) {
+        Ok((args, kwargs)) => body(py, slf, output, args, kwargs),
+        Err(e) => {
+            restore_err(py, e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// The argument in `slot`, unless it is missing or `None`, in which case the default value of
+/// the parameter is used.
+#[inline]
+pub fn provided(slot: Option<&PyAny>) -> Option<&PyAny> {
+    slot.filter(|obj| !obj.is_none())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_fn_args, FunctionParams, ParamDescription};
+    use crate::exceptions::TypeError;
+    use crate::types::{IntoPyDict, PyTuple};
+    use crate::{ObjectProtocol, Python};
+
+    static PARAMS: FunctionParams = FunctionParams::new(&[
+        ParamDescription {
+            name: "a",
+            is_optional: false,
+            kw_only: false,
+        },
+        ParamDescription {
+            name: "b",
+            is_optional: true,
+            kw_only: false,
+        },
+        ParamDescription {
+            name: "c",
+            is_optional: true,
+            kw_only: true,
+        },
+    ]);
+
+    #[test]
+    fn test_parse_fn_args() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let args = PyTuple::new(py, &[1]);
+        let kwargs = [("c", 3)].into_py_dict(py);
+
+        let mut output = [None; 3];
+        parse_fn_args(None, &PARAMS, args, Some(kwargs), false, false, &mut output).unwrap();
+        let values: Vec<Option<i32>> = output
+            .iter()
+            .map(|slot| slot.map(|obj| obj.extract().unwrap()))
+            .collect();
+        assert_eq!(values, [Some(1), None, Some(3)]);
+
+        let mut output = [None; 3];
+        let kwargs = [("d", 4)].into_py_dict(py);
+        let err = parse_fn_args(None, &PARAMS, args, Some(kwargs), false, false, &mut output);
+        assert!(err.unwrap_err().is_instance::<TypeError>(py));
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Awaiting Rust futures from Python code running in an `asyncio` event loop.
+//!
+//! [`into_awaitable`] wraps a Rust `Future` in an `asyncio.Future` of the running event loop. The
+//! Rust future is polled on the event loop's thread, each time its waker is woken, by scheduling
+//! the poll with `loop.call_soon_threadsafe`. It doesn't need a Rust executor, so futures
+//! completed by other threads or by callbacks can be awaited without blocking a worker thread:
+//!
+//! ```
+//! use pyo3::asyncio::into_awaitable;
+//! use pyo3::prelude::*;
+//! use pyo3::types::PyAny;
+//!
+//! #[pyfunction]
+//! fn answer(py: Python) -> PyResult<&PyAny> {
+//!     into_awaitable(py, async { Ok(42) })
+//! }
+//! ```
+//!
+//! Python code awaits the result with `await answer()`. Cancelling the `asyncio.Future` drops the
+//! Rust future.
+//!
+//! [`into_awaitable`]: fn.into_awaitable.html
+
+use crate::class::methods::{PyMethodDefType, PyMethodsProtocol};
+use crate::err::PyResult;
+use crate::instance::AsPyRef;
+use crate::objectprotocol::ObjectProtocol;
+use crate::pyclass::{PyClass, PyClassAlloc, PyClassShell};
+use crate::pyclass_slots::PyClassDummySlot;
+use crate::type_object::{LazyStaticType, PyTypeInfo};
+use crate::types::PyAny;
+use crate::{ffi, FromPyPointer, GILPool, IntoPy, Py, PyObject, Python};
+use parking_lot::Mutex;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+type BoxFuture = Pin<Box<dyn Future<Output = PyResult<PyObject>> + Send>>;
+
+/// Returns an `asyncio.Future` of the running event loop, which completes with the output of
+/// `future`, or raises its error.
+///
+/// Raises a `RuntimeError` if no event loop is running in the current thread.
+pub fn into_awaitable<F, T>(py: Python, future: F) -> PyResult<&PyAny>
+where
+    F: Future<Output = PyResult<T>> + Send + 'static,
+    T: IntoPy<PyObject>,
+{
+    let event_loop = running_loop(py)?;
+    let py_future = event_loop.call_method0("create_future")?;
+    let future = async move {
+        let value = future.await?;
+        // The future is only polled by `Task::poll`, which holds the GIL
+        Ok(value.into_py(unsafe { Python::assume_gil_acquired() }))
+    };
+    let task = Arc::new(Task {
+        future: Mutex::new(Some(Box::pin(future))),
+        event_loop: event_loop.into(),
+        py_future: py_future.into(),
+    });
+    let poller = Py::new(py, TaskPoller { task: task.clone() })?;
+    // Cancelling the asyncio future calls the poller, which drops the Rust future
+    py_future.call_method1("add_done_callback", (&poller,))?;
+    task.schedule(py)?;
+    Ok(py_future)
+}
+
+#[cfg(Py_3_7)]
+fn running_loop(py: Python) -> PyResult<&PyAny> {
+    py.import("asyncio")?.call0("get_running_loop")
+}
+
+#[cfg(not(Py_3_7))]
+fn running_loop(py: Python) -> PyResult<&PyAny> {
+    py.import("asyncio")?.call0("get_event_loop")
+}
+
+/// A Rust future and the `asyncio.Future` receiving its output.
+struct Task {
+    future: Mutex<Option<BoxFuture>>,
+    event_loop: PyObject,
+    py_future: PyObject,
+}
+
+impl Task {
+    /// Schedules polling the future on the event loop.
+    fn schedule(self: &Arc<Self>, py: Python) -> PyResult<()> {
+        let poller = Py::new(py, TaskPoller { task: self.clone() })?;
+        self.event_loop
+            .call_method1(py, "call_soon_threadsafe", (poller,))?;
+        Ok(())
+    }
+
+    fn poll(self: &Arc<Self>, py: Python) -> PyResult<()> {
+        let py_future = self.py_future.as_ref(py);
+        let mut slot = self.future.lock();
+        if py_future.call_method0("done")?.is_true()? {
+            // Cancelled, or already completed by an earlier poll
+            *slot = None;
+            return Ok(());
+        }
+        let future = match slot.as_mut() {
+            Some(future) => future,
+            None => return Ok(()),
+        };
+        let waker = Waker::from(self.clone());
+        let result = match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Ok(()),
+        };
+        *slot = None;
+        drop(slot);
+        match result {
+            Ok(value) => py_future.call_method1("set_result", (value,))?,
+            Err(err) => py_future.call_method1("set_exception", (err,))?,
+        };
+        Ok(())
+    }
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        if !crate::gil::is_interpreter_alive() {
+            return;
+        }
+        let gil = Python::acquire_gil();
+        // Fails if the event loop was closed, then nothing can await the result anymore
+        let _ = self.schedule(gil.python());
+    }
+}
+
+/// A callable polling a task, passed to `call_soon_threadsafe` and `add_done_callback`.
+struct TaskPoller {
+    task: Arc<Task>,
+}
+
+unsafe extern "C" fn tp_call(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+    _kwargs: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    crate::callback::handle_callback(
+        "RustTaskPoller.__call__",
+        crate::callback::PyObjectCallbackConverter,
+        |py| {
+            let _pool = GILPool::new(py);
+            let shell: &PyClassShell<TaskPoller> = FromPyPointer::from_borrowed_ptr(py, slf);
+            let task = shell.try_borrow()?.task.clone();
+            task.poll(py)?;
+            Ok(py.None())
+        },
+    )
+}
+
+unsafe impl PyTypeInfo for TaskPoller {
+    type Type = TaskPoller;
+    type BaseType = PyAny;
+    type ConcreteLayout = PyClassShell<Self>;
+    type Initializer = crate::pyclass_init::PyClassInitializer<Self>;
+    type AsRefTarget = crate::pyclass::PyClassShell<Self>;
+
+    const NAME: &'static str = "RustTaskPoller";
+    const MODULE: Option<&'static str> = None;
+    const DESCRIPTION: &'static str = "Polls a Rust future awaited from asyncio\0";
+    const FLAGS: usize = 0;
+
+    #[inline]
+    fn type_object() -> &'static ffi::PyTypeObject {
+        static TYPE_OBJECT: LazyStaticType = LazyStaticType::new();
+        TYPE_OBJECT.get_or_init::<Self>()
+    }
+}
+
+impl PyClass for TaskPoller {
+    type Dict = PyClassDummySlot;
+    type WeakRef = PyClassDummySlot;
+
+    fn customize_type_object(type_object: &mut ffi::PyTypeObject) {
+        type_object.tp_call = Some(tp_call);
+    }
+}
+
+impl PyClassAlloc for TaskPoller {}
+
+impl PyMethodsProtocol for TaskPoller {
+    fn py_methods() -> Vec<&'static PyMethodDefType> {
+        Vec::new()
+    }
+}
@@ -598,6 +598,10 @@ fn buffer_readonly_error() -> PyResult<()> {
 
 impl Drop for PyBuffer {
     fn drop(&mut self) {
+        if !crate::gil::is_interpreter_alive() {
+            // The exporting object is gone with the interpreter
+            return;
+        }
         let _gil_guard = Python::acquire_gil();
         unsafe { ffi::PyBuffer_Release(&mut *self.0) }
     }
@@ -0,0 +1,134 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Information about the build of pyo3 and the Python interpreter it was compiled against.
+//!
+//! Every `#[pymodule]` has a `__pyo3_build_info__()` function returning this as a dict, which
+//! helps to diagnose an extension being imported by a different interpreter than the one it was
+//! built for:
+//!
+//! ```python
+//! >>> import my_extension
+//! >>> my_extension.__pyo3_build_info__()
+//! {'pyo3_version': '0.9.0', 'python_version': '3.8', 'python_implementation': 'CPython',
+//!  'abiflags': '', 'limited_api': False, 'debug': False, 'features': ['extension-module']}
+//! ```
+
+use crate::callback::PyObjectCallbackConverter;
+use crate::err::PyResult;
+use crate::ffi;
+use crate::types::PyDict;
+use crate::{PyObject, Python, ToPyObject};
+
+/// The version of pyo3.
+pub const PYO3_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The `major.minor` version of the Python interpreter pyo3 was compiled against.
+pub const PYTHON_VERSION: &str = env!("PYO3_PYTHON_VERSION");
+
+/// The Python implementation pyo3 was compiled against, `CPython` or `PyPy`.
+pub const PYTHON_IMPLEMENTATION: &str = env!("PYO3_PYTHON_IMPLEMENTATION");
+
+/// The ABI flags of the Python interpreter pyo3 was compiled against, e.g. `m` or `d`.
+pub const PYTHON_ABIFLAGS: &str = env!("PYO3_PYTHON_ABIFLAGS");
+
+/// The cargo features pyo3 was compiled with, including the enabled optional dependencies, in
+/// alphabetical order.
+pub fn features() -> Vec<&'static str> {
+    // Set by the build script from the `CARGO_FEATURE_*` variables
+    env!("PYO3_FEATURES")
+        .split(',')
+        .filter(|feature| !feature.is_empty())
+        .collect()
+}
+
+/// Returns the build information as a Python dict.
+pub fn build_info(py: Python) -> PyResult<&PyDict> {
+    let info = PyDict::new(py);
+    info.set_item("pyo3_version", PYO3_VERSION)?;
+    info.set_item("python_version", PYTHON_VERSION)?;
+    info.set_item("python_implementation", PYTHON_IMPLEMENTATION)?;
+    info.set_item("abiflags", PYTHON_ABIFLAGS)?;
+    info.set_item("limited_api", cfg!(Py_LIMITED_API))?;
+    info.set_item("debug", cfg!(py_sys_config = "Py_DEBUG"))?;
+    info.set_item("features", features())?;
+    Ok(info)
+}
+
+crate::trampoline! {
+    fn py_build_info(_slf: *mut ffi::PyObject, _args: *mut ffi::PyObject) -> *mut ffi::PyObject;
+    converter = PyObjectCallbackConverter;
+    |py| build_info(py).map(|info| info.to_object(py))
+}
+
+/// Creates the Python function `__pyo3_build_info__()`, which returns `build_info`.
+pub fn python_function(py: Python) -> PyObject {
+    crate::derive_utils::builtin_function(
+        py,
+        "__pyo3_build_info__",
+        py_build_info,
+        ffi::METH_NOARGS,
+        "Returns the versions, ABI flags and features pyo3 was built with.\0",
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{build_info, features, python_function};
+    use crate::types::IntoPyDict;
+    use crate::types::PyDict;
+    use crate::{ObjectProtocol, Python};
+
+    #[test]
+    fn test_build_info() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let info = build_info(py).unwrap();
+
+        let pyo3_version: String = info.get_item("pyo3_version").unwrap().extract().unwrap();
+        assert_eq!(pyo3_version, env!("CARGO_PKG_VERSION"));
+
+        let sys = py.import("sys").unwrap();
+        let version_info = sys.get("version_info").unwrap();
+        let runtime_version: String = py
+            .eval(
+                "'{}.{}'.format(v.major, v.minor)",
+                None,
+                Some([("v", version_info)].into_py_dict(py)),
+            )
+            .unwrap()
+            .extract()
+            .unwrap();
+        let python_version: String = info.get_item("python_version").unwrap().extract().unwrap();
+        assert_eq!(python_version, runtime_version);
+
+        let info = python_function(py).call0(py).unwrap();
+        let info: &PyDict = info.cast_as(py).unwrap();
+        let features: Vec<String> = info.get_item("features").unwrap().extract().unwrap();
+        assert_eq!(features, super::features());
+    }
+
+    #[test]
+    fn test_features() {
+        let features = features();
+        assert_eq!(features.contains(&"stubs"), cfg!(feature = "stubs"));
+        assert_eq!(
+            features.contains(&"compact-codegen"),
+            cfg!(feature = "compact-codegen")
+        );
+        assert_eq!(features.contains(&"numpy"), cfg!(feature = "numpy"));
+        assert_eq!(
+            features.contains(&"debug-gil-checks"),
+            cfg!(feature = "debug-gil-checks")
+        );
+        assert_eq!(features.contains(&"log"), cfg!(feature = "log"));
+        assert_eq!(
+            features.contains(&"pickle-serde"),
+            cfg!(feature = "pickle-serde")
+        );
+        assert_eq!(features.contains(&"serde"), cfg!(feature = "serde"));
+        assert!(!features.contains(&"default"));
+        let mut sorted = features.clone();
+        sorted.sort();
+        assert_eq!(features, sorted);
+    }
+}
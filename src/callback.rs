@@ -2,13 +2,16 @@
 
 //! Utilities for a Python callable object that invokes a Rust function.
 
-use crate::err::PyResult;
-use crate::exceptions::OverflowError;
+use crate::conversion::{trace_conversion, ConversionKind};
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::{OverflowError, RuntimeError};
 use crate::ffi::{self, Py_hash_t};
+use crate::gil::GILGuard;
 use crate::IntoPyPointer;
-use crate::{IntoPy, PyObject, Python};
+use crate::{AsPyRef, IntoPy, PyObject, Python};
+use std::any::Any;
 use std::os::raw::c_int;
-use std::{isize, ptr};
+use std::{isize, panic, ptr};
 
 pub trait CallbackConverter<S> {
     type R;
@@ -25,8 +28,11 @@ where
 {
     type R = *mut ffi::PyObject;
 
+    #[inline]
     fn convert(val: S, py: Python) -> *mut ffi::PyObject {
-        val.into_py(py).into_ptr()
+        let obj = val.into_py(py);
+        trace_conversion::<S>(ConversionKind::ToPython, obj.as_ref(py));
+        obj.into_ptr()
     }
 
     #[inline]
@@ -136,6 +142,16 @@ where
     }
 }
 
+/// Converts `err` to a `PyErr` and restores it as the current Python exception.
+///
+/// Wrappers call this instead of `PyErr::restore`, so that the code creating the exception stays
+/// out of line and the success path of each wrapper stays small.
+#[cold]
+#[inline(never)]
+pub fn restore_err<E: Into<PyErr>>(py: Python, err: E) {
+    err.into().restore(py)
+}
+
 #[inline]
 pub unsafe fn cb_convert<C, T>(_c: C, py: Python, value: PyResult<T>) -> C::R
 where
@@ -144,8 +160,98 @@ where
     match value {
         Ok(val) => C::convert(val, py),
         Err(e) => {
-            e.restore(py);
+            restore_err(py, e);
             C::error_value()
         }
     }
 }
+
+/// Runs the body of a callback that is called from C code.
+///
+/// The GIL is acquired for the duration of `body`, so this can be used for callbacks that are
+/// invoked on threads which don't hold the GIL. A `PyErr` returned by `body` is restored as the
+/// current Python exception, and a panic is caught and raised as a `RuntimeError`, since unwinding
+/// into C code is undefined behavior. In both cases the error value of the converter is returned.
+///
+/// `location` is included in the message of a panic, usually the name of the callback.
+/// Use the `trampoline!` macro to define an `extern "C"` function around this.
+pub fn handle_callback<C, T, F>(location: &str, _c: C, body: F) -> C::R
+where
+    C: CallbackConverter<T>,
+    F: FnOnce(Python) -> PyResult<T>,
+{
+    let gil = GILGuard::acquire();
+    let py = gil.python();
+    let result = match panic::catch_unwind(panic::AssertUnwindSafe(|| body(py))) {
+        Ok(result) => result,
+        Err(payload) => Err(panic_to_pyerr(location, payload)),
+    };
+    match result {
+        Ok(val) => C::convert(val, py),
+        Err(e) => {
+            restore_err(py, e);
+            C::error_value()
+        }
+    }
+}
+
+fn panic_to_pyerr(location: &str, payload: Box<dyn Any + Send>) -> PyErr {
+    let msg = if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "Box<Any>".to_string()
+    };
+    RuntimeError::py_err(format!("Rust panic in {}: {}", location, msg))
+}
+
+/// Defines an `extern "C"` function that can be passed as a callback to C code.
+///
+/// The body is a closure taking a `Python` token and returning a `PyResult`, which is
+/// converted to the C return value by a `CallbackConverter`. See `handle_callback` for
+/// how the GIL, errors and panics are handled.
+///
+/// ```
+/// use pyo3::callback::UnitCallbackConverter;
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyAny;
+/// use pyo3::{trampoline, AsPyPointer};
+/// use std::os::raw::{c_int, c_void};
+///
+/// trampoline! {
+///     /// Called by the C library for every event.
+///     pub fn on_event(handler: *mut c_void, code: c_int) -> c_int;
+///     converter = UnitCallbackConverter;
+///     |py| {
+///         let handler: &PyAny = unsafe { py.from_borrowed_ptr(handler as *mut _) };
+///         handler.call1((code,))?;
+///         Ok(())
+///     }
+/// }
+///
+/// # fn main() {
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let handler = py.eval("lambda code: None", None, None).unwrap();
+/// assert_eq!(on_event(handler.as_ptr() as *mut c_void, 42), 0);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trampoline {
+    (
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($($arg:ident: $arg_ty:ty),* $(,)?) -> $ret:ty;
+        converter = $conv:expr;
+        |$py:ident| $body:expr $(;)?
+    ) => {
+        $(#[$attr])*
+        $vis extern "C" fn $name($($arg: $arg_ty),*) -> $ret {
+            $crate::callback::handle_callback(
+                concat!(module_path!(), "::", stringify!($name)),
+                $conv,
+                move |$py: $crate::Python| $body,
+            )
+        }
+    };
+}
@@ -8,17 +8,26 @@
 //! Parts of the documentation are copied from the respective methods from the
 //! [typeobj docs](https://docs.python.org/3/c-api/typeobj.html)
 
-use crate::callback::{BoolCallbackConverter, HashConverter, PyObjectCallbackConverter};
+#[cfg(not(feature = "stable"))]
+use crate::callback::{BoolCallbackConverter, PyObjectCallbackConverter};
+use crate::callback::{CallbackConverter, HashConverter};
+#[cfg(not(feature = "stable"))]
 use crate::class::methods::PyMethodDef;
 use crate::err::{PyErr, PyResult};
 use crate::ffi;
+#[cfg(not(feature = "stable"))]
 use crate::objectprotocol::ObjectProtocol;
-use crate::type_object::PyTypeInfo;
+use crate::pyclass::{PyClass, PyClassShell};
 use crate::types::PyAny;
+#[cfg(not(feature = "stable"))]
 use crate::FromPyObject;
 use crate::IntoPyPointer;
 use crate::Python;
 use crate::{exceptions, IntoPy, PyObject};
+use crate::{AsPyPointer, FromPyPointer};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::os::raw::c_int;
 use std::ptr;
 
@@ -34,8 +43,9 @@ pub enum CompareOp {
 }
 
 /// Basic python class customization
+#[cfg(not(feature = "stable"))]
 #[allow(unused_variables)]
-pub trait PyObjectProtocol<'p>: PyTypeInfo {
+pub trait PyObjectProtocol<'p>: PyClass {
     fn __getattr__(&'p self, name: Self::Name) -> Self::Result
     where
         Self: PyObjectGetAttrProtocol<'p>,
@@ -107,49 +117,60 @@ pub trait PyObjectProtocol<'p>: PyTypeInfo {
     }
 }
 
+#[cfg(not(feature = "stable"))]
 pub trait PyObjectGetAttrProtocol<'p>: PyObjectProtocol<'p> {
     type Name: FromPyObject<'p>;
     type Success: IntoPy<PyObject>;
     type Result: Into<PyResult<Self::Success>>;
 }
+#[cfg(not(feature = "stable"))]
 pub trait PyObjectSetAttrProtocol<'p>: PyObjectProtocol<'p> {
     type Name: FromPyObject<'p>;
     type Value: FromPyObject<'p>;
     type Result: Into<PyResult<()>>;
 }
+#[cfg(not(feature = "stable"))]
 pub trait PyObjectDelAttrProtocol<'p>: PyObjectProtocol<'p> {
     type Name: FromPyObject<'p>;
     type Result: Into<PyResult<()>>;
 }
+#[cfg(not(feature = "stable"))]
 pub trait PyObjectStrProtocol<'p>: PyObjectProtocol<'p> {
     type Success: IntoPy<PyObject>;
     type Result: Into<PyResult<Self::Success>>;
 }
+#[cfg(not(feature = "stable"))]
 pub trait PyObjectReprProtocol<'p>: PyObjectProtocol<'p> {
     type Success: IntoPy<PyObject>;
     type Result: Into<PyResult<Self::Success>>;
 }
+#[cfg(not(feature = "stable"))]
 pub trait PyObjectFormatProtocol<'p>: PyObjectProtocol<'p> {
     type Format: FromPyObject<'p>;
     type Success: IntoPy<PyObject>;
     type Result: Into<PyResult<Self::Success>>;
 }
+#[cfg(not(feature = "stable"))]
 pub trait PyObjectHashProtocol<'p>: PyObjectProtocol<'p> {
     type Result: Into<PyResult<isize>>;
 }
+#[cfg(not(feature = "stable"))]
 pub trait PyObjectBoolProtocol<'p>: PyObjectProtocol<'p> {
     type Result: Into<PyResult<bool>>;
 }
+#[cfg(not(feature = "stable"))]
 pub trait PyObjectBytesProtocol<'p>: PyObjectProtocol<'p> {
     type Success: IntoPy<PyObject>;
     type Result: Into<PyResult<Self::Success>>;
 }
+#[cfg(not(feature = "stable"))]
 pub trait PyObjectRichcmpProtocol<'p>: PyObjectProtocol<'p> {
     type Other: FromPyObject<'p>;
     type Success: IntoPy<PyObject>;
     type Result: Into<PyResult<Self::Success>>;
 }
 
+#[cfg(not(feature = "stable"))]
 #[doc(hidden)]
 pub trait PyObjectProtocolImpl {
     fn methods() -> Vec<PyMethodDef>;
@@ -157,6 +178,7 @@ pub trait PyObjectProtocolImpl {
     fn nb_bool_fn() -> Option<ffi::inquiry>;
 }
 
+#[cfg(not(feature = "stable"))]
 impl<T> PyObjectProtocolImpl for T {
     default fn methods() -> Vec<PyMethodDef> {
         Vec::new()
@@ -167,6 +189,7 @@ impl<T> PyObjectProtocolImpl for T {
     }
 }
 
+#[cfg(not(feature = "stable"))]
 impl<'p, T> PyObjectProtocolImpl for T
 where
     T: PyObjectProtocol<'p>,
@@ -198,10 +221,12 @@ where
     }
 }
 
+#[cfg(not(feature = "stable"))]
 trait GetAttrProtocolImpl {
     fn tp_getattro() -> Option<ffi::binaryfunc>;
 }
 
+#[cfg(not(feature = "stable"))]
 impl<'p, T> GetAttrProtocolImpl for T
 where
     T: PyObjectProtocol<'p>,
@@ -211,6 +236,7 @@ where
     }
 }
 
+#[cfg(not(feature = "stable"))]
 impl<T> GetAttrProtocolImpl for T
 where
     T: for<'p> PyObjectGetAttrProtocol<'p>,
@@ -237,12 +263,16 @@ where
                 return existing;
             }
 
-            let slf = py.mut_from_borrowed_ptr::<T>(slf);
+            let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf);
             let arg = py.from_borrowed_ptr::<crate::types::PyAny>(arg);
 
-            let result = match arg.extract() {
-                Ok(arg) => slf.__getattr__(arg).into(),
-                Err(e) => Err(e),
+            let slf = slf.try_borrow();
+            let result = match slf {
+                Ok(ref slf) => match arg.extract() {
+                    Ok(arg) => slf.__getattr__(arg).into(),
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e.into()),
             };
             crate::callback::cb_convert(PyObjectCallbackConverter, py, result)
         }
@@ -254,6 +284,7 @@ where
 /// and may support deleting attributes (by implementing PyObjectDelAttrProtocol)
 /// and we need to generate a single extern c function that supports only setting, only deleting
 /// or both, and return None in case none of the two is supported.
+#[cfg(not(feature = "stable"))]
 mod tp_setattro_impl {
     use super::*;
 
@@ -346,9 +377,11 @@ mod tp_setattro_impl {
     }
 }
 
+#[cfg(not(feature = "stable"))]
 trait StrProtocolImpl {
     fn tp_str() -> Option<ffi::unaryfunc>;
 }
+#[cfg(not(feature = "stable"))]
 impl<'p, T> StrProtocolImpl for T
 where
     T: PyObjectProtocol<'p>,
@@ -357,6 +390,7 @@ where
         None
     }
 }
+#[cfg(not(feature = "stable"))]
 impl<T> StrProtocolImpl for T
 where
     T: for<'p> PyObjectStrProtocol<'p>,
@@ -371,9 +405,11 @@ where
     }
 }
 
+#[cfg(not(feature = "stable"))]
 trait ReprProtocolImpl {
     fn tp_repr() -> Option<ffi::unaryfunc>;
 }
+#[cfg(not(feature = "stable"))]
 impl<'p, T> ReprProtocolImpl for T
 where
     T: PyObjectProtocol<'p>,
@@ -382,6 +418,7 @@ where
         None
     }
 }
+#[cfg(not(feature = "stable"))]
 impl<T> ReprProtocolImpl for T
 where
     T: for<'p> PyObjectReprProtocol<'p>,
@@ -396,10 +433,12 @@ where
     }
 }
 
+#[cfg(not(feature = "stable"))]
 #[doc(hidden)]
 pub trait FormatProtocolImpl {
     fn __format__() -> Option<PyMethodDef>;
 }
+#[cfg(not(feature = "stable"))]
 impl<'p, T> FormatProtocolImpl for T
 where
     T: PyObjectProtocol<'p>,
@@ -409,10 +448,12 @@ where
     }
 }
 
+#[cfg(not(feature = "stable"))]
 #[doc(hidden)]
 pub trait BytesProtocolImpl {
     fn __bytes__() -> Option<PyMethodDef>;
 }
+#[cfg(not(feature = "stable"))]
 impl<'p, T> BytesProtocolImpl for T
 where
     T: PyObjectProtocol<'p>,
@@ -422,10 +463,12 @@ where
     }
 }
 
+#[cfg(not(feature = "stable"))]
 #[doc(hidden)]
 pub trait UnicodeProtocolImpl {
     fn __unicode__() -> Option<PyMethodDef>;
 }
+#[cfg(not(feature = "stable"))]
 impl<'p, T> UnicodeProtocolImpl for T
 where
     T: PyObjectProtocol<'p>,
@@ -435,9 +478,11 @@ where
     }
 }
 
+#[cfg(not(feature = "stable"))]
 trait HashProtocolImpl {
     fn tp_hash() -> Option<ffi::hashfunc>;
 }
+#[cfg(not(feature = "stable"))]
 impl<'p, T> HashProtocolImpl for T
 where
     T: PyObjectProtocol<'p>,
@@ -446,6 +491,7 @@ where
         None
     }
 }
+#[cfg(not(feature = "stable"))]
 impl<T> HashProtocolImpl for T
 where
     T: for<'p> PyObjectHashProtocol<'p>,
@@ -461,9 +507,84 @@ where
     }
 }
 
+/// `tp_hash` for `#[pyclass(hash)]`, based on the `Hash` implementation.
+#[doc(hidden)]
+pub fn hash_from_rust_hash<T>() -> ffi::hashfunc
+where
+    T: PyClass + Hash,
+{
+    unsafe extern "C" fn wrap<T>(slf: *mut ffi::PyObject) -> ffi::Py_hash_t
+    where
+        T: PyClass + Hash,
+    {
+        let py = Python::assume_gil_acquired();
+        let _pool = crate::GILPool::new(py);
+        let slf = match <&PyClassShell<T>>::from_borrowed_ptr(py, slf).try_borrow() {
+            Ok(slf) => slf,
+            Err(e) => {
+                PyErr::from(e).restore(py);
+                return -1;
+            }
+        };
+
+        let mut hasher = DefaultHasher::new();
+        slf.hash(&mut hasher);
+        HashConverter::convert(hasher.finish(), py)
+    }
+    wrap::<T>
+}
+
+/// `tp_repr` for `#[pyclass(debug)]`, based on the `Debug` implementation.
+#[doc(hidden)]
+pub fn repr_from_debug<T>() -> ffi::reprfunc
+where
+    T: PyClass + fmt::Debug,
+{
+    unsafe extern "C" fn wrap<T>(slf: *mut ffi::PyObject) -> *mut ffi::PyObject
+    where
+        T: PyClass + fmt::Debug,
+    {
+        let py = Python::assume_gil_acquired();
+        let _pool = crate::GILPool::new(py);
+        match <&PyClassShell<T>>::from_borrowed_ptr(py, slf).try_borrow() {
+            Ok(slf) => IntoPy::<PyObject>::into_py(format!("{:?}", slf), py).into_ptr(),
+            Err(e) => {
+                PyErr::from(e).restore(py);
+                ptr::null_mut()
+            }
+        }
+    }
+    wrap::<T>
+}
+
+/// `tp_str` for `#[pyclass(display)]`, based on the `Display` implementation.
+#[doc(hidden)]
+pub fn str_from_display<T>() -> ffi::reprfunc
+where
+    T: PyClass + fmt::Display,
+{
+    unsafe extern "C" fn wrap<T>(slf: *mut ffi::PyObject) -> *mut ffi::PyObject
+    where
+        T: PyClass + fmt::Display,
+    {
+        let py = Python::assume_gil_acquired();
+        let _pool = crate::GILPool::new(py);
+        match <&PyClassShell<T>>::from_borrowed_ptr(py, slf).try_borrow() {
+            Ok(slf) => IntoPy::<PyObject>::into_py(slf.to_string(), py).into_ptr(),
+            Err(e) => {
+                PyErr::from(e).restore(py);
+                ptr::null_mut()
+            }
+        }
+    }
+    wrap::<T>
+}
+
+#[cfg(not(feature = "stable"))]
 trait BoolProtocolImpl {
     fn nb_bool() -> Option<ffi::inquiry>;
 }
+#[cfg(not(feature = "stable"))]
 impl<'p, T> BoolProtocolImpl for T
 where
     T: PyObjectProtocol<'p>,
@@ -472,6 +593,7 @@ where
         None
     }
 }
+#[cfg(not(feature = "stable"))]
 impl<T> BoolProtocolImpl for T
 where
     T: for<'p> PyObjectBoolProtocol<'p>,
@@ -487,9 +609,11 @@ where
     }
 }
 
+#[cfg(not(feature = "stable"))]
 trait RichcmpProtocolImpl {
     fn tp_richcompare() -> Option<ffi::richcmpfunc>;
 }
+#[cfg(not(feature = "stable"))]
 impl<'p, T> RichcmpProtocolImpl for T
 where
     T: PyObjectProtocol<'p>,
@@ -498,6 +622,7 @@ where
         None
     }
 }
+#[cfg(not(feature = "stable"))]
 impl<T> RichcmpProtocolImpl for T
 where
     T: for<'p> PyObjectRichcmpProtocol<'p>,
@@ -513,13 +638,16 @@ where
         {
             let py = Python::assume_gil_acquired();
             let _pool = crate::GILPool::new(py);
-            let slf = py.from_borrowed_ptr::<T>(slf);
+            let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf).try_borrow();
             let arg = py.from_borrowed_ptr::<PyAny>(arg);
 
             let res = match extract_op(op) {
                 Ok(op) => match arg.extract() {
-                    Ok(arg) => slf.__richcmp__(arg, op).into(),
-                    Err(e) => Err(e),
+                    Ok(arg) => match slf {
+                        Ok(ref slf) => slf.__richcmp__(arg, op).into(),
+                        Err(e) => Err(e.into()),
+                    },
+                    Err(_) => return py.NotImplemented().into_ptr(),
                 },
                 Err(e) => Err(e),
             };
@@ -535,6 +663,101 @@ where
     }
 }
 
+/// `tp_richcompare` for `#[pyclass(eq)]`: `==` and `!=` use `PartialEq`, the ordering operators
+/// return `NotImplemented`.
+#[doc(hidden)]
+pub fn richcmp_from_partial_eq<T>() -> ffi::richcmpfunc
+where
+    T: PyClass + PartialEq,
+{
+    unsafe extern "C" fn wrap<T>(
+        slf: *mut ffi::PyObject,
+        arg: *mut ffi::PyObject,
+        op: c_int,
+    ) -> *mut ffi::PyObject
+    where
+        T: PyClass + PartialEq,
+    {
+        derived_richcmp::<T, _>(slf, arg, op, |slf, other, op| match op {
+            CompareOp::Eq => Some(slf == other),
+            CompareOp::Ne => Some(slf != other),
+            _ => None,
+        })
+    }
+    wrap::<T>
+}
+
+/// `tp_richcompare` for `#[pyclass(ord)]`: all six operators use `PartialOrd`.
+#[doc(hidden)]
+pub fn richcmp_from_partial_ord<T>() -> ffi::richcmpfunc
+where
+    T: PyClass + PartialOrd,
+{
+    unsafe extern "C" fn wrap<T>(
+        slf: *mut ffi::PyObject,
+        arg: *mut ffi::PyObject,
+        op: c_int,
+    ) -> *mut ffi::PyObject
+    where
+        T: PyClass + PartialOrd,
+    {
+        derived_richcmp::<T, _>(slf, arg, op, |slf, other, op| {
+            Some(match op {
+                CompareOp::Lt => slf < other,
+                CompareOp::Le => slf <= other,
+                CompareOp::Eq => slf == other,
+                CompareOp::Ne => slf != other,
+                CompareOp::Gt => slf > other,
+                CompareOp::Ge => slf >= other,
+            })
+        })
+    }
+    wrap::<T>
+}
+
+/// Shared body of the derived comparisons, `cmp` returns `None` for unsupported operators.
+///
+/// Comparing against an object that isn't a `T` returns `NotImplemented`, so Python can try the
+/// reflected operation.
+unsafe fn derived_richcmp<T, F>(
+    slf: *mut ffi::PyObject,
+    arg: *mut ffi::PyObject,
+    op: c_int,
+    cmp: F,
+) -> *mut ffi::PyObject
+where
+    T: PyClass,
+    F: FnOnce(&T, &T, CompareOp) -> Option<bool>,
+{
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf);
+    let arg = py.from_borrowed_ptr::<PyAny>(arg);
+
+    let op = match extract_op(op) {
+        Ok(op) => op,
+        Err(e) => {
+            e.restore(py);
+            return ptr::null_mut();
+        }
+    };
+    if !T::is_instance(arg) {
+        return py.NotImplemented().into_ptr();
+    }
+    let other = <&PyClassShell<T>>::from_borrowed_ptr(py, arg.as_ptr());
+    let result = match (slf.try_borrow(), other.try_borrow()) {
+        (Ok(slf), Ok(other)) => cmp(&slf, &other, op),
+        (Err(e), _) | (_, Err(e)) => {
+            PyErr::from(e).restore(py);
+            return ptr::null_mut();
+        }
+    };
+    match result {
+        Some(val) => IntoPy::<PyObject>::into_py(val, py).into_ptr(),
+        None => py.NotImplemented().into_ptr(),
+    }
+}
+
 fn extract_op(op: c_int) -> PyResult<CompareOp> {
     match op {
         ffi::Py_LT => Ok(CompareOp::Lt),
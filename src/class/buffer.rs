@@ -7,7 +7,8 @@
 use crate::callback::UnitCallbackConverter;
 use crate::err::PyResult;
 use crate::ffi;
-use crate::type_object::PyTypeInfo;
+use crate::pyclass::{PyClass, PyClassShell};
+use crate::FromPyPointer;
 use std::os::raw::c_int;
 
 /// Buffer protocol interface
@@ -15,7 +16,7 @@ use std::os::raw::c_int;
 /// For more information check [buffer protocol](https://docs.python.org/3/c-api/buffer.html)
 /// c-api
 #[allow(unused_variables)]
-pub trait PyBufferProtocol<'p>: PyTypeInfo {
+pub trait PyBufferProtocol<'p>: PyClass {
     fn bf_getbuffer(&'p self, view: *mut ffi::Py_buffer, flags: c_int) -> Self::Result
     where
         Self: PyBufferGetBufferProtocol<'p>,
@@ -94,9 +95,19 @@ where
         {
             let py = crate::Python::assume_gil_acquired();
             let _pool = crate::GILPool::new(py);
-            let slf = py.mut_from_borrowed_ptr::<T>(slf);
+            let obj = slf;
+            let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf);
 
-            let result = slf.bf_getbuffer(arg1, arg2).into();
+            let result = match slf.try_borrow() {
+                Ok(slf) => slf.bf_getbuffer(arg1, arg2).into(),
+                Err(e) => Err(e.into()),
+            };
+            // The view must own a reference to the exporter, so that memoryviews and
+            // `pickle.PickleBuffer`s keep the object and its memory alive
+            if result.is_ok() && !arg1.is_null() && (*arg1).obj.is_null() {
+                ffi::Py_INCREF(obj);
+                (*arg1).obj = obj;
+            }
             crate::callback::cb_convert(UnitCallbackConverter, py, result)
         }
         Some(wrap::<T>)
@@ -7,31 +7,34 @@
 
 use crate::callback::{PyObjectCallbackConverter, UnitCallbackConverter};
 use crate::class::methods::PyMethodDef;
-use crate::err::PyResult;
-use crate::type_object::PyTypeInfo;
-use crate::types::{PyAny, PyType};
-use crate::FromPyObject;
-use crate::{ffi, IntoPy, PyObject};
+use crate::err::{PyErr, PyResult};
+use crate::pyclass::{PyClass, PyClassShell};
+use crate::types::PyAny;
+use crate::{exceptions, ffi, IntoPy, PyObject, Python};
+use crate::{FromPyObject, FromPyPointer, ObjectProtocol};
 use std::os::raw::c_int;
 
 /// Descriptor interface
+///
+/// `instance` is `None` in `__get__` when the descriptor is accessed on the owner class
+/// instead of an instance.
 #[allow(unused_variables)]
-pub trait PyDescrProtocol<'p>: PyTypeInfo {
-    fn __get__(&'p self, instance: &'p PyAny, owner: Option<&'p PyType>) -> Self::Result
+pub trait PyDescrProtocol<'p>: PyClass {
+    fn __get__(&'p self, instance: Option<Self::Inst>, owner: Option<Self::Owner>) -> Self::Result
     where
         Self: PyDescrGetProtocol<'p>,
     {
         unimplemented!()
     }
 
-    fn __set__(&'p self, instance: &'p PyAny, value: &'p PyAny) -> Self::Result
+    fn __set__(&'p self, instance: Self::Inst, value: Self::Value) -> Self::Result
     where
         Self: PyDescrSetProtocol<'p>,
     {
         unimplemented!()
     }
 
-    fn __delete__(&'p self, instance: &'p PyAny) -> Self::Result
+    fn __delete__(&'p self, instance: Self::Inst) -> Self::Result
     where
         Self: PyDescrDeleteProtocol<'p>,
     {
@@ -69,6 +72,18 @@ pub trait PyDescrSetNameProtocol<'p>: PyDescrProtocol<'p> {
     type Result: Into<PyResult<()>>;
 }
 
+/// Extracts an argument of `tp_descr_get`, which may be `NULL` or `None`
+unsafe fn extract_optional<'p, A>(py: Python<'p>, ptr: *mut ffi::PyObject) -> PyResult<Option<A>>
+where
+    A: FromPyObject<'p>,
+{
+    if ptr.is_null() || ptr == ffi::Py_None() {
+        Ok(None)
+    } else {
+        py.from_borrowed_ptr::<PyAny>(ptr).extract().map(Some)
+    }
+}
+
 trait PyDescrGetProtocolImpl {
     fn tp_descr_get() -> Option<ffi::descrgetfunc>;
 }
@@ -86,15 +101,35 @@ where
     T: for<'p> PyDescrGetProtocol<'p>,
 {
     fn tp_descr_get() -> Option<ffi::descrgetfunc> {
-        py_ternary_func!(
-            PyDescrGetProtocol,
-            T::__get__,
-            T::Success,
-            PyObjectCallbackConverter
-        )
+        unsafe extern "C" fn wrap<T>(
+            slf: *mut ffi::PyObject,
+            instance: *mut ffi::PyObject,
+            owner: *mut ffi::PyObject,
+        ) -> *mut ffi::PyObject
+        where
+            T: for<'p> PyDescrGetProtocol<'p>,
+        {
+            let py = Python::assume_gil_acquired();
+            let _pool = crate::GILPool::new(py);
+            let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf).try_borrow();
+
+            let result = match slf {
+                Ok(ref slf) => match extract_optional(py, instance) {
+                    Ok(instance) => match extract_optional(py, owner) {
+                        Ok(owner) => slf.__get__(instance, owner).into(),
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e.into()),
+            };
+            crate::callback::cb_convert(PyObjectCallbackConverter, py, result)
+        }
+        Some(wrap::<T>)
     }
 }
 
+/// `tp_descr_set` is used for both `__set__` and `__delete__`, the value is `NULL` for the latter
 trait PyDescrSetProtocolImpl {
     fn tp_descr_set() -> Option<ffi::descrsetfunc>;
 }
@@ -103,21 +138,153 @@ where
     T: PyDescrProtocol<'p>,
 {
     default fn tp_descr_set() -> Option<ffi::descrsetfunc> {
-        None
+        <T as PyDescrDeleteProtocolImpl>::tp_descr_delete()
     }
 }
 impl<T> PyDescrSetProtocolImpl for T
 where
     T: for<'p> PyDescrSetProtocol<'p>,
+{
+    default fn tp_descr_set() -> Option<ffi::descrsetfunc> {
+        unsafe extern "C" fn wrap<T>(
+            slf: *mut ffi::PyObject,
+            instance: *mut ffi::PyObject,
+            value: *mut ffi::PyObject,
+        ) -> c_int
+        where
+            T: for<'p> PyDescrSetProtocol<'p>,
+        {
+            let py = Python::assume_gil_acquired();
+            let _pool = crate::GILPool::new(py);
+            let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf).try_borrow();
+
+            let result = match slf {
+                Ok(ref slf) => {
+                    if value.is_null() {
+                        Err(PyErr::new::<exceptions::AttributeError, _>(
+                            "Descriptor doesn't support deletion",
+                        ))
+                    } else {
+                        descr_set(py, &**slf, instance, value)
+                    }
+                }
+                Err(e) => Err(e.into()),
+            };
+            crate::callback::cb_convert(UnitCallbackConverter, py, result)
+        }
+        Some(wrap::<T>)
+    }
+}
+impl<T> PyDescrSetProtocolImpl for T
+where
+    T: for<'p> PyDescrSetProtocol<'p> + for<'p> PyDescrDeleteProtocol<'p>,
 {
     fn tp_descr_set() -> Option<ffi::descrsetfunc> {
-        py_ternary_func!(
-            PyDescrSetProtocol,
-            T::__set__,
-            (),
-            UnitCallbackConverter,
-            c_int
-        )
+        unsafe extern "C" fn wrap<T>(
+            slf: *mut ffi::PyObject,
+            instance: *mut ffi::PyObject,
+            value: *mut ffi::PyObject,
+        ) -> c_int
+        where
+            T: for<'p> PyDescrSetProtocol<'p> + for<'p> PyDescrDeleteProtocol<'p>,
+        {
+            let py = Python::assume_gil_acquired();
+            let _pool = crate::GILPool::new(py);
+            let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf).try_borrow();
+
+            let result = match slf {
+                Ok(ref slf) => {
+                    if value.is_null() {
+                        descr_delete(py, &**slf, instance)
+                    } else {
+                        descr_set(py, &**slf, instance, value)
+                    }
+                }
+                Err(e) => Err(e.into()),
+            };
+            crate::callback::cb_convert(UnitCallbackConverter, py, result)
+        }
+        Some(wrap::<T>)
+    }
+}
+
+trait PyDescrDeleteProtocolImpl {
+    fn tp_descr_delete() -> Option<ffi::descrsetfunc>;
+}
+impl<'p, T> PyDescrDeleteProtocolImpl for T
+where
+    T: PyDescrProtocol<'p>,
+{
+    default fn tp_descr_delete() -> Option<ffi::descrsetfunc> {
+        None
+    }
+}
+impl<T> PyDescrDeleteProtocolImpl for T
+where
+    T: for<'p> PyDescrDeleteProtocol<'p>,
+{
+    fn tp_descr_delete() -> Option<ffi::descrsetfunc> {
+        unsafe extern "C" fn wrap<T>(
+            slf: *mut ffi::PyObject,
+            instance: *mut ffi::PyObject,
+            value: *mut ffi::PyObject,
+        ) -> c_int
+        where
+            T: for<'p> PyDescrDeleteProtocol<'p>,
+        {
+            let py = Python::assume_gil_acquired();
+            let _pool = crate::GILPool::new(py);
+            let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf).try_borrow();
+
+            let result = match slf {
+                Ok(ref slf) => {
+                    if value.is_null() {
+                        descr_delete(py, &**slf, instance)
+                    } else {
+                        Err(PyErr::new::<exceptions::AttributeError, _>(
+                            "Descriptor doesn't support assignment",
+                        ))
+                    }
+                }
+                Err(e) => Err(e.into()),
+            };
+            crate::callback::cb_convert(UnitCallbackConverter, py, result)
+        }
+        Some(wrap::<T>)
+    }
+}
+
+unsafe fn descr_set<'p, T>(
+    py: Python<'p>,
+    slf: &'p T,
+    instance: *mut ffi::PyObject,
+    value: *mut ffi::PyObject,
+) -> PyResult<()>
+where
+    T: PyDescrSetProtocol<'p>,
+{
+    let instance = py.from_borrowed_ptr::<PyAny>(instance);
+    let value = py.from_borrowed_ptr::<PyAny>(value);
+    match instance.extract() {
+        Ok(instance) => match value.extract() {
+            Ok(value) => slf.__set__(instance, value).into(),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    }
+}
+
+unsafe fn descr_delete<'p, T>(
+    py: Python<'p>,
+    slf: &'p T,
+    instance: *mut ffi::PyObject,
+) -> PyResult<()>
+where
+    T: PyDescrDeleteProtocol<'p>,
+{
+    match py.from_borrowed_ptr::<PyAny>(instance).extract() {
+        Ok(instance) => slf.__delete__(instance).into(),
+        Err(e) => Err(e),
     }
 }
 
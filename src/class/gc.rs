@@ -4,8 +4,10 @@
 //!
 
 use crate::ffi;
+use crate::pyclass::{PyClass, PyClassShell};
 use crate::type_object::PyTypeInfo;
 use crate::AsPyPointer;
+use crate::FromPyPointer;
 use crate::Python;
 use std::os::raw::{c_int, c_void};
 
@@ -13,23 +15,29 @@ use std::os::raw::{c_int, c_void};
 pub struct PyTraverseError(c_int);
 
 /// GC support
-pub trait PyGCProtocol<'p>: PyTypeInfo {
+#[cfg(not(feature = "stable"))]
+pub trait PyGCProtocol<'p>: PyClass {
     fn __traverse__(&'p self, visit: PyVisit) -> Result<(), PyTraverseError>;
     fn __clear__(&'p mut self);
 }
 
+#[cfg(not(feature = "stable"))]
 pub trait PyGCTraverseProtocol<'p>: PyGCProtocol<'p> {}
+#[cfg(not(feature = "stable"))]
 pub trait PyGCClearProtocol<'p>: PyGCProtocol<'p> {}
 
+#[cfg(not(feature = "stable"))]
 #[doc(hidden)]
 pub trait PyGCProtocolImpl {
     fn update_type_object(_type_object: &mut ffi::PyTypeObject);
 }
 
+#[cfg(not(feature = "stable"))]
 impl<'p, T> PyGCProtocolImpl for T {
     default fn update_type_object(_type_object: &mut ffi::PyTypeObject) {}
 }
 
+#[cfg(not(feature = "stable"))]
 impl<'p, T> PyGCProtocolImpl for T
 where
     T: PyGCProtocol<'p>,
@@ -51,11 +59,17 @@ pub struct PyVisit<'p> {
 }
 
 impl<'p> PyVisit<'p> {
+    /// Visits `obj`. Like `Py_VISIT`, a null pointer, e.g. from a `None` in an `Option<PyObject>`,
+    /// is skipped.
     pub fn call<T>(&self, obj: &T) -> Result<(), PyTraverseError>
     where
         T: AsPyPointer,
     {
-        let r = unsafe { (self.visit)(obj.as_ptr(), self.arg) };
+        let ptr = obj.as_ptr();
+        if ptr.is_null() {
+            return Ok(());
+        }
+        let r = unsafe { (self.visit)(ptr, self.arg) };
         if r == 0 {
             Ok(())
         } else {
@@ -64,10 +78,12 @@ impl<'p> PyVisit<'p> {
     }
 }
 
+#[cfg(not(feature = "stable"))]
 trait PyGCTraverseProtocolImpl {
     fn tp_traverse() -> Option<ffi::traverseproc>;
 }
 
+#[cfg(not(feature = "stable"))]
 impl<'p, T> PyGCTraverseProtocolImpl for T
 where
     T: PyGCProtocol<'p>,
@@ -77,6 +93,7 @@ where
     }
 }
 
+#[cfg(not(feature = "stable"))]
 #[doc(hidden)]
 impl<T> PyGCTraverseProtocolImpl for T
 where
@@ -94,15 +111,22 @@ where
         {
             let py = Python::assume_gil_acquired();
             let _pool = crate::GILPool::new(py);
-            let slf = py.mut_from_borrowed_ptr::<T>(slf);
+            let obj = slf;
+            let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf);
 
             let visit = PyVisit {
                 visit,
                 arg,
                 _py: py,
             };
-            match slf.__traverse__(visit) {
-                Ok(()) => 0,
+            // The collector can run while a `&mut self` method is running. The object is
+            // still referenced by that call, so it is fine not to report its references.
+            let result = match slf.try_borrow() {
+                Ok(slf) => slf.__traverse__(visit),
+                Err(_) => Ok(()),
+            };
+            match result {
+                Ok(()) => traverse_dict::<T>(obj, visit.visit, visit.arg),
                 Err(PyTraverseError(code)) => code,
             }
         }
@@ -111,10 +135,12 @@ where
     }
 }
 
+#[cfg(not(feature = "stable"))]
 trait PyGCClearProtocolImpl {
     fn tp_clear() -> Option<ffi::inquiry>;
 }
 
+#[cfg(not(feature = "stable"))]
 impl<'p, T> PyGCClearProtocolImpl for T
 where
     T: PyGCProtocol<'p>,
@@ -124,6 +150,7 @@ where
     }
 }
 
+#[cfg(not(feature = "stable"))]
 impl<T> PyGCClearProtocolImpl for T
 where
     T: for<'p> PyGCClearProtocol<'p>,
@@ -136,11 +163,69 @@ where
         {
             let py = Python::assume_gil_acquired();
             let _pool = crate::GILPool::new(py);
-            let slf = py.mut_from_borrowed_ptr::<T>(slf);
+            let obj = slf;
+            let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf);
 
-            slf.__clear__();
+            // Unreachable objects can't be borrowed by a running method
+            if let Ok(mut slf) = slf.try_borrow_mut() {
+                slf.__clear__();
+            }
+            clear_dict::<T>(obj);
             0
         }
         Some(tp_clear::<T>)
     }
 }
+
+/// Returns the `__dict__` field of `#[pyclass(dict)]` instances, which `__traverse__` and
+/// `__clear__` don't know about.
+unsafe fn dict_ptr<T: PyTypeInfo>(slf: *mut ffi::PyObject) -> Option<*mut *mut ffi::PyObject> {
+    // The offset of `T` itself, subclasses defined in Python handle their own `__dict__`
+    let offset = T::type_object().tp_dictoffset;
+    if offset > 0 {
+        Some((slf as *mut u8).offset(offset) as *mut *mut ffi::PyObject)
+    } else {
+        None
+    }
+}
+
+#[doc(hidden)]
+pub unsafe fn traverse_dict<T: PyTypeInfo>(
+    slf: *mut ffi::PyObject,
+    visit: ffi::visitproc,
+    arg: *mut c_void,
+) -> c_int {
+    match dict_ptr::<T>(slf) {
+        Some(dict) if !(*dict).is_null() => visit(*dict, arg),
+        _ => 0,
+    }
+}
+
+#[doc(hidden)]
+pub unsafe fn clear_dict<T: PyTypeInfo>(slf: *mut ffi::PyObject) {
+    if let Some(dict) = dict_ptr::<T>(slf) {
+        let obj = *dict;
+        if !obj.is_null() {
+            *dict = std::ptr::null_mut();
+            ffi::Py_DECREF(obj);
+        }
+    }
+}
+
+/// `tp_traverse` of `#[pyclass(dict)]` classes without `PyGCProtocol`, which only visits the
+/// `__dict__`
+#[doc(hidden)]
+pub unsafe extern "C" fn tp_traverse_dict<T: PyTypeInfo>(
+    slf: *mut ffi::PyObject,
+    visit: ffi::visitproc,
+    arg: *mut c_void,
+) -> c_int {
+    traverse_dict::<T>(slf, visit, arg)
+}
+
+/// `tp_clear` of `#[pyclass(dict)]` classes without `PyGCProtocol`
+#[doc(hidden)]
+pub unsafe extern "C" fn tp_clear_dict<T: PyTypeInfo>(slf: *mut ffi::PyObject) -> c_int {
+    clear_dict::<T>(slf);
+    0
+}
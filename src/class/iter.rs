@@ -14,14 +14,14 @@ use std::ptr;
 /// `https://docs.python.org/3/c-api/typeobj.html#c.PyTypeObject.tp_iter`
 #[allow(unused_variables)]
 pub trait PyIterProtocol<'p>: PyClass {
-    fn __iter__(slf: &mut PyClassShell<Self>) -> Self::Result
+    fn __iter__(slf: &PyClassShell<Self>) -> Self::Result
     where
         Self: PyIterIterProtocol<'p>,
     {
         unimplemented!()
     }
 
-    fn __next__(slf: &mut PyClassShell<Self>) -> Self::Result
+    fn __next__(slf: &PyClassShell<Self>) -> Self::Result
     where
         Self: PyIterNextProtocol<'p>,
     {
@@ -12,15 +12,30 @@ macro_rules! py_unary_func {
             *mut $crate::ffi::PyObject
         );
     };
-    ($trait:ident, $class:ident :: $f:ident, $res_type:ty, $conv:expr, $ret_type:ty) => {{
+    ($trait:ident, $class:ident :: $f:ident, $res_type:ty, $conv:expr, $ret_type:ty) => {
+        py_unary_func!($trait, $class::$f, $res_type, $conv, $ret_type, try_borrow)
+    };
+    // `$borrow` is `try_borrow_mut` for slots taking `&mut self`
+    (
+        $trait:ident,
+        $class:ident :: $f:ident,
+        $res_type:ty,
+        $conv:expr,
+        $ret_type:ty,
+        $borrow:ident
+    ) => {{
         unsafe extern "C" fn wrap<T>(slf: *mut $crate::ffi::PyObject) -> $ret_type
         where
             T: for<'p> $trait<'p>,
         {
             let py = $crate::Python::assume_gil_acquired();
             let _pool = $crate::GILPool::new(py);
-            let slf = py.mut_from_borrowed_ptr::<T>(slf);
-            let res = slf.$f().into();
+            let slf: &$crate::PyClassShell<T> = $crate::FromPyPointer::from_borrowed_ptr(py, slf);
+            let mut slf = slf.$borrow();
+            let res = match slf {
+                Ok(ref mut slf) => slf.$f().into(),
+                Err(e) => Err(e.into()),
+            };
             $crate::callback::cb_convert($conv, py, res.map(|x| x))
         }
         Some(wrap::<$class>)
@@ -35,10 +50,9 @@ macro_rules! py_unary_pyref_func {
         where
             T: for<'p> $trait<'p>,
         {
-            use $crate::pyclass::PyClassShell;
             let py = $crate::Python::assume_gil_acquired();
             let _pool = $crate::GILPool::new(py);
-            let slf: &mut PyClassShell<T> = &mut *(slf as *mut PyClassShell<T>);
+            let slf: &$crate::PyClassShell<T> = $crate::FromPyPointer::from_borrowed_ptr(py, slf);
             let res = $class::$f(slf).into();
             $crate::callback::cb_convert($conv, py, res)
         }
@@ -56,9 +70,13 @@ macro_rules! py_len_func {
         {
             let py = Python::assume_gil_acquired();
             let _pool = $crate::GILPool::new(py);
-            let slf = py.mut_from_borrowed_ptr::<T>(slf);
+            let slf: &$crate::PyClassShell<T> = $crate::FromPyPointer::from_borrowed_ptr(py, slf);
+            let slf = slf.try_borrow();
 
-            let result = slf.$f().into();
+            let result = match slf {
+                Ok(ref slf) => slf.$f().into(),
+                Err(e) => Err(e.into()),
+            };
             $crate::callback::cb_convert($conv, py, result)
         }
         Some(wrap::<$class>)
@@ -77,7 +95,18 @@ macro_rules! py_binary_func {
             *mut $crate::ffi::PyObject
         )
     };
-    ($trait:ident, $class:ident :: $f:ident, $res_type:ty, $conv:expr, $return:ty) => {{
+    ($trait:ident, $class:ident :: $f:ident, $res_type:ty, $conv:expr, $return:ty) => {
+        py_binary_func!($trait, $class::$f, $res_type, $conv, $return, try_borrow)
+    };
+    // `$borrow` is `try_borrow_mut` for slots taking `&mut self`
+    (
+        $trait:ident,
+        $class:ident :: $f:ident,
+        $res_type:ty,
+        $conv:expr,
+        $return:ty,
+        $borrow:ident
+    ) => {{
         #[allow(unused_mut)]
         unsafe extern "C" fn wrap<T>(slf: *mut ffi::PyObject, arg: *mut ffi::PyObject) -> $return
         where
@@ -86,11 +115,15 @@ macro_rules! py_binary_func {
             use $crate::ObjectProtocol;
             let py = $crate::Python::assume_gil_acquired();
             let _pool = $crate::GILPool::new(py);
-            let slf = py.mut_from_borrowed_ptr::<T>(slf);
+            let slf: &$crate::PyClassShell<T> = $crate::FromPyPointer::from_borrowed_ptr(py, slf);
+            let mut slf = slf.$borrow();
             let arg = py.from_borrowed_ptr::<$crate::types::PyAny>(arg);
 
-            let result = match arg.extract() {
-                Ok(arg) => slf.$f(arg).into(),
+            let result = match slf {
+                Ok(ref mut slf) => match arg.extract() {
+                    Ok(arg) => slf.$f(arg).into(),
+                    Err(e) => Err(e.into()),
+                },
                 Err(e) => Err(e.into()),
             };
             $crate::callback::cb_convert($conv, py, result)
@@ -117,12 +150,52 @@ macro_rules! py_binary_num_func {
             let lhs = py.from_borrowed_ptr::<$crate::types::PyAny>(lhs);
             let rhs = py.from_borrowed_ptr::<$crate::types::PyAny>(rhs);
 
-            let result = match lhs.extract() {
-                Ok(lhs) => match rhs.extract() {
-                    Ok(rhs) => $class::$f(lhs, rhs).into(),
+            // Operands of the wrong type give the other operand's type a chance to handle it
+            match (lhs.extract(), rhs.extract()) {
+                (Ok(lhs), Ok(rhs)) => {
+                    let result = $class::$f(lhs, rhs).into();
+                    $crate::callback::cb_convert($conv, py, result)
+                }
+                _ => $crate::IntoPyPointer::into_ptr(py.NotImplemented()),
+            }
+        }
+        Some(wrap::<$class>)
+    }};
+}
+
+/// Slot function for a type that only implements the reflected operation (e.g. `__radd__`).
+///
+/// Python calls the slot of the right-hand operand with the operands in their original order,
+/// so `self` is `rhs`.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! py_binary_reverse_num_func {
+    ($trait:ident, $class:ident :: $f:ident, $res_type:ty, $conv:expr) => {{
+        #[allow(unused_mut)]
+        unsafe extern "C" fn wrap<T>(
+            lhs: *mut ffi::PyObject,
+            rhs: *mut ffi::PyObject,
+        ) -> *mut $crate::ffi::PyObject
+        where
+            T: for<'p> $trait<'p>,
+        {
+            use $crate::ObjectProtocol;
+            let py = $crate::Python::assume_gil_acquired();
+            let _pool = $crate::GILPool::new(py);
+            let arg = py.from_borrowed_ptr::<$crate::types::PyAny>(lhs);
+
+            if !<T as $crate::type_object::PyTypeInfo>::is_instance(py.from_borrowed_ptr(rhs)) {
+                return $crate::IntoPyPointer::into_ptr(py.NotImplemented());
+            }
+            let slf: &$crate::PyClassShell<T> = $crate::FromPyPointer::from_borrowed_ptr(py, rhs);
+            let slf = slf.try_borrow();
+
+            let result = match arg.extract() {
+                Ok(arg) => match slf {
+                    Ok(ref slf) => slf.$f(arg).into(),
                     Err(e) => Err(e.into()),
                 },
-                Err(e) => Err(e.into()),
+                Err(_) => return $crate::IntoPyPointer::into_ptr(py.NotImplemented()),
             };
             $crate::callback::cb_convert($conv, py, result)
         }
@@ -130,6 +203,51 @@ macro_rules! py_binary_num_func {
     }};
 }
 
+/// Slot function for a type that implements both the operation and the reflected operation.
+///
+/// The reflected operation is only tried if the operands can't be extracted for the normal one.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! py_binary_fallback_num_func {
+    ($trait:ident, $rtrait:ident, $class:ident :: $f:ident, $rf:ident, $conv:expr) => {{
+        #[allow(unused_mut)]
+        unsafe extern "C" fn wrap<T>(
+            lhs: *mut ffi::PyObject,
+            rhs: *mut ffi::PyObject,
+        ) -> *mut $crate::ffi::PyObject
+        where
+            T: for<'p> $trait<'p> + for<'p> $rtrait<'p>,
+        {
+            use $crate::ObjectProtocol;
+            let py = $crate::Python::assume_gil_acquired();
+            let _pool = $crate::GILPool::new(py);
+            let lhs_obj = py.from_borrowed_ptr::<$crate::types::PyAny>(lhs);
+            let rhs_obj = py.from_borrowed_ptr::<$crate::types::PyAny>(rhs);
+
+            if let (Ok(lhs), Ok(rhs)) = (lhs_obj.extract(), rhs_obj.extract()) {
+                let result = $class::$f(lhs, rhs).into();
+                return $crate::callback::cb_convert($conv, py, result);
+            }
+
+            if <T as $crate::type_object::PyTypeInfo>::is_instance(rhs_obj) {
+                let slf: &$crate::PyClassShell<T> =
+                    $crate::FromPyPointer::from_borrowed_ptr(py, rhs);
+                let slf = slf.try_borrow();
+                let result = match lhs_obj.extract() {
+                    Ok(arg) => match slf {
+                        Ok(ref slf) => slf.$rf(arg).into(),
+                        Err(e) => Err(e.into()),
+                    },
+                    Err(_) => return $crate::IntoPyPointer::into_ptr(py.NotImplemented()),
+                };
+                return $crate::callback::cb_convert($conv, py, result);
+            }
+            $crate::IntoPyPointer::into_ptr(py.NotImplemented())
+        }
+        Some(wrap::<$class>)
+    }};
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! py_binary_self_func {
@@ -146,12 +264,17 @@ macro_rules! py_binary_self_func {
 
             let py = $crate::Python::assume_gil_acquired();
             let _pool = $crate::GILPool::new(py);
-            let slf1 = py.mut_from_borrowed_ptr::<T>(slf);
+            let slf1: &$crate::PyClassShell<T> = $crate::FromPyPointer::from_borrowed_ptr(py, slf);
+            let mut slf1 = slf1.try_borrow_mut();
             let arg = py.from_borrowed_ptr::<$crate::types::PyAny>(arg);
 
+            // Returning NotImplemented makes Python fall back to the binary operator
             let result = match arg.extract() {
-                Ok(arg) => slf1.$f(arg).into(),
-                Err(e) => Err(e.into()),
+                Ok(arg) => match slf1 {
+                    Ok(ref mut slf1) => slf1.$f(arg).into(),
+                    Err(e) => Err(e.into()),
+                },
+                Err(_) => return $crate::IntoPyPointer::into_ptr(py.NotImplemented()),
             };
             match result {
                 Ok(_) => {
@@ -159,7 +282,7 @@ macro_rules! py_binary_self_func {
                     slf
                 }
                 Err(e) => {
-                    e.restore(py);
+                    $crate::callback::restore_err(py, e);
                     std::ptr::null_mut()
                 }
             }
@@ -171,7 +294,11 @@ macro_rules! py_binary_self_func {
 #[macro_export]
 #[doc(hidden)]
 macro_rules! py_ssizearg_func {
-    ($trait:ident, $class:ident :: $f:ident, $res_type:ty, $conv:expr) => {{
+    ($trait:ident, $class:ident :: $f:ident, $res_type:ty, $conv:expr) => {
+        py_ssizearg_func!($trait, $class::$f, $res_type, $conv, try_borrow)
+    };
+    // `$borrow` is `try_borrow_mut` for slots taking `&mut self`
+    ($trait:ident, $class:ident :: $f:ident, $res_type:ty, $conv:expr, $borrow:ident) => {{
         #[allow(unused_mut)]
         unsafe extern "C" fn wrap<T>(
             slf: *mut ffi::PyObject,
@@ -182,8 +309,12 @@ macro_rules! py_ssizearg_func {
         {
             let py = $crate::Python::assume_gil_acquired();
             let _pool = $crate::GILPool::new(py);
-            let slf = py.mut_from_borrowed_ptr::<T>(slf);
-            let result = slf.$f(arg.into()).into();
+            let slf: &$crate::PyClassShell<T> = $crate::FromPyPointer::from_borrowed_ptr(py, slf);
+            let mut slf = slf.$borrow();
+            let result = match slf {
+                Ok(ref mut slf) => slf.$f(arg.into()).into(),
+                Err(e) => Err(e.into()),
+            };
             $crate::callback::cb_convert($conv, py, result)
         }
         Some(wrap::<$class>)
@@ -215,13 +346,17 @@ macro_rules! py_ternary_func {
 
             let py = $crate::Python::assume_gil_acquired();
             let _pool = $crate::GILPool::new(py);
-            let slf = py.mut_from_borrowed_ptr::<T>(slf);
+            let slf: &$crate::PyClassShell<T> = $crate::FromPyPointer::from_borrowed_ptr(py, slf);
+            let slf = slf.try_borrow();
             let arg1 = py.from_borrowed_ptr::<$crate::types::PyAny>(arg1);
             let arg2 = py.from_borrowed_ptr::<$crate::types::PyAny>(arg2);
 
-            let result = match arg1.extract() {
-                Ok(arg1) => match arg2.extract() {
-                    Ok(arg2) => slf.$f(arg1, arg2).into(),
+            let result = match slf {
+                Ok(ref slf) => match arg1.extract() {
+                    Ok(arg1) => match arg2.extract() {
+                        Ok(arg2) => slf.$f(arg1, arg2).into(),
+                        Err(e) => Err(e.into()),
+                    },
                     Err(e) => Err(e.into()),
                 },
                 Err(e) => Err(e.into()),
@@ -253,15 +388,53 @@ macro_rules! py_ternary_num_func {
             let arg2 = py.from_borrowed_ptr::<$crate::types::PyAny>(arg2);
             let arg3 = py.from_borrowed_ptr::<$crate::types::PyAny>(arg3);
 
-            let result = match arg1.extract() {
-                Ok(arg1) => match arg2.extract() {
-                    Ok(arg2) => match arg3.extract() {
-                        Ok(arg3) => $class::$f(arg1, arg2, arg3).into(),
-                        Err(e) => Err(e.into()),
-                    },
+            match (arg1.extract(), arg2.extract(), arg3.extract()) {
+                (Ok(arg1), Ok(arg2), Ok(arg3)) => {
+                    let result = $class::$f(arg1, arg2, arg3).into();
+                    $crate::callback::cb_convert($conv, py, result)
+                }
+                _ => $crate::IntoPyPointer::into_ptr(py.NotImplemented()),
+            }
+        }
+
+        Some(wrap::<T>)
+    }};
+}
+
+/// Like `py_binary_reverse_num_func`, for `pow()`. The reflected operation is only
+/// used for the two-argument form, as `__rpow__` takes no modulus.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! py_ternary_reverse_num_func {
+    ($trait:ident, $class:ident :: $f:ident, $res_type:ty, $conv:expr) => {{
+        unsafe extern "C" fn wrap<T>(
+            arg1: *mut $crate::ffi::PyObject,
+            arg2: *mut $crate::ffi::PyObject,
+            arg3: *mut $crate::ffi::PyObject,
+        ) -> *mut $crate::ffi::PyObject
+        where
+            T: for<'p> $trait<'p>,
+        {
+            use $crate::ObjectProtocol;
+
+            let py = $crate::Python::assume_gil_acquired();
+            let _pool = $crate::GILPool::new(py);
+            let arg = py.from_borrowed_ptr::<$crate::types::PyAny>(arg1);
+
+            if arg3 != $crate::ffi::Py_None()
+                || !<T as $crate::type_object::PyTypeInfo>::is_instance(py.from_borrowed_ptr(arg2))
+            {
+                return $crate::IntoPyPointer::into_ptr(py.NotImplemented());
+            }
+            let slf: &$crate::PyClassShell<T> = $crate::FromPyPointer::from_borrowed_ptr(py, arg2);
+            let slf = slf.try_borrow();
+
+            let result = match arg.extract() {
+                Ok(arg) => match slf {
+                    Ok(ref slf) => slf.$f(arg).into(),
                     Err(e) => Err(e.into()),
                 },
-                Err(e) => Err(e.into()),
+                Err(_) => return $crate::IntoPyPointer::into_ptr(py.NotImplemented()),
             };
             $crate::callback::cb_convert($conv, py, result)
         }
@@ -270,6 +443,56 @@ macro_rules! py_ternary_num_func {
     }};
 }
 
+/// Like `py_binary_fallback_num_func`, for `pow()`.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! py_ternary_fallback_num_func {
+    ($trait:ident, $rtrait:ident, $class:ident :: $f:ident, $rf:ident, $conv:expr) => {{
+        unsafe extern "C" fn wrap<T>(
+            arg1: *mut $crate::ffi::PyObject,
+            arg2: *mut $crate::ffi::PyObject,
+            arg3: *mut $crate::ffi::PyObject,
+        ) -> *mut $crate::ffi::PyObject
+        where
+            T: for<'p> $trait<'p> + for<'p> $rtrait<'p>,
+        {
+            use $crate::ObjectProtocol;
+
+            let py = $crate::Python::assume_gil_acquired();
+            let _pool = $crate::GILPool::new(py);
+            let lhs_obj = py.from_borrowed_ptr::<$crate::types::PyAny>(arg1);
+            let rhs_obj = py.from_borrowed_ptr::<$crate::types::PyAny>(arg2);
+            let modulo_obj = py.from_borrowed_ptr::<$crate::types::PyAny>(arg3);
+
+            if let (Ok(lhs), Ok(rhs), Ok(modulo)) =
+                (lhs_obj.extract(), rhs_obj.extract(), modulo_obj.extract())
+            {
+                let result = $class::$f(lhs, rhs, modulo).into();
+                return $crate::callback::cb_convert($conv, py, result);
+            }
+
+            if arg3 == $crate::ffi::Py_None()
+                && <T as $crate::type_object::PyTypeInfo>::is_instance(rhs_obj)
+            {
+                let slf: &$crate::PyClassShell<T> =
+                    $crate::FromPyPointer::from_borrowed_ptr(py, arg2);
+                let slf = slf.try_borrow();
+                let result = match lhs_obj.extract() {
+                    Ok(arg) => match slf {
+                        Ok(ref slf) => slf.$rf(arg).into(),
+                        Err(e) => Err(e.into()),
+                    },
+                    Err(_) => return $crate::IntoPyPointer::into_ptr(py.NotImplemented()),
+                };
+                return $crate::callback::cb_convert($conv, py, result);
+            }
+            $crate::IntoPyPointer::into_ptr(py.NotImplemented())
+        }
+
+        Some(wrap::<T>)
+    }};
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! py_ternary_self_func {
@@ -286,22 +509,26 @@ macro_rules! py_ternary_self_func {
 
             let py = $crate::Python::assume_gil_acquired();
             let _pool = $crate::GILPool::new(py);
-            let slf1 = py.mut_from_borrowed_ptr::<T>(slf);
+            let slf1: &$crate::PyClassShell<T> = $crate::FromPyPointer::from_borrowed_ptr(py, slf);
+            let mut slf1 = slf1.try_borrow_mut();
             let arg1 = py.from_borrowed_ptr::<$crate::types::PyAny>(arg1);
             let arg2 = py.from_borrowed_ptr::<$crate::types::PyAny>(arg2);
 
-            let result = match arg1.extract() {
-                Ok(arg1) => match arg2.extract() {
-                    Ok(arg2) => slf1.$f(arg1, arg2).into(),
+            let result = match (arg1.extract(), arg2.extract()) {
+                (Ok(arg1), Ok(arg2)) => match slf1 {
+                    Ok(ref mut slf1) => slf1.$f(arg1, arg2).into(),
                     Err(e) => Err(e.into()),
                 },
-                Err(e) => Err(e.into()),
+                _ => return $crate::IntoPyPointer::into_ptr(py.NotImplemented()),
             };
 
             match result {
-                Ok(_) => slf,
+                Ok(_) => {
+                    ffi::Py_INCREF(slf);
+                    slf
+                }
                 Err(e) => {
-                    e.restore(py);
+                    $crate::callback::restore_err(py, e);
                     std::ptr::null_mut()
                 }
             }
@@ -325,7 +552,9 @@ macro_rules! py_func_set {
 
             let py = $crate::Python::assume_gil_acquired();
             let _pool = $crate::GILPool::new(py);
-            let slf = py.mut_from_borrowed_ptr::<$generic>(slf);
+            let slf: &$crate::PyClassShell<$generic> =
+                $crate::FromPyPointer::from_borrowed_ptr(py, slf);
+            let mut slf = slf.try_borrow_mut();
 
             let result = if value.is_null() {
                 Err($crate::PyErr::new::<exceptions::NotImplementedError, _>(
@@ -335,11 +564,14 @@ macro_rules! py_func_set {
                     ),
                 ))
             } else {
-                let name = py.mut_from_borrowed_ptr::<$crate::types::PyAny>(name);
+                let name = py.from_borrowed_ptr::<$crate::types::PyAny>(name);
                 let value = py.from_borrowed_ptr::<$crate::types::PyAny>(value);
-                match name.extract() {
-                    Ok(name) => match value.extract() {
-                        Ok(value) => slf.$fn_set(name, value).into(),
+                match slf {
+                    Ok(ref mut slf) => match name.extract() {
+                        Ok(name) => match value.extract() {
+                            Ok(value) => slf.$fn_set(name, value).into(),
+                            Err(e) => Err(e.into()),
+                        },
                         Err(e) => Err(e.into()),
                     },
                     Err(e) => Err(e.into()),
@@ -348,7 +580,7 @@ macro_rules! py_func_set {
             match result {
                 Ok(_) => 0,
                 Err(e) => {
-                    e.restore(py);
+                    $crate::callback::restore_err(py, e);
                     -1
                 }
             }
@@ -375,11 +607,16 @@ macro_rules! py_func_del {
             let _pool = $crate::GILPool::new(py);
 
             let result = if value.is_null() {
-                let slf = py.mut_from_borrowed_ptr::<U>(slf);
+                let slf: &$crate::PyClassShell<U> =
+                    $crate::FromPyPointer::from_borrowed_ptr(py, slf);
+                let mut slf = slf.try_borrow_mut();
                 let name = py.from_borrowed_ptr::<$crate::types::PyAny>(name);
 
-                match name.extract() {
-                    Ok(name) => slf.$fn_del(name).into(),
+                match slf {
+                    Ok(ref mut slf) => match name.extract() {
+                        Ok(name) => slf.$fn_del(name).into(),
+                        Err(e) => Err(e.into()),
+                    },
                     Err(e) => Err(e.into()),
                 }
             } else {
@@ -390,7 +627,7 @@ macro_rules! py_func_del {
             match result {
                 Ok(_) => 0,
                 Err(e) => {
-                    e.restore(py);
+                    $crate::callback::restore_err(py, e);
                     -1
                 }
             }
@@ -415,28 +652,35 @@ macro_rules! py_func_set_del {
 
             let py = $crate::Python::assume_gil_acquired();
             let _pool = $crate::GILPool::new(py);
-            let slf = py.mut_from_borrowed_ptr::<$generic>(slf);
+            let slf: &$crate::PyClassShell<$generic> =
+                $crate::FromPyPointer::from_borrowed_ptr(py, slf);
+            let mut slf = slf.try_borrow_mut();
             let name = py.from_borrowed_ptr::<$crate::types::PyAny>(name);
 
-            let result = if value.is_null() {
-                match name.extract() {
-                    Ok(name) => slf.$fn_del(name).into(),
-                    Err(e) => Err(e.into()),
-                }
-            } else {
-                let value = py.from_borrowed_ptr::<$crate::types::PyAny>(value);
-                match name.extract() {
-                    Ok(name) => match value.extract() {
-                        Ok(value) => slf.$fn_set(name, value).into(),
-                        Err(e) => Err(e.into()),
-                    },
-                    Err(e) => Err(e.into()),
+            let result = match slf {
+                Ok(ref mut slf) => {
+                    if value.is_null() {
+                        match name.extract() {
+                            Ok(name) => slf.$fn_del(name).into(),
+                            Err(e) => Err(e.into()),
+                        }
+                    } else {
+                        let value = py.from_borrowed_ptr::<$crate::types::PyAny>(value);
+                        match name.extract() {
+                            Ok(name) => match value.extract() {
+                                Ok(value) => slf.$fn_set(name, value).into(),
+                                Err(e) => Err(e.into()),
+                            },
+                            Err(e) => Err(e.into()),
+                        }
+                    }
                 }
+                Err(e) => Err(e.into()),
             };
             match result {
                 Ok(_) => 0,
                 Err(e) => {
-                    e.restore(py);
+                    $crate::callback::restore_err(py, e);
                     -1
                 }
             }
@@ -1,8 +1,13 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
-use crate::ffi;
+use crate::err::PyResult;
+use crate::exceptions::AttributeError;
+use crate::{ffi, PyObject, Python};
 use libc::c_int;
 use std::ffi::CString;
+use std::fmt;
+use std::os::raw::c_void;
+use std::ptr;
 
 /// `PyMethodDefType` represents different types of python callable objects.
 /// It is used by `#[pymethods]` and `#[pyproto]` annotations.
@@ -22,12 +27,27 @@ pub enum PyMethodDefType {
     Getter(PyGetterDef),
     /// Represents setter descriptor, used by `#[setter]`
     Setter(PySetterDef),
+    /// Represents deleter descriptor, used by `#[deleter]`
+    Deleter(PyDeleterDef),
+    /// Represents class attribute, used by `#[classattr]`
+    ClassAttribute(PyClassAttributeDef),
+    /// Represents class `__repr__` method, used for `tp_repr`
+    Repr(ffi::reprfunc),
+    /// Represents class `__str__` method, used for `tp_str`
+    Str(ffi::reprfunc),
 }
 
 #[derive(Copy, Clone, Debug)]
 pub enum PyMethodType {
     PyCFunction(ffi::PyCFunction),
     PyCFunctionWithKeywords(ffi::PyCFunctionWithKeywords),
+    /// A `METH_FASTCALL | METH_KEYWORDS` function, and the same function taking a tuple and a dict
+    /// for Pythons without `METH_FASTCALL`. It is declared with `METH_VARARGS | METH_KEYWORDS`,
+    /// which [`PyMethodDef::as_method_def`] replaces when it uses the first function.
+    PyCFunctionFastWithKeywords(
+        ffi::_PyCFunctionFastWithKeywords,
+        ffi::PyCFunctionWithKeywords,
+    ),
     PyNewFunc(ffi::newfunc),
     PyInitFunc(ffi::initproc),
 }
@@ -54,6 +74,29 @@ pub struct PySetterDef {
     pub doc: &'static str,
 }
 
+/// A property deleter, which is called like a setter with a null value.
+#[derive(Copy, Clone, Debug)]
+pub struct PyDeleterDef {
+    pub name: &'static str,
+    pub meth: ffi::setter,
+    pub doc: &'static str,
+}
+
+/// A class attribute, whose value is created when the type object is initialized.
+#[derive(Copy, Clone)]
+pub struct PyClassAttributeDef {
+    pub name: &'static str,
+    pub meth: fn(Python) -> PyResult<PyObject>,
+}
+
+impl fmt::Debug for PyClassAttributeDef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PyClassAttributeDef")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
 unsafe impl Sync for PyMethodDef {}
 
 unsafe impl Sync for ffi::PyMethodDef {}
@@ -62,6 +105,8 @@ unsafe impl Sync for PyGetterDef {}
 
 unsafe impl Sync for PySetterDef {}
 
+unsafe impl Sync for PyDeleterDef {}
+
 unsafe impl Sync for ffi::PyGetSetDef {}
 
 impl PyMethodDef {
@@ -70,16 +115,31 @@ impl PyMethodDef {
         let meth = match self.ml_meth {
             PyMethodType::PyCFunction(meth) => meth,
             PyMethodType::PyCFunctionWithKeywords(meth) => unsafe { std::mem::transmute(meth) },
+            #[cfg(all(Py_3_7, not(Py_LIMITED_API), not(PyPy)))]
+            PyMethodType::PyCFunctionFastWithKeywords(meth, _) => unsafe {
+                std::mem::transmute(meth)
+            },
+            #[cfg(not(all(Py_3_7, not(Py_LIMITED_API), not(PyPy))))]
+            PyMethodType::PyCFunctionFastWithKeywords(_, meth) => unsafe {
+                std::mem::transmute(meth)
+            },
             PyMethodType::PyNewFunc(meth) => unsafe { std::mem::transmute(meth) },
             PyMethodType::PyInitFunc(meth) => unsafe { std::mem::transmute(meth) },
         };
+        let flags = match self.ml_meth {
+            #[cfg(all(Py_3_7, not(Py_LIMITED_API), not(PyPy)))]
+            PyMethodType::PyCFunctionFastWithKeywords(..) => {
+                self.ml_flags & !ffi::METH_VARARGS | ffi::METH_FASTCALL
+            }
+            _ => self.ml_flags,
+        };
 
         ffi::PyMethodDef {
             ml_name: CString::new(self.ml_name)
                 .expect("Method name must not contain NULL byte")
                 .into_raw(),
             ml_meth: Some(meth),
-            ml_flags: self.ml_flags,
+            ml_flags: flags,
             ml_doc: self.ml_doc.as_ptr() as *const _,
         }
     }
@@ -115,6 +175,53 @@ impl PySetterDef {
     }
 }
 
+impl PyDeleterDef {
+    /// Copy descriptor information to `ffi::PyGetSetDef`, after the setter of the same property.
+    ///
+    /// Python deletes an attribute by calling its setter with a null value, so the setter is
+    /// replaced by a function calling either the setter or the deleter, which are passed to it as
+    /// the closure.
+    pub fn copy_to(&self, dst: &mut ffi::PyGetSetDef) {
+        if dst.name.is_null() {
+            dst.name = CString::new(self.name)
+                .expect("Method name must not contain NULL byte")
+                .into_raw();
+        }
+        if dst.doc.is_null() {
+            dst.doc = self.doc.as_ptr() as *mut libc::c_char;
+        }
+        let setters = Box::new(PropertySetters {
+            set: dst.set,
+            delete: self.meth,
+        });
+        dst.set = Some(set_or_delete);
+        dst.closure = Box::into_raw(setters) as *mut c_void;
+    }
+}
+
+/// The closure of a property with a deleter
+struct PropertySetters {
+    set: Option<ffi::setter>,
+    delete: ffi::setter,
+}
+
+unsafe extern "C" fn set_or_delete(
+    slf: *mut ffi::PyObject,
+    value: *mut ffi::PyObject,
+    closure: *mut c_void,
+) -> c_int {
+    let setters = &*(closure as *const PropertySetters);
+    if value.is_null() {
+        (setters.delete)(slf, value, ptr::null_mut())
+    } else if let Some(set) = setters.set {
+        set(slf, value, ptr::null_mut())
+    } else {
+        let py = Python::assume_gil_acquired();
+        AttributeError::py_err("can't set attribute").restore(py);
+        -1
+    }
+}
+
 #[doc(hidden)] // Only to be used through the proc macros, use PyMethodsProtocol in custom code
 /// This trait is implemented for all pyclass so to implement the [PyMethodsProtocol]
 /// through inventory
@@ -152,3 +259,59 @@ where
             .collect()
     }
 }
+
+/// Adds methods defined by hand to a `#[pyclass]`, next to the ones of its `#[pymethods]`.
+///
+/// This gives full control over the `PyMethodDef`, e.g. to use `METH_O` or `METH_COEXIST`, or to
+/// add an existing `extern "C"` function as a method. The function must match the flags: it is
+/// called by the interpreter without any of the argument parsing, error handling or borrow
+/// checking pyo3 generates. `ml_doc` must be nul terminated.
+///
+/// Slots can be changed in the same way with `#[pyclass(customize = function)]`, which calls
+/// `function(&mut ffi::PyTypeObject)` after pyo3 has filled the type object.
+///
+/// # Example
+/// ```
+/// use pyo3::class::{PyMethodDef, PyMethodDefType, PyMethodType};
+/// use pyo3::prelude::*;
+/// use pyo3::{ffi, py_raw_methods, py_run};
+///
+/// #[pyclass]
+/// struct Echo {}
+///
+/// unsafe extern "C" fn echo(
+///     _slf: *mut ffi::PyObject,
+///     arg: *mut ffi::PyObject,
+/// ) -> *mut ffi::PyObject {
+///     ffi::Py_INCREF(arg);
+///     arg
+/// }
+///
+/// py_raw_methods!(Echo, [
+///     PyMethodDefType::Method(PyMethodDef {
+///         ml_name: "echo",
+///         ml_meth: PyMethodType::PyCFunction(echo),
+///         ml_flags: ffi::METH_O,
+///         ml_doc: "Returns its only argument\0",
+///     }),
+/// ]);
+///
+/// # fn main() {
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let obj = Py::new(py, Echo {}).unwrap();
+/// py_run!(py, obj, "assert obj.echo(5) == 5");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! py_raw_methods {
+    ($cls:ty, [$($def:expr),* $(,)?]) => {
+        $crate::inventory::submit! {
+            #![crate = $crate] {
+                type ClsInventory =
+                    <$cls as $crate::class::methods::PyMethodsInventoryDispatch>::InventoryType;
+                <ClsInventory as $crate::class::methods::PyMethodsInventory>::new(&[$($def),*])
+            }
+        }
+    };
+}
@@ -1,30 +1,56 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
 //! Python object protocols
+//!
+//! The protocol traits are implemented with `#[pyproto]`, which relies on specialization. With
+//! the `stable` feature, only the parts of `basic` and `gc` used by `#[pyclass]` are available.
 
+#[cfg(not(feature = "stable"))]
 #[macro_use]
 mod macros;
 
 pub mod basic;
+#[cfg(not(feature = "stable"))]
 pub mod buffer;
+#[cfg(not(feature = "stable"))]
 pub mod context;
+#[cfg(not(feature = "stable"))]
 pub mod descr;
 pub mod gc;
+#[cfg(not(feature = "stable"))]
 pub mod iter;
+#[cfg(not(feature = "stable"))]
 pub mod mapping;
 pub mod methods;
+#[cfg(not(feature = "stable"))]
 pub mod number;
+#[cfg(not(feature = "stable"))]
 pub mod pyasync;
+#[cfg(not(feature = "stable"))]
 pub mod sequence;
 
+#[cfg(not(feature = "stable"))]
 pub use self::basic::PyObjectProtocol;
+#[cfg(not(feature = "stable"))]
 pub use self::buffer::PyBufferProtocol;
+#[cfg(not(feature = "stable"))]
 pub use self::context::PyContextProtocol;
+#[cfg(not(feature = "stable"))]
 pub use self::descr::PyDescrProtocol;
-pub use self::gc::{PyGCProtocol, PyTraverseError, PyVisit};
+#[cfg(not(feature = "stable"))]
+pub use self::gc::PyGCProtocol;
+pub use self::gc::{PyTraverseError, PyVisit};
+#[cfg(not(feature = "stable"))]
 pub use self::iter::PyIterProtocol;
+#[cfg(not(feature = "stable"))]
 pub use self::mapping::PyMappingProtocol;
-pub use self::methods::{PyGetterDef, PyMethodDef, PyMethodDefType, PyMethodType, PySetterDef};
+pub use self::methods::{
+    PyClassAttributeDef, PyDeleterDef, PyGetterDef, PyMethodDef, PyMethodDefType, PyMethodType,
+    PySetterDef,
+};
+#[cfg(not(feature = "stable"))]
 pub use self::number::PyNumberProtocol;
+#[cfg(not(feature = "stable"))]
 pub use self::pyasync::PyAsyncProtocol;
+#[cfg(not(feature = "stable"))]
 pub use self::sequence::PySequenceProtocol;
@@ -7,13 +7,13 @@ use crate::callback::PyObjectCallbackConverter;
 use crate::class::basic::PyObjectProtocolImpl;
 use crate::class::methods::PyMethodDef;
 use crate::err::PyResult;
-use crate::type_object::PyTypeInfo;
+use crate::pyclass::PyClass;
 use crate::FromPyObject;
 use crate::{ffi, IntoPy, PyObject};
 
 /// Number interface
 #[allow(unused_variables)]
-pub trait PyNumberProtocol<'p>: PyTypeInfo {
+pub trait PyNumberProtocol<'p>: PyClass {
     fn __add__(lhs: Self::Left, rhs: Self::Right) -> Self::Result
     where
         Self: PyNumberAddProtocol<'p>,
@@ -469,7 +469,6 @@ pub trait PyNumberRDivmodProtocol<'p>: PyNumberProtocol<'p> {
 
 pub trait PyNumberRPowProtocol<'p>: PyNumberProtocol<'p> {
     type Other: FromPyObject<'p>;
-    type Modulo: FromPyObject<'p>;
     type Success: IntoPy<PyObject>;
     type Result: Into<PyResult<Self::Success>>;
 }
@@ -754,7 +753,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_add() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRAddSlotImpl>::nb_add_reflected()
     }
 }
 
@@ -762,7 +761,7 @@ impl<T> PyNumberAddProtocolImpl for T
 where
     T: for<'p> PyNumberAddProtocol<'p>,
 {
-    fn nb_add() -> Option<ffi::binaryfunc> {
+    default fn nb_add() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberAddProtocol,
             T::__add__,
@@ -772,6 +771,49 @@ where
     }
 }
 
+impl<T> PyNumberAddProtocolImpl for T
+where
+    T: for<'p> PyNumberAddProtocol<'p> + for<'p> PyNumberRAddProtocol<'p>,
+{
+    fn nb_add() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberAddProtocol,
+            PyNumberRAddProtocol,
+            T::__add__,
+            __radd__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_add` for types that implement `__radd__` but not `__add__`
+trait PyNumberRAddSlotImpl {
+    fn nb_add_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRAddSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_add_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRAddSlotImpl for T
+where
+    T: for<'p> PyNumberRAddProtocol<'p>,
+{
+    fn nb_add_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRAddProtocol,
+            T::__radd__,
+            <T as PyNumberRAddProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberSubProtocolImpl {
     fn nb_subtract() -> Option<ffi::binaryfunc>;
 }
@@ -781,7 +823,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_subtract() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRSubSlotImpl>::nb_subtract_reflected()
     }
 }
 
@@ -789,7 +831,7 @@ impl<T> PyNumberSubProtocolImpl for T
 where
     T: for<'p> PyNumberSubProtocol<'p>,
 {
-    fn nb_subtract() -> Option<ffi::binaryfunc> {
+    default fn nb_subtract() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberSubProtocol,
             T::__sub__,
@@ -799,6 +841,49 @@ where
     }
 }
 
+impl<T> PyNumberSubProtocolImpl for T
+where
+    T: for<'p> PyNumberSubProtocol<'p> + for<'p> PyNumberRSubProtocol<'p>,
+{
+    fn nb_subtract() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberSubProtocol,
+            PyNumberRSubProtocol,
+            T::__sub__,
+            __rsub__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_subtract` for types that implement `__rsub__` but not `__sub__`
+trait PyNumberRSubSlotImpl {
+    fn nb_subtract_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRSubSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_subtract_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRSubSlotImpl for T
+where
+    T: for<'p> PyNumberRSubProtocol<'p>,
+{
+    fn nb_subtract_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRSubProtocol,
+            T::__rsub__,
+            <T as PyNumberRSubProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberMulProtocolImpl {
     fn nb_multiply() -> Option<ffi::binaryfunc>;
 }
@@ -808,7 +893,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_multiply() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRMulSlotImpl>::nb_multiply_reflected()
     }
 }
 
@@ -816,7 +901,7 @@ impl<T> PyNumberMulProtocolImpl for T
 where
     T: for<'p> PyNumberMulProtocol<'p>,
 {
-    fn nb_multiply() -> Option<ffi::binaryfunc> {
+    default fn nb_multiply() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberMulProtocol,
             T::__mul__,
@@ -826,6 +911,49 @@ where
     }
 }
 
+impl<T> PyNumberMulProtocolImpl for T
+where
+    T: for<'p> PyNumberMulProtocol<'p> + for<'p> PyNumberRMulProtocol<'p>,
+{
+    fn nb_multiply() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberMulProtocol,
+            PyNumberRMulProtocol,
+            T::__mul__,
+            __rmul__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_multiply` for types that implement `__rmul__` but not `__mul__`
+trait PyNumberRMulSlotImpl {
+    fn nb_multiply_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRMulSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_multiply_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRMulSlotImpl for T
+where
+    T: for<'p> PyNumberRMulProtocol<'p>,
+{
+    fn nb_multiply_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRMulProtocol,
+            T::__rmul__,
+            <T as PyNumberRMulProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberMatmulProtocolImpl {
     fn nb_matrix_multiply() -> Option<ffi::binaryfunc>;
 }
@@ -835,7 +963,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_matrix_multiply() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRMatmulSlotImpl>::nb_matrix_multiply_reflected()
     }
 }
 
@@ -843,7 +971,7 @@ impl<T> PyNumberMatmulProtocolImpl for T
 where
     T: for<'p> PyNumberMatmulProtocol<'p>,
 {
-    fn nb_matrix_multiply() -> Option<ffi::binaryfunc> {
+    default fn nb_matrix_multiply() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberMatmulProtocol,
             T::__matmul__,
@@ -853,6 +981,49 @@ where
     }
 }
 
+impl<T> PyNumberMatmulProtocolImpl for T
+where
+    T: for<'p> PyNumberMatmulProtocol<'p> + for<'p> PyNumberRMatmulProtocol<'p>,
+{
+    fn nb_matrix_multiply() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberMatmulProtocol,
+            PyNumberRMatmulProtocol,
+            T::__matmul__,
+            __rmatmul__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_matrix_multiply` for types that implement `__rmatmul__` but not `__matmul__`
+trait PyNumberRMatmulSlotImpl {
+    fn nb_matrix_multiply_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRMatmulSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_matrix_multiply_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRMatmulSlotImpl for T
+where
+    T: for<'p> PyNumberRMatmulProtocol<'p>,
+{
+    fn nb_matrix_multiply_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRMatmulProtocol,
+            T::__rmatmul__,
+            <T as PyNumberRMatmulProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberTruedivProtocolImpl {
     fn nb_true_divide() -> Option<ffi::binaryfunc>;
 }
@@ -862,7 +1033,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_true_divide() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRTruedivSlotImpl>::nb_true_divide_reflected()
     }
 }
 
@@ -870,7 +1041,7 @@ impl<T> PyNumberTruedivProtocolImpl for T
 where
     T: for<'p> PyNumberTruedivProtocol<'p>,
 {
-    fn nb_true_divide() -> Option<ffi::binaryfunc> {
+    default fn nb_true_divide() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberTruedivProtocol,
             T::__truediv__,
@@ -880,6 +1051,49 @@ where
     }
 }
 
+impl<T> PyNumberTruedivProtocolImpl for T
+where
+    T: for<'p> PyNumberTruedivProtocol<'p> + for<'p> PyNumberRTruedivProtocol<'p>,
+{
+    fn nb_true_divide() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberTruedivProtocol,
+            PyNumberRTruedivProtocol,
+            T::__truediv__,
+            __rtruediv__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_true_divide` for types that implement `__rtruediv__` but not `__truediv__`
+trait PyNumberRTruedivSlotImpl {
+    fn nb_true_divide_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRTruedivSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_true_divide_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRTruedivSlotImpl for T
+where
+    T: for<'p> PyNumberRTruedivProtocol<'p>,
+{
+    fn nb_true_divide_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRTruedivProtocol,
+            T::__rtruediv__,
+            <T as PyNumberRTruedivProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberFloordivProtocolImpl {
     fn nb_floor_divide() -> Option<ffi::binaryfunc>;
 }
@@ -889,7 +1103,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_floor_divide() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRFloordivSlotImpl>::nb_floor_divide_reflected()
     }
 }
 
@@ -897,7 +1111,7 @@ impl<T> PyNumberFloordivProtocolImpl for T
 where
     T: for<'p> PyNumberFloordivProtocol<'p>,
 {
-    fn nb_floor_divide() -> Option<ffi::binaryfunc> {
+    default fn nb_floor_divide() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberFloordivProtocol,
             T::__floordiv__,
@@ -907,6 +1121,49 @@ where
     }
 }
 
+impl<T> PyNumberFloordivProtocolImpl for T
+where
+    T: for<'p> PyNumberFloordivProtocol<'p> + for<'p> PyNumberRFloordivProtocol<'p>,
+{
+    fn nb_floor_divide() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberFloordivProtocol,
+            PyNumberRFloordivProtocol,
+            T::__floordiv__,
+            __rfloordiv__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_floor_divide` for types that implement `__rfloordiv__` but not `__floordiv__`
+trait PyNumberRFloordivSlotImpl {
+    fn nb_floor_divide_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRFloordivSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_floor_divide_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRFloordivSlotImpl for T
+where
+    T: for<'p> PyNumberRFloordivProtocol<'p>,
+{
+    fn nb_floor_divide_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRFloordivProtocol,
+            T::__rfloordiv__,
+            <T as PyNumberRFloordivProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberModProtocolImpl {
     fn nb_remainder() -> Option<ffi::binaryfunc>;
 }
@@ -916,7 +1173,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_remainder() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRModSlotImpl>::nb_remainder_reflected()
     }
 }
 
@@ -924,7 +1181,7 @@ impl<T> PyNumberModProtocolImpl for T
 where
     T: for<'p> PyNumberModProtocol<'p>,
 {
-    fn nb_remainder() -> Option<ffi::binaryfunc> {
+    default fn nb_remainder() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberModProtocol,
             T::__mod__,
@@ -934,6 +1191,49 @@ where
     }
 }
 
+impl<T> PyNumberModProtocolImpl for T
+where
+    T: for<'p> PyNumberModProtocol<'p> + for<'p> PyNumberRModProtocol<'p>,
+{
+    fn nb_remainder() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberModProtocol,
+            PyNumberRModProtocol,
+            T::__mod__,
+            __rmod__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_remainder` for types that implement `__rmod__` but not `__mod__`
+trait PyNumberRModSlotImpl {
+    fn nb_remainder_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRModSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_remainder_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRModSlotImpl for T
+where
+    T: for<'p> PyNumberRModProtocol<'p>,
+{
+    fn nb_remainder_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRModProtocol,
+            T::__rmod__,
+            <T as PyNumberRModProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberDivmodProtocolImpl {
     fn nb_divmod() -> Option<ffi::binaryfunc>;
 }
@@ -943,7 +1243,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_divmod() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRDivmodSlotImpl>::nb_divmod_reflected()
     }
 }
 
@@ -951,7 +1251,7 @@ impl<T> PyNumberDivmodProtocolImpl for T
 where
     T: for<'p> PyNumberDivmodProtocol<'p>,
 {
-    fn nb_divmod() -> Option<ffi::binaryfunc> {
+    default fn nb_divmod() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberDivmodProtocol,
             T::__divmod__,
@@ -961,6 +1261,49 @@ where
     }
 }
 
+impl<T> PyNumberDivmodProtocolImpl for T
+where
+    T: for<'p> PyNumberDivmodProtocol<'p> + for<'p> PyNumberRDivmodProtocol<'p>,
+{
+    fn nb_divmod() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberDivmodProtocol,
+            PyNumberRDivmodProtocol,
+            T::__divmod__,
+            __rdivmod__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_divmod` for types that implement `__rdivmod__` but not `__divmod__`
+trait PyNumberRDivmodSlotImpl {
+    fn nb_divmod_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRDivmodSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_divmod_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRDivmodSlotImpl for T
+where
+    T: for<'p> PyNumberRDivmodProtocol<'p>,
+{
+    fn nb_divmod_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRDivmodProtocol,
+            T::__rdivmod__,
+            <T as PyNumberRDivmodProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberPowProtocolImpl {
     fn nb_power() -> Option<ffi::ternaryfunc>;
 }
@@ -970,7 +1313,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_power() -> Option<ffi::ternaryfunc> {
-        None
+        <T as PyNumberRPowSlotImpl>::nb_power_reflected()
     }
 }
 
@@ -978,7 +1321,7 @@ impl<T> PyNumberPowProtocolImpl for T
 where
     T: for<'p> PyNumberPowProtocol<'p>,
 {
-    fn nb_power() -> Option<ffi::ternaryfunc> {
+    default fn nb_power() -> Option<ffi::ternaryfunc> {
         py_ternary_num_func!(
             PyNumberPowProtocol,
             T::__pow__,
@@ -988,6 +1331,49 @@ where
     }
 }
 
+impl<T> PyNumberPowProtocolImpl for T
+where
+    T: for<'p> PyNumberPowProtocol<'p> + for<'p> PyNumberRPowProtocol<'p>,
+{
+    fn nb_power() -> Option<ffi::ternaryfunc> {
+        py_ternary_fallback_num_func!(
+            PyNumberPowProtocol,
+            PyNumberRPowProtocol,
+            T::__pow__,
+            __rpow__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_power` for types that implement `__rpow__` but not `__pow__`
+trait PyNumberRPowSlotImpl {
+    fn nb_power_reflected() -> Option<ffi::ternaryfunc>;
+}
+
+impl<'p, T> PyNumberRPowSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_power_reflected() -> Option<ffi::ternaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRPowSlotImpl for T
+where
+    T: for<'p> PyNumberRPowProtocol<'p>,
+{
+    fn nb_power_reflected() -> Option<ffi::ternaryfunc> {
+        py_ternary_reverse_num_func!(
+            PyNumberRPowProtocol,
+            T::__rpow__,
+            <T as PyNumberRPowProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberLShiftProtocolImpl {
     fn nb_lshift() -> Option<ffi::binaryfunc>;
 }
@@ -997,7 +1383,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_lshift() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRLShiftSlotImpl>::nb_lshift_reflected()
     }
 }
 
@@ -1005,7 +1391,7 @@ impl<T> PyNumberLShiftProtocolImpl for T
 where
     T: for<'p> PyNumberLShiftProtocol<'p>,
 {
-    fn nb_lshift() -> Option<ffi::binaryfunc> {
+    default fn nb_lshift() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberLShiftProtocol,
             T::__lshift__,
@@ -1015,6 +1401,49 @@ where
     }
 }
 
+impl<T> PyNumberLShiftProtocolImpl for T
+where
+    T: for<'p> PyNumberLShiftProtocol<'p> + for<'p> PyNumberRLShiftProtocol<'p>,
+{
+    fn nb_lshift() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberLShiftProtocol,
+            PyNumberRLShiftProtocol,
+            T::__lshift__,
+            __rlshift__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_lshift` for types that implement `__rlshift__` but not `__lshift__`
+trait PyNumberRLShiftSlotImpl {
+    fn nb_lshift_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRLShiftSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_lshift_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRLShiftSlotImpl for T
+where
+    T: for<'p> PyNumberRLShiftProtocol<'p>,
+{
+    fn nb_lshift_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRLShiftProtocol,
+            T::__rlshift__,
+            <T as PyNumberRLShiftProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberRShiftProtocolImpl {
     fn nb_rshift() -> Option<ffi::binaryfunc>;
 }
@@ -1024,7 +1453,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_rshift() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRRShiftSlotImpl>::nb_rshift_reflected()
     }
 }
 
@@ -1032,7 +1461,7 @@ impl<T> PyNumberRShiftProtocolImpl for T
 where
     T: for<'p> PyNumberRShiftProtocol<'p>,
 {
-    fn nb_rshift() -> Option<ffi::binaryfunc> {
+    default fn nb_rshift() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberRShiftProtocol,
             T::__rshift__,
@@ -1042,6 +1471,49 @@ where
     }
 }
 
+impl<T> PyNumberRShiftProtocolImpl for T
+where
+    T: for<'p> PyNumberRShiftProtocol<'p> + for<'p> PyNumberRRShiftProtocol<'p>,
+{
+    fn nb_rshift() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberRShiftProtocol,
+            PyNumberRRShiftProtocol,
+            T::__rshift__,
+            __rrshift__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_rshift` for types that implement `__rrshift__` but not `__rshift__`
+trait PyNumberRRShiftSlotImpl {
+    fn nb_rshift_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRRShiftSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_rshift_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRRShiftSlotImpl for T
+where
+    T: for<'p> PyNumberRRShiftProtocol<'p>,
+{
+    fn nb_rshift_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRRShiftProtocol,
+            T::__rrshift__,
+            <T as PyNumberRRShiftProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberAndProtocolImpl {
     fn nb_and() -> Option<ffi::binaryfunc>;
 }
@@ -1051,7 +1523,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_and() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRAndSlotImpl>::nb_and_reflected()
     }
 }
 
@@ -1059,7 +1531,7 @@ impl<T> PyNumberAndProtocolImpl for T
 where
     T: for<'p> PyNumberAndProtocol<'p>,
 {
-    fn nb_and() -> Option<ffi::binaryfunc> {
+    default fn nb_and() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberAndProtocol,
             T::__and__,
@@ -1069,6 +1541,49 @@ where
     }
 }
 
+impl<T> PyNumberAndProtocolImpl for T
+where
+    T: for<'p> PyNumberAndProtocol<'p> + for<'p> PyNumberRAndProtocol<'p>,
+{
+    fn nb_and() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberAndProtocol,
+            PyNumberRAndProtocol,
+            T::__and__,
+            __rand__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_and` for types that implement `__rand__` but not `__and__`
+trait PyNumberRAndSlotImpl {
+    fn nb_and_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRAndSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_and_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRAndSlotImpl for T
+where
+    T: for<'p> PyNumberRAndProtocol<'p>,
+{
+    fn nb_and_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRAndProtocol,
+            T::__rand__,
+            <T as PyNumberRAndProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberXorProtocolImpl {
     fn nb_xor() -> Option<ffi::binaryfunc>;
 }
@@ -1078,7 +1593,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_xor() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberRXorSlotImpl>::nb_xor_reflected()
     }
 }
 
@@ -1086,7 +1601,7 @@ impl<T> PyNumberXorProtocolImpl for T
 where
     T: for<'p> PyNumberXorProtocol<'p>,
 {
-    fn nb_xor() -> Option<ffi::binaryfunc> {
+    default fn nb_xor() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberXorProtocol,
             T::__xor__,
@@ -1096,6 +1611,49 @@ where
     }
 }
 
+impl<T> PyNumberXorProtocolImpl for T
+where
+    T: for<'p> PyNumberXorProtocol<'p> + for<'p> PyNumberRXorProtocol<'p>,
+{
+    fn nb_xor() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberXorProtocol,
+            PyNumberRXorProtocol,
+            T::__xor__,
+            __rxor__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_xor` for types that implement `__rxor__` but not `__xor__`
+trait PyNumberRXorSlotImpl {
+    fn nb_xor_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberRXorSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_xor_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberRXorSlotImpl for T
+where
+    T: for<'p> PyNumberRXorProtocol<'p>,
+{
+    fn nb_xor_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberRXorProtocol,
+            T::__rxor__,
+            <T as PyNumberRXorProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberOrProtocolImpl {
     fn nb_or() -> Option<ffi::binaryfunc>;
 }
@@ -1105,7 +1663,7 @@ where
     T: PyNumberProtocol<'p>,
 {
     default fn nb_or() -> Option<ffi::binaryfunc> {
-        None
+        <T as PyNumberROrSlotImpl>::nb_or_reflected()
     }
 }
 
@@ -1113,7 +1671,7 @@ impl<T> PyNumberOrProtocolImpl for T
 where
     T: for<'p> PyNumberOrProtocol<'p>,
 {
-    fn nb_or() -> Option<ffi::binaryfunc> {
+    default fn nb_or() -> Option<ffi::binaryfunc> {
         py_binary_num_func!(
             PyNumberOrProtocol,
             T::__or__,
@@ -1123,6 +1681,49 @@ where
     }
 }
 
+impl<T> PyNumberOrProtocolImpl for T
+where
+    T: for<'p> PyNumberOrProtocol<'p> + for<'p> PyNumberROrProtocol<'p>,
+{
+    fn nb_or() -> Option<ffi::binaryfunc> {
+        py_binary_fallback_num_func!(
+            PyNumberOrProtocol,
+            PyNumberROrProtocol,
+            T::__or__,
+            __ror__,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
+/// Fills `nb_or` for types that implement `__ror__` but not `__or__`
+trait PyNumberROrSlotImpl {
+    fn nb_or_reflected() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PyNumberROrSlotImpl for T
+where
+    T: PyNumberProtocol<'p>,
+{
+    default fn nb_or_reflected() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PyNumberROrSlotImpl for T
+where
+    T: for<'p> PyNumberROrProtocol<'p>,
+{
+    fn nb_or_reflected() -> Option<ffi::binaryfunc> {
+        py_binary_reverse_num_func!(
+            PyNumberROrProtocol,
+            T::__ror__,
+            <T as PyNumberROrProtocol>::Success,
+            PyObjectCallbackConverter
+        )
+    }
+}
+
 trait PyNumberIAddProtocolImpl {
     fn nb_inplace_add() -> Option<ffi::binaryfunc>;
 }
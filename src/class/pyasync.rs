@@ -12,14 +12,14 @@ use crate::callback::PyObjectCallbackConverter;
 use crate::class::methods::PyMethodDef;
 use crate::err::PyResult;
 use crate::ffi;
-use crate::type_object::PyTypeInfo;
+use crate::pyclass::PyClass;
 use crate::PyObject;
 
 /// Python Async/Await support interface.
 ///
 /// Each method in this trait corresponds to Python async/await implementation.
 #[allow(unused_variables)]
-pub trait PyAsyncProtocol<'p>: PyTypeInfo {
+pub trait PyAsyncProtocol<'p>: PyClass {
     fn __await__(&'p self) -> Self::Result
     where
         Self: PyAsyncAwaitProtocol<'p>,
@@ -244,13 +244,16 @@ mod anext {
                 PyAsyncAnextProtocol,
                 T::__anext__,
                 Option<T::Success>,
-                IterANextResultConverter
+                IterANextResultConverter,
+                *mut ffi::PyObject,
+                try_borrow_mut
             )
         }
     }
 }
 
-trait PyAsyncAenterProtocolImpl {
+#[doc(hidden)]
+pub trait PyAsyncAenterProtocolImpl {
     fn __aenter__() -> Option<PyMethodDef>;
 }
 
@@ -263,7 +266,8 @@ where
     }
 }
 
-trait PyAsyncAexitProtocolImpl {
+#[doc(hidden)]
+pub trait PyAsyncAexitProtocolImpl {
     fn __aexit__() -> Option<PyMethodDef>;
 }
 
@@ -3,20 +3,23 @@
 //! Python Sequence Interface
 //! Trait and support implementation for implementing sequence
 
-use crate::callback::{BoolCallbackConverter, LenResultConverter, PyObjectCallbackConverter};
+use crate::callback::{
+    restore_err, BoolCallbackConverter, LenResultConverter, PyObjectCallbackConverter,
+};
 use crate::err::{PyErr, PyResult};
 use crate::ffi;
 use crate::objectprotocol::ObjectProtocol;
-use crate::type_object::PyTypeInfo;
+use crate::pyclass::{PyClass, PyClassShell};
 use crate::types::PyAny;
 use crate::FromPyObject;
+use crate::FromPyPointer;
 use crate::Python;
 use crate::{exceptions, IntoPy, PyObject};
 use std::os::raw::c_int;
 
 /// Sequence interface
 #[allow(unused_variables)]
-pub trait PySequenceProtocol<'p>: PyTypeInfo + Sized {
+pub trait PySequenceProtocol<'p>: PyClass + Sized {
     fn __len__(&'p self) -> Self::Result
     where
         Self: PySequenceLenProtocol<'p>,
@@ -267,7 +270,7 @@ mod sq_ass_item_impl {
             {
                 let py = Python::assume_gil_acquired();
                 let _pool = crate::GILPool::new(py);
-                let slf = py.mut_from_borrowed_ptr::<T>(slf);
+                let mut slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf).try_borrow_mut();
 
                 let result = if value.is_null() {
                     Err(PyErr::new::<exceptions::NotImplementedError, _>(format!(
@@ -276,16 +279,19 @@ mod sq_ass_item_impl {
                     )))
                 } else {
                     let value = py.from_borrowed_ptr::<PyAny>(value);
-                    match value.extract() {
-                        Ok(value) => slf.__setitem__(key.into(), value).into(),
-                        Err(e) => Err(e),
+                    match slf {
+                        Ok(ref mut slf) => match value.extract() {
+                            Ok(value) => slf.__setitem__(key.into(), value).into(),
+                            Err(e) => Err(e),
+                        },
+                        Err(e) => Err(e.into()),
                     }
                 };
 
                 match result {
                     Ok(_) => 0,
                     Err(e) => {
-                        e.restore(py);
+                        restore_err(py, e);
                         -1
                     }
                 }
@@ -322,10 +328,13 @@ mod sq_ass_item_impl {
             {
                 let py = Python::assume_gil_acquired();
                 let _pool = crate::GILPool::new(py);
-                let slf = py.mut_from_borrowed_ptr::<T>(slf);
+                let mut slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf).try_borrow_mut();
 
                 let result = if value.is_null() {
-                    slf.__delitem__(key.into()).into()
+                    match slf {
+                        Ok(ref mut slf) => slf.__delitem__(key.into()).into(),
+                        Err(e) => Err(e.into()),
+                    }
                 } else {
                     Err(PyErr::new::<exceptions::NotImplementedError, _>(format!(
                         "Item assignment not supported by {:?}",
@@ -336,7 +345,7 @@ mod sq_ass_item_impl {
                 match result {
                     Ok(_) => 0,
                     Err(e) => {
-                        e.restore(py);
+                        restore_err(py, e);
                         -1
                     }
                 }
@@ -373,21 +382,26 @@ mod sq_ass_item_impl {
             {
                 let py = Python::assume_gil_acquired();
                 let _pool = crate::GILPool::new(py);
-                let slf = py.mut_from_borrowed_ptr::<T>(slf);
-
-                let result = if value.is_null() {
-                    slf.__delitem__(key.into()).into()
-                } else {
-                    let value = py.from_borrowed_ptr::<PyAny>(value);
-                    match value.extract() {
-                        Ok(value) => slf.__setitem__(key.into(), value).into(),
-                        Err(e) => Err(e),
+                let mut slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf).try_borrow_mut();
+
+                let result = match slf {
+                    Ok(ref mut slf) => {
+                        if value.is_null() {
+                            slf.__delitem__(key.into()).into()
+                        } else {
+                            let value = py.from_borrowed_ptr::<PyAny>(value);
+                            match value.extract() {
+                                Ok(value) => slf.__setitem__(key.into(), value).into(),
+                                Err(e) => Err(e),
+                            }
+                        }
                     }
+                    Err(e) => Err(e.into()),
                 };
                 match result {
                     Ok(_) => 0,
                     Err(e) => {
-                        e.restore(py);
+                        restore_err(py, e);
                         -1
                     }
                 }
@@ -501,7 +515,9 @@ where
             PySequenceInplaceConcatProtocol,
             T::__inplace_concat__,
             T,
-            PyObjectCallbackConverter
+            PyObjectCallbackConverter,
+            *mut ffi::PyObject,
+            try_borrow_mut
         )
     }
 }
@@ -528,7 +544,8 @@ where
             PySequenceInplaceRepeatProtocol,
             T::__inplace_repeat__,
             T,
-            PyObjectCallbackConverter
+            PyObjectCallbackConverter,
+            try_borrow_mut
         )
     }
 }
@@ -0,0 +1,88 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Conversion between a `Vec` of structs and a dict of columns.
+//!
+//! Libraries like pandas build a dataframe much faster from a dict of lists, one per column,
+//! than from a list of objects, one per row. `#[derive(Columnar)]` implements [`Columnar`] for a
+//! struct with named fields, converting every field to a column:
+//!
+//! ```
+//! use pyo3::columnar::Columnar;
+//! use pyo3::prelude::*;
+//!
+//! #[derive(Columnar)]
+//! struct Measurement {
+//!     station: String,
+//!     temperature: f64,
+//! }
+//!
+//! # fn main() -> PyResult<()> {
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let rows = vec![
+//!     Measurement { station: "north".to_string(), temperature: 3.5 },
+//!     Measurement { station: "south".to_string(), temperature: 12.0 },
+//! ];
+//!
+//! let columns = Measurement::to_columns(py, &rows)?;
+//! let temperatures: Vec<f64> = columns.get_item("temperature").unwrap().extract()?;
+//! assert_eq!(temperatures, vec![3.5, 12.0]);
+//!
+//! let rows = Measurement::from_columns(columns)?;
+//! assert_eq!(rows[1].station, "south");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Columnar`]: trait.Columnar.html
+
+use crate::exceptions::{KeyError, ValueError};
+use crate::types::PyDict;
+use crate::{FromPyObject, ObjectProtocol, PyResult, Python};
+
+pub use pyo3cls::Columnar;
+
+/// A row type that can be converted to and from columns.
+///
+/// Use `#[derive(Columnar)]` to implement this trait.
+pub trait Columnar: Sized {
+    /// The names of the columns, which are the names of the fields.
+    const COLUMNS: &'static [&'static str];
+
+    /// Converts `rows` to a dict mapping the name of each column to a list of its values.
+    ///
+    /// Every list is allocated once with its final length.
+    fn to_columns<'p>(py: Python<'p>, rows: &[Self]) -> PyResult<&'p PyDict>;
+
+    /// Converts a dict of columns back to rows.
+    ///
+    /// The values of a column can be any sequence, including objects supporting the buffer
+    /// protocol like numpy arrays. A missing column raises a `KeyError`, and columns with
+    /// different lengths raise a `ValueError`.
+    fn from_columns(columns: &PyDict) -> PyResult<Vec<Self>>;
+}
+
+/// Extracts the column `name` of `columns`. `len` is the length of the columns extracted before,
+/// which this column must have as well.
+#[doc(hidden)]
+pub fn extract_column<T>(columns: &PyDict, name: &str, len: &mut Option<usize>) -> PyResult<Vec<T>>
+where
+    for<'a> T: FromPyObject<'a>,
+{
+    let column = columns
+        .get_item(name)
+        .ok_or_else(|| KeyError::py_err(name.to_string()))?;
+    let values: Vec<T> = column.extract()?;
+    match *len {
+        Some(len) if len != values.len() => Err(ValueError::py_err(format!(
+            "column '{}' has {} values, but the columns before it have {}",
+            name,
+            values.len(),
+            len
+        ))),
+        _ => {
+            *len = Some(values.len());
+            Ok(values)
+        }
+    }
+}
@@ -1,14 +1,20 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
 //! Conversions between various states of rust and python types and their wrappers.
+#[cfg(feature = "convert-trace")]
+pub(crate) use crate::convert_trace::ConversionKind;
 use crate::err::{self, PyDowncastError, PyResult};
+use crate::instance::PyNativeType;
 use crate::object::PyObject;
-use crate::type_object::{PyObjectLayout, PyTypeInfo};
+use crate::pyclass::{PyClass, PyClassShell};
+use crate::type_object::PyTypeInfo;
 use crate::types::PyAny;
 use crate::types::PyTuple;
-use crate::{ffi, gil, Py, Python};
+use crate::{ffi, gil, AsPyRef, Py, Python};
 use std::ptr::NonNull;
 
+pub use pyo3cls::FromPyObject;
+
 /// This trait represents that, **we can do zero-cost conversion from the object to FFI pointer**.
 ///
 /// This trait is implemented for types that internally wrap a pointer to a python object.
@@ -77,6 +83,26 @@ where
     }
 }
 
+/// The direction of a conversion, see `convert_trace::ConversionKind`.
+#[cfg(not(feature = "convert-trace"))]
+#[derive(Clone, Copy)]
+pub(crate) enum ConversionKind {
+    ToPython,
+    FromPython,
+}
+
+/// Reports the conversion of a `T` from or to `object` to the hook of the `convert-trace`
+/// feature.
+#[cfg(feature = "convert-trace")]
+#[inline]
+pub(crate) fn trace_conversion<T: ?Sized>(kind: ConversionKind, object: &PyAny) {
+    crate::convert_trace::trace(kind, std::any::type_name::<T>(), object)
+}
+
+#[cfg(not(feature = "convert-trace"))]
+#[inline]
+pub(crate) fn trace_conversion<T: ?Sized>(_kind: ConversionKind, _object: &PyAny) {}
+
 /// Conversion trait that allows various objects to be converted into `PyObject`
 pub trait ToPyObject {
     /// Converts self into a Python object.
@@ -100,6 +126,7 @@ pub trait ToBorrowedObject: ToPyObject {
         F: FnOnce(*mut ffi::PyObject) -> R;
 }
 
+#[cfg(not(feature = "stable"))]
 impl<T> ToBorrowedObject for T
 where
     T: ToPyObject,
@@ -108,7 +135,9 @@ where
     where
         F: FnOnce(*mut ffi::PyObject) -> R,
     {
-        let ptr = self.to_object(py).into_ptr();
+        let obj = self.to_object(py);
+        trace_conversion::<T>(ConversionKind::ToPython, obj.as_ref(py));
+        let ptr = obj.into_ptr();
         let result = f(ptr);
         unsafe {
             ffi::Py_XDECREF(ptr);
@@ -117,6 +146,29 @@ where
     }
 }
 
+/// Without specialization, Python objects are converted like everything else, which costs an
+/// increment and a decrement of the reference count.
+#[cfg(feature = "stable")]
+impl<T> ToBorrowedObject for T
+where
+    T: ToPyObject,
+{
+    fn with_borrowed_ptr<F, R>(&self, py: Python, f: F) -> R
+    where
+        F: FnOnce(*mut ffi::PyObject) -> R,
+    {
+        let obj = self.to_object(py);
+        trace_conversion::<T>(ConversionKind::ToPython, obj.as_ref(py));
+        let ptr = obj.into_ptr();
+        let result = f(ptr);
+        unsafe {
+            ffi::Py_XDECREF(ptr);
+        }
+        result
+    }
+}
+
+#[cfg(not(feature = "stable"))]
 impl<T> ToBorrowedObject for T
 where
     T: ToPyObject + AsPyPointer,
@@ -125,6 +177,12 @@ where
     where
         F: FnOnce(*mut ffi::PyObject) -> R,
     {
+        // `from_borrowed_ptr` registers the object in the release pool, so the `&PyAny` is only
+        // created for the hook
+        #[cfg(feature = "convert-trace")]
+        trace_conversion::<T>(ConversionKind::Borrowed, unsafe {
+            _py.from_borrowed_ptr(self.as_ptr())
+        });
         f(self.as_ptr())
     }
 }
@@ -254,15 +312,14 @@ pub mod extract_impl {
 
     pub struct Cloned;
     pub struct Reference;
-    pub struct MutReference;
 
     impl<'a, T: 'a> ExtractImpl<'a, T> for Cloned
     where
-        T: Clone,
-        Reference: ExtractImpl<'a, &'a T>,
+        T: PyClass + Clone,
     {
         fn extract(source: &'a PyAny) -> PyResult<T> {
-            Ok(Reference::extract(source)?.clone())
+            let shell = <PyClassShell<T> as PyTryFrom>::try_from(source)?;
+            Ok(shell.try_borrow()?.clone())
         }
     }
 
@@ -274,15 +331,6 @@ pub mod extract_impl {
             Ok(T::try_from(source)?)
         }
     }
-
-    impl<'a, T> ExtractImpl<'a, &'a mut T> for MutReference
-    where
-        T: PyTryFrom<'a>,
-    {
-        fn extract(source: &'a PyAny) -> PyResult<&'a mut T> {
-            Ok(T::try_from_mut(source)?)
-        }
-    }
 }
 
 use extract_impl::ExtractImpl;
@@ -294,9 +342,10 @@ pub trait FromPyObjectImpl {
     // Implement this trait with to specify the implementor of `extract_impl::ExtractImpl` to use for
     // extracting this type from Python objects.
     //
-    // Example valid implementations are `extract_impl::Cloned`, `extract_impl::Reference`, and
-    // `extract_impl::MutReference`, which are for extracting `T`, `&T` and `&mut T` respectively via
-    // PyTryFrom.
+    // Example valid implementations are `extract_impl::Cloned` and `extract_impl::Reference`,
+    // which are for extracting a `#[pyclass]` `T` by cloning a borrow of it and `&T` of a native
+    // type via PyTryFrom respectively. References to the contents of a `#[pyclass]` can't be
+    // extracted, as they would bypass its borrow checks; use `PyRef<T>` or `PyRefMut<T>`.
     //
     // We deliberately don't require Impl: ExtractImpl here because we allow #[pyclass]
     // to specify an Impl which doesn't satisfy the ExtractImpl constraints.
@@ -345,16 +394,13 @@ pub trait PyTryInto<T>: Sized {
 
     /// Cast from PyObject to a concrete Python object type. With exact type check.
     fn try_into_exact(&self) -> Result<&T, PyDowncastError>;
-
-    /// Cast from PyObject to a concrete Python object type.
-    fn try_into_mut(&self) -> Result<&mut T, PyDowncastError>;
-
-    /// Cast from PyObject to a concrete Python object type. With exact type check.
-    fn try_into_mut_exact(&self) -> Result<&mut T, PyDowncastError>;
 }
 
 /// Trait implemented by Python object types that allow a checked downcast.
 /// This trait is similar to `std::convert::TryFrom`
+///
+/// It's implemented for native types and for `PyClassShell<T>` of a `#[pyclass]` `T`, whose
+/// contents are then borrowed with `try_borrow` or `try_borrow_mut`.
 pub trait PyTryFrom<'v>: Sized {
     /// Cast from a concrete Python object type to PyObject.
     fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError>;
@@ -362,20 +408,9 @@ pub trait PyTryFrom<'v>: Sized {
     /// Cast from a concrete Python object type to PyObject. With exact type check.
     fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError>;
 
-    /// Cast from a concrete Python object type to PyObject.
-    fn try_from_mut<V: Into<&'v PyAny>>(value: V) -> Result<&'v mut Self, PyDowncastError>;
-
-    /// Cast from a concrete Python object type to PyObject. With exact type check.
-    fn try_from_mut_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v mut Self, PyDowncastError>;
-
     /// Cast a PyAny to a specific type of PyObject. The caller must
     /// have already verified the reference is for this type.
     unsafe fn try_from_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v Self;
-
-    /// Cast a PyAny to a specific type of PyObject. The caller must
-    /// have already verified the reference is for this type.
-    #[allow(clippy::mut_from_ref)]
-    unsafe fn try_from_mut_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v mut Self;
 }
 
 // TryFrom implies TryInto
@@ -389,17 +424,11 @@ where
     fn try_into_exact(&self) -> Result<&U, PyDowncastError> {
         U::try_from_exact(self)
     }
-    fn try_into_mut(&self) -> Result<&mut U, PyDowncastError> {
-        U::try_from_mut(self)
-    }
-    fn try_into_mut_exact(&self) -> Result<&mut U, PyDowncastError> {
-        U::try_from_mut_exact(self)
-    }
 }
 
 impl<'v, T> PyTryFrom<'v> for T
 where
-    T: PyTypeInfo,
+    T: PyTypeInfo + PyNativeType,
 {
     fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v T, PyDowncastError> {
         let value = value.into();
@@ -407,7 +436,7 @@ where
             if T::is_instance(value) {
                 Ok(PyTryFrom::try_from_unchecked(value))
             } else {
-                Err(PyDowncastError)
+                Err(PyDowncastError::new(value, T::NAME))
             }
         }
     }
@@ -418,81 +447,105 @@ where
             if T::is_exact_instance(value) {
                 Ok(PyTryFrom::try_from_unchecked(value))
             } else {
-                Err(PyDowncastError)
+                Err(PyDowncastError::new(value, T::NAME))
             }
         }
     }
 
-    fn try_from_mut<V: Into<&'v PyAny>>(value: V) -> Result<&'v mut T, PyDowncastError> {
+    #[inline]
+    unsafe fn try_from_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v T {
+        let value = value.into();
+        &*(value as *const PyAny as *const T)
+    }
+}
+
+impl<'v, T> PyTryFrom<'v> for PyClassShell<T>
+where
+    T: PyClass,
+{
+    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError> {
         let value = value.into();
         unsafe {
             if T::is_instance(value) {
-                Ok(PyTryFrom::try_from_mut_unchecked(value))
+                Ok(PyTryFrom::try_from_unchecked(value))
             } else {
-                Err(PyDowncastError)
+                Err(PyDowncastError::new(value, T::NAME))
             }
         }
     }
 
-    fn try_from_mut_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v mut T, PyDowncastError> {
+    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError> {
         let value = value.into();
         unsafe {
             if T::is_exact_instance(value) {
-                Ok(PyTryFrom::try_from_mut_unchecked(value))
+                Ok(PyTryFrom::try_from_unchecked(value))
             } else {
-                Err(PyDowncastError)
+                Err(PyDowncastError::new(value, T::NAME))
             }
         }
     }
 
     #[inline]
-    unsafe fn try_from_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v T {
-        let value = value.into();
-        T::ConcreteLayout::internal_ref_cast(value)
-    }
-
-    #[inline]
-    unsafe fn try_from_mut_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v mut T {
+    unsafe fn try_from_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v Self {
         let value = value.into();
-        T::ConcreteLayout::internal_mut_cast(value)
+        &*(value.as_ptr() as *const Self)
     }
 }
 
 /// Converts `()` to an empty Python tuple.
 impl FromPy<()> for Py<PyTuple> {
-    fn from_py(_: (), py: Python) -> Py<PyTuple> {
-        Py::from_py(PyTuple::empty(py), py)
+    fn from_py(_: (), _py: Python) -> Py<PyTuple> {
+        // `PyTuple_New(0)` returns CPython's empty tuple singleton
+        unsafe { Py::from_owned_ptr_or_panic(ffi::PyTuple_New(0)) }
     }
 }
 
 /// Raw level conversion between `*mut ffi::PyObject` and PyO3 types.
+///
+/// The `owned` variants take over a new reference, as returned by most FFI functions, and the
+/// `borrowed` variants increase the reference count of a borrowed reference. For `&'p T`, the
+/// reference is stored in the release pool of the current `GILPool`.
+///
+/// All variants differ only in how a `null` pointer is handled:
+/// the plain and `_or_panic` variants panic, `_or_err` fetches the current Python exception and
+/// `_or_opt` returns `None`.
+///
+/// All of them are unsafe because the pointer is not type checked.
 pub unsafe trait FromPyPointer<'p>: Sized {
+    /// Converts an owned pointer, returns `None` if the pointer is `null`.
     unsafe fn from_owned_ptr_or_opt(py: Python<'p>, ptr: *mut ffi::PyObject) -> Option<Self>;
+    /// Converts an owned pointer, panics if the pointer is `null`.
     unsafe fn from_owned_ptr_or_panic(py: Python<'p>, ptr: *mut ffi::PyObject) -> Self {
         match Self::from_owned_ptr_or_opt(py, ptr) {
             Some(s) => s,
             None => err::panic_after_error(),
         }
     }
+    /// Converts an owned pointer, panics if the pointer is `null`.
     unsafe fn from_owned_ptr(py: Python<'p>, ptr: *mut ffi::PyObject) -> Self {
         Self::from_owned_ptr_or_panic(py, ptr)
     }
+    /// Converts an owned pointer, returns the current exception if the pointer is `null`.
     unsafe fn from_owned_ptr_or_err(py: Python<'p>, ptr: *mut ffi::PyObject) -> PyResult<Self> {
         match Self::from_owned_ptr_or_opt(py, ptr) {
             Some(s) => Ok(s),
             None => Err(err::PyErr::fetch(py)),
         }
     }
+    /// Converts a borrowed pointer, returns `None` if the pointer is `null`.
     unsafe fn from_borrowed_ptr_or_opt(py: Python<'p>, ptr: *mut ffi::PyObject) -> Option<Self>;
+    /// Converts a borrowed pointer, panics if the pointer is `null`.
     unsafe fn from_borrowed_ptr_or_panic(py: Python<'p>, ptr: *mut ffi::PyObject) -> Self {
         match Self::from_borrowed_ptr_or_opt(py, ptr) {
             Some(s) => s,
             None => err::panic_after_error(),
         }
     }
+    /// Converts a borrowed pointer, panics if the pointer is `null`.
     unsafe fn from_borrowed_ptr(py: Python<'p>, ptr: *mut ffi::PyObject) -> Self {
         Self::from_borrowed_ptr_or_panic(py, ptr)
     }
+    /// Converts a borrowed pointer, returns the current exception if the pointer is `null`.
     unsafe fn from_borrowed_ptr_or_err(py: Python<'p>, ptr: *mut ffi::PyObject) -> PyResult<Self> {
         match Self::from_borrowed_ptr_or_opt(py, ptr) {
             Some(s) => Ok(s),
@@ -503,34 +556,21 @@ pub unsafe trait FromPyPointer<'p>: Sized {
 
 unsafe impl<'p, T> FromPyPointer<'p> for &'p T
 where
-    T: PyTypeInfo,
-{
-    unsafe fn from_owned_ptr_or_opt(py: Python<'p>, ptr: *mut ffi::PyObject) -> Option<Self> {
-        NonNull::new(ptr).map(|p| T::ConcreteLayout::internal_ref_cast(gil::register_owned(py, p)))
-    }
-    unsafe fn from_borrowed_ptr_or_opt(py: Python<'p>, ptr: *mut ffi::PyObject) -> Option<Self> {
-        NonNull::new(ptr)
-            .map(|p| T::ConcreteLayout::internal_ref_cast(gil::register_borrowed(py, p)))
-    }
-}
-
-unsafe impl<'p, T> FromPyPointer<'p> for &'p mut T
-where
-    T: PyTypeInfo,
+    T: PyTypeInfo + PyNativeType,
 {
     unsafe fn from_owned_ptr_or_opt(py: Python<'p>, ptr: *mut ffi::PyObject) -> Option<Self> {
-        NonNull::new(ptr).map(|p| T::ConcreteLayout::internal_mut_cast(gil::register_owned(py, p)))
+        NonNull::new(ptr).map(|p| T::try_from_unchecked(gil::register_owned(py, p)))
     }
     unsafe fn from_borrowed_ptr_or_opt(py: Python<'p>, ptr: *mut ffi::PyObject) -> Option<Self> {
-        NonNull::new(ptr)
-            .map(|p| T::ConcreteLayout::internal_mut_cast(gil::register_borrowed(py, p)))
+        NonNull::new(ptr).map(|p| T::try_from_unchecked(gil::register_borrowed(py, p)))
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::types::PyList;
-    use crate::Python;
+    use crate::exceptions::TypeError;
+    use crate::types::{PyDict, PyList, PySequence};
+    use crate::{AsPyRef, IntoPy, PyErr, PyObject, Python};
 
     use super::PyTryFrom;
 
@@ -542,4 +582,29 @@ mod test {
         let val = unsafe { <PyList as PyTryFrom>::try_from_unchecked(list.as_ref()) };
         assert_eq!(list, val);
     }
+
+    #[test]
+    fn test_downcast_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = py.eval("1", None, None).unwrap();
+
+        let err = obj.downcast::<PyDict>().unwrap_err();
+        assert_eq!(err.from_type_name(), "int");
+        assert_eq!(err.to_type_name(), "PyDict");
+        let err = <PySequence as PyTryFrom>::try_from(obj).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "'int' object cannot be converted to 'Sequence'"
+        );
+        assert_eq!(err.clone(), err);
+
+        let err = PyErr::from(err);
+        assert!(err.is_instance::<TypeError>(py));
+        let value: PyObject = err.into_py(py);
+        assert_eq!(
+            value.as_ref(py).to_string(),
+            "'int' object cannot be converted to 'Sequence'"
+        );
+    }
 }
@@ -0,0 +1,219 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Tracing of conversions between Rust and Python, enabled with the `convert-trace` feature.
+//!
+//! The hook set with [`set_hook`] is called for every conversion done by pyo3: extracting the
+//! arguments of functions and methods and values with `extract()`, converting return values with
+//! `IntoPy`, and converting the arguments of Python APIs like `call` or `set_item` with
+//! `ToPyObject`. Values that already are Python objects are passed to Python without a
+//! conversion, which the hook sees as `ConversionKind::Borrowed`.
+//!
+//! The hook is called where pyo3 converts values, not inside the `ToPyObject`, `IntoPy` and
+//! `FromPyObject` implementations. Calling `to_object` or `into_py` directly isn't traced, and
+//! neither are the conversions of the elements of containers like `Vec<T>` to Python.
+//!
+//! Counting the conversions by type shows which ones dominate a workload, and whether the
+//! zero-copy paths, e.g. extracting `&str` instead of `String`, are taken:
+//!
+//! ```
+//! use pyo3::convert_trace::{self, Conversion, ConversionKind};
+//! use pyo3::prelude::*;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! static EXTRACTED_STRINGS: AtomicUsize = AtomicUsize::new(0);
+//!
+//! fn count(conversion: &Conversion) {
+//!     if conversion.kind == ConversionKind::FromPython && conversion.rust_type.ends_with("String") {
+//!         EXTRACTED_STRINGS.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! convert_trace::set_hook(Some(count));
+//! let _: String = py.eval("'a' * 3", None, None).unwrap().extract().unwrap();
+//! convert_trace::set_hook(None);
+//! assert_eq!(EXTRACTED_STRINGS.load(Ordering::Relaxed), 1);
+//! ```
+//!
+//! Without the feature, pyo3 doesn't check for a hook at all. With it, a conversion costs an
+//! additional atomic load while no hook is set.
+//!
+//! [`set_hook`]: fn.set_hook.html
+
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::PyAny;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The direction of a conversion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConversionKind {
+    /// A Rust value converted to a new Python object.
+    ToPython,
+    /// A Python object passed to Python as it is, without a conversion.
+    Borrowed,
+    /// A Python object extracted to a Rust value. The extraction may fail.
+    FromPython,
+}
+
+/// A conversion passed to the hook.
+#[derive(Clone, Copy, Debug)]
+pub struct Conversion<'a> {
+    pub kind: ConversionKind,
+    /// The name of the Rust type, as returned by `std::any::type_name`.
+    pub rust_type: &'static str,
+    /// The Python object that was created or passed, or that is being extracted.
+    pub object: &'a PyAny,
+}
+
+impl<'a> Conversion<'a> {
+    /// The name of the type of the Python object, e.g. `str`.
+    pub fn python_type(&self) -> Cow<'a, str> {
+        self.object.get_type().name()
+    }
+}
+
+/// A function called for each conversion.
+pub type Hook = fn(&Conversion);
+
+static HOOK: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static IN_HOOK: Cell<bool> = Cell::new(false);
+}
+
+/// Sets the hook called for every conversion, or removes it with `None`, and returns the
+/// previous hook.
+///
+/// The hook is global and called on all threads. Conversions done by the hook itself are not
+/// traced.
+pub fn set_hook(hook: Option<Hook>) -> Option<Hook> {
+    let previous = HOOK.swap(hook.map_or(0, |hook| hook as usize), Ordering::AcqRel);
+    to_hook(previous)
+}
+
+fn to_hook(address: usize) -> Option<Hook> {
+    if address == 0 {
+        None
+    } else {
+        // Only ever set from a `Hook` in `set_hook`
+        Some(unsafe { mem::transmute::<usize, Hook>(address) })
+    }
+}
+
+/// Calls the hook, if there is one and it isn't already running on this thread.
+pub(crate) fn trace(kind: ConversionKind, rust_type: &'static str, object: &PyAny) {
+    let hook = match to_hook(HOOK.load(Ordering::Acquire)) {
+        Some(hook) => hook,
+        None => return,
+    };
+    if IN_HOOK.with(|in_hook| in_hook.replace(true)) {
+        return;
+    }
+    let _guard = HookGuard;
+    hook(&Conversion {
+        kind,
+        rust_type,
+        object,
+    });
+}
+
+/// Clears `IN_HOOK` when the hook returns or panics.
+struct HookGuard;
+
+impl Drop for HookGuard {
+    fn drop(&mut self) {
+        IN_HOOK.with(|in_hook| in_hook.set(false));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{set_hook, Conversion, ConversionKind};
+    use crate::types::PyDict;
+    use crate::{ObjectProtocol, PyObject, Python, ToPyObject};
+    use std::cell::{Cell, RefCell};
+    use std::panic::{self, AssertUnwindSafe};
+
+    thread_local! {
+        static CONVERSIONS: RefCell<Vec<(ConversionKind, String, String)>> = RefCell::new(Vec::new());
+        static PANIC: Cell<bool> = Cell::new(false);
+    }
+
+    fn record(conversion: &Conversion) {
+        if PANIC.with(|panic| panic.replace(false)) {
+            panic!("panicking hook");
+        }
+        // Extracting the type name doesn't recurse into the hook
+        let python_type: String = conversion
+            .object
+            .get_type()
+            .getattr("__name__")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(python_type, conversion.python_type());
+        CONVERSIONS.with(|conversions| {
+            conversions.borrow_mut().push((
+                conversion.kind,
+                conversion.rust_type.to_string(),
+                python_type,
+            ))
+        });
+    }
+
+    #[test]
+    fn test_trace() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = PyDict::new(py);
+        let list = vec![1].to_object(py);
+
+        set_hook(Some(record));
+        let _: i32 = py.eval("1", None, None).unwrap().extract().unwrap();
+        dict.set_item("key", list).unwrap();
+        set_hook(None);
+        let _: i32 = py.eval("2", None, None).unwrap().extract().unwrap();
+
+        let conversions = CONVERSIONS.with(|conversions| conversions.borrow().clone());
+        let conversions: Vec<(ConversionKind, &str, &str)> = conversions
+            .iter()
+            .map(|(kind, rust_type, python_type)| (*kind, rust_type.as_str(), python_type.as_str()))
+            .collect();
+        assert_eq!(
+            conversions,
+            vec![
+                (ConversionKind::FromPython, "i32", "int"),
+                (ConversionKind::ToPython, "&str", "str"),
+                (
+                    ConversionKind::Borrowed,
+                    std::any::type_name::<PyObject>(),
+                    "list"
+                ),
+            ]
+        );
+
+        // A panicking hook doesn't stop the hook from being called afterwards
+        CONVERSIONS.with(|conversions| conversions.borrow_mut().clear());
+        let one = py.eval("1", None, None).unwrap();
+        set_hook(Some(record));
+        PANIC.with(|panic| panic.set(true));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| one.extract::<i32>()));
+        assert!(result.is_err());
+        let _: i32 = one.extract().unwrap();
+        set_hook(None);
+
+        let conversions = CONVERSIONS.with(|conversions| conversions.borrow().clone());
+        assert_eq!(
+            conversions,
+            vec![(
+                ConversionKind::FromPython,
+                "i32".to_string(),
+                "int".to_string()
+            )]
+        );
+    }
+}
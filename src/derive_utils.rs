@@ -4,140 +4,47 @@
 
 //! Functionality for the code generated by the derive backend
 
-use crate::err::PyResult;
+use crate::class::methods::{PyMethodDef, PyMethodType};
+use crate::err::{PyErr, PyResult};
 use crate::exceptions::TypeError;
 use crate::init_once;
-use crate::instance::PyNativeType;
 use crate::pyclass::PyClass;
 use crate::pyclass_init::PyClassInitializer;
-use crate::types::{PyAny, PyDict, PyModule, PyTuple};
-use crate::{ffi, GILPool, IntoPy, PyObject, Python};
+use crate::types::{free_module_state, init_module_state, prepare_module_def, PyAny, PyModule};
+use crate::{ffi, AsPyPointer, AsPyRef, FromPy, GILPool, IntoPy, ObjectProtocol, PyNativeType};
+use crate::{PyObject, Python};
+use std::ffi::CStr;
+use std::os::raw::{c_int, c_void};
 use std::ptr;
 
-/// Description of a python parameter; used for `parse_args()`.
-#[derive(Debug)]
-pub struct ParamDescription {
-    /// The name of the parameter.
-    pub name: &'static str,
-    /// Whether the parameter is optional.
-    pub is_optional: bool,
-    /// Whether the parameter is optional.
-    pub kw_only: bool,
-}
-
-/// Parse argument list
-///
-/// * fname:  Name of the current function
-/// * params: Declared parameters of the function
-/// * args:   Positional arguments
-/// * kwargs: Keyword arguments
-/// * output: Output array that receives the arguments.
-///           Must have same length as `params` and must be initialized to `None`.
-pub fn parse_fn_args<'p>(
-    fname: Option<&str>,
-    params: &[ParamDescription],
-    args: &'p PyTuple,
-    kwargs: Option<&'p PyDict>,
-    accept_args: bool,
-    accept_kwargs: bool,
-    output: &mut [Option<&'p PyAny>],
-) -> PyResult<(&'p PyTuple, Option<&'p PyDict>)> {
-    let nargs = args.len();
-    let mut used_args = 0;
-    macro_rules! raise_error {
-        ($s: expr $(,$arg:expr)*) => (return Err(TypeError::py_err(format!(
-            concat!("{} ", $s), fname.unwrap_or("function") $(,$arg)*
-        ))))
-    }
-    // Copy kwargs not to modify it
-    let kwargs = match kwargs {
-        Some(k) => Some(k.copy()?),
-        None => None,
-    };
-    // Iterate through the parameters and assign values to output:
-    for (i, (p, out)) in params.iter().zip(output).enumerate() {
-        *out = match kwargs.and_then(|d| d.get_item(p.name)) {
-            Some(kwarg) => {
-                if i < nargs {
-                    raise_error!("got multiple values for argument: {}", p.name)
-                }
-                kwargs.as_ref().unwrap().del_item(p.name).unwrap();
-                Some(kwarg)
-            }
-            None => {
-                if p.kw_only {
-                    if !p.is_optional {
-                        raise_error!("missing required keyword-only argument: {}", p.name)
-                    }
-                    None
-                } else if i < nargs {
-                    used_args += 1;
-                    Some(args.get_item(i))
-                } else {
-                    if !p.is_optional {
-                        raise_error!("missing required positional argument: {}", p.name)
-                    }
-                    None
-                }
-            }
-        }
-    }
-    let is_kwargs_empty = kwargs.as_ref().map_or(true, |dict| dict.is_empty());
-    // Raise an error when we get an unknown key
-    if !accept_kwargs && !is_kwargs_empty {
-        let (key, _) = kwargs.unwrap().iter().next().unwrap();
-        raise_error!("got an unexpected keyword argument: {}", key)
-    }
-    // Raise an error when we get too many positional args
-    if !accept_args && used_args < nargs {
-        raise_error!(
-            "takes at most {} positional argument{} ({} given)",
-            used_args,
-            if used_args == 1 { "" } else { "s" },
-            nargs
-        )
-    }
-    // Adjust the remaining args
-    let args = if accept_args {
-        let py = args.py();
-        let slice = args.slice(used_args as isize, nargs as isize).into_py(py);
-        py.checked_cast_as(slice).unwrap()
-    } else {
-        args
-    };
-    let kwargs = if accept_kwargs && is_kwargs_empty {
-        None
-    } else {
-        kwargs
-    };
-    Ok((args, kwargs))
-}
+pub use crate::argparse::{parse_fn_args, ParamDescription};
 
 /// Builds a module (or null) from a user given initializer. Used for `#[pymodule]`.
-pub unsafe fn make_module(
+///
+/// `module_def` must be a static of the module, because Python keeps a reference to it. The
+/// module state of type `T` is stored in the module object and initialized with `T::default()`
+/// before the initializer runs. `name` and `doc` must be null terminated. `free` replaces the
+/// `m_free` function dropping the state, and should call [`free_module`](fn.free_module.html).
+pub unsafe fn make_module<T>(
+    module_def: *mut ffi::PyModuleDef,
     name: &str,
     doc: &str,
+    free: Option<ffi::freefunc>,
     initializer: impl Fn(Python, &PyModule) -> PyResult<()>,
-) -> *mut ffi::PyObject {
+) -> *mut ffi::PyObject
+where
+    T: Default + Send + 'static,
+{
     use crate::IntoPyPointer;
 
-    init_once();
-
-    #[cfg(py_sys_config = "WITH_THREAD")]
-    // > Changed in version 3.7: This function is now called by Py_Initialize(), so you don’t have
-    // > to call it yourself anymore.
-    #[cfg(not(Py_3_7))]
-    ffi::PyEval_InitThreads();
-
-    static mut MODULE_DEF: ffi::PyModuleDef = ffi::PyModuleDef_INIT;
-    // We can't convert &'static str to *const c_char within a static initializer,
-    // so we'll do it here in the module initialization:
-    MODULE_DEF.m_name = name.as_ptr() as *const _;
+    prepare_module(module_def, name, doc);
+    prepare_module_def::<T>(&mut *module_def, free);
 
-    let module = ffi::PyModule_Create(&mut MODULE_DEF);
+    let module = ffi::PyModule_Create(module_def);
     if module.is_null() {
         return module;
     }
+    init_module_state::<T>(module);
 
     let py = Python::assume_gil_acquired();
     let _pool = GILPool::new(py);
@@ -149,10 +56,7 @@ pub unsafe fn make_module(
         }
     };
 
-    module
-        .add("__doc__", doc)
-        .expect("Failed to add doc for module");
-    match initializer(py, module) {
+    match run_module_initializer(py, module, initializer) {
         Ok(_) => module.into_ptr(),
         Err(e) => {
             e.restore(py);
@@ -161,6 +65,167 @@ pub unsafe fn make_module(
     }
 }
 
+/// Returns the module definition for multi-phase initialization (PEP 489). Used for
+/// `#[pymodule(multi_phase)]`.
+///
+/// Python creates the module object from the returned definition and then calls `exec`, which
+/// should call [`exec_module`](fn.exec_module.html). `module_def` and `slots` must be statics of
+/// the module, and `name` and `doc` must be null terminated. `free` is used like in
+/// [`make_module`](fn.make_module.html).
+pub unsafe fn make_module_def<T>(
+    module_def: *mut ffi::PyModuleDef,
+    slots: *mut [ffi::PyModuleDef_Slot; 2],
+    name: &str,
+    doc: &str,
+    free: Option<ffi::freefunc>,
+    exec: unsafe extern "C" fn(*mut ffi::PyObject) -> c_int,
+) -> *mut ffi::PyObject
+where
+    T: Default + Send + 'static,
+{
+    prepare_module(module_def, name, doc);
+    prepare_module_def::<T>(&mut *module_def, free);
+
+    (*slots)[0] = ffi::PyModuleDef_Slot {
+        slot: ffi::Py_mod_exec,
+        value: exec as *mut c_void,
+    };
+    (*slots)[1] = ffi::PyModuleDef_Slot {
+        slot: 0,
+        value: ptr::null_mut(),
+    };
+    (*module_def).m_slots = slots as *mut ffi::PyModuleDef_Slot;
+
+    ffi::PyModuleDef_Init(module_def)
+}
+
+/// Runs a user given initializer on a module created from the definition returned by
+/// [`make_module_def`](fn.make_module_def.html). This is the `Py_mod_exec` slot, so it returns
+/// `0` on success and `-1` with an exception set on failure.
+pub unsafe fn exec_module<T>(
+    module: *mut ffi::PyObject,
+    initializer: impl Fn(Python, &PyModule) -> PyResult<()>,
+) -> c_int
+where
+    T: Default + Send + 'static,
+{
+    init_module_state::<T>(module);
+
+    let py = Python::assume_gil_acquired();
+    let _pool = GILPool::new(py);
+    let module = py.from_borrowed_ptr::<PyModule>(module);
+
+    match run_module_initializer(py, module, initializer) {
+        Ok(_) => 0,
+        Err(e) => {
+            e.restore(py);
+            -1
+        }
+    }
+}
+
+/// Runs the `on_unload` hook of a module and drops its state of type `T`. This is the `m_free`
+/// function of `#[pymodule(on_unload = ...)]`, which Python calls when the module object is
+/// deallocated.
+pub unsafe fn free_module<T: 'static>(module: *mut c_void, on_unload: fn(Python)) {
+    let py = Python::assume_gil_acquired();
+    {
+        let _pool = GILPool::new(py);
+        on_unload(py);
+    }
+    free_module_state::<T>(module);
+}
+
+/// Creates a module object from the result of a `PyInit_*` function. Used by `wrap_pymodule!`.
+///
+/// Single-phase init functions return the module itself. For multi-phase init functions the
+/// module is created and executed from the returned definition, like the import system would.
+pub unsafe fn module_from_init(py: Python, ptr: *mut ffi::PyObject) -> PyObject {
+    if ptr.is_null() || ffi::PyModule_Check(ptr) != 0 {
+        return PyObject::from_owned_ptr(py, ptr);
+    }
+
+    let def = ptr as *mut ffi::PyModuleDef;
+    let name = CStr::from_ptr((*def).m_name).to_string_lossy();
+    let spec = py
+        .import("importlib.machinery")
+        .and_then(|machinery| machinery.call1("ModuleSpec", (name.as_ref(), py.None())))
+        .expect("Failed to create module spec");
+
+    let module =
+        PyObject::from_owned_ptr_or_panic(py, ffi::PyModule_FromDefAndSpec(def, spec.as_ptr()));
+    if ffi::PyModule_ExecDef(module.as_ptr(), def) != 0 {
+        PyErr::fetch(py).print(py);
+        panic!("Failed to execute module {}", name);
+    }
+    module
+}
+
+unsafe fn prepare_module(module_def: *mut ffi::PyModuleDef, name: &str, doc: &str) {
+    init_once();
+
+    #[cfg(py_sys_config = "WITH_THREAD")]
+    // > Changed in version 3.7: This function is now called by Py_Initialize(), so you don’t have
+    // > to call it yourself anymore.
+    #[cfg(not(Py_3_7))]
+    ffi::PyEval_InitThreads();
+
+    // We can't convert &'static str to *const c_char within a static initializer,
+    // so we'll do it here in the module initialization:
+    (*module_def).m_name = name.as_ptr() as *const _;
+    // Without a doc comment, `__doc__` is `None` like for Python modules
+    if doc != "\0" {
+        (*module_def).m_doc = doc.as_ptr() as *const _;
+    }
+}
+
+fn run_module_initializer(
+    py: Python,
+    module: &PyModule,
+    initializer: impl Fn(Python, &PyModule) -> PyResult<()>,
+) -> PyResult<()> {
+    module.setattr(
+        "__pyo3_build_info__",
+        crate::build_info::python_function(py),
+    )?;
+    crate::initializer::run_initializers(py)?;
+    initializer(py, module)
+}
+
+/// Creates a builtin function object from `def`. Used for `#[pyfunction]` and `#[pyfn]`.
+///
+/// The method definition is leaked, because the function object keeps a pointer to it.
+pub fn make_function(py: Python, def: PyMethodDef) -> PyObject {
+    unsafe {
+        PyObject::from_owned_ptr_or_panic(
+            py,
+            ffi::PyCFunction_New(
+                Box::into_raw(Box::new(def.as_method_def())),
+                ptr::null_mut(),
+            ),
+        )
+    }
+}
+
+/// Creates a builtin function object calling `meth`, for the functions pyo3 adds to modules
+/// itself. `meth` should be defined with `trampoline!`, so errors and panics are raised as Python
+/// exceptions, and `doc` must be null terminated.
+pub(crate) fn builtin_function(
+    py: Python,
+    name: &'static str,
+    meth: ffi::PyCFunction,
+    flags: c_int,
+    doc: &'static str,
+) -> PyObject {
+    let def = PyMethodDef {
+        ml_name: name,
+        ml_meth: PyMethodType::PyCFunction(meth),
+        ml_flags: flags,
+        ml_doc: doc,
+    };
+    make_function(py, def)
+}
+
 /// This trait wraps a T: IntoPy<PyObject> into PyResult<T> while PyResult<T> remains PyResult<T>.
 ///
 /// This is necessary because proc macros run before typechecking and can't decide
@@ -171,17 +236,27 @@ pub trait IntoPyResult<T> {
 }
 
 impl<T: IntoPy<PyObject>> IntoPyResult<T> for T {
+    #[inline]
     fn into_py_result(self) -> PyResult<T> {
         Ok(self)
     }
 }
 
 impl<T: IntoPy<PyObject>> IntoPyResult<T> for PyResult<T> {
+    #[inline]
     fn into_py_result(self) -> PyResult<T> {
         self
     }
 }
 
+/// Other errors are converted with the [error_table](../error_table/index.html).
+impl<T: IntoPy<PyObject>, E: std::error::Error + 'static> IntoPyResult<T> for Result<T, E> {
+    #[inline]
+    fn into_py_result(self) -> PyResult<T> {
+        self.map_err(crate::error_table::translate)
+    }
+}
+
 /// Variant of IntoPyResult for the specific case of #[new]. In the case of returning (Sub, Base)
 /// from #[new], IntoPyResult can't apply because (Sub, Base) doesn't implement IntoPy<PyObject>.
 pub trait IntoPyNewResult<T: PyClass, I: Into<PyClassInitializer<T>>> {
@@ -189,17 +264,31 @@ pub trait IntoPyNewResult<T: PyClass, I: Into<PyClassInitializer<T>>> {
 }
 
 impl<T: PyClass, I: Into<PyClassInitializer<T>>> IntoPyNewResult<T, I> for I {
+    #[inline]
     fn into_pynew_result(self) -> PyResult<I> {
         Ok(self)
     }
 }
 
 impl<T: PyClass, I: Into<PyClassInitializer<T>>> IntoPyNewResult<T, I> for PyResult<I> {
+    #[inline]
     fn into_pynew_result(self) -> PyResult<I> {
         self
     }
 }
 
+impl<T, I, E> IntoPyNewResult<T, I> for Result<I, E>
+where
+    T: PyClass,
+    I: Into<PyClassInitializer<T>>,
+    E: std::error::Error + 'static,
+{
+    #[inline]
+    fn into_pynew_result(self) -> PyResult<I> {
+        self.map_err(crate::error_table::translate)
+    }
+}
+
 pub trait GetPropertyValue {
     fn get_property_value(&self, py: Python) -> PyObject;
 }
@@ -208,13 +297,36 @@ impl<T> GetPropertyValue for &T
 where
     T: IntoPy<PyObject> + Clone,
 {
+    #[inline]
     fn get_property_value(&self, py: Python) -> PyObject {
         (*self).clone().into_py(py)
     }
 }
 
 impl GetPropertyValue for PyObject {
+    #[inline]
     fn get_property_value(&self, py: Python) -> PyObject {
         self.clone_ref(py)
     }
 }
+
+/// The `TypeError` of `#[derive(FromPyObject)]` for an enum when none of its variants could be
+/// extracted, listing the error of each variant.
+pub fn failed_to_extract_enum(obj: &PyAny, enum_name: &str, errors: Vec<(&str, PyErr)>) -> PyErr {
+    let py = obj.py();
+    let mut message = format!(
+        "failed to extract enum {} from '{}' object",
+        enum_name,
+        obj.get_type().name()
+    );
+    for (variant, err) in errors {
+        let error_type = err.ptype.as_ref(py).name().into_owned();
+        let error = PyObject::from_py(err, py);
+        let error = match error.as_ref(py).str() {
+            Ok(error) => error.to_string_lossy().into_owned(),
+            Err(_) => String::new(),
+        };
+        message.push_str(&format!("\n- {}: {}: {}", variant, error_type, error));
+    }
+    TypeError::py_err(message)
+}
@@ -1,7 +1,8 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
-use crate::instance::Py;
+use crate::instance::{AsPyRef, Py};
 use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
 use crate::type_object::PyTypeObject;
 use crate::types::{PyAny, PyType};
 use crate::AsPyPointer;
@@ -13,8 +14,6 @@ use crate::{ToBorrowedObject, ToPyObject};
 use libc::c_int;
 use std::ffi::CString;
 use std::io;
-use std::os::raw::c_char;
-use std::ptr::NonNull;
 
 /// Represents a `PyErr` value
 ///
@@ -56,8 +55,39 @@ pub struct PyErr {
 /// Represents the result of a Python call.
 pub type PyResult<T> = Result<T, PyErr>;
 
-/// Marker type that indicates an error while downcasting
-pub struct PyDowncastError;
+/// The error returned when an object isn't an instance of the type it is downcast to, e.g. by
+/// [PyAny::downcast](types/struct.PyAny.html#method.downcast).
+///
+/// It converts to a `TypeError` with the names of both types. The error keeps the type of the
+/// object and reads its name only when it is displayed, so failed downcasts that are handled,
+/// e.g. when trying several types, don't format anything.
+#[derive(Debug, PartialEq)]
+pub struct PyDowncastError {
+    from: Py<PyType>,
+    to: &'static str,
+}
+
+impl PyDowncastError {
+    /// Creates the error for `from` not being an instance of the type named `to`.
+    #[cold]
+    pub fn new(from: &PyAny, to: &'static str) -> Self {
+        PyDowncastError {
+            from: from.get_type().into(),
+            to,
+        }
+    }
+
+    /// The name of the type of the object that was downcast.
+    pub fn from_type_name(&self) -> String {
+        let gil = Python::acquire_gil();
+        self.from.as_ref(gil.python()).name().into_owned()
+    }
+
+    /// The name of the type the object was downcast to.
+    pub fn to_type_name(&self) -> &'static str {
+        self.to
+    }
+}
 
 /// Helper conversion trait that allows to use custom arguments for exception constructor.
 pub trait PyErrArguments {
@@ -173,14 +203,42 @@ impl PyErr {
     /// Creates a new exception type with the given name, which must be of the form
     /// `<module>.<ExceptionName>`, as required by `PyErr_NewException`.
     ///
-    /// `base` can be an existing exception type to subclass, or a tuple of classes
+    /// `base` can be an existing exception type to subclass, `Exception` if `None`
+    /// `doc` specifies an optional docstring of the type
     /// `dict` specifies an optional dictionary of class variables and methods
-    pub fn new_type<'p>(
-        _: Python<'p>,
+    ///
+    /// This is the runtime counterpart of [create_exception!](macro.create_exception.html), for
+    /// exceptions whose names are only known at runtime:
+    ///
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::{IntoPyDict, PyType};
+    /// use pyo3::{exceptions, PyErr};
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let module = PyModule::new(py, "schema").unwrap();
+    /// let dict = [("code", 404)].into_py_dict(py);
+    /// let not_found: Py<PyType> = PyErr::new_type(
+    ///     py,
+    ///     "schema.NotFound",
+    ///     Some(py.get_type::<exceptions::LookupError>()),
+    ///     Some("Raised when a record doesn't exist."),
+    ///     Some(dict.into()),
+    /// )
+    /// .unwrap();
+    /// module.add("NotFound", &not_found).unwrap();
+    ///
+    /// let err = PyErr::from_type(not_found.clone_ref(py), "record 7");
+    /// assert!(err.is_instance::<exceptions::LookupError>(py));
+    /// ```
+    pub fn new_type(
+        py: Python,
         name: &str,
         base: Option<&PyType>,
+        doc: Option<&str>,
         dict: Option<PyObject>,
-    ) -> NonNull<ffi::PyTypeObject> {
+    ) -> PyResult<Py<PyType>> {
         let base: *mut ffi::PyObject = match base {
             None => std::ptr::null_mut(),
             Some(obj) => obj.as_ptr(),
@@ -188,18 +246,23 @@ impl PyErr {
 
         let dict: *mut ffi::PyObject = match dict {
             None => std::ptr::null_mut(),
-            Some(obj) => obj.as_ptr(),
+            Some(ref obj) => obj.as_ptr(),
         };
 
-        unsafe {
-            let null_terminated_name =
-                CString::new(name).expect("Failed to initialize nul terminated exception name");
+        let name = CString::new(name)?;
+        let doc = match doc {
+            None => None,
+            Some(doc) => Some(CString::new(doc)?),
+        };
 
-            NonNull::new_unchecked(ffi::PyErr_NewException(
-                null_terminated_name.as_ptr() as *mut c_char,
+        unsafe {
+            let ptr = ffi::PyErr_NewExceptionWithDoc(
+                name.as_ptr(),
+                doc.as_ref().map_or(std::ptr::null(), |doc| doc.as_ptr()),
                 base,
                 dict,
-            ) as *mut ffi::PyTypeObject)
+            );
+            Py::from_owned_ptr_or_err(py, ptr)
         }
     }
 
@@ -333,6 +396,8 @@ impl PyErr {
 
     #[doc(hidden)]
     /// Utility method for proc-macro code
+    #[cold]
+    #[inline(never)]
     pub fn restore_and_null<T>(self, py: Python) -> *mut T {
         self.restore(py);
         std::ptr::null_mut()
@@ -401,19 +466,46 @@ impl<'a> IntoPy<PyObject> for &'a PyErr {
     }
 }
 
-/// Converts `PyDowncastError` to Python `TypeError`.
+impl Eq for PyDowncastError {}
+
+impl Clone for PyDowncastError {
+    fn clone(&self) -> Self {
+        let gil = Python::acquire_gil();
+        PyDowncastError {
+            from: self.from.clone_ref(gil.python()),
+            to: self.to,
+        }
+    }
+}
+
+impl PyErrArguments for PyDowncastError {
+    fn arguments(&self, py: Python) -> PyObject {
+        self.to_string().to_object(py)
+    }
+}
+
+/// Converts `PyDowncastError` to Python `TypeError`. The message is created when the exception
+/// is normalized.
 impl std::convert::From<PyDowncastError> for PyErr {
-    fn from(_err: PyDowncastError) -> PyErr {
-        exceptions::TypeError.into()
+    fn from(err: PyDowncastError) -> PyErr {
+        PyErr::from_value::<exceptions::TypeError>(PyErrValue::from_err_args(err))
     }
 }
 
-impl<'p> std::fmt::Debug for PyDowncastError {
+impl std::fmt::Display for PyDowncastError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        f.write_str("PyDowncastError")
+        let gil = Python::acquire_gil();
+        write!(
+            f,
+            "'{}' object cannot be converted to '{}'",
+            self.from.as_ref(gil.python()).name(),
+            self.to
+        )
     }
 }
 
+impl std::error::Error for PyDowncastError {}
+
 /// Convert `PyErr` to `io::Error`
 impl std::convert::From<PyErr> for std::io::Error {
     fn from(err: PyErr) -> Self {
@@ -546,7 +638,9 @@ pub fn error_on_minusone(py: Python, result: c_int) -> PyResult<()> {
 #[cfg(test)]
 mod tests {
     use crate::exceptions;
-    use crate::{PyErr, Python};
+    use crate::instance::AsPyRef;
+    use crate::types::IntoPyDict;
+    use crate::{ObjectProtocol, PyErr, Python};
 
     #[test]
     fn set_typeerror() {
@@ -557,4 +651,39 @@ mod tests {
         assert!(PyErr::occurred(py));
         drop(PyErr::fetch(py));
     }
+
+    #[test]
+    fn new_type() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = [("code", 7)].into_py_dict(py);
+        let ty = PyErr::new_type(
+            py,
+            "generated.Failure",
+            Some(py.get_type::<exceptions::ValueError>()),
+            Some("A generated exception."),
+            Some(dict.into()),
+        )
+        .unwrap();
+        let ty = ty.as_ref(py);
+        assert_eq!(ty.name(), "Failure");
+        assert_eq!(
+            ty.getattr("__doc__").unwrap().extract::<String>().unwrap(),
+            "A generated exception."
+        );
+        assert_eq!(ty.getattr("code").unwrap().extract::<i32>().unwrap(), 7);
+        assert_eq!(
+            ty.getattr("__module__")
+                .unwrap()
+                .extract::<String>()
+                .unwrap(),
+            "generated"
+        );
+
+        // The name must contain the module
+        let err = PyErr::new_type(py, "Failure", None, None, None).unwrap_err();
+        assert!(err.is_instance::<exceptions::SystemError>(py));
+        let err = PyErr::new_type(py, "generated.Fail\0ure", None, None, None).unwrap_err();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+    }
 }
@@ -0,0 +1,169 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! A global table translating Rust error types to Python exceptions.
+//!
+//! Functions and methods exposed to Python can return `Result<T, E>` for any error type `E`
+//! implementing `std::error::Error`, not just `PyResult<T>`. The generated wrapper translates the
+//! error with the function [`register`]ed for `E`, so that the exceptions raised for an error
+//! type are decided once, at initialization, instead of by a `From` implementation or
+//! `map_err` at every call site:
+//!
+//! ```
+//! use pyo3::error_table;
+//! use pyo3::exceptions::{KeyError, ValueError};
+//! use pyo3::prelude::*;
+//! use std::fmt;
+//!
+//! #[derive(Debug)]
+//! enum ConfigError {
+//!     Missing(String),
+//!     Invalid(String),
+//! }
+//!
+//! impl fmt::Display for ConfigError {
+//!     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//!         match self {
+//!             ConfigError::Missing(key) => write!(f, "missing key {}", key),
+//!             ConfigError::Invalid(key) => write!(f, "invalid value for {}", key),
+//!         }
+//!     }
+//! }
+//!
+//! impl std::error::Error for ConfigError {}
+//!
+//! #[pyfunction]
+//! fn read_config(key: String) -> Result<String, ConfigError> {
+//!     Err(ConfigError::Missing(key))
+//! }
+//!
+//! #[pymodule]
+//! fn config(_py: Python, m: &PyModule) -> PyResult<()> {
+//!     error_table::register(|err: ConfigError| match err {
+//!         ConfigError::Missing(key) => KeyError::py_err(key),
+//!         ConfigError::Invalid(_) => ValueError::py_err(err.to_string()),
+//!     });
+//!     m.add_wrapped(pyo3::wrap_pyfunction!(read_config))
+//! }
+//! ```
+//!
+//! Errors of types without a registered function raise `RuntimeError` with the `Display`
+//! message of the error. The errors of the standard library with a `From` conversion to `PyErr`,
+//! like `std::io::Error` or `std::num::ParseIntError`, are registered with that conversion, and
+//! can be registered again to change it. Rust code can translate errors with [`translate`], e.g.
+//! `do_something().map_err(error_table::translate)?` in a function returning `PyResult`.
+//!
+//! [`register`]: fn.register.html
+//! [`translate`]: fn.translate.html
+
+use crate::err::PyErr;
+use crate::exceptions::RuntimeError;
+use parking_lot::{const_mutex, Mutex};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+type Translator = Arc<dyn Fn(Box<dyn Any>) -> PyErr + Send + Sync>;
+
+static TABLE: Mutex<Option<HashMap<TypeId, Translator>>> = const_mutex(None);
+
+/// Registers `translate` to convert errors of type `E` to Python exceptions, replacing the
+/// function registered before for `E`.
+pub fn register<E, F>(translate: F)
+where
+    E: Error + 'static,
+    F: Fn(E) -> PyErr + Send + Sync + 'static,
+{
+    let translator: Translator = Arc::new(move |err| translate(*err.downcast::<E>().unwrap()));
+    with_table(|table| table.insert(TypeId::of::<E>(), translator));
+}
+
+/// Removes the function registered for `E`, so its errors raise `RuntimeError` again.
+pub fn unregister<E: Error + 'static>() {
+    with_table(|table| table.remove(&TypeId::of::<E>()));
+}
+
+/// Converts `err` to a Python exception with the function registered for `E`, or to a
+/// `RuntimeError` with its message if there's none.
+pub fn translate<E: Error + 'static>(err: E) -> PyErr {
+    // Translators may translate other errors, so they're called without holding the lock
+    let translator = with_table(|table| table.get(&TypeId::of::<E>()).cloned());
+    match translator {
+        Some(translator) => translator(Box::new(err)),
+        None => RuntimeError::py_err(err.to_string()),
+    }
+}
+
+fn with_table<T>(f: impl FnOnce(&mut HashMap<TypeId, Translator>) -> T) -> T {
+    let mut table = TABLE.lock();
+    f(table.get_or_insert_with(std_errors))
+}
+
+/// The table with the errors of the standard library converted by `From` implementations.
+fn std_errors() -> HashMap<TypeId, Translator> {
+    fn from<E: Error + 'static>() -> (TypeId, Translator)
+    where
+        PyErr: From<E>,
+    {
+        let translator: Translator = Arc::new(|err| PyErr::from(*err.downcast::<E>().unwrap()));
+        (TypeId::of::<E>(), translator)
+    }
+
+    vec![
+        from::<std::io::Error>(),
+        from::<std::array::TryFromSliceError>(),
+        from::<std::num::ParseIntError>(),
+        from::<std::num::ParseFloatError>(),
+        from::<std::num::TryFromIntError>(),
+        from::<std::str::ParseBoolError>(),
+        from::<std::ffi::IntoStringError>(),
+        from::<std::ffi::NulError>(),
+        from::<std::str::Utf8Error>(),
+        from::<std::string::FromUtf8Error>(),
+        from::<std::string::FromUtf16Error>(),
+        from::<std::char::DecodeUtf16Error>(),
+        from::<std::net::AddrParseError>(),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{register, translate, unregister};
+    use crate::exceptions::{FileNotFoundError, KeyError, RuntimeError, ValueError};
+    use crate::Python;
+    use std::fmt;
+    use std::io;
+
+    #[derive(Debug)]
+    struct TableError(&'static str);
+
+    impl fmt::Display for TableError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    impl std::error::Error for TableError {}
+
+    #[test]
+    fn test_translate() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err = translate(TableError("unregistered"));
+        assert!(err.is_instance::<RuntimeError>(py));
+
+        register(|err: TableError| KeyError::py_err(err.0));
+        assert!(translate(TableError("key")).is_instance::<KeyError>(py));
+        // Translators can translate other errors
+        register(|err: TableError| translate(err.0.parse::<i32>().unwrap_err()));
+        assert!(translate(TableError("nan")).is_instance::<ValueError>(py));
+        unregister::<TableError>();
+        assert!(translate(TableError("again")).is_instance::<RuntimeError>(py));
+
+        let err = translate(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        assert!(err.is_instance::<FileNotFoundError>(py));
+    }
+}
@@ -4,11 +4,13 @@
 
 use crate::err::{PyErr, PyResult};
 use crate::ffi;
+use crate::objectprotocol::ObjectProtocol;
 use crate::type_object::PyTypeObject;
-use crate::types::{PyAny, PyTuple};
+use crate::types::{PyAny, PyDict, PyTuple};
 use crate::Python;
 use crate::{AsPyPointer, ToPyObject};
 use std::ffi::CStr;
+use std::io;
 use std::ops;
 use std::os::raw::c_char;
 
@@ -177,12 +179,19 @@ macro_rules! create_exception_type_object {
                 static TYPE_OBJECT: LazyHeapType = LazyHeapType::new();
 
                 let ptr = TYPE_OBJECT.get_or_init(|py| {
-                    $crate::PyErr::new_type(
+                    let type_object = $crate::PyErr::new_type(
                         py,
                         concat!(stringify!($module), ".", stringify!($name)),
                         Some(py.get_type::<$base>()),
                         None,
+                        None,
                     )
+                    .expect("Failed to initialize new exception type.");
+                    // The static keeps the type alive
+                    let ptr = $crate::IntoPyPointer::into_ptr(type_object);
+                    unsafe {
+                        std::ptr::NonNull::new_unchecked(ptr as *mut $crate::ffi::PyTypeObject)
+                    }
                 });
 
                 unsafe { $crate::Py::from_borrowed_ptr(ptr.as_ptr() as *mut $crate::ffi::PyObject) }
@@ -321,6 +330,70 @@ impl UnicodeDecodeError {
     }
 }
 
+impl SyntaxError {
+    /// Creates a `SyntaxError` carrying the same location details the Python parser attaches,
+    /// i.e. `SyntaxError(msg, (filename, lineno, offset, text))`.
+    ///
+    /// `lineno` and `offset` are 1-based, as in Python.
+    pub fn new_err(msg: &str, filename: &str, lineno: usize, offset: usize, text: &str) -> PyErr {
+        PyErr::new::<SyntaxError, _>((
+            msg.to_string(),
+            (filename.to_string(), lineno, offset, text.to_string()),
+        ))
+    }
+}
+
+impl OSError {
+    /// Creates an `OSError` from a Rust `io::Error`, passing along an `errno` value.
+    ///
+    /// The OS error code is used when available; otherwise one is derived from the
+    /// `io::ErrorKind`. Python then picks the matching subclass
+    /// (e.g. `FileNotFoundError` for `ENOENT`) and fills in `OSError.errno`.
+    pub fn from_io_error(err: &io::Error) -> PyErr {
+        match err.raw_os_error().or_else(|| errno_from_kind(err.kind())) {
+            Some(errno) => PyErr::new::<OSError, _>((errno, err.to_string())),
+            None => PyErr::new::<OSError, _>(err.to_string()),
+        }
+    }
+}
+
+fn errno_from_kind(kind: io::ErrorKind) -> Option<i32> {
+    let errno = match kind {
+        io::ErrorKind::NotFound => libc::ENOENT,
+        io::ErrorKind::PermissionDenied => libc::EACCES,
+        io::ErrorKind::ConnectionRefused => libc::ECONNREFUSED,
+        io::ErrorKind::ConnectionReset => libc::ECONNRESET,
+        io::ErrorKind::ConnectionAborted => libc::ECONNABORTED,
+        io::ErrorKind::NotConnected => libc::ENOTCONN,
+        io::ErrorKind::AddrInUse => libc::EADDRINUSE,
+        io::ErrorKind::AddrNotAvailable => libc::EADDRNOTAVAIL,
+        io::ErrorKind::BrokenPipe => libc::EPIPE,
+        io::ErrorKind::AlreadyExists => libc::EEXIST,
+        io::ErrorKind::WouldBlock => libc::EAGAIN,
+        io::ErrorKind::InvalidInput => libc::EINVAL,
+        io::ErrorKind::TimedOut => libc::ETIMEDOUT,
+        io::ErrorKind::Interrupted => libc::EINTR,
+        _ => return None,
+    };
+    Some(errno)
+}
+
+impl ImportError {
+    /// Creates an `ImportError` with the `name` and `path` attributes set,
+    /// like `ImportError(msg, name=name, path=path)`.
+    pub fn new_err(py: Python, msg: &str, name: Option<&str>, path: Option<&str>) -> PyErr {
+        let kwargs = PyDict::new(py);
+        let instance = kwargs
+            .set_item("name", name)
+            .and_then(|_| kwargs.set_item("path", path))
+            .and_then(|_| py.get_type::<ImportError>().call((msg,), Some(kwargs)));
+        match instance {
+            Ok(instance) => PyErr::from_instance(instance),
+            Err(err) => err,
+        }
+    }
+}
+
 impl StopIteration {
     pub fn stop_iteration(_py: Python, args: &PyTuple) {
         unsafe {
@@ -433,4 +506,44 @@ mod test {
         )
         .unwrap();
     }
+
+    #[test]
+    fn structured_exceptions() {
+        use crate::exceptions::{ImportError, OSError, SyntaxError};
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let syntax_err = SyntaxError::new_err("invalid syntax", "<string>", 1, 5, "a b c");
+        let io_err = OSError::from_io_error(&std::io::Error::from(std::io::ErrorKind::NotFound));
+        let import_err = ImportError::new_err(py, "no module", Some("foo"), Some("/tmp/foo"));
+
+        let d = [
+            ("syntax_err", syntax_err),
+            ("io_err", io_err),
+            ("import_err", import_err),
+        ]
+        .into_py_dict(py);
+        py.run(
+            "assert (syntax_err.filename, syntax_err.lineno, syntax_err.offset) == ('<string>', 1, 5)",
+            None,
+            Some(d),
+        )
+        .map_err(|e| e.print(py))
+        .expect("assertion failed");
+        py.run(
+            "assert isinstance(io_err, FileNotFoundError)",
+            None,
+            Some(d),
+        )
+        .map_err(|e| e.print(py))
+        .expect("assertion failed");
+        py.run(
+            "assert (import_err.name, import_err.path) == ('foo', '/tmp/foo')",
+            None,
+            Some(d),
+        )
+        .map_err(|e| e.print(py))
+        .expect("assertion failed");
+    }
 }
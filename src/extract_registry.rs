@@ -0,0 +1,153 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! A global registry of functions extracting Rust values from objects of third-party Python types.
+//!
+//! pyo3 can't implement `FromPyObject` for the types of every Python library, and a crate can't
+//! implement it for a Rust type it doesn't own either. Instead, a crate can [`register`] a
+//! function extracting a Rust type `T` from the instances of a Python type, e.g. from numpy
+//! scalars or pandas timestamps, when its module is initialized. [`extract_registered`] (or
+//! `PyAny::extract_registered`) then extracts a `T` with the function registered for the type of
+//! the object, or for the nearest of its base classes:
+//!
+//! ```
+//! use pyo3::extract_registry;
+//! use pyo3::prelude::*;
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Meters(f64);
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let locals = pyo3::types::PyDict::new(py);
+//! py.run(
+//!     "class Distance:\n    def __init__(self, km):\n        self.km = km",
+//!     None,
+//!     Some(locals),
+//! )
+//! .unwrap();
+//! let distance_type = locals.get_item("Distance").unwrap().downcast().unwrap();
+//!
+//! extract_registry::register(distance_type, |obj| {
+//!     Ok(Meters(obj.getattr("km")?.extract::<f64>()? * 1000.0))
+//! });
+//!
+//! let distance = py.eval("Distance(1.5)", None, Some(locals)).unwrap();
+//! assert_eq!(distance.extract_registered::<Meters>().unwrap(), Meters(1500.0));
+//! assert!(py.eval("1.5", None, None).unwrap().extract_registered::<Meters>().is_err());
+//! ```
+//!
+//! Objects of types without a registered function raise a `TypeError`. Registering a function for
+//! a type and `T` replaces the function registered before, so e.g. an application can override
+//! the conversions of the libraries it uses.
+//!
+//! [`register`]: fn.register.html
+//! [`extract_registered`]: fn.extract_registered.html
+
+use crate::err::PyResult;
+use crate::exceptions::TypeError;
+use crate::types::{PyAny, PyTuple, PyType};
+use crate::{AsPyPointer, ObjectProtocol, PyNativeType, PyObject, ToPyObject};
+use parking_lot::{const_mutex, Mutex};
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+type Converter = Arc<dyn Fn(&PyAny) -> PyResult<Box<dyn Any>> + Send + Sync>;
+
+/// The converters by the Rust type they extract and the address of the Python type, which is kept
+/// alive by the `PyObject` next to the converter.
+type Registry = HashMap<(TypeId, usize), (PyObject, Converter)>;
+
+static REGISTRY: Mutex<Option<Registry>> = const_mutex(None);
+
+/// Registers `convert` to extract values of type `T` from instances of `ty` and its subclasses,
+/// replacing the function registered before for `ty` and `T`.
+pub fn register<T, F>(ty: &PyType, convert: F)
+where
+    T: 'static,
+    F: Fn(&PyAny) -> PyResult<T> + Send + Sync + 'static,
+{
+    let converter: Converter = Arc::new(move |obj| Ok(Box::new(convert(obj)?)));
+    let key = (TypeId::of::<T>(), ty.as_ptr() as usize);
+    let ty = ty.to_object(ty.py());
+    // The replaced type is released after unlocking, as that may run arbitrary code
+    let _replaced = with_registry(|registry| registry.insert(key, (ty, converter)));
+}
+
+/// Removes the function registered to extract values of type `T` from instances of `ty`.
+pub fn unregister<T: 'static>(ty: &PyType) {
+    let key = (TypeId::of::<T>(), ty.as_ptr() as usize);
+    let _removed = with_registry(|registry| registry.remove(&key));
+}
+
+/// Extracts a `T` from `obj` with the function registered for the type of `obj` or the nearest
+/// of its base classes, or raises a `TypeError` if there's none.
+pub fn extract_registered<T: 'static>(obj: &PyAny) -> PyResult<T> {
+    let ty = obj.get_type();
+    let mro: &PyTuple = ty
+        .getattr(crate::intern!(obj.py(), "__mro__"))?
+        .downcast()?;
+    // Converters may extract other values, so they're called without holding the lock
+    let converter = with_registry(|registry| {
+        mro.iter().find_map(|base| {
+            registry
+                .get(&(TypeId::of::<T>(), base.as_ptr() as usize))
+                .map(|(_, converter)| converter.clone())
+        })
+    });
+    match converter {
+        Some(converter) => Ok(*converter(obj)?
+            .downcast::<T>()
+            .expect("converter returned a value of the wrong type")),
+        None => Err(TypeError::py_err(format!(
+            "no conversion registered to extract {} from '{}' objects",
+            type_name::<T>(),
+            ty.name()
+        ))),
+    }
+}
+
+fn with_registry<T>(f: impl FnOnce(&mut Registry) -> T) -> T {
+    let mut registry = REGISTRY.lock();
+    f(registry.get_or_insert_with(HashMap::new))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{extract_registered, register, unregister};
+    use crate::exceptions::{TypeError, ValueError};
+    use crate::types::{PyFloat, PyLong};
+    use crate::{ObjectProtocol, PyErr, Python};
+
+    #[derive(Debug, PartialEq)]
+    struct Celsius(f64);
+
+    #[test]
+    fn test_extract_registered() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let float = py.get_type::<PyFloat>();
+        let int = py.get_type::<PyLong>();
+        let celsius = |code| extract_registered::<Celsius>(py.eval(code, None, None).unwrap());
+
+        assert!(celsius("1.0").unwrap_err().is_instance::<TypeError>(py));
+
+        register(float, |obj| Ok(Celsius(obj.extract()?)));
+        assert_eq!(celsius("21.5").unwrap(), Celsius(21.5));
+        // `bool` is a subclass of `int`, whose converter is used for it
+        register(int, |obj| Ok(Celsius(obj.extract::<i64>()? as f64 - 273.0)));
+        assert_eq!(celsius("300").unwrap(), Celsius(27.0));
+        assert_eq!(celsius("True").unwrap(), Celsius(-272.0));
+        // Registering again replaces the converter, which can raise
+        register(int, |_| -> Result<Celsius, PyErr> {
+            Err(ValueError::py_err("no ints"))
+        });
+        assert!(celsius("300").unwrap_err().is_instance::<ValueError>(py));
+
+        unregister::<Celsius>(int);
+        assert!(celsius("300").unwrap_err().is_instance::<TypeError>(py));
+        // Converters are registered per Rust type
+        assert!(extract_registered::<f64>(py.eval("1.0", None, None).unwrap()).is_err());
+        unregister::<Celsius>(float);
+    }
+}
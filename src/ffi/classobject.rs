@@ -0,0 +1,21 @@
+use crate::ffi::object::*;
+use std::os::raw::c_int;
+
+#[cfg_attr(windows, link(name = "pythonXY"))]
+extern "C" {
+    #[cfg_attr(PyPy, link_name = "PyPyInstanceMethod_Type")]
+    pub static mut PyInstanceMethod_Type: PyTypeObject;
+}
+
+#[inline]
+pub unsafe fn PyInstanceMethod_Check(op: *mut PyObject) -> c_int {
+    (Py_TYPE(op) == &mut PyInstanceMethod_Type) as c_int
+}
+
+#[cfg_attr(windows, link(name = "pythonXY"))]
+extern "C" {
+    #[cfg_attr(PyPy, link_name = "PyPyInstanceMethod_New")]
+    pub fn PyInstanceMethod_New(func: *mut PyObject) -> *mut PyObject;
+    #[cfg_attr(PyPy, link_name = "PyPyInstanceMethod_Function")]
+    pub fn PyInstanceMethod_Function(im: *mut PyObject) -> *mut PyObject;
+}
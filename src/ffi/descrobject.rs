@@ -42,6 +42,24 @@ pub const PyGetSetDef_DICT: PyGetSetDef = PyGetSetDef {
     closure: ptr::null_mut(),
 };
 
+#[cfg(not(Py_LIMITED_API))]
+#[repr(C)]
+pub struct PyDescrObject {
+    pub ob_base: PyObject,
+    pub d_type: *mut PyTypeObject,
+    pub d_name: *mut PyObject,
+    pub d_qualname: *mut PyObject,
+}
+
+#[cfg(not(Py_LIMITED_API))]
+#[repr(C)]
+pub struct PyMethodDescrObject {
+    pub d_common: PyDescrObject,
+    pub d_method: *mut PyMethodDef,
+    #[cfg(Py_3_8)]
+    pub vectorcall: *mut c_void,
+}
+
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
     #[cfg_attr(PyPy, link_name = "PyPyClassMethodDescr_Type")]
@@ -47,10 +47,19 @@ pub unsafe fn PyVectorcall_NARGS(
     n & !PY_VECTORCALL_ARGUMENTS_OFFSET
 }
 
+/// The signature of `METH_FASTCALL` functions, which take no keyword arguments.
 #[cfg(all(Py_3_7, not(Py_LIMITED_API)))]
 pub type _PyCFunctionFast = unsafe extern "C" fn(
     slf: *mut PyObject,
-    args: *mut *mut PyObject,
+    args: *const *mut PyObject,
+    nargs: crate::ffi::pyport::Py_ssize_t,
+) -> *mut PyObject;
+
+/// The signature of `METH_FASTCALL | METH_KEYWORDS` functions. The flag only exists in Python 3.7
+/// and later, but the type is always defined so that generated code can use it.
+pub type _PyCFunctionFastWithKeywords = unsafe extern "C" fn(
+    slf: *mut PyObject,
+    args: *const *mut PyObject,
     nargs: crate::ffi::pyport::Py_ssize_t,
     kwnames: *mut PyObject,
 ) -> *mut PyObject;
@@ -96,6 +105,19 @@ impl Default for PyMethodDef {
     }
 }
 
+/// The object of a builtin function. Only `m_ml` is used by pyo3, to change the documentation.
+#[cfg(not(Py_LIMITED_API))]
+#[repr(C)]
+pub struct PyCFunctionObject {
+    pub ob_base: PyObject,
+    pub m_ml: *mut PyMethodDef,
+    pub m_self: *mut PyObject,
+    pub m_module: *mut PyObject,
+    pub m_weakreflist: *mut PyObject,
+    #[cfg(Py_3_8)]
+    pub vectorcall: Option<PyObject_Vectorcall>,
+}
+
 #[inline]
 pub unsafe fn PyCFunction_New(ml: *mut PyMethodDef, slf: *mut PyObject) -> *mut PyObject {
     #[cfg_attr(PyPy, link_name = "PyPyCFunction_NewEx")]
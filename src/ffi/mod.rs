@@ -7,6 +7,8 @@ pub use self::boolobject::*;
 pub use self::bytearrayobject::*;
 pub use self::bytesobject::*;
 pub use self::ceval::*;
+#[cfg(not(Py_LIMITED_API))]
+pub use self::classobject::*;
 pub use self::code::*;
 pub use self::codecs::*;
 pub use self::compile::*;
@@ -92,7 +94,8 @@ mod methodobject; // TODO supports PEP-384 only; needs adjustment for Python 3.3
 mod moduleobject;
 mod setobject; // TODO supports PEP-384 only; needs adjustment for Python 3.3 and 3.5
                // mod funcobject; TODO excluded by PEP-384
-               // mod classobject; TODO excluded by PEP-384
+#[cfg(not(Py_LIMITED_API))]
+mod classobject; // TODO only instance methods, excluded by PEP-384
 mod fileobject; // TODO supports PEP-384 only; needs adjustment for Python 3.3 and 3.5
 mod pycapsule; // TODO supports PEP-384 only; needs adjustment for Python 3.3 and 3.5
 mod sliceobject;
@@ -762,6 +762,9 @@ extern "C" {
     pub fn PyType_ClearCache() -> c_uint;
     #[cfg_attr(PyPy, link_name = "PyPyType_Modified")]
     pub fn PyType_Modified(t: *mut PyTypeObject);
+    #[cfg(not(Py_LIMITED_API))]
+    #[cfg(not(PyPy))]
+    pub fn _PyType_Lookup(t: *mut PyTypeObject, name: *mut PyObject) -> *mut PyObject;
 
     #[cfg(not(Py_LIMITED_API))]
     #[cfg_attr(PyPy, link_name = "PyPyObject_Print")]
@@ -49,6 +49,12 @@ extern "C" {
     #[cfg_attr(PyPy, link_name = "PyPyThreadState_GetDict")]
     pub fn PyThreadState_GetDict() -> *mut PyObject;
     pub fn PyThreadState_SetAsyncExc(arg1: c_long, arg2: *mut PyObject) -> c_int;
+    #[cfg(all(Py_3_7, not(PyPy)))]
+    pub fn PyInterpreterState_GetID(arg1: *mut PyInterpreterState) -> i64;
+    #[cfg(all(Py_3_9, not(PyPy)))]
+    pub fn PyThreadState_GetInterpreter(tstate: *mut PyThreadState) -> *mut PyInterpreterState;
+    #[cfg(all(not(Py_LIMITED_API), not(PyPy)))]
+    pub fn _PyThreadState_UncheckedGet() -> *mut PyThreadState;
 }
 
 #[repr(C)]
@@ -65,6 +71,8 @@ extern "C" {
     #[cfg_attr(PyPy, link_name = "PyPyGILState_Release")]
     pub fn PyGILState_Release(arg1: PyGILState_STATE) -> ();
     pub fn PyGILState_GetThisThreadState() -> *mut PyThreadState;
+    #[cfg_attr(PyPy, link_name = "PyPyGILState_Check")]
+    pub fn PyGILState_Check() -> c_int;
 }
 
 #[inline]
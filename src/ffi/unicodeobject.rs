@@ -31,6 +31,14 @@ pub unsafe fn PyUnicode_CheckExact(op: *mut PyObject) -> c_int {
 
 pub const Py_UNICODE_REPLACEMENT_CHARACTER: Py_UCS4 = 0xFFFD;
 
+/* The kinds of the data of strings (PEP 393), for PyUnicode_FromKindAndData */
+#[cfg(not(Py_LIMITED_API))]
+pub const PyUnicode_1BYTE_KIND: c_int = 1;
+#[cfg(not(Py_LIMITED_API))]
+pub const PyUnicode_2BYTE_KIND: c_int = 2;
+#[cfg(not(Py_LIMITED_API))]
+pub const PyUnicode_4BYTE_KIND: c_int = 4;
+
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
     #[cfg(not(Py_LIMITED_API))]
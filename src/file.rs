@@ -0,0 +1,778 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Python file objects over Rust streams, and Rust streams over Python file objects.
+//!
+//! [`RustFile`] exposes a Rust `Read`, `Write` and/or `Seek` to Python as a binary file object
+//! with `read`, `readinto`, `write`, `seek`, `tell`, `flush` and `close`. It's a virtual subclass
+//! of `io.RawIOBase`, so `io.BufferedReader(f)` and `io.TextIOWrapper` add buffering, `readline`
+//! and text decoding. The I/O runs without the GIL.
+//!
+//! ```
+//! use pyo3::file::RustFile;
+//! use pyo3::prelude::*;
+//! use pyo3::py_run;
+//! use std::io::Cursor;
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let data = Cursor::new(b"first\nsecond\n".to_vec());
+//! let file: PyObject = RustFile::seekable_reader(data).into_py(py);
+//! py_run!(py, file, r#"
+//!     import io
+//!     assert file.read(5) == b"first"
+//!     file.seek(0)
+//!     assert io.TextIOWrapper(io.BufferedReader(file)).readlines() == ["first\n", "second\n"]
+//! "#);
+//! ```
+//!
+//! [`PyFileObject`] goes the other way: it implements `Read`, `Write` and `Seek` by calling the
+//! `read`, `write`, `flush` and `seek` methods of a Python binary file object, e.g. a file opened
+//! in Python, an `io.BytesIO` or `socket.makefile("rb")`, so Rust code can stream from and to it.
+//! Each call acquires the GIL.
+//!
+//! [`RustFile`]: struct.RustFile.html
+//! [`PyFileObject`]: struct.PyFileObject.html
+
+use crate::buffer::PyBuffer;
+use crate::callback::{handle_callback, PyObjectCallbackConverter};
+use crate::class::methods::{
+    PyGetterDef, PyMethodDef, PyMethodDefType, PyMethodType, PyMethodsProtocol,
+};
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::{BufferError, TypeError, ValueError};
+use crate::objectprotocol::ObjectProtocol;
+use crate::pyclass::{PyClass, PyClassAlloc, PyClassShell};
+use crate::pyclass_slots::PyClassDummySlot;
+use crate::type_object::{LazyStaticType, PyTypeInfo};
+use crate::types::{PyAny, PyBytes, PyTuple};
+use crate::{ffi, AsPyPointer, FromPyObject, FromPyPointer, IntoPy, Py, PyObject, Python};
+use parking_lot::Mutex;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::raw::c_void;
+use std::slice;
+use std::sync::Once;
+
+/// A binary file object reading from and/or writing to a Rust stream.
+///
+/// The constructors decide which of `read`, `write` and `seek` are supported, the others raise
+/// `io.UnsupportedOperation`. `close()` flushes and drops the stream.
+pub struct RustFile {
+    // `None` once the file is closed
+    stream: Mutex<Option<Box<dyn RawStream>>>,
+}
+
+impl RustFile {
+    /// A file reading from `reader`.
+    pub fn reader<R: Read + Send + 'static>(reader: R) -> Self {
+        let mut stream = Stream::new(reader);
+        stream.read = Some((R::read, R::read_to_end));
+        RustFile::from_stream(stream)
+    }
+
+    /// A file writing to `writer`.
+    pub fn writer<W: Write + Send + 'static>(writer: W) -> Self {
+        let mut stream = Stream::new(writer);
+        stream.write = Some((W::write_all, W::flush));
+        RustFile::from_stream(stream)
+    }
+
+    /// A file reading from `reader`, which can seek.
+    pub fn seekable_reader<R: Read + Seek + Send + 'static>(reader: R) -> Self {
+        let mut stream = Stream::new(reader);
+        stream.read = Some((R::read, R::read_to_end));
+        stream.seek = Some(R::seek);
+        RustFile::from_stream(stream)
+    }
+
+    /// A file writing to `writer`, which can seek.
+    pub fn seekable_writer<W: Write + Seek + Send + 'static>(writer: W) -> Self {
+        let mut stream = Stream::new(writer);
+        stream.write = Some((W::write_all, W::flush));
+        stream.seek = Some(W::seek);
+        RustFile::from_stream(stream)
+    }
+
+    /// A file reading from and writing to `file`, which can seek, like a `std::fs::File`.
+    pub fn read_write<F: Read + Write + Seek + Send + 'static>(file: F) -> Self {
+        let mut stream = Stream::new(file);
+        stream.read = Some((F::read, F::read_to_end));
+        stream.write = Some((F::write_all, F::flush));
+        stream.seek = Some(F::seek);
+        RustFile::from_stream(stream)
+    }
+
+    fn from_stream<T: Send + 'static>(stream: Stream<T>) -> Self {
+        RustFile {
+            stream: Mutex::new(Some(Box::new(stream))),
+        }
+    }
+
+    /// Runs `f` on the stream without the GIL. `f` returns `None` if the stream doesn't support
+    /// the operation `name`.
+    fn with_stream<T, F>(&self, py: Python, name: &str, f: F) -> PyResult<T>
+    where
+        T: Send,
+        F: FnOnce(&mut dyn RawStream) -> Option<io::Result<T>> + Send,
+    {
+        // The lock is released before the GIL is acquired again, so threads waiting for the lock
+        // with the GIL can't block this one
+        let result =
+            py.allow_threads(|| self.stream.lock().as_mut().map(|stream| f(&mut **stream)));
+        match result {
+            Some(Some(result)) => result.map_err(PyErr::from),
+            Some(None) => Err(unsupported(py, name)),
+            None => Err(closed()),
+        }
+    }
+
+    /// Returns `flag` of the stream, or raises `ValueError` if the file is closed.
+    fn check(&self, flag: impl FnOnce(&dyn RawStream) -> bool) -> PyResult<bool> {
+        match *self.stream.lock() {
+            Some(ref stream) => Ok(flag(&**stream)),
+            None => Err(closed()),
+        }
+    }
+
+    fn read(&self, py: Python, size: Option<usize>) -> PyResult<PyObject> {
+        let mut data = Vec::new();
+        match size {
+            Some(size) => {
+                data.resize(size, 0);
+                let read = self.with_stream(py, "read", |stream| stream.read(&mut data))?;
+                data.truncate(read);
+            }
+            None => {
+                self.with_stream(py, "read", |stream| stream.read_to_end(&mut data))?;
+            }
+        }
+        Ok(PyBytes::new(py, &data).into())
+    }
+
+    fn readinto(&self, py: Python, buffer: &PyAny) -> PyResult<PyObject> {
+        let buffer = contiguous_buffer(py, buffer)?;
+        if buffer.readonly() {
+            return Err(TypeError::py_err(
+                "readinto() argument must be a writable bytes-like object",
+            ));
+        }
+        // The buffer stays exported, so its memory can't move while the GIL is released
+        let (ptr, len) = (buffer.buf_ptr() as usize, buffer.len_bytes());
+        let read = self.with_stream(py, "readinto", move |stream| {
+            stream.read(unsafe { slice::from_raw_parts_mut(ptr as *mut u8, len) })
+        });
+        buffer.release(py);
+        Ok(read?.into_py(py))
+    }
+
+    fn write(&self, py: Python, data: &PyAny) -> PyResult<PyObject> {
+        let buffer = contiguous_buffer(py, data)?;
+        let (ptr, len) = (buffer.buf_ptr() as usize, buffer.len_bytes());
+        let written = self.with_stream(py, "write", move |stream| {
+            stream.write_all(unsafe { slice::from_raw_parts(ptr as *const u8, len) })
+        });
+        buffer.release(py);
+        written?;
+        Ok(len.into_py(py))
+    }
+
+    fn seek(&self, py: Python, offset: i64, whence: i32) -> PyResult<PyObject> {
+        let pos = match whence {
+            0 if offset < 0 => {
+                return Err(ValueError::py_err(format!(
+                    "negative seek position {}",
+                    offset
+                )))
+            }
+            0 => SeekFrom::Start(offset as u64),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => {
+                return Err(ValueError::py_err(format!(
+                    "invalid whence ({}, should be 0, 1 or 2)",
+                    whence
+                )))
+            }
+        };
+        let pos = self.with_stream(py, "seek", |stream| stream.seek(pos))?;
+        Ok(pos.into_py(py))
+    }
+
+    fn close(&self, py: Python) -> PyResult<()> {
+        let stream = self.stream.lock().take();
+        match stream {
+            // Flushing and dropping, e.g. a `BufWriter`, may block
+            Some(mut stream) => py
+                .allow_threads(move || {
+                    let result = stream.flush();
+                    drop(stream);
+                    result
+                })
+                .map_err(PyErr::from),
+            None => Ok(()),
+        }
+    }
+}
+
+impl IntoPy<PyObject> for RustFile {
+    fn into_py(self, py: Python) -> PyObject {
+        let file = Py::new(py, self).unwrap().into_py(py);
+        register_raw_io(py);
+        file
+    }
+}
+
+/// Registers `RustFile` as a virtual subclass of `io.RawIOBase`.
+fn register_raw_io(py: Python) {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        let ty = py.get_type::<RustFile>();
+        py.import("io")
+            .and_then(|io| io.getattr("RawIOBase"))
+            .and_then(|base| base.call_method1("register", (ty,)))
+            .expect("failed to register RustFile as io.RawIOBase");
+    });
+}
+
+fn contiguous_buffer(py: Python, obj: &PyAny) -> PyResult<PyBuffer> {
+    let buffer = PyBuffer::get(py, obj)?;
+    if !buffer.is_c_contiguous() {
+        buffer.release(py);
+        return Err(BufferError::py_err("the buffer is not contiguous"));
+    }
+    Ok(buffer)
+}
+
+fn closed() -> PyErr {
+    ValueError::py_err("I/O operation on closed file.")
+}
+
+fn unsupported(py: Python, name: &str) -> PyErr {
+    let err = py
+        .import("io")
+        .and_then(|io| io.call1("UnsupportedOperation", (name,)));
+    match err {
+        Ok(err) => PyErr::from_instance(err),
+        Err(e) => e,
+    }
+}
+
+/// The operations of a Rust stream, `None` if they aren't supported.
+trait RawStream: Send {
+    fn readable(&self) -> bool;
+    fn writable(&self) -> bool;
+    fn seekable(&self) -> bool;
+    fn read(&mut self, buf: &mut [u8]) -> Option<io::Result<usize>>;
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Option<io::Result<usize>>;
+    fn write_all(&mut self, buf: &[u8]) -> Option<io::Result<()>>;
+    fn seek(&mut self, pos: SeekFrom) -> Option<io::Result<u64>>;
+    /// Flushes written data, and does nothing for streams which can't write.
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+type ReadFns<T> = (
+    fn(&mut T, &mut [u8]) -> io::Result<usize>,
+    fn(&mut T, &mut Vec<u8>) -> io::Result<usize>,
+);
+type WriteFns<T> = (
+    fn(&mut T, &[u8]) -> io::Result<()>,
+    fn(&mut T) -> io::Result<()>,
+);
+
+/// A stream with the trait methods it implements, set by the constructors of `RustFile`.
+struct Stream<T> {
+    inner: T,
+    read: Option<ReadFns<T>>,
+    write: Option<WriteFns<T>>,
+    seek: Option<fn(&mut T, SeekFrom) -> io::Result<u64>>,
+}
+
+impl<T> Stream<T> {
+    fn new(inner: T) -> Self {
+        Stream {
+            inner,
+            read: None,
+            write: None,
+            seek: None,
+        }
+    }
+}
+
+impl<T: Send> RawStream for Stream<T> {
+    fn readable(&self) -> bool {
+        self.read.is_some()
+    }
+
+    fn writable(&self) -> bool {
+        self.write.is_some()
+    }
+
+    fn seekable(&self) -> bool {
+        self.seek.is_some()
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Option<io::Result<usize>> {
+        self.read.map(|(read, _)| read(&mut self.inner, buf))
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Option<io::Result<usize>> {
+        self.read
+            .map(|(_, read_to_end)| read_to_end(&mut self.inner, buf))
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> Option<io::Result<()>> {
+        self.write
+            .map(|(write_all, _)| write_all(&mut self.inner, buf))
+    }
+
+    fn seek(&mut self, pos: SeekFrom) -> Option<io::Result<u64>> {
+        self.seek.map(|seek| seek(&mut self.inner, pos))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.write {
+            Some((_, flush)) => flush(&mut self.inner),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Calls `body` with the `RustFile` behind `slf`.
+fn method<F>(location: &str, slf: *mut ffi::PyObject, body: F) -> *mut ffi::PyObject
+where
+    F: FnOnce(Python, &RustFile) -> PyResult<PyObject>,
+{
+    handle_callback(location, PyObjectCallbackConverter, |py| {
+        let shell: &PyClassShell<RustFile> = unsafe { FromPyPointer::from_borrowed_ptr(py, slf) };
+        body(py, &*shell.try_borrow()?)
+    })
+}
+
+/// Returns the positional arguments of a `METH_VARARGS` method taking at most `max`.
+unsafe fn varargs<'p>(
+    py: Python<'p>,
+    args: *mut ffi::PyObject,
+    name: &str,
+    max: usize,
+) -> PyResult<&'p PyTuple> {
+    let args: &PyTuple = py.from_borrowed_ptr(args);
+    if args.len() > max {
+        return Err(TypeError::py_err(format!(
+            "{}() takes at most {} arguments ({} given)",
+            name,
+            max,
+            args.len()
+        )));
+    }
+    Ok(args)
+}
+
+unsafe extern "C" fn read(slf: *mut ffi::PyObject, args: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    method("RustFile.read", slf, |py, file| {
+        let args = varargs(py, args, "read", 1)?;
+        let size: Option<i64> = match args.len() {
+            0 => None,
+            _ => args.get_item(0).extract()?,
+        };
+        // A negative size reads everything, like `None`
+        file.read(py, size.filter(|&size| size >= 0).map(|size| size as usize))
+    })
+}
+
+unsafe extern "C" fn readall(slf: *mut ffi::PyObject, _: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    method("RustFile.readall", slf, |py, file| file.read(py, None))
+}
+
+unsafe extern "C" fn readinto(
+    slf: *mut ffi::PyObject,
+    arg: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    method("RustFile.readinto", slf, |py, file| {
+        file.readinto(py, py.from_borrowed_ptr(arg))
+    })
+}
+
+unsafe extern "C" fn write(slf: *mut ffi::PyObject, arg: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    method("RustFile.write", slf, |py, file| {
+        file.write(py, py.from_borrowed_ptr(arg))
+    })
+}
+
+unsafe extern "C" fn seek(slf: *mut ffi::PyObject, args: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    method("RustFile.seek", slf, |py, file| {
+        let args = varargs(py, args, "seek", 2)?;
+        if args.is_empty() {
+            return Err(TypeError::py_err("seek() missing the offset argument"));
+        }
+        let offset: i64 = args.get_item(0).extract()?;
+        let whence: i32 = match args.len() {
+            1 => 0,
+            _ => args.get_item(1).extract()?,
+        };
+        file.seek(py, offset, whence)
+    })
+}
+
+unsafe extern "C" fn tell(slf: *mut ffi::PyObject, _: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    method("RustFile.tell", slf, |py, file| file.seek(py, 0, 1))
+}
+
+unsafe extern "C" fn flush(slf: *mut ffi::PyObject, _: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    method("RustFile.flush", slf, |py, file| {
+        file.with_stream(py, "flush", |stream| Some(stream.flush()))?;
+        Ok(py.None())
+    })
+}
+
+unsafe extern "C" fn close(slf: *mut ffi::PyObject, _: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    method("RustFile.close", slf, |py, file| {
+        file.close(py)?;
+        Ok(py.None())
+    })
+}
+
+unsafe extern "C" fn readable(
+    slf: *mut ffi::PyObject,
+    _: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    method("RustFile.readable", slf, |py, file| {
+        Ok(file.check(|stream| stream.readable())?.into_py(py))
+    })
+}
+
+unsafe extern "C" fn writable(
+    slf: *mut ffi::PyObject,
+    _: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    method("RustFile.writable", slf, |py, file| {
+        Ok(file.check(|stream| stream.writable())?.into_py(py))
+    })
+}
+
+unsafe extern "C" fn seekable(
+    slf: *mut ffi::PyObject,
+    _: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    method("RustFile.seekable", slf, |py, file| {
+        Ok(file.check(|stream| stream.seekable())?.into_py(py))
+    })
+}
+
+unsafe extern "C" fn isatty(slf: *mut ffi::PyObject, _: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    method("RustFile.isatty", slf, |py, file| {
+        Ok(file.check(|_| false)?.into_py(py))
+    })
+}
+
+unsafe extern "C" fn fileno(slf: *mut ffi::PyObject, _: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    method("RustFile.fileno", slf, |py, _| {
+        Err(unsupported(py, "fileno"))
+    })
+}
+
+unsafe extern "C" fn enter(slf: *mut ffi::PyObject, _: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    method("RustFile.__enter__", slf, |py, file| {
+        file.check(|_| true)?;
+        Ok(PyObject::from_borrowed_ptr(py, slf))
+    })
+}
+
+unsafe extern "C" fn exit(slf: *mut ffi::PyObject, _: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    method("RustFile.__exit__", slf, |py, file| {
+        file.close(py)?;
+        Ok(py.None())
+    })
+}
+
+unsafe extern "C" fn get_closed(slf: *mut ffi::PyObject, _: *mut c_void) -> *mut ffi::PyObject {
+    method("RustFile.closed", slf, |py, file| {
+        Ok(file.stream.lock().is_none().into_py(py))
+    })
+}
+
+macro_rules! file_method {
+    ($name: expr, $meth: ident, $flags: expr) => {
+        PyMethodDefType::Method(PyMethodDef {
+            ml_name: $name,
+            ml_meth: PyMethodType::PyCFunction($meth),
+            ml_flags: $flags,
+            ml_doc: "\0",
+        })
+    };
+}
+
+static METHODS: [PyMethodDefType; 17] = [
+    file_method!("read", read, ffi::METH_VARARGS),
+    file_method!("readall", readall, ffi::METH_NOARGS),
+    file_method!("readinto", readinto, ffi::METH_O),
+    file_method!("write", write, ffi::METH_O),
+    file_method!("seek", seek, ffi::METH_VARARGS),
+    file_method!("tell", tell, ffi::METH_NOARGS),
+    file_method!("flush", flush, ffi::METH_NOARGS),
+    file_method!("close", close, ffi::METH_NOARGS),
+    file_method!("readable", readable, ffi::METH_NOARGS),
+    file_method!("writable", writable, ffi::METH_NOARGS),
+    file_method!("seekable", seekable, ffi::METH_NOARGS),
+    file_method!("isatty", isatty, ffi::METH_NOARGS),
+    file_method!("fileno", fileno, ffi::METH_NOARGS),
+    file_method!("__enter__", enter, ffi::METH_NOARGS),
+    file_method!("__exit__", exit, ffi::METH_VARARGS),
+    PyMethodDefType::Getter(PyGetterDef {
+        name: "closed",
+        meth: get_closed,
+        doc: "True if the file is closed.\0",
+    }),
+    PyMethodDefType::Getter(PyGetterDef {
+        name: "mode",
+        meth: get_mode,
+        doc: "The mode of the file, like 'rb', 'wb' or 'rb+'.\0",
+    }),
+];
+
+unsafe extern "C" fn get_mode(slf: *mut ffi::PyObject, _: *mut c_void) -> *mut ffi::PyObject {
+    method("RustFile.mode", slf, |py, file| {
+        let readable = file.check(|stream| stream.readable())?;
+        let writable = file.check(|stream| stream.writable())?;
+        let mode = match (readable, writable) {
+            (true, true) => "rb+",
+            (false, true) => "wb",
+            _ => "rb",
+        };
+        Ok(mode.into_py(py))
+    })
+}
+
+unsafe impl PyTypeInfo for RustFile {
+    type Type = RustFile;
+    type BaseType = PyAny;
+    type ConcreteLayout = PyClassShell<Self>;
+    type Initializer = crate::pyclass_init::PyClassInitializer<Self>;
+    type AsRefTarget = crate::pyclass::PyClassShell<Self>;
+
+    const NAME: &'static str = "RustFile";
+    const MODULE: Option<&'static str> = None;
+    const DESCRIPTION: &'static str = "A binary file object over a Rust stream\0";
+    const FLAGS: usize = 0;
+
+    #[inline]
+    fn type_object() -> &'static ffi::PyTypeObject {
+        static TYPE_OBJECT: LazyStaticType = LazyStaticType::new();
+        TYPE_OBJECT.get_or_init::<Self>()
+    }
+}
+
+impl PyClass for RustFile {
+    type Dict = PyClassDummySlot;
+    type WeakRef = PyClassDummySlot;
+}
+
+impl PyClassAlloc for RustFile {}
+
+impl PyMethodsProtocol for RustFile {
+    fn py_methods() -> Vec<&'static PyMethodDefType> {
+        METHODS.iter().collect()
+    }
+}
+
+/// A Rust stream over a Python binary file object.
+///
+/// `Read` calls `read(n)`, `Write` calls `write(data)` and `flush()`, and `Seek` calls
+/// `seek(offset, whence)`. Python exceptions become `io::Error`s; a `read` or `write` returning
+/// `None`, like a non-blocking raw stream without data, returns `io::ErrorKind::WouldBlock`.
+#[derive(Debug)]
+pub struct PyFileObject {
+    file: PyObject,
+}
+
+impl PyFileObject {
+    /// Wraps the Python file object `file`.
+    pub fn new(file: PyObject) -> Self {
+        PyFileObject { file }
+    }
+
+    /// Returns the Python file object.
+    pub fn into_inner(self) -> PyObject {
+        self.file
+    }
+}
+
+impl<'source> FromPyObject<'source> for PyFileObject {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        Ok(PyFileObject::new(obj.into()))
+    }
+}
+
+impl AsPyPointer for PyFileObject {
+    fn as_ptr(&self) -> *mut ffi::PyObject {
+        self.file.as_ptr()
+    }
+}
+
+fn would_block(method: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::WouldBlock,
+        format!("{}() returned None", method),
+    )
+}
+
+impl Read for PyFileObject {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let data = self.file.call_method1(py, "read", (buf.len(),))?;
+        if data.is_none() {
+            return Err(would_block("read"));
+        }
+        let data: &PyBytes = data.cast_as(py).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "read() didn't return bytes")
+        })?;
+        let data = data.as_bytes();
+        if data.len() > buf.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "read() returned more bytes than requested",
+            ));
+        }
+        buf[..data.len()].copy_from_slice(data);
+        Ok(data.len())
+    }
+}
+
+impl Write for PyFileObject {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let written = self
+            .file
+            .call_method1(py, "write", (PyBytes::new(py, buf),))?;
+        if written.is_none() {
+            return Err(would_block("write"));
+        }
+        Ok(written.extract(py)?)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let gil = Python::acquire_gil();
+        self.file.call_method0(gil.python(), "flush")?;
+        Ok(())
+    }
+}
+
+impl Seek for PyFileObject {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as i64, 0),
+            SeekFrom::Current(offset) => (offset, 1),
+            SeekFrom::End(offset) => (offset, 2),
+        };
+        let pos = self.file.call_method1(py, "seek", (offset, whence))?;
+        Ok(pos.extract(py)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PyFileObject, RustFile};
+    use crate::types::IntoPyDict;
+    use crate::{IntoPy, PyObject, Python};
+    use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_rust_file() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let buffer = SharedBuffer::default();
+        let reader: PyObject =
+            RustFile::seekable_reader(Cursor::new(b"0123456789".to_vec())).into_py(py);
+        let writer: PyObject = RustFile::writer(buffer.clone()).into_py(py);
+        let rw: PyObject = RustFile::read_write(Cursor::new(Vec::new())).into_py(py);
+        let locals = [("reader", reader), ("writer", writer), ("rw", rw)].into_py_dict(py);
+        py.run(
+            r#"
+import io
+assert isinstance(reader, io.RawIOBase)
+assert reader.readable() and reader.seekable() and not reader.writable()
+assert reader.mode == "rb" and writer.mode == "wb" and rw.mode == "rb+"
+assert reader.read(3) == b"012"
+assert reader.tell() == 3
+b = bytearray(4)
+assert reader.readinto(b) == 4 and b == b"3456"
+assert reader.read() == b"789"
+assert reader.seek(-2, 2) == 8
+assert reader.read(-1) == b"89"
+try:
+    reader.write(b"x")
+    raise AssertionError("wrote to a reader")
+except io.UnsupportedOperation:
+    pass
+with reader:
+    pass
+assert reader.closed
+try:
+    reader.read()
+    raise AssertionError("read a closed file")
+except ValueError:
+    pass
+
+assert writer.write(b"abc") == 3
+assert writer.write(memoryview(b"def")) == 3
+try:
+    writer.seek(0)
+    raise AssertionError("seeked a writer")
+except io.UnsupportedOperation:
+    pass
+text = io.TextIOWrapper(io.BufferedWriter(writer), encoding="utf-8")
+text.write("ghi")
+text.close()
+assert writer.closed
+
+rw.write(b"hello")
+rw.seek(0)
+assert rw.read() == b"hello"
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        assert_eq!(&*buffer.0.lock().unwrap(), b"abcdefghi");
+    }
+
+    #[test]
+    fn test_py_file_object() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let bytes_io = py.import("io").unwrap().call0("BytesIO").unwrap();
+        let mut file = PyFileObject::new(bytes_io.into());
+
+        file.write_all(b"hello world").unwrap();
+        file.flush().unwrap();
+        assert_eq!(file.seek(SeekFrom::Start(6)).unwrap(), 6);
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "world");
+
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut head = [0; 5];
+        file.read_exact(&mut head).unwrap();
+        assert_eq!(&head, b"hello");
+
+        let text_io = py.import("io").unwrap().call0("StringIO").unwrap();
+        let mut file = PyFileObject::new(text_io.into());
+        assert!(file.write(b"text").is_err());
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Running Rust code around `os.fork()`, for extensions with background threads or locks that
+//! are imported by forking servers like gunicorn or celery.
+//!
+//! A forked child process only has the thread which called `fork()`. Threads started by the
+//! extension are gone in the child, and locks they held stay locked forever. [`register_at_fork`]
+//! registers handlers with `os.register_at_fork`, so the extension can stop its threads before
+//! forking and start them again in the child:
+//!
+//! ```
+//! use pyo3::fork::{register_at_fork, ForkHandlers};
+//! use pyo3::prelude::*;
+//! use pyo3::register_initializer;
+//!
+//! fn restart_workers(_py: Python) {
+//!     // e.g. replace the global thread pool with a new one
+//! }
+//!
+//! fn setup(py: Python) -> PyResult<()> {
+//!     register_at_fork(
+//!         py,
+//!         ForkHandlers {
+//!             after_in_child: Some(restart_workers),
+//!             ..ForkHandlers::default()
+//!         },
+//!     )?;
+//!     Ok(())
+//! }
+//!
+//! register_initializer!(setup);
+//! # fn main() {}
+//! ```
+//!
+//! The handlers only run for forks through `os.fork()` and the functions using it, like
+//! `multiprocessing` with the `fork` start method, but not for forks by C code calling `fork()`
+//! directly. Handlers can't be unregistered, so they should be registered once, like with an
+//! [initializer](../initializer/index.html) above, rather than for every module object.
+//!
+//! [`register_at_fork`]: fn.register_at_fork.html
+
+use crate::callback::{handle_callback, PyObjectCallbackConverter};
+use crate::class::methods::{PyMethodDef, PyMethodType};
+use crate::err::{PyErr, PyResult};
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::PyDict;
+use crate::{ffi, AsPyPointer, PyObject, Python};
+use std::os::raw::c_void;
+
+const CAPSULE_NAME: &[u8] = b"pyo3.fork_handler\0";
+
+/// The functions run around `os.fork()`. They're called with the GIL held.
+#[derive(Clone, Copy, Default)]
+pub struct ForkHandlers {
+    /// Runs in the parent before forking, e.g. to stop threads or acquire locks.
+    pub before: Option<fn(Python)>,
+    /// Runs in the parent after forking, e.g. to restart threads or release locks.
+    pub after_in_parent: Option<fn(Python)>,
+    /// Runs in the child after forking, e.g. to reinitialize locks and start new threads.
+    pub after_in_child: Option<fn(Python)>,
+}
+
+/// Registers `handlers` with `os.register_at_fork`.
+///
+/// Returns `false` without registering them when the interpreter doesn't support fork
+/// handlers, i.e. on Windows and before Python 3.7.
+pub fn register_at_fork(py: Python, handlers: ForkHandlers) -> PyResult<bool> {
+    let os = py.import("os")?;
+    if !os.hasattr("register_at_fork")? {
+        return Ok(false);
+    }
+    let kwargs = PyDict::new(py);
+    if let Some(before) = handlers.before {
+        kwargs.set_item("before", make_handler(py, before)?)?;
+    }
+    if let Some(after_in_parent) = handlers.after_in_parent {
+        kwargs.set_item("after_in_parent", make_handler(py, after_in_parent)?)?;
+    }
+    if let Some(after_in_child) = handlers.after_in_child {
+        kwargs.set_item("after_in_child", make_handler(py, after_in_child)?)?;
+    }
+    if !kwargs.is_empty() {
+        os.call("register_at_fork", (), Some(kwargs))?;
+    }
+    Ok(true)
+}
+
+/// Creates a builtin function calling `handler`, which is stored in a capsule passed as `self`.
+fn make_handler(py: Python, handler: fn(Python)) -> PyResult<PyObject> {
+    unsafe extern "C" fn call_handler(
+        capsule: *mut ffi::PyObject,
+        _args: *mut ffi::PyObject,
+    ) -> *mut ffi::PyObject {
+        handle_callback("fork_handler", PyObjectCallbackConverter, |py| {
+            let handler = ffi::PyCapsule_GetPointer(capsule, CAPSULE_NAME.as_ptr() as *const _);
+            let handler: fn(Python) = std::mem::transmute(handler);
+            handler(py);
+            Ok(())
+        })
+    }
+
+    let capsule = unsafe {
+        PyObject::from_owned_ptr_or_err(
+            py,
+            ffi::PyCapsule_New(
+                handler as *mut c_void,
+                CAPSULE_NAME.as_ptr() as *const _,
+                None,
+            ),
+        )?
+    };
+    let def = PyMethodDef {
+        ml_name: "fork_handler",
+        ml_meth: PyMethodType::PyCFunction(call_handler),
+        ml_flags: ffi::METH_NOARGS,
+        ml_doc: "\0",
+    };
+    unsafe {
+        let function = ffi::PyCFunction_New(
+            Box::into_raw(Box::new(def.as_method_def())),
+            capsule.as_ptr(),
+        );
+        if function.is_null() {
+            return Err(PyErr::fetch(py));
+        }
+        Ok(PyObject::from_owned_ptr(py, function))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{register_at_fork, ForkHandlers};
+    use crate::types::IntoPyDict;
+    use crate::{ObjectProtocol, Python};
+    use std::process::Command;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static BEFORE: AtomicUsize = AtomicUsize::new(0);
+    static AFTER_IN_PARENT: AtomicUsize = AtomicUsize::new(0);
+
+    fn before(_py: Python) {
+        BEFORE.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn after_in_parent(_py: Python) {
+        AFTER_IN_PARENT.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn after_in_child(py: Python) {
+        py.import("sys")
+            .unwrap()
+            .setattr("_pyo3_test_forked", true)
+            .unwrap();
+    }
+
+    fn panicking(_py: Python) {
+        panic!("fork handler failed");
+    }
+
+    #[test]
+    fn test_register_at_fork() {
+        // Forking the test harness, which runs other tests on its threads, isn't safe, so the
+        // test forks in a new process of the test binary running only this test
+        if std::env::var_os("PYO3_TEST_FORK").is_none() {
+            let output = Command::new(std::env::current_exe().unwrap())
+                .args(&["--exact", "fork::test::test_register_at_fork"])
+                .args(&["--test-threads", "1", "--nocapture"])
+                .env("PYO3_TEST_FORK", "1")
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(
+                output.status.success() && stdout.contains("1 passed"),
+                "{}{}",
+                stdout,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            return;
+        }
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let handlers = ForkHandlers {
+            before: Some(before),
+            after_in_parent: Some(after_in_parent),
+            after_in_child: Some(after_in_child),
+        };
+        if !register_at_fork(py, handlers).unwrap() {
+            // Windows or Python 3.6
+            assert!(!py
+                .import("os")
+                .unwrap()
+                .hasattr("register_at_fork")
+                .unwrap());
+            return;
+        }
+        let failing = ForkHandlers {
+            before: Some(panicking),
+            ..ForkHandlers::default()
+        };
+        assert!(register_at_fork(py, failing).unwrap());
+
+        // The child reports whether its handler ran with its exit code. Python reports errors of
+        // fork handlers as unraisable and forks anyway.
+        let locals = [("os", py.import("os").unwrap())].into_py_dict(py);
+        py.run(
+            r#"
+import sys
+unraisable = []
+hook = sys.unraisablehook
+sys.unraisablehook = unraisable.append
+try:
+    pid = os.fork()
+    if pid == 0:
+        os._exit(0 if getattr(sys, "_pyo3_test_forked", False) else 1)
+finally:
+    sys.unraisablehook = hook
+_, status = os.waitpid(pid, 0)
+assert os.WIFEXITED(status) and os.WEXITSTATUS(status) == 0, status
+assert len(unraisable) == 1 and isinstance(unraisable[0].exc_value, RuntimeError), unraisable
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        assert_eq!(BEFORE.load(Ordering::SeqCst), 1);
+        assert_eq!(AFTER_IN_PARENT.load(Ordering::SeqCst), 1);
+        assert!(!py
+            .import("sys")
+            .unwrap()
+            .hasattr("_pyo3_test_forked")
+            .unwrap());
+    }
+}
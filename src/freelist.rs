@@ -28,7 +28,7 @@ pub struct FreeList<T> {
 }
 
 impl<T> FreeList<T> {
-    /// Create new `FreeList` instance with specified capacity
+    /// Create new `FreeList` instance, which holds up to `capacity` values
     pub fn with_capacity(capacity: usize) -> FreeList<T> {
         let entries = (0..capacity).map(|_| Slot::Empty).collect::<Vec<_>>();
 
@@ -55,12 +55,11 @@ impl<T> FreeList<T> {
         }
     }
 
-    /// Insert a value into the list
+    /// Insert a value into the list, or return it if the list is full
     pub fn insert(&mut self, val: T) -> Option<T> {
-        let next = self.split + 1;
-        if next < self.capacity {
+        if self.split < self.capacity {
             self.entries[self.split] = Slot::Filled(val);
-            self.split = next;
+            self.split += 1;
             None
         } else {
             Some(val)
@@ -74,20 +73,38 @@ where
 {
     unsafe fn alloc(_py: Python) -> *mut Self::ConcreteLayout {
         if let Some(obj) = <Self as PyClassWithFreeList>::get_free_list().pop() {
-            ffi::PyObject_Init(obj, <Self as PyTypeInfo>::type_object() as *const _ as _);
+            let ty = <Self as PyTypeInfo>::type_object() as *const _ as _;
+            ffi::PyObject_Init(obj, ty);
             obj as _
         } else {
-            crate::pyclass::default_alloc::<Self>() as _
+            let obj = crate::pyclass::default_alloc::<Self>();
+            // Like objects from the free list, it's tracked once it's initialized
+            if !obj.is_null() && ffi::PyType_IS_GC(ffi::Py_TYPE(obj)) != 0 {
+                ffi::PyObject_GC_UnTrack(obj as *mut c_void);
+            }
+            obj as _
         }
     }
 
-    unsafe fn dealloc(py: Python, self_: *mut Self::ConcreteLayout) {
-        (*self_).py_drop(py);
+    unsafe fn initialized(_py: Python, self_: *mut Self::ConcreteLayout) {
+        // The GC must not traverse the contents left by the previous user of the object
+        let obj = self_ as *mut ffi::PyObject;
+        if ffi::PyType_IS_GC(ffi::Py_TYPE(obj)) != 0 {
+            ffi::PyObject_GC_Track(obj as *mut c_void);
+        }
+    }
 
+    unsafe fn dealloc(py: Python, self_: *mut Self::ConcreteLayout) {
         let obj = self_ as _;
+        // The finalizer sees the object intact, and may resurrect it
         if ffi::PyObject_CallFinalizerFromDealloc(obj) < 0 {
             return;
         }
+        // The GC must neither traverse the object while it is dropped nor while it is in the list
+        if ffi::PyType_IS_GC(ffi::Py_TYPE(obj)) != 0 {
+            ffi::PyObject_GC_UnTrack(obj as *mut c_void);
+        }
+        (*self_).py_drop(py);
 
         if let Some(obj) = <Self as PyClassWithFreeList>::get_free_list().insert(obj) {
             match Self::type_object().tp_free {
@@ -97,3 +114,19 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::FreeList;
+
+    #[test]
+    fn test_free_list_capacity() {
+        let mut list = FreeList::with_capacity(2);
+        assert_eq!(list.insert(1), None);
+        assert_eq!(list.insert(2), None);
+        assert_eq!(list.insert(3), Some(3));
+        assert_eq!(list.pop(), Some(2));
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), None);
+    }
+}
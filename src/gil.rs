@@ -77,6 +77,20 @@ pub fn prepare_freethreaded_python() {
     });
 }
 
+/// Returns `false` once the Python interpreter has been finalized with `Py_Finalize`, or before
+/// it's initialized.
+///
+/// Threads outliving the interpreter, like daemon threads of an embedding application during
+/// process exit, must check this before acquiring the GIL. The interpreter already counts as
+/// finalized while `Py_Finalize` tears down modules.
+///
+/// Dropping a `PyObject` or `Py<T>` while the interpreter isn't alive leaks the reference
+/// instead of releasing it later, when no interpreter can deallocate the object. The
+/// `debug-gil-checks` feature logs each leak to stderr.
+pub fn is_interpreter_alive() -> bool {
+    unsafe { ffi::Py_IsInitialized() != 0 }
+}
+
 #[doc(hidden)]
 pub fn init_once() {
     START_PYO3.call_once(|| unsafe {
@@ -217,6 +231,13 @@ impl<'p> Drop for GILPool<'p> {
     }
 }
 
+/// The number of owned references in the release pool, for tests of conversions which shouldn't
+/// register temporary objects.
+#[cfg(test)]
+pub(crate) fn owned_count() -> usize {
+    unsafe { (*POOL).owned.len() }
+}
+
 pub unsafe fn register_any<'p, T: 'static>(obj: T) -> &'p T {
     let pool: &'static mut ReleasePool = &mut *POOL;
 
@@ -230,16 +251,22 @@ pub unsafe fn register_any<'p, T: 'static>(obj: T) -> &'p T {
 }
 
 pub unsafe fn register_pointer(obj: NonNull<ffi::PyObject>) {
+    if !is_interpreter_alive() {
+        log_leak(obj);
+        return;
+    }
     let pool = &mut *POOL;
     (**pool.p.lock()).push(obj);
 }
 
 pub unsafe fn register_owned(_py: Python, obj: NonNull<ffi::PyObject>) -> &PyAny {
+    check_gil_held();
     let pool = &mut *POOL;
     &*(pool.owned.push_back(obj) as *const _ as *const PyAny)
 }
 
 pub unsafe fn register_borrowed(_py: Python, obj: NonNull<ffi::PyObject>) -> &PyAny {
+    check_gil_held();
     let pool = &mut *POOL;
     &*(pool.borrowed.push_back(obj) as *const _ as *const PyAny)
 }
@@ -249,11 +276,22 @@ impl GILGuard {
     ///
     /// If the Python runtime is not already initialized, this function will initialize it.
     /// See [prepare_freethreaded_python()](fn.prepare_freethreaded_python.html) for details.
+    ///
+    /// Threads that may outlive the interpreter must check
+    /// [is_interpreter_alive()](fn.is_interpreter_alive.html) first, acquiring the GIL after
+    /// `Py_Finalize` is undefined behavior.
     pub fn acquire() -> GILGuard {
         prepare_freethreaded_python();
 
         unsafe {
-            let gstate = ffi::PyGILState_Ensure(); // acquire GIL
+            let gstate = record_acquisition(|| {
+                let gstate = ffi::PyGILState_Ensure(); // acquire GIL
+                let acquired = match gstate {
+                    ffi::PyGILState_STATE::PyGILState_UNLOCKED => true,
+                    ffi::PyGILState_STATE::PyGILState_LOCKED => false,
+                };
+                (gstate, acquired)
+            });
             let pool: &'static mut ReleasePool = &mut *POOL;
             GILGuard {
                 owned: pool.owned.len(),
@@ -271,6 +309,52 @@ impl GILGuard {
     }
 }
 
+/// Acquires the GIL with `acquire`, which returns whether the GIL wasn't held before.
+///
+/// Records the wait time with the `gil-metrics` feature.
+#[cfg(feature = "gil-metrics")]
+#[inline]
+pub(crate) fn record_acquisition<T>(acquire: impl FnOnce() -> (T, bool)) -> T {
+    crate::gil_metrics::record(acquire)
+}
+
+#[cfg(not(feature = "gil-metrics"))]
+#[inline]
+pub(crate) fn record_acquisition<T>(acquire: impl FnOnce() -> (T, bool)) -> T {
+    acquire().0
+}
+
+/// Panics if the current thread doesn't hold the GIL, so that unsafe code using Python objects
+/// without the GIL fails where it happens instead of corrupting the interpreter.
+///
+/// Only checks with the `debug-gil-checks` feature.
+#[cfg(feature = "debug-gil-checks")]
+#[inline]
+pub(crate) fn check_gil_held() {
+    if unsafe { ffi::PyGILState_Check() } == 0 {
+        panic!("the current thread doesn't hold the GIL");
+    }
+}
+
+#[cfg(not(feature = "debug-gil-checks"))]
+#[inline]
+pub(crate) fn check_gil_held() {}
+
+/// Reports a reference dropped after the interpreter was finalized, which is leaked.
+///
+/// Only logs with the `debug-gil-checks` feature.
+#[cfg(feature = "debug-gil-checks")]
+fn log_leak(obj: NonNull<ffi::PyObject>) {
+    eprintln!(
+        "pyo3: leaking object {:p} dropped after the Python interpreter was finalized",
+        obj.as_ptr()
+    );
+}
+
+#[cfg(not(feature = "debug-gil-checks"))]
+#[inline]
+fn log_leak(_obj: NonNull<ffi::PyObject>) {}
+
 use self::array_list::ArrayList;
 
 mod array_list {
@@ -438,6 +522,23 @@ mod test {
         }
     }
 
+    #[test]
+    #[cfg(not(feature = "convert-trace"))]
+    fn test_with_borrowed_ptr_is_not_registered() {
+        use crate::ToBorrowedObject;
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = get_object();
+
+        unsafe {
+            let p: &'static mut ReleasePool = &mut *POOL;
+            let _pool = GILPool::new(py);
+            let borrowed = p.borrowed.len();
+            obj.with_borrowed_ptr(py, |ptr| assert_eq!(ptr, obj.as_ptr()));
+            assert_eq!(p.borrowed.len(), borrowed);
+        }
+    }
+
     #[test]
     fn test_borrowed_nested() {
         gil::init_once();
@@ -501,4 +602,18 @@ mod test {
             assert_eq!(ffi::Py_REFCNT(obj_ptr), 1);
         }
     }
+
+    #[test]
+    #[cfg(feature = "debug-gil-checks")]
+    fn test_check_gil_held() {
+        gil::init_once();
+        let gil = Python::acquire_gil();
+        let _ = gil.python();
+        // The GIL is held by this thread, not by the new one
+        let result = std::thread::spawn(|| unsafe {
+            Python::assume_gil_acquired();
+        })
+        .join();
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,150 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Metrics about acquiring the GIL, enabled with the `gil-metrics` feature.
+//!
+//! Every time pyo3 acquires the GIL on a thread that didn't hold it, i.e. in
+//! `Python::acquire_gil` and when `Python::allow_threads` returns, the number of acquisitions and
+//! the time spent waiting for the GIL are recorded, both for the current thread and in total.
+//! Comparing the wait time before and after moving code into `allow_threads` shows whether the
+//! released GIL is actually used by other threads or just adds contention.
+//!
+//! ```
+//! use pyo3::gil_metrics;
+//! use pyo3::prelude::*;
+//!
+//! let gil = Python::acquire_gil();
+//! let metrics = gil_metrics::thread_metrics();
+//! println!("{} acquisitions, waited {:?}", metrics.acquisitions, metrics.wait_time);
+//! ```
+//!
+//! `python_function` creates a Python function returning the same numbers, which can be added to a
+//! module with `m.add_wrapped(&pyo3::gil_metrics::python_function)`.
+
+use crate::callback::PyObjectCallbackConverter;
+use crate::err::PyResult;
+use crate::ffi;
+use crate::types::PyDict;
+use crate::{PyObject, Python, ToPyObject};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// The number of times the GIL was acquired and the time spent waiting for it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GilMetrics {
+    pub acquisitions: u64,
+    pub wait_time: Duration,
+}
+
+thread_local! {
+    static THREAD_METRICS: Cell<GilMetrics> = Cell::new(GilMetrics::default());
+}
+
+static TOTAL_ACQUISITIONS: AtomicU64 = AtomicU64::new(0);
+static TOTAL_WAIT_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the metrics of the current thread.
+pub fn thread_metrics() -> GilMetrics {
+    THREAD_METRICS.with(|metrics| metrics.get())
+}
+
+/// Returns the metrics summed over all threads.
+pub fn total_metrics() -> GilMetrics {
+    GilMetrics {
+        acquisitions: TOTAL_ACQUISITIONS.load(Ordering::Relaxed),
+        wait_time: Duration::from_nanos(TOTAL_WAIT_NANOS.load(Ordering::Relaxed)),
+    }
+}
+
+/// Resets the metrics of the current thread and the total metrics.
+///
+/// The metrics of other threads are not reset.
+pub fn reset() {
+    THREAD_METRICS.with(|metrics| metrics.set(GilMetrics::default()));
+    TOTAL_ACQUISITIONS.store(0, Ordering::Relaxed);
+    TOTAL_WAIT_NANOS.store(0, Ordering::Relaxed);
+}
+
+/// Runs `acquire` and records the time it took as GIL wait time.
+///
+/// `acquire` returns whether the GIL was actually acquired, instead of already being held by
+/// the current thread.
+pub(crate) fn record<T>(acquire: impl FnOnce() -> (T, bool)) -> T {
+    let start = Instant::now();
+    let (result, acquired) = acquire();
+    if acquired {
+        let wait_time = start.elapsed();
+        THREAD_METRICS.with(|metrics| {
+            let mut current = metrics.get();
+            current.acquisitions += 1;
+            current.wait_time += wait_time;
+            metrics.set(current);
+        });
+        TOTAL_ACQUISITIONS.fetch_add(1, Ordering::Relaxed);
+        TOTAL_WAIT_NANOS.fetch_add(wait_time.as_nanos() as u64, Ordering::Relaxed);
+    }
+    result
+}
+
+/// Returns the metrics as a dict with the keys `thread` and `total`, each containing
+/// `acquisitions` and `wait_seconds`.
+pub fn snapshot(py: Python) -> PyResult<&PyDict> {
+    fn to_dict(py: Python, metrics: GilMetrics) -> PyResult<&PyDict> {
+        let dict = PyDict::new(py);
+        dict.set_item("acquisitions", metrics.acquisitions)?;
+        dict.set_item("wait_seconds", metrics.wait_time.as_secs_f64())?;
+        Ok(dict)
+    }
+
+    let snapshot = PyDict::new(py);
+    snapshot.set_item("thread", to_dict(py, thread_metrics())?)?;
+    snapshot.set_item("total", to_dict(py, total_metrics())?)?;
+    Ok(snapshot)
+}
+
+crate::trampoline! {
+    fn py_gil_metrics(_slf: *mut ffi::PyObject, _args: *mut ffi::PyObject) -> *mut ffi::PyObject;
+    converter = PyObjectCallbackConverter;
+    |py| snapshot(py).map(|snapshot| snapshot.to_object(py))
+}
+
+/// Creates the Python function `gil_metrics()`, which returns `snapshot`.
+pub fn python_function(py: Python) -> PyObject {
+    crate::derive_utils::builtin_function(
+        py,
+        "gil_metrics",
+        py_gil_metrics,
+        ffi::METH_NOARGS,
+        "Returns the GIL acquisition metrics of the current thread and in total.\0",
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{python_function, thread_metrics};
+    use crate::types::PyDict;
+    use crate::Python;
+
+    #[test]
+    fn test_gil_metrics() {
+        let before = {
+            let _gil = Python::acquire_gil();
+            thread_metrics()
+        };
+        {
+            let gil = Python::acquire_gil();
+            let py = gil.python();
+            py.allow_threads(|| ());
+        }
+        let after = thread_metrics();
+        assert!(after.acquisitions > before.acquisitions);
+        assert!(after.wait_time >= before.wait_time);
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let snapshot = python_function(py).call0(py).unwrap();
+        let snapshot: &PyDict = snapshot.cast_as(py).unwrap();
+        assert!(snapshot.get_item("thread").is_some());
+        assert!(snapshot.get_item("total").is_some());
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Initialization hooks that run when the first `#[pymodule]` of an extension is imported.
+//!
+//! Register a hook with `register_initializer!`:
+//!
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::register_initializer;
+//!
+//! fn setup_logging(_py: Python) -> PyResult<()> {
+//!     // e.g. `env_logger::try_init()`
+//!     Ok(())
+//! }
+//!
+//! register_initializer!(setup_logging);
+//! # fn main() {}
+//! ```
+//!
+//! All hooks registered in the shared library run once, before the body of the first
+//! `#[pymodule]` function is executed, no matter how many modules the library defines.
+//! If a hook fails, the import raises its error, and the next import runs the failed hook and
+//! the hooks after it. A module imported by a hook doesn't run the hooks again, while importing a
+//! module of the extension on another thread, when a hook released the GIL, raises an
+//! `ImportError`.
+//!
+//! The progress marker lives in the interpreter's `sys` module, so when an embedding
+//! application finalizes Python and initializes it again, the hooks run once more for the new
+//! interpreter.
+
+use crate::err::PyResult;
+use crate::exceptions::ImportError;
+use crate::ffi;
+use crate::types::PyAny;
+use crate::{AsPyPointer, ObjectProtocol, PyObject, Python, ToPyObject};
+use std::cell::Cell;
+use std::ffi::CString;
+
+/// A function that is run once before the first module of the extension is initialized.
+///
+/// Use `register_initializer!` instead of constructing this directly.
+pub struct Initializer {
+    pub init: fn(Python) -> PyResult<()>,
+}
+
+inventory::collect!(Initializer);
+
+thread_local! {
+    /// Whether the hooks are running on this thread, to tell the imports made by the hooks from
+    /// imports on other threads while the hooks released the GIL.
+    static RUNNING: Cell<bool> = Cell::new(false);
+}
+
+/// Registers a `fn(Python) -> PyResult<()>` as an `Initializer`.
+///
+/// See the `initializer` module for when the hooks are run.
+#[macro_export]
+macro_rules! register_initializer {
+    ($init: path) => {
+        $crate::inventory::submit! {
+            $crate::initializer::Initializer { init: $init }
+        }
+    };
+}
+
+/// Runs all registered initializers, unless they already ran in the current interpreter.
+///
+/// Must be called with the GIL held, which also serializes concurrent imports.
+pub(crate) fn run_initializers(py: Python) -> PyResult<()> {
+    // Every shared library built with pyo3 has its own copy of this static and its own
+    // inventory, so the address makes the marker unique per library.
+    static MARKER: u8 = 0;
+    let key = CString::new(format!("_pyo3_initialized_{:p}", &MARKER))
+        .expect("Marker name must not contain NULL byte");
+
+    // The marker is `None` while the hooks run, and the number of hooks which succeeded
+    // otherwise.
+    let marker = unsafe { ffi::PySys_GetObject(key.as_ptr()) };
+    let done = match unsafe { py.from_borrowed_ptr_or_opt::<PyAny>(marker) } {
+        None => 0,
+        Some(marker) if marker.is_none() => {
+            if RUNNING.with(Cell::get) {
+                return Ok(());
+            }
+            return Err(ImportError::py_err(
+                "the initializers of the extension are running on another thread",
+            ));
+        }
+        Some(marker) => marker.extract::<usize>()?,
+    };
+    let initializers: Vec<&Initializer> = inventory::iter::<Initializer>.into_iter().collect();
+    if done >= initializers.len() {
+        return Ok(());
+    }
+
+    set_marker(py, &key, py.None())?;
+    let guard = RunningGuard {
+        py,
+        key: &key,
+        done: Cell::new(done),
+    };
+    RUNNING.with(|running| running.set(true));
+    let result = initializers[done..].iter().try_for_each(|initializer| {
+        (initializer.init)(py)?;
+        guard.done.set(guard.done.get() + 1);
+        Ok(())
+    });
+    set_marker(py, &key, guard.done.get().to_object(py))?;
+    result
+}
+
+/// Clears `RUNNING` when the hooks return or panic, and records the hooks which succeeded
+/// before a panic.
+struct RunningGuard<'a> {
+    py: Python<'a>,
+    key: &'a CString,
+    done: Cell<usize>,
+}
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        RUNNING.with(|running| running.set(false));
+        if std::thread::panicking() {
+            // The error, if any, is fetched and dropped, as it can't be raised during a panic
+            let _ = set_marker(self.py, self.key, self.done.get().to_object(self.py));
+        }
+    }
+}
+
+fn set_marker(py: Python, key: &CString, value: PyObject) -> PyResult<()> {
+    unsafe { crate::err::error_on_minusone(py, ffi::PySys_SetObject(key.as_ptr(), value.as_ptr())) }
+}
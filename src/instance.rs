@@ -84,6 +84,14 @@ impl<T> Py<T> {
         }
     }
 
+    /// Construct `Py<T>` from the result of a Python FFI call that
+    /// returns a new reference (owned pointer).
+    /// Returns `None` if the pointer is `null`.
+    /// Unsafe because the pointer might be invalid.
+    pub unsafe fn from_owned_ptr_or_opt(ptr: *mut ffi::PyObject) -> Option<Py<T>> {
+        NonNull::new(ptr).map(|nonnull_ptr| Py(nonnull_ptr, PhantomData))
+    }
+
     /// Creates a `Py<T>` instance for the given Python FFI pointer.
     /// Calls Py_INCREF() on the ptr.
     /// Undefined behavior if the pointer is NULL or invalid.
@@ -97,6 +105,40 @@ impl<T> Py<T> {
         Py(NonNull::new_unchecked(ptr), PhantomData)
     }
 
+    /// Creates a `Py<T>` instance for the given Python FFI pointer.
+    /// Calls Py_INCREF() on the ptr.
+    /// Panics if the pointer is `null`.
+    /// Undefined behavior if the pointer is invalid.
+    #[inline]
+    pub unsafe fn from_borrowed_ptr_or_panic(ptr: *mut ffi::PyObject) -> Py<T> {
+        match Self::from_borrowed_ptr_or_opt(ptr) {
+            Some(ob) => ob,
+            None => crate::err::panic_after_error(),
+        }
+    }
+
+    /// Creates a `Py<T>` instance for the given Python FFI pointer.
+    /// Calls Py_INCREF() on the ptr.
+    /// Returns `Err(PyErr)` if the pointer is `null`.
+    /// Unsafe because the pointer might be invalid.
+    pub unsafe fn from_borrowed_ptr_or_err(py: Python, ptr: *mut ffi::PyObject) -> PyResult<Py<T>> {
+        match Self::from_borrowed_ptr_or_opt(ptr) {
+            Some(ob) => Ok(ob),
+            None => Err(PyErr::fetch(py)),
+        }
+    }
+
+    /// Creates a `Py<T>` instance for the given Python FFI pointer.
+    /// Calls Py_INCREF() on the ptr.
+    /// Returns `None` if the pointer is `null`.
+    /// Unsafe because the pointer might be invalid.
+    pub unsafe fn from_borrowed_ptr_or_opt(ptr: *mut ffi::PyObject) -> Option<Py<T>> {
+        NonNull::new(ptr).map(|nonnull_ptr| {
+            ffi::Py_INCREF(nonnull_ptr.as_ptr());
+            Py(nonnull_ptr, PhantomData)
+        })
+    }
+
     /// Gets the reference count of the ffi::PyObject pointer.
     #[inline]
     pub fn get_refcnt(&self) -> isize {
@@ -120,12 +162,13 @@ impl<T> Py<T> {
 }
 
 pub trait AsPyRef<T: PyTypeInfo>: Sized {
-    /// Return reference to object.
-    fn as_ref(&self, py: Python) -> &T;
+    /// Return reference to object: `&T` for native types, and `&PyClassShell<T>` for
+    /// `#[pyclass]`es, whose contents are borrowed from it.
+    fn as_ref(&self, py: Python) -> &T::AsRefTarget;
 }
 
 impl<T: PyTypeInfo> AsPyRef<T> for Py<T> {
-    fn as_ref(&self, _py: Python) -> &T {
+    fn as_ref(&self, _py: Python) -> &T::AsRefTarget {
         let any = self as *const Py<T> as *const PyAny;
         unsafe { T::ConcreteLayout::internal_ref_cast(&*any) }
     }
@@ -184,15 +227,6 @@ where
     }
 }
 
-impl<'a, T> std::convert::From<&mut PyClassShell<T>> for Py<T>
-where
-    T: PyClass,
-{
-    fn from(shell: &mut PyClassShell<T>) -> Self {
-        unsafe { Py::from_borrowed_ptr(shell.as_ptr()) }
-    }
-}
-
 impl<T> PartialEq for Py<T> {
     #[inline]
     fn eq(&self, o: &Py<T>) -> bool {
@@ -307,6 +341,7 @@ pub trait ManagedPyRefDispatch: ToPyObject {
 ///
 /// Note that the actual implementations are part of the trait declaration to avoid
 /// a specialization error
+#[cfg(not(feature = "stable"))]
 impl<T: ToPyObject + ?Sized> ManagedPyRefDispatch for T {
     /// Contains the case 1 impl (with to_object) to avoid a specialization error
     default fn to_managed_py_ref<'p>(&self, py: Python<'p>) -> ManagedPyRef<'p, Self> {
@@ -323,8 +358,25 @@ impl<T: ToPyObject + ?Sized> ManagedPyRefDispatch for T {
     }
 }
 
+/// Without specialization, everything takes case 1, including objects on the python heap.
+#[cfg(feature = "stable")]
+impl<T: ToPyObject + ?Sized> ManagedPyRefDispatch for T {
+    fn to_managed_py_ref<'p>(&self, py: Python<'p>) -> ManagedPyRef<'p, Self> {
+        ManagedPyRef {
+            data: self.to_object(py).into_ptr(),
+            data_type: PhantomData,
+            _py: py,
+        }
+    }
+
+    fn drop_impl(borrowed: &mut ManagedPyRef<Self>) {
+        unsafe { ffi::Py_DECREF(borrowed.data) };
+    }
+}
+
 /// Case 2: It's an object on the python heap, we're just storing a borrowed pointer.
 /// The object we're getting is an owned pointer, it might have it's own drop impl.
+#[cfg(not(feature = "stable"))]
 impl<T: ToPyObject + AsPyPointer + ?Sized> ManagedPyRefDispatch for T {
     /// Use AsPyPointer to copy the pointer and store it as borrowed pointer
     fn to_managed_py_ref<'p>(&self, py: Python<'p>) -> ManagedPyRef<'p, Self> {
@@ -352,7 +404,7 @@ mod test {
     use super::{ManagedPyRef, Py};
     use crate::ffi;
     use crate::types::PyDict;
-    use crate::{AsPyPointer, Python};
+    use crate::{AsPyPointer, GILPool, IntoPyPointer, Python};
 
     #[test]
     fn py_from_dict() {
@@ -366,6 +418,35 @@ mod test {
     }
 
     #[test]
+    fn py_from_raw_ptr() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let native = PyDict::new(py);
+        let ref_count = unsafe { ffi::Py_REFCNT(native.as_ptr()) };
+
+        let borrowed: Py<PyDict> =
+            unsafe { Py::from_borrowed_ptr_or_opt(native.as_ptr()) }.unwrap();
+        assert_eq!(ref_count + 1, borrowed.get_refcnt());
+        {
+            // Dropping a `Py` releases the reference when the pool is dropped
+            let _pool = GILPool::new(py);
+            let owned: Py<PyDict> =
+                unsafe { Py::from_owned_ptr_or_opt(borrowed.into_ptr()) }.unwrap();
+            assert_eq!(ref_count + 1, owned.get_refcnt());
+            drop(owned);
+        }
+        assert_eq!(ref_count, unsafe { ffi::Py_REFCNT(native.as_ptr()) });
+
+        assert!(unsafe { Py::<PyDict>::from_owned_ptr_or_opt(std::ptr::null_mut()) }.is_none());
+        assert!(unsafe { Py::<PyDict>::from_borrowed_ptr_or_opt(std::ptr::null_mut()) }.is_none());
+        // No exception is set, so a SystemError is raised instead
+        assert!(
+            unsafe { Py::<PyDict>::from_borrowed_ptr_or_err(py, std::ptr::null_mut()) }.is_err()
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "stable"))]
     fn borrowed_py_ref_with_to_pointer() {
         let gil = Python::acquire_gil();
         let py = gil.python();
@@ -0,0 +1,283 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Conversion between fieldless Rust enums and Python integers.
+//!
+//! `#[derive(IntEnum)]` implements [`IntEnum`] for an enum with an integer `#[repr]`, and converts
+//! it to and from a Python `int`. Extraction accepts ints, `bool`s and members of a Python
+//! `enum.Enum` with int values, and raises a `ValueError` listing the valid values for others.
+//! `TryFrom` of the `#[repr]` type validates Rust integers the same way:
+//!
+//! ```
+//! use pyo3::int_enum::IntEnum;
+//! use pyo3::prelude::*;
+//! use std::convert::TryFrom;
+//!
+//! #[derive(IntEnum, Clone, Copy, Debug, PartialEq)]
+//! #[repr(u8)]
+//! enum Color {
+//!     Red = 1,
+//!     Green,
+//!     Blue,
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let green: Color = py.eval("2", None, None).unwrap().extract().unwrap();
+//! assert_eq!(green, Color::Green);
+//! assert_eq!(Color::Blue.to_object(py).extract::<u8>(py).unwrap(), 3);
+//!
+//! let err = Color::try_from(7).unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     "7 is not a valid Color, expected one of 1 (Red), 2 (Green), 3 (Blue)"
+//! );
+//! ```
+//!
+//! `#[pyclass]` on a fieldless enum creates a Python class instead, with a class attribute for
+//! each variant. Its instances support `int()` and `operator.index()`, compare and hash like their
+//! values, and can be extracted from an instance, an int or an `enum.Enum` member:
+//!
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::py_run;
+//!
+//! #[pyclass]
+//! #[derive(Clone, Copy)]
+//! enum Mode {
+//!     Read = 1,
+//!     Write = 2,
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let cls = py.get_type::<Mode>();
+//! py_run!(py, cls, "assert cls.Write == 2 and int(cls.Read) == 1");
+//! py_run!(py, cls, "assert repr(cls.Read) == 'Mode.Read'");
+//! let write: Mode = py.eval("2", None, None).unwrap().extract().unwrap();
+//! assert!(matches!(write, Mode::Write));
+//! ```
+//!
+//! [`IntEnum`]: trait.IntEnum.html
+
+use crate::conversion::PyTryFrom;
+use crate::err::PyErr;
+use crate::exceptions::ValueError;
+use crate::pyclass::{PyClass, PyClassShell};
+use crate::types::{PyAny, PyLong};
+use crate::{ffi, AsPyPointer, IntoPy, IntoPyPointer, ObjectProtocol, PyNativeType, PyObject};
+use crate::{PyResult, Python, ToPyObject};
+use std::{fmt, mem};
+
+pub use pyo3cls::IntEnum;
+
+/// A fieldless enum with an integer representation.
+///
+/// Use `#[derive(IntEnum)]` to implement this trait. `#[pyclass]` implements it for enums too.
+pub trait IntEnum: Sized + Copy + 'static {
+    /// The name of the enum.
+    const NAME: &'static str;
+
+    /// The variants with their names and values, in declaration order.
+    const VARIANTS: &'static [(&'static str, i128, Self)];
+}
+
+/// The variant of `T` with the value `value`.
+pub fn from_value<T: IntEnum>(value: i128) -> Result<T, InvalidValue> {
+    T::VARIANTS
+        .iter()
+        .find(|(_, v, _)| *v == value)
+        .map(|(_, _, variant)| *variant)
+        .ok_or_else(|| InvalidValue::new::<T>(value))
+}
+
+/// Extracts a variant of `T` from an int, a `bool` or a member of an `enum.Enum` with an int
+/// value.
+pub fn extract<T: IntEnum>(obj: &PyAny) -> PyResult<T> {
+    let py = obj.py();
+    let value =
+        if !obj.is_instance::<PyLong>() && py.import_type("enum", "Enum")?.is_instance(obj)? {
+            obj.getattr(crate::intern!(py, "value"))?
+        } else {
+            obj
+        };
+    Ok(from_value(value.extract()?)?)
+}
+
+/// The error of converting a value that isn't one of the variants of an enum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidValue {
+    value: i128,
+    name: &'static str,
+    valid: Vec<(&'static str, i128)>,
+}
+
+impl InvalidValue {
+    #[cold]
+    pub fn new<T: IntEnum>(value: i128) -> Self {
+        InvalidValue {
+            value,
+            name: T::NAME,
+            valid: T::VARIANTS
+                .iter()
+                .map(|(name, value, _)| (*name, *value))
+                .collect(),
+        }
+    }
+
+    /// The value that isn't a variant.
+    pub fn value(&self) -> i128 {
+        self.value
+    }
+}
+
+impl fmt::Display for InvalidValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} is not a valid {}, expected ", self.value, self.name)?;
+        if self.valid.is_empty() {
+            return f.write_str("no value");
+        }
+        f.write_str("one of ")?;
+        for (i, (name, value)) in self.valid.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{} ({})", value, name)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InvalidValue {}
+
+/// Converts to a `ValueError`.
+impl From<InvalidValue> for PyErr {
+    fn from(err: InvalidValue) -> PyErr {
+        ValueError::py_err(err.to_string())
+    }
+}
+
+/// The name and value of `variant`.
+fn variant_of<T: IntEnum>(variant: &T) -> (&'static str, i128) {
+    T::VARIANTS
+        .iter()
+        .find(|(_, _, v)| mem::discriminant(v) == mem::discriminant(variant))
+        .map(|(name, value, _)| (*name, *value))
+        .expect("variant missing from IntEnum::VARIANTS")
+}
+
+/// Extracts a variant of a `#[pyclass]` enum from an instance of the class, or like [`extract`]
+/// from an int or an `enum.Enum` member.
+///
+/// [`extract`]: fn.extract.html
+#[doc(hidden)]
+pub fn extract_class<T: IntEnum + PyClass>(obj: &PyAny) -> PyResult<T> {
+    match <PyClassShell<T> as PyTryFrom>::try_from(obj) {
+        Ok(variant) => Ok(*variant.try_borrow()?),
+        Err(_) => extract(obj),
+    }
+}
+
+/// Reads the variant of a `#[pyclass]` enum from the object `slf` of a slot.
+unsafe fn slot_variant<T: IntEnum + PyClass>(py: Python, slf: *mut ffi::PyObject) -> PyResult<T> {
+    let slf = py.from_borrowed_ptr::<PyClassShell<T>>(slf);
+    Ok(*slf.try_borrow()?)
+}
+
+/// `tp_richcompare` of a `#[pyclass]` enum, comparing the values of variants and ints.
+#[doc(hidden)]
+pub fn richcmp<T: IntEnum + PyClass>() -> ffi::richcmpfunc {
+    unsafe extern "C" fn wrap<T>(
+        slf: *mut ffi::PyObject,
+        arg: *mut ffi::PyObject,
+        op: std::os::raw::c_int,
+    ) -> *mut ffi::PyObject
+    where
+        T: IntEnum + PyClass,
+    {
+        let py = Python::assume_gil_acquired();
+        let _pool = crate::GILPool::new(py);
+        let value = match slot_variant::<T>(py, slf) {
+            Ok(slf) => variant_of(&slf).1,
+            Err(e) => return e.restore_and_null(py),
+        };
+        let arg = py.from_borrowed_ptr::<PyAny>(arg);
+        let other = match <PyClassShell<T> as PyTryFrom>::try_from(arg) {
+            Ok(other) => match other.try_borrow() {
+                Ok(other) => variant_of(&*other).1,
+                Err(e) => return PyErr::from(e).restore_and_null(py),
+            },
+            Err(_) if arg.is_instance::<PyLong>() => match arg.extract::<i128>() {
+                Ok(other) => other,
+                Err(_) => return py.NotImplemented().into_ptr(),
+            },
+            Err(_) => return py.NotImplemented().into_ptr(),
+        };
+        let result = match op {
+            ffi::Py_LT => value < other,
+            ffi::Py_LE => value <= other,
+            ffi::Py_EQ => value == other,
+            ffi::Py_NE => value != other,
+            ffi::Py_GT => value > other,
+            ffi::Py_GE => value >= other,
+            _ => return py.NotImplemented().into_ptr(),
+        };
+        IntoPy::<PyObject>::into_py(result, py).into_ptr()
+    }
+    wrap::<T>
+}
+
+/// `tp_hash` of a `#[pyclass]` enum, the hash of its value, as variants compare equal to it.
+#[doc(hidden)]
+pub fn hash<T: IntEnum + PyClass>() -> ffi::hashfunc {
+    unsafe extern "C" fn wrap<T>(slf: *mut ffi::PyObject) -> ffi::Py_hash_t
+    where
+        T: IntEnum + PyClass,
+    {
+        let py = Python::assume_gil_acquired();
+        let _pool = crate::GILPool::new(py);
+        match slot_variant::<T>(py, slf) {
+            Ok(slf) => ffi::PyObject_Hash(variant_of(&slf).1.to_object(py).as_ptr()),
+            Err(e) => {
+                e.restore(py);
+                -1
+            }
+        }
+    }
+    wrap::<T>
+}
+
+/// `tp_repr` of a `#[pyclass]` enum, e.g. `Color.Red`.
+#[doc(hidden)]
+pub fn repr<T: IntEnum + PyClass>() -> ffi::reprfunc {
+    unsafe extern "C" fn wrap<T>(slf: *mut ffi::PyObject) -> *mut ffi::PyObject
+    where
+        T: IntEnum + PyClass,
+    {
+        let py = Python::assume_gil_acquired();
+        let _pool = crate::GILPool::new(py);
+        let slf = match slot_variant::<T>(py, slf) {
+            Ok(slf) => slf,
+            Err(e) => return e.restore_and_null(py),
+        };
+        let repr = format!("{}.{}", <T as IntEnum>::NAME, variant_of(&slf).0);
+        IntoPy::<PyObject>::into_py(repr, py).into_ptr()
+    }
+    wrap::<T>
+}
+
+/// `nb_int` and `nb_index` of a `#[pyclass]` enum, returning the value of the variant.
+#[doc(hidden)]
+pub fn index<T: IntEnum + PyClass>() -> ffi::unaryfunc {
+    unsafe extern "C" fn wrap<T>(slf: *mut ffi::PyObject) -> *mut ffi::PyObject
+    where
+        T: IntEnum + PyClass,
+    {
+        let py = Python::assume_gil_acquired();
+        let _pool = crate::GILPool::new(py);
+        match slot_variant::<T>(py, slf) {
+            Ok(slf) => variant_of(&slf).1.to_object(py).into_ptr(),
+            Err(e) => e.restore_and_null(py),
+        }
+    }
+    wrap::<T>
+}
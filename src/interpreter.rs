@@ -0,0 +1,257 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Typed information about the running Python interpreter.
+//!
+//! [`Python::version_info`], [`Python::flags`] and [`Python::platform`] read `sys.version_info`,
+//! `sys.flags` and the platform of the interpreter once and cache them for the lifetime of the
+//! process, so version dependent code paths can check them cheaply:
+//!
+//! ```
+//! use pyo3::prelude::*;
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! if py.version_info() >= (3, 8) {
+//!     // Use `PyObject_Vectorcall`
+//! }
+//! assert_eq!(py.version_info().major, 3);
+//! assert!(!py.flags().isolated);
+//! ```
+//!
+//! In contrast to [`build_info`](../build_info/index.html), which describes the interpreter pyo3
+//! was compiled against, these describe the interpreter the code is running in.
+//!
+//! [`Python::version_info`]: ../struct.Python.html#method.version_info
+//! [`Python::flags`]: ../struct.Python.html#method.flags
+//! [`Python::platform`]: ../struct.Python.html#method.platform
+
+use crate::err::PyResult;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::PyAny;
+use crate::Python;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// The release level of a Python version, as in `sys.version_info.releaselevel`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReleaseLevel {
+    Alpha,
+    Beta,
+    Candidate,
+    Final,
+}
+
+/// The version of the running interpreter, as in `sys.version_info`.
+///
+/// Versions compare like the tuple in Python, and can be compared with `(major, minor)` tuples.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PythonVersionInfo {
+    pub major: u8,
+    pub minor: u8,
+    pub micro: u8,
+    pub releaselevel: ReleaseLevel,
+    pub serial: u8,
+}
+
+impl PartialEq<(u8, u8)> for PythonVersionInfo {
+    fn eq(&self, other: &(u8, u8)) -> bool {
+        (self.major, self.minor) == *other
+    }
+}
+
+impl PartialOrd<(u8, u8)> for PythonVersionInfo {
+    fn partial_cmp(&self, other: &(u8, u8)) -> Option<Ordering> {
+        (self.major, self.minor).partial_cmp(other)
+    }
+}
+
+impl fmt::Display for PythonVersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.micro)?;
+        match self.releaselevel {
+            ReleaseLevel::Alpha => write!(f, "a{}", self.serial),
+            ReleaseLevel::Beta => write!(f, "b{}", self.serial),
+            ReleaseLevel::Candidate => write!(f, "rc{}", self.serial),
+            ReleaseLevel::Final => Ok(()),
+        }
+    }
+}
+
+/// The command line flags of the running interpreter, as in `sys.flags`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterpreterFlags {
+    pub debug: bool,
+    pub inspect: bool,
+    pub interactive: bool,
+    /// `-O` level
+    pub optimize: u8,
+    pub dont_write_bytecode: bool,
+    pub no_user_site: bool,
+    pub no_site: bool,
+    pub ignore_environment: bool,
+    /// `-v` level
+    pub verbose: u8,
+    /// `-b` level
+    pub bytes_warning: u8,
+    pub quiet: bool,
+    pub hash_randomization: bool,
+    pub isolated: bool,
+    /// Always false before Python 3.7.
+    pub dev_mode: bool,
+    /// Always false before Python 3.7.
+    pub utf8_mode: bool,
+}
+
+/// The platform of the running interpreter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlatformInfo {
+    /// `sys.platform`, e.g. `linux`, `win32` or `darwin`
+    pub platform: String,
+    /// `sys.implementation.name`, e.g. `cpython` or `pypy`
+    pub implementation: String,
+    /// `sys.implementation.cache_tag`, e.g. `cpython-38`
+    pub cache_tag: Option<String>,
+}
+
+pub(crate) struct InterpreterInfo {
+    pub(crate) version_info: PythonVersionInfo,
+    pub(crate) flags: InterpreterFlags,
+    pub(crate) platform: PlatformInfo,
+}
+
+static INFO: parking_lot::Mutex<Option<&'static InterpreterInfo>> = parking_lot::const_mutex(None);
+
+/// Reads the interpreter information on the first call.
+pub(crate) fn get(py: Python) -> &'static InterpreterInfo {
+    if let Some(info) = *INFO.lock() {
+        return info;
+    }
+    // The lock must not be held while reading the attributes, which runs Python code
+    let info = read(py).expect("failed to read the interpreter information from sys");
+    INFO.lock().get_or_insert_with(|| Box::leak(Box::new(info)))
+}
+
+fn read(py: Python) -> PyResult<InterpreterInfo> {
+    let sys = py.import("sys")?;
+
+    let version_info = sys.get("version_info")?;
+    let releaselevel = match version_info.getattr("releaselevel")?.extract()? {
+        "alpha" => ReleaseLevel::Alpha,
+        "beta" => ReleaseLevel::Beta,
+        "candidate" => ReleaseLevel::Candidate,
+        _ => ReleaseLevel::Final,
+    };
+    let version_info = PythonVersionInfo {
+        major: version_info.getattr("major")?.extract()?,
+        minor: version_info.getattr("minor")?.extract()?,
+        micro: version_info.getattr("micro")?.extract()?,
+        releaselevel,
+        serial: version_info.getattr("serial")?.extract()?,
+    };
+
+    let flags = sys.get("flags")?;
+    let flag = |name| -> PyResult<u8> {
+        match flags.getattr(name) {
+            Ok(value) => value.extract(),
+            Err(_) => Ok(0),
+        }
+    };
+    let flags = InterpreterFlags {
+        debug: flag("debug")? != 0,
+        inspect: flag("inspect")? != 0,
+        interactive: flag("interactive")? != 0,
+        optimize: flag("optimize")?,
+        dont_write_bytecode: flag("dont_write_bytecode")? != 0,
+        no_user_site: flag("no_user_site")? != 0,
+        no_site: flag("no_site")? != 0,
+        ignore_environment: flag("ignore_environment")? != 0,
+        verbose: flag("verbose")?,
+        bytes_warning: flag("bytes_warning")?,
+        quiet: flag("quiet")? != 0,
+        hash_randomization: flag("hash_randomization")? != 0,
+        isolated: flag("isolated")? != 0,
+        dev_mode: flag("dev_mode")? != 0,
+        utf8_mode: flag("utf8_mode")? != 0,
+    };
+
+    let implementation = sys.get("implementation")?;
+    let cache_tag: &PyAny = implementation.getattr("cache_tag")?;
+    let platform = PlatformInfo {
+        platform: sys.get("platform")?.extract()?,
+        implementation: implementation.getattr("name")?.extract()?,
+        cache_tag: cache_tag.extract()?,
+    };
+
+    Ok(InterpreterInfo {
+        version_info,
+        flags,
+        platform,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReleaseLevel;
+    use crate::{ObjectProtocol, Python};
+
+    #[test]
+    fn test_version_info() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let version_info = py.version_info();
+        let expected: (u8, u8, u8, String, u8) = py
+            .eval("tuple(__import__('sys').version_info)", None, None)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(
+            (version_info.major, version_info.minor, version_info.micro),
+            (expected.0, expected.1, expected.2)
+        );
+        assert_eq!(version_info.serial, expected.4);
+        if expected.3 == "final" {
+            assert_eq!(version_info.releaselevel, ReleaseLevel::Final);
+            assert_eq!(
+                version_info.to_string(),
+                format!("{}.{}.{}", expected.0, expected.1, expected.2)
+            );
+        }
+
+        assert!(version_info >= (3, 5));
+        assert!(version_info < (4, 0));
+        assert!(version_info == (expected.0, expected.1));
+        assert!(version_info > (expected.0, expected.1 - 1));
+        // Cached
+        assert!(std::ptr::eq(
+            super::get(py) as *const _,
+            super::get(py) as *const _
+        ));
+    }
+
+    #[test]
+    fn test_flags_and_platform() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let flags = py.flags();
+        let optimize: u8 = py
+            .eval("__import__('sys').flags.optimize", None, None)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(flags.optimize, optimize);
+        assert!(!flags.isolated);
+
+        let platform = py.platform();
+        let expected: String = py
+            .eval("__import__('sys').platform", None, None)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(platform.platform, expected);
+        if cfg!(PyPy) {
+            assert_eq!(platform.implementation, "pypy");
+        } else {
+            assert_eq!(platform.implementation, "cpython");
+        }
+    }
+}
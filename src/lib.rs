@@ -1,4 +1,4 @@
-#![feature(specialization)]
+#![cfg_attr(not(feature = "stable"), feature(specialization))]
 #![allow(clippy::missing_safety_doc)] // FIXME (#698)
 
 //! Rust bindings to the Python interpreter.
@@ -124,11 +124,11 @@ pub use crate::conversion::{
     ToBorrowedObject, ToPyObject,
 };
 pub use crate::err::{PyDowncastError, PyErr, PyErrArguments, PyErrValue, PyResult};
-pub use crate::gil::{init_once, GILGuard, GILPool};
+pub use crate::gil::{init_once, is_interpreter_alive, GILGuard, GILPool};
 pub use crate::instance::{AsPyRef, ManagedPyRef, Py, PyNativeType};
 pub use crate::object::PyObject;
 pub use crate::objectprotocol::ObjectProtocol;
-pub use crate::pyclass::{PyClass, PyClassShell};
+pub use crate::pyclass::{PyBorrowError, PyBorrowMutError, PyClass, PyClassShell, PyRef, PyRefMut};
 pub use crate::pyclass_init::PyClassInitializer;
 pub use crate::python::{prepare_freethreaded_python, Python};
 pub use crate::type_object::{type_flags, PyTypeInfo};
@@ -149,32 +149,69 @@ pub use libc;
 #[doc(hidden)]
 pub use unindent;
 
+pub mod argparse;
+pub mod asyncio;
 pub mod buffer;
+pub mod build_info;
 #[doc(hidden)]
 pub mod callback;
 pub mod class;
+pub mod columnar;
 pub mod conversion;
+#[cfg(feature = "convert-trace")]
+pub mod convert_trace;
 #[doc(hidden)]
 pub mod derive_utils;
 mod err;
+pub mod error_table;
 pub mod exceptions;
+pub mod extract_registry;
 /// Raw ffi declarations for the c interface of python
 #[allow(clippy::unknown_clippy_lints)]
 #[allow(clippy::missing_safety_doc)]
 pub mod ffi;
+pub mod file;
+pub mod fork;
 pub mod freelist;
 mod gil;
+#[cfg(feature = "gil-metrics")]
+pub mod gil_metrics;
+pub mod initializer;
 mod instance;
+pub mod int_enum;
+pub mod interpreter;
 #[macro_use]
 mod internal_tricks;
+#[cfg(feature = "log")]
+pub mod logging;
 pub mod marshal;
+pub mod namespace;
+#[cfg(feature = "numpy")]
+pub mod numpy;
 mod object;
 mod objectprotocol;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod pickle;
 pub mod prelude;
+mod py_class;
 pub mod pyclass;
 pub mod pyclass_init;
 pub mod pyclass_slots;
+pub mod pyiter;
 mod python;
+pub mod redirect;
+pub mod resource;
+pub mod scope;
+pub mod sentinel;
+#[cfg(feature = "serde")]
+pub mod serde;
+pub mod shared;
+pub mod shared_memory;
+#[cfg(feature = "stubs")]
+pub mod stubs;
+pub mod testing;
+pub mod threading;
 pub mod type_object;
 pub mod types;
 
@@ -197,16 +234,170 @@ macro_rules! wrap_pyfunction {
 
 /// Returns a function that takes a [Python] instance and returns a python module.
 ///
-/// Use this together with `#[pymodule]` and [types::PyModule::add_wrapped].
+/// Use this together with `#[pymodule]` and [types::PyModule::add_wrapped]. Every call creates
+/// and initializes a new module object without going through the import system, so tests and
+/// embedders can use a module directly:
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::wrap_pymodule;
+///
+/// #[pymodule]
+/// fn answers(_py: Python, m: &PyModule) -> PyResult<()> {
+///     m.add("answer", 42)
+/// }
+///
+/// # fn main() -> PyResult<()> {
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let module = wrap_pymodule!(answers)(py);
+/// let module: &PyModule = module.extract(py)?;
+/// assert_eq!(module.get("answer")?.extract::<i32>()?, 42);
+///
+/// // make it importable from Python code
+/// module.add_to_sys_modules("answers")?;
+/// py.run("import answers; assert answers.answer == 42", None, None)?;
+/// # Ok(())
+/// # }
+/// ```
 #[macro_export]
 macro_rules! wrap_pymodule {
     ($module_name:ident) => {{
         pyo3::paste::expr! {
-            &|py| unsafe { pyo3::PyObject::from_owned_ptr(py, [<PyInit_ $module_name>]()) }
+            &|py| unsafe { pyo3::derive_utils::module_from_init(py, [<PyInit_ $module_name>]()) }
         }
     }};
 }
 
+/// Declares the tree of submodules of a `#[pymodule]` in one place.
+///
+/// `module_tree!(module, { ... })` fills `module` with the listed items and returns a
+/// `PyResult<()>`. Each item ends with a semicolon, except for submodules:
+///
+/// * `fn name;` adds a `#[pyfunction]`, like `add_function(wrap_pyfunction!(name))`
+/// * `class Type;` adds a `#[pyclass]`, like `add_class::<Type>()`
+/// * `init path;` calls a `fn(Python, &PyModule) -> PyResult<()>` adding anything else
+/// * `doc "text";` sets the `__doc__` of the module
+/// * `"name" => { ... }` creates the submodule `name` with the items in the braces
+/// * `use "sub.module" => [a, b];` adds the attributes `a` and `b` of a submodule declared
+///   before it, e.g. to re-export the most used names from the package
+///
+/// Submodules are called `parent.name` and are inserted into `sys.modules`, so
+/// `import package.name` and `from package.name import x` work like for Python packages.
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::{module_tree, wrap_pymodule};
+///
+/// #[pyfunction]
+/// fn add(a: i64, b: i64) -> i64 {
+///     a + b
+/// }
+///
+/// #[pyclass]
+/// struct Matrix {}
+///
+/// fn add_constants(_py: Python, module: &PyModule) -> PyResult<()> {
+///     module.add("PI", std::f64::consts::PI)
+/// }
+///
+/// #[pymodule]
+/// fn package(_py: Python, module: &PyModule) -> PyResult<()> {
+///     module_tree!(module, {
+///         "core" => {
+///             doc "Arithmetic";
+///             fn add;
+///             init add_constants;
+///             "linalg" => {
+///                 class Matrix;
+///             }
+///         }
+///         use "core" => [add];
+///         use "core.linalg" => [Matrix];
+///     })
+/// }
+///
+/// # let gil = Python::acquire_gil();
+/// # let py = gil.python();
+/// # wrap_pymodule!(package)(py).extract::<&PyModule>(py).unwrap().add_to_sys_modules("package").unwrap();
+/// py.run("from package.core.linalg import Matrix", None, None).unwrap();
+/// py.run("import package; assert package.add(1, 2) == 3", None, None).unwrap();
+/// ```
+#[macro_export]
+macro_rules! module_tree {
+    ($module:expr, { $($items:tt)* }) => {
+        (|| -> $crate::PyResult<()> {
+            let module: &$crate::types::PyModule = $module;
+            #[allow(unused_variables)]
+            let name = module.name()?.to_string();
+            $crate::__module_tree_items!(module, name, $($items)*);
+            Ok(())
+        })()
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __module_tree_items {
+    ($module:ident, $name:ident,) => {};
+    ($module:ident, $name:ident, fn $function:ident; $($rest:tt)*) => {
+        $module.add_function($crate::wrap_pyfunction!($function))?;
+        $crate::__module_tree_items!($module, $name, $($rest)*);
+    };
+    ($module:ident, $name:ident, class $class:ty; $($rest:tt)*) => {
+        $module.add_class::<$class>()?;
+        $crate::__module_tree_items!($module, $name, $($rest)*);
+    };
+    ($module:ident, $name:ident, init $init:path; $($rest:tt)*) => {
+        $init($crate::PyNativeType::py($module), $module)?;
+        $crate::__module_tree_items!($module, $name, $($rest)*);
+    };
+    ($module:ident, $name:ident, doc $doc:literal; $($rest:tt)*) => {
+        $crate::ObjectProtocol::setattr($module, "__doc__", $doc)?;
+        $crate::__module_tree_items!($module, $name, $($rest)*);
+    };
+    ($module:ident, $name:ident, $submodule:literal => { $($items:tt)* } $($rest:tt)*) => {
+        {
+            let name = format!("{}.{}", $name, $submodule);
+            let submodule = $crate::types::PyModule::new($crate::PyNativeType::py($module), &name)?;
+            $crate::__module_tree_items!(submodule, name, $($items)*);
+            submodule.add_to_sys_modules(&name)?;
+            $module.add($submodule, submodule)?;
+        }
+        $crate::__module_tree_items!($module, $name, $($rest)*);
+    };
+    ($module:ident, $name:ident, use $path:literal => [$($attr:ident),* $(,)?]; $($rest:tt)*) => {
+        {
+            let mut source: &$crate::types::PyModule = $module;
+            for part in $path.split('.') {
+                source = $crate::ObjectProtocol::getattr(source, part)?.downcast()?;
+            }
+            $($module.add(stringify!($attr), source.get(stringify!($attr))?)?;)*
+        }
+        $crate::__module_tree_items!($module, $name, $($rest)*);
+    };
+}
+
+/// Registers a stub signature generated by the proc macros with the `stubs` feature.
+#[cfg(feature = "stubs")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pyo3_stub {
+    ($stub:expr) => {
+        $crate::inventory::submit! {
+            #![crate = $crate] $stub
+        }
+    };
+}
+
+/// Without the `stubs` feature, the signatures are not recorded.
+#[cfg(not(feature = "stubs"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pyo3_stub {
+    ($stub:expr) => {};
+}
+
 /// A convenient macro to execute a Python code snippet, with some local variables set.
 ///
 /// # Example
@@ -0,0 +1,254 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Forwarding records of the `log` crate to Python's `logging` module, enabled with the `log`
+//! feature.
+//!
+//! [`init`] installs a logger passing each record to `logging.getLogger(target)`, with the `::`
+//! of the target replaced by `.`, so the loggers of Rust modules are children of the logger of
+//! their crate and use the handlers configured in Python:
+//!
+//! ```
+//! use pyo3::prelude::*;
+//!
+//! #[pymodule]
+//! fn my_module(py: Python, _m: &PyModule) -> PyResult<()> {
+//!     pyo3::logging::init(py)?;
+//!     log::info!("my_module loaded");
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Records logged on a thread holding the GIL are forwarded right away. Other threads don't
+//! acquire the GIL for every record: their records are queued, and a background thread forwards
+//! the queue at most every 50 milliseconds. `log::logger().flush()` forwards it immediately.
+//!
+//! The levels `error`, `warn`, `info` and `debug` map to the Python levels of the same names,
+//! `trace` maps to level 5. `init` sets the maximum level of `log` from the effective level of
+//! the root Python logger, so that records Python would ignore aren't even formatted; change it
+//! later with `log::set_max_level`.
+//!
+//! [`init`]: fn.init.html
+
+use crate::err::PyResult;
+use crate::exceptions::RuntimeError;
+use crate::gil::is_interpreter_alive;
+use crate::types::PyTuple;
+use crate::{ffi, GILPool, ObjectProtocol, Python};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use parking_lot::{const_mutex, Condvar, Mutex};
+use std::sync::Once;
+use std::thread;
+use std::time::Duration;
+
+/// How long the records of threads without the GIL are collected before they're forwarded.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(50);
+
+static LOGGER: PyLogger = PyLogger {
+    queue: const_mutex(Vec::new()),
+    queued: Condvar::new(),
+    flusher: Once::new(),
+};
+
+/// Installs the logger forwarding records of the `log` crate to Python's `logging` module.
+///
+/// Raises a `RuntimeError` if another logger is already installed.
+pub fn init(py: Python) -> PyResult<()> {
+    let level: u32 = py
+        .import("logging")?
+        .call0("getLogger")?
+        .call_method0("getEffectiveLevel")?
+        .extract()?;
+    log::set_logger(&LOGGER).map_err(|e| RuntimeError::py_err(e.to_string()))?;
+    log::set_max_level(level_filter(level));
+    Ok(())
+}
+
+struct PyLogger {
+    queue: Mutex<Vec<QueuedRecord>>,
+    queued: Condvar,
+    flusher: Once,
+}
+
+impl PyLogger {
+    fn run_flusher(&'static self) {
+        loop {
+            {
+                let mut queue = self.queue.lock();
+                while queue.is_empty() {
+                    self.queued.wait(&mut queue);
+                }
+            }
+            // Collect the records logged in the meantime, to acquire the GIL once for all of them
+            thread::sleep(FLUSH_INTERVAL);
+            if !is_interpreter_alive() {
+                return;
+            }
+            self.flush();
+        }
+    }
+}
+
+impl Log for PyLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) || !is_interpreter_alive() {
+            return;
+        }
+        let record = QueuedRecord::new(record);
+        if unsafe { ffi::PyGILState_Check() } != 0 {
+            let py = unsafe { Python::assume_gil_acquired() };
+            // Keep the order of records queued by threads without the GIL
+            let mut records = std::mem::take(&mut *self.queue.lock());
+            records.push(record);
+            forward(py, records);
+        } else {
+            self.queue.lock().push(record);
+            self.queued.notify_one();
+            self.flusher
+                .call_once(|| drop(thread::spawn(|| LOGGER.run_flusher())));
+        }
+    }
+
+    fn flush(&self) {
+        if self.queue.lock().is_empty() || !is_interpreter_alive() {
+            return;
+        }
+        let gil = Python::acquire_gil();
+        let records = std::mem::take(&mut *self.queue.lock());
+        forward(gil.python(), records);
+    }
+}
+
+/// A record copied out of a `log::Record`, which borrows the arguments of the logging call.
+struct QueuedRecord {
+    level: Level,
+    target: String,
+    message: String,
+    file: Option<String>,
+    line: Option<u32>,
+}
+
+impl QueuedRecord {
+    fn new(record: &Record) -> Self {
+        QueuedRecord {
+            level: record.level(),
+            target: record.target().replace("::", "."),
+            message: record.args().to_string(),
+            file: record.file().map(str::to_string),
+            line: record.line(),
+        }
+    }
+
+    fn forward(self, py: Python) -> PyResult<()> {
+        let logger = py.import("logging")?.call1("getLogger", (self.target,))?;
+        let level = python_level(self.level);
+        if !logger.call_method1("isEnabledFor", (level,))?.is_true()? {
+            return Ok(());
+        }
+        let record = logger.call_method1(
+            "makeRecord",
+            (
+                logger.getattr("name")?,
+                level,
+                self.file.unwrap_or_default(),
+                self.line.unwrap_or(0),
+                self.message,
+                PyTuple::empty(py),
+                py.None(),
+            ),
+        )?;
+        logger.call_method1("handle", (record,))?;
+        Ok(())
+    }
+}
+
+fn forward(py: Python, records: Vec<QueuedRecord>) {
+    let _pool = GILPool::new(py);
+    for record in records {
+        // `logging` reports the errors of handlers itself, and logging must not fail the caller
+        let _ = record.forward(py);
+    }
+}
+
+fn python_level(level: Level) -> u32 {
+    match level {
+        Level::Error => 40,
+        Level::Warn => 30,
+        Level::Info => 20,
+        Level::Debug => 10,
+        Level::Trace => 5,
+    }
+}
+
+fn level_filter(python_level: u32) -> LevelFilter {
+    match python_level {
+        0..=5 => LevelFilter::Trace,
+        6..=10 => LevelFilter::Debug,
+        11..=20 => LevelFilter::Info,
+        21..=30 => LevelFilter::Warn,
+        31..=40 => LevelFilter::Error,
+        _ => LevelFilter::Off,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::PyDict;
+    use crate::Python;
+    use std::thread;
+
+    #[test]
+    fn test_forward_records() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        super::init(py).unwrap();
+        assert!(super::init(py).is_err());
+
+        // `emit` looks up `records` in its globals
+        let globals = PyDict::new(py);
+        globals
+            .set_item("__builtins__", py.import("builtins").unwrap())
+            .unwrap();
+        py.run(
+            "import logging\n\
+             class Collect(logging.Handler):\n    \
+                 def emit(self, record):\n        \
+                     records.append(record)\n\
+             records = []\n\
+             logging.getLogger('pyo3_logging_test').addHandler(Collect())",
+            Some(globals),
+            None,
+        )
+        .unwrap();
+
+        log::warn!(target: "pyo3_logging_test::inner", "from {}", "rust");
+        // Below the WARNING level of the root logger
+        log::info!(target: "pyo3_logging_test", "ignored");
+        py.allow_threads(|| {
+            thread::spawn(|| log::error!(target: "pyo3_logging_test", "from a thread"))
+                .join()
+                .unwrap();
+            log::logger().flush();
+            thread::spawn(|| log::error!(target: "pyo3_logging_test", "flushed later"))
+                .join()
+                .unwrap();
+            // Forwarded by the background thread
+            thread::sleep(super::FLUSH_INTERVAL * 4);
+        });
+
+        py.run(
+            "assert [(r.name, r.levelname, r.getMessage()) for r in records] == [\n    \
+                 ('pyo3_logging_test.inner', 'WARNING', 'from rust'),\n    \
+                 ('pyo3_logging_test', 'ERROR', 'from a thread'),\n    \
+                 ('pyo3_logging_test', 'ERROR', 'flushed later'),\n\
+             ], records\n\
+             assert records[0].pathname.endswith('logging.rs') and records[0].lineno > 0",
+            Some(globals),
+            None,
+        )
+        .unwrap();
+    }
+}
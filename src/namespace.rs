@@ -0,0 +1,148 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Reusable namespaces for running Python code with `Python::run` and `Python::eval`.
+//!
+//! ```
+//! use pyo3::namespace::ExecutionNamespace;
+//! use pyo3::prelude::*;
+//!
+//! # fn main() -> PyResult<()> {
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//!
+//! let ns = ExecutionNamespace::with_builtins(py, &["len", "sum"])?.with_locals(vec![("x", 3)]);
+//! ns.run("y = sum([x, len('ab')])")?;
+//! let y: i32 = ns.eval("y")?.extract()?;
+//! assert_eq!(y, 5);
+//! assert!(ns.eval("print(y)").is_err());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Restricting the builtins only controls which names are available to the code. It is **not** a
+//! sandbox: the code can still reach everything else, e.g. through the attributes of objects.
+
+use crate::err::PyResult;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{IntoPyDict, PyAny, PyDict};
+use crate::{Python, ToPyObject};
+
+/// Globals, including the builtins, and optionally separate locals, for running Python code.
+///
+/// Unlike passing dicts to `Python::run` directly, the namespace makes sure `__builtins__` is
+/// set, and it can be reused so that later code sees the names defined by earlier code.
+#[derive(Clone, Copy)]
+pub struct ExecutionNamespace<'p> {
+    py: Python<'p>,
+    globals: &'p PyDict,
+    locals: Option<&'p PyDict>,
+}
+
+impl<'p> ExecutionNamespace<'p> {
+    /// Creates a namespace with all builtins.
+    pub fn new(py: Python<'p>) -> PyResult<Self> {
+        let globals = PyDict::new(py);
+        globals.set_item("__builtins__", py.import("builtins")?)?;
+        Ok(ExecutionNamespace {
+            py,
+            globals,
+            locals: None,
+        })
+    }
+
+    /// Creates a namespace with only the builtins named in `names`.
+    pub fn with_builtins(py: Python<'p>, names: &[&str]) -> PyResult<Self> {
+        let all = py.import("builtins")?;
+        let builtins = PyDict::new(py);
+        for name in names {
+            builtins.set_item(*name, all.getattr(*name)?)?;
+        }
+        let globals = PyDict::new(py);
+        globals.set_item("__builtins__", builtins)?;
+        Ok(ExecutionNamespace {
+            py,
+            globals,
+            locals: None,
+        })
+    }
+
+    /// Uses `locals` as local variables, which are otherwise the same as the globals.
+    ///
+    /// Note that names assigned by the code then end up in the locals, which e.g. functions
+    /// defined by the code can't see.
+    pub fn with_locals<L: IntoPyDict>(self, locals: L) -> Self {
+        ExecutionNamespace {
+            locals: Some(locals.into_py_dict(self.py)),
+            ..self
+        }
+    }
+
+    /// Sets the global variable `name`.
+    pub fn set_global<V: ToPyObject>(&self, name: &str, value: V) -> PyResult<()> {
+        self.globals.set_item(name, value)
+    }
+
+    /// Returns the globals.
+    pub fn globals(&self) -> &'p PyDict {
+        self.globals
+    }
+
+    /// Returns the locals, which are the globals if no separate locals were given.
+    pub fn locals(&self) -> &'p PyDict {
+        self.locals.unwrap_or(self.globals)
+    }
+
+    /// Runs Python statements in the namespace, like `Python::run`.
+    pub fn run(&self, code: &str) -> PyResult<()> {
+        self.py.run(code, Some(self.globals), self.locals)
+    }
+
+    /// Evaluates a Python expression in the namespace, like `Python::eval`.
+    pub fn eval(&self, code: &str) -> PyResult<&'p PyAny> {
+        self.py.eval(code, Some(self.globals), self.locals)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ExecutionNamespace;
+    use crate::exceptions;
+    use crate::objectprotocol::ObjectProtocol;
+    use crate::Python;
+
+    #[test]
+    fn test_namespace_is_reused() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let ns = ExecutionNamespace::new(py).unwrap();
+        ns.set_global("a", 1).unwrap();
+        ns.run("def f(x):\n    return a + len(x)").unwrap();
+        let v: i32 = ns.eval("f('abc')").unwrap().extract().unwrap();
+        assert_eq!(v, 4);
+        assert!(ns.globals().get_item("f").is_some());
+    }
+
+    #[test]
+    fn test_restricted_builtins() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let ns = ExecutionNamespace::with_builtins(py, &["len"])
+            .unwrap()
+            .with_locals(vec![("s", "ab")]);
+        let v: usize = ns.eval("len(s)").unwrap().extract().unwrap();
+        assert_eq!(v, 2);
+        let err = ns.eval("open").unwrap_err();
+        assert!(err.is_instance::<exceptions::NameError>(py));
+
+        ns.run("t = s * 2").unwrap();
+        assert!(ns.locals().get_item("t").is_some());
+        assert!(ns.globals().get_item("t").is_none());
+
+        let err = ExecutionNamespace::with_builtins(py, &["does_not_exist"])
+            .err()
+            .unwrap();
+        assert!(err.is_instance::<exceptions::AttributeError>(py));
+    }
+}
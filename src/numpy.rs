@@ -0,0 +1,414 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Typed numpy arrays, accessed without copying them through Python lists.
+//!
+//! Requires the `numpy` feature. numpy isn't linked: its C API is imported from the
+//! `_ARRAY_API` capsule of the installed numpy the first time it's needed, so the extension works
+//! with any numpy version installed next to it.
+//!
+//! [`PyArray<T>`] is an `ndarray` with elements of type `T`. Extracting it checks that the dtype
+//! matches `T`, and [`PyArray::as_array`] gets a view of the elements through the buffer
+//! protocol:
+//!
+//! ```no_run
+//! use pyo3::numpy::PyArray;
+//! use pyo3::prelude::*;
+//!
+//! #[pyfunction]
+//! fn total(array: &PyArray<f64>) -> PyResult<f64> {
+//!     let view = array.as_array()?;
+//!     Ok(match view.as_slice() {
+//!         Some(slice) => slice.iter().map(|x| x.get()).sum(),
+//!         // Not contiguous, e.g. a slice with a step
+//!         None => array.to_vec()?.iter().sum(),
+//!     })
+//! }
+//!
+//! #[pyfunction]
+//! fn squares(py: Python, n: u32) -> PyResult<&PyArray<u64>> {
+//!     let squares: Vec<u64> = (0..n as u64).map(|i| i * i).collect();
+//!     PyArray::from_slice(py, &squares)
+//! }
+//! ```
+//!
+//! [`PyArray<T>`]: struct.PyArray.html
+//! [`PyArray::as_array`]: struct.PyArray.html#method.as_array
+
+use crate::buffer::{Element, ElementType, PyBuffer, ReadOnlyCell};
+use crate::conversion::{extract_impl, FromPyObjectImpl};
+use crate::err::{PyDowncastError, PyErr, PyResult};
+use crate::ffi;
+use crate::instance::PyNativeType;
+use crate::internal_tricks::Unsendable;
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::PyAny;
+use crate::{AsPyPointer, PyTryFrom, Python, ToPyObject};
+use std::marker::PhantomData;
+use std::os::raw::{c_char, c_int, c_long, c_longlong, c_short, c_void};
+use std::{mem, ptr, slice};
+
+// Indices of the C API functions and objects in the `_ARRAY_API` table, from numpy_api.py
+const API_ARRAY_TYPE: usize = 2;
+const API_ARRAY_NEW: usize = 93;
+
+// The C API table, only accessed while holding the GIL
+static mut ARRAY_API: *const *const c_void = ptr::null();
+
+/// The public fields of numpy's `PyArrayObject`, which are part of its ABI.
+#[repr(C)]
+struct PyArrayObject {
+    ob_base: ffi::PyObject,
+    data: *mut c_char,
+    nd: c_int,
+    dimensions: *mut ffi::Py_ssize_t,
+    strides: *mut ffi::Py_ssize_t,
+    base: *mut ffi::PyObject,
+    descr: *mut PyArrayDescr,
+}
+
+/// The public fields of numpy's `PyArray_Descr` that have the same layout in all versions.
+#[repr(C)]
+struct PyArrayDescr {
+    ob_base: ffi::PyObject,
+    typeobj: *mut ffi::PyTypeObject,
+    kind: c_char,
+    type_: c_char,
+    byteorder: c_char,
+    flags: c_char,
+    type_num: c_int,
+}
+
+type ArrayNew = unsafe extern "C" fn(
+    subtype: *mut ffi::PyTypeObject,
+    nd: c_int,
+    dims: *const ffi::Py_ssize_t,
+    type_num: c_int,
+    strides: *const ffi::Py_ssize_t,
+    data: *mut c_void,
+    itemsize: c_int,
+    flags: c_int,
+    obj: *mut ffi::PyObject,
+) -> *mut ffi::PyObject;
+
+/// Imports numpy's C API table.
+fn array_api(py: Python) -> PyResult<*const *const c_void> {
+    unsafe {
+        if ARRAY_API.is_null() {
+            // numpy 2 renamed `numpy.core` to `numpy._core`
+            let multiarray = py
+                .import("numpy._core.multiarray")
+                .or_else(|_| py.import("numpy.core.multiarray"))?;
+            let capsule = multiarray.getattr("_ARRAY_API")?;
+            let api = ffi::PyCapsule_GetPointer(capsule.as_ptr(), ptr::null());
+            if api.is_null() {
+                return Err(PyErr::fetch(py));
+            }
+            ARRAY_API = api as *const *const c_void;
+        }
+        Ok(ARRAY_API)
+    }
+}
+
+fn array_type(py: Python) -> PyResult<*mut ffi::PyTypeObject> {
+    Ok(unsafe { *array_api(py)?.add(API_ARRAY_TYPE) as *mut ffi::PyTypeObject })
+}
+
+/// The layout of the elements of a numpy type number, which depends on the sizes of C types.
+fn type_num_element_type(type_num: c_int) -> ElementType {
+    fn signed<T>() -> ElementType {
+        ElementType::SignedInteger {
+            bytes: mem::size_of::<T>(),
+        }
+    }
+    fn unsigned<T>() -> ElementType {
+        ElementType::UnsignedInteger {
+            bytes: mem::size_of::<T>(),
+        }
+    }
+    match type_num {
+        0 => ElementType::Bool,
+        1 => signed::<i8>(),
+        2 => unsigned::<u8>(),
+        3 => signed::<c_short>(),
+        4 => unsigned::<c_short>(),
+        5 => signed::<c_int>(),
+        6 => unsigned::<c_int>(),
+        7 => signed::<c_long>(),
+        8 => unsigned::<c_long>(),
+        9 => signed::<c_longlong>(),
+        10 => unsigned::<c_longlong>(),
+        11 => ElementType::Float { bytes: 4 },
+        12 => ElementType::Float { bytes: 8 },
+        _ => ElementType::Unknown,
+    }
+}
+
+/// An element type of numpy arrays.
+pub unsafe trait ArrayElement: Element + Copy {
+    /// The numpy type number used to create arrays, e.g. `NPY_DOUBLE` for `f64`.
+    const TYPE_NUM: c_int;
+    /// The layout of the element.
+    const ELEMENT_TYPE: ElementType;
+}
+
+macro_rules! impl_array_element(
+    ($t:ty, $f:ident, $type_num:expr) => {
+        unsafe impl ArrayElement for $t {
+            const TYPE_NUM: c_int = $type_num;
+            const ELEMENT_TYPE: ElementType = ElementType::$f { bytes: mem::size_of::<$t>() };
+        }
+    }
+);
+
+impl_array_element!(i8, SignedInteger, 1);
+impl_array_element!(u8, UnsignedInteger, 2);
+impl_array_element!(i16, SignedInteger, 3);
+impl_array_element!(u16, UnsignedInteger, 4);
+impl_array_element!(i32, SignedInteger, 5);
+impl_array_element!(u32, UnsignedInteger, 6);
+impl_array_element!(i64, SignedInteger, 9);
+impl_array_element!(u64, UnsignedInteger, 10);
+impl_array_element!(f32, Float, 11);
+impl_array_element!(f64, Float, 12);
+
+/// Represents a numpy `ndarray` with elements of type `T` in native byte order.
+#[repr(transparent)]
+pub struct PyArray<T>(PyObject, Unsendable, PhantomData<T>);
+crate::pyobject_native_type_named!(PyArray<T>, T);
+
+impl<T: ArrayElement> PyArray<T> {
+    /// Creates a one-dimensional array with a copy of `data`.
+    ///
+    /// Returns an error if numpy can't be imported or the array can't be allocated.
+    pub fn from_slice<'p>(py: Python<'p>, data: &[T]) -> PyResult<&'p PyArray<T>> {
+        let array = Self::new_uninit(py, &[data.len()])?;
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr(), array.data() as *mut T, data.len());
+        }
+        Ok(array)
+    }
+
+    /// Creates a C-contiguous array of the shape `shape`, filled with zeros.
+    ///
+    /// Returns an error if numpy can't be imported or the array can't be allocated.
+    pub fn zeros<'p>(py: Python<'p>, shape: &[usize]) -> PyResult<&'p PyArray<T>> {
+        let array = Self::new_uninit(py, shape)?;
+        unsafe { ptr::write_bytes(array.data() as *mut T, 0, array.len()) };
+        Ok(array)
+    }
+
+    fn new_uninit<'p>(py: Python<'p>, shape: &[usize]) -> PyResult<&'p PyArray<T>> {
+        unsafe {
+            let api = array_api(py)?;
+            let new: ArrayNew = mem::transmute(*api.add(API_ARRAY_NEW));
+            let ptr = new(
+                array_type(py)?,
+                shape.len() as c_int,
+                shape.as_ptr() as *const ffi::Py_ssize_t,
+                T::TYPE_NUM,
+                ptr::null(),
+                ptr::null_mut(),
+                0,
+                0,
+                ptr::null_mut(),
+            );
+            let array: &PyAny = py.from_owned_ptr_or_err(ptr)?;
+            Ok(<PyArray<T> as PyTryFrom>::try_from_unchecked(array))
+        }
+    }
+
+    /// The number of dimensions.
+    pub fn ndim(&self) -> usize {
+        unsafe { (*self.raw()).nd as usize }
+    }
+
+    /// The length of each dimension.
+    pub fn shape(&self) -> &[usize] {
+        unsafe { slice::from_raw_parts((*self.raw()).dimensions as *const usize, self.ndim()) }
+    }
+
+    /// The number of bytes between consecutive elements of each dimension.
+    pub fn strides(&self) -> &[isize] {
+        unsafe { slice::from_raw_parts((*self.raw()).strides as *const isize, self.ndim()) }
+    }
+
+    /// The total number of elements.
+    pub fn len(&self) -> usize {
+        self.shape().iter().product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets a view of the elements through the buffer protocol, which keeps the array from being
+    /// resized while the view exists.
+    pub fn as_array(&self) -> PyResult<ArrayView<'_, T>> {
+        Ok(ArrayView {
+            py: self.py(),
+            buffer: PyBuffer::get(self.py(), self.as_ref())?,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Copies the elements in C order.
+    pub fn to_vec(&self) -> PyResult<Vec<T>> {
+        PyBuffer::get(self.py(), self.as_ref())?.to_vec(self.py())
+    }
+
+    fn raw(&self) -> *const PyArrayObject {
+        self.as_ptr() as *const PyArrayObject
+    }
+
+    fn data(&self) -> *mut c_char {
+        unsafe { (*self.raw()).data }
+    }
+}
+
+/// Returns `true` if `obj` is an `ndarray` with elements of type `T`.
+fn is_array_of<T: ArrayElement>(obj: &PyAny) -> bool {
+    let array_type = match array_type(obj.py()) {
+        Ok(array_type) => array_type,
+        Err(_) => return false,
+    };
+    unsafe {
+        if ffi::PyObject_TypeCheck(obj.as_ptr(), array_type) == 0 {
+            return false;
+        }
+        let descr = &*(*(obj.as_ptr() as *const PyArrayObject)).descr;
+        let native = match descr.byteorder as u8 {
+            b'=' | b'|' => true,
+            b'<' => cfg!(target_endian = "little"),
+            b'>' => cfg!(target_endian = "big"),
+            _ => false,
+        };
+        native && type_num_element_type(descr.type_num) == T::ELEMENT_TYPE
+    }
+}
+
+impl<T: ArrayElement> FromPyObjectImpl for &'_ PyArray<T> {
+    type Impl = extract_impl::Reference;
+}
+
+impl<T> ToPyObject for PyArray<T> {
+    #[inline]
+    fn to_object(&self, py: Python) -> PyObject {
+        unsafe { PyObject::from_borrowed_ptr(py, self.0.as_ptr()) }
+    }
+}
+
+impl<'a, T> From<&'a PyArray<T>> for &'a PyAny {
+    fn from(ob: &'a PyArray<T>) -> Self {
+        unsafe { &*(ob as *const PyArray<T> as *const PyAny) }
+    }
+}
+
+impl<'v, T: ArrayElement> PyTryFrom<'v> for PyArray<T> {
+    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyArray<T>, PyDowncastError> {
+        let value = value.into();
+        if is_array_of::<T>(value) {
+            Ok(unsafe { <PyArray<T> as PyTryFrom>::try_from_unchecked(value) })
+        } else {
+            Err(PyDowncastError::new(value, "ndarray"))
+        }
+    }
+
+    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyArray<T>, PyDowncastError> {
+        <PyArray<T> as PyTryFrom>::try_from(value)
+    }
+
+    #[inline]
+    unsafe fn try_from_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v PyArray<T> {
+        let ptr = value.into() as *const _ as *const PyArray<T>;
+        &*ptr
+    }
+}
+
+/// A view of the elements of a [`PyArray`](struct.PyArray.html).
+///
+/// The elements may be modified by Python code, so they are only accessed as
+/// [`ReadOnlyCell`](../buffer/struct.ReadOnlyCell.html)s or copies.
+pub struct ArrayView<'p, T> {
+    py: Python<'p>,
+    buffer: PyBuffer,
+    _marker: PhantomData<T>,
+}
+
+impl<'p, T: ArrayElement> ArrayView<'p, T> {
+    /// The number of dimensions.
+    pub fn ndim(&self) -> usize {
+        self.buffer.dimensions()
+    }
+
+    /// The length of each dimension.
+    pub fn shape(&self) -> &[usize] {
+        self.buffer.shape()
+    }
+
+    /// The number of bytes between consecutive elements of each dimension.
+    pub fn strides(&self) -> &[isize] {
+        self.buffer.strides()
+    }
+
+    /// Returns the element at `index`, or `None` if it is out of bounds or doesn't have one index
+    /// for each dimension.
+    pub fn get(&self, index: &[usize]) -> Option<T> {
+        if index.len() != self.ndim() || index.iter().zip(self.shape()).any(|(i, n)| i >= n) {
+            return None;
+        }
+        let ptr = self.buffer.get_ptr(index) as *const T;
+        Some(unsafe { ptr.read_unaligned() })
+    }
+
+    /// Returns the elements in C order, or `None` if the array isn't C-contiguous.
+    pub fn as_slice(&self) -> Option<&[ReadOnlyCell<T>]> {
+        self.buffer.as_slice(self.py)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyArray;
+    use crate::instance::AsPyRef;
+    use crate::{ObjectProtocol, PyTryFrom, Python, ToPyObject};
+
+    #[test]
+    fn test_list_is_not_array() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = vec![1.0f64, 2.0].to_object(py);
+        assert!(<PyArray<f64> as PyTryFrom>::try_from(list.as_ref(py)).is_err());
+    }
+
+    #[test]
+    fn test_array() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        // CI installs numpy, so a missing numpy fails instead of skipping the test
+        py.import("numpy")
+            .expect("testing the numpy feature requires numpy to be installed");
+        let array = PyArray::from_slice(py, &[1i64, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(array.shape(), [6]);
+        assert_eq!(array.to_vec().unwrap(), [1, 2, 3, 4, 5, 6]);
+        assert!(<PyArray<f64> as PyTryFrom>::try_from(array.as_ref()).is_err());
+
+        let matrix = array.call_method1("reshape", (2, 3)).unwrap();
+        let matrix: &PyArray<i64> = matrix.extract().unwrap();
+        assert_eq!(matrix.ndim(), 2);
+        assert_eq!(matrix.shape(), [2, 3]);
+        assert_eq!(matrix.strides(), [24, 8]);
+        let view = matrix.as_array().unwrap();
+        assert_eq!(view.get(&[1, 2]), Some(6));
+        assert_eq!(view.get(&[2, 0]), None);
+        assert_eq!(view.as_slice().unwrap()[4].get(), 5);
+
+        let transposed: &PyArray<i64> = matrix.getattr("T").unwrap().extract().unwrap();
+        assert!(transposed.as_array().unwrap().as_slice().is_none());
+        assert_eq!(transposed.to_vec().unwrap(), [1, 4, 2, 5, 3, 6]);
+
+        let zeros = PyArray::<f32>::zeros(py, &[2, 2]).unwrap();
+        assert_eq!(zeros.to_vec().unwrap(), [0.0; 4]);
+        assert!(!zeros.is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
+use crate::conversion::{trace_conversion, ConversionKind};
 use crate::err::{PyDowncastError, PyErr, PyResult};
 use crate::ffi;
 use crate::gil;
@@ -7,7 +8,7 @@ use crate::instance::{AsPyRef, PyNativeType};
 use crate::types::{PyAny, PyDict, PyTuple};
 use crate::{AsPyPointer, Py, Python};
 use crate::{FromPyObject, IntoPy, IntoPyPointer, PyTryFrom, ToBorrowedObject, ToPyObject};
-use std::ptr::NonNull;
+use std::ptr::{self, NonNull};
 
 /// A python object
 ///
@@ -93,6 +94,18 @@ impl PyObject {
         PyObject(NonNull::new_unchecked(ptr))
     }
 
+    /// Creates a `PyObject` instance for the given Python FFI pointer.
+    /// Calls Py_INCREF() on the ptr.
+    /// Panics if the pointer is `null`.
+    /// Undefined behavior if the pointer is invalid.
+    #[inline]
+    pub unsafe fn from_borrowed_ptr_or_panic(py: Python, ptr: *mut ffi::PyObject) -> PyObject {
+        match PyObject::from_borrowed_ptr_or_opt(py, ptr) {
+            Some(ob) => ob,
+            None => crate::err::panic_after_error(),
+        }
+    }
+
     /// Creates a `PyObject` instance for the given Python FFI pointer.
     /// Calls Py_INCREF() on the ptr.
     /// Returns `Err(PyErr)` if the pointer is `null`.
@@ -162,7 +175,9 @@ impl PyObject {
     where
         D: FromPyObject<'p>,
     {
-        FromPyObject::extract(self.as_ref(py))
+        let obj = self.as_ref(py);
+        trace_conversion::<D>(ConversionKind::FromPython, obj);
+        FromPyObject::extract(obj)
     }
 
     /// Retrieves an attribute value.
@@ -199,7 +214,11 @@ impl PyObject {
     /// Calls the object without arguments.
     /// This is equivalent to the Python expression: 'self()'
     pub fn call0(&self, py: Python) -> PyResult<PyObject> {
-        self.call(py, (), None)
+        unsafe {
+            let args: *mut ffi::PyObject = ptr::null_mut();
+            let result = ffi::PyObject_CallFunctionObjArgs(self.as_ptr(), args);
+            PyObject::from_owned_ptr_or_err(py, result)
+        }
     }
 
     /// Calls the object.
@@ -210,13 +229,16 @@ impl PyObject {
 
     /// Calls a method on the object.
     /// This is equivalent to the Python expression: 'self.name(*args, **kwargs)'
-    pub fn call_method(
+    pub fn call_method<N>(
         &self,
         py: Python,
-        name: &str,
+        name: N,
         args: impl IntoPy<Py<PyTuple>>,
         kwargs: Option<&PyDict>,
-    ) -> PyResult<PyObject> {
+    ) -> PyResult<PyObject>
+    where
+        N: ToPyObject,
+    {
         name.with_borrowed_ptr(py, |name| unsafe {
             let args = args.into_py(py).into_ptr();
             let kwargs = kwargs.into_ptr();
@@ -234,18 +256,28 @@ impl PyObject {
 
     /// Calls a method on the object.
     /// This is equivalent to the Python expression: 'self.name()'
-    pub fn call_method0(&self, py: Python, name: &str) -> PyResult<PyObject> {
-        self.call_method(py, name, (), None)
+    pub fn call_method0<N>(&self, py: Python, name: N) -> PyResult<PyObject>
+    where
+        N: ToPyObject,
+    {
+        name.with_borrowed_ptr(py, |name| unsafe {
+            let args: *mut ffi::PyObject = ptr::null_mut();
+            let result = ffi::PyObject_CallMethodObjArgs(self.as_ptr(), name, args);
+            PyObject::from_owned_ptr_or_err(py, result)
+        })
     }
 
     /// Calls a method on the object.
     /// This is equivalent to the Python expression: 'self.name(*args)'
-    pub fn call_method1(
+    pub fn call_method1<N>(
         &self,
         py: Python,
-        name: &str,
+        name: N,
         args: impl IntoPy<Py<PyTuple>>,
-    ) -> PyResult<PyObject> {
+    ) -> PyResult<PyObject>
+    where
+        N: ToPyObject,
+    {
         self.call_method(py, name, args, None)
     }
 }
@@ -1,6 +1,7 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
 use crate::class::basic::CompareOp;
+use crate::conversion::{trace_conversion, ConversionKind};
 use crate::err::{self, PyDowncastError, PyErr, PyResult};
 use crate::exceptions::TypeError;
 use crate::ffi;
@@ -15,6 +16,7 @@ use crate::Python;
 use crate::{FromPyObject, IntoPy, PyTryFrom, ToBorrowedObject, ToPyObject};
 use std::cmp::Ordering;
 use std::os::raw::c_int;
+use std::ptr;
 
 /// Python object model helper methods
 pub trait ObjectProtocol {
@@ -37,6 +39,17 @@ pub trait ObjectProtocol {
         N: ToBorrowedObject,
         V: ToBorrowedObject;
 
+    /// Sets several attributes, like calling [setattr](#tymethod.setattr) for each name and value,
+    /// and stops at the first error.
+    ///
+    /// The names are interned like the identifiers in Python code, which makes setting the same
+    /// attributes on many objects faster.
+    fn setattrs<I, K, V>(&self, attrs: I) -> PyResult<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: ToBorrowedObject;
+
     /// Deletes an attribute.
     /// This is equivalent to the Python expression `del self.attr_name`.
     fn delattr<N>(&self, attr_name: N) -> PyResult<()>
@@ -111,20 +124,26 @@ pub trait ObjectProtocol {
     /// list.call_method(py, "sort", (), Some(dict)).unwrap();
     /// assert_eq!(list.extract::<Vec<i32>>(py).unwrap(), vec![7, 6, 5, 4, 3]);
     /// ```
-    fn call_method(
+    fn call_method<N>(
         &self,
-        name: &str,
+        name: N,
         args: impl IntoPy<Py<PyTuple>>,
         kwargs: Option<&PyDict>,
-    ) -> PyResult<&PyAny>;
+    ) -> PyResult<&PyAny>
+    where
+        N: ToPyObject;
 
     /// Calls a method on the object.
     /// This is equivalent to the Python expression: `self.name()`.
-    fn call_method0(&self, name: &str) -> PyResult<&PyAny>;
+    fn call_method0<N>(&self, name: N) -> PyResult<&PyAny>
+    where
+        N: ToPyObject;
 
     /// Calls a method on the object with positional arguments only.
     /// This is equivalent to the Python expression: `self.name(*args)`.
-    fn call_method1(&self, name: &str, args: impl IntoPy<Py<PyTuple>>) -> PyResult<&PyAny>;
+    fn call_method1<N>(&self, name: N, args: impl IntoPy<Py<PyTuple>>) -> PyResult<&PyAny>
+    where
+        N: ToPyObject;
 
     /// Retrieves the hash code of the object.
     /// This is equivalent to the Python expression: `hash(self)`.
@@ -178,13 +197,8 @@ pub trait ObjectProtocol {
     /// Gets the Python base object for this object.
     fn get_base(&self) -> &<Self as PyTypeInfo>::BaseType
     where
-        Self: PyTypeInfo;
-
-    /// Gets the Python base object for this object.
-
-    fn get_mut_base(&mut self) -> &mut <Self as PyTypeInfo>::BaseType
-    where
-        Self: PyTypeInfo;
+        Self: PyTypeInfo,
+        <Self as PyTypeInfo>::BaseType: PyNativeType;
 
     /// Casts the PyObject to a concrete Python object type.
     fn cast_as<'a, D>(&'a self) -> Result<&'a D, PyDowncastError>
@@ -245,6 +259,19 @@ where
         })
     }
 
+    fn setattrs<I, K, V>(&self, attrs: I) -> PyResult<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: ToBorrowedObject,
+    {
+        let py = self.py();
+        for (name, value) in attrs {
+            self.setattr(PyString::intern(py, name.as_ref()), value)?;
+        }
+        Ok(())
+    }
+
     fn delattr<N>(&self, attr_name: N) -> PyResult<()>
     where
         N: ToPyObject,
@@ -338,19 +365,27 @@ where
     }
 
     fn call0(&self) -> PyResult<&PyAny> {
-        self.call((), None)
+        // Passes no argument tuple, so CPython can use its faster calling conventions
+        unsafe {
+            let args: *mut ffi::PyObject = ptr::null_mut();
+            let result = ffi::PyObject_CallFunctionObjArgs(self.as_ptr(), args);
+            self.py().from_owned_ptr_or_err(result)
+        }
     }
 
     fn call1(&self, args: impl IntoPy<Py<PyTuple>>) -> PyResult<&PyAny> {
         self.call(args, None)
     }
 
-    fn call_method(
+    fn call_method<N>(
         &self,
-        name: &str,
+        name: N,
         args: impl IntoPy<Py<PyTuple>>,
         kwargs: Option<&PyDict>,
-    ) -> PyResult<&PyAny> {
+    ) -> PyResult<&PyAny>
+    where
+        N: ToPyObject,
+    {
         name.with_borrowed_ptr(self.py(), |name| unsafe {
             let py = self.py();
             let ptr = ffi::PyObject_GetAttr(self.as_ptr(), name);
@@ -368,11 +403,22 @@ where
         })
     }
 
-    fn call_method0(&self, name: &str) -> PyResult<&PyAny> {
-        self.call_method(name, (), None)
+    fn call_method0<N>(&self, name: N) -> PyResult<&PyAny>
+    where
+        N: ToPyObject,
+    {
+        // Unlike getting the attribute and calling it, this doesn't create a bound method
+        name.with_borrowed_ptr(self.py(), |name| unsafe {
+            let args: *mut ffi::PyObject = ptr::null_mut();
+            let result = ffi::PyObject_CallMethodObjArgs(self.as_ptr(), name, args);
+            self.py().from_owned_ptr_or_err(result)
+        })
     }
 
-    fn call_method1(&self, name: &str, args: impl IntoPy<Py<PyTuple>>) -> PyResult<&PyAny> {
+    fn call_method1<N>(&self, name: N, args: impl IntoPy<Py<PyTuple>>) -> PyResult<&PyAny>
+    where
+        N: ToPyObject,
+    {
         self.call_method(name, args, None)
     }
 
@@ -443,7 +489,7 @@ where
     }
 
     fn iter(&self) -> PyResult<PyIterator> {
-        Ok(PyIterator::from_object(self.py(), self)?)
+        PyIterator::iter_object(self.py(), self)
     }
 
     fn get_type(&self) -> &PyType {
@@ -458,17 +504,11 @@ where
     fn get_base(&self) -> &<Self as PyTypeInfo>::BaseType
     where
         Self: PyTypeInfo,
+        <Self as PyTypeInfo>::BaseType: PyNativeType,
     {
         unsafe { self.py().from_borrowed_ptr(self.as_ptr()) }
     }
 
-    fn get_mut_base(&mut self) -> &mut <Self as PyTypeInfo>::BaseType
-    where
-        Self: PyTypeInfo,
-    {
-        unsafe { self.py().mut_from_borrowed_ptr(self.as_ptr()) }
-    }
-
     fn cast_as<'a, D>(&'a self) -> Result<&'a D, PyDowncastError>
     where
         D: PyTryFrom<'a>,
@@ -482,7 +522,9 @@ where
         D: FromPyObject<'a>,
         &'a PyAny: std::convert::From<&'a T>,
     {
-        FromPyObject::extract(self.into())
+        let obj = self.into();
+        trace_conversion::<D>(ConversionKind::FromPython, obj);
+        FromPyObject::extract(obj)
     }
 
     fn get_refcnt(&self) -> isize {
@@ -498,6 +540,7 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::exceptions::AttributeError;
     use crate::instance::AsPyRef;
     use crate::types::{IntoPyDict, PyString};
     use crate::Python;
@@ -532,6 +575,20 @@ mod test {
         assert!(a.call_method1("nonexistent_method", (1,)).is_err());
     }
 
+    #[test]
+    fn test_call_without_arguments() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = py.eval("[3, 1, 2]", None, None).unwrap();
+        let copy = list.call_method0("copy").unwrap();
+        assert_eq!(copy.extract::<Vec<i32>>().unwrap(), vec![3, 1, 2]);
+
+        let answer = py.eval("lambda: 42", None, None).unwrap();
+        assert_eq!(answer.call0().unwrap().extract::<i32>().unwrap(), 42);
+        let identity = py.eval("lambda x: x", None, None).unwrap();
+        assert!(identity.call0().unwrap_err().is_instance::<TypeError>(py));
+    }
+
     #[test]
     fn test_call_with_kwargs() {
         let gil = Python::acquire_gil();
@@ -542,6 +599,38 @@ mod test {
         assert_eq!(list.extract::<Vec<i32>>(py).unwrap(), vec![7, 6, 5, 4, 3]);
     }
 
+    #[test]
+    fn test_setattrs() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let namespace = py
+            .eval("__import__('types').SimpleNamespace()", None, None)
+            .unwrap();
+        namespace
+            .setattrs(vec![("name", "x".to_object(py)), ("size", 3.to_object(py))])
+            .unwrap();
+        assert_eq!(
+            namespace
+                .getattr("name")
+                .unwrap()
+                .extract::<&str>()
+                .unwrap(),
+            "x"
+        );
+        assert_eq!(
+            namespace.getattr("size").unwrap().extract::<i32>().unwrap(),
+            3
+        );
+
+        // Attributes before the failing one are set
+        let slots = py
+            .eval("type('Slots', (), {'__slots__': ('x',)})()", None, None)
+            .unwrap();
+        let err = slots.setattrs(vec![("x", 1), ("y", 2)]).unwrap_err();
+        assert!(err.is_instance::<AttributeError>(py));
+        assert_eq!(slots.getattr("x").unwrap().extract::<i32>().unwrap(), 1);
+    }
+
     #[test]
     fn test_type() {
         let gil = Python::acquire_gil();
@@ -0,0 +1,79 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Parallel processing of Python sequences with [rayon](https://docs.rs/rayon), enabled with the
+//! `rayon` feature.
+//!
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::parallel::par_map;
+//! use pyo3::types::PyAny;
+//!
+//! #[pyfunction]
+//! fn word_lengths(py: Python, words: &PyAny) -> PyResult<PyObject> {
+//!     let lengths = par_map(py, words, |word: String| word.chars().count())?;
+//!     Ok(lengths.into())
+//! }
+//! ```
+
+use crate::err::PyResult;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyList};
+use crate::{FromPyObject, IntoPy, PyObject, Python};
+use rayon::prelude::*;
+use std::panic;
+
+/// Applies `f` to the items of `sequence` on the rayon thread pool and returns the results as a
+/// list, in the order of the items.
+///
+/// The items of any Python iterable are extracted to `T` first, with the GIL held. Then the GIL is
+/// released while `f` runs, so other Python threads can continue, and the results are converted
+/// with `IntoPy` after it has been reacquired. A panic in `f` is resumed once the GIL is held
+/// again.
+pub fn par_map<'p, T, R, F>(py: Python<'p>, sequence: &'p PyAny, f: F) -> PyResult<&'p PyList>
+where
+    T: for<'a> FromPyObject<'a> + Send,
+    R: IntoPy<PyObject> + Send,
+    F: Fn(T) -> R + Send + Sync,
+{
+    let items = sequence
+        .iter()?
+        .map(|item| item.and_then(ObjectProtocol::extract))
+        .collect::<PyResult<Vec<T>>>()?;
+
+    let results = py.allow_threads(|| {
+        panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            items.into_par_iter().map(f).collect::<Vec<R>>()
+        }))
+    });
+    let results = match results {
+        Ok(results) => results,
+        Err(payload) => panic::resume_unwind(payload),
+    };
+
+    let results: Vec<PyObject> = results.into_iter().map(|r| r.into_py(py)).collect();
+    Ok(PyList::new(py, results))
+}
+
+#[cfg(test)]
+mod test {
+    use super::par_map;
+    use crate::types::PyList;
+    use crate::{ObjectProtocol, Python};
+
+    #[test]
+    fn test_par_map() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let words = py
+            .eval("iter(['a', 'bb', 'ccc'] * 100)", None, None)
+            .unwrap();
+        let lengths = par_map(py, words, |word: String| word.len()).unwrap();
+        let lengths: Vec<usize> = lengths.extract().unwrap();
+        assert_eq!(lengths.len(), 300);
+        assert_eq!(lengths[..4], [1, 2, 3, 1]);
+
+        let mixed = PyList::new(py, &[1, 2]);
+        mixed.append("three").unwrap();
+        assert!(par_map(py, mixed.as_ref(), |i: i32| i * 2).is_err());
+    }
+}
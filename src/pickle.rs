@@ -0,0 +1,270 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Pickling classes backed by large buffers without copying them, with the out-of-band buffers of
+//! pickle protocol 5 ([PEP 574](https://www.python.org/dev/peps/pep-0574/)).
+//!
+//! A class exporting its data with the buffer protocol returns [`reduce_buffer`] from
+//! `__reduce_ex__`. Under protocol 5 the data is passed as a `pickle.PickleBuffer` over the
+//! object itself, so `pickle.dumps(obj, protocol=5, buffer_callback=...)` hands the memory to the
+//! callback instead of copying it into the pickle, e.g. to send it through shared memory to
+//! another process. Older protocols, and Python before 3.8, pickle a `bytes` copy instead.
+//!
+//! On unpickling, the class is called with the data, which is whatever object the buffer was
+//! restored as: a `bytes` or `bytearray` from the pickle, or an object passed in the `buffers`
+//! of `pickle.loads`. Extracting it as a [`PickledBuffer`] keeps that object's memory without
+//! copying it:
+//!
+//! ```
+//! # #![feature(specialization)]
+//! use pyo3::class::PyBufferProtocol;
+//! use pyo3::ffi;
+//! use pyo3::pickle::{self, PickledBuffer};
+//! use pyo3::prelude::*;
+//! use pyo3::PyClassShell;
+//! use std::os::raw::c_int;
+//! use std::ptr;
+//!
+//! #[pyclass]
+//! struct Blob {
+//!     data: PickledBuffer,
+//! }
+//!
+//! #[pymethods]
+//! impl Blob {
+//!     #[new]
+//!     fn new(data: PickledBuffer) -> Self {
+//!         Blob { data }
+//!     }
+//!
+//!     fn __reduce_ex__(
+//!         slf: &PyClassShell<Self>,
+//!         py: Python,
+//!         protocol: i32,
+//!     ) -> PyResult<PyObject> {
+//!         pickle::reduce_buffer(py, slf, protocol)
+//!     }
+//! }
+//!
+//! #[pyproto]
+//! impl PyBufferProtocol for Blob {
+//!     fn bf_getbuffer(&self, view: *mut ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+//!         let (ptr, len) = (self.data.buf_ptr(), self.data.len() as isize);
+//!         let readonly = self.data.readonly() as c_int;
+//!         // The exporting object is filled in by PyO3
+//!         let result =
+//!             unsafe { ffi::PyBuffer_FillInfo(view, ptr::null_mut(), ptr, len, readonly, flags) };
+//!         match result {
+//!             0 => Ok(()),
+//!             _ => Err(PyErr::fetch(unsafe { Python::assume_gil_acquired() })),
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! With the `pickle-serde` feature, `#[derive(Pickle)]` makes a `#[pyclass]` implementing serde's
+//! `Serialize` and `Deserialize` picklable, and copyable with `copy.deepcopy`. It adds
+//! `__getstate__`, which returns the struct serialized to Python objects by the [`serde`] module,
+//! `__setstate__`, and a `__reduce__` restoring the object from that state with any pickle
+//! protocol, without calling the `#[new]` constructor:
+//!
+//! ```ignore
+//! use pyo3::pickle::Pickle;
+//! use pyo3::prelude::*;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[pyclass(module = "geometry")]
+//! #[derive(Serialize, Deserialize, Pickle)]
+//! struct Point {
+//!     x: f64,
+//!     y: f64,
+//! }
+//! ```
+//!
+//! Pickle finds the class by its module and name, so add the class to the module named by
+//! `#[pyclass(module = ...)]`. Instances of Python subclasses are restored as the `#[pyclass]`.
+//!
+//! [`reduce_buffer`]: fn.reduce_buffer.html
+//! [`PickledBuffer`]: struct.PickledBuffer.html
+//! [`serde`]: ../serde/index.html
+
+use crate::buffer::{PyBuffer, ReadOnlyCell};
+use crate::err::PyResult;
+use crate::exceptions::BufferError;
+#[cfg(feature = "pickle-serde")]
+use crate::exceptions::ValueError;
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::objectprotocol::ObjectProtocol;
+#[cfg(feature = "pickle-serde")]
+use crate::pyclass::{PyClass, PyClassShell};
+use crate::types::PyAny;
+#[cfg(feature = "pickle-serde")]
+use crate::PyErr;
+use crate::{FromPyObject, IntoPy, PyObject, Python, ToPyObject};
+use std::os::raw::c_void;
+use std::slice;
+
+/// The first pickle protocol with out-of-band buffers.
+pub const OUT_OF_BAND_PROTOCOL: i32 = 5;
+
+/// Returns the value of `__reduce_ex__(protocol)` for `obj`, which exports its data with the
+/// buffer protocol: `type(obj)` is called with the data given by [`pickle_buffer`].
+///
+/// [`pickle_buffer`]: fn.pickle_buffer.html
+pub fn reduce_buffer(py: Python, obj: impl ToPyObject, protocol: i32) -> PyResult<PyObject> {
+    let obj = obj.to_object(py);
+    let obj = obj.as_ref(py);
+    let data = pickle_buffer(py, obj, protocol)?;
+    Ok((obj.get_type(), (data,)).into_py(py))
+}
+
+/// Returns the data of `obj` to be pickled with `protocol`: a `pickle.PickleBuffer` over `obj`,
+/// which may be pickled out-of-band, for protocol 5 or higher, and a `bytes` copy otherwise.
+///
+/// Use this instead of [`reduce_buffer`] to reconstruct the object with another callable or more
+/// arguments.
+///
+/// [`reduce_buffer`]: fn.reduce_buffer.html
+pub fn pickle_buffer<'p>(py: Python<'p>, obj: &'p PyAny, protocol: i32) -> PyResult<&'p PyAny> {
+    let pickle = py.import("pickle")?;
+    if protocol >= OUT_OF_BAND_PROTOCOL && pickle.hasattr("PickleBuffer")? {
+        pickle.call1("PickleBuffer", (obj,))
+    } else {
+        py.import("builtins")?.call1("bytes", (obj,))
+    }
+}
+
+/// The contiguous memory of an unpickled buffer, kept exported from the object it was restored
+/// as, like a `bytes`, a `bytearray` or a `pickle.PickleBuffer`.
+///
+/// Extracting it doesn't copy the data, and it keeps the object alive.
+pub struct PickledBuffer {
+    buffer: PyBuffer,
+}
+
+impl PickledBuffer {
+    /// The size of the data in bytes.
+    pub fn len(&self) -> usize {
+        self.buffer.len_bytes()
+    }
+
+    /// Returns true if there's no data.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if the memory can't be written, e.g. for a `bytes`.
+    pub fn readonly(&self) -> bool {
+        self.buffer.readonly()
+    }
+
+    /// Gets the pointer to the start of the data, e.g. to export it again with the buffer
+    /// protocol.
+    ///
+    /// The object may change the data of writable buffers while the GIL is held.
+    pub fn buf_ptr(&self) -> *mut c_void {
+        self.buffer.buf_ptr()
+    }
+
+    /// The data, which Python code may change between reads for writable buffers.
+    pub fn as_slice<'a>(&'a self, _py: Python<'a>) -> &'a [ReadOnlyCell<u8>] {
+        unsafe { slice::from_raw_parts(self.buf_ptr() as *const ReadOnlyCell<u8>, self.len()) }
+    }
+
+    /// Copies the data into a new `Vec`.
+    pub fn to_vec(&self, py: Python) -> Vec<u8> {
+        self.as_slice(py).iter().map(ReadOnlyCell::get).collect()
+    }
+}
+
+impl<'source> FromPyObject<'source> for PickledBuffer {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let py = obj.py();
+        let buffer = PyBuffer::get(py, obj)?;
+        if !buffer.is_c_contiguous() {
+            buffer.release(py);
+            return Err(BufferError::py_err("the pickled buffer is not contiguous"));
+        }
+        Ok(PickledBuffer { buffer })
+    }
+}
+
+#[cfg(feature = "pickle-serde")]
+pub use pyo3cls::Pickle;
+
+/// Serializes `value` as the state of a `#[derive(Pickle)]` class, with
+/// [`serde::to_object`](../serde/fn.to_object.html). Raises `pickle.PicklingError` if serde
+/// fails.
+#[cfg(feature = "pickle-serde")]
+pub fn dump_state<T: serde::Serialize>(py: Python, value: &T) -> PyResult<PyObject> {
+    crate::serde::to_object(py, value).map_err(|e| pickle_error(py, "PicklingError", e))
+}
+
+/// Deserializes a state returned by [`dump_state`]. Raises `pickle.UnpicklingError` if serde
+/// fails, and `TypeError` if the state has the wrong type.
+///
+/// [`dump_state`]: fn.dump_state.html
+#[cfg(feature = "pickle-serde")]
+pub fn load_state<T: serde::de::DeserializeOwned>(state: &PyAny) -> PyResult<T> {
+    crate::serde::from_object(state).map_err(|e| pickle_error(state.py(), "UnpicklingError", e))
+}
+
+/// Returns the value of `__reduce__` for a `#[derive(Pickle)]` class: the classmethod
+/// `_pyo3_unpickle` of the class is called with the state.
+#[cfg(feature = "pickle-serde")]
+pub fn reduce_state<T>(py: Python, obj: &PyClassShell<T>) -> PyResult<PyObject>
+where
+    T: PyClass + serde::Serialize,
+{
+    let state = dump_state(py, &*obj.try_borrow()?)?;
+    let unpickle = py.get_type::<T>().getattr("_pyo3_unpickle")?;
+    Ok((unpickle, (state,)).into_py(py))
+}
+
+/// Raises the `ValueError`s of serde as `pickle.<name>`.
+#[cfg(feature = "pickle-serde")]
+fn pickle_error(py: Python, name: &str, err: PyErr) -> PyErr {
+    if !err.is_instance::<ValueError>(py) {
+        return err;
+    }
+    let message = err.to_object(py);
+    let err = py.import("pickle").and_then(|pickle| {
+        let message = message.as_ref(py).str()?;
+        pickle.call1(name, (message,))
+    });
+    match err {
+        Ok(err) => PyErr::from_instance(err),
+        Err(e) => e,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{pickle_buffer, PickledBuffer};
+    use crate::types::PyBytes;
+    use crate::{ObjectProtocol, Python};
+
+    #[test]
+    fn test_pickle_buffer() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let bytes = PyBytes::new(py, b"data");
+
+        let copy = pickle_buffer(py, bytes.as_ref(), 4).unwrap();
+        assert_eq!(copy.extract::<&[u8]>().unwrap(), b"data");
+        let data = pickle_buffer(py, bytes.as_ref(), 5).unwrap();
+        if py
+            .import("pickle")
+            .unwrap()
+            .hasattr("PickleBuffer")
+            .unwrap()
+        {
+            assert!(data.get_type().name().ends_with("PickleBuffer"));
+        }
+
+        let pickled: PickledBuffer = data.extract().unwrap();
+        assert_eq!(pickled.len(), 4);
+        assert!(pickled.readonly());
+        assert_eq!(pickled.buf_ptr() as *const u8, bytes.as_bytes().as_ptr());
+        assert_eq!(pickled.to_vec(py), b"data");
+    }
+}
@@ -0,0 +1,359 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! `py_class!`, a declarative alternative to `#[pyclass]` and `#[pymethods]`
+
+/// Defines a Python class without procedural macros.
+///
+/// This generates the same type object as `#[pyclass]` together with `#[pymethods]`, for
+/// projects that can't use procedural macros. The struct is defined as written, and the
+/// functions of the `impl` block become its methods:
+///
+/// * `&self` and `&mut self` methods are instance methods. Like with `#[pymethods]`, the
+///   instance is borrowed while the method runs, so a conflicting borrow raises a `RuntimeError`.
+/// * A function marked with `#[new]` is the constructor. It returns `Self` or `PyResult<Self>`.
+///
+/// The arguments are extracted with `FromPyObject` and may be passed by position or by keyword.
+/// Default values, `*args`, `**kwargs`, properties and protocols are not supported; use
+/// `#[pyclass]` for those. Doc comments become the `__doc__` of the class and the methods.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::{py_class, py_run};
+///
+/// py_class! {
+///     /// Counts up from a start value
+///     pub struct Counter {
+///         count: i32,
+///     }
+///
+///     impl Counter {
+///         #[new]
+///         fn new(start: i32) -> Self {
+///             Counter { count: start }
+///         }
+///
+///         /// Adds `step` to the counter and returns the new value
+///         fn increment(&mut self, step: i32) -> i32 {
+///             self.count += step;
+///             self.count
+///         }
+///
+///         fn get(&self) -> i32 {
+///             self.count
+///         }
+///     }
+/// }
+///
+/// # fn main() {
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let counter = py.get_type::<Counter>();
+/// py_run!(py, counter, r#"
+///     c = counter(5)
+///     assert c.increment(2) == 7
+///     assert c.increment(step=3) == 10
+///     assert c.get() == 10
+/// "#);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! py_class {
+    (
+        $(#[$($attr:tt)*])*
+        $vis:vis struct $name:ident { $($fields:tt)* }
+    ) => {
+        $crate::py_class! {
+            $(#[$($attr)*])*
+            $vis struct $name { $($fields)* }
+
+            impl $name {}
+        }
+    };
+    (
+        $(#[$($attr:tt)*])*
+        $vis:vis struct $name:ident { $($fields:tt)* }
+
+        impl $impl_name:ident { $($methods:tt)* }
+    ) => {
+        $(#[$($attr)*])*
+        $vis struct $name { $($fields)* }
+
+        unsafe impl $crate::type_object::PyTypeInfo for $name {
+            type Type = $name;
+            type BaseType = $crate::types::PyAny;
+            type ConcreteLayout = $crate::pyclass::PyClassShell<Self>;
+            type Initializer = $crate::pyclass_init::PyClassInitializer<Self>;
+            type AsRefTarget = $crate::pyclass::PyClassShell<Self>;
+
+            const NAME: &'static str = stringify!($name);
+            const MODULE: Option<&'static str> = None;
+            const DESCRIPTION: &'static str = $crate::py_class_impl!(@docs [] $([$($attr)*])*);
+            const FLAGS: usize = 0;
+
+            #[inline]
+            fn type_object() -> &'static $crate::ffi::PyTypeObject {
+                use $crate::type_object::LazyStaticType;
+                static TYPE_OBJECT: LazyStaticType = LazyStaticType::new();
+                TYPE_OBJECT.get_or_init::<Self>()
+            }
+        }
+
+        impl $crate::PyClass for $name {
+            type Dict = $crate::pyclass_slots::PyClassDummySlot;
+            type WeakRef = $crate::pyclass_slots::PyClassDummySlot;
+        }
+
+        impl $crate::pyclass::PyClassAlloc for $name {}
+
+        impl $crate::conversion::FromPyObjectImpl for $name {
+            type Impl = $crate::conversion::extract_impl::Cloned;
+        }
+
+        impl $crate::IntoPy<$crate::PyObject> for $name {
+            fn into_py(self, py: $crate::Python) -> $crate::PyObject {
+                $crate::IntoPy::into_py($crate::Py::new(py, self).unwrap(), py)
+            }
+        }
+
+        $crate::py_class_impl!(@methods $impl_name [] $($methods)*);
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! py_class_impl {
+    // Collects the doc comments from a list of attributes
+    (@docs [$($doc:expr),*]) => {
+        $crate::py_class_impl!(@doc $($doc),*)
+    };
+    (@docs [$($doc:expr),*] [doc = $new:expr] $($rest:tt)*) => {
+        $crate::py_class_impl!(@docs [$($doc,)* $new] $($rest)*)
+    };
+    (@docs [$($doc:expr),*] [$($other:tt)*] $($rest:tt)*) => {
+        $crate::py_class_impl!(@docs [$($doc),*] $($rest)*)
+    };
+
+    // Joins doc comment lines into a null terminated string
+    (@doc) => {
+        "\0"
+    };
+    (@doc $first:expr $(, $line:expr)*) => {
+        concat!($first, $("\n", $line,)* "\0")
+    };
+
+    // All methods are processed
+    (@methods $cls:ident [$($defs:expr,)*]) => {
+        impl $crate::class::methods::PyMethodsProtocol for $cls {
+            fn py_methods() -> Vec<&'static $crate::class::PyMethodDefType> {
+                static METHODS: &[$crate::class::PyMethodDefType] = &[$($defs),*];
+                METHODS.iter().collect()
+            }
+        }
+    };
+
+    // Constructor
+    (@methods $cls:ident [$($defs:expr,)*]
+        $(#[doc = $doc:expr])*
+        #[new]
+        $vis:vis fn $meth:ident($($arg:ident: $arg_ty:ty),* $(,)?) -> $ret:ty $body:block
+        $($rest:tt)*
+    ) => {
+        impl $cls {
+            $(#[doc = $doc])*
+            $vis fn $meth($($arg: $arg_ty),*) -> $ret $body
+        }
+
+        $crate::py_class_impl!(@methods $cls [$($defs,)* $crate::class::PyMethodDefType::New({
+            unsafe extern "C" fn __wrap(
+                _cls: *mut $crate::ffi::PyTypeObject,
+                _args: *mut $crate::ffi::PyObject,
+                _kwargs: *mut $crate::ffi::PyObject,
+            ) -> *mut $crate::ffi::PyObject {
+                use $crate::ObjectProtocol;
+
+                const _LOCATION: &'static str =
+                    concat!(stringify!($cls), ".", stringify!($meth), "()");
+                let _py = $crate::Python::assume_gil_acquired();
+                let _pool = $crate::GILPool::new(_py);
+                let _args = _py.from_borrowed_ptr::<$crate::types::PyTuple>(_args);
+                let _kwargs: Option<&$crate::types::PyDict> = _py.from_borrowed_ptr_or_opt(_kwargs);
+
+                static _PARAMS: $crate::argparse::FunctionParams =
+                    $crate::argparse::FunctionParams::new(&[$(
+                        $crate::argparse::ParamDescription {
+                            name: stringify!($arg),
+                            is_optional: false,
+                            kw_only: false,
+                        }
+                    ),*]);
+                let mut _output = [$($crate::py_class_impl!(@none $arg)),*];
+
+                let _result = (|| {
+                    $crate::argparse::parse_fn_args(
+                        Some(_LOCATION),
+                        &_PARAMS,
+                        _args,
+                        _kwargs,
+                        false,
+                        false,
+                        &mut _output,
+                    )?;
+                    #[allow(unused_mut, unused_variables)]
+                    let mut _output = _output.iter().cloned();
+                    $(
+                        let $arg: $arg_ty = _output.next().unwrap().unwrap().extract()?;
+                    )*
+                    $crate::derive_utils::IntoPyNewResult::into_pynew_result($cls::$meth($($arg),*))
+                })();
+
+                match _result
+                    .and_then(|init| $crate::PyClassInitializer::from(init).create_shell(_py))
+                {
+                    Ok(slf) => slf as _,
+                    Err(e) => e.restore_and_null(_py),
+                }
+            }
+
+            $crate::class::PyMethodDef {
+                ml_name: stringify!($meth),
+                ml_meth: $crate::class::PyMethodType::PyNewFunc(__wrap),
+                ml_flags: $crate::ffi::METH_VARARGS | $crate::ffi::METH_KEYWORDS,
+                ml_doc: $crate::py_class_impl!(@doc $($doc),*),
+            }
+        }),] $($rest)*);
+    };
+
+    // Methods taking `&mut self` or `&self`. `self` is passed through from the input, because
+    // the method body can only refer to it with the same hygiene.
+    (@methods $cls:ident [$($defs:expr,)*]
+        $(#[doc = $doc:expr])*
+        $vis:vis fn $meth:ident(&mut $self_:ident $(, $arg:ident: $arg_ty:ty)* $(,)?) $(-> $ret:ty)? $body:block
+        $($rest:tt)*
+    ) => {
+        impl $cls {
+            $(#[doc = $doc])*
+            $vis fn $meth(&mut $self_ $(, $arg: $arg_ty)*) $(-> $ret)? $body
+        }
+
+        $crate::py_class_impl!(@method $cls try_borrow_mut [$($defs,)*] [$($doc),*]
+            $meth($($arg: $arg_ty),*) $($rest)*);
+    };
+    (@methods $cls:ident [$($defs:expr,)*]
+        $(#[doc = $doc:expr])*
+        $vis:vis fn $meth:ident(&$self_:ident $(, $arg:ident: $arg_ty:ty)* $(,)?) $(-> $ret:ty)? $body:block
+        $($rest:tt)*
+    ) => {
+        impl $cls {
+            $(#[doc = $doc])*
+            $vis fn $meth(&$self_ $(, $arg: $arg_ty)*) $(-> $ret)? $body
+        }
+
+        $crate::py_class_impl!(@method $cls try_borrow [$($defs,)*] [$($doc),*]
+            $meth($($arg: $arg_ty),*) $($rest)*);
+    };
+
+    // The method definition and its wrapper, without arguments
+    (@method $cls:ident $borrow:ident [$($defs:expr,)*] [$($doc:expr),*]
+        $meth:ident() $($rest:tt)*
+    ) => {
+        $crate::py_class_impl!(@methods $cls [$($defs,)* $crate::class::PyMethodDefType::Method({
+            unsafe extern "C" fn __wrap(
+                _slf: *mut $crate::ffi::PyObject,
+                _args: *mut $crate::ffi::PyObject,
+            ) -> *mut $crate::ffi::PyObject {
+                let _py = $crate::Python::assume_gil_acquired();
+                let _pool = $crate::GILPool::new(_py);
+                let _cell: &$crate::PyClassShell<$cls> =
+                    $crate::FromPyPointer::from_borrowed_ptr(_py, _slf);
+                #[allow(unused_mut)]
+                let mut _slf = match _cell.$borrow() {
+                    Ok(_slf) => _slf,
+                    Err(e) => return $crate::PyErr::from(e).restore_and_null(_py),
+                };
+                let _result = $crate::derive_utils::IntoPyResult::into_py_result(_slf.$meth());
+
+                $crate::callback::cb_convert(
+                    $crate::callback::PyObjectCallbackConverter, _py, _result)
+            }
+
+            $crate::class::PyMethodDef {
+                ml_name: stringify!($meth),
+                ml_meth: $crate::class::PyMethodType::PyCFunction(__wrap),
+                ml_flags: $crate::ffi::METH_NOARGS,
+                ml_doc: $crate::py_class_impl!(@doc $($doc),*),
+            }
+        }),] $($rest)*);
+    };
+
+    // The method definition and its wrapper, with arguments
+    (@method $cls:ident $borrow:ident [$($defs:expr,)*] [$($doc:expr),*]
+        $meth:ident($($arg:ident: $arg_ty:ty),+) $($rest:tt)*
+    ) => {
+        $crate::py_class_impl!(@methods $cls [$($defs,)* $crate::class::PyMethodDefType::Method({
+            unsafe extern "C" fn __wrap(
+                _slf: *mut $crate::ffi::PyObject,
+                _args: *mut $crate::ffi::PyObject,
+                _kwargs: *mut $crate::ffi::PyObject,
+            ) -> *mut $crate::ffi::PyObject {
+                use $crate::ObjectProtocol;
+
+                const _LOCATION: &'static str =
+                    concat!(stringify!($cls), ".", stringify!($meth), "()");
+                let _py = $crate::Python::assume_gil_acquired();
+                let _pool = $crate::GILPool::new(_py);
+                let _cell: &$crate::PyClassShell<$cls> =
+                    $crate::FromPyPointer::from_borrowed_ptr(_py, _slf);
+                #[allow(unused_mut)]
+                let mut _slf = match _cell.$borrow() {
+                    Ok(_slf) => _slf,
+                    Err(e) => return $crate::PyErr::from(e).restore_and_null(_py),
+                };
+                let _args = _py.from_borrowed_ptr::<$crate::types::PyTuple>(_args);
+                let _kwargs: Option<&$crate::types::PyDict> = _py.from_borrowed_ptr_or_opt(_kwargs);
+
+                static _PARAMS: $crate::argparse::FunctionParams =
+                    $crate::argparse::FunctionParams::new(&[$(
+                        $crate::argparse::ParamDescription {
+                            name: stringify!($arg),
+                            is_optional: false,
+                            kw_only: false,
+                        }
+                    ),*]);
+                let mut _output = [$($crate::py_class_impl!(@none $arg)),*];
+
+                let _result = (|| {
+                    $crate::argparse::parse_fn_args(
+                        Some(_LOCATION),
+                        &_PARAMS,
+                        _args,
+                        _kwargs,
+                        false,
+                        false,
+                        &mut _output,
+                    )?;
+                    let mut _output = _output.iter().cloned();
+                    $(
+                        let $arg: $arg_ty = _output.next().unwrap().unwrap().extract()?;
+                    )*
+                    $crate::derive_utils::IntoPyResult::into_py_result(_slf.$meth($($arg),*))
+                })();
+
+                $crate::callback::cb_convert(
+                    $crate::callback::PyObjectCallbackConverter, _py, _result)
+            }
+
+            $crate::class::PyMethodDef {
+                ml_name: stringify!($meth),
+                ml_meth: $crate::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                ml_flags: $crate::ffi::METH_VARARGS | $crate::ffi::METH_KEYWORDS,
+                ml_doc: $crate::py_class_impl!(@doc $($doc),*),
+            }
+        }),] $($rest)*);
+    };
+
+    // An empty slot of the argument array
+    (@none $arg:ident) => {
+        None
+    };
+}
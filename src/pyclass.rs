@@ -1,12 +1,16 @@
 //! Traits and structs for `#[pyclass]`.
 use crate::class::methods::{PyMethodDefType, PyMethodsProtocol};
-use crate::conversion::{AsPyPointer, FromPyPointer, ToPyObject};
+use crate::conversion::{
+    extract_impl, AsPyPointer, FromPyObject, FromPyObjectImpl, FromPyPointer, PyTryFrom, ToPyObject,
+};
 use crate::pyclass_init::PyClassInitializer;
 use crate::pyclass_slots::{PyClassDict, PyClassWeakRef};
 use crate::type_object::{type_flags, PyObjectLayout, PyObjectSizedLayout};
 use crate::types::PyAny;
-use crate::{class, ffi, gil, PyErr, PyObject, PyResult, PyTypeInfo, Python};
+use crate::{class, exceptions, ffi, gil, PyErr, PyObject, PyResult, PyTypeInfo, Python};
+use std::cell::{Cell, UnsafeCell};
 use std::ffi::CString;
+use std::fmt;
 use std::mem::ManuallyDrop;
 use std::os::raw::c_void;
 use std::ptr::{self, NonNull};
@@ -36,16 +40,27 @@ pub trait PyClassAlloc: PyTypeInfo + Sized {
         default_alloc::<Self>() as _
     }
 
+    /// Called when the object returned by `alloc` is initialized.
+    ///
+    /// # Safety
+    /// `self_` must be a valid pointer to the Python heap.
+    unsafe fn initialized(_py: Python, _self_: *mut Self::ConcreteLayout) {}
+
     /// Deallocate `#[pyclass]` on the Python heap.
     ///
     /// # Safety
     /// `self_` must be a valid pointer to the Python heap.
     unsafe fn dealloc(py: Python, self_: *mut Self::ConcreteLayout) {
-        (*self_).py_drop(py);
         let obj = self_ as _;
+        // The finalizer sees the object intact, and may resurrect it
         if ffi::PyObject_CallFinalizerFromDealloc(obj) < 0 {
             return;
         }
+        // The GC must not traverse the object while its contents are dropped
+        if ffi::PyType_IS_GC(ffi::Py_TYPE(obj)) != 0 {
+            ffi::PyObject_GC_UnTrack(obj as *mut c_void);
+        }
+        (*self_).py_drop(py);
 
         match Self::type_object().tp_free {
             Some(free) => free(obj as *mut c_void),
@@ -76,10 +91,55 @@ pub unsafe fn tp_free_fallback(obj: *mut ffi::PyObject) {
 /// The `#[pyclass]` attribute automatically implements this trait for your Rust struct,
 /// so you don't have to use this trait directly.
 pub trait PyClass:
-    PyTypeInfo<ConcreteLayout = PyClassShell<Self>> + Sized + PyClassAlloc + PyMethodsProtocol
+    PyTypeInfo<ConcreteLayout = PyClassShell<Self>, AsRefTarget = PyClassShell<Self>>
+    + Sized
+    + PyClassAlloc
+    + PyMethodsProtocol
 {
     type Dict: PyClassDict;
     type WeakRef: PyClassWeakRef;
+
+    /// `tp_richcompare` derived from `PartialEq` or `PartialOrd` by `#[pyclass(eq)]` and
+    /// `#[pyclass(ord)]`.
+    #[doc(hidden)]
+    fn derived_richcmp() -> Option<ffi::richcmpfunc> {
+        None
+    }
+
+    /// `tp_hash` derived from `Hash` by `#[pyclass(hash)]`.
+    #[doc(hidden)]
+    fn derived_hash() -> Option<ffi::hashfunc> {
+        None
+    }
+
+    /// `tp_repr` derived from `Debug` by `#[pyclass(debug)]`.
+    #[doc(hidden)]
+    fn derived_repr() -> Option<ffi::reprfunc> {
+        None
+    }
+
+    /// `tp_str` derived from `Display` by `#[pyclass(display)]`.
+    #[doc(hidden)]
+    fn derived_str() -> Option<ffi::reprfunc> {
+        None
+    }
+
+    /// `nb_int` and `nb_index` of a `#[pyclass]` enum, returning the value of the variant.
+    #[doc(hidden)]
+    fn derived_index() -> Option<ffi::unaryfunc> {
+        None
+    }
+
+    /// `tp_finalize` of a `#[pyclass(resource)]`, closing an object which wasn't closed.
+    #[doc(hidden)]
+    fn derived_finalize() -> Option<ffi::destructor> {
+        None
+    }
+
+    /// Changes the type object after pyo3 has filled it, right before `PyType_Ready`.
+    /// Set by `#[pyclass(customize = function)]`.
+    #[doc(hidden)]
+    fn customize_type_object(_type_object: &mut ffi::PyTypeObject) {}
 }
 
 /// `PyClassShell` represents the concrete layout of `T: PyClass` when it is converted
@@ -105,10 +165,16 @@ pub trait PyClass:
 /// let book_shell = PyClassShell::new_ref(py, book).unwrap();
 /// py_run!(py, book_shell, "assert book_shell.name[-6:] == 'Castle'");
 /// ```
+///
+/// Like a `RefCell`, the shell tracks how its contents are borrowed. Methods generated by
+/// `#[pymethods]` borrow them with [`try_borrow`](#method.try_borrow) for `&self` and
+/// [`try_borrow_mut`](#method.try_borrow_mut) for `&mut self`, so calling a `&mut self` method
+/// while another method of the same object runs raises a `RuntimeError`.
 #[repr(C)]
 pub struct PyClassShell<T: PyClass> {
     ob_base: <T::BaseType as PyTypeInfo>::ConcreteLayout,
-    pyclass: ManuallyDrop<T>,
+    borrow_flag: Cell<BorrowFlag>,
+    pyclass: ManuallyDrop<UnsafeCell<T>>,
     dict: T::Dict,
     weakref: T::WeakRef,
 }
@@ -127,27 +193,47 @@ impl<T: PyClass> PyClassShell<T> {
         }
     }
 
-    /// Make new `PyClassShell` on the Python heap and returns the mutable reference of it.
-    pub fn new_mut(py: Python, value: impl Into<PyClassInitializer<T>>) -> PyResult<&mut Self>
-    where
-        <T::BaseType as PyTypeInfo>::ConcreteLayout:
-            crate::type_object::PyObjectSizedLayout<T::BaseType>,
-    {
-        unsafe {
-            let initializer = value.into();
-            let self_ = initializer.create_shell(py)?;
-            FromPyPointer::from_owned_ptr_or_err(py, self_ as _)
-        }
-    }
-
     /// Get the reference of base object.
     pub fn get_super(&self) -> &<T::BaseType as PyTypeInfo>::ConcreteLayout {
         &self.ob_base
     }
 
-    /// Get the mutable reference of base object.
-    pub fn get_super_mut(&mut self) -> &mut <T::BaseType as PyTypeInfo>::ConcreteLayout {
-        &mut self.ob_base
+    /// Immutably borrows the contents, failing if they are mutably borrowed.
+    pub fn try_borrow(&self) -> Result<PyRef<T>, PyBorrowError> {
+        // Fails while the contents are mutably borrowed, as `WRITING` can't be incremented
+        match self.borrow_flag.get().increment() {
+            Some(flag) => {
+                self.borrow_flag.set(flag);
+                Ok(PyRef { shell: self })
+            }
+            None => Err(PyBorrowError { _private: () }),
+        }
+    }
+
+    /// Mutably borrows the contents, failing if they are borrowed at all.
+    pub fn try_borrow_mut(&self) -> Result<PyRefMut<T>, PyBorrowMutError> {
+        if self.borrow_flag.get() != BorrowFlag::UNUSED {
+            Err(PyBorrowMutError { _private: () })
+        } else {
+            self.borrow_flag.set(BorrowFlag::WRITING);
+            Ok(PyRefMut { shell: self })
+        }
+    }
+
+    /// Immutably borrows the contents.
+    ///
+    /// # Panics
+    /// Panics if the contents are mutably borrowed.
+    pub fn borrow(&self) -> PyRef<T> {
+        self.try_borrow().expect("Already mutably borrowed")
+    }
+
+    /// Mutably borrows the contents.
+    ///
+    /// # Panics
+    /// Panics if the contents are borrowed.
+    pub fn borrow_mut(&self) -> PyRefMut<T> {
+        self.try_borrow_mut().expect("Already borrowed")
     }
 
     pub(crate) unsafe fn new(py: Python) -> PyResult<*mut Self>
@@ -160,6 +246,7 @@ impl<T: PyClass> PyClassShell<T> {
             return Err(PyErr::fetch(py));
         }
         let self_ = base as *mut Self;
+        (*self_).borrow_flag = Cell::new(BorrowFlag::UNUSED);
         (*self_).dict = T::Dict::new();
         (*self_).weakref = T::WeakRef::new();
         Ok(self_)
@@ -171,22 +258,18 @@ impl<T: PyClass> PyObjectLayout<T> for PyClassShell<T> {
     fn get_super_or(&mut self) -> Option<&mut <T::BaseType as PyTypeInfo>::ConcreteLayout> {
         Some(&mut self.ob_base)
     }
-    unsafe fn internal_ref_cast(obj: &PyAny) -> &T {
-        let shell = obj.as_ptr() as *const Self;
-        &(*shell).pyclass
-    }
-    unsafe fn internal_mut_cast(obj: &PyAny) -> &mut T {
-        let shell = obj.as_ptr() as *const _ as *mut Self;
-        &mut (*shell).pyclass
+    unsafe fn internal_ref_cast(obj: &PyAny) -> &Self {
+        &*(obj.as_ptr() as *const Self)
     }
     unsafe fn py_drop(&mut self, py: Python) {
+        // Weak references must be cleared before the object is torn down
+        self.weakref.clear_weakrefs(self.as_ptr(), py);
         ManuallyDrop::drop(&mut self.pyclass);
         self.dict.clear_dict(py);
-        self.weakref.clear_weakrefs(self.as_ptr(), py);
         self.ob_base.py_drop(py);
     }
     unsafe fn py_init(&mut self, value: T) {
-        self.pyclass = ManuallyDrop::new(value);
+        self.pyclass = ManuallyDrop::new(UnsafeCell::new(value));
     }
 }
 
@@ -198,57 +281,184 @@ impl<T: PyClass> AsPyPointer for PyClassShell<T> {
     }
 }
 
-impl<T: PyClass> std::ops::Deref for PyClassShell<T> {
+impl<T: PyClass> ToPyObject for &PyClassShell<T> {
+    fn to_object(&self, py: Python<'_>) -> PyObject {
+        unsafe { PyObject::from_borrowed_ptr(py, self.as_ptr()) }
+    }
+}
+
+/// The number of shared borrows of a `PyClassShell`, or `WRITING` while it is mutably borrowed
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct BorrowFlag(usize);
+
+impl BorrowFlag {
+    const UNUSED: BorrowFlag = BorrowFlag(0);
+    const WRITING: BorrowFlag = BorrowFlag(usize::max_value());
+
+    /// Adds a shared borrow. Returns `None` while mutably borrowed, and if there are too many
+    /// shared borrows to count.
+    fn increment(self) -> Option<Self> {
+        match self.0.checked_add(1) {
+            Some(n) if n != Self::WRITING.0 => Some(BorrowFlag(n)),
+            _ => None,
+        }
+    }
+
+    fn decrement(self) -> Self {
+        BorrowFlag(self.0 - 1)
+    }
+}
+
+/// A shared borrow of the contents of a `PyClassShell`, created by
+/// [`PyClassShell::try_borrow`](struct.PyClassShell.html#method.try_borrow).
+pub struct PyRef<'a, T: PyClass> {
+    shell: &'a PyClassShell<T>,
+}
+
+impl<'a, T: PyClass> PyRef<'a, T> {
+    /// Returns the shell, e.g. to convert the object back to Python.
+    pub fn shell(this: &Self) -> &'a PyClassShell<T> {
+        this.shell
+    }
+}
+
+impl<T: PyClass> std::ops::Deref for PyRef<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.shell.pyclass.get() }
+    }
+}
+
+impl<T: PyClass> Drop for PyRef<'_, T> {
+    fn drop(&mut self) {
+        let flag = self.shell.borrow_flag.get();
+        self.shell.borrow_flag.set(flag.decrement());
+    }
+}
+
+impl<'a, T: PyClass> FromPyObject<'a> for PyRef<'a, T> {
+    fn extract(obj: &'a PyAny) -> PyResult<Self> {
+        Ok(<PyClassShell<T> as PyTryFrom>::try_from(obj)?.try_borrow()?)
+    }
+}
+
+impl<T: PyClass + fmt::Debug> fmt::Debug for PyRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A mutable borrow of the contents of a `PyClassShell`, created by
+/// [`PyClassShell::try_borrow_mut`](struct.PyClassShell.html#method.try_borrow_mut).
+pub struct PyRefMut<'a, T: PyClass> {
+    shell: &'a PyClassShell<T>,
+}
+
+impl<'a, T: PyClass> PyRefMut<'a, T> {
+    /// Returns the shell, e.g. to convert the object back to Python.
+    pub fn shell(this: &Self) -> &'a PyClassShell<T> {
+        this.shell
+    }
+}
+
+impl<T: PyClass> std::ops::Deref for PyRefMut<'_, T> {
     type Target = T;
     fn deref(&self) -> &T {
-        self.pyclass.deref()
+        unsafe { &*self.shell.pyclass.get() }
     }
 }
 
-impl<T: PyClass> std::ops::DerefMut for PyClassShell<T> {
+impl<T: PyClass> std::ops::DerefMut for PyRefMut<'_, T> {
     fn deref_mut(&mut self) -> &mut T {
-        self.pyclass.deref_mut()
+        // The borrow flag guarantees that this is the only reference to the contents
+        unsafe { &mut *self.shell.pyclass.get() }
     }
 }
 
-impl<T: PyClass> ToPyObject for &PyClassShell<T> {
-    fn to_object(&self, py: Python<'_>) -> PyObject {
-        unsafe { PyObject::from_borrowed_ptr(py, self.as_ptr()) }
+impl<T: PyClass> Drop for PyRefMut<'_, T> {
+    fn drop(&mut self) {
+        self.shell.borrow_flag.set(BorrowFlag::UNUSED);
     }
 }
 
-impl<T: PyClass> ToPyObject for &mut PyClassShell<T> {
-    fn to_object(&self, py: Python<'_>) -> PyObject {
-        unsafe { PyObject::from_borrowed_ptr(py, self.as_ptr()) }
+impl<'a, T: PyClass> FromPyObject<'a> for PyRefMut<'a, T> {
+    fn extract(obj: &'a PyAny) -> PyResult<Self> {
+        Ok(<PyClassShell<T> as PyTryFrom>::try_from(obj)?.try_borrow_mut()?)
     }
 }
 
-unsafe impl<'p, T> FromPyPointer<'p> for &'p PyClassShell<T>
-where
-    T: PyClass,
-{
-    unsafe fn from_owned_ptr_or_opt(py: Python<'p>, ptr: *mut ffi::PyObject) -> Option<Self> {
-        NonNull::new(ptr).map(|p| &*(gil::register_owned(py, p).as_ptr() as *const PyClassShell<T>))
+impl<T: PyClass + fmt::Debug> fmt::Debug for PyRefMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
     }
-    unsafe fn from_borrowed_ptr_or_opt(py: Python<'p>, ptr: *mut ffi::PyObject) -> Option<Self> {
-        NonNull::new(ptr)
-            .map(|p| &*(gil::register_borrowed(py, p).as_ptr() as *const PyClassShell<T>))
+}
+
+impl<T: PyClass> FromPyObjectImpl for &'_ PyClassShell<T> {
+    type Impl = extract_impl::Reference;
+}
+
+/// The error returned by `PyClassShell::try_borrow` when the contents are mutably borrowed.
+/// It is converted to a `RuntimeError`.
+pub struct PyBorrowError {
+    _private: (),
+}
+
+impl fmt::Debug for PyBorrowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PyBorrowError").finish()
+    }
+}
+
+impl fmt::Display for PyBorrowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt("Already mutably borrowed", f)
+    }
+}
+
+impl std::error::Error for PyBorrowError {}
+
+impl From<PyBorrowError> for PyErr {
+    fn from(other: PyBorrowError) -> Self {
+        PyErr::new::<exceptions::RuntimeError, _>(other.to_string())
+    }
+}
+
+/// The error returned by `PyClassShell::try_borrow_mut` when the contents are borrowed.
+/// It is converted to a `RuntimeError`.
+pub struct PyBorrowMutError {
+    _private: (),
+}
+
+impl fmt::Debug for PyBorrowMutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PyBorrowMutError").finish()
+    }
+}
+
+impl fmt::Display for PyBorrowMutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt("Already borrowed", f)
+    }
+}
+
+impl std::error::Error for PyBorrowMutError {}
+
+impl From<PyBorrowMutError> for PyErr {
+    fn from(other: PyBorrowMutError) -> Self {
+        PyErr::new::<exceptions::RuntimeError, _>(other.to_string())
     }
 }
 
-unsafe impl<'p, T> FromPyPointer<'p> for &'p mut PyClassShell<T>
+unsafe impl<'p, T> FromPyPointer<'p> for &'p PyClassShell<T>
 where
     T: PyClass,
 {
     unsafe fn from_owned_ptr_or_opt(py: Python<'p>, ptr: *mut ffi::PyObject) -> Option<Self> {
-        NonNull::new(ptr).map(|p| {
-            &mut *(gil::register_owned(py, p).as_ptr() as *const _ as *mut PyClassShell<T>)
-        })
+        NonNull::new(ptr).map(|p| &*(gil::register_owned(py, p).as_ptr() as *const PyClassShell<T>))
     }
     unsafe fn from_borrowed_ptr_or_opt(py: Python<'p>, ptr: *mut ffi::PyObject) -> Option<Self> {
-        NonNull::new(ptr).map(|p| {
-            &mut *(gil::register_borrowed(py, p).as_ptr() as *const _ as *mut PyClassShell<T>)
-        })
+        NonNull::new(ptr)
+            .map(|p| &*(gil::register_borrowed(py, p).as_ptr() as *const PyClassShell<T>))
     }
 }
 
@@ -292,50 +502,76 @@ where
     // type size
     type_object.tp_basicsize = std::mem::size_of::<T::ConcreteLayout>() as ffi::Py_ssize_t;
 
+    // The fields are at the end of `PyClassShell`, in the order `dict`, `weakref`
     let mut offset = type_object.tp_basicsize;
 
-    // __dict__ support
-    if let Some(dict_offset) = T::Dict::OFFSET {
-        offset += dict_offset as ffi::Py_ssize_t;
-        type_object.tp_dictoffset = offset;
-    }
-
     // weakref support
     if let Some(weakref_offset) = T::WeakRef::OFFSET {
         offset += weakref_offset as ffi::Py_ssize_t;
         type_object.tp_weaklistoffset = offset;
     }
 
+    // __dict__ support
+    if let Some(dict_offset) = T::Dict::OFFSET {
+        offset += dict_offset as ffi::Py_ssize_t;
+        type_object.tp_dictoffset = offset;
+    }
+
     // GC support
+    #[cfg(not(feature = "stable"))]
     <T as class::gc::PyGCProtocolImpl>::update_type_object(type_object);
+    // The __dict__ can be part of reference cycles, so it must be visible to the GC
+    if T::Dict::OFFSET.is_some() && type_object.tp_traverse.is_none() {
+        type_object.tp_traverse = Some(class::gc::tp_traverse_dict::<T>);
+        type_object.tp_clear = Some(class::gc::tp_clear_dict::<T>);
+    }
 
     // descriptor protocol
+    #[cfg(not(feature = "stable"))]
     <T as class::descr::PyDescrProtocolImpl>::tp_as_descr(type_object);
 
     // iterator methods
+    #[cfg(not(feature = "stable"))]
     <T as class::iter::PyIterProtocolImpl>::tp_as_iter(type_object);
 
     // basic methods
+    #[cfg(not(feature = "stable"))]
     <T as class::basic::PyObjectProtocolImpl>::tp_as_object(type_object);
-
-    fn to_ptr<T>(value: Option<T>) -> *mut T {
-        value
-            .map(|v| Box::into_raw(Box::new(v)))
-            .unwrap_or_else(ptr::null_mut)
+    py_class_slot_methods::<T>(type_object);
+    // An explicit __richcmp__ takes precedence over the derived comparisons
+    let derives_richcmp = type_object.tp_richcompare.is_none() && T::derived_richcmp().is_some();
+    if derives_richcmp {
+        type_object.tp_richcompare = T::derived_richcmp();
+    }
+    if type_object.tp_hash.is_none() {
+        type_object.tp_hash = T::derived_hash();
+    }
+    if type_object.tp_repr.is_none() {
+        type_object.tp_repr = T::derived_repr();
+    }
+    if type_object.tp_str.is_none() {
+        type_object.tp_str = T::derived_str();
+    }
+    if type_object.tp_finalize.is_none() {
+        type_object.tp_finalize = T::derived_finalize();
+    }
+    // Like a Python class defining __eq__ without __hash__, a class deriving comparisons is
+    // unhashable. Classes with `__richcmp__` are left to `PyType_Ready`, which doesn't inherit
+    // `tp_hash` for them either.
+    if derives_richcmp && type_object.tp_hash.is_none() {
+        type_object.tp_hash = Some(ffi::PyObject_HashNotImplemented);
     }
 
-    // number methods
-    type_object.tp_as_number = to_ptr(<T as class::number::PyNumberProtocolImpl>::tp_as_number());
-    // mapping methods
-    type_object.tp_as_mapping =
-        to_ptr(<T as class::mapping::PyMappingProtocolImpl>::tp_as_mapping());
-    // sequence methods
-    type_object.tp_as_sequence =
-        to_ptr(<T as class::sequence::PySequenceProtocolImpl>::tp_as_sequence());
-    // async methods
-    type_object.tp_as_async = to_ptr(<T as class::pyasync::PyAsyncProtocolImpl>::tp_as_async());
-    // buffer protocol
-    type_object.tp_as_buffer = to_ptr(<T as class::buffer::PyBufferProtocolImpl>::tp_as_buffer());
+    #[cfg(not(feature = "stable"))]
+    py_class_protocol_tables::<T>(type_object);
+    if let Some(index) = T::derived_index() {
+        if type_object.tp_as_number.is_null() {
+            type_object.tp_as_number = Box::into_raw(Box::new(ffi::PyNumberMethods_INIT));
+        }
+        let number_methods = unsafe { &mut *type_object.tp_as_number };
+        number_methods.nb_int = number_methods.nb_int.or(Some(index));
+        number_methods.nb_index = number_methods.nb_index.or(Some(index));
+    }
 
     // normal methods
     let (new, call, mut methods) = py_class_method_defs::<T>();
@@ -363,14 +599,18 @@ where
     // set type flags
     py_class_flags::<T>(type_object);
 
+    // slots and flags set by hand take precedence over the generated ones
+    T::customize_type_object(type_object);
+
     // register type object
     unsafe {
-        if ffi::PyType_Ready(type_object) == 0 {
-            Ok(())
-        } else {
-            PyErr::fetch(py).into()
+        if ffi::PyType_Ready(type_object) != 0 {
+            return PyErr::fetch(py).into();
         }
     }
+
+    // class attributes go into `tp_dict`, which is created by `PyType_Ready`
+    py_class_attributes::<T>(py, type_object)
 }
 
 fn py_class_flags<T: PyTypeInfo>(type_object: &mut ffi::PyTypeObject) {
@@ -385,6 +625,47 @@ fn py_class_flags<T: PyTypeInfo>(type_object: &mut ffi::PyTypeObject) {
     if T::FLAGS & type_flags::BASETYPE != 0 {
         type_object.tp_flags |= ffi::Py_TPFLAGS_BASETYPE;
     }
+    if type_object.tp_finalize.is_some() {
+        type_object.tp_flags |= ffi::Py_TPFLAGS_HAVE_FINALIZE;
+    }
+}
+
+/// Sets the slots of `__repr__` and `__str__` defined in `#[pymethods]`
+fn py_class_slot_methods<T: PyMethodsProtocol>(type_object: &mut ffi::PyTypeObject) {
+    for def in T::py_methods() {
+        match *def {
+            PyMethodDefType::Repr(meth) => type_object.tp_repr = Some(meth),
+            PyMethodDefType::Str(meth) => type_object.tp_str = Some(meth),
+            _ => (),
+        }
+    }
+}
+
+/// Sets the `#[classattr]`s defined in `#[pymethods]`. Their values may be instances of the
+/// class itself, which can be created at this point.
+fn py_class_attributes<T: PyMethodsProtocol>(
+    py: Python,
+    type_object: &mut ffi::PyTypeObject,
+) -> PyResult<()> {
+    let mut modified = false;
+    for def in T::py_methods() {
+        if let PyMethodDefType::ClassAttribute(ref attr) = *def {
+            let value = (attr.meth)(py)?;
+            let name = CString::new(attr.name).expect("Attribute name must not contain NULL byte");
+            let result = unsafe {
+                ffi::PyDict_SetItemString(type_object.tp_dict, name.as_ptr(), value.as_ptr())
+            };
+            if result == -1 {
+                return Err(PyErr::fetch(py));
+            }
+            modified = true;
+        }
+    }
+    if modified {
+        // Clear the attribute cache of the type
+        unsafe { ffi::PyType_Modified(type_object) };
+    }
+    Ok(())
 }
 
 fn py_class_method_defs<T: PyMethodsProtocol>() -> (
@@ -419,6 +700,38 @@ fn py_class_method_defs<T: PyMethodsProtocol>() -> (
         }
     }
 
+    #[cfg(not(feature = "stable"))]
+    py_class_protocol_methods::<T>(&mut defs);
+
+    (new, call, defs)
+}
+
+/// Sets the method tables of the protocols implemented with `#[pyproto]`
+#[cfg(not(feature = "stable"))]
+fn py_class_protocol_tables<T>(type_object: &mut ffi::PyTypeObject) {
+    fn to_ptr<T>(value: Option<T>) -> *mut T {
+        value
+            .map(|v| Box::into_raw(Box::new(v)))
+            .unwrap_or_else(ptr::null_mut)
+    }
+
+    // number methods
+    type_object.tp_as_number = to_ptr(<T as class::number::PyNumberProtocolImpl>::tp_as_number());
+    // mapping methods
+    type_object.tp_as_mapping =
+        to_ptr(<T as class::mapping::PyMappingProtocolImpl>::tp_as_mapping());
+    // sequence methods
+    type_object.tp_as_sequence =
+        to_ptr(<T as class::sequence::PySequenceProtocolImpl>::tp_as_sequence());
+    // async methods
+    type_object.tp_as_async = to_ptr(<T as class::pyasync::PyAsyncProtocolImpl>::tp_as_async());
+    // buffer protocol
+    type_object.tp_as_buffer = to_ptr(<T as class::buffer::PyBufferProtocolImpl>::tp_as_buffer());
+}
+
+/// Adds the methods of the protocols implemented with `#[pyproto]` which have no slot
+#[cfg(not(feature = "stable"))]
+fn py_class_protocol_methods<T>(defs: &mut Vec<ffi::PyMethodDef>) {
     for def in <T as class::basic::PyObjectProtocolImpl>::methods() {
         defs.push(def.as_method_def());
     }
@@ -434,13 +747,6 @@ fn py_class_method_defs<T: PyMethodsProtocol>() -> (
     for def in <T as class::descr::PyDescrProtocolImpl>::methods() {
         defs.push(def.as_method_def());
     }
-
-    py_class_async_methods::<T>(&mut defs);
-
-    (new, call, defs)
-}
-
-fn py_class_async_methods<T>(defs: &mut Vec<ffi::PyMethodDef>) {
     for def in <T as class::pyasync::PyAsyncProtocolImpl>::methods() {
         defs.push(def.as_method_def());
     }
@@ -471,5 +777,14 @@ fn py_class_properties<T: PyMethodsProtocol>() -> Vec<ffi::PyGetSetDef> {
         }
     }
 
+    // Deleters wrap the setters, so they're added once all setters are known
+    for def in T::py_methods() {
+        if let PyMethodDefType::Deleter(ref deleter) = *def {
+            let name = deleter.name.to_string();
+            let def = defs.entry(name).or_insert(ffi::PyGetSetDef_INIT);
+            deleter.copy_to(def);
+        }
+    }
+
     defs.values().cloned().collect()
 }
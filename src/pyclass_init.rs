@@ -122,6 +122,7 @@ impl<T: PyClass> PyClassInitializer<T> {
     {
         let shell = PyClassShell::new(py)?;
         self.init_class(&mut *shell);
+        T::initialized(py, shell);
         Ok(shell)
     }
 }
@@ -51,7 +51,8 @@ impl PyClassDict for PyClassDictSlot {
     }
     unsafe fn clear_dict(&mut self, _py: Python) {
         if !self.0.is_null() {
-            ffi::PyDict_Clear(self.0)
+            let dict = std::mem::replace(&mut self.0, std::ptr::null_mut());
+            ffi::Py_DECREF(dict)
         }
     }
 }
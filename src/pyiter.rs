@@ -0,0 +1,152 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Lazy Python iterators over the items of Rust iterators.
+//!
+//! `#[pyfunction]`s and methods returning `impl Iterator<Item = T>` or
+//! `PyResult<impl Iterator<Item = T>>` return a `PyIter` to Python, which converts the items one
+//! at a time when Python asks for them instead of collecting them into a list upfront:
+//!
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::{py_run, wrap_pyfunction};
+//!
+//! #[pyfunction]
+//! fn squares(n: u64) -> impl Iterator<Item = u64> {
+//!     (0..n).map(|i| i * i)
+//! }
+//!
+//! # fn main() {
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let squares = wrap_pyfunction!(squares)(py);
+//! py_run!(py, squares, r#"
+//!     it = squares(10 ** 18)
+//!     assert next(it) == 0
+//!     assert next(it) == 1
+//!     assert [x for x, _ in zip(it, range(3))] == [4, 9, 16]
+//! "#);
+//! # }
+//! ```
+//!
+//! The Rust iterator must be `Send` and `'static`, so it can't borrow the arguments of the
+//! function. Clone or extract what it needs instead.
+
+use crate::callback::restore_err;
+use crate::class::methods::{PyMethodDefType, PyMethodsProtocol};
+use crate::pyclass::{PyClass, PyClassAlloc, PyClassShell};
+use crate::pyclass_slots::PyClassDummySlot;
+use crate::type_object::{LazyStaticType, PyTypeInfo};
+use crate::types::PyAny;
+use crate::{ffi, FromPyPointer, GILPool, IntoPy, IntoPyPointer, Py, PyObject, Python};
+use std::fmt;
+use std::ptr;
+
+/// A Python iterator yielding the items of a Rust iterator, converted with `IntoPy`.
+pub struct PyIter {
+    next: Box<dyn FnMut(Python) -> Option<PyObject> + Send>,
+}
+
+impl PyIter {
+    /// Wraps `iter`, whose items are converted to Python objects when they are requested.
+    pub fn new<I>(iter: I) -> Self
+    where
+        I: Iterator + Send + 'static,
+        I::Item: IntoPy<PyObject>,
+    {
+        let mut iter = iter;
+        PyIter {
+            next: Box::new(move |py| iter.next().map(|item| item.into_py(py))),
+        }
+    }
+}
+
+impl fmt::Debug for PyIter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PyIter").finish()
+    }
+}
+
+unsafe extern "C" fn tp_iternext(slf: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = GILPool::new(py);
+    let shell: &PyClassShell<PyIter> = FromPyPointer::from_borrowed_ptr(py, slf);
+    // The iterator may call back into Python code, which could advance the same iterator
+    let mut iter = match shell.try_borrow_mut() {
+        Ok(iter) => iter,
+        Err(e) => {
+            restore_err(py, e);
+            return ptr::null_mut();
+        }
+    };
+    // Returning null without an exception signals the end of the iteration
+    (iter.next)(py).map_or(ptr::null_mut(), IntoPyPointer::into_ptr)
+}
+
+unsafe impl PyTypeInfo for PyIter {
+    type Type = PyIter;
+    type BaseType = PyAny;
+    type ConcreteLayout = PyClassShell<Self>;
+    type Initializer = crate::pyclass_init::PyClassInitializer<Self>;
+    type AsRefTarget = crate::pyclass::PyClassShell<Self>;
+
+    const NAME: &'static str = "RustIterator";
+    const MODULE: Option<&'static str> = None;
+    const DESCRIPTION: &'static str = "An iterator over the items of a Rust iterator\0";
+    const FLAGS: usize = 0;
+
+    #[inline]
+    fn type_object() -> &'static ffi::PyTypeObject {
+        static TYPE_OBJECT: LazyStaticType = LazyStaticType::new();
+        TYPE_OBJECT.get_or_init::<Self>()
+    }
+}
+
+impl PyClass for PyIter {
+    type Dict = PyClassDummySlot;
+    type WeakRef = PyClassDummySlot;
+
+    fn customize_type_object(type_object: &mut ffi::PyTypeObject) {
+        type_object.tp_iter = Some(ffi::PyObject_SelfIter);
+        type_object.tp_iternext = Some(tp_iternext);
+    }
+}
+
+impl PyClassAlloc for PyIter {}
+
+impl PyMethodsProtocol for PyIter {
+    fn py_methods() -> Vec<&'static PyMethodDefType> {
+        Vec::new()
+    }
+}
+
+impl IntoPy<PyObject> for PyIter {
+    fn into_py(self, py: Python) -> PyObject {
+        Py::new(py, self).unwrap().into_py(py)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyIter;
+    use crate::types::IntoPyDict;
+    use crate::{IntoPy, PyObject, Python};
+
+    #[test]
+    fn test_iterate() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let it: PyObject = PyIter::new(vec!["a", "b"].into_iter()).into_py(py);
+        let locals = [("it", it)].into_py_dict(py);
+        py.run("assert iter(it) is it", None, Some(locals)).unwrap();
+        py.run("assert list(it) == ['a', 'b']", None, Some(locals))
+            .unwrap();
+        py.run("assert next(it, None) is None", None, Some(locals))
+            .unwrap();
+        py.run(
+            "assert type(it).__name__ == 'RustIterator'",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+    }
+}
@@ -3,17 +3,22 @@
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
 use crate::err::{PyDowncastError, PyErr, PyResult};
+use crate::exceptions;
 use crate::ffi;
 use crate::gil::{self, GILGuard};
 use crate::instance::AsPyRef;
+use crate::interpreter::{self, InterpreterFlags, PlatformInfo, PythonVersionInfo};
 use crate::object::PyObject;
-use crate::type_object::{PyObjectLayout, PyTypeInfo, PyTypeObject};
-use crate::types::{PyAny, PyDict, PyModule, PyType};
+use crate::objectprotocol::ObjectProtocol;
+use crate::scope::Scope;
+use crate::type_object::PyTypeObject;
+use crate::types::{PyAny, PyDict, PyModule, PyTuple, PyType};
 use crate::AsPyPointer;
 use crate::{FromPyPointer, IntoPyPointer, PyTryFrom};
 use std::ffi::CString;
 use std::marker::PhantomData;
-use std::os::raw::c_int;
+use std::os::raw::{c_char, c_int};
+use std::panic;
 use std::ptr::NonNull;
 
 pub use gil::prepare_freethreaded_python;
@@ -50,6 +55,10 @@ pub use gil::prepare_freethreaded_python;
 #[derive(Copy, Clone)]
 pub struct Python<'p>(PhantomData<&'p GILGuard>);
 
+/// The name of the dict in `sys` caching the objects found by `Python::lookup`, by their
+/// dotted path. It's stored there, so each interpreter has its own cache.
+const LOOKUP_CACHE: &str = "_pyo3_lookup_cache\0";
+
 impl<'p> Python<'p> {
     /// Retrieve Python instance under the assumption that the GIL is already acquired at this point,
     /// and stays acquired for the lifetime `'p`.
@@ -59,6 +68,7 @@ impl<'p> Python<'p> {
     /// when calling this function.
     #[inline]
     pub unsafe fn assume_gil_acquired() -> Python<'p> {
+        gil::check_gil_held();
         Python(PhantomData)
     }
 
@@ -128,11 +138,60 @@ impl<'p> Python<'p> {
         unsafe {
             let save = ffi::PyEval_SaveThread();
             let result = f();
-            ffi::PyEval_RestoreThread(save);
+            gil::record_acquisition(|| {
+                ffi::PyEval_RestoreThread(save);
+                ((), true)
+            });
             result
         }
     }
 
+    /// Temporarily releases the `GIL` to run `f` with a [Scope](scope/struct.Scope.html)
+    /// for spawning threads.
+    ///
+    /// Unlike threads spawned with `std::thread::spawn`, the threads may borrow data
+    /// from the caller, because all of them are joined before this function reacquires the GIL
+    /// and returns. This makes it safe to use them from a `#[pyfunction]`: no thread is still
+    /// running when control goes back to Python.
+    ///
+    /// Returns a `RuntimeError` if a thread that was not joined explicitly panicked.
+    /// A panic in `f` itself is resumed after the GIL has been reacquired.
+    ///
+    /// # Example
+    /// ```
+    /// # use pyo3::prelude::*;
+    /// #[pyfunction]
+    /// fn parallel_count(py: Python<'_>, strings: Vec<String>, query: String) -> PyResult<usize> {
+    ///     let query = query.as_str();
+    ///     py.allow_threads_scoped(|scope| {
+    ///         let handles: Vec<_> = strings
+    ///             .iter()
+    ///             .map(|s| scope.spawn(move || s.matches(query).count()))
+    ///             .collect();
+    ///         handles.into_iter().map(|h| h.join().unwrap()).sum()
+    ///     })
+    /// }
+    /// ```
+    pub fn allow_threads_scoped<'env, F, R>(self, f: F) -> PyResult<R>
+    where
+        F: Send + FnOnce(&Scope<'env>) -> R,
+    {
+        let (result, thread_panicked) = self.allow_threads(|| {
+            let scope = Scope::new();
+            let result = panic::catch_unwind(panic::AssertUnwindSafe(|| f(&scope)));
+            let thread_panicked = scope.join_all();
+            (result, thread_panicked)
+        });
+
+        match result {
+            Ok(_) if thread_panicked => Err(exceptions::RuntimeError::py_err(
+                "A thread spawned in allow_threads_scoped panicked",
+            )),
+            Ok(value) => Ok(value),
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
     /// Evaluates a Python expression in the given context and returns the result.
     ///
     /// If `globals` is `None`, it defaults to Python module `__main__`.
@@ -242,11 +301,72 @@ impl<'p> Python<'p> {
         PyModule::import(self, name)
     }
 
+    /// Gets the object at a dotted path such as `"collections.abc.Mapping"`.
+    ///
+    /// Everything before the last dot is imported as a module, and the part after it is looked up
+    /// as an attribute of that module. A path without a dot imports a module.
+    ///
+    /// Found objects are cached for the lifetime of the interpreter, so later calls with the same
+    /// path don't go through the import machinery. Consequently, replacing the attribute or
+    /// reloading the module afterwards is not observed.
+    pub fn lookup(self, path: &str) -> PyResult<&'p PyAny> {
+        let cache = self.lookup_cache()?;
+        if let Some(obj) = cache.get_item(path) {
+            return Ok(obj);
+        }
+
+        let obj: &PyAny = match path.rfind('.') {
+            Some(dot) => self.import(&path[..dot])?.getattr(&path[dot + 1..])?,
+            None => self.import(path)?.as_ref(),
+        };
+        cache.set_item(path, obj)?;
+        Ok(obj)
+    }
+
+    /// Gets the cache of [Python::lookup](#method.lookup) of the current interpreter.
+    fn lookup_cache(self) -> PyResult<&'p PyDict> {
+        let name = LOOKUP_CACHE.as_ptr() as *const c_char;
+        unsafe {
+            if let Some(cache) = self.from_borrowed_ptr_or_opt::<PyAny>(ffi::PySys_GetObject(name))
+            {
+                return Ok(cache.downcast_ref()?);
+            }
+            let cache = PyDict::new(self);
+            crate::err::error_on_minusone(self, ffi::PySys_SetObject(name, cache.as_ptr()))?;
+            Ok(cache)
+        }
+    }
+
+    /// Gets the type `name` from the module `module`, e.g.
+    /// `py.import_type("collections.abc", "Mapping")`.
+    ///
+    /// Raises `TypeError` if the attribute is not a type. Like [Python::lookup](#method.lookup),
+    /// the result is cached.
+    pub fn import_type(self, module: &str, name: &str) -> PyResult<&'p PyType> {
+        let obj = self.lookup(&format!("{}.{}", module, name))?;
+        Ok(<PyType as PyTryFrom>::try_from(obj)?)
+    }
+
     /// Check whether `obj` is an instance of type `T` like Python `isinstance` function
+    ///
+    /// For types implementing `PyTypeInfo`, [PyAny::is_instance](types/struct.PyAny.html#method.is_instance)
+    /// is faster.
     pub fn is_instance<T: PyTypeObject, V: AsPyPointer>(self, obj: &V) -> PyResult<bool> {
         T::type_object().as_ref(self).is_instance(obj)
     }
 
+    /// Check whether `obj` is an instance of any of `types` like Python
+    /// `isinstance(obj, (type1, type2, ...))`
+    pub fn is_instance_of_any<V: AsPyPointer>(self, obj: &V, types: &[&PyType]) -> PyResult<bool> {
+        let types = PyTuple::new(self, types);
+        let result = unsafe { ffi::PyObject_IsInstance(obj.as_ptr(), types.as_ptr()) };
+        if result == -1 {
+            Err(PyErr::fetch(self))
+        } else {
+            Ok(result == 1)
+        }
+    }
+
     /// Check whether type `T` is subclass of type `U` like Python `issubclass` function
     pub fn is_subclass<T, U>(self) -> PyResult<bool>
     where
@@ -269,13 +389,31 @@ impl<'p> Python<'p> {
     pub fn NotImplemented(self) -> PyObject {
         unsafe { PyObject::from_borrowed_ptr(self, ffi::Py_NotImplemented()) }
     }
+
+    /// The version of the running interpreter, from `sys.version_info`.
+    ///
+    /// Like [Python::flags](#method.flags) and [Python::platform](#method.platform), it's read on
+    /// the first call and cached for the lifetime of the process.
+    pub fn version_info(self) -> PythonVersionInfo {
+        interpreter::get(self).version_info
+    }
+
+    /// The command line flags of the running interpreter, from `sys.flags`.
+    pub fn flags(self) -> &'static InterpreterFlags {
+        &interpreter::get(self).flags
+    }
+
+    /// The platform of the running interpreter, from `sys.platform` and `sys.implementation`.
+    pub fn platform(self) -> &'static PlatformInfo {
+        &interpreter::get(self).platform
+    }
 }
 
 impl<'p> Python<'p> {
     /// Register object in release pool, and try to downcast to specific type.
     pub fn checked_cast_as<T>(self, obj: PyObject) -> Result<&'p T, PyDowncastError>
     where
-        T: PyTypeInfo,
+        T: PyTryFrom<'p>,
     {
         let p = unsafe { gil::register_owned(self, obj.into_nonnull()) };
         <T as PyTryFrom>::try_from(p)
@@ -284,10 +422,10 @@ impl<'p> Python<'p> {
     /// Register object in release pool, and do unchecked downcast to specific type.
     pub unsafe fn cast_as<T>(self, obj: PyObject) -> &'p T
     where
-        T: PyTypeInfo,
+        T: PyTryFrom<'p>,
     {
         let p = gil::register_owned(self, obj.into_nonnull());
-        T::ConcreteLayout::internal_ref_cast(p)
+        T::try_from_unchecked(p)
     }
 
     /// Register `ffi::PyObject` pointer in release pool
@@ -304,16 +442,7 @@ impl<'p> Python<'p> {
     #[allow(clippy::wrong_self_convention)]
     pub unsafe fn from_owned_ptr<T>(self, ptr: *mut ffi::PyObject) -> &'p T
     where
-        T: PyTypeInfo,
-    {
-        FromPyPointer::from_owned_ptr(self, ptr)
-    }
-
-    /// Register `ffi::PyObject` pointer in release pool,
-    /// Do unchecked downcast to specific type. Returns mutable reference.
-    pub unsafe fn mut_from_owned_ptr<T>(self, ptr: *mut ffi::PyObject) -> &'p mut T
-    where
-        T: PyTypeInfo,
+        &'p T: FromPyPointer<'p>,
     {
         FromPyPointer::from_owned_ptr(self, ptr)
     }
@@ -324,7 +453,7 @@ impl<'p> Python<'p> {
     #[allow(clippy::wrong_self_convention)]
     pub unsafe fn from_owned_ptr_or_err<T>(self, ptr: *mut ffi::PyObject) -> PyResult<&'p T>
     where
-        T: PyTypeInfo,
+        &'p T: FromPyPointer<'p>,
     {
         FromPyPointer::from_owned_ptr_or_err(self, ptr)
     }
@@ -335,7 +464,7 @@ impl<'p> Python<'p> {
     #[allow(clippy::wrong_self_convention)]
     pub unsafe fn from_owned_ptr_or_opt<T>(self, ptr: *mut ffi::PyObject) -> Option<&'p T>
     where
-        T: PyTypeInfo,
+        &'p T: FromPyPointer<'p>,
     {
         FromPyPointer::from_owned_ptr_or_opt(self, ptr)
     }
@@ -346,17 +475,7 @@ impl<'p> Python<'p> {
     #[allow(clippy::wrong_self_convention)]
     pub unsafe fn from_borrowed_ptr<T>(self, ptr: *mut ffi::PyObject) -> &'p T
     where
-        T: PyTypeInfo,
-    {
-        FromPyPointer::from_borrowed_ptr(self, ptr)
-    }
-
-    /// Register borrowed `ffi::PyObject` pointer in release pool.
-    /// Panics if the pointer is `null`.
-    /// do unchecked downcast to specific type.
-    pub unsafe fn mut_from_borrowed_ptr<T>(self, ptr: *mut ffi::PyObject) -> &'p mut T
-    where
-        T: PyTypeInfo,
+        &'p T: FromPyPointer<'p>,
     {
         FromPyPointer::from_borrowed_ptr(self, ptr)
     }
@@ -367,7 +486,7 @@ impl<'p> Python<'p> {
     #[allow(clippy::wrong_self_convention)]
     pub unsafe fn from_borrowed_ptr_or_err<T>(self, ptr: *mut ffi::PyObject) -> PyResult<&'p T>
     where
-        T: PyTypeInfo,
+        &'p T: FromPyPointer<'p>,
     {
         FromPyPointer::from_borrowed_ptr_or_err(self, ptr)
     }
@@ -378,7 +497,7 @@ impl<'p> Python<'p> {
     #[allow(clippy::wrong_self_convention)]
     pub unsafe fn from_borrowed_ptr_or_opt<T>(self, ptr: *mut ffi::PyObject) -> Option<&'p T>
     where
-        T: PyTypeInfo,
+        &'p T: FromPyPointer<'p>,
     {
         FromPyPointer::from_borrowed_ptr_or_opt(self, ptr)
     }
@@ -413,9 +532,36 @@ impl<'p> Python<'p> {
 
 #[cfg(test)]
 mod test {
+    use crate::exceptions;
     use crate::objectprotocol::ObjectProtocol;
-    use crate::types::{IntoPyDict, PyAny, PyBool, PyInt, PyList};
-    use crate::Python;
+    use crate::types::{IntoPyDict, PyAny, PyBool, PyDict, PyInt, PyList};
+    use crate::{AsPyPointer, Python};
+
+    #[test]
+    fn test_lookup() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let mapping = py.import_type("collections.abc", "Mapping").unwrap();
+        assert!(mapping.is_instance(PyDict::new(py)).unwrap());
+        // The second lookup comes from the cache
+        let cached = py.import_type("collections.abc", "Mapping").unwrap();
+        assert_eq!(mapping.as_ptr(), cached.as_ptr());
+        // The cache belongs to the interpreter
+        let cache = py.import("sys").unwrap().get("_pyo3_lookup_cache").unwrap();
+        assert!(cache.get_item("collections.abc.Mapping").is_ok());
+
+        let sep: String = py.lookup("os.sep").unwrap().extract().unwrap();
+        assert!(sep == "/" || sep == "\\");
+        assert!(py.lookup("sys").is_ok());
+
+        let err = py.import_type("os", "sep").unwrap_err();
+        assert!(err.is_instance::<exceptions::TypeError>(py));
+        let err = py.lookup("collections.abc.DoesNotExist").unwrap_err();
+        assert!(err.is_instance::<exceptions::AttributeError>(py));
+        let err = py.lookup("does_not_exist.Foo").unwrap_err();
+        assert!(err.is_instance::<exceptions::ImportError>(py));
+    }
 
     #[test]
     fn test_eval() {
@@ -476,5 +622,54 @@ mod test {
         let py = gil.python();
         assert!(py.is_subclass::<PyBool, PyInt>().unwrap());
         assert!(!py.is_subclass::<PyBool, PyList>().unwrap());
+
+        let bool_type = py.get_type::<PyBool>();
+        assert!(bool_type.is_subclass_of(py.get_type::<PyInt>()).unwrap());
+        assert!(!bool_type.is_subclass_of(py.get_type::<PyList>()).unwrap());
+    }
+
+    #[test]
+    fn test_typed_is_instance() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list: &PyAny = PyList::new(py, &[1, 2]).as_ref();
+        assert!(list.is_instance::<PyList>());
+        assert!(!list.is_instance::<PyBool>());
+        assert!(list.is_instance_of_any::<(PyBool, PyList)>());
+        assert!(!list.is_instance_of_any::<(PyBool, PyInt)>());
+
+        let types = [py.get_type::<PyBool>(), py.get_type::<PyList>()];
+        assert!(py.is_instance_of_any(list, &types).unwrap());
+        assert!(!py.is_instance_of_any(list, &types[..1]).unwrap());
+    }
+
+    #[test]
+    fn test_allow_threads_scoped() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let numbers = vec![1, 2, 3, 4];
+        let mut doubled = vec![0; 4];
+        let sum = py
+            .allow_threads_scoped(|scope| {
+                // Not joined explicitly; the scope joins it before returning
+                scope.spawn(|| {
+                    for (d, n) in doubled.iter_mut().zip(&numbers) {
+                        *d = n * 2;
+                    }
+                });
+                let handle = scope.spawn(|| numbers.iter().sum::<i32>());
+                handle.join().unwrap()
+            })
+            .unwrap();
+        assert_eq!(sum, 10);
+        assert_eq!(doubled, vec![2, 4, 6, 8]);
+
+        let err = py
+            .allow_threads_scoped(|scope| {
+                scope.spawn(|| panic!("scoped thread panic"));
+            })
+            .unwrap_err();
+        assert!(err.is_instance::<crate::exceptions::RuntimeError>(py));
     }
 }
@@ -0,0 +1,341 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Redirecting Python's `sys.stdout` and `sys.stderr` into Rust writers, for applications
+//! embedding Python which show the output of the interpreter in their own logs or UI.
+//!
+//! [`Redirect::stdout`] and [`Redirect::stderr`] replace the stream with a text stream writing
+//! UTF-8 to any `std::io::Write`, and restore the previous stream when the `Redirect` is
+//! dropped:
+//!
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::redirect::Redirect;
+//! use std::io::Write;
+//! use std::sync::{Arc, Mutex};
+//!
+//! /// Collects the output in a buffer shared with the application.
+//! #[derive(Clone, Default)]
+//! struct Buffer(Arc<Mutex<Vec<u8>>>);
+//!
+//! impl Write for Buffer {
+//!     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+//!         self.0.lock().unwrap().write(buf)
+//!     }
+//!
+//!     fn flush(&mut self) -> std::io::Result<()> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let buffer = Buffer::default();
+//! {
+//!     let _redirect = Redirect::stdout(py, buffer.clone()).unwrap();
+//!     py.run("print('hello')", None, None).unwrap();
+//! }
+//! assert_eq!(&*buffer.0.lock().unwrap(), b"hello\n");
+//! ```
+//!
+//! The stream is writable and not a tty, it has no file descriptor, and `flush()` flushes the
+//! writer. The GIL is released while the writer writes. Python code keeping a reference to the
+//! stream can still write to it after the `Redirect` is dropped; the writer is dropped with the
+//! last reference.
+//!
+//! [`Redirect::stdout`]: struct.Redirect.html#method.stdout
+//! [`Redirect::stderr`]: struct.Redirect.html#method.stderr
+
+use crate::callback::{handle_callback, PyObjectCallbackConverter};
+use crate::class::methods::{PyMethodDef, PyMethodType};
+use crate::err::{PyErr, PyResult};
+use crate::gil::is_interpreter_alive;
+use crate::instance::AsPyRef;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyDict, PyString};
+use crate::{ffi, AsPyPointer, PyObject, Python};
+use parking_lot::Mutex;
+use std::io::Write;
+use std::os::raw::c_void;
+use std::panic;
+
+const STREAM_CLASS: &str = r#"
+import io
+
+class RustStream(io.TextIOBase):
+    def __init__(self, name, write, flush):
+        self._name = name
+        self._write = write
+        self._flush = flush
+
+    @property
+    def name(self):
+        return self._name
+
+    @property
+    def encoding(self):
+        return "utf-8"
+
+    @property
+    def errors(self):
+        return "strict"
+
+    def writable(self):
+        return True
+
+    def write(self, s):
+        if self.closed:
+            raise ValueError("I/O operation on closed file.")
+        if not isinstance(s, str):
+            raise TypeError("write() argument must be str, not " + type(s).__name__)
+        return self._write(s)
+
+    def flush(self):
+        if self.closed:
+            raise ValueError("I/O operation on closed file.")
+        self._flush()
+
+stream = RustStream(NAME, write, flush)
+"#;
+
+const CAPSULE_NAME: &[u8] = b"pyo3.redirect.Writer\0";
+
+type Writer = Mutex<Box<dyn Write + Send>>;
+
+/// Replaces `sys.stdout` or `sys.stderr` with a stream writing into a Rust writer, until it's
+/// dropped.
+pub struct Redirect {
+    name: &'static str,
+    stream: PyObject,
+    previous: PyObject,
+}
+
+impl Redirect {
+    /// Redirects `sys.stdout` into `writer`.
+    pub fn stdout<W: Write + Send + 'static>(py: Python, writer: W) -> PyResult<Self> {
+        Redirect::new(py, "stdout", Box::new(writer))
+    }
+
+    /// Redirects `sys.stderr` into `writer`.
+    pub fn stderr<W: Write + Send + 'static>(py: Python, writer: W) -> PyResult<Self> {
+        Redirect::new(py, "stderr", Box::new(writer))
+    }
+
+    /// The stream replacing `sys.stdout` or `sys.stderr`.
+    pub fn stream<'p>(&'p self, py: Python<'p>) -> &'p PyAny {
+        self.stream.as_ref(py)
+    }
+
+    fn new(py: Python, name: &'static str, writer: Box<dyn Write + Send>) -> PyResult<Self> {
+        let sys = py.import("sys")?;
+        let stream = create_stream(py, name, writer)?;
+        let previous = sys.getattr(name)?;
+        sys.setattr(name, stream)?;
+        Ok(Redirect {
+            name,
+            stream: stream.into(),
+            previous: previous.into(),
+        })
+    }
+}
+
+impl Drop for Redirect {
+    fn drop(&mut self) {
+        if !is_interpreter_alive() {
+            return;
+        }
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        // Errors of the writer can't be reported anymore
+        let _ = self.stream.call_method0(py, "flush");
+        if let Ok(sys) = py.import("sys") {
+            let _ = sys.setattr(self.name, &self.previous);
+        }
+    }
+}
+
+fn create_stream<'p>(
+    py: Python<'p>,
+    name: &str,
+    writer: Box<dyn Write + Send>,
+) -> PyResult<&'p PyAny> {
+    let writer: *mut Writer = Box::into_raw(Box::new(Mutex::new(writer)));
+    let capsule = unsafe {
+        ffi::PyCapsule_New(
+            writer as *mut c_void,
+            CAPSULE_NAME.as_ptr() as *const _,
+            Some(drop_writer),
+        )
+    };
+    if capsule.is_null() {
+        unsafe { drop(Box::from_raw(writer)) };
+        return Err(PyErr::fetch(py));
+    }
+    let capsule = unsafe { PyObject::from_owned_ptr(py, capsule) };
+
+    let write = PyMethodDef {
+        ml_name: "write",
+        ml_meth: PyMethodType::PyCFunction(write),
+        ml_flags: ffi::METH_O,
+        ml_doc: "\0",
+    };
+    let flush = PyMethodDef {
+        ml_name: "flush",
+        ml_meth: PyMethodType::PyCFunction(flush),
+        ml_flags: ffi::METH_NOARGS,
+        ml_doc: "\0",
+    };
+    let globals = PyDict::new(py);
+    globals.set_item("__builtins__", py.import("builtins")?)?;
+    globals.set_item("NAME", format!("<{}>", name))?;
+    globals.set_item("write", make_method(py, write, &capsule)?)?;
+    globals.set_item("flush", make_method(py, flush, &capsule)?)?;
+    py.run(STREAM_CLASS, Some(globals), None)?;
+    Ok(globals.get_item("stream").unwrap())
+}
+
+/// Creates a builtin function with `capsule` as `self`, which the function receives as its
+/// first argument.
+fn make_method(py: Python, def: PyMethodDef, capsule: &PyObject) -> PyResult<PyObject> {
+    unsafe {
+        PyObject::from_owned_ptr_or_err(
+            py,
+            ffi::PyCFunction_New(
+                Box::into_raw(Box::new(def.as_method_def())),
+                capsule.as_ptr(),
+            ),
+        )
+    }
+}
+
+unsafe extern "C" fn drop_writer(capsule: *mut ffi::PyObject) {
+    let writer = ffi::PyCapsule_GetPointer(capsule, CAPSULE_NAME.as_ptr() as *const _);
+    drop(Box::from_raw(writer as *mut Writer));
+}
+
+unsafe fn capsule_writer<'a>(capsule: *mut ffi::PyObject) -> &'a Writer {
+    &*(ffi::PyCapsule_GetPointer(capsule, CAPSULE_NAME.as_ptr() as *const _) as *const Writer)
+}
+
+/// Runs `f` with the GIL released, resuming a panic of the writer once the GIL is reacquired.
+fn without_gil<T>(py: Python, f: impl Send + FnOnce() -> T) -> T {
+    let result = py.allow_threads(|| panic::catch_unwind(panic::AssertUnwindSafe(f)));
+    result.unwrap_or_else(|payload| panic::resume_unwind(payload))
+}
+
+unsafe extern "C" fn write(
+    capsule: *mut ffi::PyObject,
+    arg: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    handle_callback("write", PyObjectCallbackConverter, |py| {
+        let text: &PyString = py.from_borrowed_ptr::<PyAny>(arg).downcast()?;
+        let text = text.to_string()?;
+        let writer = capsule_writer(capsule);
+        let bytes = text.as_bytes();
+        without_gil(py, || writer.lock().write_all(bytes))?;
+        Ok(text.chars().count())
+    })
+}
+
+unsafe extern "C" fn flush(
+    capsule: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    handle_callback("flush", PyObjectCallbackConverter, |py| {
+        let writer = capsule_writer(capsule);
+        without_gil(py, || writer.lock().flush())?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::Redirect;
+    use crate::exceptions::RuntimeError;
+    use crate::{AsPyPointer, ObjectProtocol, Python};
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Sink {
+        data: Arc<Mutex<Vec<u8>>>,
+        flushes: Arc<Mutex<usize>>,
+    }
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.data.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            *self.flushes.lock().unwrap() += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_redirect() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let sys = py.import("sys").unwrap();
+        let stderr = sys.getattr("stderr").unwrap().as_ptr();
+        let sink = Sink::default();
+        {
+            let redirect = Redirect::stderr(py, sink.clone()).unwrap();
+            assert_eq!(
+                redirect.stream(py).as_ptr(),
+                sys.getattr("stderr").unwrap().as_ptr()
+            );
+            py.run(
+                "import sys\n\
+                 print('héllo', file=sys.stderr)\n\
+                 assert sys.stderr.write('x') == 1\n\
+                 assert sys.stderr.writable() and not sys.stderr.isatty()\n\
+                 assert sys.stderr.name == '<stderr>' and sys.stderr.encoding == 'utf-8'\n\
+                 sys.stderr.flush()\n\
+                 try:\n    \
+                     sys.stderr.fileno()\n    \
+                     raise AssertionError('fileno() returned')\n\
+                 except OSError:\n    \
+                     pass\n\
+                 try:\n    \
+                     sys.stderr.write(b'bytes')\n    \
+                     raise AssertionError('wrote bytes')\n\
+                 except TypeError:\n    \
+                     pass",
+                None,
+                None,
+            )
+            .unwrap();
+        }
+        assert_eq!(&*sink.data.lock().unwrap(), "héllo\nx".as_bytes());
+        // By `flush()` and when the redirect is dropped
+        assert_eq!(*sink.flushes.lock().unwrap(), 2);
+        assert_eq!(sys.getattr("stderr").unwrap().as_ptr(), stderr);
+    }
+
+    struct Panicking;
+
+    impl Write for Panicking {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            panic!("write failed")
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            panic!("flush failed")
+        }
+    }
+
+    #[test]
+    fn test_panicking_writer() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let redirect = Redirect::stdout(py, Panicking).unwrap();
+        let stream = redirect.stream(py);
+        let err = stream.call_method1("write", ("x",)).unwrap_err();
+        assert!(err.is_instance::<RuntimeError>(py));
+        let err = stream.call_method0("flush").unwrap_err();
+        assert!(err.is_instance::<RuntimeError>(py));
+        // Dropping the redirect flushes the writer again, which mustn't unwind either
+        drop(redirect);
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Classes owning a resource whose cleanup can fail, like a socket, a file or a transaction.
+//!
+//! `Drop` can't report errors, so such a class should be closed explicitly, which Python code
+//! does with `close()` or a `with` statement. `#[pyclass(resource)]` generates that interface from
+//! an implementation of [`PyResource`]:
+//!
+//! * `close()` calls [`PyResource::close`] unless the object is already closed, and raises its
+//!   error.
+//! * The `closed` property returns [`PyResource::closed`].
+//! * `__enter__` returns the object, or raises a `ValueError` if it's closed, and `__exit__`
+//!   closes it.
+//! * An object which is garbage collected without being closed emits a `ResourceWarning` and is
+//!   then closed, reporting an error as unraisable, like the files of the `io` module.
+//!
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::py_run;
+//! use pyo3::resource::PyResource;
+//!
+//! #[pyclass(resource)]
+//! struct Transaction {
+//!     statements: Vec<String>,
+//!     committed: bool,
+//! }
+//!
+//! impl PyResource for Transaction {
+//!     fn close(&mut self) -> PyResult<()> {
+//!         // A failed commit must not be retried, so the transaction is closed either way
+//!         self.committed = true;
+//!         println!("commit {:?}", self.statements);
+//!         Ok(())
+//!     }
+//!
+//!     fn closed(&self) -> bool {
+//!         self.committed
+//!     }
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let transaction = Py::new(py, Transaction { statements: vec![], committed: false }).unwrap();
+//! py_run!(py, transaction, r#"
+//!     with transaction as t:
+//!         assert not t.closed
+//!     assert transaction.closed
+//!     transaction.close()
+//! "#);
+//! ```
+//!
+//! [`PyResource`]: trait.PyResource.html
+//! [`PyResource::close`]: trait.PyResource.html#tymethod.close
+//! [`PyResource::closed`]: trait.PyResource.html#tymethod.closed
+
+use crate::callback::{handle_callback, PyObjectCallbackConverter, UnitCallbackConverter};
+use crate::class::methods::{PyGetterDef, PyMethodDef, PyMethodDefType, PyMethodType};
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::ValueError;
+use crate::pyclass::{PyClass, PyClassShell};
+use crate::types::PyAny;
+use crate::{ffi, FromPyPointer, PyObject};
+use std::ptr;
+
+/// A `#[pyclass(resource)]` which must be closed explicitly.
+pub trait PyResource: PyClass {
+    /// Releases the resource. It's only called while [`closed`] returns `false`.
+    ///
+    /// If releasing the resource fails, implementations should usually still mark the object as
+    /// closed, as Python code doesn't expect to close an object twice.
+    ///
+    /// [`closed`]: #tymethod.closed
+    fn close(&mut self) -> PyResult<()>;
+
+    /// Whether the resource has been released.
+    fn closed(&self) -> bool;
+}
+
+/// The members generated for a `#[pyclass(resource)]`, submitted with its other methods.
+#[doc(hidden)]
+pub trait PyResourceMethods {
+    const METHODS: &'static [PyMethodDefType];
+}
+
+impl<T: PyResource> PyResourceMethods for T {
+    const METHODS: &'static [PyMethodDefType] = &[
+        PyMethodDefType::Method(PyMethodDef {
+            ml_name: "close",
+            ml_meth: PyMethodType::PyCFunction(close::<T>),
+            ml_flags: ffi::METH_NOARGS,
+            ml_doc: "close($self, /)\n--\n\nReleases the resource. Closing a closed object does nothing.\0",
+        }),
+        PyMethodDefType::Method(PyMethodDef {
+            ml_name: "__enter__",
+            ml_meth: PyMethodType::PyCFunction(enter::<T>),
+            ml_flags: ffi::METH_NOARGS,
+            ml_doc: "__enter__($self, /)\n--\n\nReturns the object, which must not be closed.\0",
+        }),
+        PyMethodDefType::Method(PyMethodDef {
+            ml_name: "__exit__",
+            ml_meth: PyMethodType::PyCFunction(exit::<T>),
+            ml_flags: ffi::METH_VARARGS,
+            ml_doc: "__exit__($self, *args)\n--\n\nCloses the object.\0",
+        }),
+        PyMethodDefType::Getter(PyGetterDef {
+            name: "closed",
+            meth: closed::<T>,
+            doc: "Whether the resource has been released.\0",
+        }),
+    ];
+}
+
+/// Closes `slf` if it isn't closed yet.
+fn close_once<T: PyResource>(slf: &mut T) -> PyResult<()> {
+    if slf.closed() {
+        Ok(())
+    } else {
+        slf.close()
+    }
+}
+
+unsafe extern "C" fn close<T: PyResource>(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    handle_callback("close", PyObjectCallbackConverter, |py| {
+        let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf);
+        close_once(&mut *slf.try_borrow_mut()?)
+    })
+}
+
+unsafe extern "C" fn enter<T: PyResource>(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    handle_callback("__enter__", PyObjectCallbackConverter, |py| {
+        let shell = <&PyClassShell<T>>::from_borrowed_ptr(py, slf);
+        if shell.try_borrow()?.closed() {
+            return Err(ValueError::py_err(format!(
+                "cannot enter a closed {} object",
+                T::NAME
+            )));
+        }
+        Ok(PyObject::from_borrowed_ptr(py, slf))
+    })
+}
+
+unsafe extern "C" fn exit<T: PyResource>(
+    slf: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    close::<T>(slf, args)
+}
+
+unsafe extern "C" fn closed<T: PyResource>(
+    slf: *mut ffi::PyObject,
+    _closure: *mut std::os::raw::c_void,
+) -> *mut ffi::PyObject {
+    handle_callback("closed", PyObjectCallbackConverter, |py| {
+        let slf = <&PyClassShell<T>>::from_borrowed_ptr(py, slf);
+        let closed = slf.try_borrow()?.closed();
+        Ok(closed)
+    })
+}
+
+/// `tp_finalize` of a `#[pyclass(resource)]`, warning about and closing an unclosed object.
+#[doc(hidden)]
+pub fn finalize<T: PyResource>() -> ffi::destructor {
+    unsafe extern "C" fn wrap<T: PyResource>(slf: *mut ffi::PyObject) {
+        // A finalizer runs at an arbitrary point, so it must keep the current exception
+        let (mut ptype, mut pvalue, mut ptraceback) =
+            (ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+        ffi::PyErr_Fetch(&mut ptype, &mut pvalue, &mut ptraceback);
+
+        handle_callback("tp_finalize", UnitCallbackConverter, |py| {
+            let shell = <&PyClassShell<T>>::from_borrowed_ptr(py, slf);
+            let mut resource = shell.try_borrow_mut()?;
+            if resource.closed() {
+                return Ok(());
+            }
+            let category = py.from_borrowed_ptr::<PyAny>(ffi::PyExc_ResourceWarning);
+            let message = format!("unclosed {} object", T::NAME);
+            if let Err(err) = PyErr::warn(py, category, &message, 1) {
+                err.restore(py);
+                ffi::PyErr_WriteUnraisable(slf);
+            }
+            resource.close()
+        });
+        // Errors of `close`, panics included, can't be raised from a finalizer
+        if !ffi::PyErr_Occurred().is_null() {
+            ffi::PyErr_WriteUnraisable(slf);
+        }
+
+        ffi::PyErr_Restore(ptype, pvalue, ptraceback);
+    }
+    wrap::<T>
+}
@@ -0,0 +1,96 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Scoped threads that run while the GIL is released, see [Python::allow_threads_scoped].
+//!
+//! [Python::allow_threads_scoped]: ../struct.Python.html#method.allow_threads_scoped
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::thread;
+
+type SharedHandle = Arc<parking_lot::Mutex<Option<thread::JoinHandle<()>>>>;
+
+/// A scope for spawning threads that may borrow data living outside of the scope.
+///
+/// All threads spawned in the scope are joined before the scope ends.
+pub struct Scope<'env> {
+    handles: parking_lot::Mutex<Vec<SharedHandle>>,
+    // Invariant in 'env, like crossbeam's scope
+    _marker: PhantomData<&'env mut &'env ()>,
+}
+
+/// A handle to join a thread spawned with [Scope::spawn].
+pub struct ScopedJoinHandle<'scope, T> {
+    handle: SharedHandle,
+    result: Arc<parking_lot::Mutex<Option<T>>>,
+    _marker: PhantomData<&'scope ()>,
+}
+
+impl<'env> Scope<'env> {
+    pub(crate) fn new() -> Self {
+        Scope {
+            handles: parking_lot::Mutex::new(Vec::new()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Spawns a thread that may borrow anything that outlives the scope.
+    pub fn spawn<'scope, F, T>(&'scope self, f: F) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce() -> T + Send + 'env,
+        T: Send + 'env,
+    {
+        let result = Arc::new(parking_lot::Mutex::new(None));
+        let result_slot = result.clone();
+        let closure: Box<dyn FnOnce() + Send + 'env> = Box::new(move || {
+            *result_slot.lock() = Some(f());
+        });
+        // This is safe because the scope joins the thread before 'env ends
+        let closure: Box<dyn FnOnce() + Send + 'static> = unsafe { std::mem::transmute(closure) };
+
+        let handle = Arc::new(parking_lot::Mutex::new(Some(thread::spawn(closure))));
+        self.handles.lock().push(handle.clone());
+        ScopedJoinHandle {
+            handle,
+            result,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Joins all threads that were not joined explicitly.
+    ///
+    /// Returns `true` if any of them panicked.
+    pub(crate) fn join_all(&self) -> bool {
+        let mut panicked = false;
+        loop {
+            let handles = std::mem::replace(&mut *self.handles.lock(), Vec::new());
+            if handles.is_empty() {
+                return panicked;
+            }
+            for handle in handles {
+                if let Some(handle) = handle.lock().take() {
+                    panicked |= handle.join().is_err();
+                }
+            }
+        }
+    }
+}
+
+impl<'scope, T> ScopedJoinHandle<'scope, T> {
+    /// Waits for the thread to finish and returns its result.
+    ///
+    /// Returns `Err` with the panic payload if the thread panicked.
+    pub fn join(self) -> thread::Result<T> {
+        let handle = self
+            .handle
+            .lock()
+            .take()
+            .expect("Scoped thread can only be joined once");
+        handle.join().map(|()| {
+            self.result
+                .lock()
+                .take()
+                .expect("Scoped thread finished without a result")
+        })
+    }
+}
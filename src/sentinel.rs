@@ -0,0 +1,183 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Module-lifetime singleton objects, like `MISSING` or `DEFAULT`, to distinguish arguments that
+//! weren't passed from `None`.
+//!
+//! A [`Sentinel`] is a `static` that creates its Python object once, the first time it's used.
+//! [`Sentinel::add_to`] exposes the object as an attribute of a module, and Rust code compares
+//! arguments with it by identity with [`Sentinel::matches`]:
+//!
+//! ```
+//! use pyo3::exceptions::KeyError;
+//! use pyo3::prelude::*;
+//! use pyo3::sentinel::Sentinel;
+//! use pyo3::types::{PyAny, PyDict};
+//! use pyo3::wrap_pyfunction;
+//!
+//! static MISSING: Sentinel = Sentinel::new("MISSING");
+//!
+//! /// Returns `default` if `key` isn't in `dict`, raises `KeyError` if `default` is `MISSING`.
+//! #[pyfunction]
+//! fn lookup<'p>(dict: &'p PyDict, key: &PyAny, default: &'p PyAny) -> PyResult<&'p PyAny> {
+//!     match dict.get_item(key) {
+//!         Some(value) => Ok(value),
+//!         None if MISSING.matches(default) => Err(KeyError::py_err(key.to_string())),
+//!         None => Ok(default),
+//!     }
+//! }
+//!
+//! #[pymodule]
+//! fn options(_py: Python, m: &PyModule) -> PyResult<()> {
+//!     MISSING.add_to(m)?;
+//!     m.add_wrapped(wrap_pyfunction!(lookup))
+//! }
+//! ```
+//!
+//! The objects have their name as `repr()`, and are pickled and copied as themselves. Add them to
+//! their module before using them, so that pickle can find them.
+//!
+//! [`Sentinel`]: struct.Sentinel.html
+//! [`Sentinel::add_to`]: struct.Sentinel.html#method.add_to
+//! [`Sentinel::matches`]: struct.Sentinel.html#method.matches
+
+use crate::err::PyResult;
+use crate::instance::AsPyRef;
+use crate::types::{PyAny, PyDict, PyModule};
+use crate::{AsPyPointer, IntoPy, PyNativeType, PyObject, Python, ToPyObject};
+use std::cell::UnsafeCell;
+
+const SENTINEL_CLASS: &str = r#"
+class Sentinel:
+    __slots__ = ()
+
+    def __repr__(self):
+        return NAME
+
+    def __reduce__(self):
+        return NAME
+
+Sentinel.__name__ = Sentinel.__qualname__ = NAME + "Type"
+value = Sentinel()
+"#;
+
+/// A singleton Python object, created once and shared by all uses of the `static`.
+pub struct Sentinel {
+    name: &'static str,
+    // Only accessed with the GIL held
+    value: UnsafeCell<Option<PyObject>>,
+}
+
+unsafe impl Sync for Sentinel {}
+
+impl Sentinel {
+    /// Creates a sentinel with `name` as `repr()` and attribute name.
+    pub const fn new(name: &'static str) -> Self {
+        Sentinel {
+            name,
+            value: UnsafeCell::new(None),
+        }
+    }
+
+    /// The name of the sentinel.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the object, creating it if this is the first use.
+    pub fn get<'p>(&'static self, py: Python<'p>) -> &'p PyAny {
+        self.get_or_create(py, None)
+            .unwrap_or_else(|e| panic!("failed to create sentinel {}: {:?}", self.name, e))
+    }
+
+    /// Adds the object to `module` as the attribute `name`.
+    ///
+    /// If the object doesn't exist yet, it's created as a member of `module`, which pickle
+    /// uses to find it.
+    pub fn add_to(&'static self, module: &PyModule) -> PyResult<()> {
+        let value = self.get_or_create(module.py(), Some(module.name()?))?;
+        module.add(self.name, value)
+    }
+
+    /// Returns `true` if `obj` is this sentinel. Equivalent to Python `obj is NAME`.
+    pub fn matches(&'static self, obj: &PyAny) -> bool {
+        // `obj` proves that the GIL is held
+        unsafe {
+            match *self.value.get() {
+                Some(ref value) => value.as_ptr() == obj.as_ptr(),
+                None => false,
+            }
+        }
+    }
+
+    fn get_or_create<'p>(
+        &'static self,
+        py: Python<'p>,
+        module: Option<&str>,
+    ) -> PyResult<&'p PyAny> {
+        unsafe {
+            if (*self.value.get()).is_none() {
+                let value = self.create(py, module)?;
+                // Creating the object runs Python code, so another thread may have been first
+                if (*self.value.get()).is_none() {
+                    *self.value.get() = Some(value.to_object(py));
+                }
+            }
+            Ok((*self.value.get()).as_ref().unwrap().as_ref(py))
+        }
+    }
+
+    fn create<'p>(&self, py: Python<'p>, module: Option<&str>) -> PyResult<&'p PyAny> {
+        let globals = PyDict::new(py);
+        globals.set_item("__builtins__", py.import("builtins")?)?;
+        globals.set_item("__name__", module.unwrap_or("builtins"))?;
+        globals.set_item("NAME", self.name)?;
+        py.run(SENTINEL_CLASS, Some(globals), None)?;
+        Ok(globals.get_item("value").unwrap())
+    }
+}
+
+impl IntoPy<PyObject> for &'static Sentinel {
+    fn into_py(self, py: Python) -> PyObject {
+        self.get(py).to_object(py)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Sentinel;
+    use crate::instance::AsPyRef;
+    use crate::types::{PyDict, PyModule};
+    use crate::{IntoPy, ObjectProtocol, PyObject, Python};
+
+    static MISSING: Sentinel = Sentinel::new("MISSING");
+    static DEFAULT: Sentinel = Sentinel::new("DEFAULT");
+
+    #[test]
+    fn test_sentinel() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = PyModule::new(py, "sentinel_test").unwrap();
+        module.add_to_sys_modules("sentinel_test").unwrap();
+        MISSING.add_to(module).unwrap();
+
+        let missing = MISSING.get(py);
+        assert!(MISSING.matches(missing));
+        assert!(!DEFAULT.matches(missing));
+        assert!(!MISSING.matches(py.None().as_ref(py)));
+        assert_eq!(missing.repr().unwrap().to_string().unwrap(), "MISSING");
+        let obj: PyObject = (&MISSING).into_py(py);
+        assert!(MISSING.matches(obj.as_ref(py)));
+
+        let locals = PyDict::new(py);
+        locals.set_item("m", module).unwrap();
+        py.run(
+            "import copy, pickle\n\
+             assert m.MISSING is copy.deepcopy(m.MISSING)\n\
+             assert m.MISSING is pickle.loads(pickle.dumps(m.MISSING))\n\
+             assert type(m.MISSING).__name__ == 'MISSINGType'",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,859 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Converting any serde `Serialize` type to Python objects and Python objects to any
+//! `Deserialize` type, enabled with the `serde` feature.
+//!
+//! [`to_object`] serializes a value to nested Python builtins, and [`from_object`] deserializes
+//! one from them, so config- and JSON-shaped data needs no conversion code:
+//!
+//! ```
+//! use pyo3::prelude::*;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Config {
+//!     name: String,
+//!     retries: Option<u32>,
+//!     tags: Vec<String>,
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let obj = py
+//!     .eval("{'name': 'job', 'retries': None, 'tags': ['a', 'b']}", None, None)
+//!     .unwrap();
+//! let config: Config = pyo3::serde::from_object(obj).unwrap();
+//! assert_eq!(config.tags, vec!["a", "b"]);
+//!
+//! let obj = pyo3::serde::to_object(py, &config).unwrap();
+//! pyo3::py_run!(py, obj, "assert obj == {'name': 'job', 'retries': None, 'tags': ['a', 'b']}");
+//! ```
+//!
+//! Values are serialized like `serde_json` would write them, with Python types in place of JSON
+//! types:
+//!
+//! | Rust                                | Python                     |
+//! |-------------------------------------|----------------------------|
+//! | `bool`, integers, floats            | `bool`, `int`, `float`     |
+//! | `char`, `String`                    | `str`                      |
+//! | bytes (`serde_bytes`)               | `bytes`                    |
+//! | `None`, `()`, unit structs          | `None`                     |
+//! | sequences                           | `list`                     |
+//! | tuples and tuple structs            | `tuple`                    |
+//! | maps and structs                    | `dict`                     |
+//! | unit variants                       | the name of the variant    |
+//! | other variants                      | `{name: content}`          |
+//!
+//! Deserialization accepts the same types, any sequence or set for sequences, `bytearray` for
+//! bytes, and any `dict` for maps. `serde_json::Value` round-trips through both.
+//!
+//! [`to_object`]: fn.to_object.html
+//! [`from_object`]: fn.from_object.html
+
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::{TypeError, ValueError};
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{
+    PyAny, PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyFrozenSet, PyIterator, PyList, PyLong,
+    PySet, PyString, PyTuple,
+};
+use crate::{IntoPy, PyObject, Python};
+use ::serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use ::serde::ser::{self, Serialize};
+use std::fmt;
+
+/// Serializes `value` to Python objects.
+pub fn to_object<T: Serialize + ?Sized>(py: Python, value: &T) -> PyResult<PyObject> {
+    Ok(value.serialize(Serializer::new(py))?)
+}
+
+/// Deserializes a `T` from the Python object `obj`.
+pub fn from_object<'de, T: de::Deserialize<'de>>(obj: &'de PyAny) -> PyResult<T> {
+    Ok(T::deserialize(Deserializer::new(obj))?)
+}
+
+/// An error of [`to_object`] or [`from_object`], converted to a Python exception by `?`.
+///
+/// Errors raised by Python are kept, wrong types raise `TypeError`, and other errors of serde
+/// raise `ValueError`.
+///
+/// [`to_object`]: fn.to_object.html
+/// [`from_object`]: fn.from_object.html
+#[derive(Debug)]
+pub struct Error(ErrorImpl);
+
+#[derive(Debug)]
+enum ErrorImpl {
+    Py(PyErr),
+    Type(String),
+    Message(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            ErrorImpl::Py(ref err) => write!(f, "Python exception: {:?}", err),
+            ErrorImpl::Type(ref msg) | ErrorImpl::Message(ref msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(ErrorImpl::Message(msg.to_string()))
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(ErrorImpl::Message(msg.to_string()))
+    }
+
+    fn invalid_type(unexpected: de::Unexpected, expected: &dyn de::Expected) -> Self {
+        Error(ErrorImpl::Type(format!(
+            "invalid type: {}, expected {}",
+            unexpected, expected
+        )))
+    }
+}
+
+impl From<PyErr> for Error {
+    fn from(err: PyErr) -> Self {
+        Error(ErrorImpl::Py(err))
+    }
+}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> PyErr {
+        match err.0 {
+            ErrorImpl::Py(err) => err,
+            ErrorImpl::Type(msg) => TypeError::py_err(msg),
+            ErrorImpl::Message(msg) => ValueError::py_err(msg),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A serde `Serializer` creating Python objects.
+pub struct Serializer<'p> {
+    py: Python<'p>,
+}
+
+impl<'p> Serializer<'p> {
+    pub fn new(py: Python<'p>) -> Self {
+        Serializer { py }
+    }
+
+    fn object<T: IntoPy<PyObject>>(self, value: T) -> Result<PyObject> {
+        Ok(value.into_py(self.py))
+    }
+
+    /// `{variant: content}`
+    fn variant(self, variant: &str, content: PyObject) -> Result<PyObject> {
+        let dict = PyDict::new(self.py);
+        dict.set_item(variant, content)?;
+        Ok(dict.into())
+    }
+}
+
+impl<'p> ser::Serializer for Serializer<'p> {
+    type Ok = PyObject;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer<'p>;
+    type SerializeTuple = SeqSerializer<'p>;
+    type SerializeTupleStruct = SeqSerializer<'p>;
+    type SerializeTupleVariant = SeqSerializer<'p>;
+    type SerializeMap = MapSerializer<'p>;
+    type SerializeStruct = MapSerializer<'p>;
+    type SerializeStructVariant = MapSerializer<'p>;
+
+    fn serialize_bool(self, v: bool) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<PyObject> {
+        self.object(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<PyObject> {
+        Ok(PyBytes::new(self.py, v).into())
+    }
+
+    fn serialize_none(self) -> Result<PyObject> {
+        Ok(self.py.None())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<PyObject> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<PyObject> {
+        Ok(self.py.None())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<PyObject> {
+        Ok(self.py.None())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<PyObject> {
+        self.object(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<PyObject> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<PyObject> {
+        let content = value.serialize(Serializer::new(self.py))?;
+        self.variant(variant, content)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer<'p>> {
+        Ok(SeqSerializer::new(self.py, len, SeqKind::List))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer<'p>> {
+        Ok(SeqSerializer::new(self.py, Some(len), SeqKind::Tuple))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqSerializer<'p>> {
+        Ok(SeqSerializer::new(self.py, Some(len), SeqKind::Tuple))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer<'p>> {
+        Ok(SeqSerializer::new(
+            self.py,
+            Some(len),
+            SeqKind::Variant(variant),
+        ))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'p>> {
+        Ok(MapSerializer::new(self.py, None))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<MapSerializer<'p>> {
+        Ok(MapSerializer::new(self.py, None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer<'p>> {
+        Ok(MapSerializer::new(self.py, Some(variant)))
+    }
+}
+
+enum SeqKind {
+    List,
+    Tuple,
+    Variant(&'static str),
+}
+
+#[doc(hidden)]
+pub struct SeqSerializer<'p> {
+    py: Python<'p>,
+    items: Vec<PyObject>,
+    kind: SeqKind,
+}
+
+impl<'p> SeqSerializer<'p> {
+    fn new(py: Python<'p>, len: Option<usize>, kind: SeqKind) -> Self {
+        SeqSerializer {
+            py,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            kind,
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.items.push(value.serialize(Serializer::new(self.py))?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<PyObject> {
+        let py = self.py;
+        match self.kind {
+            SeqKind::List => Ok(PyList::new(py, self.items).into()),
+            SeqKind::Tuple => Ok(PyTuple::new(py, self.items).into()),
+            SeqKind::Variant(variant) => {
+                Serializer::new(py).variant(variant, PyTuple::new(py, self.items).into())
+            }
+        }
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+#[doc(hidden)]
+pub struct MapSerializer<'p> {
+    py: Python<'p>,
+    dict: &'p PyDict,
+    key: Option<PyObject>,
+    variant: Option<&'static str>,
+}
+
+impl<'p> MapSerializer<'p> {
+    fn new(py: Python<'p>, variant: Option<&'static str>) -> Self {
+        MapSerializer {
+            py,
+            dict: PyDict::new(py),
+            key: None,
+            variant,
+        }
+    }
+
+    fn insert<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<()> {
+        let value = value.serialize(Serializer::new(self.py))?;
+        self.dict.set_item(key, value)?;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<PyObject> {
+        match self.variant {
+            Some(variant) => Serializer::new(self.py).variant(variant, self.dict.into()),
+            None => Ok(self.dict.into()),
+        }
+    }
+}
+
+impl ser::SerializeMap for MapSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(key.serialize(Serializer::new(self.py))?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        let value = value.serialize(Serializer::new(self.py))?;
+        self.dict.set_item(key, value)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+/// A serde `Deserializer` reading a Python object.
+pub struct Deserializer<'de> {
+    obj: &'de PyAny,
+}
+
+impl<'de> Deserializer<'de> {
+    pub fn new(obj: &'de PyAny) -> Self {
+        Deserializer { obj }
+    }
+
+    fn type_error(&self, expected: &dyn de::Expected) -> Error {
+        let name = self.obj.get_type().name().into_owned();
+        de::Error::invalid_type(de::Unexpected::Other(&format!("Python {}", name)), expected)
+    }
+
+    fn sequence(&self) -> Result<Option<PyIterator<'de>>> {
+        let obj = self.obj;
+        let is_sequence = obj.downcast::<PyList>().is_ok()
+            || obj.downcast::<PyTuple>().is_ok()
+            || obj.downcast::<PySet>().is_ok()
+            || obj.downcast::<PyFrozenSet>().is_ok();
+        if is_sequence {
+            Ok(Some(obj.iter()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let obj = self.obj;
+        if obj.is_none() {
+            visitor.visit_unit()
+        } else if let Ok(b) = obj.downcast::<PyBool>() {
+            visitor.visit_bool(b.is_true())
+        } else if obj.downcast::<PyLong>().is_ok() {
+            if let Ok(v) = obj.extract::<i64>() {
+                visitor.visit_i64(v)
+            } else if let Ok(v) = obj.extract::<u64>() {
+                visitor.visit_u64(v)
+            } else {
+                visitor.visit_i128(obj.extract::<i128>()?)
+            }
+        } else if let Ok(f) = obj.downcast::<PyFloat>() {
+            visitor.visit_f64(f.value())
+        } else if let Ok(s) = obj.downcast::<PyString>() {
+            visitor.visit_str(&s.to_string()?)
+        } else if let Ok(b) = obj.downcast::<PyBytes>() {
+            visitor.visit_borrowed_bytes(b.as_bytes())
+        } else if let Ok(b) = obj.downcast::<PyByteArray>() {
+            visitor.visit_byte_buf(b.to_vec())
+        } else if let Ok(dict) = obj.downcast::<PyDict>() {
+            visitor.visit_map(MapDeserializer::new(dict))
+        } else if let Some(iter) = self.sequence()? {
+            visitor.visit_seq(SeqDeserializer { iter })
+        } else {
+            Err(self.type_error(&visitor))
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.obj.is_none() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.obj.is_none() {
+            visitor.visit_unit()
+        } else {
+            Err(self.type_error(&visitor))
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let obj = self.obj;
+        if let Ok(variant) = obj.downcast::<PyString>() {
+            let variant = variant.to_string()?.into_owned();
+            visitor.visit_enum(variant.into_deserializer())
+        } else if let Ok(dict) = obj.downcast::<PyDict>() {
+            if dict.len() != 1 {
+                return Err(de::Error::invalid_length(
+                    dict.len(),
+                    &"a dict with a single key",
+                ));
+            }
+            let (variant, content) = dict.iter().next().unwrap();
+            visitor.visit_enum(EnumDeserializer { variant, content })
+        } else {
+            Err(self.type_error(&visitor))
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct SeqDeserializer<'de> {
+    iter: PyIterator<'de>,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match self.iter.next() {
+            Some(item) => seed.deserialize(Deserializer::new(item?)).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+struct MapDeserializer<'de> {
+    items: std::vec::IntoIter<(&'de PyAny, &'de PyAny)>,
+    len: usize,
+    value: Option<&'de PyAny>,
+}
+
+impl<'de> MapDeserializer<'de> {
+    fn new(dict: &'de PyDict) -> Self {
+        MapDeserializer {
+            items: dict.iter().collect::<Vec<_>>().into_iter(),
+            len: dict.len(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.items.next() {
+            Some((key, value)) => {
+                self.len -= 1;
+                self.value = Some(value);
+                seed.deserialize(Deserializer::new(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        seed.deserialize(Deserializer::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+struct EnumDeserializer<'de> {
+    variant: &'de PyAny,
+    content: &'de PyAny,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer<'de> {
+    type Error = Error;
+    type Variant = Deserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Deserializer<'de>)> {
+        let variant = seed.deserialize(Deserializer::new(self.variant))?;
+        Ok((variant, Deserializer::new(self.content)))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Deserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{from_object, to_object};
+    use crate::exceptions::{TypeError, ValueError};
+    use crate::types::{IntoPyDict, PyDict};
+    use crate::{AsPyRef, Python};
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Point(i32, i32),
+        Rect { w: u32, h: u32 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Unit;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Meters(f32);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Everything {
+        flag: bool,
+        small: i8,
+        big: u64,
+        huge: i128,
+        ratio: f64,
+        letter: char,
+        name: String,
+        missing: Option<String>,
+        present: Option<u8>,
+        unit: Unit,
+        meters: Meters,
+        pair: (u8, String),
+        list: Vec<i32>,
+        map: BTreeMap<String, u8>,
+        shapes: Vec<Shape>,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let value = Everything {
+            flag: true,
+            small: -3,
+            big: u64::max_value(),
+            huge: i128::min_value(),
+            ratio: 0.5,
+            letter: 'é',
+            name: "name".to_string(),
+            missing: None,
+            present: Some(7),
+            unit: Unit,
+            meters: Meters(1.5),
+            pair: (1, "one".to_string()),
+            list: vec![1, 2, 3],
+            map: vec![("a".to_string(), 1), ("b".to_string(), 2)]
+                .into_iter()
+                .collect(),
+            shapes: vec![
+                Shape::Empty,
+                Shape::Circle(2.0),
+                Shape::Point(1, -1),
+                Shape::Rect { w: 3, h: 4 },
+            ],
+        };
+        let obj = to_object(py, &value).unwrap();
+        let locals = [("obj", &obj)].into_py_dict(py);
+        py.run(
+            r#"
+assert obj == {
+    "flag": True,
+    "small": -3,
+    "big": 2**64 - 1,
+    "huge": -2**127,
+    "ratio": 0.5,
+    "letter": "é",
+    "name": "name",
+    "missing": None,
+    "present": 7,
+    "unit": None,
+    "meters": 1.5,
+    "pair": (1, "one"),
+    "list": [1, 2, 3],
+    "map": {"a": 1, "b": 2},
+    "shapes": ["Empty", {"Circle": 2.0}, {"Point": (1, -1)}, {"Rect": {"w": 3, "h": 4}}],
+}, obj
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        assert_eq!(from_object::<Everything>(obj.as_ref(py)).unwrap(), value);
+
+        // Lists for tuples, and ints for floats
+        let obj = py
+            .eval("{'Point': [1, 2]}, {'Circle': 1}, {1, 2}", None, None)
+            .unwrap();
+        let (point, circle, set): (Shape, Shape, Vec<u8>) = from_object(obj).unwrap();
+        assert_eq!(point, Shape::Point(1, 2));
+        assert_eq!(circle, Shape::Circle(1.0));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_errors() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let err = from_object::<Vec<u8>>(py.eval("'abc'", None, None).unwrap()).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        let err = from_object::<u8>(py.eval("300", None, None).unwrap()).unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+        let err = from_object::<Everything>(PyDict::new(py).as_ref()).unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+        let err = from_object::<Shape>(py.eval("{'Nope': 1}", None, None).unwrap()).unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+        let err = from_object::<f64>(py.eval("object()", None, None).unwrap()).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Sharing Rust values, like an async runtime or a thread pool, between extension modules.
+//!
+//! Every extension module is a separate Rust binary, so statics aren't shared, and two Rust
+//! extensions loaded into the same process would each start their own runtime. [`publish`] puts a
+//! value into a [capsule](https://docs.python.org/3/c-api/capsule.html) attribute of a module,
+//! and [`import`] gets it from another extension by the path of the attribute, the way C
+//! extensions share their C APIs:
+//!
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::shared;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! pub struct Config {
+//!     pub max_threads: usize,
+//!     pub started_tasks: AtomicUsize,
+//! }
+//!
+//! #[pymodule]
+//! fn runtime(_py: Python, m: &PyModule) -> PyResult<()> {
+//!     let config = Config { max_threads: 4, started_tasks: AtomicUsize::new(0) };
+//!     shared::publish(m, "_config", config)
+//! }
+//!
+//! // In another extension, after `runtime` was imported
+//! fn start_task(py: Python) -> PyResult<()> {
+//!     // Safety: both extensions use the same version of the crate defining `Config`
+//!     let config = unsafe { shared::import::<Config>(py, "runtime._config")? };
+//!     config.started_tasks.fetch_add(1, Ordering::Relaxed);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! The value lives until the capsule is garbage collected, usually until the interpreter is
+//! finalized. To use it without holding the GIL, share an `Arc` or a handle that can be cloned.
+//!
+//! [`publish`]: fn.publish.html
+//! [`import`]: fn.import.html
+
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::TypeError;
+use crate::ffi;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::PyModule;
+use crate::{AsPyPointer, PyNativeType, PyObject, Python};
+use std::any;
+use std::ffi::CString;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::slice;
+use std::str;
+
+/// The description of the type of a shared value, which the importing extension checks before
+/// accessing the value. Only contains fields with the same layout in every build.
+#[repr(C)]
+struct Header {
+    type_name: *const u8,
+    type_name_len: usize,
+    size: usize,
+    align: usize,
+}
+
+#[repr(C)]
+struct Shared<T> {
+    header: Header,
+    // The name of the capsule, which must stay valid as long as the capsule
+    name: *mut c_char,
+    value: T,
+}
+
+/// Adds `value` to `module` as a capsule named `name`, so that other extensions can get it with
+/// [`import`](fn.import.html) as `"<module name>.<name>"`.
+pub fn publish<T>(module: &PyModule, name: &str, value: T) -> PyResult<()>
+where
+    T: Send + Sync + 'static,
+{
+    let py = module.py();
+    let capsule_name = CString::new(format!("{}.{}", module.name()?, name))?;
+    let type_name = any::type_name::<T>();
+    let shared = Box::new(Shared {
+        header: Header {
+            type_name: type_name.as_ptr(),
+            type_name_len: type_name.len(),
+            size: mem::size_of::<T>(),
+            align: mem::align_of::<T>(),
+        },
+        name: capsule_name.into_raw(),
+        value,
+    });
+    let name_ptr = shared.name;
+    let shared = Box::into_raw(shared);
+    let capsule =
+        unsafe { ffi::PyCapsule_New(shared as *mut c_void, name_ptr, Some(destructor::<T>)) };
+    if capsule.is_null() {
+        unsafe { drop_shared::<T>(shared) };
+        return Err(PyErr::fetch(py));
+    }
+    let capsule = unsafe { PyObject::from_owned_ptr(py, capsule) };
+    module.add(name, capsule)
+}
+
+unsafe extern "C" fn destructor<T>(capsule: *mut ffi::PyObject) {
+    let shared = ffi::PyCapsule_GetPointer(capsule, ffi::PyCapsule_GetName(capsule));
+    drop_shared::<T>(shared as *mut Shared<T>);
+}
+
+unsafe fn drop_shared<T>(shared: *mut Shared<T>) {
+    let shared = Box::from_raw(shared);
+    let name = shared.name;
+    drop(shared);
+    drop(CString::from_raw(name));
+}
+
+/// Gets a value published with [`publish`](fn.publish.html) by another extension, from the
+/// dotted path of its capsule, e.g. `"package.module.name"`. The module is imported if needed.
+///
+/// Raises a `TypeError` if the attribute isn't a capsule published as `path`, or if the value
+/// has a different type, size or alignment than `T`.
+///
+/// # Safety
+///
+/// The published value must really be a `T`, which is only checked by comparing the type names
+/// and layouts. The extensions must use the same version of the crate defining `T`, built with
+/// the same compiler.
+pub unsafe fn import<'p, T>(py: Python<'p>, path: &str) -> PyResult<&'p T>
+where
+    T: Send + Sync + 'static,
+{
+    let (module, name) = match path.rfind('.') {
+        Some(dot) => (&path[..dot], &path[dot + 1..]),
+        None => {
+            return Err(TypeError::py_err(format!(
+                "'{}' is not a dotted path",
+                path
+            )))
+        }
+    };
+    let capsule = py.import(module)?.getattr(name)?;
+    let capsule_name = CString::new(path)?;
+    if ffi::PyCapsule_IsValid(capsule.as_ptr(), capsule_name.as_ptr()) == 0 {
+        return Err(TypeError::py_err(format!(
+            "'{}' is not a published capsule",
+            path
+        )));
+    }
+    let shared = ffi::PyCapsule_GetPointer(capsule.as_ptr(), capsule_name.as_ptr());
+
+    let header = &*(shared as *const Header);
+    let type_name = str::from_utf8_unchecked(slice::from_raw_parts(
+        header.type_name,
+        header.type_name_len,
+    ));
+    if type_name != any::type_name::<T>()
+        || header.size != mem::size_of::<T>()
+        || header.align != mem::align_of::<T>()
+    {
+        return Err(TypeError::py_err(format!(
+            "'{}' is a '{}', not a '{}'",
+            path,
+            type_name,
+            any::type_name::<T>()
+        )));
+    }
+    // The capsule is owned by the release pool, so the value lives at least as long as 'p
+    Ok(&(*(shared as *const Shared<T>)).value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{import, publish};
+    use crate::exceptions::TypeError;
+    use crate::types::PyModule;
+    use crate::Python;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_publish_import() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = PyModule::new(py, "shared_test").unwrap();
+        module.add_to_sys_modules("shared_test").unwrap();
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        publish(module, "counter", counter.clone()).unwrap();
+        let imported = unsafe { import::<Arc<AtomicUsize>>(py, "shared_test.counter") }.unwrap();
+        imported.fetch_add(1, Ordering::SeqCst);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert_eq!(Arc::strong_count(&counter), 2);
+
+        let err = unsafe { import::<Arc<usize>>(py, "shared_test.counter") }.unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        module.add("number", 1).unwrap();
+        let err = unsafe { import::<usize>(py, "shared_test.number") }.unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        assert!(unsafe { import::<usize>(py, "shared_test.missing") }.is_err());
+
+        // Replacing the attribute drops the value once the capsule isn't referenced anymore
+        module.add("counter", 0).unwrap();
+        drop(gil);
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+}
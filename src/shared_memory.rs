@@ -0,0 +1,211 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Blocks of `multiprocessing.shared_memory`, accessible as Rust slices and Python memoryviews.
+//!
+//! [`SharedMemory`] creates or attaches to a shared memory block through Python's
+//! `multiprocessing.shared_memory` module (Python 3.8 or newer), so Rust and Python code in
+//! several processes work on the same memory. The producer creates a block and passes its name to
+//! the consumers, e.g. through a `multiprocessing.Queue`, and they attach to it by name:
+//!
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::shared_memory::SharedMemory;
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! # if py.import("multiprocessing.shared_memory").is_err() { return; }
+//! let mut producer = SharedMemory::create(py, 1024).unwrap();
+//! unsafe { producer.as_mut_slice()[..5].copy_from_slice(b"hello") };
+//!
+//! // In another process
+//! let name = producer.name(py).unwrap();
+//! let consumer = SharedMemory::attach(py, &name).unwrap();
+//! assert_eq!(unsafe { &consumer.as_slice()[..5] }, b"hello");
+//! let buf = consumer.buf(py).unwrap();
+//! pyo3::py_run!(py, buf, "assert bytes(buf[:5]) == b'hello'");
+//!
+//! consumer.close(py).unwrap();
+//! producer.unlink(py).unwrap();
+//! producer.close(py).unwrap();
+//! ```
+//!
+//! A block is only freed when it's unlinked, which the creator does once all processes are done
+//! with it. The memory stays mapped until `close()`, or until the Python `SharedMemory` object is
+//! garbage collected, and `close()` fails with a `BufferError` while Python code still holds
+//! memoryviews of it.
+//!
+//! [`SharedMemory`]: struct.SharedMemory.html
+
+use crate::buffer::PyBuffer;
+use crate::err::PyResult;
+use crate::exceptions::BufferError;
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyDict};
+use crate::{FromPyObject, PyObject, Python};
+use std::slice;
+
+/// A mapped block of `multiprocessing.shared_memory`.
+pub struct SharedMemory {
+    // Exports the mapping, so it can't be unmapped before `close()`. It's released before the
+    // object is dropped, whose `__del__` unmaps the memory.
+    buffer: PyBuffer,
+    shm: PyObject,
+}
+
+impl SharedMemory {
+    /// Creates a new block of `size` bytes with a random name.
+    pub fn create(py: Python, size: usize) -> PyResult<Self> {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("create", true)?;
+        kwargs.set_item("size", size)?;
+        SharedMemory::open(py, kwargs)
+    }
+
+    /// Creates a new block of `size` bytes called `name`, raising `FileExistsError` if it
+    /// exists.
+    pub fn create_named(py: Python, name: &str, size: usize) -> PyResult<Self> {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("name", name)?;
+        kwargs.set_item("create", true)?;
+        kwargs.set_item("size", size)?;
+        SharedMemory::open(py, kwargs)
+    }
+
+    /// Attaches to the existing block `name`, raising `FileNotFoundError` if there's none.
+    pub fn attach(py: Python, name: &str) -> PyResult<Self> {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("name", name)?;
+        SharedMemory::open(py, kwargs)
+    }
+
+    fn open(py: Python, kwargs: &PyDict) -> PyResult<Self> {
+        let shm = py
+            .import("multiprocessing.shared_memory")?
+            .getattr("SharedMemory")?
+            .call((), Some(kwargs))?;
+        SharedMemory::from_object(shm)
+    }
+
+    /// Maps a Python `multiprocessing.shared_memory.SharedMemory`.
+    pub fn from_object(shm: &PyAny) -> PyResult<Self> {
+        let py = shm.py();
+        let buffer = PyBuffer::get(py, shm.getattr("buf")?)?;
+        if buffer.readonly() || !buffer.is_c_contiguous() {
+            buffer.release(py);
+            return Err(BufferError::py_err(
+                "shared memory must be a writable contiguous buffer",
+            ));
+        }
+        Ok(SharedMemory {
+            buffer,
+            shm: shm.into(),
+        })
+    }
+
+    /// The name of the block, to attach to it from other processes.
+    pub fn name(&self, py: Python) -> PyResult<String> {
+        self.shm.getattr(py, "name")?.extract(py)
+    }
+
+    /// The size of the mapped memory in bytes, which may be rounded up from the requested size.
+    pub fn len(&self) -> usize {
+        self.buffer.len_bytes()
+    }
+
+    /// Returns true if the block is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The Python `SharedMemory` object, to pass the block to Python code.
+    pub fn object<'p>(&'p self, py: Python<'p>) -> &'p PyAny {
+        self.shm.as_ref(py)
+    }
+
+    /// The memory as a Python `memoryview`.
+    pub fn buf<'p>(&'p self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        self.object(py).getattr("buf")
+    }
+
+    /// The memory as a slice.
+    ///
+    /// # Safety
+    ///
+    /// Other processes, and Python code of this process, may write to the memory. The caller
+    /// must make sure they don't while the slice is in use, e.g. with a lock or by passing
+    /// messages.
+    pub unsafe fn as_slice(&self) -> &[u8] {
+        slice::from_raw_parts(self.buffer.buf_ptr() as *const u8, self.len())
+    }
+
+    /// The memory as a mutable slice.
+    ///
+    /// # Safety
+    ///
+    /// Other processes, and Python code of this process, may read or write the memory. The
+    /// caller must make sure they don't while the slice is in use.
+    pub unsafe fn as_mut_slice(&mut self) -> &mut [u8] {
+        slice::from_raw_parts_mut(self.buffer.buf_ptr() as *mut u8, self.len())
+    }
+
+    /// Unmaps the memory in this process. The block stays available to other processes until
+    /// it's unlinked.
+    pub fn close(self, py: Python) -> PyResult<()> {
+        let SharedMemory { buffer, shm } = self;
+        buffer.release(py);
+        shm.call_method0(py, "close")?;
+        Ok(())
+    }
+
+    /// Requests the block to be destroyed once all processes have closed it. It can't be
+    /// attached to anymore.
+    pub fn unlink(&self, py: Python) -> PyResult<()> {
+        self.shm.call_method0(py, "unlink")?;
+        Ok(())
+    }
+}
+
+impl<'source> FromPyObject<'source> for SharedMemory {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        SharedMemory::from_object(obj)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SharedMemory;
+    use crate::types::IntoPyDict;
+    use crate::{ObjectProtocol, Python};
+
+    #[test]
+    fn test_shared_memory() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        if py.import("multiprocessing.shared_memory").is_err() {
+            // Python 3.7 and older
+            return;
+        }
+        let mut producer = SharedMemory::create(py, 100).unwrap();
+        assert!(producer.len() >= 100);
+        unsafe { producer.as_mut_slice()[..3].copy_from_slice(&[1, 2, 3]) };
+
+        let name = producer.name(py).unwrap();
+        let consumer = SharedMemory::attach(py, &name).unwrap();
+        assert_eq!(unsafe { &consumer.as_slice()[..3] }, &[1, 2, 3]);
+
+        let locals = [("shm", producer.object(py))].into_py_dict(py);
+        py.run("shm.buf[3] = 4", None, Some(locals)).unwrap();
+        assert_eq!(unsafe { consumer.as_slice()[3] }, 4);
+        let extracted: SharedMemory = producer.object(py).extract().unwrap();
+        assert_eq!(extracted.len(), producer.len());
+        // Dropping it doesn't close the object of `producer`
+        drop(extracted);
+        assert_eq!(unsafe { producer.as_slice()[3] }, 4);
+
+        consumer.close(py).unwrap();
+        producer.unlink(py).unwrap();
+        assert!(SharedMemory::attach(py, &name).is_err());
+        producer.close(py).unwrap();
+    }
+}
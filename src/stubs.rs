@@ -0,0 +1,298 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Generation of `.pyi` stub files, enabled with the `stubs` feature.
+//!
+//! Type checkers and IDEs can't look into extension modules. With this feature, `#[pyfunction]`,
+//! `#[pymethods]` and `#[pyo3(get, set)]` record the Python types of their arguments and return
+//! values, and [`generate`] combines them with the docstrings of a module's objects into a stub:
+//!
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::wrap_pyfunction;
+//!
+//! /// Repeats `text`
+//! #[pyfunction(times = 2)]
+//! fn repeat(text: &str, times: usize) -> String {
+//!     text.repeat(times)
+//! }
+//!
+//! # fn main() -> PyResult<()> {
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let module = PyModule::new(py, "texts")?;
+//! module.add_wrapped(wrap_pyfunction!(repeat))?;
+//!
+//! let stub = pyo3::stubs::generate(module)?;
+//! assert!(stub.contains("def repeat(text: str, times: int = 2) -> str:\n    \"\"\"Repeats `text`\"\"\"\n"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A build script or a test can write the stub next to the extension, e.g. as `texts.pyi`.
+//! `python_function` creates a Python function doing the same, which can be added to the module
+//! with `m.add_wrapped(&pyo3::stubs::python_function)` and called as `generate_stub(module)`.
+//!
+//! Rust types are translated following pyo3's conversions, e.g. `Vec<i32>` to `List[int]` and
+//! `Option<T>` to `Optional[T]`. Other types are written with their Rust name, which is the name
+//! of the class for `#[pyclass]`es. Methods implemented with `#[pyproto]` are not included.
+//!
+//! [`generate`]: fn.generate.html
+
+use crate::callback::PyObjectCallbackConverter;
+use crate::err::PyResult;
+use crate::ffi;
+use crate::instance::PyNativeType;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyModule, PyType};
+use crate::{AsPyPointer, PyObject, PyTryFrom, Python};
+use std::fmt::Write;
+use std::ptr;
+
+/// The typed signature of a `#[pyfunction]`.
+pub struct FunctionStub {
+    /// The address of the C function implementing the Python function.
+    pub function: usize,
+    /// The parameters and return type, e.g. `(a: int, b: Optional[int] = None) -> int`.
+    pub signature: &'static str,
+}
+
+/// The kinds of members of a class.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MemberKind {
+    Method,
+    ClassMethod,
+    StaticMethod,
+    Getter,
+    Setter,
+    Deleter,
+    ClassAttribute,
+}
+
+/// The typed signature of a method, property or constructor of a `#[pyclass]`, or the type of a
+/// class attribute.
+pub struct MemberStub {
+    pub owner: fn() -> &'static ffi::PyTypeObject,
+    pub kind: MemberKind,
+    /// The Python name, `__init__` for the constructor.
+    pub name: &'static str,
+    /// The parameters, including `self` or `cls`, and return type, or the type of a class
+    /// attribute.
+    pub signature: &'static str,
+}
+
+inventory::collect!(FunctionStub);
+inventory::collect!(MemberStub);
+
+/// Generates the stub of `module`, with its functions, its classes and the types of its other
+/// attributes.
+pub fn generate(module: &PyModule) -> PyResult<String> {
+    let mut stub = String::new();
+    if let Some(doc) = docstring(module.as_ref())? {
+        write_docstring(&mut stub, "", &doc);
+        stub.push('\n');
+    }
+    stub.push_str("from typing import Any, Dict, Iterator, List, Optional, Set, Tuple\n");
+
+    for (name, value) in module.dict().iter() {
+        let name: &str = name.extract()?;
+        // e.g. `__doc__`, `__pyo3_build_info__`
+        if name.starts_with("__") && name.ends_with("__") {
+            continue;
+        }
+        stub.push('\n');
+        if unsafe { ffi::PyCFunction_Check(value.as_ptr()) } != 0 {
+            let signature = match function_signature(value) {
+                Some(signature) => signature.to_string(),
+                None => untyped_signature(value)?,
+            };
+            write_def(&mut stub, "", name, &signature, docstring(value)?)
+        } else if let Ok(cls) = <PyType as PyTryFrom>::try_from(value) {
+            write_class(&mut stub, module, name, cls)?;
+        } else {
+            let annotation = annotation(module, value.get_type())?;
+            writeln!(stub, "{}: {}", name, annotation).unwrap();
+        }
+    }
+    Ok(stub)
+}
+
+crate::trampoline! {
+    fn py_generate_stub(_slf: *mut ffi::PyObject, module: *mut ffi::PyObject) -> *mut ffi::PyObject;
+    converter = PyObjectCallbackConverter;
+    |py| {
+        let module = unsafe { py.from_borrowed_ptr::<PyAny>(module) };
+        generate(<PyModule as PyTryFrom>::try_from(module)?)
+    }
+}
+
+inventory::submit! {
+    FunctionStub {
+        function: py_generate_stub as usize,
+        signature: "(module: Any) -> str",
+    }
+}
+
+/// Creates the Python function `generate_stub(module)`, which returns the stub of the module as a
+/// string.
+pub fn python_function(py: Python) -> PyObject {
+    crate::derive_utils::builtin_function(
+        py,
+        "generate_stub",
+        py_generate_stub,
+        ffi::METH_O,
+        "generate_stub(module)\n--\n\nReturns the `.pyi` stub of the module.\0",
+    )
+}
+
+/// Finds the signature recorded for a built-in function by the C function implementing it.
+fn function_signature(function: &PyAny) -> Option<&'static str> {
+    let address = unsafe { ffi::PyCFunction_GetFunction(function.as_ptr()) }? as usize;
+    inventory::iter::<FunctionStub>
+        .into_iter()
+        .find(|stub| stub.function == address)
+        .map(|stub| stub.signature)
+}
+
+/// The `__text_signature__` of a function without a recorded signature, or a signature accepting
+/// any arguments.
+fn untyped_signature(function: &PyAny) -> PyResult<String> {
+    let signature = function.getattr("__text_signature__")?;
+    if signature.is_none() {
+        return Ok("(*args: Any, **kwargs: Any) -> Any".to_string());
+    }
+    let signature: &str = signature.extract()?;
+    let signature = signature
+        .replacen("($module, ", "(", 1)
+        .replacen("($module)", "()", 1);
+    Ok(format!("{} -> Any", signature))
+}
+
+fn write_class(stub: &mut String, module: &PyModule, name: &str, cls: &PyType) -> PyResult<()> {
+    let mut members: Vec<&MemberStub> = inventory::iter::<MemberStub>
+        .into_iter()
+        .filter(|member| ptr::eq((member.owner)(), unsafe { cls.as_type_ptr() }))
+        .collect();
+    // Class attributes first, then the constructor, and the getter of a property before its setter
+    // and deleter
+    members.sort_by_key(|member| {
+        (
+            member.kind != MemberKind::ClassAttribute,
+            member.name != "__init__",
+            member.name,
+            member.kind,
+        )
+    });
+
+    let base: &PyType = cls.getattr("__base__")?.downcast_ref()?;
+    if ptr::eq(unsafe { base.as_type_ptr() }, unsafe {
+        &ffi::PyBaseObject_Type
+    }) {
+        write!(stub, "class {}:", name).unwrap();
+    } else {
+        write!(stub, "class {}({}):", name, annotation(module, base)?).unwrap();
+    }
+    let doc = docstring(cls.as_ref())?;
+    if doc.is_none() && members.is_empty() {
+        stub.push_str(" ...\n");
+        return Ok(());
+    }
+    stub.push('\n');
+    if let Some(doc) = doc {
+        write_docstring(stub, "    ", &doc);
+    }
+
+    for member in members.iter() {
+        if member.kind == MemberKind::ClassAttribute {
+            writeln!(stub, "    {}: {}", member.name, member.signature).unwrap();
+            continue;
+        }
+        let doc = match (member.name, member.kind) {
+            // The docstring of the class describes the constructor
+            ("__init__", _) | (_, MemberKind::Setter) | (_, MemberKind::Deleter) => None,
+            (name, _) => docstring(cls.getattr(name)?)?,
+        };
+        match member.kind {
+            MemberKind::Method => {}
+            MemberKind::ClassMethod => stub.push_str("    @classmethod\n"),
+            MemberKind::StaticMethod => stub.push_str("    @staticmethod\n"),
+            MemberKind::Getter => stub.push_str("    @property\n"),
+            MemberKind::Setter | MemberKind::Deleter => {
+                let has_getter = members
+                    .iter()
+                    .any(|m| m.name == member.name && m.kind == MemberKind::Getter);
+                if has_getter {
+                    let decorator = if member.kind == MemberKind::Setter {
+                        "setter"
+                    } else {
+                        "deleter"
+                    };
+                    writeln!(stub, "    @{}.{}", member.name, decorator).unwrap();
+                } else {
+                    // A write-only property, which stubs can't express
+                    continue;
+                }
+            }
+            MemberKind::ClassAttribute => unreachable!(),
+        }
+        write_def(stub, "    ", member.name, member.signature, doc);
+    }
+    Ok(())
+}
+
+fn write_def(stub: &mut String, indent: &str, name: &str, signature: &str, doc: Option<String>) {
+    write!(stub, "{}def {}{}:", indent, name, signature).unwrap();
+    match doc {
+        Some(doc) => {
+            stub.push('\n');
+            write_docstring(stub, &format!("{}    ", indent), &doc);
+        }
+        None => stub.push_str(" ...\n"),
+    }
+}
+
+fn write_docstring(stub: &mut String, indent: &str, doc: &str) {
+    let doc = doc.replace('\\', "\\\\").replace("\"\"\"", "\\\"\\\"\\\"");
+    write!(stub, "{}\"\"\"", indent).unwrap();
+    for (i, line) in doc.lines().enumerate() {
+        if i > 0 {
+            stub.push('\n');
+            if !line.is_empty() {
+                stub.push_str(indent);
+            }
+        }
+        stub.push_str(line);
+    }
+    stub.push_str("\"\"\"\n");
+}
+
+/// The `__doc__` of `obj`, unless it is missing or empty.
+fn docstring(obj: &PyAny) -> PyResult<Option<String>> {
+    let doc = obj.getattr("__doc__")?;
+    if doc.is_none() {
+        return Ok(None);
+    }
+    let doc: String = doc.extract()?;
+    Ok(if doc.trim().is_empty() {
+        None
+    } else {
+        Some(doc)
+    })
+}
+
+/// The annotation for an instance of `ty`: its name for builtins and for classes of the module,
+/// and `Any` for other types, which would need to be imported.
+fn annotation(module: &PyModule, ty: &PyType) -> PyResult<String> {
+    let py = module.py();
+    if ptr::eq(unsafe { ty.as_type_ptr() }, unsafe {
+        ffi::Py_TYPE(py.None().as_ptr())
+    }) {
+        return Ok("None".to_string());
+    }
+    let name: &str = ty.getattr("__name__")?.extract()?;
+    let is_ty = |obj: PyResult<&PyAny>| obj.map_or(false, |obj| obj.as_ptr() == ty.as_ptr());
+    if is_ty(py.import("builtins")?.get(name)) || is_ty(module.get(name)) {
+        Ok(name.to_string())
+    } else {
+        Ok("Any".to_string())
+    }
+}
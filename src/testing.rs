@@ -0,0 +1,242 @@
+//! Helpers to test conversions between Rust and Python.
+//!
+//! [`roundtrip`] converts a value to a Python object and extracts it back. The functions in
+//! [`edge_cases`] return values that conversions commonly get wrong, and [`roundtrips`] can be
+//! used as a property with quickcheck or proptest:
+//!
+//! ```
+//! use pyo3::testing::{assert_roundtrip, edge_cases, roundtrips};
+//!
+//! # let gil = pyo3::Python::acquire_gil();
+//! # let py = gil.python();
+//! for value in edge_cases::integers::<i64>() {
+//!     assert_roundtrip(py, &value);
+//! }
+//! for value in edge_cases::strings() {
+//!     assert_roundtrip(py, &value);
+//! }
+//!
+//! // e.g. `quickcheck::quickcheck(roundtrips as fn(Vec<String>) -> bool)`
+//! assert!(roundtrips(vec!["a".to_string(), "€".to_string()]));
+//! ```
+//!
+//! [`roundtrip`]: fn.roundtrip.html
+//! [`edge_cases`]: edge_cases/index.html
+//! [`roundtrips`]: fn.roundtrips.html
+
+use crate::{FromPyObject, PyResult, Python, ToPyObject};
+use std::fmt::Debug;
+
+/// Converts `value` to a Python object with `ToPyObject` and extracts it back.
+pub fn roundtrip<T>(py: Python, value: &T) -> PyResult<T>
+where
+    T: ToPyObject + for<'a> FromPyObject<'a>,
+{
+    let obj = value.to_object(py);
+    obj.extract(py)
+}
+
+/// Checks that `value` is unchanged by a [`roundtrip`](fn.roundtrip.html), acquiring the GIL.
+///
+/// Because it takes the value by value and returns a `bool`, it can be passed to quickcheck
+/// or called from a proptest.
+pub fn roundtrips<T>(value: T) -> bool
+where
+    T: ToPyObject + for<'a> FromPyObject<'a> + PartialEq,
+{
+    let gil = Python::acquire_gil();
+    match roundtrip(gil.python(), &value) {
+        Ok(result) => result == value,
+        Err(_) => false,
+    }
+}
+
+/// Asserts that `value` is unchanged by a [`roundtrip`](fn.roundtrip.html).
+///
+/// Floating point values are compared with `==`, so use
+/// [`assert_roundtrip_with`](fn.assert_roundtrip_with.html) for NaN.
+pub fn assert_roundtrip<T>(py: Python, value: &T)
+where
+    T: ToPyObject + for<'a> FromPyObject<'a> + PartialEq + Debug,
+{
+    assert_roundtrip_with(py, value, |a, b| a == b)
+}
+
+/// Asserts that `value` is unchanged by a [`roundtrip`](fn.roundtrip.html), comparing with `eq`.
+pub fn assert_roundtrip_with<T>(py: Python, value: &T, eq: impl Fn(&T, &T) -> bool)
+where
+    T: ToPyObject + for<'a> FromPyObject<'a> + Debug,
+{
+    match roundtrip(py, value) {
+        Ok(result) => assert!(
+            eq(value, &result),
+            "{:?} became {:?} after converting to Python and back",
+            value,
+            result
+        ),
+        Err(e) => {
+            e.print(py);
+            panic!(
+                "{:?} could not be extracted after converting to Python",
+                value
+            );
+        }
+    }
+}
+
+/// Values that conversions commonly get wrong.
+pub mod edge_cases {
+    use crate::types::PyAny;
+    use crate::Python;
+    use num_traits::PrimInt;
+
+    /// The limits of `T`, the values next to them, and `-1`, `0` and `1` where they exist.
+    pub fn integers<T: PrimInt>() -> Vec<T> {
+        let (min, max, one) = (T::min_value(), T::max_value(), T::one());
+        let mut values = vec![min, min + one, T::zero(), one, max - one, max];
+        if let Some(minus_one) = T::zero().checked_sub(&one) {
+            values.push(minus_one);
+        }
+        values
+    }
+
+    /// Infinities, NaN, negative zero, subnormals and the limits of `f64`.
+    pub fn floats() -> Vec<f64> {
+        vec![
+            0.0,
+            -0.0,
+            1.0,
+            -1.5,
+            std::f64::EPSILON,
+            std::f64::MIN_POSITIVE,
+            std::f64::MIN_POSITIVE / 2.0,
+            std::f64::MIN,
+            std::f64::MAX,
+            std::f64::INFINITY,
+            std::f64::NEG_INFINITY,
+            std::f64::NAN,
+        ]
+    }
+
+    /// Empty strings, nul characters, characters of every UTF-8 width and a long string.
+    pub fn strings() -> Vec<String> {
+        vec![
+            String::new(),
+            "\0".to_string(),
+            "a\0b".to_string(),
+            "ascii".to_string(),
+            "é".to_string(),
+            "€".to_string(),
+            "𝄞".to_string(),
+            "\u{10FFFF}".to_string(),
+            "\u{FEFF}byte order mark".to_string(),
+            "e\u{301}".to_string(),
+            "mixed: aé€𝄞".to_string(),
+            "x".repeat(100_000),
+        ]
+    }
+
+    /// Python strings that can't be represented as a Rust `String`, because they contain lone
+    /// surrogates. Extracting them must fail with an error.
+    pub fn surrogate_strings(py: Python) -> Vec<&PyAny> {
+        ["'\\ud800'", "'\\udfff'", "'a\\ud83d'", "'\\udc00\\ud800'"]
+            .iter()
+            .map(|code| py.eval(code, None, None).unwrap())
+            .collect()
+    }
+
+    /// Python integers outside of the range of every Rust integer type.
+    pub fn huge_integers(py: Python) -> Vec<&PyAny> {
+        ["2 ** 128", "-(2 ** 127) - 1", "10 ** 100", "-(10 ** 100)"]
+            .iter()
+            .map(|code| py.eval(code, None, None).unwrap())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::edge_cases;
+    use super::{assert_roundtrip, assert_roundtrip_with, roundtrips};
+    use crate::{ObjectProtocol, Python};
+    use std::collections::{BTreeMap, HashMap};
+
+    macro_rules! test_integers {
+        ($($t:ty),*) => {
+            $(
+                for value in edge_cases::integers::<$t>() {
+                    assert_roundtrip(Python::acquire_gil().python(), &value);
+                }
+            )*
+        };
+    }
+
+    #[test]
+    fn integers_roundtrip() {
+        test_integers!(i8, u8, i16, u16, i32, u32, i64, u64, isize, usize);
+        #[cfg(not(Py_LIMITED_API))]
+        test_integers!(i128, u128);
+    }
+
+    #[test]
+    fn huge_integers_are_rejected() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        for value in edge_cases::huge_integers(py) {
+            assert!(value.extract::<i64>().is_err());
+            assert!(value.extract::<u64>().is_err());
+            #[cfg(not(Py_LIMITED_API))]
+            assert!(value.extract::<i128>().is_err());
+        }
+    }
+
+    #[test]
+    fn floats_roundtrip() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        for value in edge_cases::floats() {
+            assert_roundtrip_with(py, &value, |a, b| a.to_bits() == b.to_bits());
+            assert_roundtrip_with(py, &(value as f32), |a, b| a.to_bits() == b.to_bits());
+        }
+    }
+
+    #[test]
+    fn strings_roundtrip() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        for value in edge_cases::strings() {
+            assert_roundtrip(py, &value);
+        }
+        assert_roundtrip(py, &edge_cases::strings());
+    }
+
+    #[test]
+    fn surrogates_are_rejected() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        for value in edge_cases::surrogate_strings(py) {
+            assert!(value.extract::<String>().is_err());
+        }
+    }
+
+    #[test]
+    fn collections_roundtrip() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert_roundtrip(py, &Vec::<i32>::new());
+        assert_roundtrip(py, &vec![Some(1), None]);
+        assert_roundtrip(py, &(1, "two".to_string(), 3.0));
+        assert_roundtrip(py, &[(1, 2)].iter().cloned().collect::<HashMap<i32, i32>>());
+        assert_roundtrip(
+            py,
+            &[(1, 2)].iter().cloned().collect::<BTreeMap<i32, i32>>(),
+        );
+    }
+
+    #[test]
+    fn roundtrips_property() {
+        assert!(roundtrips(42u8));
+        assert!(roundtrips(vec!["a".to_string(), String::new()]));
+        assert!(!roundtrips(std::f64::NAN));
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Python's `threading` locks and events, used from Rust threads.
+//!
+//! Rust code coordinating with Python threads must not block while holding the GIL, or the Python
+//! thread it waits for can never run. [`PyLock`] and [`PyEvent`] call the methods of
+//! `threading.Lock`, `threading.RLock` and `threading.Event`, which release the GIL while they
+//! block, so they can be used both with and without the GIL held. They are `Send` and `Sync` and
+//! acquire the GIL for each call, like [`PyFileObject`]:
+//!
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::threading::PyEvent;
+//! use std::time::Duration;
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let event = PyEvent::new(py).unwrap();
+//! let locals = pyo3::types::PyDict::new(py);
+//! locals.set_item("event", event.as_object()).unwrap();
+//! py.run(
+//!     "import threading; threading.Timer(0.01, event.set).start()",
+//!     None,
+//!     Some(locals),
+//! )
+//! .unwrap();
+//! // The timer thread runs while this thread waits, even though the GIL is held here
+//! assert!(event.wait(Some(Duration::from_secs(10))).unwrap());
+//! ```
+//!
+//! Waiting can be interrupted by a signal, like `KeyboardInterrupt`, which is then returned as
+//! the error.
+//!
+//! [`PyLock`]: struct.PyLock.html
+//! [`PyEvent`]: struct.PyEvent.html
+//! [`PyFileObject`]: ../file/struct.PyFileObject.html
+
+use crate::err::PyResult;
+use crate::exceptions::TypeError;
+use crate::types::{PyAny, PyDict, PyTuple};
+use crate::{ffi, AsPyPointer, FromPyObject, IntoPy, ObjectProtocol, Py, PyObject, Python};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// Calls `obj.method(*args, timeout=timeout)`, passing no timeout for `None`.
+fn call_with_timeout(
+    py: Python,
+    obj: &PyObject,
+    method: &str,
+    args: impl IntoPy<Py<PyTuple>>,
+    timeout: Option<Duration>,
+) -> PyResult<bool> {
+    let kwargs = PyDict::new(py);
+    if let Some(timeout) = timeout {
+        kwargs.set_item("timeout", timeout.as_secs_f64())?;
+    }
+    obj.call_method(py, method, args, Some(kwargs))?.extract(py)
+}
+
+/// Raises a `TypeError` unless `obj` has all `methods`, so objects which are neither a lock nor
+/// an event aren't extracted as one.
+fn check_methods(obj: &PyAny, to: &str, methods: &[&str]) -> PyResult<()> {
+    for &method in methods {
+        if !obj.hasattr(method)? {
+            return Err(TypeError::py_err(format!(
+                "'{}' object cannot be converted to '{}', it has no '{}' method",
+                obj.get_type().name(),
+                to,
+                method
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A `threading.Lock` or `threading.RLock`, or any object with their `acquire` and `release`
+/// methods.
+#[derive(Debug)]
+pub struct PyLock {
+    lock: PyObject,
+}
+
+impl PyLock {
+    /// Creates a `threading.Lock`.
+    pub fn new(py: Python) -> PyResult<Self> {
+        Ok(PyLock::from_object(
+            py.lookup("threading.Lock")?.call0()?.into(),
+        ))
+    }
+
+    /// Creates a `threading.RLock`, which the thread holding it can acquire again.
+    pub fn new_reentrant(py: Python) -> PyResult<Self> {
+        Ok(PyLock::from_object(
+            py.lookup("threading.RLock")?.call0()?.into(),
+        ))
+    }
+
+    /// Wraps a lock created by Python code.
+    pub fn from_object(lock: PyObject) -> Self {
+        PyLock { lock }
+    }
+
+    /// The Python lock object, e.g. to share it with Python code.
+    pub fn as_object(&self) -> &PyObject {
+        &self.lock
+    }
+
+    /// Returns the Python lock object.
+    pub fn into_inner(self) -> PyObject {
+        self.lock
+    }
+
+    /// Blocks until the lock is acquired, with the GIL released while waiting.
+    pub fn acquire(&self) -> PyResult<PyLockGuard> {
+        self.acquire_with(true, None)
+            .map(|guard| guard.expect("a blocking acquire() without timeout failed"))
+    }
+
+    /// Acquires the lock if it isn't held, without blocking.
+    pub fn try_acquire(&self) -> PyResult<Option<PyLockGuard>> {
+        self.acquire_with(false, None)
+    }
+
+    /// Waits up to `timeout` for the lock, with the GIL released while waiting. Returns `None`
+    /// if the timeout expired.
+    pub fn acquire_timeout(&self, timeout: Duration) -> PyResult<Option<PyLockGuard>> {
+        self.acquire_with(true, Some(timeout))
+    }
+
+    fn acquire_with(
+        &self,
+        blocking: bool,
+        timeout: Option<Duration>,
+    ) -> PyResult<Option<PyLockGuard>> {
+        let gil = Python::acquire_gil();
+        let acquired =
+            call_with_timeout(gil.python(), &self.lock, "acquire", (blocking,), timeout)?;
+        Ok(if acquired {
+            Some(PyLockGuard {
+                lock: self,
+                not_send: PhantomData,
+            })
+        } else {
+            None
+        })
+    }
+}
+
+impl<'source> FromPyObject<'source> for PyLock {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        check_methods(obj, "PyLock", &["acquire", "release"])?;
+        Ok(PyLock::from_object(obj.into()))
+    }
+}
+
+impl AsPyPointer for PyLock {
+    fn as_ptr(&self) -> *mut ffi::PyObject {
+        self.lock.as_ptr()
+    }
+}
+
+/// Releases the lock acquired by [`PyLock::acquire`] when it's dropped.
+///
+/// It can't be sent to another thread, as an `RLock` must be released by the thread that
+/// acquired it. An error raised by `release()` is reported as unraisable.
+///
+/// [`PyLock::acquire`]: struct.PyLock.html#method.acquire
+#[derive(Debug)]
+pub struct PyLockGuard<'a> {
+    lock: &'a PyLock,
+    not_send: PhantomData<*const ()>,
+}
+
+impl Drop for PyLockGuard<'_> {
+    fn drop(&mut self) {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        if let Err(err) = self.lock.lock.call_method0(py, "release") {
+            err.restore(py);
+            unsafe { ffi::PyErr_WriteUnraisable(self.lock.as_ptr()) };
+        }
+    }
+}
+
+/// A `threading.Event`, or any object with its `set`, `clear`, `is_set` and `wait` methods.
+#[derive(Debug)]
+pub struct PyEvent {
+    event: PyObject,
+}
+
+impl PyEvent {
+    /// Creates a `threading.Event`.
+    pub fn new(py: Python) -> PyResult<Self> {
+        Ok(PyEvent::from_object(
+            py.lookup("threading.Event")?.call0()?.into(),
+        ))
+    }
+
+    /// Wraps an event created by Python code.
+    pub fn from_object(event: PyObject) -> Self {
+        PyEvent { event }
+    }
+
+    /// The Python event object, e.g. to share it with Python code.
+    pub fn as_object(&self) -> &PyObject {
+        &self.event
+    }
+
+    /// Returns the Python event object.
+    pub fn into_inner(self) -> PyObject {
+        self.event
+    }
+
+    /// Sets the flag, waking up the threads waiting for it.
+    pub fn set(&self) -> PyResult<()> {
+        let gil = Python::acquire_gil();
+        self.event.call_method0(gil.python(), "set")?;
+        Ok(())
+    }
+
+    /// Resets the flag.
+    pub fn clear(&self) -> PyResult<()> {
+        let gil = Python::acquire_gil();
+        self.event.call_method0(gil.python(), "clear")?;
+        Ok(())
+    }
+
+    /// Whether the flag is set.
+    pub fn is_set(&self) -> PyResult<bool> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        self.event.call_method0(py, "is_set")?.extract(py)
+    }
+
+    /// Blocks until the flag is set or the timeout expires, with the GIL released while waiting.
+    /// Returns whether the flag is set.
+    pub fn wait(&self, timeout: Option<Duration>) -> PyResult<bool> {
+        let gil = Python::acquire_gil();
+        call_with_timeout(gil.python(), &self.event, "wait", (), timeout)
+    }
+}
+
+impl<'source> FromPyObject<'source> for PyEvent {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        check_methods(obj, "PyEvent", &["set", "clear", "is_set", "wait"])?;
+        Ok(PyEvent::from_object(obj.into()))
+    }
+}
+
+impl AsPyPointer for PyEvent {
+    fn as_ptr(&self) -> *mut ffi::PyObject {
+        self.event.as_ptr()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PyEvent, PyLock};
+    use crate::exceptions::TypeError;
+    use crate::types::PyDict;
+    use crate::{ObjectProtocol, Python};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_lock_released_by_python_thread() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let lock = PyLock::new(py).unwrap();
+        let guard = lock.try_acquire().unwrap().unwrap();
+        assert!(lock.try_acquire().unwrap().is_none());
+        assert!(lock
+            .acquire_timeout(Duration::from_millis(10))
+            .unwrap()
+            .is_none());
+        std::mem::forget(guard);
+
+        // The Python thread can only release the lock if waiting releases the GIL
+        let locals = PyDict::new(py);
+        locals.set_item("lock", lock.as_object()).unwrap();
+        py.run(
+            "import threading; threading.Timer(0.01, lock.release).start()",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        let guard = lock.acquire().unwrap();
+        drop(guard);
+        assert!(lock.try_acquire().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_reentrant_lock() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let lock = PyLock::new_reentrant(py).unwrap();
+        let _outer = lock.acquire().unwrap();
+        let _inner = lock.acquire().unwrap();
+
+        // Rust threads don't hold the GIL while they wait
+        let other = PyLock::from_object(lock.as_object().clone_ref(py));
+        let waiter = thread::spawn(move || {
+            other
+                .acquire_timeout(Duration::from_millis(10))
+                .unwrap()
+                .is_none()
+        });
+        let timed_out = py.allow_threads(move || waiter.join().unwrap());
+        assert!(timed_out);
+    }
+
+    #[test]
+    fn test_event() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let event = PyEvent::new(py).unwrap();
+        assert!(!event.is_set().unwrap());
+        assert!(!event.wait(Some(Duration::from_millis(10))).unwrap());
+
+        let locals = PyDict::new(py);
+        locals.set_item("event", event.as_object()).unwrap();
+        py.run(
+            "import threading; threading.Timer(0.01, event.set).start()",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        assert!(event.wait(None).unwrap());
+        assert!(event.is_set().unwrap());
+        event.clear().unwrap();
+        assert!(!event.is_set().unwrap());
+    }
+
+    #[test]
+    fn test_extract() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let lock = py
+            .eval("__import__('threading').RLock()", None, None)
+            .unwrap();
+        let event = py
+            .eval("__import__('threading').Event()", None, None)
+            .unwrap();
+        assert!(lock.extract::<PyLock>().is_ok());
+        assert!(event.extract::<PyEvent>().is_ok());
+
+        let err = lock.extract::<PyEvent>().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        let err = event.extract::<PyLock>().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        let err = py.None().extract::<PyLock>(py).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+}
@@ -6,9 +6,10 @@ use crate::pyclass::{initialize_type_object, PyClass};
 use crate::pyclass_init::PyObjectInit;
 use crate::types::{PyAny, PyType};
 use crate::{ffi, AsPyPointer, Python};
+use parking_lot::{const_mutex, Condvar, Mutex};
 use std::cell::UnsafeCell;
 use std::ptr::NonNull;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// `T: PyObjectLayout<U>` represents that `T` is a concrete representaion of `U` in Python heap.
 /// E.g., `PyClassShell` is a concrete representaion of all `pyclass`es, and `ffi::PyObject`
@@ -22,13 +23,8 @@ pub trait PyObjectLayout<T: PyTypeInfo> {
         None
     }
 
-    unsafe fn internal_ref_cast(obj: &PyAny) -> &T {
-        &*(obj as *const _ as *const T)
-    }
-
-    #[allow(clippy::mut_from_ref)]
-    unsafe fn internal_mut_cast(obj: &PyAny) -> &mut T {
-        &mut *(obj as *const _ as *const T as *mut T)
+    unsafe fn internal_ref_cast(obj: &PyAny) -> &T::AsRefTarget {
+        &*(obj as *const _ as *const T::AsRefTarget)
     }
 
     unsafe fn py_init(&mut self, _value: T) {}
@@ -92,6 +88,10 @@ pub unsafe trait PyTypeInfo: Sized {
     /// Initializer for layout
     type Initializer: PyObjectInit<Self>;
 
+    /// The type a reference to an object of this type points to: the type itself for native
+    /// types, and `PyClassShell<Self>` for `#[pyclass]`es, whose contents are borrowed from it.
+    type AsRefTarget;
+
     /// PyTypeObject instance for this type.
     fn type_object() -> &'static ffi::PyTypeObject;
 
@@ -128,18 +128,205 @@ where
     }
 }
 
+/// A tuple of types implementing `PyTypeInfo`, like the tuple of types accepted by Python's
+/// `isinstance`. Implemented for tuples of up to 8 types.
+pub trait PyTypeInfoTuple {
+    /// Check if `object` is an instance of any of the types
+    fn is_instance_of_any(object: &PyAny) -> bool;
+}
+
+macro_rules! type_info_tuple {
+    ($($T:ident),+) => {
+        impl<$($T: PyTypeInfo),+> PyTypeInfoTuple for ($($T,)+) {
+            fn is_instance_of_any(object: &PyAny) -> bool {
+                $($T::is_instance(object))||+
+            }
+        }
+    };
+}
+
+type_info_tuple!(A);
+type_info_tuple!(A, B);
+type_info_tuple!(A, B, C);
+type_info_tuple!(A, B, C, D);
+type_info_tuple!(A, B, C, D, E);
+type_info_tuple!(A, B, C, D, E, F);
+type_info_tuple!(A, B, C, D, E, F, G);
+type_info_tuple!(A, B, C, D, E, F, G, H);
+
+/// The initialization state of a lazily created type object.
+///
+/// Type objects are created with the GIL held, but creating them can run Python code, which may
+/// release the GIL. A second thread must then neither see the half initialized type object nor
+/// block while holding the GIL, so it waits with the GIL released until the first one is done.
+struct LazyInit {
+    state: AtomicUsize,
+    lock: Mutex<()>,
+    done: Condvar,
+}
+
+impl LazyInit {
+    /// No thread started the initialization yet
+    const UNINITIALIZED: usize = 0;
+    /// The initialization is complete. Other values are the id of the initializing thread.
+    const DONE: usize = usize::MAX;
+
+    const fn new() -> Self {
+        LazyInit {
+            state: AtomicUsize::new(Self::UNINITIALIZED),
+            lock: const_mutex(()),
+            done: Condvar::new(),
+        }
+    }
+
+    /// Runs `init` once. Returns immediately when called again from `init`, e.g. because a class
+    /// refers to itself, in which case the type object is not fully initialized yet.
+    fn call_once(&self, init: impl FnOnce(Python)) {
+        let thread = current_thread_id();
+        loop {
+            match self.state.load(Ordering::Acquire) {
+                Self::DONE => return,
+                Self::UNINITIALIZED => {
+                    if self
+                        .state
+                        .compare_exchange(
+                            Self::UNINITIALIZED,
+                            thread,
+                            Ordering::Acquire,
+                            Ordering::Relaxed,
+                        )
+                        .is_ok()
+                    {
+                        // Let other threads retry if `init` panics
+                        struct Reset<'a>(&'a LazyInit);
+                        impl Drop for Reset<'_> {
+                            fn drop(&mut self) {
+                                self.0.finish(LazyInit::UNINITIALIZED);
+                            }
+                        }
+                        let reset = Reset(self);
+                        let gil = Python::acquire_gil();
+                        init(gil.python());
+                        std::mem::forget(reset);
+                        self.finish(Self::DONE);
+                        return;
+                    }
+                }
+                owner if owner == thread => return,
+                _ => {
+                    let gil = Python::acquire_gil();
+                    gil.python().allow_threads(|| {
+                        let mut guard = self.lock.lock();
+                        loop {
+                            match self.state.load(Ordering::Acquire) {
+                                Self::DONE | Self::UNINITIALIZED => break,
+                                _ => self.done.wait(&mut guard),
+                            }
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    /// Sets the state at the end of the initialization and wakes up the waiting threads.
+    fn finish(&self, state: usize) {
+        // Waiting threads check the state with the lock held, so they can't miss the wakeup
+        let _guard = self.lock.lock();
+        self.state.store(state, Ordering::Release);
+        self.done.notify_all();
+    }
+}
+
+/// Returns an id of the current thread, which is never `LazyInit::UNINITIALIZED` or
+/// `LazyInit::DONE`.
+fn current_thread_id() -> usize {
+    thread_local!(static ID: u8 = 0);
+    ID.with(|id| id as *const u8 as usize)
+}
+
+/// A value which exists once per interpreter, like the type objects, which belong to the
+/// interpreter which created them.
+///
+/// The value of the main interpreter is stored inline. The values of subinterpreters are created
+/// when they first use it, and are kept until the end of the process.
+struct PerInterpreter<T> {
+    main: T,
+    others: Mutex<Vec<(i64, Box<T>)>>,
+}
+
+impl<T> PerInterpreter<T> {
+    const fn new(main: T) -> Self {
+        PerInterpreter {
+            main,
+            others: const_mutex(Vec::new()),
+        }
+    }
+
+    /// Gets the value of the current interpreter, creating it with `new` if needed.
+    fn get(&self, new: impl FnOnce() -> T) -> &T {
+        let id = interpreter_id();
+        if id == 0 {
+            return &self.main;
+        }
+        let mut others = self.others.lock();
+        let value: *const T = match others.iter().find(|(other, _)| *other == id) {
+            Some((_, value)) => &**value,
+            None => {
+                others.push((id, Box::new(new())));
+                &*others[others.len() - 1].1
+            }
+        };
+        // The values are boxed and never removed, so they live as long as `self`
+        unsafe { &*value }
+    }
+}
+
+/// Returns the id of the interpreter of the current thread, which is 0 for the main interpreter
+/// and for threads without a thread state, as acquiring the GIL uses the main interpreter.
+fn interpreter_id() -> i64 {
+    #[cfg(all(Py_3_7, not(Py_LIMITED_API), not(PyPy)))]
+    unsafe {
+        let state = ffi::_PyThreadState_UncheckedGet();
+        if state.is_null() {
+            return 0;
+        }
+        #[cfg(Py_3_9)]
+        let interpreter = ffi::PyThreadState_GetInterpreter(state);
+        #[cfg(not(Py_3_9))]
+        let interpreter = (*state).interp;
+        ffi::PyInterpreterState_GetID(interpreter)
+    }
+    // Without interpreter ids, all interpreters share the values of the main interpreter
+    #[cfg(not(all(Py_3_7, not(Py_LIMITED_API), not(PyPy))))]
+    0
+}
+
+/// The type object of an exception in one interpreter
+struct HeapType {
+    value: UnsafeCell<Option<NonNull<ffi::PyTypeObject>>>,
+    init: LazyInit,
+}
+
+impl HeapType {
+    const fn new() -> Self {
+        HeapType {
+            value: UnsafeCell::new(None),
+            init: LazyInit::new(),
+        }
+    }
+}
+
 /// Lazy type object for Exceptions
 #[doc(hidden)]
 pub struct LazyHeapType {
-    value: UnsafeCell<Option<NonNull<ffi::PyTypeObject>>>,
-    initialized: AtomicBool,
+    types: PerInterpreter<HeapType>,
 }
 
 impl LazyHeapType {
     pub const fn new() -> Self {
         LazyHeapType {
-            value: UnsafeCell::new(None),
-            initialized: AtomicBool::new(false),
+            types: PerInterpreter::new(HeapType::new()),
         }
     }
 
@@ -147,17 +334,14 @@ impl LazyHeapType {
     where
         F: Fn(Python) -> NonNull<ffi::PyTypeObject>,
     {
-        if !self
-            .initialized
-            .compare_and_swap(false, true, Ordering::Acquire)
-        {
-            // We have to get the GIL before setting the value to the global!!!
-            let gil = Python::acquire_gil();
+        let ty = self.types.get(HeapType::new);
+        ty.init.call_once(|py| {
+            let value = constructor(py);
             unsafe {
-                *self.value.get() = Some(constructor(gil.python()));
+                *ty.value.get() = Some(value);
             }
-        }
-        unsafe { (*self.value.get()).unwrap() }
+        });
+        unsafe { (*ty.value.get()).expect("Exception type is used while it is created") }
     }
 }
 
@@ -167,37 +351,72 @@ impl LazyHeapType {
 // to allow sharing on the Rust side too.
 unsafe impl Sync for LazyHeapType {}
 
+/// The type object of a `#[pyclass]` in one interpreter
+struct StaticType {
+    value: UnsafeCell<ffi::PyTypeObject>,
+    init: LazyInit,
+}
+
+impl StaticType {
+    const fn new() -> Self {
+        StaticType {
+            value: UnsafeCell::new(ffi::PyTypeObject_INIT),
+            init: LazyInit::new(),
+        }
+    }
+}
+
 /// Lazy type object for PyClass
 #[doc(hidden)]
 pub struct LazyStaticType {
-    value: UnsafeCell<ffi::PyTypeObject>,
-    initialized: AtomicBool,
+    types: PerInterpreter<StaticType>,
 }
 
 impl LazyStaticType {
     pub const fn new() -> Self {
         LazyStaticType {
-            value: UnsafeCell::new(ffi::PyTypeObject_INIT),
-            initialized: AtomicBool::new(false),
+            types: PerInterpreter::new(StaticType::new()),
         }
     }
 
     pub fn get_or_init<T: PyClass>(&self) -> &ffi::PyTypeObject {
-        if !self
-            .initialized
-            .compare_and_swap(false, true, Ordering::Acquire)
-        {
-            let gil = Python::acquire_gil();
-            let py = gil.python();
-            initialize_type_object::<T>(py, T::MODULE, unsafe { &mut *self.value.get() })
+        let ty = self.types.get(StaticType::new);
+        ty.init.call_once(|py| {
+            initialize_type_object::<T>(py, T::MODULE, unsafe { &mut *ty.value.get() })
                 .unwrap_or_else(|e| {
                     e.print(py);
                     panic!("An error occurred while initializing class {}", T::NAME)
                 });
-        }
-        unsafe { &*self.value.get() }
+        });
+        unsafe { &*ty.value.get() }
     }
 }
 
 // This is necessary for making static `LazyStaticType`s
 unsafe impl Sync for LazyStaticType {}
+
+#[cfg(test)]
+mod test {
+    use super::PerInterpreter;
+    use crate::{ffi, Python};
+
+    #[test]
+    #[cfg(all(Py_3_7, not(Py_LIMITED_API), not(PyPy)))]
+    fn per_interpreter() {
+        let gil = Python::acquire_gil();
+        let _py = gil.python();
+        let values = PerInterpreter::new(0);
+        assert_eq!(*values.get(|| 1), 0);
+
+        unsafe {
+            let main = ffi::PyThreadState_Get();
+            let sub = ffi::Py_NewInterpreter();
+            assert!(!sub.is_null());
+            assert_eq!(*values.get(|| 1), 1);
+            assert_eq!(*values.get(|| 2), 1);
+            ffi::Py_EndInterpreter(sub);
+            ffi::PyThreadState_Swap(main);
+        }
+        assert_eq!(*values.get(|| 1), 0);
+    }
+}
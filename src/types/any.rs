@@ -1,7 +1,8 @@
 use crate::conversion::PyTryFrom;
 use crate::err::PyDowncastError;
 use crate::internal_tricks::Unsendable;
-use crate::{ffi, PyObject};
+use crate::type_object::{PyTypeInfo, PyTypeInfoTuple};
+use crate::{ffi, PyObject, PyResult};
 
 /// Represents a python's [Any](https://docs.python.org/3/library/typing.html#typing.Any) type.
 /// We can convert all python objects as `PyAny`.
@@ -36,17 +37,54 @@ pyobject_native_type_convert!(
 pyobject_native_type_extract!(PyAny);
 
 impl PyAny {
-    pub fn downcast_ref<T>(&self) -> Result<&T, PyDowncastError>
+    /// Check whether `self` is an instance of type `T` like Python `isinstance` function.
+    ///
+    /// Unlike [Python::is_instance](../struct.Python.html#method.is_instance), this uses the check
+    /// function of the type, e.g. `PyList_Check`, which mostly just tests a flag, and can't fail.
+    pub fn is_instance<T: PyTypeInfo>(&self) -> bool {
+        T::is_instance(self)
+    }
+
+    /// Check whether `self` is an instance of any of the types in the tuple `T` like Python
+    /// `isinstance` function, e.g. `obj.is_instance_of_any::<(PyList, PyTuple)>()`.
+    pub fn is_instance_of_any<T: PyTypeInfoTuple>(&self) -> bool {
+        T::is_instance_of_any(self)
+    }
+
+    /// Converts `self` to a concrete Python object type, e.g. `obj.downcast::<PyDict>()`.
+    ///
+    /// The error names the expected type and the type of `self`, and converts to a `TypeError`:
+    ///
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::{PyAny, PyDict, PyList};
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let obj: &PyAny = PyDict::new(py).as_ref();
+    /// assert!(obj.downcast::<PyDict>().is_ok());
+    ///
+    /// let err = obj.downcast::<PyList>().unwrap_err();
+    /// assert_eq!(err.to_string(), "'dict' object cannot be converted to 'PyList'");
+    /// ```
+    pub fn downcast<T>(&self) -> Result<&T, PyDowncastError>
     where
         T: for<'gil> PyTryFrom<'gil>,
     {
         T::try_from(self)
     }
 
-    pub fn downcast_mut<T>(&self) -> Result<&mut T, PyDowncastError>
+    /// Extracts a `T` with the function registered for the type of `self` in the
+    /// [extract_registry](../extract_registry/index.html), e.g. from an object of a third-party
+    /// library. Raises `TypeError` if no function is registered.
+    pub fn extract_registered<T: 'static>(&self) -> PyResult<T> {
+        crate::extract_registry::extract_registered(self)
+    }
+
+    pub fn downcast_ref<T>(&self) -> Result<&T, PyDowncastError>
     where
         T: for<'gil> PyTryFrom<'gil>,
     {
-        T::try_from_mut(self)
+        T::try_from(self)
     }
 }
@@ -49,7 +49,7 @@ impl ToPyObject for bool {
 impl FromPy<bool> for PyObject {
     #[inline]
     fn from_py(other: bool, py: Python) -> Self {
-        PyBool::new(py, other).into()
+        other.to_object(py)
     }
 }
 
@@ -88,4 +88,15 @@ mod test {
         assert_eq!(false, t.extract().unwrap());
         assert_eq!(false.to_object(py), PyBool::new(py, false).into());
     }
+
+    #[test]
+    fn test_into_py_is_singleton() {
+        use crate::{AsPyPointer, IntoPy, PyObject};
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let t: PyObject = true.into_py(py);
+        let f: PyObject = false.into_py(py);
+        assert_eq!(t.as_ptr(), unsafe { crate::ffi::Py_True() });
+        assert_eq!(f.as_ptr(), unsafe { crate::ffi::Py_False() });
+    }
 }
@@ -1,9 +1,10 @@
 use crate::conversion::FromPyObject;
 use crate::conversion::{PyTryFrom, ToPyObject};
 use crate::err::PyResult;
+use crate::instance::PyNativeType;
 use crate::internal_tricks::Unsendable;
 use crate::object::PyObject;
-use crate::types::PyAny;
+use crate::types::{PyAny, PyString};
 use crate::AsPyPointer;
 use crate::Python;
 use crate::{ffi, FromPy};
@@ -41,6 +42,16 @@ impl PyBytes {
         ))
     }
 
+    /// Decodes the bytes with the codec `encoding`. Equivalent to python
+    /// `b.decode(encoding, errors)`.
+    ///
+    /// `errors` is the name of the error handler for data that can't be decoded, e.g.
+    /// `"strict"`, `"replace"` or `"surrogateescape"`, see
+    /// [PyString::from_encoded](struct.PyString.html#method.from_encoded).
+    pub fn decode(&self, encoding: &str, errors: &str) -> PyResult<&PyString> {
+        PyString::from_encoded(self.py(), self.as_bytes(), encoding, errors)
+    }
+
     /// Get the Python string as a byte slice.
     #[inline]
     pub fn as_bytes(&self) -> &[u8] {
@@ -76,6 +87,7 @@ impl<'a> FromPyObject<'a> for &'a [u8] {
 #[cfg(test)]
 mod test {
     use super::PyBytes;
+    use crate::exceptions::UnicodeDecodeError;
     use crate::FromPyObject;
     use crate::Python;
 
@@ -96,4 +108,20 @@ mod test {
         let bytes = PyBytes::new(py, b"Hello World");
         assert_eq!(bytes[1], b'e');
     }
+
+    #[test]
+    fn test_decode() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let bytes = PyBytes::new(py, b"\xff\xfeh\x00i\x00");
+        let s = bytes.decode("utf-16", "strict").unwrap();
+        assert_eq!(s.to_string().unwrap(), "hi");
+
+        let bytes = PyBytes::new(py, b"a\xffb");
+        let err = bytes.decode("utf-8", "strict").unwrap_err();
+        assert!(err.is_instance::<UnicodeDecodeError>(py));
+        let s = bytes.decode("utf-8", "replace").unwrap();
+        assert_eq!(s.to_string().unwrap(), "a\u{fffd}b");
+        assert!(bytes.decode("no-such-codec", "strict").is_err());
+    }
 }
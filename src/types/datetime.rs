@@ -6,6 +6,7 @@
 #![allow(clippy::too_many_arguments)]
 
 use crate::err::PyResult;
+use crate::exceptions::ValueError;
 use crate::ffi;
 #[cfg(PyPy)]
 use crate::ffi::datetime::{PyDateTime_FromTimestamp, PyDate_FromTimestamp};
@@ -27,13 +28,15 @@ use crate::ffi::{
 };
 use crate::internal_tricks::Unsendable;
 use crate::object::PyObject;
-use crate::types::PyTuple;
+use crate::types::{PyAny, PyTuple};
 use crate::AsPyPointer;
 use crate::Python;
 use crate::ToPyObject;
+use crate::{FromPyObject, ObjectProtocol};
 use std::os::raw::c_int;
 #[cfg(not(PyPy))]
 use std::ptr;
+use std::time::Duration;
 
 /// Access traits
 
@@ -141,7 +144,7 @@ impl PyDateTime {
         minute: u8,
         second: u8,
         microsecond: u32,
-        tzinfo: Option<&PyObject>,
+        tzinfo: Option<&PyTzInfo>,
     ) -> PyResult<&'p PyDateTime> {
         unsafe {
             let ptr = (PyDateTimeAPI.DateTime_FromDateAndTime)(
@@ -248,7 +251,7 @@ impl PyTime {
         minute: u8,
         second: u8,
         microsecond: u32,
-        tzinfo: Option<&PyObject>,
+        tzinfo: Option<&PyTzInfo>,
     ) -> PyResult<&'p PyTime> {
         unsafe {
             let ptr = (PyDateTimeAPI.Time_FromTime)(
@@ -273,7 +276,7 @@ impl PyTime {
         minute: u8,
         second: u8,
         microsecond: u32,
-        tzinfo: Option<&PyObject>,
+        tzinfo: Option<&PyTzInfo>,
         fold: bool,
     ) -> PyResult<&'p PyTime> {
         unsafe {
@@ -326,6 +329,48 @@ pyobject_native_type!(
     PyTZInfo_Check
 );
 
+/// Returns `datetime.timezone.utc`.
+pub fn timezone_utc<'p>(py: Python<'p>) -> PyResult<&'p PyTzInfo> {
+    #[cfg(all(Py_3_7, not(PyPy)))]
+    unsafe {
+        Ok(py.from_borrowed_ptr(PyDateTimeAPI.TimeZone_UTC))
+    }
+
+    #[cfg(not(all(Py_3_7, not(PyPy))))]
+    {
+        Ok(py.lookup("datetime.timezone")?.getattr("utc")?.downcast()?)
+    }
+}
+
+/// Returns a `datetime.timezone` with a fixed offset from UTC of `seconds`, which must be less
+/// than a day in either direction.
+///
+/// This is equivalent to `datetime.timezone(datetime.timedelta(seconds=seconds))`.
+pub fn fixed_offset<'p>(py: Python<'p>, seconds: i32) -> PyResult<&'p PyTzInfo> {
+    let offset = PyDelta::new(py, 0, seconds, 0, true)?;
+
+    #[cfg(all(Py_3_7, not(PyPy)))]
+    unsafe {
+        let ptr = (PyDateTimeAPI.TimeZone_FromTimeZone)(offset.as_ptr(), ptr::null_mut());
+        py.from_owned_ptr_or_err(ptr)
+    }
+
+    #[cfg(not(all(Py_3_7, not(PyPy))))]
+    {
+        let timezone = py.lookup("datetime.timezone")?.call1((offset,))?;
+        Ok(timezone.downcast()?)
+    }
+}
+
+/// Returns the time zone of the IANA time zone database named `key`, e.g. `Europe/Paris`.
+///
+/// This is equivalent to `zoneinfo.ZoneInfo(key)`. The `zoneinfo` module was added in Python
+/// 3.9, so this returns an `ImportError` with older versions.
+pub fn zoneinfo<'p>(py: Python<'p>, key: &str) -> PyResult<&'p PyTzInfo> {
+    let zoneinfo = py.import("zoneinfo")?.call1("ZoneInfo", (key,))?;
+    Ok(zoneinfo.downcast()?)
+}
+
 /// Bindings for `datetime.timedelta`
 pub struct PyDelta(PyObject, Unsendable);
 pyobject_native_type!(
@@ -371,8 +416,94 @@ impl PyDeltaAccess for PyDelta {
     }
 }
 
+/// Converts a `timedelta` to a `Duration`, raising a `ValueError` if it is negative.
+///
+/// Use [`SaturatingDuration`] or [`AbsDuration`] to map negative timedeltas to a duration
+/// instead.
+///
+/// [`SaturatingDuration`]: struct.SaturatingDuration.html
+/// [`AbsDuration`]: struct.AbsDuration.html
+impl<'source> FromPyObject<'source> for Duration {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let micros = delta_microseconds(obj)?;
+        if micros < 0 {
+            return Err(ValueError::py_err(format!(
+                "cannot convert negative timedelta {} to Duration",
+                obj.str()?
+            )));
+        }
+        Ok(duration_from_microseconds(micros))
+    }
+}
+
+/// A `Duration` extracted from a `timedelta`, with negative timedeltas clamped to zero.
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::SaturatingDuration;
+/// use std::time::Duration;
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let delta = py.eval("__import__('datetime').timedelta(seconds=-5)", None, None).unwrap();
+/// let SaturatingDuration(duration) = delta.extract().unwrap();
+/// assert_eq!(duration, Duration::from_secs(0));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SaturatingDuration(pub Duration);
+
+impl<'source> FromPyObject<'source> for SaturatingDuration {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let micros = delta_microseconds(obj)?;
+        Ok(SaturatingDuration(duration_from_microseconds(
+            micros.max(0),
+        )))
+    }
+}
+
+/// A `Duration` extracted from a `timedelta`, with the absolute value of negative timedeltas.
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::AbsDuration;
+/// use std::time::Duration;
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let delta = py.eval("__import__('datetime').timedelta(seconds=-5)", None, None).unwrap();
+/// let AbsDuration(duration) = delta.extract().unwrap();
+/// assert_eq!(duration, Duration::from_secs(5));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AbsDuration(pub Duration);
+
+impl<'source> FromPyObject<'source> for AbsDuration {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let micros = delta_microseconds(obj)?;
+        Ok(AbsDuration(duration_from_microseconds(micros.abs())))
+    }
+}
+
+/// The total number of microseconds of a `timedelta`, which doesn't fit an `i64` for the
+/// largest ones.
+fn delta_microseconds(obj: &PyAny) -> PyResult<i128> {
+    let delta: &PyDelta = obj.downcast()?;
+    Ok(
+        (i128::from(delta.get_days()) * 86_400 + i128::from(delta.get_seconds())) * 1_000_000
+            + i128::from(delta.get_microseconds()),
+    )
+}
+
+fn duration_from_microseconds(micros: i128) -> Duration {
+    debug_assert!(micros >= 0);
+    Duration::new(
+        (micros / 1_000_000) as u64,
+        (micros % 1_000_000) as u32 * 1000,
+    )
+}
+
 // Utility function
-unsafe fn opt_to_pyobj(py: Python, opt: Option<&PyObject>) -> *mut ffi::PyObject {
+unsafe fn opt_to_pyobj(py: Python, opt: Option<&PyTzInfo>) -> *mut ffi::PyObject {
     // Convenience function for unpacking Options to either an Object or None
     match opt {
         Some(tzi) => tzi.as_ptr(),
@@ -32,6 +32,38 @@ impl PyDict {
         unsafe { py.from_owned_ptr::<PyDict>(ffi::PyDict_New()) }
     }
 
+    /// Creates a new empty dictionary with room for `capacity` items, so that inserting them
+    /// doesn't resize it.
+    pub fn with_capacity(py: Python, capacity: usize) -> &PyDict {
+        #[cfg(all(not(Py_LIMITED_API), not(PyPy)))]
+        unsafe {
+            py.from_owned_ptr::<PyDict>(ffi::_PyDict_NewPresized(capacity as ffi::Py_ssize_t))
+        }
+        #[cfg(any(Py_LIMITED_API, PyPy))]
+        {
+            let _ = capacity;
+            PyDict::new(py)
+        }
+    }
+
+    /// Creates a new dictionary from key-value pairs, e.g. the items of a `HashMap`, sized for
+    /// the number of pairs the iterator reports.
+    ///
+    /// Returns an error if a key can't be inserted, e.g. because it isn't hashable. In the case
+    /// of key collisions, this keeps the last entry seen.
+    pub fn from_pairs<I>(py: Python, pairs: I) -> PyResult<&PyDict>
+    where
+        I: IntoIterator,
+        I::Item: PyDictItem,
+    {
+        let pairs = pairs.into_iter();
+        let dict = PyDict::with_capacity(py, pairs.size_hint().0);
+        for item in pairs {
+            dict.set_item(item.key(), item.value())?;
+        }
+        Ok(dict)
+    }
+
     /// Creates a new dictionary from the sequence given.
     ///
     /// The sequence must consist of `(PyObject, PyObject)`. This is
@@ -60,6 +92,25 @@ impl PyDict {
         }
     }
 
+    /// Inserts the key-value pairs of a Rust map, replacing existing keys. This is equivalent to
+    /// `self.update(map)` in Python.
+    ///
+    /// The pairs are collected in a dictionary of the right size first, so `self` is resized at
+    /// most once, however many pairs are inserted.
+    pub fn merge_from_map<I>(&self, map: I) -> PyResult<()>
+    where
+        I: IntoIterator,
+        I::Item: PyDictItem,
+    {
+        let other = PyDict::from_pairs(self.py(), map)?;
+        unsafe {
+            err::error_on_minusone(
+                self.py(),
+                ffi::PyDict_Merge(self.as_ptr(), other.as_ptr(), 1),
+            )
+        }
+    }
+
     /// Empty an existing dictionary of all key-value pairs.
     pub fn clear(&self) {
         unsafe { ffi::PyDict_Clear(self.as_ptr()) }
@@ -157,18 +208,30 @@ impl PyDict {
 
     /// Returns a iterator of (key, value) pairs in this dictionary.
     ///
-    /// Note that it's unsafe to use when the dictionary might be changed by other code.
+    /// The pairs are read with `PyDict_Next`, without copying the dictionary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dictionary changes its size during the iteration, where Python raises a
+    /// `RuntimeError`, as `PyDict_Next` could skip or repeat items. Changing the values of
+    /// existing keys is allowed.
     pub fn iter(&self) -> PyDictIterator {
+        let len = self.len();
         PyDictIterator {
-            dict: self.as_ref(),
+            dict: self,
             pos: 0,
+            len,
+            remaining: len,
         }
     }
 }
 
+/// Used by `PyDict::iter()`.
 pub struct PyDictIterator<'py> {
-    dict: &'py PyAny,
+    dict: &'py PyDict,
     pos: isize,
+    len: usize,
+    remaining: usize,
 }
 
 impl<'py> Iterator for PyDictIterator<'py> {
@@ -176,10 +239,16 @@ impl<'py> Iterator for PyDictIterator<'py> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        if self.dict.len() != self.len {
+            panic!("dictionary changed size during iteration");
+        }
         unsafe {
             let mut key: *mut ffi::PyObject = std::ptr::null_mut();
             let mut value: *mut ffi::PyObject = std::ptr::null_mut();
-            if ffi::PyDict_Next(self.dict.as_ptr(), &mut self.pos, &mut key, &mut value) != 0 {
+            if self.remaining > 0
+                && ffi::PyDict_Next(self.dict.as_ptr(), &mut self.pos, &mut key, &mut value) != 0
+            {
+                self.remaining -= 1;
                 let py = self.dict.py();
                 Some((py.from_borrowed_ptr(key), py.from_borrowed_ptr(value)))
             } else {
@@ -187,6 +256,11 @@ impl<'py> Iterator for PyDictIterator<'py> {
             }
         }
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
 impl<'a> std::iter::IntoIterator for &'a PyDict {
@@ -260,12 +334,7 @@ where
     I: IntoIterator<Item = T>,
 {
     fn into_py_dict(self, py: Python) -> &PyDict {
-        let dict = PyDict::new(py);
-        for item in self {
-            dict.set_item(item.key(), item.value())
-                .expect("Failed to set_item on dict");
-        }
-        dict
+        PyDict::from_pairs(py, self).expect("Failed to set_item on dict")
     }
 }
 
@@ -362,6 +431,54 @@ mod test {
         assert_eq!(map, dict.extract().unwrap());
     }
 
+    #[test]
+    fn test_with_capacity() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = PyDict::with_capacity(py, 1000);
+        assert!(dict.is_empty());
+        dict.set_item("a", 1).unwrap();
+        assert_eq!(1, dict.get_item("a").unwrap().extract::<i32>().unwrap());
+    }
+
+    #[test]
+    fn test_from_pairs() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let map: HashMap<i32, i32> = (0..1000).map(|i| (i, i * i)).collect();
+        let dict = PyDict::from_pairs(py, &map).unwrap();
+        assert_eq!(map, dict.extract().unwrap());
+
+        // The last entry wins
+        let dict = PyDict::from_pairs(py, vec![("a", 1), ("b", 2), ("a", 3)]).unwrap();
+        assert_eq!(2, dict.len());
+        assert_eq!(3, dict.get_item("a").unwrap().extract::<i32>().unwrap());
+
+        let unhashable = PyList::empty(py).to_object(py);
+        let err = PyDict::from_pairs(py, vec![(unhashable, 1)]).unwrap_err();
+        assert!(err.is_instance::<crate::exceptions::TypeError>(py));
+    }
+
+    #[test]
+    fn test_merge_from_map() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = [("a", 1), ("b", 2)].into_py_dict(py);
+        let mut map = BTreeMap::new();
+        map.insert("b", 20);
+        map.insert("c", 30);
+        dict.merge_from_map(&map).unwrap();
+        let merged: BTreeMap<&str, i32> = dict.extract().unwrap();
+        assert_eq!(
+            merged,
+            [("a", 1), ("b", 20), ("c", 30)].iter().cloned().collect()
+        );
+
+        let unhashable = PyList::empty(py).to_object(py);
+        assert!(dict.merge_from_map(vec![(unhashable, 1)]).is_err());
+        assert_eq!(3, dict.len());
+    }
+
     #[test]
     fn test_from_sequence() {
         let gil = Python::acquire_gil();
@@ -607,6 +724,33 @@ mod test {
         assert_eq!(32 + 42 + 123, value_sum);
     }
 
+    #[test]
+    fn test_iter_size_hint() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = [(7, 32), (8, 42)].into_py_dict(py);
+        let mut iter = dict.iter();
+        assert_eq!(iter.size_hint(), (2, Some(2)));
+        iter.next();
+        assert_eq!(iter.size_hint(), (1, Some(1)));
+        // Overwriting a value doesn't change the size
+        dict.set_item(8, 43).unwrap();
+        assert_eq!(iter.next().unwrap().1.extract::<i32>().unwrap(), 43);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "dictionary changed size during iteration")]
+    fn test_iter_changed_size() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = [(7, 32), (8, 42)].into_py_dict(py);
+        for (key, _) in dict {
+            let key: i32 = key.extract().unwrap();
+            dict.set_item(key + 10, 0).unwrap();
+        }
+    }
+
     #[test]
     fn test_hashmap_to_python() {
         let gil = Python::acquire_gil();
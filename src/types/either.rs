@@ -0,0 +1,149 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::conversion::{FromPyObject, IntoPy, ToPyObject};
+use crate::err::PyResult;
+use crate::object::PyObject;
+use crate::python::Python;
+use crate::types::PyAny;
+
+/// A value extracted as `L` or, if that fails, as `R`, for parameters accepting one of two types,
+/// like `str | int`.
+///
+/// If neither can be extracted, the `TypeError` contains the errors of both. Nest `PyEither`s
+/// for more types, e.g. `PyEither<String, PyEither<i64, Vec<i64>>>`. `PyEither` converts to
+/// Python like the value it holds.
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyEither;
+///
+/// fn describe(value: PyEither<i64, String>) -> String {
+///     match value {
+///         PyEither::Left(number) => format!("number {}", number),
+///         PyEither::Right(text) => format!("text {}", text),
+///     }
+/// }
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let value = py.eval("'abc'", None, None).unwrap().extract().unwrap();
+/// assert_eq!(describe(value), "text abc");
+/// assert!(py.eval("[1]", None, None).unwrap().extract::<PyEither<i64, String>>().is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PyEither<L, R> {
+    Left(L),
+    Right(R),
+}
+
+impl<L, R> PyEither<L, R> {
+    /// The value if it was extracted as `L`.
+    pub fn left(self) -> Option<L> {
+        match self {
+            PyEither::Left(value) => Some(value),
+            PyEither::Right(_) => None,
+        }
+    }
+
+    /// The value if it was extracted as `R`.
+    pub fn right(self) -> Option<R> {
+        match self {
+            PyEither::Left(_) => None,
+            PyEither::Right(value) => Some(value),
+        }
+    }
+}
+
+impl<'source, L, R> FromPyObject<'source> for PyEither<L, R>
+where
+    L: FromPyObject<'source>,
+    R: FromPyObject<'source>,
+{
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let left_err = match L::extract(obj) {
+            Ok(value) => return Ok(PyEither::Left(value)),
+            Err(err) => err,
+        };
+        match R::extract(obj) {
+            Ok(value) => Ok(PyEither::Right(value)),
+            Err(right_err) => Err(crate::derive_utils::failed_to_extract_enum(
+                obj,
+                "PyEither",
+                vec![("Left", left_err), ("Right", right_err)],
+            )),
+        }
+    }
+}
+
+impl<L: ToPyObject, R: ToPyObject> ToPyObject for PyEither<L, R> {
+    fn to_object(&self, py: Python) -> PyObject {
+        match self {
+            PyEither::Left(value) => value.to_object(py),
+            PyEither::Right(value) => value.to_object(py),
+        }
+    }
+}
+
+impl<L, R> IntoPy<PyObject> for PyEither<L, R>
+where
+    L: IntoPy<PyObject>,
+    R: IntoPy<PyObject>,
+{
+    fn into_py(self, py: Python) -> PyObject {
+        match self {
+            PyEither::Left(value) => value.into_py(py),
+            PyEither::Right(value) => value.into_py(py),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyEither;
+    use crate::exceptions::TypeError;
+    use crate::{AsPyRef, FromPy, ObjectProtocol, PyObject, Python, ToPyObject};
+
+    #[test]
+    fn test_extract() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let extract = |code| {
+            py.eval(code, None, None)
+                .unwrap()
+                .extract::<PyEither<i64, PyEither<String, Vec<i64>>>>()
+        };
+
+        assert_eq!(extract("1").unwrap(), PyEither::Left(1));
+        assert_eq!(
+            extract("'a'").unwrap().right().unwrap().left().unwrap(),
+            "a"
+        );
+        assert_eq!(
+            extract("[1, 2]").unwrap(),
+            PyEither::Right(PyEither::Right(vec![1, 2]))
+        );
+
+        let err = extract("1.5").unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        let message = PyObject::from_py(err, py)
+            .as_ref(py)
+            .str()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        assert!(message.starts_with(
+            "failed to extract enum PyEither from 'float' object\n- Left: TypeError:"
+        ));
+        assert!(message.contains("\n- Right: TypeError: failed to extract enum PyEither"));
+    }
+
+    #[test]
+    fn test_to_object() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let value: PyEither<i64, String> = PyEither::Right("b".to_string());
+        assert_eq!(value.to_object(py).extract::<String>(py).unwrap(), "b");
+        let value: PyEither<i64, String> = PyEither::Left(3);
+        assert_eq!(value.to_object(py).extract::<i64>(py).unwrap(), 3);
+    }
+}
@@ -0,0 +1,368 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::callback::{handle_callback, PyObjectCallbackConverter};
+use crate::err::PyResult;
+use crate::exceptions::{RuntimeError, TypeError, ValueError};
+use crate::ffi;
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::internal_tricks::Unsendable;
+use crate::object::PyObject;
+use crate::types::{PyAny, PyDict, PyTuple};
+use crate::{AsPyPointer, IntoPy, Python};
+#[cfg(not(Py_LIMITED_API))]
+use std::collections::HashSet;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+use std::sync::{Mutex, TryLockError};
+
+/// Represents a builtin Python function, like the functions created by `wrap_pyfunction!`.
+#[repr(transparent)]
+pub struct PyCFunction(PyObject, Unsendable);
+
+pyobject_native_var_type!(PyCFunction, ffi::PyCFunction_Type, ffi::PyCFunction_Check);
+
+/// The separator between the text signature and the documentation in `ml_doc`
+const SIGNATURE_END: &str = "\n--\n\n";
+
+/// The closure called by a function created from Rust code at runtime. It must be `Sync`, as
+/// another thread can call the function while the closure released the GIL.
+type Closure = Box<dyn Fn(&PyTuple, Option<&PyDict>) -> PyResult<PyObject> + Send + Sync>;
+
+/// The data of a function created from a closure, owned by a capsule which is the `__self__` of
+/// the function, so it's freed with the function.
+struct ClosureFunction {
+    def: ffi::PyMethodDef,
+    name: CString,
+    // `def` points to the name and the documentation
+    _doc: Option<CString>,
+    closure: Closure,
+}
+
+const CLOSURE_CAPSULE: &[u8] = b"pyo3.closure\0";
+
+/// A method definition created by `replace_doc`, with the documentation it points to. The
+/// definition is the first field, so the `m_ml` of the function points to the whole struct.
+#[cfg(not(Py_LIMITED_API))]
+#[repr(C)]
+struct ReplacedDoc {
+    def: ffi::PyMethodDef,
+    _doc: CString,
+}
+
+// The addresses of the `ReplacedDoc`s, only accessed while holding the GIL. They're never freed,
+// as CPython has no hook to free them with the function.
+#[cfg(not(Py_LIMITED_API))]
+static mut REPLACED_DOCS: Option<HashSet<usize>> = None;
+
+impl PyCFunction {
+    /// Creates a function from a Rust closure, which gets the positional and keyword arguments.
+    ///
+    /// Unlike a `#[pyfunction]`, the closure can capture and change state, e.g. to register a
+    /// callback with Python code. It's dropped when the function is garbage collected. Calling
+    /// the function while the closure runs, from the closure itself or from another thread while
+    /// the closure released the GIL, raises a `RuntimeError`.
+    ///
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::PyCFunction;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    /// let recorded = events.clone();
+    /// let mut count = 0;
+    /// let on_event = PyCFunction::new_closure(py, "on_event", "", move |args, _| {
+    ///     let event: String = args.get_item(0).extract()?;
+    ///     recorded.lock().unwrap().push(event);
+    ///     count += 1;
+    ///     Ok(count)
+    /// })
+    /// .unwrap();
+    /// pyo3::py_run!(py, on_event, r#"
+    ///     assert on_event("start") == 1
+    ///     assert on_event("stop") == 2
+    /// "#);
+    /// assert_eq!(*events.lock().unwrap(), vec!["start", "stop"]);
+    /// ```
+    pub fn new_closure<'p, F, R>(
+        py: Python<'p>,
+        name: &str,
+        doc: &str,
+        closure: F,
+    ) -> PyResult<&'p PyCFunction>
+    where
+        F: FnMut(&PyTuple, Option<&PyDict>) -> PyResult<R> + Send + 'static,
+        R: IntoPy<PyObject>,
+    {
+        let function_name = name.to_string();
+        let closure = Mutex::new(closure);
+        let closure = move |args: &PyTuple, kwargs: Option<&PyDict>| {
+            let mut closure = match closure.try_lock() {
+                Ok(closure) => closure,
+                // A panic was raised as an exception, so the closure can be called again
+                Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+                Err(TryLockError::WouldBlock) => {
+                    return Err(RuntimeError::py_err(format!(
+                        "{}() is already running",
+                        function_name
+                    )))
+                }
+            };
+            (*closure)(args, kwargs).map(|result| result.into_py(args.py()))
+        };
+        PyCFunction::from_closure(py, name, doc, Box::new(closure))
+    }
+
+    /// Creates a method from a Rust closure, for the dict of a class created with
+    /// [PyType::new](struct.PyType.html#method.new) or to be set on an existing class.
+    ///
+    /// The closure gets the instance and the other positional and keyword arguments. The result
+    /// is an `instancemethod`, which passes the instance like the methods of Python classes.
+    /// The closure is dropped when the method is garbage collected.
+    ///
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::{PyCFunction, PyDict, PyType};
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let greeting = String::from("Hello");
+    /// let greet = PyCFunction::new_method(py, "greet", "Greets the user", move |slf, _, _| {
+    ///     let user: String = slf.getattr("user")?.extract()?;
+    ///     Ok(format!("{}, {}!", greeting, user))
+    /// })
+    /// .unwrap();
+    /// let dict = PyDict::new(py);
+    /// dict.set_item("greet", greet).unwrap();
+    /// let greeter = PyType::new(py, "Greeter", &[], Some(dict)).unwrap();
+    /// pyo3::py_run!(py, greeter, r#"
+    ///     g = greeter()
+    ///     g.user = "Alice"
+    ///     assert g.greet() == "Hello, Alice!"
+    /// "#);
+    /// ```
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn new_method<'p, F, R>(
+        py: Python<'p>,
+        name: &str,
+        doc: &str,
+        method: F,
+    ) -> PyResult<&'p PyAny>
+    where
+        F: Fn(&PyAny, &PyTuple, Option<&PyDict>) -> PyResult<R> + Send + Sync + 'static,
+        R: IntoPy<PyObject>,
+    {
+        let method_name = name.to_string();
+        let closure = move |args: &PyTuple, kwargs: Option<&PyDict>| {
+            let py = args.py();
+            if args.is_empty() {
+                return Err(TypeError::py_err(format!(
+                    "{}() needs an instance as its first argument",
+                    method_name
+                )));
+            }
+            let rest = args.split_from(1);
+            method(args.get_item(0), rest.as_ref(py), kwargs).map(|result| result.into_py(py))
+        };
+        let function = PyCFunction::from_closure(py, name, doc, Box::new(closure))?;
+        unsafe { py.from_owned_ptr_or_err(ffi::PyInstanceMethod_New(function.as_ptr())) }
+    }
+
+    /// Creates a function calling `closure` with the positional and keyword arguments.
+    fn from_closure<'p>(
+        py: Python<'p>,
+        name: &str,
+        doc: &str,
+        closure: Closure,
+    ) -> PyResult<&'p PyCFunction> {
+        let name = CString::new(name)?;
+        let doc = if doc.is_empty() {
+            None
+        } else {
+            Some(CString::new(doc)?)
+        };
+        let function = Box::into_raw(Box::new(ClosureFunction {
+            def: ffi::PyMethodDef {
+                ml_name: name.as_ptr(),
+                ml_meth: Some(unsafe {
+                    std::mem::transmute::<ffi::PyCFunctionWithKeywords, ffi::PyCFunction>(
+                        call_closure,
+                    )
+                }),
+                ml_flags: ffi::METH_VARARGS | ffi::METH_KEYWORDS,
+                ml_doc: doc.as_ref().map_or(ptr::null(), |doc| doc.as_ptr()),
+            },
+            name,
+            _doc: doc,
+            closure,
+        }));
+        unsafe {
+            let capsule = ffi::PyCapsule_New(
+                function as *mut _,
+                CLOSURE_CAPSULE.as_ptr() as *const c_char,
+                Some(drop_closure),
+            );
+            if capsule.is_null() {
+                drop(Box::from_raw(function));
+            }
+            let capsule = PyObject::from_owned_ptr_or_err(py, capsule)?;
+            py.from_owned_ptr_or_err(ffi::PyCFunction_NewEx(
+                &mut (*function).def,
+                capsule.as_ptr(),
+                ptr::null_mut(),
+            ))
+        }
+    }
+
+    /// Replaces the `__doc__` of the function, keeping its `__text_signature__`.
+    ///
+    /// This is meant for functions whose documentation is only known at runtime. The method
+    /// definition of a `#[pyfunction]` may be shared with other functions, so the first call
+    /// copies it for this function, and later calls change the copy. Functions created from
+    /// closures change their own definition.
+    ///
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::PyCFunction;
+    /// use pyo3::wrap_pyfunction;
+    ///
+    /// #[pyfunction]
+    /// fn add(a: i64, b: i64) -> i64 {
+    ///     a + b
+    /// }
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let add = wrap_pyfunction!(add)(py);
+    /// let add: &PyCFunction = add.extract(py).unwrap();
+    /// add.set_doc("Adds two integers").unwrap();
+    /// add.set_text_signature("(a, b, /)").unwrap();
+    /// pyo3::py_run!(py, add, r#"
+    ///     assert add.__doc__ == "Adds two integers"
+    ///     assert add.__text_signature__ == "(a, b, /)"
+    /// "#);
+    /// ```
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn set_doc(&self, doc: &str) -> PyResult<()> {
+        let (signature, _) = self.split_doc();
+        self.replace_doc(signature.as_ref().map(String::as_str), doc)
+    }
+
+    /// Replaces the `__text_signature__` of the function, keeping its `__doc__`.
+    ///
+    /// The signature must be in parentheses, like `"(a, b=1, *args)"`, which is what
+    /// `inspect.signature` and `help()` show. The method definition is replaced like with
+    /// [set_doc](#method.set_doc).
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn set_text_signature(&self, signature: &str) -> PyResult<()> {
+        if !signature.starts_with('(') || !signature.ends_with(')') {
+            return Err(ValueError::py_err(format!(
+                "text signature {:?} must be in parentheses",
+                signature
+            )));
+        }
+        let (_, doc) = self.split_doc();
+        self.replace_doc(Some(signature), &doc)
+    }
+
+    /// The name of the function in its method definition.
+    #[cfg(not(Py_LIMITED_API))]
+    fn method_name(&self) -> &str {
+        unsafe {
+            let def = (*(self.as_ptr() as *mut ffi::PyCFunctionObject)).m_ml;
+            CStr::from_ptr((*def).ml_name)
+                .to_str()
+                .expect("function name must be valid UTF-8")
+        }
+    }
+
+    /// The text signature and the documentation in `ml_doc`, which starts with the name and the
+    /// signature of the function if it has one, like `add(a, b)\n--\n\nAdds a and b`.
+    #[cfg(not(Py_LIMITED_API))]
+    fn split_doc(&self) -> (Option<String>, String) {
+        let doc = unsafe {
+            let def = (*(self.as_ptr() as *mut ffi::PyCFunctionObject)).m_ml;
+            if (*def).ml_doc.is_null() {
+                return (None, String::new());
+            }
+            CStr::from_ptr((*def).ml_doc).to_string_lossy().into_owned()
+        };
+        let name = self.method_name();
+        if doc.starts_with(name) && doc[name.len()..].starts_with('(') {
+            if let Some(end) = doc.find(SIGNATURE_END) {
+                let signature = doc[name.len()..end].to_string();
+                return (
+                    Some(signature),
+                    doc[end + SIGNATURE_END.len()..].to_string(),
+                );
+            }
+        }
+        (None, doc)
+    }
+
+    #[cfg(not(Py_LIMITED_API))]
+    fn replace_doc(&self, signature: Option<&str>, doc: &str) -> PyResult<()> {
+        let ml_doc = match signature {
+            Some(signature) => format!(
+                "{}{}{}{}",
+                self.method_name(),
+                signature,
+                SIGNATURE_END,
+                doc
+            ),
+            None => doc.to_string(),
+        };
+        let ml_doc = CString::new(ml_doc)?;
+        unsafe {
+            let function = self.as_ptr() as *mut ffi::PyCFunctionObject;
+            let slf = (*function).m_self;
+            let capsule_name = CLOSURE_CAPSULE.as_ptr() as *const c_char;
+            if !slf.is_null() && ffi::PyCapsule_IsValid(slf, capsule_name) != 0 {
+                let closure = ffi::PyCapsule_GetPointer(slf, capsule_name) as *mut ClosureFunction;
+                (*closure).def.ml_doc = ml_doc.as_ptr();
+                (*closure)._doc = Some(ml_doc);
+                return Ok(());
+            }
+            let replaced_docs = REPLACED_DOCS.get_or_insert_with(HashSet::new);
+            let def = (*function).m_ml;
+            if replaced_docs.contains(&(def as usize)) {
+                let replaced = &mut *(def as *mut ReplacedDoc);
+                replaced.def.ml_doc = ml_doc.as_ptr();
+                replaced._doc = ml_doc;
+            } else {
+                let replaced = Box::into_raw(Box::new(ReplacedDoc {
+                    def: ffi::PyMethodDef {
+                        ml_doc: ml_doc.as_ptr(),
+                        ..*def
+                    },
+                    _doc: ml_doc,
+                }));
+                replaced_docs.insert(replaced as usize);
+                (*function).m_ml = &mut (*replaced).def;
+            }
+        }
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn call_closure(
+    capsule: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+    kwargs: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let function = &*(ffi::PyCapsule_GetPointer(capsule, CLOSURE_CAPSULE.as_ptr() as *const c_char)
+        as *const ClosureFunction);
+    let location = function.name.to_str().unwrap_or("closure");
+    handle_callback(location, PyObjectCallbackConverter, |py| {
+        let args = py.from_borrowed_ptr::<PyTuple>(args);
+        let kwargs = py.from_borrowed_ptr_or_opt::<PyDict>(kwargs);
+        (function.closure)(args, kwargs)
+    })
+}
+
+unsafe extern "C" fn drop_closure(capsule: *mut ffi::PyObject) {
+    let function = ffi::PyCapsule_GetPointer(capsule, CLOSURE_CAPSULE.as_ptr() as *const c_char);
+    drop(Box::from_raw(function as *mut ClosureFunction));
+}
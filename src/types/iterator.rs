@@ -39,21 +39,38 @@ impl<'p> PyIterator<'p> {
         T: AsPyPointer,
     {
         unsafe {
+            let error = || PyDowncastError::new(py.from_borrowed_ptr(obj.as_ptr()), "Iterator");
             let ptr = ffi::PyObject_GetIter(obj.as_ptr());
             // Returns NULL if an object cannot be iterated.
             if ptr.is_null() {
                 PyErr::fetch(py);
-                return Err(PyDowncastError);
+                return Err(error());
             }
 
             if ffi::PyIter_Check(ptr) != 0 {
                 // this is not right, but this cause of segfault check #71
                 Ok(PyIterator(py.from_borrowed_ptr(ptr)))
             } else {
-                Err(PyDowncastError)
+                Err(error())
             }
         }
     }
+
+    /// Calls Python's `iter()` on `obj`, returning the exception raised by it, e.g. by an
+    /// `__iter__` method or because the object isn't iterable.
+    pub(crate) fn iter_object<T>(py: Python<'p>, obj: &T) -> PyResult<PyIterator<'p>>
+    where
+        T: AsPyPointer,
+    {
+        unsafe {
+            // `iter()` raises a `TypeError` if `__iter__` doesn't return an iterator
+            let ptr = ffi::PyObject_GetIter(obj.as_ptr());
+            if ptr.is_null() {
+                return Err(PyErr::fetch(py));
+            }
+            Ok(PyIterator(py.from_borrowed_ptr(ptr)))
+        }
+    }
 }
 
 impl<'p> Iterator for PyIterator<'p> {
@@ -89,6 +106,7 @@ impl<'p> Drop for PyIterator<'p> {
 
 #[cfg(test)]
 mod tests {
+    use crate::exceptions::{TypeError, ValueError};
     use crate::gil::GILPool;
     use crate::instance::AsPyRef;
     use crate::objectprotocol::ObjectProtocol;
@@ -186,4 +204,25 @@ mod tests {
             assert_eq!(actual, *expected)
         }
     }
+
+    #[test]
+    fn iter_error() {
+        let gil = GILGuard::acquire();
+        let py = gil.python();
+        let context = PyDict::new(py);
+        py.run(
+            "class Broken:\n    def __iter__(self):\n        raise ValueError('broken')",
+            None,
+            Some(context),
+        )
+        .unwrap();
+
+        let broken = py.eval("Broken()", None, Some(context)).unwrap();
+        let err = broken.iter().err().unwrap();
+        assert!(err.is_instance::<ValueError>(py));
+
+        let number = 1.to_object(py);
+        let err = number.as_ref(py).iter().err().unwrap();
+        assert!(err.is_instance::<TypeError>(py));
+    }
 }
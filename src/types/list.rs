@@ -114,6 +114,9 @@ impl PyList {
     }
 
     /// Returns an iterator over this list items.
+    ///
+    /// The length is read on every step, so items appended during the iteration are visited and
+    /// `size_hint` returns the number of items left at the time it is called.
     pub fn iter(&self) -> PyListIterator {
         PyListIterator {
             list: self,
@@ -143,14 +146,28 @@ impl<'a> Iterator for PyListIterator<'a> {
 
     #[inline]
     fn next(&mut self) -> Option<&'a PyAny> {
+        // The length is checked on every step, as the list may change during the iteration
         if self.index < self.list.len() as isize {
-            let item = self.list.get_item(self.index);
+            let item = unsafe {
+                #[cfg(not(Py_LIMITED_API))]
+                let item = ffi::PyList_GET_ITEM(self.list.as_ptr(), self.index);
+                // The limited API only has the bounds checked function
+                #[cfg(Py_LIMITED_API)]
+                let item = ffi::PyList_GetItem(self.list.as_ptr(), self.index);
+                self.list.py().from_borrowed_ptr(item)
+            };
             self.index += 1;
             Some(item)
         } else {
             None
         }
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.list.len().saturating_sub(self.index as usize);
+        (remaining, Some(remaining))
+    }
 }
 
 impl<'a> std::iter::IntoIterator for &'a PyList {
@@ -379,6 +396,20 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_iter_size_hint() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[1, 2]);
+        let mut iter = list.iter();
+        assert_eq!(iter.size_hint(), (2, Some(2)));
+        iter.next();
+        assert_eq!(iter.size_hint(), (1, Some(1)));
+        list.append(3).unwrap();
+        assert_eq!(iter.size_hint(), (2, Some(2)));
+        assert_eq!(iter.map(|i| i.extract::<i32>().unwrap()).sum::<i32>(), 5);
+    }
+
     #[test]
     fn test_extract() {
         let gil = Python::acquire_gil();
@@ -0,0 +1,219 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{self, PyDowncastError, PyErr, PyResult};
+use crate::ffi;
+use crate::instance::PyNativeType;
+use crate::internal_tricks::Unsendable;
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyList};
+use crate::AsPyPointer;
+use crate::{PyTryFrom, ToBorrowedObject};
+
+/// Represents a reference to a python object supporting the mapping protocol, e.g. a `dict` or
+/// a `collections.abc.Mapping`.
+#[repr(transparent)]
+pub struct PyMapping(PyObject, Unsendable);
+pyobject_native_type_named!(PyMapping);
+pyobject_native_type_extract!(PyMapping);
+
+impl PyMapping {
+    /// Returns the number of keys in the mapping. This is equivalent to Python `len()`.
+    #[inline]
+    pub fn len(&self) -> PyResult<isize> {
+        let v = unsafe { ffi::PyMapping_Size(self.as_ptr()) };
+        if v == -1 {
+            Err(PyErr::fetch(self.py()))
+        } else {
+            Ok(v as isize)
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> PyResult<bool> {
+        self.len().map(|l| l == 0)
+    }
+
+    /// Determine if the mapping contains the key. This is equivalent to the Python expression
+    /// `key in o`
+    #[inline]
+    pub fn contains<K>(&self, key: K) -> PyResult<bool>
+    where
+        K: ToBorrowedObject,
+    {
+        let r = key.with_borrowed_ptr(self.py(), |ptr| unsafe {
+            ffi::PySequence_Contains(self.as_ptr(), ptr)
+        });
+        match r {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(PyErr::fetch(self.py())),
+        }
+    }
+
+    /// Return the value for the key. Equivalent to python `o[key]`
+    #[inline]
+    pub fn get_item<K>(&self, key: K) -> PyResult<&PyAny>
+    where
+        K: ToBorrowedObject,
+    {
+        key.with_borrowed_ptr(self.py(), |key| unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyObject_GetItem(self.as_ptr(), key))
+        })
+    }
+
+    /// Set the value for the key. Equivalent to Python statement `o[key] = value`
+    #[inline]
+    pub fn set_item<K, V>(&self, key: K, value: V) -> PyResult<()>
+    where
+        K: ToBorrowedObject,
+        V: ToBorrowedObject,
+    {
+        key.with_borrowed_ptr(self.py(), |key| {
+            value.with_borrowed_ptr(self.py(), |value| unsafe {
+                err::error_on_minusone(self.py(), ffi::PyObject_SetItem(self.as_ptr(), key, value))
+            })
+        })
+    }
+
+    /// Delete the key. Equivalent to Python statement `del o[key]`
+    #[inline]
+    pub fn del_item<K>(&self, key: K) -> PyResult<()>
+    where
+        K: ToBorrowedObject,
+    {
+        key.with_borrowed_ptr(self.py(), |key| unsafe {
+            err::error_on_minusone(self.py(), ffi::PyObject_DelItem(self.as_ptr(), key))
+        })
+    }
+
+    /// Return a fresh list of the keys. Equivalent to python `list(o.keys())`
+    #[inline]
+    pub fn keys(&self) -> PyResult<&PyList> {
+        self.list_of("keys")
+    }
+
+    /// Return a fresh list of the values. Equivalent to python `list(o.values())`
+    #[inline]
+    pub fn values(&self) -> PyResult<&PyList> {
+        self.list_of("values")
+    }
+
+    /// Return a fresh list of the `(key, value)` tuples. Equivalent to python `list(o.items())`
+    #[inline]
+    pub fn items(&self) -> PyResult<&PyList> {
+        self.list_of("items")
+    }
+
+    /// `PyMapping_Keys` and friends are macros before Python 3.7, and may return views.
+    fn list_of(&self, method: &str) -> PyResult<&PyList> {
+        let view = self.call_method0(method)?;
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PySequence_List(view.as_ptr()))
+        }
+    }
+}
+
+impl<'v> PyTryFrom<'v> for PyMapping {
+    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyMapping, PyDowncastError> {
+        let value = value.into();
+        unsafe {
+            if ffi::PyMapping_Check(value.as_ptr()) != 0 {
+                Ok(<PyMapping as PyTryFrom>::try_from_unchecked(value))
+            } else {
+                Err(PyDowncastError::new(value, "Mapping"))
+            }
+        }
+    }
+
+    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyMapping, PyDowncastError> {
+        <PyMapping as PyTryFrom>::try_from(value)
+    }
+
+    #[inline]
+    unsafe fn try_from_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v PyMapping {
+        let ptr = value.into() as *const _ as *const PyMapping;
+        &*ptr
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::instance::AsPyRef;
+    use crate::types::{PyDict, PyMapping};
+    use crate::{ObjectProtocol, PyTryFrom, Python, ToPyObject};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_numbers_are_not_mappings() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let v = 42i32.to_object(py);
+        let err = <PyMapping as PyTryFrom>::try_from(v.as_ref(py))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "'int' object cannot be converted to 'Mapping'"
+        );
+    }
+
+    #[test]
+    fn test_dict_mapping() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let mut v = HashMap::new();
+        v.insert(7, 32);
+        let ob = v.to_object(py);
+        let mapping = <PyMapping as PyTryFrom>::try_from(ob.as_ref(py)).unwrap();
+        assert_eq!(1, mapping.len().unwrap());
+        assert!(mapping.contains(7).unwrap());
+        assert!(!mapping.contains(8).unwrap());
+        assert_eq!(32, mapping.get_item(7).unwrap().extract::<i32>().unwrap());
+        assert!(mapping.get_item(8).is_err());
+
+        mapping.set_item(8, 42).unwrap();
+        assert_eq!(
+            vec![7, 8],
+            mapping.keys().unwrap().extract::<Vec<i32>>().unwrap()
+        );
+        assert_eq!(
+            vec![32, 42],
+            mapping.values().unwrap().extract::<Vec<i32>>().unwrap()
+        );
+        assert_eq!(
+            vec![(7, 32), (8, 42)],
+            mapping
+                .items()
+                .unwrap()
+                .extract::<Vec<(i32, i32)>>()
+                .unwrap()
+        );
+
+        mapping.del_item(7).unwrap();
+        assert_eq!(1, mapping.len().unwrap());
+        assert!(mapping.del_item(7).is_err());
+        assert!(!mapping.is_empty().unwrap());
+    }
+
+    #[test]
+    fn test_abstract_mapping() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = PyDict::new(py);
+        py.run(
+            "from collections import ChainMap\nm = ChainMap({'a': 1}, {'b': 2})",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        let mapping: &PyMapping = locals.get_item("m").unwrap().extract().unwrap();
+        assert_eq!(2, mapping.len().unwrap());
+        assert!(mapping.contains("b").unwrap());
+        let mut keys: Vec<String> = mapping.keys().unwrap().extract().unwrap();
+        keys.sort();
+        assert_eq!(keys, ["a", "b"]);
+    }
+}
@@ -8,22 +8,30 @@ pub use self::bytearray::PyByteArray;
 pub use self::bytes::PyBytes;
 pub use self::complex::PyComplex;
 pub use self::datetime::PyDeltaAccess;
+pub use self::datetime::{fixed_offset, timezone_utc, zoneinfo};
+pub use self::datetime::{AbsDuration, SaturatingDuration};
 pub use self::datetime::{
     PyDate, PyDateAccess, PyDateTime, PyDelta, PyTime, PyTimeAccess, PyTzInfo,
 };
 pub use self::dict::{IntoPyDict, PyDict};
+pub use self::either::PyEither;
 pub use self::floatob::PyFloat;
+pub use self::function::PyCFunction;
 pub use self::iterator::PyIterator;
 pub use self::list::PyList;
+pub use self::mapping::PyMapping;
 pub use self::module::PyModule;
+pub(crate) use self::module::{free_module_state, init_module_state, prepare_module_def};
 pub use self::num::PyLong;
 pub use self::num::PyLong as PyInt;
 pub use self::sequence::PySequence;
 pub use self::set::{PyFrozenSet, PySet};
 pub use self::slice::{PySlice, PySliceIndices};
+#[doc(hidden)]
+pub use self::string::Interned;
 pub use self::string::{PyString, PyString as PyUnicode};
 pub use self::tuple::PyTuple;
-pub use self::typeobject::PyType;
+pub use self::typeobject::{PyType, ResolvedMethod};
 
 #[macro_export]
 macro_rules! pyobject_native_type_named (
@@ -119,6 +127,7 @@ macro_rules! pyobject_native_type_convert(
             type BaseType = $crate::types::PyAny;
             type ConcreteLayout = $layout;
             type Initializer = $crate::pyclass_init::PyNativeTypeInitializer<Self>;
+            type AsRefTarget = Self;
 
             const NAME: &'static str = stringify!($name);
             const MODULE: Option<&'static str> = $module;
@@ -173,9 +182,12 @@ mod bytes;
 mod complex;
 mod datetime;
 mod dict;
+mod either;
 mod floatob;
+mod function;
 mod iterator;
 mod list;
+mod mapping;
 mod module;
 mod num;
 mod sequence;
@@ -2,6 +2,9 @@
 //
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
+use crate::callback::{handle_callback, PyObjectCallbackConverter};
+use crate::class::methods::{PyMethodDef, PyMethodType};
+use crate::derive_utils::make_function;
 use crate::err::{PyErr, PyResult};
 use crate::exceptions;
 use crate::ffi;
@@ -13,10 +16,12 @@ use crate::pyclass::PyClass;
 use crate::type_object::PyTypeObject;
 use crate::types::PyTuple;
 use crate::types::{PyAny, PyDict, PyList};
-use crate::{AsPyPointer, IntoPy, Py, Python, ToPyObject};
+use crate::{AsPyPointer, AsPyRef, IntoPy, Py, Python, ToPyObject};
+use std::any::{self, TypeId};
 use std::ffi::{CStr, CString};
-use std::os::raw::c_char;
-use std::str;
+use std::mem::{self, MaybeUninit};
+use std::os::raw::{c_char, c_void};
+use std::{ptr, str};
 
 /// Represents a Python `module` object.
 #[repr(transparent)]
@@ -37,11 +42,28 @@ impl PyModule {
         unsafe { py.from_owned_ptr_or_err(ffi::PyImport_ImportModule(name.as_ptr())) }
     }
 
-    /// Loads the python code specified into a new module
-    /// 'code' is the raw Python you want to load into the module
-    /// 'file_name' is the file name to associate with the module
-    ///     (this is used when Python reports errors, for example)
-    /// 'module_name' is the name to give the module
+    /// Loads the python code specified into a new module.
+    ///
+    /// `code` is the raw Python you want to load into the module.
+    /// `file_name` is the file name to associate with the module
+    /// (this is used when Python reports errors, for example).
+    /// `module_name` is the name to give the module.
+    ///
+    /// The module is executed like an imported module and added to `sys.modules`, so Python
+    /// code can import it by `module_name` afterwards. Errors raised while compiling or running
+    /// the code, such as a `SyntaxError`, are returned.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let module = PyModule::from_code(py, "def double(x):\n    return 2 * x", "helpers.py", "helpers")
+    ///     .unwrap();
+    /// let result: i32 = module.call1("double", (21,)).unwrap().extract().unwrap();
+    /// assert_eq!(result, 42);
+    /// ```
     pub fn from_code<'p>(
         py: Python<'p>,
         code: &str,
@@ -59,6 +81,7 @@ impl PyModule {
             }
 
             let mptr = ffi::PyImport_ExecCodeModuleEx(module.as_ptr(), cptr, filename.as_ptr());
+            ffi::Py_DECREF(cptr);
             if mptr.is_null() {
                 return Err(PyErr::fetch(py));
             }
@@ -72,7 +95,7 @@ impl PyModule {
     pub fn dict(&self) -> &PyDict {
         unsafe {
             self.py()
-                .from_owned_ptr::<PyDict>(ffi::PyModule_GetDict(self.as_ptr()))
+                .from_borrowed_ptr::<PyDict>(ffi::PyModule_GetDict(self.as_ptr()))
         }
     }
 
@@ -120,6 +143,52 @@ impl PyModule {
         unsafe { self.str_from_ptr(ffi::PyModule_GetFilename(self.as_ptr())) }
     }
 
+    /// Gets the state of a module defined with `#[pymodule(state = T)]`.
+    ///
+    /// The state lives as long as the module object, so unlike a `static` each interpreter
+    /// importing the module gets its own. It is shared by all users of the module, so use
+    /// interior mutability such as `Cell` or `Mutex` to change it.
+    ///
+    /// Fails with a `TypeError` if the module has no state of type `T`.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use std::cell::Cell;
+    ///
+    /// #[derive(Default)]
+    /// struct Counter {
+    ///     calls: Cell<u32>,
+    /// }
+    ///
+    /// #[pymodule(state = Counter)]
+    /// fn counting(_py: Python, m: &PyModule) -> PyResult<()> {
+    ///     let state = m.state::<Counter>()?;
+    ///     state.calls.set(state.calls.get() + 1);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn state<T: 'static>(&self) -> PyResult<&T> {
+        unsafe {
+            let def = ffi::PyModule_GetDef(self.as_ptr());
+            let state = ffi::PyModule_GetState(self.as_ptr()) as *const ModuleState<T>;
+            if !def.is_null()
+                && (*def).m_size == mem::size_of::<ModuleState<T>>() as ffi::Py_ssize_t
+                && !state.is_null()
+                && (*state).initialized
+                && (*state).type_id == TypeId::of::<T>()
+            {
+                Ok(&*(*state).value.as_ptr())
+            } else {
+                Err(exceptions::TypeError::py_err(format!(
+                    "module {} has no state of type {}",
+                    self.name().unwrap_or("<unnamed>"),
+                    any::type_name::<T>()
+                )))
+            }
+        }
+    }
+
     /// Calls a function in the module.
     /// This is equivalent to the Python expression: `getattr(module, name)(*args, **kwargs)`
     pub fn call(
@@ -162,6 +231,17 @@ impl PyModule {
         self.setattr(name, value)
     }
 
+    /// Inserts the module into `sys.modules` as `name`, so that Python code can import it.
+    ///
+    /// This is useful for modules that weren't imported, like the ones created with
+    /// [wrap_pymodule!]. A submodule can be made importable as `parent.child` the same way.
+    pub fn add_to_sys_modules(&self, name: &str) -> PyResult<()> {
+        self.py()
+            .import("sys")?
+            .get("modules")?
+            .set_item(name, self)
+    }
+
     /// Adds a new extension type to the module.
     ///
     /// This is a convenience function that initializes the `class`,
@@ -174,10 +254,37 @@ impl PyModule {
         self.add(T::NAME, <T as PyTypeObject>::type_object())
     }
 
+    /// Adds a new extension type to the module like [add_class](PyModule::add_class), but
+    /// initializes the class only when it is first accessed.
+    ///
+    /// Until then, the class is resolved by a module level `__getattr__` (PEP 562), so a module
+    /// with many classes doesn't initialize all of them when it is imported. The class is listed
+    /// in `__all__` and by `dir()`. If the module already has a `__getattr__`, it is called for
+    /// all other names, so define it before adding lazy classes.
+    ///
+    /// Python 3.5 and 3.6 don't support `__getattr__` for modules, so there the class is added
+    /// immediately.
+    pub fn add_class_lazy<T>(&self) -> PyResult<()>
+    where
+        T: PyClass,
+    {
+        if cfg!(not(Py_3_7)) {
+            return self.add_class::<T>();
+        }
+        let def = PyMethodDef {
+            ml_name: T::NAME,
+            ml_meth: PyMethodType::PyCFunction(create_lazy_class::<T>),
+            ml_flags: ffi::METH_NOARGS,
+            ml_doc: "\0",
+        };
+        lazy_classes(self)?.set_item(T::NAME, make_function(self.py(), def))?;
+        self.index()?.append(T::NAME)
+    }
+
     /// Adds a function or a (sub)module to a module, using the functions __name__ as name.
     ///
     /// Use this together with the`#[pyfunction]` and [wrap_pyfunction!] or `#[pymodule]` and
-    /// [wrap_pymodule!].
+    /// [wrap_pymodule!]. Functions are added with [add_function](PyModule::add_function).
     ///
     /// ```rust,ignore
     /// m.add_wrapped(wrap_pyfunction!(double));
@@ -190,10 +297,209 @@ impl PyModule {
     /// m.add("also_double", wrap_pyfunction!(double)(py));
     /// ```
     pub fn add_wrapped(&self, wrapper: &impl Fn(Python) -> PyObject) -> PyResult<()> {
-        let function = wrapper(self.py());
-        let name = function
+        let object = wrapper(self.py());
+        if unsafe { ffi::PyCFunction_Check(object.as_ptr()) } != 0 {
+            return self.add_function_object(object);
+        }
+        let name = object
             .getattr(self.py(), "__name__")
             .expect("A function or module must have a __name__");
-        self.add(name.extract(self.py()).unwrap(), function)
+        self.add(name.extract(self.py()).unwrap(), object)
+    }
+
+    /// Adds a function to the module, using its `__name__` as name, and sets its `__module__`
+    /// to the name of this module, so it is shown and pickled as a member of this module.
+    /// Functions with a special name like `__getattr__` or `__dir__` are not added to
+    /// `__all__`.
+    ///
+    /// Use this together with `#[pyfunction]` and [wrap_pyfunction!]:
+    ///
+    /// ```rust
+    /// use pyo3::prelude::*;
+    /// use pyo3::wrap_pyfunction;
+    ///
+    /// /// Doubles the given value
+    /// #[pyfunction]
+    /// fn double(x: usize) -> usize {
+    ///     x * 2
+    /// }
+    ///
+    /// # let gil = Python::acquire_gil();
+    /// # let py = gil.python();
+    /// let m = PyModule::new(py, "numbers").unwrap();
+    /// m.add_function(wrap_pyfunction!(double)).unwrap();
+    ///
+    /// let double = m.get("double").unwrap();
+    /// assert_eq!(double.getattr("__module__").unwrap().extract::<&str>().unwrap(), "numbers");
+    /// assert_eq!(double.getattr("__doc__").unwrap().extract::<&str>().unwrap(), "Doubles the given value");
+    /// ```
+    ///
+    /// Returns a `TypeError` if the wrapper doesn't create a builtin function.
+    pub fn add_function(&self, wrapper: &impl Fn(Python) -> PyObject) -> PyResult<()> {
+        let function = wrapper(self.py());
+        if unsafe { ffi::PyCFunction_Check(function.as_ptr()) } == 0 {
+            return Err(exceptions::TypeError::py_err(
+                "add_function expects a wrapped function, use add_wrapped for modules",
+            ));
+        }
+        self.add_function_object(function)
+    }
+
+    fn add_function_object(&self, function: PyObject) -> PyResult<()> {
+        let function = function.as_ref(self.py());
+        function.setattr("__module__", self.name()?)?;
+        let name: &str = function.getattr("__name__")?.extract()?;
+        // Special functions like a module level `__getattr__` and `__dir__` (PEP 562) are not
+        // exported by `from module import *`
+        if name.starts_with("__") && name.ends_with("__") {
+            self.setattr(name, function)
+        } else {
+            self.add(name, function)
+        }
+    }
+}
+
+/// The name of the dict of the classes added with `add_class_lazy` that haven't been accessed yet
+const LAZY_CLASSES: &str = "__pyo3_lazy_classes__";
+
+/// Returns the dict of the lazy classes of `module`, adding the `__getattr__` and `__dir__` that
+/// resolve them the first time.
+fn lazy_classes(module: &PyModule) -> PyResult<&PyDict> {
+    let namespace = module.dict();
+    if let Some(classes) = namespace.get_item(LAZY_CLASSES) {
+        return classes.downcast_ref().map_err(PyErr::from);
+    }
+
+    let classes = PyDict::new(module.py());
+    // `__getattr__` is only called for missing attributes, so this key can't be a class name
+    if let Some(getattr) = namespace.get_item("__getattr__") {
+        classes.set_item("__getattr__", getattr)?;
+    }
+    module.setattr(LAZY_CLASSES, classes)?;
+
+    let mut defs = vec![PyMethodDef {
+        ml_name: "__getattr__",
+        ml_meth: PyMethodType::PyCFunction(lazy_getattr),
+        ml_flags: ffi::METH_O,
+        ml_doc: "\0",
+    }];
+    if namespace.get_item("__dir__").is_none() {
+        defs.push(PyMethodDef {
+            ml_name: "__dir__",
+            ml_meth: PyMethodType::PyCFunction(lazy_dir),
+            ml_flags: ffi::METH_NOARGS,
+            ml_doc: "\0",
+        });
+    }
+    for def in defs {
+        // The module is passed as `self` to the functions
+        let function: PyObject = unsafe {
+            PyObject::from_owned_ptr_or_err(
+                module.py(),
+                ffi::PyCFunction_NewEx(
+                    Box::into_raw(Box::new(def.as_method_def())),
+                    module.as_ptr(),
+                    ptr::null_mut(),
+                ),
+            )?
+        };
+        module.setattr(def.ml_name, function)?;
+    }
+    Ok(classes)
+}
+
+unsafe extern "C" fn create_lazy_class<T: PyClass>(
+    _slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    handle_callback(T::NAME, PyObjectCallbackConverter, |_py| {
+        Ok(<T as PyTypeObject>::type_object())
+    })
+}
+
+unsafe extern "C" fn lazy_getattr(
+    module: *mut ffi::PyObject,
+    name: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    handle_callback("__getattr__", PyObjectCallbackConverter, |py| {
+        let module: &PyModule = py.from_borrowed_ptr(module);
+        let name: &PyAny = py.from_borrowed_ptr(name);
+        let classes = lazy_classes(module)?;
+        if let Some(create) = classes.get_item(name) {
+            let class = create.call0()?;
+            module.setattr(name, class)?;
+            classes.del_item(name)?;
+            return Ok(class.to_object(py));
+        }
+        if let Some(getattr) = classes.get_item("__getattr__") {
+            return Ok(getattr.call1((name,))?.to_object(py));
+        }
+        Err(exceptions::AttributeError::py_err(format!(
+            "module '{}' has no attribute '{}'",
+            module.name()?,
+            name
+        )))
+    })
+}
+
+unsafe extern "C" fn lazy_dir(
+    module: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    handle_callback("__dir__", PyObjectCallbackConverter, |py| {
+        let module: &PyModule = py.from_borrowed_ptr(module);
+        let names = module.dict().keys();
+        for name in lazy_classes(module)?.keys() {
+            if name.extract::<&str>()? != "__getattr__" {
+                names.append(name)?;
+            }
+        }
+        Ok(names.to_object(py))
+    })
+}
+
+/// The layout of the state of `#[pymodule]`s. Python allocates it zeroed, so `initialized` is
+/// `false` until the value has been written.
+#[repr(C)]
+struct ModuleState<T> {
+    type_id: TypeId,
+    initialized: bool,
+    value: MaybeUninit<T>,
+}
+
+/// Lets modules created from `def` allocate and free a state of type `T`. `free` replaces the
+/// default `m_free` function, and must call `free_module_state::<T>`.
+pub(crate) fn prepare_module_def<T: 'static>(
+    def: &mut ffi::PyModuleDef,
+    free: Option<ffi::freefunc>,
+) {
+    def.m_size = mem::size_of::<ModuleState<T>>() as ffi::Py_ssize_t;
+    def.m_free = Some(free.unwrap_or(free_module_state::<T>));
+}
+
+/// Initializes the state of a module created from a def prepared with `prepare_module_def::<T>`.
+///
+/// A module which is executed again keeps its state. CPython doesn't do that for
+/// `importlib.reload`, but other embedders may call `PyModule_ExecDef` repeatedly.
+pub(crate) unsafe fn init_module_state<T: Default + 'static>(module: *mut ffi::PyObject) {
+    let state = ffi::PyModule_GetState(module) as *mut ModuleState<T>;
+    if (*state).initialized {
+        return;
+    }
+    ptr::write(
+        state,
+        ModuleState {
+            type_id: TypeId::of::<T>(),
+            initialized: true,
+            value: MaybeUninit::new(T::default()),
+        },
+    );
+}
+
+pub(crate) unsafe extern "C" fn free_module_state<T>(module: *mut c_void) {
+    let state = ffi::PyModule_GetState(module as *mut ffi::PyObject) as *mut ModuleState<T>;
+    if !state.is_null() && (*state).initialized {
+        (*state).initialized = false;
+        ptr::drop_in_place((*state).value.as_mut_ptr());
     }
 }
@@ -73,6 +73,10 @@ macro_rules! int_convert_128 {
         }
         impl IntoPy<PyObject> for $rust_type {
             fn into_py(self, py: Python) -> PyObject {
+                // `_PyLong_FromByteArray` doesn't return the cached small ints on older Python versions
+                if let Some(value) = cast::<$rust_type, c_long>(self) {
+                    return value.into_py(py);
+                }
                 unsafe {
                     let bytes = self.to_ne_bytes();
                     let obj = ffi::_PyLong_FromByteArray(
@@ -127,9 +131,7 @@ macro_rules! int_fits_c_long {
         impl ToPyObject for $rust_type {
             #![cfg_attr(feature = "cargo-clippy", allow(clippy::cast_lossless))]
             fn to_object(&self, py: Python) -> PyObject {
-                unsafe {
-                    PyObject::from_owned_ptr_or_panic(py, ffi::PyLong_FromLong(*self as c_long))
-                }
+                (*self).into_py(py)
             }
         }
         impl IntoPy<PyObject> for $rust_type {
@@ -168,7 +170,7 @@ macro_rules! int_convert_u64_or_i64 {
         impl ToPyObject for $rust_type {
             #[inline]
             fn to_object(&self, py: Python) -> PyObject {
-                unsafe { PyObject::from_owned_ptr_or_panic(py, $pylong_from_ll_or_ull(*self)) }
+                (*self).into_py(py)
             }
         }
         impl IntoPy<PyObject> for $rust_type {
@@ -533,6 +535,44 @@ mod test {
         assert!(obj.extract::<i128>(py).is_err());
     }
 
+    #[test]
+    fn test_small_ints_are_singletons() {
+        use crate::AsPyPointer;
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        for &v in &[-5i64, -1, 0, 1, 42, 256] {
+            let expected = py.eval(&v.to_string(), None, None).unwrap();
+            assert_eq!(v.to_object(py).as_ptr(), expected.as_ptr());
+            assert_eq!((v as i32).to_object(py).as_ptr(), expected.as_ptr());
+            #[cfg(not(Py_LIMITED_API))]
+            assert_eq!((v as i128).to_object(py).as_ptr(), expected.as_ptr());
+            if v >= 0 {
+                assert_eq!((v as u16).to_object(py).as_ptr(), expected.as_ptr());
+                assert_eq!((v as u64).to_object(py).as_ptr(), expected.as_ptr());
+                assert_eq!((v as usize).to_object(py).as_ptr(), expected.as_ptr());
+                #[cfg(not(Py_LIMITED_API))]
+                assert_eq!((v as u128).to_object(py).as_ptr(), expected.as_ptr());
+            }
+        }
+        assert_eq!(257u16.to_object(py).extract::<u16>(py).unwrap(), 257);
+        assert_eq!((-6i8).to_object(py).extract::<i8>(py).unwrap(), -6);
+    }
+
+    #[test]
+    fn test_small_int_refcount() {
+        use crate::{AsPyPointer, IntoPyPointer};
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = 7u32.to_object(py);
+        let count = unsafe { crate::ffi::Py_REFCNT(obj.as_ptr()) };
+        for _ in 0..10 {
+            let ptr = 7u32.to_object(py).into_ptr();
+            assert_eq!(unsafe { crate::ffi::Py_REFCNT(ptr) }, count + 1);
+            unsafe { crate::ffi::Py_DECREF(ptr) };
+        }
+        assert_eq!(unsafe { crate::ffi::Py_REFCNT(obj.as_ptr()) }, count);
+    }
+
     #[test]
     #[cfg(not(Py_LIMITED_API))]
     fn test_u128_overflow() {
@@ -1,5 +1,6 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
+#[cfg(not(feature = "stable"))]
 use crate::buffer;
 use crate::err::{self, PyDowncastError, PyErr, PyResult};
 use crate::ffi::{self, Py_ssize_t};
@@ -15,6 +16,7 @@ use crate::{FromPyObject, PyTryFrom, ToBorrowedObject};
 #[repr(transparent)]
 pub struct PySequence(PyObject, Unsendable);
 pyobject_native_type_named!(PySequence);
+pyobject_native_type_extract!(PySequence);
 
 impl PySequence {
     /// Returns the number of objects in sequence. This is equivalent to Python `len()`.
@@ -241,6 +243,7 @@ impl PySequence {
     }
 }
 
+#[cfg(not(feature = "stable"))]
 impl<'a, T> FromPyObject<'a> for Vec<T>
 where
     T: FromPyObject<'a>,
@@ -250,6 +253,18 @@ where
     }
 }
 
+/// Without specialization, the buffer protocol isn't tried first for primitive element types.
+#[cfg(feature = "stable")]
+impl<'a, T> FromPyObject<'a> for Vec<T>
+where
+    T: FromPyObject<'a>,
+{
+    fn extract(obj: &'a PyAny) -> PyResult<Self> {
+        extract_sequence(obj)
+    }
+}
+
+#[cfg(not(feature = "stable"))]
 impl<'source, T> FromPyObject<'source> for Vec<T>
 where
     for<'a> T: FromPyObject<'a> + buffer::Element + Copy,
@@ -289,7 +304,7 @@ impl<'v> PyTryFrom<'v> for PySequence {
             if ffi::PySequence_Check(value.as_ptr()) != 0 {
                 Ok(<PySequence as PyTryFrom>::try_from_unchecked(value))
             } else {
-                Err(PyDowncastError)
+                Err(PyDowncastError::new(value, "Sequence"))
             }
         }
     }
@@ -298,34 +313,11 @@ impl<'v> PyTryFrom<'v> for PySequence {
         <PySequence as PyTryFrom>::try_from(value)
     }
 
-    fn try_from_mut<V: Into<&'v PyAny>>(value: V) -> Result<&'v mut PySequence, PyDowncastError> {
-        let value = value.into();
-        unsafe {
-            if ffi::PySequence_Check(value.as_ptr()) != 0 {
-                Ok(<PySequence as PyTryFrom>::try_from_mut_unchecked(value))
-            } else {
-                Err(PyDowncastError)
-            }
-        }
-    }
-
-    fn try_from_mut_exact<V: Into<&'v PyAny>>(
-        value: V,
-    ) -> Result<&'v mut PySequence, PyDowncastError> {
-        <PySequence as PyTryFrom>::try_from_mut(value)
-    }
-
     #[inline]
     unsafe fn try_from_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v PySequence {
         let ptr = value.into() as *const _ as *const PySequence;
         &*ptr
     }
-
-    #[inline]
-    unsafe fn try_from_mut_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v mut PySequence {
-        let ptr = value.into() as *const _ as *mut PySequence;
-        &mut *ptr
-    }
 }
 
 #[cfg(test)]
@@ -116,6 +116,44 @@ impl PySet {
             pos: 0,
         }
     }
+
+    /// Removes the objects that are equal to an earlier one, using Python's `__hash__` and
+    /// `__eq__`, while keeping the order of the remaining objects.
+    ///
+    /// Fails with `TypeError` if an object is unhashable.
+    pub fn dedup(py: Python, objects: Vec<PyObject>) -> PyResult<Vec<PyObject>> {
+        let seen = PySet::empty(py)?;
+        let mut unique = Vec::with_capacity(objects.len());
+        for obj in objects {
+            let len = seen.len();
+            seen.add(&obj)?;
+            if seen.len() > len {
+                unique.push(obj);
+            }
+        }
+        Ok(unique)
+    }
+
+    /// Returns the objects of `a` followed by those of `b`, without duplicates.
+    /// See [PySet::dedup](#method.dedup).
+    pub fn union(py: Python, a: Vec<PyObject>, b: Vec<PyObject>) -> PyResult<Vec<PyObject>> {
+        let mut objects = a;
+        objects.extend(b);
+        PySet::dedup(py, objects)
+    }
+
+    /// Returns the objects of `a` that are equal to an object of `b`, without duplicates.
+    /// See [PySet::dedup](#method.dedup).
+    pub fn intersection(py: Python, a: Vec<PyObject>, b: &[PyObject]) -> PyResult<Vec<PyObject>> {
+        let b = PySet::new(py, b)?;
+        let mut common = Vec::new();
+        for obj in PySet::dedup(py, a)? {
+            if b.contains(&obj)? {
+                common.push(obj);
+            }
+        }
+        Ok(common)
+    }
 }
 
 #[cfg(not(Py_LIMITED_API))]
@@ -264,6 +302,34 @@ mod test {
         assert!(PySet::new(py, &[v]).is_err());
     }
 
+    #[test]
+    fn test_set_operations() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let objects =
+            |values: &[i32]| -> Vec<_> { values.iter().map(|v| v.to_object(py)).collect() };
+        let values = |objects: Vec<crate::PyObject>| -> Vec<i32> {
+            objects.iter().map(|o| o.extract(py).unwrap()).collect()
+        };
+
+        let unique = PySet::dedup(py, objects(&[3, 1, 3, 2, 1])).unwrap();
+        assert_eq!(values(unique), vec![3, 1, 2]);
+
+        let union = PySet::union(py, objects(&[1, 2]), objects(&[2, 3, 1])).unwrap();
+        assert_eq!(values(union), vec![1, 2, 3]);
+
+        let common = PySet::intersection(py, objects(&[4, 1, 2, 1]), &objects(&[1, 4])).unwrap();
+        assert_eq!(values(common), vec![4, 1]);
+
+        // 1 and 1.0 are equal in Python
+        let mixed = vec![1.to_object(py), 1.0.to_object(py)];
+        assert_eq!(PySet::dedup(py, mixed).unwrap().len(), 1);
+
+        let unhashable = vec![vec![1].to_object(py)];
+        assert!(PySet::dedup(py, unhashable).is_err());
+    }
+
     #[test]
     fn test_set_empty() {
         let gil = Python::acquire_gil();
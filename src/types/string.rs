@@ -7,13 +7,14 @@ use crate::gil;
 use crate::instance::PyNativeType;
 use crate::internal_tricks::Unsendable;
 use crate::object::PyObject;
-use crate::types::PyAny;
+use crate::types::{PyAny, PyBytes};
 use crate::AsPyPointer;
 use crate::IntoPy;
 use crate::Python;
 use crate::{ffi, FromPy};
 use std::borrow::Cow;
-use std::ffi::CStr;
+use std::cell::UnsafeCell;
+use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::ptr::NonNull;
 use std::str;
@@ -31,18 +32,92 @@ impl PyString {
     ///
     /// Panics if out of memory.
     pub fn new<'p>(py: Python<'p>, s: &str) -> &'p PyString {
-        let ptr = s.as_ptr() as *const c_char;
-        let len = s.len() as ffi::Py_ssize_t;
-        unsafe { py.from_owned_ptr(ffi::PyUnicode_FromStringAndSize(ptr, len)) }
+        unsafe { py.from_owned_ptr(new_unicode(s)) }
     }
 
+    /// Creates an interned Python string, which is the same object for all interned strings with
+    /// the same text.
+    ///
+    /// Python interns identifiers, so using an interned string as an attribute name or dict key
+    /// lets lookups compare pointers instead of the text. To also avoid creating a string on
+    /// every call, use [`intern!`](../macro.intern.html).
+    ///
+    /// Panics if out of memory.
+    pub fn intern<'p>(py: Python<'p>, s: &str) -> &'p PyString {
+        unsafe {
+            let mut ptr = new_unicode(s);
+            if !ptr.is_null() {
+                ffi::PyUnicode_InternInPlace(&mut ptr);
+            }
+            py.from_owned_ptr(ptr)
+        }
+    }
+
+    /// Decodes `src`, which must support the buffer protocol, e.g. `bytes`, with the codec
+    /// `encoding`. Equivalent to python `str(src, encoding, errors)`.
     pub fn from_object<'p>(src: &'p PyAny, encoding: &str, errors: &str) -> PyResult<&'p PyString> {
+        let (encoding, errors) = codec_args(encoding, errors)?;
         unsafe {
             src.py()
                 .from_owned_ptr_or_err::<PyString>(ffi::PyUnicode_FromEncodedObject(
                     src.as_ptr(),
-                    encoding.as_ptr() as *const c_char,
-                    errors.as_ptr() as *const c_char,
+                    encoding.as_ptr(),
+                    errors.as_ptr(),
+                ))
+        }
+    }
+
+    /// Decodes `data` with the codec `encoding`, e.g. `"latin-1"` or `"utf-8"`.
+    ///
+    /// `errors` is the name of the error handler for data that can't be decoded: `"strict"`
+    /// raises a `UnicodeDecodeError`, `"replace"` inserts U+FFFD REPLACEMENT CHARACTER, and
+    /// `"surrogateescape"` decodes each invalid byte to a lone surrogate, so that encoding the
+    /// string with `"surrogateescape"` restores the data.
+    ///
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::PyString;
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let data = b"caf\xe9";
+    /// assert!(PyString::from_encoded(py, data, "utf-8", "strict").is_err());
+    /// let s = PyString::from_encoded(py, data, "utf-8", "surrogateescape").unwrap();
+    /// assert_eq!(s.encode("utf-8", "surrogateescape").unwrap().as_bytes(), data);
+    /// let s = PyString::from_encoded(py, data, "latin-1", "strict").unwrap();
+    /// assert_eq!(s.to_string().unwrap(), "café");
+    /// ```
+    pub fn from_encoded<'p>(
+        py: Python<'p>,
+        data: &[u8],
+        encoding: &str,
+        errors: &str,
+    ) -> PyResult<&'p PyString> {
+        let (encoding, errors) = codec_args(encoding, errors)?;
+        unsafe {
+            py.from_owned_ptr_or_err(ffi::PyUnicode_Decode(
+                data.as_ptr() as *const c_char,
+                data.len() as ffi::Py_ssize_t,
+                encoding.as_ptr(),
+                errors.as_ptr(),
+            ))
+        }
+    }
+
+    /// Encodes the string with the codec `encoding`. Equivalent to python
+    /// `s.encode(encoding, errors)`.
+    ///
+    /// `errors` is the name of the error handler for characters that can't be encoded, e.g.
+    /// `"strict"`, `"replace"` or `"surrogateescape"`, see
+    /// [from_encoded](#method.from_encoded).
+    pub fn encode(&self, encoding: &str, errors: &str) -> PyResult<&PyBytes> {
+        let (encoding, errors) = codec_args(encoding, errors)?;
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyUnicode_AsEncodedString(
+                    self.as_ptr(),
+                    encoding.as_ptr(),
+                    errors.as_ptr(),
                 ))
         }
     }
@@ -102,19 +177,102 @@ impl PyString {
     }
 }
 
+/// The names of a codec and an error handler as C strings.
+fn codec_args(encoding: &str, errors: &str) -> PyResult<(CString, CString)> {
+    Ok((CString::new(encoding)?, CString::new(errors)?))
+}
+
+/// A Python string interned on first use, created by [`intern!`](../macro.intern.html).
+#[doc(hidden)]
+pub struct Interned {
+    text: &'static str,
+    // Only accessed with the GIL held
+    value: UnsafeCell<Option<PyObject>>,
+}
+
+unsafe impl Sync for Interned {}
+
+impl Interned {
+    pub const fn new(text: &'static str) -> Self {
+        Interned {
+            text,
+            value: UnsafeCell::new(None),
+        }
+    }
+
+    pub fn get<'p>(&'static self, py: Python<'p>) -> &'p PyString {
+        unsafe {
+            if (*self.value.get()).is_none() {
+                // Interning doesn't run Python code, so no other thread can initialize it first
+                *self.value.get() = Some(PyString::intern(py, self.text).into());
+            }
+            let value = (*self.value.get()).as_ref().unwrap();
+            &*(value as *const PyObject as *const PyString)
+        }
+    }
+}
+
+/// Returns the Python string `text`, interned when the expression is evaluated for the first time.
+///
+/// Hot paths accessing the same attributes repeatedly can pass the result to `getattr`,
+/// `setattr` or `call_method` instead of a `&str`, which would be converted to a new Python
+/// string on every call:
+///
+/// ```
+/// use pyo3::{intern, PyNativeType};
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyAny;
+///
+/// fn total_seconds(delta: &PyAny) -> PyResult<f64> {
+///     delta.call_method0(intern!(delta.py(), "total_seconds"))?.extract()
+/// }
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let delta = py.eval("__import__('datetime').timedelta(minutes=1)", None, None).unwrap();
+/// assert_eq!(total_seconds(delta).unwrap(), 60.0);
+/// ```
+#[macro_export]
+macro_rules! intern {
+    ($py: expr, $text: expr) => {{
+        static INTERNED: $crate::types::Interned = $crate::types::Interned::new($text);
+        INTERNED.get($py)
+    }};
+}
+
+/// Converts `s` to a new reference without going through the release pool. CPython returns its
+/// empty string singleton for empty strings.
+fn str_to_object(py: Python, s: &str) -> PyObject {
+    unsafe { PyObject::from_owned_ptr_or_panic(py, new_unicode(s)) }
+}
+
+/// Creates a new reference to a Python string. ASCII strings, which are common for identifiers
+/// and keys, are copied into a 1-byte string instead of being decoded from UTF-8.
+unsafe fn new_unicode(s: &str) -> *mut ffi::PyObject {
+    let len = s.len() as ffi::Py_ssize_t;
+    #[cfg(all(not(Py_LIMITED_API), not(PyPy)))]
+    {
+        if s.is_ascii() {
+            let data = s.as_ptr() as *const std::os::raw::c_void;
+            return ffi::PyUnicode_FromKindAndData(ffi::PyUnicode_1BYTE_KIND, data, len);
+        }
+    }
+    ffi::PyUnicode_FromStringAndSize(s.as_ptr() as *const c_char, len)
+}
+
 /// Converts Rust `str` to Python object.
 /// See `PyString::new` for details on the conversion.
 impl ToPyObject for str {
     #[inline]
     fn to_object(&self, py: Python) -> PyObject {
-        PyString::new(py, self).into()
+        str_to_object(py, self)
     }
 }
 
 impl<'a> IntoPy<PyObject> for &'a str {
     #[inline]
     fn into_py(self, py: Python) -> PyObject {
-        PyString::new(py, self).into()
+        str_to_object(py, self)
     }
 }
 
@@ -123,7 +281,7 @@ impl<'a> IntoPy<PyObject> for &'a str {
 impl<'a> ToPyObject for Cow<'a, str> {
     #[inline]
     fn to_object(&self, py: Python) -> PyObject {
-        PyString::new(py, self).into()
+        str_to_object(py, self)
     }
 }
 
@@ -132,20 +290,20 @@ impl<'a> ToPyObject for Cow<'a, str> {
 impl ToPyObject for String {
     #[inline]
     fn to_object(&self, py: Python) -> PyObject {
-        PyString::new(py, self).into()
+        str_to_object(py, self)
     }
 }
 
 impl FromPy<String> for PyObject {
     fn from_py(other: String, py: Python) -> Self {
-        PyString::new(py, &other).into()
+        str_to_object(py, &other)
     }
 }
 
 impl<'a> IntoPy<PyObject> for &'a String {
     #[inline]
     fn into_py(self, py: Python) -> PyObject {
-        PyString::new(py, self).into()
+        str_to_object(py, self)
     }
 }
 
@@ -185,11 +343,81 @@ impl<'source> FromPyObject<'source> for String {
 #[cfg(test)]
 mod test {
     use super::PyString;
+    use crate::exceptions::{UnicodeEncodeError, ValueError};
     use crate::instance::AsPyRef;
     use crate::object::PyObject;
+    use crate::types::PyBytes;
     use crate::Python;
-    use crate::{FromPyObject, PyTryFrom, ToPyObject};
+    use crate::{AsPyPointer, FromPyObject, ObjectProtocol, PyTryFrom, ToPyObject};
     use std::borrow::Cow;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn test_to_object_skips_release_pool() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let owned = crate::gil::owned_count();
+        let hello = "hello".to_object(py);
+        let empty = String::new().to_object(py);
+        let text = Cow::Borrowed("caf\u{e9}").to_object(py);
+        assert_eq!(crate::gil::owned_count(), owned);
+        assert_eq!(hello.extract::<&str>(py).unwrap(), "hello");
+        assert_eq!(empty.extract::<&str>(py).unwrap(), "");
+        assert_eq!(text.extract::<&str>(py).unwrap(), "caf\u{e9}");
+    }
+
+    #[test]
+    fn test_encode() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let s = PyString::new(py, "naïve");
+        assert_eq!(
+            s.encode("latin-1", "strict").unwrap().as_bytes(),
+            b"na\xefve"
+        );
+        let err = s.encode("ascii", "strict").unwrap_err();
+        assert!(err.is_instance::<UnicodeEncodeError>(py));
+        assert_eq!(s.encode("ascii", "replace").unwrap().as_bytes(), b"na?ve");
+        assert!(s.encode("ascii", "no-such-handler").is_err());
+        let err = s.encode("utf-8\0", "strict").unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+
+        let bytes = PyBytes::new(py, b"na\xefve");
+        let s = PyString::from_object(bytes.as_ref(), "latin-1", "strict").unwrap();
+        assert_eq!(s.to_string().unwrap(), "naïve");
+        let s = PyString::from_encoded(py, b"na\xefve", "utf-8", "surrogateescape").unwrap();
+        assert_eq!(
+            s.encode("utf-8", "surrogateescape").unwrap().as_bytes(),
+            b"na\xefve"
+        );
+    }
+
+    #[test]
+    fn test_intern() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let a = PyString::intern(py, "attribute");
+        let b = PyString::intern(py, &"attri-bute".replace('-', ""));
+        assert_eq!(a.as_ptr(), b.as_ptr());
+        assert_eq!(a.to_string().unwrap(), "attribute");
+
+        let name = || intern!(py, "attribute");
+        assert_eq!(name().as_ptr(), a.as_ptr());
+        assert_eq!(name().as_ptr(), name().as_ptr());
+
+        let obj = py.eval("type('A', (), {})()", None, None).unwrap();
+        obj.setattr(name(), 42).unwrap();
+        assert_eq!(obj.getattr(name()).unwrap().extract::<i32>().unwrap(), 42);
+        assert!(obj.hasattr(a).unwrap());
+        let s = 42.to_object(py);
+        assert_eq!(
+            s.call_method0(py, intern!(py, "__str__"))
+                .unwrap()
+                .extract::<String>(py)
+                .unwrap(),
+            "42"
+        );
+    }
 
     #[test]
     fn test_non_bmp() {
@@ -200,6 +428,27 @@ mod test {
         assert_eq!(s, py_string.extract::<String>(py).unwrap());
     }
 
+    #[test]
+    fn test_ascii_and_non_ascii() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let cases = [
+            ("identifier_42", "'identifier_42'"),
+            ("\x7f~", "'\\x7f~'"),
+            ("caf\u{e9}", "'caf\\xe9'"),
+            ("\u{ff}", "'\\xff'"),
+            ("ascii \u{1F408}", "'ascii \\U0001f408'"),
+        ];
+        for (s, literal) in cases.iter() {
+            let expected = py.eval(literal, None, None).unwrap();
+            let py_string = PyString::new(py, s);
+            assert_eq!(py_string.compare(expected).unwrap(), Ordering::Equal);
+            assert_eq!(py_string.to_string().unwrap(), *s);
+            let obj = s.to_object(py);
+            assert_eq!(obj.as_ref(py).compare(expected).unwrap(), Ordering::Equal);
+        }
+    }
+
     #[test]
     fn test_extract_str() {
         let gil = Python::acquire_gil();
@@ -263,4 +512,16 @@ mod test {
         let py_string = <PyString as PyTryFrom>::try_from(obj.as_ref(py)).unwrap();
         assert_eq!(py_string.to_string_lossy(), "🐈 Hello ���World");
     }
+
+    #[test]
+    fn test_empty_string_is_singleton() {
+        use crate::{AsPyPointer, IntoPy};
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let empty = py.eval("''", None, None).unwrap();
+        assert_eq!("".to_object(py).as_ptr(), empty.as_ptr());
+        let obj: PyObject = String::new().into_py(py);
+        assert_eq!(obj.as_ptr(), empty.as_ptr());
+        assert_eq!(obj.extract::<String>(py).unwrap(), "");
+    }
 }
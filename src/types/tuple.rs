@@ -312,4 +312,15 @@ mod test {
             assert_eq!(i + 1, item.extract().unwrap());
         }
     }
+
+    #[test]
+    fn test_empty_tuple_is_singleton() {
+        use crate::{AsPyPointer, IntoPy, Py};
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let empty = py.eval("()", None, None).unwrap();
+        let tuple: Py<PyTuple> = ().into_py(py);
+        assert_eq!(tuple.as_ptr(), empty.as_ptr());
+        assert_eq!(PyTuple::empty(py).as_ptr(), empty.as_ptr());
+    }
 }
@@ -4,12 +4,15 @@
 
 use crate::err::{PyErr, PyResult};
 use crate::ffi;
-use crate::instance::{Py, PyNativeType};
+use crate::instance::{AsPyRef, Py, PyNativeType};
 use crate::internal_tricks::Unsendable;
 use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
 use crate::type_object::PyTypeObject;
+use crate::types::{PyAny, PyDict, PyString, PyTuple};
 use crate::AsPyPointer;
-use crate::Python;
+use crate::{IntoPy, Python};
+use crate::{ToBorrowedObject, ToPyObject};
 use std::borrow::Cow;
 use std::ffi::CStr;
 
@@ -20,9 +23,34 @@ pub struct PyType(PyObject, Unsendable);
 pyobject_native_var_type!(PyType, ffi::PyType_Type, ffi::PyType_Check);
 
 impl PyType {
-    #[inline]
-    pub fn new<T: PyTypeObject>() -> Py<PyType> {
-        T::type_object()
+    /// Creates a class at runtime, like the Python expression `type(name, bases, dict)`.
+    ///
+    /// This is for classes whose shape is only known at runtime, e.g. in plugin systems. The
+    /// methods can be Rust closures created with
+    /// [PyCFunction::new_method](struct.PyCFunction.html#method.new_method). Without `bases`, the
+    /// class derives from `object`.
+    ///
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::{IntoPyDict, PyType};
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let base = py.import_type("collections", "OrderedDict").unwrap();
+    /// let dict = [("__module__", "plugins"), ("kind", "registry")].into_py_dict(py);
+    /// let registry = PyType::new(py, "Registry", &[base], Some(dict)).unwrap();
+    /// assert!(registry.is_subclass_of(base).unwrap());
+    /// assert_eq!(registry.getattr("kind").unwrap().extract::<&str>().unwrap(), "registry");
+    /// ```
+    pub fn new<'p>(
+        py: Python<'p>,
+        name: &str,
+        bases: &[&PyType],
+        dict: Option<&PyDict>,
+    ) -> PyResult<&'p PyType> {
+        let dict = dict.unwrap_or_else(|| PyDict::new(py));
+        let args = (name, PyTuple::new(py, bases), dict);
+        Ok(py.get_type::<PyType>().call1(args)?.downcast_ref()?)
     }
 
     /// Retrieves the underlying FFI pointer associated with this Python object.
@@ -44,6 +72,42 @@ impl PyType {
         unsafe { CStr::from_ptr((*self.as_type_ptr()).tp_name).to_string_lossy() }
     }
 
+    /// Resolves the method `name` of this type once, so that it can be called on many instances
+    /// without looking it up again.
+    ///
+    /// Methods implemented in C or Rust, like those of builtin types and `#[pymethods]`, are
+    /// called directly through their C function when the arguments fit its calling convention.
+    /// Otherwise, e.g. for methods defined in Python, calls fall back to
+    /// `getattr(obj, name)(*args, **kwargs)`.
+    pub fn resolve_method(&self, name: &str) -> PyResult<ResolvedMethod> {
+        let py = self.py();
+        let name = PyString::new(py, name);
+        #[cfg(all(not(Py_LIMITED_API), not(PyPy)))]
+        let descr = unsafe {
+            // Borrowed reference, or null without an exception if the type has no such attribute
+            let descr = ffi::_PyType_Lookup(self.as_type_ptr(), name.as_ptr());
+            if descr.is_null() {
+                return Err(PyErr::new::<crate::exceptions::AttributeError, _>(format!(
+                    "type object '{}' has no attribute '{}'",
+                    self.name(),
+                    name.to_string_lossy()
+                )));
+            }
+            if ffi::Py_TYPE(descr) == &mut ffi::PyMethodDescr_Type {
+                Some(PyObject::from_borrowed_ptr(py, descr))
+            } else {
+                None
+            }
+        };
+        #[cfg(any(Py_LIMITED_API, PyPy))]
+        self.getattr(name)?;
+        Ok(ResolvedMethod {
+            name: name.to_object(py),
+            #[cfg(all(not(Py_LIMITED_API), not(PyPy)))]
+            descr,
+        })
+    }
+
     /// Check whether `self` is subclass of type `T` like Python `issubclass` function
     pub fn is_subclass<T>(&self) -> PyResult<bool>
     where
@@ -59,6 +123,50 @@ impl PyType {
         }
     }
 
+    /// Check whether `self` is subclass of `other` like Python `issubclass` function
+    pub fn is_subclass_of(&self, other: &PyType) -> PyResult<bool> {
+        let result = unsafe { ffi::PyObject_IsSubclass(self.as_ptr(), other.as_ptr()) };
+        if result == -1 {
+            Err(PyErr::fetch(self.py()))
+        } else {
+            Ok(result == 1)
+        }
+    }
+
+    /// Creates an instance by calling the type with `args`, then sets the attributes `attrs` on it
+    /// with [setattrs](../trait.ObjectProtocol.html#tymethod.setattrs).
+    ///
+    /// This configures objects which are usually filled in attribute by attribute, like an
+    /// `argparse.Namespace`, from Rust values:
+    ///
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let mut options = BTreeMap::new();
+    /// options.insert("verbose", 1);
+    /// options.insert("jobs", 4);
+    /// let namespace = py.import_type("argparse", "Namespace").unwrap();
+    /// let args = namespace.call_with_attrs((), options).unwrap();
+    /// assert_eq!(args.getattr("jobs").unwrap().extract::<i32>().unwrap(), 4);
+    /// ```
+    pub fn call_with_attrs<I, K, V>(
+        &self,
+        args: impl IntoPy<Py<PyTuple>>,
+        attrs: I,
+    ) -> PyResult<&PyAny>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: ToBorrowedObject,
+    {
+        let obj = self.call1(args)?;
+        obj.setattrs(attrs)?;
+        Ok(obj)
+    }
+
     // Check whether `obj` is an instance of `self`
     pub fn is_instance<T: AsPyPointer>(&self, obj: &T) -> PyResult<bool> {
         let result = unsafe { ffi::PyObject_IsInstance(obj.as_ptr(), self.as_ptr()) };
@@ -71,3 +179,114 @@ impl PyType {
         }
     }
 }
+
+/// A method resolved by [PyType::resolve_method](struct.PyType.html#method.resolve_method).
+///
+/// Unlike looking up the method on every call, an attribute of the same name set on an instance
+/// doesn't shadow a method which is called directly.
+pub struct ResolvedMethod {
+    name: PyObject,
+    /// The method descriptor of a method implemented in C
+    #[cfg(all(not(Py_LIMITED_API), not(PyPy)))]
+    descr: Option<PyObject>,
+}
+
+impl ResolvedMethod {
+    /// Returns whether calls can use the C function of the method directly.
+    pub fn is_direct(&self) -> bool {
+        #[cfg(all(not(Py_LIMITED_API), not(PyPy)))]
+        return self.descr.is_some();
+        #[cfg(any(Py_LIMITED_API, PyPy))]
+        return false;
+    }
+
+    /// Calls the method on `obj`.
+    /// This is equivalent to the Python expression: `obj.name(*args, **kwargs)`.
+    pub fn call<'p>(
+        &self,
+        obj: &'p PyAny,
+        args: impl IntoPy<Py<PyTuple>>,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<&'p PyAny> {
+        let py = obj.py();
+        let args = args.into_py(py);
+        let args = args.as_ref(py);
+        #[cfg(all(not(Py_LIMITED_API), not(PyPy)))]
+        {
+            if let Some(descr) = &self.descr {
+                if let Some(result) = unsafe { call_direct(py, descr, obj, args, kwargs) } {
+                    return result;
+                }
+            }
+        }
+        obj.getattr(&self.name)?.call(args, kwargs)
+    }
+
+    /// Calls the method on `obj` without arguments.
+    /// This is equivalent to the Python expression: `obj.name()`.
+    pub fn call0<'p>(&self, obj: &'p PyAny) -> PyResult<&'p PyAny> {
+        self.call(obj, (), None)
+    }
+
+    /// Calls the method on `obj` with positional arguments only.
+    /// This is equivalent to the Python expression: `obj.name(*args)`.
+    pub fn call1<'p>(&self, obj: &'p PyAny, args: impl IntoPy<Py<PyTuple>>) -> PyResult<&'p PyAny> {
+        self.call(obj, args, None)
+    }
+}
+
+/// Calls the C function of a method descriptor, or returns `None` if `obj` or the arguments
+/// don't fit it.
+#[cfg(all(not(Py_LIMITED_API), not(PyPy)))]
+unsafe fn call_direct<'p>(
+    py: Python<'p>,
+    descr: &PyObject,
+    obj: &PyAny,
+    args: &PyTuple,
+    kwargs: Option<&PyDict>,
+) -> Option<PyResult<&'p PyAny>> {
+    let descr = descr.as_ptr() as *mut ffi::PyMethodDescrObject;
+    // The C function may rely on the layout of its own type
+    if ffi::PyObject_TypeCheck(obj.as_ptr(), (*descr).d_common.d_type) == 0 {
+        return None;
+    }
+    let def = &*(*descr).d_method;
+    let meth = def.ml_meth?;
+    let no_kwargs = kwargs.map_or(true, PyDict::is_empty);
+    let slf = obj.as_ptr();
+    let result = match def.ml_flags & !ffi::METH_COEXIST {
+        ffi::METH_NOARGS if args.is_empty() && no_kwargs => meth(slf, std::ptr::null_mut()),
+        ffi::METH_O if args.len() == 1 && no_kwargs => meth(slf, args.get_item(0).as_ptr()),
+        ffi::METH_VARARGS if no_kwargs => meth(slf, args.as_ptr()),
+        flags if flags == ffi::METH_VARARGS | ffi::METH_KEYWORDS => {
+            let meth: ffi::PyCFunctionWithKeywords = std::mem::transmute(meth);
+            meth(
+                slf,
+                args.as_ptr(),
+                kwargs.map_or(std::ptr::null_mut(), AsPyPointer::as_ptr),
+            )
+        }
+        #[cfg(Py_3_7)]
+        ffi::METH_FASTCALL if no_kwargs => {
+            let meth: ffi::_PyCFunctionFast = std::mem::transmute(meth);
+            let args = args.as_slice();
+            meth(
+                slf,
+                args.as_ptr() as *const *mut ffi::PyObject,
+                args.len() as ffi::Py_ssize_t,
+            )
+        }
+        #[cfg(Py_3_7)]
+        flags if flags == ffi::METH_FASTCALL | ffi::METH_KEYWORDS => {
+            let meth: ffi::_PyCFunctionFastWithKeywords = std::mem::transmute(meth);
+            crate::argparse::call_fast(
+                meth,
+                slf,
+                args.as_ptr(),
+                kwargs.map_or(std::ptr::null_mut(), AsPyPointer::as_ptr),
+            )
+        }
+        _ => return None,
+    };
+    Some(py.from_owned_ptr_or_err(result))
+}
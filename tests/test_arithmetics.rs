@@ -105,6 +105,31 @@ impl PyNumberProtocol for InPlaceOperations {
         self.value |= other;
         Ok(())
     }
+
+    fn __itruediv__(&mut self, other: u32) -> PyResult<()> {
+        self.value /= other;
+        Ok(())
+    }
+
+    fn __ifloordiv__(&mut self, other: u32) -> PyResult<()> {
+        self.value /= other;
+        Ok(())
+    }
+
+    fn __imod__(&mut self, other: u32) -> PyResult<()> {
+        self.value %= other;
+        Ok(())
+    }
+
+    fn __ipow__(&mut self, other: u32, _modulo: &PyAny) -> PyResult<()> {
+        self.value = self.value.pow(other);
+        Ok(())
+    }
+
+    fn __imatmul__(&mut self, other: u32) -> PyResult<()> {
+        self.value *= other + 1;
+        Ok(())
+    }
 }
 
 #[test]
@@ -125,6 +150,63 @@ fn inplace_operations() {
     init(12, "d = c; c &= 10; assert repr(c) == repr(d) == 'IPO(8)'");
     init(12, "d = c; c |= 3; assert repr(c) == repr(d) == 'IPO(15)'");
     init(12, "d = c; c ^= 5; assert repr(c) == repr(d) == 'IPO(9)'");
+    init(12, "d = c; c /= 4; assert repr(c) == repr(d) == 'IPO(3)'");
+    init(12, "d = c; c //= 5; assert repr(c) == repr(d) == 'IPO(2)'");
+    init(12, "d = c; c %= 5; assert repr(c) == repr(d) == 'IPO(2)'");
+    init(3, "d = c; c **= 2; assert repr(c) == repr(d) == 'IPO(9)'");
+    init(3, "d = c; c @= 2; assert repr(c) == repr(d) == 'IPO(9)'");
+}
+
+#[pyclass]
+struct InPlaceFallback {}
+
+#[pyproto]
+impl PyNumberProtocol for InPlaceFallback {
+    fn __add__(_lhs: PyRef<'p, InPlaceFallback>, rhs: &PyAny) -> PyResult<String> {
+        Ok(format!("IPF + {:?}", rhs))
+    }
+
+    fn __iadd__(&mut self, _other: u32) -> PyResult<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn inplace_operations_fall_back_to_binary() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let c = Py::new(py, InPlaceFallback {}).unwrap();
+    py_run!(py, c, "d = c; d += 1; assert d is c");
+    py_run!(py, c, "d = c; d += 'x'; assert d == \"IPF + 'x'\"");
+}
+
+#[pyclass]
+struct MatrixMultiplication {}
+
+#[pyproto]
+impl PyNumberProtocol for MatrixMultiplication {
+    fn __matmul__(lhs: &PyAny, rhs: &PyAny) -> PyResult<String> {
+        Ok(format!("{:?} @ {:?}", lhs, rhs))
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for MatrixMultiplication {
+    fn __repr__(&self) -> PyResult<&'static str> {
+        Ok("MM")
+    }
+}
+
+#[test]
+fn matrix_multiplication() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let c = Py::new(py, MatrixMultiplication {}).unwrap();
+    py_run!(py, c, "assert c @ 1 == 'MM @ 1'");
+    py_run!(py, c, "assert 1 @ c == '1 @ MM'");
+    py_run!(py, c, "d = c; d @= 1; assert d == 'MM @ 1'");
 }
 
 #[pyproto]
@@ -205,9 +287,73 @@ fn rhs_arithmetic() {
 
     let c = Py::new(py, RhsArithmetic {}).unwrap();
     py_run!(py, c, "assert c.__radd__(1) == '1 + RA'");
-    // TODO: commented out for now until reflected arithemtics gets fixed.
-    // see discussion here: https://github.com/PyO3/pyo3/pull/550
-    // py_run!(py, c, "assert 1 + c == '1 + RA'");
+    py_run!(py, c, "assert 1 + c == '1 + RA'");
+    py_expect_exception!(py, c, "c + 1", TypeError);
+}
+
+#[pyclass]
+struct LhsAndRhsArithmetic {}
+
+#[pyproto]
+impl PyNumberProtocol for LhsAndRhsArithmetic {
+    fn __add__(_lhs: PyRef<'p, LhsAndRhsArithmetic>, rhs: &PyAny) -> PyResult<String> {
+        Ok(format!("LRA + {:?}", rhs))
+    }
+
+    fn __radd__(&self, other: &PyAny) -> PyResult<String> {
+        Ok(format!("{:?} + LRA", other))
+    }
+
+    fn __pow__(
+        _lhs: PyRef<'p, LhsAndRhsArithmetic>,
+        rhs: &PyAny,
+        _modulo: &PyAny,
+    ) -> PyResult<String> {
+        Ok(format!("LRA ** {:?}", rhs))
+    }
+
+    fn __rpow__(&self, other: &PyAny) -> PyResult<String> {
+        Ok(format!("{:?} ** LRA", other))
+    }
+}
+
+#[test]
+fn lhs_and_rhs_arithmetic() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let c = Py::new(py, LhsAndRhsArithmetic {}).unwrap();
+    py_run!(py, c, "assert c + 1 == 'LRA + 1'");
+    py_run!(py, c, "assert 1 + c == '1 + LRA'");
+    py_run!(py, c, "assert c ** 1 == 'LRA ** 1'");
+    py_run!(py, c, "assert 1 ** c == '1 ** LRA'");
+    py_expect_exception!(py, c, "pow(1, c, 2)", TypeError);
+}
+
+#[pyclass]
+struct TypedArithmetic {}
+
+#[pyproto]
+impl PyNumberProtocol for TypedArithmetic {
+    fn __mul__(_lhs: PyRef<'p, TypedArithmetic>, rhs: i64) -> PyResult<i64> {
+        Ok(rhs * 2)
+    }
+}
+
+#[test]
+fn binary_arithmetic_not_implemented() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let c = Py::new(py, TypedArithmetic {}).unwrap();
+    py_run!(py, c, "assert c * 21 == 42");
+    py_expect_exception!(py, c, "c * 'a'", TypeError);
+    py_expect_exception!(py, c, "3 * c", TypeError);
+    py_run!(
+        py,
+        c,
+        "class Other:\n    def __rmul__(self, other): return 'Other'\nassert c * Other() == 'Other'"
+    );
 }
 
 #[pyclass]
@@ -301,3 +447,77 @@ fn rich_comparisons_python_3_type_error() {
     py_expect_exception!(py, c2, "c2 >= 1", TypeError);
     py_expect_exception!(py, c2, "1 >= c2", TypeError);
 }
+
+#[pyclass]
+struct RichComparisonsTyped {
+    value: i32,
+}
+
+#[pyproto]
+impl PyObjectProtocol for RichComparisonsTyped {
+    fn __richcmp__(&self, other: i32, op: CompareOp) -> PyResult<bool> {
+        match op {
+            CompareOp::Eq => Ok(self.value == other),
+            CompareOp::Ne => Ok(self.value != other),
+            _ => Ok(false),
+        }
+    }
+}
+
+#[test]
+fn rich_comparisons_not_implemented() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let c = Py::new(py, RichComparisonsTyped { value: 5 }).unwrap();
+    py_run!(py, c, "assert c == 5");
+    py_run!(py, c, "assert c != 6");
+    // Python falls back to identity for == and !=
+    py_run!(py, c, "assert not (c == 'x')");
+    py_run!(py, c, "assert c != 'x'");
+    py_expect_exception!(py, c, "c < 'x'", TypeError);
+    // Python doesn't inherit the hash of `object` for a class overriding the comparisons
+    py_expect_exception!(py, c, "hash(c)", TypeError);
+}
+
+#[pyclass(eq)]
+#[derive(PartialEq)]
+struct DerivedEq {
+    value: i32,
+}
+
+#[pyclass(ord)]
+#[derive(PartialEq, PartialOrd)]
+struct DerivedOrd {
+    major: u32,
+    minor: u32,
+}
+
+#[test]
+fn derived_eq() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let a = Py::new(py, DerivedEq { value: 1 }).unwrap();
+    let b = Py::new(py, DerivedEq { value: 1 }).unwrap();
+    let c = Py::new(py, DerivedEq { value: 2 }).unwrap();
+    py_run!(py, a b c, "assert a == b and not (a != b)");
+    py_run!(py, a b c, "assert a != c and not (a == c)");
+    py_run!(py, a, "assert a != 1");
+    py_expect_exception!(py, a, "a < a", TypeError);
+    py_expect_exception!(py, a, "a >= a", TypeError);
+}
+
+#[test]
+fn derived_ord() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let v1 = Py::new(py, DerivedOrd { major: 1, minor: 2 }).unwrap();
+    let v2 = Py::new(py, DerivedOrd { major: 2, minor: 0 }).unwrap();
+    py_run!(py, v1 v2, "assert v1 < v2 and v1 <= v2 and v1 <= v1");
+    py_run!(py, v1 v2, "assert v2 > v1 and v2 >= v1 and v2 >= v2");
+    py_run!(py, v1 v2, "assert v1 != v2 and not (v1 == v2)");
+    py_run!(py, v1 v2, "assert sorted([v2, v1]) == [v1, v2]");
+    py_expect_exception!(py, v1, "v1 < 1", TypeError);
+}
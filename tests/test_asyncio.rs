@@ -0,0 +1,146 @@
+use pyo3::asyncio::into_awaitable;
+use pyo3::exceptions::ValueError;
+use pyo3::indoc::indoc;
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyAny};
+use pyo3::wrap_pyfunction;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+/// A future completed by another thread after a delay.
+struct Delay {
+    state: Arc<Mutex<(bool, Option<Waker>)>>,
+}
+
+impl Delay {
+    fn new(millis: u64) -> Self {
+        let state = Arc::new(Mutex::new((false, None::<Waker>)));
+        let thread_state = state.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(millis));
+            let mut state = thread_state.lock().unwrap();
+            state.0 = true;
+            if let Some(waker) = state.1.take() {
+                waker.wake();
+            }
+        });
+        Delay { state }
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.0 {
+            Poll::Ready(())
+        } else {
+            state.1 = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[pyfunction]
+fn sleep_then(py: Python, millis: u64, value: u32) -> PyResult<&PyAny> {
+    into_awaitable(py, async move {
+        Delay::new(millis).await;
+        Ok(value)
+    })
+}
+
+#[pyfunction]
+fn fail(py: Python) -> PyResult<&PyAny> {
+    into_awaitable(py, async { Err::<(), _>(ValueError::py_err("failed")) })
+}
+
+static NEVER_DROPPED: AtomicBool = AtomicBool::new(false);
+
+struct SetOnDrop;
+
+impl Drop for SetOnDrop {
+    fn drop(&mut self) {
+        NEVER_DROPPED.store(true, Ordering::SeqCst);
+    }
+}
+
+#[pyfunction]
+fn never(py: Python) -> PyResult<&PyAny> {
+    let guard = SetOnDrop;
+    into_awaitable(py, async move {
+        let _guard = guard;
+        std::future::pending::<()>().await;
+        Ok(())
+    })
+}
+
+#[pyfunction]
+fn never_dropped() -> bool {
+    NEVER_DROPPED.load(Ordering::SeqCst)
+}
+
+#[test]
+fn test_await_rust_futures() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let sleep_then = wrap_pyfunction!(sleep_then)(py);
+    let fail = wrap_pyfunction!(fail)(py);
+    let never = wrap_pyfunction!(never)(py);
+    let never_dropped = wrap_pyfunction!(never_dropped)(py);
+    // The coroutine function looks up the functions in its globals
+    let globals = [
+        ("sleep_then", sleep_then),
+        ("fail", fail),
+        ("never", never),
+        ("never_dropped", never_dropped),
+    ]
+    .into_py_dict(py);
+    globals
+        .set_item("__builtins__", py.import("builtins").unwrap())
+        .unwrap();
+    let code = indoc!(
+        r#"
+        import asyncio
+
+        async def main():
+            assert await asyncio.gather(sleep_then(50, 1), sleep_then(10, 2)) == [1, 2]
+            try:
+                await fail()
+                raise AssertionError("fail() didn't raise")
+            except ValueError as e:
+                assert str(e) == "failed"
+
+            try:
+                await asyncio.wait_for(never(), 0.01)
+                raise AssertionError("never() completed")
+            except asyncio.TimeoutError:
+                pass
+            await asyncio.sleep(0)
+            assert never_dropped()
+
+        loop = asyncio.new_event_loop()
+        try:
+            loop.run_until_complete(main())
+        finally:
+            loop.close()
+        "#
+    );
+    py.run(code, Some(globals), None)
+        .map_err(|e| e.print(py))
+        .unwrap();
+}
+
+#[test]
+#[cfg(Py_3_7)]
+fn test_no_running_loop() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let err = into_awaitable(py, async { Ok(()) }).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::RuntimeError>(py));
+}
@@ -1,8 +1,10 @@
 use pyo3::class::PyBufferProtocol;
 use pyo3::exceptions::BufferError;
 use pyo3::ffi;
+use pyo3::pickle::{self, PickledBuffer};
 use pyo3::prelude::*;
-use pyo3::types::IntoPyDict;
+use pyo3::types::{IntoPyDict, PyBytes};
+use pyo3::PyClassShell;
 use std::ffi::CStr;
 use std::os::raw::{c_int, c_void};
 use std::ptr;
@@ -76,3 +78,75 @@ fn test_buffer() {
     let d = [("ob", t)].into_py_dict(py);
     py.run("assert bytes(ob) == b' 23'", None, Some(d)).unwrap();
 }
+
+#[pyclass(module = "test_pickle_buffer")]
+struct Blob {
+    data: PickledBuffer,
+}
+
+#[pymethods]
+impl Blob {
+    #[new]
+    fn new(data: PickledBuffer) -> Self {
+        Blob { data }
+    }
+
+    fn __reduce_ex__(slf: &PyClassShell<Self>, py: Python, protocol: i32) -> PyResult<PyObject> {
+        pickle::reduce_buffer(py, slf, protocol)
+    }
+}
+
+#[pyproto]
+impl PyBufferProtocol for Blob {
+    fn bf_getbuffer(&self, view: *mut ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+        let (ptr, len) = (self.data.buf_ptr(), self.data.len() as isize);
+        let readonly = self.data.readonly() as c_int;
+        let result =
+            unsafe { ffi::PyBuffer_FillInfo(view, ptr::null_mut(), ptr, len, readonly, flags) };
+        match result {
+            0 => Ok(()),
+            _ => Err(PyErr::fetch(unsafe { Python::assume_gil_acquired() })),
+        }
+    }
+}
+
+#[test]
+fn test_pickle_out_of_band() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let module = PyModule::new(py, "test_pickle_buffer").unwrap();
+    module.add_class::<Blob>().unwrap();
+    py.import("sys")
+        .unwrap()
+        .getattr("modules")
+        .unwrap()
+        .set_item("test_pickle_buffer", module)
+        .unwrap();
+
+    let data = PyBytes::new(py, &[7; 1 << 20]);
+    let blob = Py::new(py, Blob::new(data.extract().unwrap())).unwrap();
+    let d = [("blob", blob.to_object(py))].into_py_dict(py);
+    py.run(
+        r#"
+import pickle
+for protocol in range(2, pickle.HIGHEST_PROTOCOL + 1):
+    assert bytes(pickle.loads(pickle.dumps(blob, protocol))) == bytes(blob)
+if pickle.HIGHEST_PROTOCOL >= 5:
+    buffers = []
+    pickled = pickle.dumps(blob, 5, buffer_callback=buffers.append)
+    assert len(buffers) == 1 and len(pickled) < 1000
+    copy = pickle.loads(pickled, buffers=buffers)
+    del buffers
+else:
+    copy = blob
+"#,
+        None,
+        Some(d),
+    )
+    .unwrap();
+
+    let copy: PyRef<Blob> = d.get_item("copy").unwrap().extract().unwrap();
+    assert_eq!(copy.data.to_vec(py), vec![7; 1 << 20]);
+    // The data wasn't copied
+    assert_eq!(copy.data.buf_ptr() as *const u8, data.as_bytes().as_ptr());
+}
@@ -0,0 +1,79 @@
+use pyo3::prelude::*;
+use pyo3::py_run;
+
+mod common;
+
+#[pyclass]
+struct Foo {
+    #[pyo3(get)]
+    x: i32,
+}
+
+#[pyclass]
+struct Bar {
+    #[pyo3(get)]
+    x: i32,
+}
+
+#[pymethods]
+impl Foo {
+    #[classattr]
+    const MY_CONST: &'static str = "foobar";
+
+    #[classattr]
+    #[name = "RENAMED_CONST"]
+    const MY_CONST_2: &'static str = "foobar_2";
+
+    #[classattr]
+    fn a() -> i32 {
+        5
+    }
+
+    #[classattr]
+    #[name = "B"]
+    fn b() -> String {
+        "bar".to_string()
+    }
+
+    #[classattr]
+    fn bar() -> Bar {
+        Bar { x: 2 }
+    }
+
+    #[classattr]
+    fn a_foo() -> Foo {
+        Foo { x: 1 }
+    }
+
+    #[classattr]
+    fn checked() -> PyResult<Vec<u8>> {
+        Ok(vec![1, 2])
+    }
+}
+
+#[test]
+fn class_attributes() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let foo_obj = py.get_type::<Foo>();
+    py_assert!(py, foo_obj, "foo_obj.MY_CONST == 'foobar'");
+    py_assert!(py, foo_obj, "foo_obj.RENAMED_CONST == 'foobar_2'");
+    py_assert!(py, foo_obj, "not hasattr(foo_obj, 'MY_CONST_2')");
+    py_assert!(py, foo_obj, "foo_obj.a == 5");
+    py_assert!(py, foo_obj, "foo_obj.B == 'bar'");
+    py_assert!(py, foo_obj, "foo_obj.bar.x == 2");
+    py_assert!(py, foo_obj, "foo_obj.checked == [1, 2]");
+    // Instances of the class itself, e.g. for enum-like constants
+    py_assert!(py, foo_obj, "isinstance(foo_obj.a_foo, foo_obj)");
+    py_assert!(py, foo_obj, "foo_obj.a_foo.x == 1");
+    // Also visible on instances
+    py_assert!(py, foo_obj, "foo_obj.a_foo.MY_CONST == 'foobar'");
+}
+
+#[test]
+fn class_attributes_are_immutable() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let foo_obj = py.get_type::<Foo>();
+    py_expect_exception!(py, foo_obj, "foo_obj.a = 6", TypeError);
+}
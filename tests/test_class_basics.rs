@@ -1,5 +1,5 @@
 use pyo3::prelude::*;
-use pyo3::py_run;
+use pyo3::{py_run, AsPyPointer};
 
 mod common;
 
@@ -94,6 +94,27 @@ fn custom_names() {
     py_assert!(py, typeobj, "not hasattr(typeobj, 'bar_static')");
 }
 
+#[pyclass(name = "type", module = "mypkg.sub")]
+struct NamedInPackage {}
+
+#[test]
+fn custom_name_and_module() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let typeobj = py.get_type::<NamedInPackage>();
+    py_assert!(py, typeobj, "typeobj.__name__ == 'type'");
+    py_assert!(py, typeobj, "typeobj.__qualname__ == 'type'");
+    py_assert!(py, typeobj, "typeobj.__module__ == 'mypkg.sub'");
+    py_assert!(py, typeobj, "repr(typeobj) == \"<class 'mypkg.sub.type'>\"");
+
+    let obj = Py::new(py, NamedInPackage {}).unwrap();
+    py_assert!(
+        py,
+        obj,
+        "repr(obj).startswith('<mypkg.sub.type object at ')"
+    );
+}
+
 #[pyclass]
 struct RawIdents {
     #[pyo3(get, set)]
@@ -163,3 +184,95 @@ fn class_with_object_field() {
     py_assert!(py, ty, "ty(5).value == 5");
     py_assert!(py, ty, "ty(None).value == None");
 }
+
+#[pyclass]
+struct CreatedConcurrently {}
+
+#[test]
+fn type_object_created_concurrently() {
+    // Make sure the other threads can run while the type object is created
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let types: Vec<usize> = py.allow_threads(|| {
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    let gil = Python::acquire_gil();
+                    let py = gil.python();
+                    py.get_type::<CreatedConcurrently>().as_ptr() as usize
+                })
+            })
+            .collect();
+        threads.into_iter().map(|t| t.join().unwrap()).collect()
+    });
+    assert!(types.iter().all(|&t| t == types[0]));
+    let typeobj = py.get_type::<CreatedConcurrently>();
+    py_assert!(py, typeobj, "typeobj.__name__ == 'CreatedConcurrently'");
+}
+
+#[pyclass(debug, customize = customize_repr)]
+#[derive(Debug)]
+struct CustomizedSlots {}
+
+unsafe extern "C" fn fixed_repr(_slf: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject {
+    pyo3::ffi::PyUnicode_FromString("customized\0".as_ptr() as *const _)
+}
+
+fn customize_repr(type_object: &mut pyo3::ffi::PyTypeObject) {
+    type_object.tp_repr = Some(fixed_repr);
+    type_object.tp_flags |= pyo3::ffi::Py_TPFLAGS_BASETYPE;
+}
+
+#[test]
+fn customized_type_object() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj = Py::new(py, CustomizedSlots {}).unwrap();
+    py_assert!(py, obj, "repr(obj) == 'customized'");
+
+    let ty = py.get_type::<CustomizedSlots>();
+    py_run!(py, ty, "class Subclass(ty): pass");
+}
+
+#[pyclass]
+struct RawMethods {
+    value: i32,
+}
+
+#[pymethods]
+impl RawMethods {
+    fn value(&self) -> i32 {
+        self.value
+    }
+}
+
+unsafe extern "C" fn raw_identity(
+    _slf: *mut pyo3::ffi::PyObject,
+    arg: *mut pyo3::ffi::PyObject,
+) -> *mut pyo3::ffi::PyObject {
+    pyo3::ffi::Py_INCREF(arg);
+    arg
+}
+
+pyo3::py_raw_methods!(
+    RawMethods,
+    [pyo3::class::PyMethodDefType::Method(
+        pyo3::class::PyMethodDef {
+            ml_name: "identity",
+            ml_meth: pyo3::class::PyMethodType::PyCFunction(raw_identity),
+            ml_flags: pyo3::ffi::METH_O,
+            ml_doc: "Returns its argument\0",
+        }
+    )]
+);
+
+#[test]
+fn raw_methods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj = Py::new(py, RawMethods { value: 3 }).unwrap();
+    py_assert!(py, obj, "obj.identity([1]) == [1]");
+    py_assert!(py, obj, "obj.identity.__doc__ == 'Returns its argument'");
+    py_assert!(py, obj, "obj.value() == 3");
+    py_expect_exception!(py, obj, "obj.identity()", TypeError);
+}
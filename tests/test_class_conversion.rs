@@ -1,4 +1,5 @@
 use pyo3::prelude::*;
+use pyo3::PyRefMut;
 
 #[pyclass]
 #[derive(Clone, Debug, PartialEq)]
@@ -16,10 +17,13 @@ fn test_cloneable_pyclass() {
     let py_c = Py::new(py, c.clone()).unwrap().to_object(py);
 
     let c2: Cloneable = py_c.extract(py).unwrap();
-    let rc: &Cloneable = py_c.extract(py).unwrap();
-    let mrc: &mut Cloneable = py_c.extract(py).unwrap();
-
     assert_eq!(c, c2);
-    assert_eq!(&c, rc);
-    assert_eq!(&c, mrc);
+    {
+        let rc: PyRef<Cloneable> = py_c.extract(py).unwrap();
+        assert_eq!(c, *rc);
+        // Can't be mutably borrowed while `rc` is alive
+        assert!(py_c.extract::<PyRefMut<Cloneable>>(py).is_err());
+    }
+    let mrc: PyRefMut<Cloneable> = py_c.extract(py).unwrap();
+    assert_eq!(c, *mrc);
 }
@@ -19,7 +19,7 @@ fn empty_class_with_new() {
     assert!(typeobj
         .call((), None)
         .unwrap()
-        .cast_as::<EmptyClassWithNew>()
+        .cast_as::<PyClassShell<EmptyClassWithNew>>()
         .is_ok());
 }
 
@@ -43,8 +43,8 @@ fn new_with_one_arg() {
     let py = gil.python();
     let typeobj = py.get_type::<NewWithOneArg>();
     let wrp = typeobj.call((42,), None).unwrap();
-    let obj = wrp.cast_as::<NewWithOneArg>().unwrap();
-    assert_eq!(obj._data, 42);
+    let obj = wrp.cast_as::<PyClassShell<NewWithOneArg>>().unwrap();
+    assert_eq!(obj.borrow()._data, 42);
 }
 
 #[pyclass]
@@ -73,7 +73,10 @@ fn new_with_two_args() {
         .call((10, 20), None)
         .map_err(|e| e.print(py))
         .unwrap();
-    let obj = wrp.cast_as::<NewWithTwoArgs>().unwrap();
+    let obj = wrp
+        .cast_as::<PyClassShell<NewWithTwoArgs>>()
+        .unwrap()
+        .borrow();
     assert_eq!(obj._data1, 10);
     assert_eq!(obj._data2, 20);
 }
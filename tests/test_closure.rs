@@ -0,0 +1,117 @@
+use pyo3::exceptions::ValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction, PyModule};
+use pyo3::{py_run, GILPool};
+use std::sync::Arc;
+
+mod common;
+
+#[test]
+fn closure_with_state() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let mut total = 0;
+    let add = PyCFunction::new_closure(py, "add", "Adds to the total", move |args, kwargs| {
+        let value: i64 = args.get_item(0).extract()?;
+        let times = match kwargs.and_then(|kwargs| kwargs.get_item("times")) {
+            Some(times) => times.extract()?,
+            None => 1,
+        };
+        if value < 0 {
+            return Err(ValueError::py_err("negative value"));
+        }
+        total += value * times;
+        Ok(total)
+    })
+    .unwrap();
+
+    py_run!(
+        py,
+        add,
+        r#"
+        assert add(1) == 1
+        assert add(2, times=3) == 7
+        assert add.__name__ == "add"
+        assert add.__doc__ == "Adds to the total"
+        "#
+    );
+    py_expect_exception!(py, add, "add(-1)", ValueError);
+    py_expect_exception!(py, add, "add('a')", TypeError);
+    py_assert!(py, add, "add(0) == 7");
+}
+
+#[test]
+fn registered_callback() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let module = PyModule::new(py, "events").unwrap();
+    let mut events = Vec::new();
+    let on_event = PyCFunction::new_closure(py, "on_event", "", move |args, _| {
+        events.push(args.get_item(0).extract::<String>()?);
+        Ok(events.join(","))
+    })
+    .unwrap();
+    module.add("on_event", on_event).unwrap();
+    py_run!(
+        py,
+        module,
+        r#"
+        assert "on_event" in module.__all__
+        module.on_event("start")
+        assert module.on_event("stop") == "start,stop"
+        "#
+    );
+}
+
+#[test]
+fn reentrant_call() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let call = PyCFunction::new_closure(py, "call", "", |args, _| {
+        args.get_item(0).call1((args.get_item(0),))?;
+        Ok(())
+    })
+    .unwrap();
+    py_expect_exception!(py, call, "call(call)", RuntimeError);
+    py_expect_exception!(py, call, "call(len)", TypeError);
+    py_run!(py, call, "call(lambda f: None)");
+}
+
+#[test]
+fn closure_after_panic() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let mut calls = 0;
+    let flaky = PyCFunction::new_closure(py, "flaky", "", move |_, _| {
+        calls += 1;
+        if calls == 1 {
+            panic!("first call");
+        }
+        Ok(calls)
+    })
+    .unwrap();
+    py_expect_exception!(py, flaky, "flaky()", RuntimeError);
+    py_assert!(py, flaky, "flaky() == 2");
+}
+
+#[test]
+fn closure_dropped_with_function() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let state = Arc::new(());
+
+    {
+        let _pool = GILPool::new(py);
+        let captured = state.clone();
+        let function = PyCFunction::new_closure(py, "count", "", move |_, _| {
+            Ok(Arc::strong_count(&captured))
+        })
+        .unwrap();
+        py_assert!(py, function, "function() == 2");
+    }
+    assert_eq!(Arc::strong_count(&state), 1);
+}
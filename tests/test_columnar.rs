@@ -0,0 +1,121 @@
+use pyo3::columnar::Columnar;
+use pyo3::prelude::*;
+use pyo3::types::IntoPyDict;
+
+mod common;
+
+#[derive(Columnar, Debug, PartialEq)]
+struct Row {
+    id: u32,
+    name: String,
+    score: Option<f64>,
+    r#type: bool,
+}
+
+fn rows() -> Vec<Row> {
+    vec![
+        Row {
+            id: 1,
+            name: "one".to_string(),
+            score: Some(0.5),
+            r#type: true,
+        },
+        Row {
+            id: 2,
+            name: "two".to_string(),
+            score: None,
+            r#type: false,
+        },
+    ]
+}
+
+#[test]
+fn columns_roundtrip() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    assert_eq!(Row::COLUMNS, &["id", "name", "score", "type"]);
+
+    let columns = Row::to_columns(py, &rows()).unwrap();
+    py_assert!(py, columns, "columns['id'] == [1, 2]");
+    py_assert!(py, columns, "columns['name'] == ['one', 'two']");
+    py_assert!(py, columns, "columns['score'] == [0.5, None]");
+    py_assert!(py, columns, "columns['type'] == [True, False]");
+    assert_eq!(Row::from_columns(columns).unwrap(), rows());
+
+    let empty = Row::to_columns(py, &[]).unwrap();
+    py_assert!(
+        py,
+        empty,
+        "empty == {'id': [], 'name': [], 'score': [], 'type': []}"
+    );
+    assert_eq!(Row::from_columns(empty).unwrap(), vec![]);
+}
+
+#[test]
+fn columns_from_other_sequences() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let columns = py
+        .eval(
+            "{'id': array.array('I', [1, 2]), 'name': ('one', 'two'), \
+             'score': [0.5, None], 'type': [True, False], 'ignored': []}",
+            None,
+            Some([("array", py.import("array").unwrap())].into_py_dict(py)),
+        )
+        .unwrap()
+        .downcast_ref()
+        .unwrap();
+    assert_eq!(Row::from_columns(columns).unwrap(), rows());
+}
+
+#[test]
+fn invalid_columns() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let missing = [("id", vec![1])].into_py_dict(py);
+    let err = Row::from_columns(missing).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::KeyError>(py));
+
+    let columns = Row::to_columns(py, &rows()).unwrap();
+    columns.set_item("name", vec!["one"]).unwrap();
+    let err = Row::from_columns(columns).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+
+    columns.set_item("name", vec![1, 2]).unwrap();
+    let err = Row::from_columns(columns).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+}
+
+#[derive(Columnar)]
+struct Pair<T> {
+    first: T,
+    second: T,
+}
+
+#[test]
+fn generic_columns() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let pairs = vec![
+        Pair {
+            first: 1,
+            second: 2,
+        },
+        Pair {
+            first: 3,
+            second: 4,
+        },
+    ];
+    let columns = Pair::to_columns(py, &pairs).unwrap();
+    py_assert!(
+        py,
+        columns,
+        "columns == {'first': [1, 3], 'second': [2, 4]}"
+    );
+    let pairs: Vec<Pair<i64>> = Pair::from_columns(columns).unwrap();
+    assert_eq!(pairs[1].second, 4);
+}
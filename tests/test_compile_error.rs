@@ -1,8 +1,12 @@
 #[test]
 fn test_compile_errors() {
     let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/invalid_columnar.rs");
+    t.compile_fail("tests/ui/invalid_int_enum.rs");
     t.compile_fail("tests/ui/invalid_property_args.rs");
+    t.compile_fail("tests/ui/invalid_pyconst.rs");
     t.compile_fail("tests/ui/invalid_pymethod_names.rs");
+    t.compile_fail("tests/ui/invalid_pymethods.rs");
     t.compile_fail("tests/ui/missing_clone.rs");
     t.compile_fail("tests/ui/reject_generics.rs");
 }
@@ -104,16 +104,13 @@ fn test_delta_check() {
 #[test]
 fn test_datetime_utc() {
     use assert_approx_eq::assert_approx_eq;
-    use pyo3::types::PyDateTime;
+    use pyo3::types::{timezone_utc, PyDateTime};
 
     let gil = Python::acquire_gil();
     let py = gil.python();
 
-    let datetime = py.import("datetime").map_err(|e| e.print(py)).unwrap();
-    let timezone = datetime.get("timezone").unwrap();
-    let utc = timezone.getattr("utc").unwrap().to_object(py);
-
-    let dt = PyDateTime::new(py, 2018, 1, 1, 0, 0, 0, 0, Some(&utc)).unwrap();
+    let utc = timezone_utc(py).unwrap();
+    let dt = PyDateTime::new(py, 2018, 1, 1, 0, 0, 0, 0, Some(utc)).unwrap();
 
     let locals = [("dt", dt)].into_py_dict(py);
 
@@ -207,3 +204,98 @@ fn test_pydatetime_out_of_bounds() {
         dt.unwrap_err();
     }
 }
+
+#[test]
+fn test_timezones() {
+    use pyo3::types::{fixed_offset, timezone_utc, zoneinfo, PyDateTime};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let utc = timezone_utc(py).unwrap();
+    let paris_winter = fixed_offset(py, 3600).unwrap();
+    let dt = PyDateTime::new(py, 2020, 1, 1, 12, 0, 0, 0, Some(paris_winter)).unwrap();
+    let locals = [("dt", dt.as_ref()), ("utc", utc.as_ref())].into_py_dict(py);
+    py.run(
+        "assert dt.utcoffset().total_seconds() == 3600\nassert dt.astimezone(utc).hour == 11",
+        None,
+        Some(locals),
+    )
+    .unwrap();
+
+    let err = fixed_offset(py, 24 * 3600).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+
+    match zoneinfo(py, "Europe/Paris") {
+        Ok(paris) => {
+            let dt = PyDateTime::new(py, 2020, 7, 1, 12, 0, 0, 0, Some(paris)).unwrap();
+            let locals = [("dt", dt)].into_py_dict(py);
+            py.run(
+                "assert dt.utcoffset().total_seconds() == 7200",
+                None,
+                Some(locals),
+            )
+            .unwrap();
+        }
+        // Python < 3.9, or no time zone database
+        Err(err) => assert!(
+            err.is_instance::<pyo3::exceptions::ImportError>(py) || py.import("zoneinfo").is_ok()
+        ),
+    }
+}
+
+#[test]
+fn test_duration_policies() {
+    use pyo3::types::{AbsDuration, SaturatingDuration};
+    use std::time::Duration;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let delta = |expr: &str| {
+        py.eval(
+            &format!("__import__('datetime').timedelta({})", expr),
+            None,
+            None,
+        )
+        .unwrap()
+    };
+
+    let positive = delta("days=1, seconds=2, microseconds=3");
+    let expected = Duration::new(86_402, 3_000);
+    assert_eq!(positive.extract::<Duration>().unwrap(), expected);
+    assert_eq!(
+        positive.extract::<SaturatingDuration>().unwrap().0,
+        expected
+    );
+    assert_eq!(positive.extract::<AbsDuration>().unwrap().0, expected);
+
+    // Stored as days=-1, seconds=86399, microseconds=999999
+    let negative = delta("microseconds=-1");
+    let err = negative.extract::<Duration>().unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+    assert_eq!(
+        negative.extract::<SaturatingDuration>().unwrap().0,
+        Duration::from_secs(0)
+    );
+    assert_eq!(
+        negative.extract::<AbsDuration>().unwrap().0,
+        Duration::from_micros(1)
+    );
+
+    let min = delta("days=-999999999");
+    assert_eq!(
+        min.extract::<AbsDuration>().unwrap().0,
+        Duration::from_secs(999_999_999 * 86_400)
+    );
+    let max = delta("days=999999999, seconds=86399, microseconds=999999");
+    assert_eq!(
+        max.extract::<Duration>().unwrap(),
+        Duration::new(999_999_999 * 86_400 + 86_399, 999_999_000)
+    );
+
+    assert!(py
+        .eval("1", None, None)
+        .unwrap()
+        .extract::<Duration>()
+        .is_err());
+}
@@ -1,12 +1,15 @@
 #![feature(specialization)]
 
+use pyo3::asyncio::into_awaitable;
 use pyo3::class::{
-    PyContextProtocol, PyIterProtocol, PyMappingProtocol, PyObjectProtocol, PySequenceProtocol,
+    PyAsyncProtocol, PyContextProtocol, PyDescrProtocol, PyIterProtocol, PyMappingProtocol,
+    PyNumberProtocol, PyObjectProtocol, PySequenceProtocol,
 };
-use pyo3::exceptions::{IndexError, ValueError};
+use pyo3::exceptions::{AttributeError, IndexError, ValueError};
 use pyo3::prelude::*;
-use pyo3::types::{IntoPyDict, PyAny, PyBytes, PySlice, PyType};
-use pyo3::{ffi, py_run, AsPyPointer, PyClassShell};
+use pyo3::types::{IntoPyDict, PyAny, PyBytes, PyDict, PySlice, PyTuple, PyType};
+use pyo3::{ffi, indoc, py_run, AsPyPointer, PyClassShell, PyNativeType};
+use std::cell::Cell;
 use std::convert::TryFrom;
 use std::{isize, iter};
 
@@ -53,12 +56,12 @@ struct Iterator {
 
 #[pyproto]
 impl<'p> PyIterProtocol for Iterator {
-    fn __iter__(slf: &mut PyClassShell<Self>) -> PyResult<Py<Iterator>> {
+    fn __iter__(slf: &PyClassShell<Self>) -> PyResult<Py<Iterator>> {
         Ok(slf.into())
     }
 
-    fn __next__(slf: &mut PyClassShell<Self>) -> PyResult<Option<i32>> {
-        Ok(slf.iter.next())
+    fn __next__(slf: &PyClassShell<Self>) -> PyResult<Option<i32>> {
+        Ok(slf.try_borrow_mut()?.iter.next())
     }
 }
 
@@ -113,6 +116,61 @@ fn string_methods() {
     py_assert!(py, obj, "bytes(obj) == b'bytes'");
 }
 
+#[pyclass]
+struct StringPyMethods {
+    name: String,
+}
+
+#[pymethods]
+impl StringPyMethods {
+    fn __repr__(&self) -> String {
+        format!("StringPyMethods(name={})", self.name)
+    }
+
+    fn __str__(&self) -> PyResult<&str> {
+        Ok(&self.name)
+    }
+}
+
+#[test]
+fn string_pymethods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let obj = Py::new(
+        py,
+        StringPyMethods {
+            name: "abc".to_string(),
+        },
+    )
+    .unwrap();
+    py_assert!(py, obj, "repr(obj) == 'StringPyMethods(name=abc)'");
+    py_assert!(py, obj, "str(obj) == 'abc'");
+    py_assert!(py, obj, "'{}'.format(obj) == 'abc'");
+}
+
+#[pyclass(debug, display)]
+#[derive(Debug)]
+struct DerivedStrings {
+    x: i32,
+}
+
+impl std::fmt::Display for DerivedStrings {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "x is {}", self.x)
+    }
+}
+
+#[test]
+fn derived_string_methods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let obj = Py::new(py, DerivedStrings { x: 3 }).unwrap();
+    py_assert!(py, obj, "repr(obj) == 'DerivedStrings { x: 3 }'");
+    py_assert!(py, obj, "str(obj) == 'x is 3'");
+}
+
 #[pyclass]
 struct Comparisons {
     val: i32,
@@ -145,6 +203,39 @@ fn comparisons() {
     py_assert!(py, zero, "not zero");
 }
 
+#[pyclass(eq, hash)]
+#[derive(PartialEq, Hash)]
+struct DerivedHash {
+    name: String,
+}
+
+#[test]
+fn derived_hash() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let a = Py::new(py, DerivedHash { name: "a".into() }).unwrap();
+    let a2 = Py::new(py, DerivedHash { name: "a".into() }).unwrap();
+    let b = Py::new(py, DerivedHash { name: "b".into() }).unwrap();
+    py_run!(py, a a2, "assert hash(a) == hash(a2)");
+    py_run!(py, a a2 b, "assert len({a: 1, a2: 2, b: 3}) == 2");
+    py_run!(py, a a2, "assert {a: 1}[a2] == 1");
+}
+
+#[pyclass(eq)]
+#[derive(PartialEq)]
+struct Unhashable {}
+
+#[test]
+fn unhashable() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let obj = Py::new(py, Unhashable {}).unwrap();
+    py_expect_exception!(py, obj, "hash(obj)", TypeError);
+    py_assert!(py, obj, "type(obj).__hash__ is None");
+}
+
 #[pyclass]
 #[derive(Debug)]
 struct Sequence {
@@ -230,6 +321,52 @@ fn callable() {
     py_assert!(py, nc, "not callable(nc)");
 }
 
+#[pyclass]
+struct CallableWithArgs {
+    prefix: String,
+}
+
+#[pymethods]
+impl CallableWithArgs {
+    #[args(sep = "\"-\"", args = "*", kwargs = "**")]
+    fn __call__(
+        &self,
+        name: &str,
+        sep: &str,
+        args: &PyTuple,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<String> {
+        Ok(format!(
+            "{}{}{} {} {}",
+            self.prefix,
+            sep,
+            name,
+            args.len(),
+            kwargs.map_or(0, |kwargs| kwargs.len())
+        ))
+    }
+}
+
+#[test]
+fn callable_without_attribute() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let c = Py::new(
+        py,
+        CallableWithArgs {
+            prefix: "p".to_string(),
+        },
+    )
+    .unwrap();
+    py_assert!(py, c, "callable(c)");
+    py_assert!(py, c, "c('x') == 'p-x 0 0'");
+    py_assert!(py, c, "c('x', '+') == 'p+x 0 0'");
+    py_assert!(py, c, "c(name='x', sep='_') == 'p_x 0 0'");
+    py_assert!(py, c, "c('x', '-', 1, 2, a=3) == 'p-x 2 1'");
+    py_expect_exception!(py, c, "c()", TypeError);
+}
+
 #[pyclass]
 struct SetItem {
     key: i32,
@@ -252,8 +389,8 @@ fn setitem() {
 
     let c = PyClassShell::new_ref(py, SetItem { key: 0, val: 0 }).unwrap();
     py_run!(py, c, "c[1] = 2");
-    assert_eq!(c.key, 1);
-    assert_eq!(c.val, 2);
+    assert_eq!(c.borrow().key, 1);
+    assert_eq!(c.borrow().val, 2);
     py_expect_exception!(py, c, "del c[1]", NotImplementedError);
 }
 
@@ -277,7 +414,7 @@ fn delitem() {
 
     let c = PyClassShell::new_ref(py, DelItem { key: 0 }).unwrap();
     py_run!(py, c, "del c[1]");
-    assert_eq!(c.key, 1);
+    assert_eq!(c.borrow().key, 1);
     py_expect_exception!(py, c, "c[1] = 2", NotImplementedError);
 }
 
@@ -306,9 +443,9 @@ fn setdelitem() {
 
     let c = PyClassShell::new_ref(py, SetDelItem { val: None }).unwrap();
     py_run!(py, c, "c[1] = 2");
-    assert_eq!(c.val, Some(2));
+    assert_eq!(c.borrow().val, Some(2));
     py_run!(py, c, "del c[1]");
-    assert_eq!(c.val, None);
+    assert_eq!(c.borrow().val, None);
 }
 
 #[pyclass]
@@ -383,22 +520,171 @@ fn context_manager() {
     let gil = Python::acquire_gil();
     let py = gil.python();
 
-    let c = PyClassShell::new_mut(py, ContextManager { exit_called: false }).unwrap();
+    let c = PyClassShell::new_ref(py, ContextManager { exit_called: false }).unwrap();
     py_run!(py, c, "with c as x: assert x == 42");
-    assert!(c.exit_called);
+    assert!(c.borrow().exit_called);
 
-    c.exit_called = false;
+    c.borrow_mut().exit_called = false;
     py_run!(py, c, "with c as x: raise ValueError");
-    assert!(c.exit_called);
+    assert!(c.borrow().exit_called);
 
-    c.exit_called = false;
+    c.borrow_mut().exit_called = false;
     py_expect_exception!(
         py,
         c,
         "with c as x: raise NotImplementedError",
         NotImplementedError
     );
-    assert!(c.exit_called);
+    assert!(c.borrow().exit_called);
+}
+
+#[pyclass]
+struct ContextManagerMethods {
+    entered: bool,
+    exit_called: bool,
+}
+
+#[pymethods]
+impl ContextManagerMethods {
+    fn __enter__(&mut self) -> i32 {
+        self.entered = true;
+        42
+    }
+
+    fn __exit__(
+        &mut self,
+        py: Python,
+        ty: Option<&PyType>,
+        _value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> bool {
+        self.exit_called = true;
+        ty == Some(py.get_type::<ValueError>())
+    }
+}
+
+#[test]
+fn context_manager_pymethods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let c = PyClassShell::new_ref(
+        py,
+        ContextManagerMethods {
+            entered: false,
+            exit_called: false,
+        },
+    )
+    .unwrap();
+    py_run!(py, c, "with c as x: assert x == 42");
+    assert!(c.borrow().entered);
+    assert!(c.borrow().exit_called);
+
+    c.borrow_mut().exit_called = false;
+    py_run!(py, c, "with c as x: raise ValueError");
+    assert!(c.borrow().exit_called);
+
+    c.borrow_mut().exit_called = false;
+    py_expect_exception!(py, c, "with c as x: raise IndexError", IndexError);
+    assert!(c.borrow().exit_called);
+}
+
+#[pyclass]
+struct AsyncCounter {
+    count: u32,
+    limit: u32,
+    exit_called: bool,
+}
+
+impl AsyncCounter {
+    fn new(limit: u32) -> Self {
+        AsyncCounter {
+            count: 0,
+            limit,
+            exit_called: false,
+        }
+    }
+}
+
+/// An awaitable completing with `value`.
+fn ready<T>(value: T) -> PyResult<PyObject>
+where
+    T: IntoPy<PyObject> + Send + 'static,
+{
+    let gil = GILGuard::acquire();
+    let py = gil.python();
+    Ok(into_awaitable(py, async move { Ok(value) })?.to_object(py))
+}
+
+#[pyproto]
+impl<'p> PyAsyncProtocol<'p> for AsyncCounter {
+    fn __await__(&self) -> PyResult<PyObject> {
+        let gil = GILGuard::acquire();
+        let py = gil.python();
+        Ok(ready(self.count)?.call_method0(py, "__await__")?)
+    }
+
+    fn __aiter__(&self) -> PyResult<AsyncCounter> {
+        Ok(AsyncCounter::new(self.limit))
+    }
+
+    fn __anext__(&mut self) -> PyResult<Option<PyObject>> {
+        if self.count == self.limit {
+            return Ok(None);
+        }
+        self.count += 1;
+        ready(self.count).map(Some)
+    }
+
+    fn __aenter__(&mut self) -> PyResult<PyObject> {
+        ready(42)
+    }
+
+    fn __aexit__(
+        &mut self,
+        ty: Option<&'p PyType>,
+        _value: Option<&'p PyAny>,
+        _traceback: Option<&'p PyAny>,
+    ) -> PyResult<PyObject> {
+        let gil = GILGuard::acquire();
+        self.exit_called = true;
+        ready(ty == Some(gil.python().get_type::<ValueError>()))
+    }
+}
+
+#[test]
+fn async_protocol() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let c = PyClassShell::new_ref(py, AsyncCounter::new(3)).unwrap();
+    let globals = [("c", c)].into_py_dict(py);
+    globals
+        .set_item("__builtins__", py.import("builtins").unwrap())
+        .unwrap();
+    let code = indoc::indoc!(
+        r#"
+        import asyncio
+
+        async def main():
+            assert await c == 0
+            assert [x async for x in c] == [1, 2, 3]
+            async with c as x:
+                assert x == 42
+            async with c:
+                raise ValueError
+
+        loop = asyncio.new_event_loop()
+        try:
+            loop.run_until_complete(main())
+        finally:
+            loop.close()
+        "#
+    );
+    py.run(code, Some(globals), None)
+        .map_err(|e| e.print(py))
+        .unwrap();
+    assert!(c.borrow().exit_called);
 }
 
 #[test]
@@ -517,3 +803,177 @@ fn getattr_doesnt_override_member() {
     py_assert!(py, inst, "inst.data == 4");
     py_assert!(py, inst, "inst.a == 8");
 }
+
+#[pyclass]
+struct Descriptor {
+    value: Cell<Option<i32>>,
+}
+
+#[pyproto]
+impl<'p> PyDescrProtocol<'p> for Descriptor {
+    fn __get__(
+        &'p self,
+        instance: Option<&'p PyAny>,
+        owner: Option<&'p PyType>,
+    ) -> PyResult<PyObject> {
+        let py = owner
+            .expect("the owner is passed by the attribute lookup")
+            .py();
+        match instance {
+            None => Ok(py.None()),
+            Some(_) => match self.value.get() {
+                Some(value) => Ok(value.to_object(py)),
+                None => Err(PyErr::new::<AttributeError, _>("unset")),
+            },
+        }
+    }
+
+    fn __set__(&'p self, _instance: &'p PyAny, value: i32) -> PyResult<()> {
+        self.value.set(Some(value));
+        Ok(())
+    }
+
+    fn __delete__(&'p self, _instance: &'p PyAny) -> PyResult<()> {
+        self.value.set(None);
+        Ok(())
+    }
+}
+
+#[test]
+fn descriptor() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let descr = Py::new(
+        py,
+        Descriptor {
+            value: Cell::new(None),
+        },
+    )
+    .unwrap();
+    py_run!(
+        py,
+        descr,
+        r#"
+        C = type("C", (), {"attr": descr})
+
+        assert C.attr is None
+        c = C()
+        c.attr = 5
+        assert c.attr == 5
+        del c.attr
+        try:
+            c.attr
+        except AttributeError:
+            pass
+        else:
+            assert False
+        "#
+    );
+}
+
+#[pyclass]
+struct SetOnlyDescriptor {}
+
+#[pyproto]
+impl<'p> PyDescrProtocol<'p> for SetOnlyDescriptor {
+    fn __set__(&'p self, _instance: &'p PyAny, _value: i32) -> PyResult<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn descriptor_without_delete() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let descr = Py::new(py, SetOnlyDescriptor {}).unwrap();
+    py_run!(
+        py,
+        descr,
+        r#"
+        C = type("C", (), {"attr": descr})
+
+        c = C()
+        c.attr = 1
+        try:
+            del c.attr
+        except AttributeError:
+            pass
+        else:
+            assert False
+        "#
+    );
+}
+
+#[pyclass]
+struct ReentrantMapping {
+    values: Vec<(String, i32)>,
+    total: i32,
+}
+
+#[pymethods]
+impl ReentrantMapping {
+    fn read_with(&self, callback: &PyAny) -> PyResult<PyObject> {
+        callback.call0().map(Into::into)
+    }
+}
+
+#[pyproto]
+impl<'p> PyMappingProtocol<'p> for ReentrantMapping {
+    fn __getitem__(&'p self, key: String) -> PyResult<i32> {
+        self.values
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+            .ok_or_else(|| pyo3::exceptions::KeyError::py_err(key))
+    }
+
+    fn __setitem__(&'p mut self, key: String, value: i32) -> PyResult<()> {
+        self.values.push((key, value));
+        Ok(())
+    }
+}
+
+#[pyproto]
+impl<'p> PyNumberProtocol<'p> for ReentrantMapping {
+    fn __iadd__(&'p mut self, other: i32) -> PyResult<()> {
+        self.total += other;
+        Ok(())
+    }
+}
+
+#[test]
+fn pyproto_reentrant_borrows() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj = PyClassShell::new_ref(
+        py,
+        ReentrantMapping {
+            values: vec![("a".to_string(), 1)],
+            total: 0,
+        },
+    )
+    .unwrap();
+
+    // Slots taking `&self` can run while the object is borrowed immutably
+    py_run!(py, obj, "assert obj.read_with(lambda o=obj: o['a']) == 1");
+
+    // Slots taking `&mut self` can't
+    py_expect_exception!(
+        py,
+        obj,
+        "obj.read_with(lambda o=obj: o.__setitem__('b', 2))",
+        RuntimeError
+    );
+    py_expect_exception!(
+        py,
+        obj,
+        "obj.read_with(lambda o=obj: o.__iadd__(1))",
+        RuntimeError
+    );
+
+    py_run!(py, obj, "obj['b'] = 2; obj += 3; assert obj['b'] == 2");
+    assert_eq!(obj.borrow().total, 3);
+    assert_eq!(obj.borrow().values.len(), 2);
+}
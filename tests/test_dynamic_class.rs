@@ -0,0 +1,125 @@
+use pyo3::exceptions::ValueError;
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyCFunction, PyDict, PyTuple, PyType};
+use pyo3::{py_run, GILPool};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+
+#[test]
+fn class_with_closure_methods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let counted = calls.clone();
+    let scale = PyCFunction::new_method(py, "scale", "Scales the value", move |slf, args, _| {
+        counted.fetch_add(1, Ordering::SeqCst);
+        let value: f64 = slf.getattr("value")?.extract()?;
+        let factor: f64 = args.get_item(0).extract()?;
+        Ok(value * factor)
+    })
+    .unwrap();
+    let describe = PyCFunction::new_method(py, "describe", "", |slf, args, kwargs| {
+        let prefix = match kwargs.and_then(|kwargs| kwargs.get_item("prefix")) {
+            Some(prefix) => prefix.extract()?,
+            None => "value",
+        };
+        if !args.is_empty() {
+            return Err(ValueError::py_err(
+                "describe() takes no positional arguments",
+            ));
+        }
+        Ok(format!("{} {}", prefix, slf.getattr("value")?))
+    })
+    .unwrap();
+    let dict = PyDict::new(py);
+    dict.set_item("value", 2.0).unwrap();
+    dict.set_item("scale", scale).unwrap();
+    dict.set_item("describe", describe).unwrap();
+    let cls = PyType::new(py, "Measure", &[], Some(dict)).unwrap();
+    assert_eq!(cls.name(), "Measure");
+
+    py_run!(
+        py,
+        cls,
+        r#"
+        m = cls()
+        assert m.scale(3) == 6.0
+        m.value = 1.5
+        assert m.scale(2) == 3.0
+        assert cls.scale(m, 1) == 1.5
+        assert m.describe() == "value 1.5"
+        assert m.describe(prefix="length") == "length 1.5"
+        assert cls.scale.__doc__ == "Scales the value"
+        assert cls.describe.__doc__ is None
+
+        class Sub(cls):
+            value = 10
+        assert Sub().scale(2) == 20
+        "#
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+    py_expect_exception!(py, cls, "cls().describe(1)", ValueError);
+    py_expect_exception!(py, cls, "cls().scale('a')", TypeError);
+    py_expect_exception!(py, cls, "cls.scale()", TypeError);
+}
+
+#[test]
+fn bases() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let exception = py.get_type::<pyo3::exceptions::Exception>();
+    let dict = [("__module__", "plugins")].into_py_dict(py);
+    let cls = PyType::new(py, "PluginError", &[exception], Some(dict)).unwrap();
+    assert!(cls.is_subclass_of(exception).unwrap());
+    py_run!(
+        py,
+        cls,
+        r#"
+        assert cls.__module__ == "plugins"
+        try:
+            raise cls("failed")
+        except Exception as e:
+            assert str(e) == "failed"
+        "#
+    );
+
+    let without_bases = PyType::new(py, "Empty", &[], None).unwrap();
+    let bases: &PyTuple = without_bases
+        .getattr("__bases__")
+        .unwrap()
+        .downcast_ref()
+        .unwrap();
+    assert_eq!(bases.len(), 1);
+
+    let int = py.get_type::<pyo3::types::PyLong>();
+    let string = py.get_type::<pyo3::types::PyString>();
+    assert!(PyType::new(py, "Invalid", &[int, string], None).is_err());
+}
+
+#[test]
+fn closure_dropped_with_class() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let state = Arc::new(());
+
+    {
+        let _pool = GILPool::new(py);
+        let captured = state.clone();
+        let method = PyCFunction::new_method(py, "method", "", move |_, _, _| {
+            Ok(Arc::strong_count(&captured))
+        })
+        .unwrap();
+        let dict = [("method", method)].into_py_dict(py);
+        let cls = PyType::new(py, "Temporary", &[], Some(dict)).unwrap();
+        py_assert!(py, cls, "cls().method() == 2");
+    }
+    assert_eq!(Arc::strong_count(&state), 2);
+
+    // Classes are in reference cycles with their `__mro__`
+    py.run("import gc; gc.collect()", None, None).unwrap();
+    assert_eq!(Arc::strong_count(&state), 1);
+}
@@ -92,3 +92,109 @@ fn test_exception_nosegfault() {
     assert!(io_err().is_err());
     assert!(parse_int().is_err());
 }
+
+#[derive(Debug)]
+struct LookupError(String);
+
+impl Error for LookupError {}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no entry {}", self.0)
+    }
+}
+
+#[pyfunction]
+fn lookup(key: String) -> Result<u32, LookupError> {
+    Err(LookupError(key))
+}
+
+#[pyfunction]
+#[cfg(not(target_os = "windows"))]
+fn open_missing_file() -> std::io::Result<()> {
+    File::open("not_there.txt")?;
+    Ok(())
+}
+
+#[pyclass]
+struct Table {}
+
+#[pymethods]
+impl Table {
+    #[new]
+    fn new(fail: bool) -> Result<Self, LookupError> {
+        if fail {
+            Err(LookupError("table".to_string()))
+        } else {
+            Ok(Table {})
+        }
+    }
+
+    fn get(&self, key: String) -> Result<u32, LookupError> {
+        Err(LookupError(key))
+    }
+}
+
+#[test]
+fn test_error_table() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    pyo3::error_table::register(|err: LookupError| exceptions::KeyError::py_err(err.0));
+    let lookup = wrap_pyfunction!(lookup)(py);
+    let table = py.get_type::<Table>();
+
+    py_run!(
+        py,
+        lookup table,
+        r#"
+        try:
+            lookup("a")
+            raise AssertionError("didn't raise")
+        except KeyError as e:
+            assert e.args == ("a",)
+        try:
+            table(False).get("b")
+            raise AssertionError("didn't raise")
+        except KeyError as e:
+            assert e.args == ("b",)
+        try:
+            table(True)
+            raise AssertionError("didn't raise")
+        except KeyError as e:
+            assert e.args == ("table",)
+        "#
+    );
+
+    pyo3::error_table::unregister::<LookupError>();
+    py_run!(
+        py,
+        lookup,
+        r#"
+        try:
+            lookup("a")
+            raise AssertionError("didn't raise")
+        except RuntimeError as e:
+            assert str(e) == "no entry a"
+        "#
+    );
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn test_error_table_std_errors() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let open_missing_file = wrap_pyfunction!(open_missing_file)(py);
+
+    py_run!(
+        py,
+        open_missing_file,
+        r#"
+        try:
+            open_missing_file()
+            raise AssertionError("didn't raise")
+        except FileNotFoundError:
+            pass
+        "#
+    );
+}
@@ -0,0 +1,30 @@
+//! Finalizes the interpreter, so it runs in its own test binary.
+
+use pyo3::prelude::*;
+use pyo3::{ffi, is_interpreter_alive};
+use std::thread;
+
+#[test]
+fn test_drop_after_finalize() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    assert!(is_interpreter_alive());
+
+    let list: PyObject = vec![1, 2, 3].to_object(py);
+    let from_thread: PyObject = "text".to_object(py);
+    let (sender, receiver) = std::sync::mpsc::channel::<()>();
+    let daemon = thread::spawn(move || {
+        receiver.recv().unwrap();
+        assert!(!is_interpreter_alive());
+        drop(from_thread);
+    });
+
+    // Py_Finalize releases the GIL state, so the guard must not
+    std::mem::forget(gil);
+    unsafe { ffi::Py_Finalize() };
+    assert!(!is_interpreter_alive());
+
+    drop(list);
+    sender.send(()).unwrap();
+    daemon.join().unwrap();
+}
@@ -0,0 +1,133 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyAny, PyDict};
+
+mod common;
+
+#[derive(Debug, FromPyObject)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, FromPyObject)]
+struct Config {
+    #[pyo3(item)]
+    name: String,
+    #[pyo3(item("max-size"))]
+    max_size: usize,
+    #[pyo3(attribute("__len__"))]
+    len: PyObject,
+}
+
+#[derive(Debug, FromPyObject)]
+struct Wrapper<'a> {
+    #[pyo3(attribute)]
+    inner: &'a PyAny,
+}
+
+#[derive(Debug, FromPyObject)]
+struct Meters(f64);
+
+#[derive(Debug, FromPyObject)]
+struct Pair<T>(T, T);
+
+#[derive(Debug, FromPyObject)]
+enum Shape {
+    Circle { radius: f64 },
+    Square { side: f64 },
+    Sides(Vec<f64>),
+    Number(f64),
+}
+
+fn eval<'p>(py: Python<'p>, code: &str) -> &'p PyAny {
+    let locals = PyDict::new(py);
+    py.run(
+        r#"
+class Object:
+    def __init__(self, **kwargs):
+        self.__dict__.update(kwargs)
+"#,
+        None,
+        Some(locals),
+    )
+    .unwrap();
+    py.eval(code, None, Some(locals)).unwrap()
+}
+
+#[test]
+fn test_struct() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let point: Point = eval(py, "Object(x=1.5, y=-2)").extract().unwrap();
+    assert_eq!((point.x, point.y), (1.5, -2.0));
+    let err = eval(py, "Object(x=1.5)").extract::<Point>().unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::AttributeError>(py));
+
+    let config: Config = eval(py, "{'name': 'cache', 'max-size': 10}")
+        .extract()
+        .unwrap();
+    assert_eq!((config.name.as_str(), config.max_size), ("cache", 10));
+    let err = eval(py, "{'name': 'cache'}")
+        .extract::<Config>()
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::KeyError>(py));
+
+    let wrapper: Wrapper = eval(py, "Object(inner=[1, 2])").extract().unwrap();
+    assert_eq!(wrapper.inner.len().unwrap(), 2);
+}
+
+#[test]
+fn test_tuple_struct() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let meters: Meters = eval(py, "2.5").extract().unwrap();
+    assert_eq!(meters.0, 2.5);
+    let pair: Pair<String> = eval(py, "('a', 'b')").extract().unwrap();
+    assert_eq!((pair.0.as_str(), pair.1.as_str()), ("a", "b"));
+    assert!(eval(py, "('a', 'b', 'c')")
+        .extract::<Pair<String>>()
+        .is_err());
+}
+
+#[test]
+fn test_enum() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    match eval(py, "Object(radius=2)").extract().unwrap() {
+        Shape::Circle { radius } => assert_eq!(radius, 2.0),
+        shape => panic!("{:?}", shape),
+    }
+    // The variants are tried in order
+    match eval(py, "Object(side=3, radius=2)").extract().unwrap() {
+        Shape::Circle { radius } => assert_eq!(radius, 2.0),
+        shape => panic!("{:?}", shape),
+    }
+    match eval(py, "Object(side=3)").extract().unwrap() {
+        Shape::Square { side } => assert_eq!(side, 3.0),
+        shape => panic!("{:?}", shape),
+    }
+    match eval(py, "[1, 2, 3]").extract().unwrap() {
+        Shape::Sides(sides) => assert_eq!(sides, vec![1.0, 2.0, 3.0]),
+        shape => panic!("{:?}", shape),
+    }
+    match eval(py, "4").extract().unwrap() {
+        Shape::Number(number) => assert_eq!(number, 4.0),
+        shape => panic!("{:?}", shape),
+    }
+
+    let err = eval(py, "None").extract::<Shape>().unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+    let message: String = PyObject::from_py(err, py)
+        .as_ref(py)
+        .str()
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert!(message.starts_with(
+        "failed to extract enum Shape from 'NoneType' object\n- Circle: AttributeError:"
+    ));
+    assert!(message.contains("\n- Number: TypeError:"));
+}
@@ -3,7 +3,7 @@ use pyo3::class::PyTraverseError;
 use pyo3::class::PyVisit;
 use pyo3::prelude::*;
 use pyo3::types::{PyAny, PyTuple};
-use pyo3::{ffi, py_run, AsPyPointer, PyClassShell};
+use pyo3::{ffi, py_run, AsPyPointer, GILPool, PyClassShell};
 use std::cell::RefCell;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -16,26 +16,66 @@ struct ClassWithFreelist {}
 #[test]
 fn class_with_freelist() {
     let ptr;
+    let ptr2;
     {
         let gil = Python::acquire_gil();
         let py = gil.python();
 
         let inst = Py::new(py, ClassWithFreelist {}).unwrap();
-        let _inst2 = Py::new(py, ClassWithFreelist {}).unwrap();
+        let inst2 = Py::new(py, ClassWithFreelist {}).unwrap();
         ptr = inst.as_ptr();
+        ptr2 = inst2.as_ptr();
         drop(inst);
+        drop(inst2);
     }
 
     {
         let gil = Python::acquire_gil();
         let py = gil.python();
 
+        // The free list holds both instances and returns the last one first
         let inst3 = Py::new(py, ClassWithFreelist {}).unwrap();
-        assert_eq!(ptr, inst3.as_ptr());
+        assert_eq!(ptr2, inst3.as_ptr());
 
         let inst4 = Py::new(py, ClassWithFreelist {}).unwrap();
-        assert_ne!(ptr, inst4.as_ptr())
+        assert_eq!(ptr, inst4.as_ptr());
+
+        let inst5 = Py::new(py, ClassWithFreelist {}).unwrap();
+        assert_ne!(ptr, inst5.as_ptr());
+        assert_ne!(ptr2, inst5.as_ptr());
+    }
+}
+
+#[pyclass(freelist = 2, gc)]
+struct GCWithFreelist {}
+
+#[pyproto]
+impl PyGCProtocol for GCWithFreelist {
+    fn __traverse__(&self, _visit: PyVisit) -> Result<(), PyTraverseError> {
+        Ok(())
+    }
+    fn __clear__(&mut self) {}
+}
+
+#[test]
+fn gc_with_freelist() {
+    let ptr;
+    {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let inst = Py::new(py, GCWithFreelist {}).unwrap();
+        ptr = inst.as_ptr();
+        py_run!(py, inst, "import gc; assert gc.is_tracked(inst)");
     }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    // The instance in the free list must not be visible to the GC
+    py.run("import gc; gc.collect()", None, None).unwrap();
+
+    let inst = Py::new(py, GCWithFreelist {}).unwrap();
+    assert_eq!(ptr, inst.as_ptr());
+    py_run!(py, inst, "import gc; assert inst in gc.get_objects()");
 }
 
 struct TestDropCall {
@@ -153,7 +193,7 @@ fn gc_integration() {
     {
         let gil = Python::acquire_gil();
         let py = gil.python();
-        let inst = PyClassShell::new_mut(
+        let inst = PyClassShell::new_ref(
             py,
             GCIntegration {
                 self_ref: RefCell::new(py.None()),
@@ -164,7 +204,7 @@ fn gc_integration() {
         )
         .unwrap();
 
-        *inst.self_ref.borrow_mut() = inst.to_object(py);
+        *inst.borrow().self_ref.borrow_mut() = inst.to_object(py);
     }
 
     let gil = Python::acquire_gil();
@@ -192,6 +232,52 @@ fn gc_integration2() {
     py_run!(py, inst, "import gc; assert inst in gc.get_objects()");
 }
 
+#[pyclass(gc)]
+struct GCOptionalRef {
+    obj: Option<PyObject>,
+    dropped: TestDropCall,
+}
+
+#[pyproto]
+impl PyGCProtocol for GCOptionalRef {
+    fn __traverse__(&self, visit: PyVisit) -> Result<(), PyTraverseError> {
+        visit.call(&self.obj)
+    }
+
+    fn __clear__(&mut self) {
+        self.obj = None;
+    }
+}
+
+#[test]
+fn gc_optional_ref() {
+    let drop_called = Arc::new(AtomicBool::new(false));
+
+    {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let inst = PyClassShell::new_ref(
+            py,
+            GCOptionalRef {
+                obj: None,
+                dropped: TestDropCall {
+                    drop_called: Arc::clone(&drop_called),
+                },
+            },
+        )
+        .unwrap();
+        // Traversing the object with a `None` field must not crash
+        py.run("import gc; gc.collect()", None, None).unwrap();
+
+        inst.borrow_mut().obj = Some(inst.to_object(py));
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    py.run("import gc; gc.collect()", None, None).unwrap();
+    assert!(drop_called.load(Ordering::Relaxed));
+}
+
 #[pyclass(weakref)]
 struct WeakRefSupport {}
 
@@ -207,6 +293,94 @@ fn weakref_support() {
     );
 }
 
+#[allow(dead_code)]
+#[pyclass(dict)]
+struct DictSupport {
+    dropped: TestDropCall,
+}
+
+#[test]
+fn dict_is_released() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj: PyObject = PyTuple::new(py, &[1, 2]).into_py(py);
+    let cnt = obj.get_refcnt();
+    {
+        let _pool = GILPool::new(py);
+        let inst = PyClassShell::new_ref(
+            py,
+            DictSupport {
+                dropped: TestDropCall {
+                    drop_called: Arc::new(AtomicBool::new(false)),
+                },
+            },
+        )
+        .unwrap();
+        py_run!(py, inst obj, "inst.a = obj");
+        assert!(obj.get_refcnt() > cnt);
+    }
+    assert_eq!(obj.get_refcnt(), cnt);
+}
+
+#[test]
+fn dict_cycle_is_collected() {
+    let drop_called = Arc::new(AtomicBool::new(false));
+
+    {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let inst = PyClassShell::new_ref(
+            py,
+            DictSupport {
+                dropped: TestDropCall {
+                    drop_called: Arc::clone(&drop_called),
+                },
+            },
+        )
+        .unwrap();
+        py_run!(py, inst, "inst.me = inst");
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    py.run("import gc; gc.collect()", None, None).unwrap();
+    assert!(drop_called.load(Ordering::Relaxed));
+}
+
+#[pyclass(dict, weakref)]
+struct DictAndWeakRefSupport {}
+
+#[pymethods]
+impl DictAndWeakRefSupport {
+    #[new]
+    fn new() -> Self {
+        DictAndWeakRefSupport {}
+    }
+}
+
+#[test]
+fn dict_and_weakref_support() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let cls = py.get_type::<DictAndWeakRefSupport>();
+    py_run!(
+        py,
+        cls,
+        r#"
+        import weakref
+        called = []
+        inst = cls()
+        inst.a = 1
+        ref = weakref.ref(inst, called.append)
+        assert ref() is inst
+        assert inst.__dict__ == {'a': 1}
+        del inst
+        assert ref() is None
+        assert called == [ref]
+        "#
+    );
+}
+
 #[pyclass]
 struct BaseClassWithDrop {
     data: Option<Arc<AtomicBool>>,
@@ -264,12 +438,11 @@ fn inheritance_with_new_methods_with_drop() {
         let typeobj = py.get_type::<SubClassWithDrop>();
         let inst = typeobj.call((), None).unwrap();
 
-        let obj = SubClassWithDrop::try_from_mut(inst).unwrap();
-        obj.data = Some(Arc::clone(&drop_called1));
-
-        let base: &mut <SubClassWithDrop as pyo3::PyTypeInfo>::BaseType =
-            unsafe { py.mut_from_borrowed_ptr(inst.as_ptr()) };
-        base.data = Some(Arc::clone(&drop_called2));
+        let obj = inst
+            .downcast_ref::<PyClassShell<SubClassWithDrop>>()
+            .unwrap();
+        obj.borrow_mut().data = Some(Arc::clone(&drop_called1));
+        obj.get_super().borrow_mut().data = Some(Arc::clone(&drop_called2));
     }
 
     assert!(drop_called1.load(Ordering::Relaxed));
@@ -1,3 +1,4 @@
+use pyo3::exceptions::AttributeError;
 use pyo3::prelude::*;
 use pyo3::py_run;
 use pyo3::types::{IntoPyDict, PyList};
@@ -106,3 +107,84 @@ fn getter_setter_autogen() {
         "assert inst.text == 'Hello'; inst.text = 'There'; assert inst.text == 'There'"
     );
 }
+
+#[pyclass]
+struct Deletable {
+    value: Option<i32>,
+}
+
+#[pymethods]
+impl Deletable {
+    #[getter]
+    fn get_value(&self) -> PyResult<i32> {
+        self.value
+            .ok_or_else(|| AttributeError::py_err("value has been deleted"))
+    }
+
+    #[setter]
+    fn set_value(&mut self, value: i32) {
+        self.value = Some(value);
+    }
+
+    #[deleter]
+    fn del_value(&mut self) -> PyResult<()> {
+        match self.value.take() {
+            Some(_) => Ok(()),
+            None => Err(AttributeError::py_err("value has been deleted")),
+        }
+    }
+
+    #[getter(count)]
+    fn get_count(&self) -> i32 {
+        self.value.unwrap_or(0)
+    }
+
+    #[deleter(count)]
+    fn reset_count(&mut self) {
+        self.value = Some(0);
+    }
+}
+
+#[test]
+fn deleter() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let inst = Py::new(py, Deletable { value: Some(1) }).unwrap();
+    py_run!(py, inst, "assert inst.value == 1; del inst.value");
+    py_run!(py, inst, "assert not hasattr(inst, 'value')");
+    py_expect_exception!(py, inst, "inst.value", AttributeError);
+    py_expect_exception!(py, inst, "del inst.value", AttributeError);
+    py_run!(
+        py,
+        inst,
+        "inst.value = 2; assert inst.value == inst.count == 2"
+    );
+
+    // A property with a deleter but without a setter
+    py_run!(
+        py,
+        inst,
+        "del inst.count; assert inst.value == inst.count == 0"
+    );
+    py_expect_exception!(py, inst, "inst.count = 3", AttributeError);
+}
+
+#[test]
+fn delete_without_deleter() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let inst = Py::new(py, ClassWithProperties { num: 10 }).unwrap();
+    py_expect_exception!(py, inst, "del inst.DATA", AttributeError);
+    let inst = Py::new(
+        py,
+        GetterSetter {
+            num: 10,
+            text: "Hello".to_string(),
+        },
+    )
+    .unwrap();
+    py_expect_exception!(py, inst, "del inst.num", AttributeError);
+    py_run!(py, inst, "assert inst.num == 10");
+}
@@ -0,0 +1,73 @@
+use pyo3::exceptions::{ImportError, RuntimeError};
+use pyo3::prelude::*;
+use pyo3::{register_initializer, wrap_pymodule};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+static FAILURES: AtomicUsize = AtomicUsize::new(0);
+static IMPORTS: AtomicUsize = AtomicUsize::new(0);
+
+fn count_calls(_py: Python) -> PyResult<()> {
+    CALLS.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+fn fail_once(_py: Python) -> PyResult<()> {
+    if FAILURES.fetch_add(1, Ordering::SeqCst) == 0 {
+        Err(RuntimeError::py_err("initialization failed"))
+    } else {
+        Ok(())
+    }
+}
+
+fn import_module(py: Python) -> PyResult<()> {
+    IMPORTS.fetch_add(1, Ordering::SeqCst);
+    wrap_pymodule!(first)(py);
+
+    // Another thread can't import a module before the hooks are done
+    let raised_import_error = py.allow_threads(|| {
+        std::thread::spawn(|| {
+            let gil = Python::acquire_gil();
+            let py = gil.python();
+            let result = unsafe { PyObject::from_owned_ptr_or_err(py, PyInit_first()) };
+            result.err().unwrap().is_instance::<ImportError>(py)
+        })
+        .join()
+        .unwrap()
+    });
+    assert!(raised_import_error);
+    Ok(())
+}
+
+register_initializer!(count_calls);
+register_initializer!(fail_once);
+register_initializer!(import_module);
+
+#[pymodule]
+fn first(_py: Python, _m: &PyModule) -> PyResult<()> {
+    Ok(())
+}
+
+#[pymodule]
+fn second(_py: Python, _m: &PyModule) -> PyResult<()> {
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[test]
+fn test_initializer_runs_once() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let result = unsafe { PyObject::from_owned_ptr_or_err(py, PyInit_second()) };
+    assert!(result.err().unwrap().is_instance::<RuntimeError>(py));
+
+    // Only the failed hook and the ones after it run again
+    wrap_pymodule!(first)(py);
+    wrap_pymodule!(second)(py);
+    wrap_pymodule!(first)(py);
+
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    assert_eq!(FAILURES.load(Ordering::SeqCst), 2);
+    assert_eq!(IMPORTS.load(Ordering::SeqCst), 1);
+}
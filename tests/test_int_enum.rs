@@ -0,0 +1,184 @@
+use pyo3::int_enum::IntEnum;
+use pyo3::prelude::*;
+use pyo3::py_run;
+use pyo3::types::IntoPyDict;
+use pyo3::wrap_pyfunction;
+use std::convert::TryFrom;
+
+mod common;
+
+#[derive(IntEnum, Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+enum Level {
+    Low,
+    Medium = 5,
+    High,
+}
+
+#[derive(IntEnum, Clone, Copy, Debug, PartialEq)]
+#[repr(i32)]
+enum Sign {
+    Negative = -1,
+    Zero = 0,
+    Positive = 1,
+}
+
+#[pyfunction]
+fn raise_level(level: Level) -> Level {
+    match level {
+        Level::Low => Level::Medium,
+        _ => Level::High,
+    }
+}
+
+#[test]
+fn int_enum_variants() {
+    assert_eq!(Level::NAME, "Level");
+    assert_eq!(
+        Level::VARIANTS,
+        &[
+            ("Low", 0, Level::Low),
+            ("Medium", 5, Level::Medium),
+            ("High", 6, Level::High),
+        ]
+    );
+    assert_eq!(Level::try_from(6), Ok(Level::High));
+    assert_eq!(Sign::try_from(-1), Ok(Sign::Negative));
+
+    let err = Level::try_from(1).unwrap_err();
+    assert_eq!(err.value(), 1);
+    assert_eq!(
+        err.to_string(),
+        "1 is not a valid Level, expected one of 0 (Low), 5 (Medium), 6 (High)"
+    );
+}
+
+#[test]
+fn int_enum_conversions() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let f = wrap_pyfunction!(raise_level)(py);
+    let locals = [("f", f)].into_py_dict(py);
+    py.run(
+        r#"
+import enum
+
+class Level(enum.Enum):
+    LOW = 0
+    MEDIUM = 5
+
+class IntLevel(enum.IntEnum):
+    MEDIUM = 5
+
+assert f(0) == 5
+assert f(Level.LOW) == 5
+assert f(Level.MEDIUM) == 6
+assert f(IntLevel.MEDIUM) == 6
+assert f(False) == 5
+"#,
+        None,
+        Some(locals),
+    )
+    .unwrap();
+
+    let err = py.eval("f(3)", None, Some(locals)).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+    // Out of the range of the `#[repr]` type
+    let err = py.eval("f(1000)", None, Some(locals)).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+    let err = py.eval("f('0')", None, Some(locals)).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+
+    let sign: Sign = py.eval("-1", None, None).unwrap().extract().unwrap();
+    assert_eq!(sign, Sign::Negative);
+    assert_eq!(Sign::Negative.to_object(py).extract::<i32>(py).unwrap(), -1);
+}
+
+/// Documentation of the class
+#[pyclass]
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Permission {
+    Read = 4,
+    Write = 2,
+    Execute = 1,
+}
+
+#[pyfunction]
+fn is_writable(permission: Permission) -> bool {
+    permission == Permission::Write
+}
+
+#[pyfunction]
+fn strongest() -> Permission {
+    Permission::Read
+}
+
+#[test]
+fn pyclass_enum_attributes() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let cls = py.get_type::<Permission>();
+    py_run!(
+        py,
+        cls,
+        r#"
+        assert cls.__doc__ == "Documentation of the class"
+        assert isinstance(cls.Read, cls)
+        assert repr(cls.Write) == "Permission.Write"
+        assert int(cls.Read) == 4
+        assert [1, 2, 3, 4, 5][cls.Execute] == 2
+        assert cls.Write == 2 and 2 == cls.Write
+        assert cls.Write != cls.Read and cls.Write != 4
+        assert cls.Execute < cls.Write < 3
+        assert cls.Write != "Write"
+        assert hash(cls.Write) == hash(2)
+        assert {2: "w"}[cls.Write] == "w"
+        "#
+    );
+    py_expect_exception!(py, cls, "cls.Read < 'a'", TypeError);
+}
+
+#[test]
+fn pyclass_enum_conversions() {
+    assert_eq!(<Permission as IntEnum>::NAME, "Permission");
+    assert_eq!(
+        pyo3::int_enum::from_value::<Permission>(2),
+        Ok(Permission::Write)
+    );
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let locals = [
+        ("is_writable", wrap_pyfunction!(is_writable)(py)),
+        ("strongest", wrap_pyfunction!(strongest)(py)),
+        ("Permission", py.get_type::<Permission>().into()),
+    ]
+    .into_py_dict(py);
+    py.run(
+        r#"
+import enum
+
+class Mode(enum.Enum):
+    WRITE = 2
+
+assert is_writable(Permission.Write)
+assert not is_writable(Permission.Read)
+assert is_writable(2)
+assert is_writable(Mode.WRITE)
+assert strongest() == Permission.Read
+assert isinstance(strongest(), Permission)
+"#,
+        None,
+        Some(locals),
+    )
+    .unwrap();
+
+    let err = py.eval("is_writable(3)", None, Some(locals)).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+
+    let read: Permission = py.eval("4", None, None).unwrap().extract().unwrap();
+    assert_eq!(read, Permission::Read);
+    let obj: PyObject = Permission::Execute.into_py(py);
+    assert_eq!(obj.extract::<Permission>(py).unwrap(), Permission::Execute);
+    assert_eq!(obj.extract::<i32>(py).unwrap(), 1);
+}
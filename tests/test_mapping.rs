@@ -69,6 +69,18 @@ impl PyMappingProtocol for Mapping {
     }
 }
 
+#[test]
+fn test_len() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let d = [("Mapping", py.get_type::<Mapping>())].into_py_dict(py);
+
+    let run = |code| py.run(code, None, Some(d)).unwrap();
+
+    run("m = Mapping(); assert len(m) == 0");
+    run("m = Mapping(['1', '2', '3']); assert len(m) == 3");
+}
+
 #[test]
 fn test_getitem() {
     let gil = Python::acquire_gil();
@@ -95,7 +107,7 @@ fn test_setitem() {
 
     run("m = Mapping(['1', '2', '3']); m['1'] = 4; assert m['1'] == 4");
     run("m = Mapping(['1', '2', '3']); m['0'] = 0; assert m['0'] == 0");
-    run("m = Mapping(['1', '2', '3']); len(m) == 4");
+    run("m = Mapping(['1', '2', '3']); m['0'] = 0; assert len(m) == 4");
     err("m = Mapping(['1', '2', '3']); m[0] = 'hello'");
     err("m = Mapping(['1', '2', '3']); m[0] = -1");
 }
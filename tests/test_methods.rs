@@ -1,7 +1,7 @@
 use pyo3::prelude::*;
 use pyo3::py_run;
-use pyo3::types::{IntoPyDict, PyDict, PyList, PySet, PyString, PyTuple, PyType};
-use pyo3::PyClassShell;
+use pyo3::types::{IntoPyDict, PyAny, PyDict, PyList, PySet, PyString, PyTuple, PyType};
+use pyo3::{AsPyPointer, PyClassShell};
 
 mod common;
 
@@ -23,8 +23,8 @@ fn instance_method() {
     let gil = Python::acquire_gil();
     let py = gil.python();
 
-    let obj = PyClassShell::new_mut(py, InstanceMethod { member: 42 }).unwrap();
-    assert_eq!(obj.method().unwrap(), 42);
+    let obj = PyClassShell::new_ref(py, InstanceMethod { member: 42 }).unwrap();
+    assert_eq!(obj.borrow().method().unwrap(), 42);
     let d = [("obj", obj)].into_py_dict(py);
     py.run("assert obj.method() == 42", None, Some(d)).unwrap();
     py.run("assert obj.method.__doc__ == 'Test method'", None, Some(d))
@@ -49,14 +49,84 @@ fn instance_method_with_args() {
     let gil = Python::acquire_gil();
     let py = gil.python();
 
-    let obj = PyClassShell::new_mut(py, InstanceMethodWithArgs { member: 7 }).unwrap();
-    assert_eq!(obj.method(6).unwrap(), 42);
+    let obj = PyClassShell::new_ref(py, InstanceMethodWithArgs { member: 7 }).unwrap();
+    assert_eq!(obj.borrow().method(6).unwrap(), 42);
     let d = [("obj", obj)].into_py_dict(py);
     py.run("assert obj.method(3) == 21", None, Some(d)).unwrap();
     py.run("assert obj.method(multiplier=6) == 42", None, Some(d))
         .unwrap();
 }
 
+#[test]
+fn resolved_method() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let append = py.get_type::<PyList>().resolve_method("append").unwrap();
+    let list: &PyAny = PyList::empty(py).as_ref();
+    append.call1(list, (1,)).unwrap();
+    append.call1(list, (2,)).unwrap();
+    assert_eq!(list.extract::<Vec<i32>>().unwrap(), vec![1, 2]);
+    // Wrong number of arguments raise like a normal call
+    assert!(append.call0(list).is_err());
+
+    // `list.insert` and `list.pop` are `METH_FASTCALL` functions
+    let insert = py.get_type::<PyList>().resolve_method("insert").unwrap();
+    insert.call1(list, (0, 0)).unwrap();
+    let pop = py.get_type::<PyList>().resolve_method("pop").unwrap();
+    assert_eq!(pop.call0(list).unwrap().extract::<i32>().unwrap(), 2);
+    assert_eq!(pop.call1(list, (0,)).unwrap().extract::<i32>().unwrap(), 0);
+    assert!(insert.call1(list, (0,)).is_err());
+    assert_eq!(list.extract::<Vec<i32>>().unwrap(), vec![1]);
+
+    let keys = py.get_type::<PyDict>().resolve_method("keys").unwrap();
+    let dict: &PyAny = [("a", 1)].into_py_dict(py).as_ref();
+    let keys: Vec<String> = keys
+        .call0(dict)
+        .unwrap()
+        .iter()
+        .unwrap()
+        .map(|k| k.unwrap().extract().unwrap())
+        .collect();
+    assert_eq!(keys, vec!["a"]);
+
+    let method = py
+        .get_type::<InstanceMethodWithArgs>()
+        .resolve_method("method")
+        .unwrap();
+    let obj = PyClassShell::new_ref(py, InstanceMethodWithArgs { member: 7 })
+        .unwrap()
+        .to_object(py);
+    let kwargs = [("multiplier", 2)].into_py_dict(py);
+    let result: i32 = method
+        .call(obj.as_ref(py), (), Some(kwargs))
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert_eq!(result, 14);
+
+    // Methods defined in Python are looked up on the instance
+    let cls = py
+        .eval(
+            "type('Py', (), {'double': lambda self, x: 2 * x})",
+            None,
+            None,
+        )
+        .unwrap()
+        .downcast_ref::<PyType>()
+        .unwrap();
+    let double = cls.resolve_method("double").unwrap();
+    assert!(!double.is_direct());
+    let result: i32 = double
+        .call1(cls.call0().unwrap(), (21,))
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert_eq!(result, 42);
+
+    assert!(cls.resolve_method("missing").is_err());
+}
+
 #[pyclass]
 struct ClassMethod {}
 
@@ -327,6 +397,19 @@ fn meth_args() {
     py_expect_exception!(py, inst, "inst.get_pos_kw(1,2)", TypeError);
 
     py_run!(py, inst, "assert inst.args_as_vec(1,2,3) == 6");
+
+    // Keyword names created at runtime aren't interned
+    py_run!(
+        py,
+        inst,
+        "assert inst.get_kwarg(**{''.join(['te', 'st']): 100}) == 100"
+    );
+    py_expect_exception!(
+        py,
+        inst,
+        "inst.get_default(5, **{''.join(['te', 'st']): 100})",
+        TypeError
+    );
 }
 
 #[pyclass]
@@ -402,3 +485,211 @@ fn method_with_lifetime() {
         "assert obj.set_to_list(set((1, 2, 3))) == [1, 2, 3]"
     );
 }
+
+#[pyclass]
+struct Reentrant {
+    #[pyo3(get, set)]
+    count: i32,
+}
+
+#[pymethods]
+impl Reentrant {
+    fn increment(&mut self) {
+        self.count += 1;
+    }
+
+    fn read_with(&self, callback: &PyAny) -> PyResult<PyObject> {
+        callback.call0().map(Into::into)
+    }
+
+    fn increment_with(&mut self, callback: &PyAny) -> PyResult<PyObject> {
+        self.count += 1;
+        callback.call0().map(Into::into)
+    }
+}
+
+#[test]
+fn reentrant_borrows() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj = PyClassShell::new_ref(py, Reentrant { count: 0 }).unwrap();
+
+    // Shared borrows can nest
+    py_run!(py, obj, "assert obj.read_with(lambda o=obj: o.count) == 0");
+    py_run!(
+        py,
+        obj,
+        "assert obj.read_with(lambda o=obj: o.read_with(lambda: 1)) == 1"
+    );
+
+    // A mutable borrow conflicts with any other borrow
+    py_expect_exception!(py, obj, "obj.read_with(obj.increment)", RuntimeError);
+    py_expect_exception!(
+        py,
+        obj,
+        "obj.increment_with(lambda o=obj: o.count)",
+        RuntimeError
+    );
+    py_expect_exception!(py, obj, "obj.increment_with(obj.increment)", RuntimeError);
+    py_expect_exception!(
+        py,
+        obj,
+        "obj.increment_with(lambda o=obj: setattr(o, 'count', 5))",
+        RuntimeError
+    );
+
+    // The borrows are released after the errors
+    py_run!(py, obj, "obj.increment(); obj.count = obj.count + 1");
+    assert_eq!(obj.borrow().count, 5);
+
+    let shared = obj.try_borrow().unwrap();
+    assert!(obj.try_borrow().is_ok());
+    assert!(obj.try_borrow_mut().is_err());
+    drop(shared);
+    let mut exclusive = obj.try_borrow_mut().unwrap();
+    exclusive.count = 0;
+    assert!(obj.try_borrow().is_err());
+    let err: PyErr = obj.try_borrow_mut().err().unwrap().into();
+    assert!(err.is_instance::<pyo3::exceptions::RuntimeError>(py));
+    drop(exclusive);
+    assert_eq!(obj.borrow().count, 0);
+}
+
+#[pyclass]
+struct CallingConventions {}
+
+#[pymethods]
+impl CallingConventions {
+    fn no_args(&self, py: Python) -> PyObject {
+        py.None()
+    }
+
+    fn __format__(&self, spec: &str) -> String {
+        spec.to_uppercase()
+    }
+
+    fn add(&self, a: i32, b: Option<i32>) -> i32 {
+        a + b.unwrap_or(0)
+    }
+
+    #[args(args = "*")]
+    fn count(&self, args: &PyTuple) -> usize {
+        args.len()
+    }
+
+    #[classmethod]
+    fn class_add(_cls: &PyType, a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    #[staticmethod]
+    fn static_add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn calling_conventions() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let inst = PyClassShell::new_ref(py, CallingConventions {})
+        .unwrap()
+        .to_object(py);
+
+    let flags = |name: &str| {
+        let method = inst.getattr(py, name).unwrap();
+        let flags = unsafe { pyo3::ffi::PyCFunction_GetFlags(method.as_ptr()) };
+        flags & !(pyo3::ffi::METH_CLASS | pyo3::ffi::METH_STATIC)
+    };
+    #[cfg(Py_3_7)]
+    let fast = pyo3::ffi::METH_FASTCALL | pyo3::ffi::METH_KEYWORDS;
+    #[cfg(not(Py_3_7))]
+    let fast = pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS;
+    assert_eq!(flags("no_args"), pyo3::ffi::METH_NOARGS);
+    assert_eq!(flags("__format__"), pyo3::ffi::METH_O);
+    assert_eq!(flags("add"), fast);
+    assert_eq!(flags("class_add"), fast);
+    assert_eq!(flags("static_add"), fast);
+    assert_eq!(
+        flags("count"),
+        pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS
+    );
+
+    py_run!(py, inst, "assert inst.no_args() is None");
+    py_run!(py, inst, "assert format(inst, 'abc') == 'ABC'");
+    // `METH_O` arguments are positional-only
+    py_run!(
+        py,
+        inst,
+        "assert inst.__format__.__text_signature__ == '($self, spec, /)'"
+    );
+    py_run!(py, inst, "assert inst.add(1) == 1");
+    py_run!(py, inst, "assert inst.add(1, 2) == 3");
+    py_run!(py, inst, "assert inst.add(b=2, a=1) == 3");
+    py_run!(py, inst, "assert inst.count(1, 2, 3) == 3");
+    py_run!(py, inst, "assert inst.class_add(1, b=2) == 3");
+    py_run!(py, inst, "assert inst.static_add(a=1, b=2) == 3");
+
+    py_expect_exception!(py, inst, "inst.no_args(1)", TypeError);
+    py_expect_exception!(py, inst, "inst.__format__(spec='abc')", TypeError);
+    py_expect_exception!(py, inst, "inst.add()", TypeError);
+    py_expect_exception!(py, inst, "inst.add(1, 2, 3)", TypeError);
+    py_expect_exception!(py, inst, "inst.add(1, a=2)", TypeError);
+    py_expect_exception!(py, inst, "inst.add(1, c=2)", TypeError);
+    py_expect_exception!(py, inst, "inst.add('1')", TypeError);
+    py_run!(
+        py,
+        inst,
+        r#"
+        try:
+            inst.add(1, c=2)
+        except TypeError as e:
+            assert str(e) == "CallingConventions.add() got an unexpected keyword argument: c", str(e)
+        "#
+    );
+}
+
+#[pyclass]
+struct Iterators {
+    words: Vec<String>,
+}
+
+#[pymethods]
+impl Iterators {
+    fn words(&self) -> impl Iterator<Item = String> {
+        self.words.clone().into_iter()
+    }
+
+    #[staticmethod]
+    fn countdown(start: i32) -> PyResult<impl ExactSizeIterator<Item = i32> + Send> {
+        if start < 0 {
+            return Err(PyErr::new::<pyo3::exceptions::ValueError, _>(
+                "negative start",
+            ));
+        }
+        Ok((0..start + 1).rev())
+    }
+}
+
+#[test]
+fn iterator_results() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let inst = PyClassShell::new_ref(
+        py,
+        Iterators {
+            words: vec!["a".to_string(), "b".to_string()],
+        },
+    )
+    .unwrap();
+
+    py_run!(py, inst, "assert not isinstance(inst.words(), list)");
+    py_run!(py, inst, "assert list(inst.words()) == ['a', 'b']");
+    py_run!(py, inst, "assert list(inst.countdown(2)) == [2, 1, 0]");
+    py_run!(
+        py,
+        inst,
+        "it = inst.countdown(10 ** 6); assert next(it) == 10 ** 6"
+    );
+    py_expect_exception!(py, inst, "inst.countdown(-1)", ValueError);
+}
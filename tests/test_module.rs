@@ -63,6 +63,7 @@ fn test_module_with_functions() {
     let run = |code| py.run(code, None, Some(d)).unwrap();
 
     run("assert module_with_functions.__doc__ == 'This module is implemented in Rust.'");
+    run("assert '__doc__' not in module_with_functions.__all__");
     run("assert module_with_functions.sum_as_string(1, 2) == '3'");
     run("assert module_with_functions.no_parameters() == 42");
     run("assert module_with_functions.foo == 'bar'");
@@ -71,8 +72,11 @@ fn test_module_with_functions() {
     run("assert module_with_functions.LocatedClass.__module__ == 'module'");
     run("assert module_with_functions.double(3) == 6");
     run("assert module_with_functions.double.__doc__ == 'Doubles the given value'");
+    run("assert module_with_functions.double.__module__ == 'module_with_functions'");
+    run("assert module_with_functions.sum_as_string.__module__ == 'module_with_functions'");
     run("assert module_with_functions.also_double(3) == 6");
     run("assert module_with_functions.also_double.__doc__ == 'Doubles the given value'");
+    run("assert 'python_version' in module_with_functions.__pyo3_build_info__()");
 }
 
 #[pymodule(other_name)]
@@ -95,6 +99,8 @@ fn test_module_renaming() {
         Some(d),
     )
     .unwrap();
+    py.run("assert different_name.__doc__ is None", None, Some(d))
+        .unwrap();
 }
 
 #[test]
@@ -124,6 +130,34 @@ fn test_module_from_code() {
     assert_eq!(ret_value, 3);
 }
 
+#[test]
+fn test_module_from_code_errors() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let err = PyModule::from_code(py, "def f(:\n    pass", "broken.py", "broken").unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::SyntaxError>(py));
+
+    let err = PyModule::from_code(py, "1 / 0", "failing.py", "failing").unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ZeroDivisionError>(py));
+}
+
+#[test]
+fn test_module_from_code_importable() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    PyModule::from_code(py, "VALUE = 5", "embedded.py", "embedded").unwrap();
+    let value: i32 = py
+        .import("embedded")
+        .unwrap()
+        .get("VALUE")
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert_eq!(value, 5);
+}
+
 #[pyfunction]
 fn r#move() -> usize {
     42
@@ -219,6 +253,71 @@ fn test_module_nesting() {
         supermodule,
         "supermodule.submodule.subfunction() == 'Subfunction'"
     );
+    py_assert!(
+        py,
+        supermodule,
+        "supermodule.submodule.subfunction.__module__ == 'submodule'"
+    );
+}
+
+#[test]
+fn test_module_nesting_importable() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let supermodule = wrap_pymodule!(supermodule)(py);
+    let supermodule: &PyModule = supermodule.extract(py).unwrap();
+    let submodule: &PyModule = supermodule.get("submodule").unwrap().extract().unwrap();
+
+    supermodule.add_to_sys_modules("supermodule").unwrap();
+    submodule
+        .add_to_sys_modules("supermodule.submodule")
+        .unwrap();
+    py.run(
+        "from supermodule.submodule import subfunction; assert subfunction() == 'Subfunction'",
+        None,
+        None,
+    )
+    .unwrap();
+
+    let sys_modules = py.import("sys").unwrap().get("modules").unwrap();
+    sys_modules.del_item("supermodule").unwrap();
+    sys_modules.del_item("supermodule.submodule").unwrap();
+}
+
+#[test]
+fn test_add_function() {
+    use pyo3::{wrap_pyfunction, wrap_pymodule};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let module = PyModule::new(py, "functions").unwrap();
+
+    module.add_function(wrap_pyfunction!(double)).unwrap();
+    py_assert!(py, module, "module.double(2) == 4");
+    py_assert!(py, module, "module.double.__module__ == 'functions'");
+    py_assert!(py, module, "'double' in module.__all__");
+
+    let err = module.add_function(wrap_pymodule!(submodule)).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+}
+
+#[test]
+fn test_module_dict_is_borrowed() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let module = PyModule::new(py, "borrowed").unwrap();
+    let ref_count = module.dict().get_refcnt();
+
+    {
+        let _pool = pyo3::GILPool::new(py);
+        for _ in 0..3 {
+            module.dict();
+        }
+    }
+    assert_eq!(module.dict().get_refcnt(), ref_count);
+    py_assert!(py, module, "module.__name__ == 'borrowed'");
 }
 
 // Test that argument parsing specification works for pyfunctions
@@ -252,3 +351,431 @@ fn test_vararg_module() {
     py_assert!(py, m, "m.int_vararg_fn() == [5, ()]");
     py_assert!(py, m, "m.int_vararg_fn(1, 2) == [1, (2,)]");
 }
+
+#[derive(Debug, Default)]
+struct ModuleState {
+    counter: std::cell::Cell<i32>,
+}
+
+#[pyfunction]
+fn increment_state(py: Python) -> PyResult<i32> {
+    let module = py.import("stateful_module")?;
+    let state = module.state::<ModuleState>()?;
+    state.counter.set(state.counter.get() + 1);
+    Ok(state.counter.get())
+}
+
+#[pymodule(stateful_module, state = ModuleState)]
+fn stateful(_py: Python, module: &PyModule) -> PyResult<()> {
+    use pyo3::wrap_pyfunction;
+
+    module.state::<ModuleState>()?.counter.set(10);
+    module.add_wrapped(wrap_pyfunction!(increment_state))?;
+    Ok(())
+}
+
+#[test]
+fn test_module_state() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let first = wrap_pymodule!(stateful_module)(py);
+    let second = wrap_pymodule!(stateful_module)(py);
+    let first: &PyModule = first.extract(py).unwrap();
+    let second: &PyModule = second.extract(py).unwrap();
+
+    first.state::<ModuleState>().unwrap().counter.set(1);
+    assert_eq!(second.state::<ModuleState>().unwrap().counter.get(), 10);
+
+    let sys_modules = py.import("sys").unwrap().get("modules").unwrap();
+    sys_modules.set_item("stateful_module", first).unwrap();
+    assert_eq!(
+        first
+            .call0("increment_state")
+            .unwrap()
+            .extract::<i32>()
+            .unwrap(),
+        2
+    );
+    assert_eq!(first.state::<ModuleState>().unwrap().counter.get(), 2);
+    sys_modules.del_item("stateful_module").unwrap();
+}
+
+#[test]
+fn test_module_state_wrong_type() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let stateful = wrap_pymodule!(stateful_module)(py);
+    let stateful: &PyModule = stateful.extract(py).unwrap();
+    assert!(stateful.state::<i32>().is_err());
+
+    let stateless = wrap_pymodule!(module_with_functions)(py);
+    let stateless: &PyModule = stateless.extract(py).unwrap();
+    assert!(stateless.state::<ModuleState>().is_err());
+
+    let python_module = PyModule::new(py, "plain").unwrap();
+    let err = python_module.state::<ModuleState>().unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+}
+
+static DROPPED_STATES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[derive(Default)]
+struct DropCountingState;
+
+impl Drop for DropCountingState {
+    fn drop(&mut self) {
+        DROPPED_STATES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[pymodule(state = DropCountingState)]
+fn drop_counting(_py: Python, _module: &PyModule) -> PyResult<()> {
+    Ok(())
+}
+
+#[test]
+fn test_module_state_dropped() {
+    use pyo3::wrap_pymodule;
+
+    {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let _module = wrap_pymodule!(drop_counting)(py);
+        assert_eq!(DROPPED_STATES.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+    assert_eq!(DROPPED_STATES.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+static LOADS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+static UNLOADS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+fn on_load(_py: Python, module: &PyModule) -> PyResult<()> {
+    // Runs after the module function
+    assert_eq!(module.getattr("answer")?.extract::<i32>()?, 42);
+    LOADS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    Ok(())
+}
+
+fn on_unload(_py: Python) {
+    UNLOADS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+}
+
+#[pymodule(multi_phase, state = ModuleState, on_load = on_load, on_unload = on_unload)]
+fn lifecycle_module(_py: Python, module: &PyModule) -> PyResult<()> {
+    let state = module.state::<ModuleState>()?;
+    state.counter.set(state.counter.get() + 1);
+    module.add("answer", 42)?;
+    Ok(())
+}
+
+#[test]
+fn test_module_lifecycle_hooks() {
+    use pyo3::{ffi, wrap_pymodule, AsPyPointer};
+    use std::sync::atomic::Ordering;
+
+    {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = wrap_pymodule!(lifecycle_module)(py);
+        assert_eq!(LOADS.load(Ordering::SeqCst), 1);
+
+        // Executing the module again runs the module function and `on_load` with the same state
+        let def = unsafe { ffi::PyModule_GetDef(module.as_ptr()) };
+        assert_eq!(unsafe { ffi::PyModule_ExecDef(module.as_ptr(), def) }, 0);
+        assert_eq!(LOADS.load(Ordering::SeqCst), 2);
+        let module: &PyModule = module.extract(py).unwrap();
+        assert_eq!(module.state::<ModuleState>().unwrap().counter.get(), 2);
+        assert_eq!(UNLOADS.load(Ordering::SeqCst), 0);
+    }
+    assert_eq!(UNLOADS.load(Ordering::SeqCst), 1);
+}
+
+/// This module is initialized in multiple phases.
+#[pymodule(multi_phase, state = ModuleState)]
+fn multi_phase_module(_py: Python, module: &PyModule) -> PyResult<()> {
+    module.state::<ModuleState>()?.counter.set(5);
+    module.add("answer", 42)?;
+    Ok(())
+}
+
+#[pymodule(multi_phase)]
+fn failing_multi_phase_module(_py: Python, _module: &PyModule) -> PyResult<()> {
+    Err(pyo3::exceptions::ValueError::py_err(
+        "initialization failed",
+    ))
+}
+
+#[test]
+fn test_module_multi_phase() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    // The definition is a static, so the init function returns a borrowed reference
+    let module_def = unsafe { PyObject::from_borrowed_ptr(py, PyInit_multi_phase_module()) };
+    py_assert!(py, module_def, "type(module_def).__name__ == 'moduledef'");
+
+    let first = wrap_pymodule!(multi_phase_module)(py);
+    let second = wrap_pymodule!(multi_phase_module)(py);
+    py_assert!(py, first, "first.__name__ == 'multi_phase_module'");
+    py_assert!(
+        py,
+        first,
+        "first.__doc__ == 'This module is initialized in multiple phases.'"
+    );
+    py_assert!(py, first, "first.answer == 42");
+
+    let first: &PyModule = first.extract(py).unwrap();
+    let second: &PyModule = second.extract(py).unwrap();
+    first.state::<ModuleState>().unwrap().counter.set(1);
+    assert_eq!(second.state::<ModuleState>().unwrap().counter.get(), 5);
+}
+
+#[test]
+fn test_module_multi_phase_error() {
+    use pyo3::{ffi, AsPyPointer};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let spec = py
+        .import("importlib.machinery")
+        .unwrap()
+        .call1("ModuleSpec", ("failing_multi_phase_module", py.None()))
+        .unwrap();
+    unsafe {
+        let def = PyInit_failing_multi_phase_module() as *mut ffi::PyModuleDef;
+        let module = PyObject::from_owned_ptr(py, ffi::PyModule_FromDefAndSpec(def, spec.as_ptr()));
+        assert_eq!(ffi::PyModule_ExecDef(module.as_ptr(), def), -1);
+    }
+    assert!(PyErr::fetch(py).is_instance::<pyo3::exceptions::ValueError>(py));
+}
+
+static LAZY_LOADS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[pymodule]
+fn lazy_module(_py: Python, m: &PyModule) -> PyResult<()> {
+    #[pyfn(m, "__getattr__")]
+    fn getattr(py: Python, name: &str) -> PyResult<PyObject> {
+        match name {
+            "expensive" => {
+                LAZY_LOADS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(vec![1, 2, 3].into_py(py))
+            }
+            _ => Err(pyo3::exceptions::AttributeError::py_err(format!(
+                "module 'lazy_module' has no attribute '{}'",
+                name
+            ))),
+        }
+    }
+
+    #[pyfn(m, "__dir__")]
+    fn dir() -> Vec<&'static str> {
+        vec!["eager", "expensive"]
+    }
+
+    m.add("eager", 1)?;
+    Ok(())
+}
+
+#[test]
+fn test_module_getattr_and_dir() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let lazy = wrap_pymodule!(lazy_module)(py);
+
+    assert_eq!(LAZY_LOADS.load(std::sync::atomic::Ordering::SeqCst), 0);
+    py_assert!(py, lazy, "lazy.eager == 1");
+    py_assert!(py, lazy, "lazy.expensive == [1, 2, 3]");
+    assert_eq!(LAZY_LOADS.load(std::sync::atomic::Ordering::SeqCst), 1);
+    py_assert!(py, lazy, "dir(lazy) == ['eager', 'expensive']");
+    py_assert!(py, lazy, "lazy.__all__ == ['eager']");
+    py_assert!(py, lazy, "lazy.__getattr__.__module__ == 'lazy_module'");
+    py_expect_exception!(py, lazy, "lazy.missing", AttributeError);
+}
+
+#[pyclass]
+struct LazyClass {}
+
+#[pymodule]
+fn lazy_classes(_py: Python, m: &PyModule) -> PyResult<()> {
+    #[pyfn(m, "__getattr__")]
+    fn getattr(name: &str) -> PyResult<String> {
+        if name.starts_with("dynamic_") {
+            Ok(name.to_string())
+        } else {
+            Err(pyo3::exceptions::AttributeError::py_err(name.to_string()))
+        }
+    }
+
+    m.add_class_lazy::<LazyClass>()?;
+    m.add_class::<AnonClass>()?;
+    Ok(())
+}
+
+#[test]
+fn test_add_class_lazy() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let module = wrap_pymodule!(lazy_classes)(py);
+
+    py_assert!(py, module, "'LazyClass' not in module.__dict__");
+    py_assert!(py, module, "'AnonClass' in module.__dict__");
+    py_assert!(py, module, "'LazyClass' in dir(module)");
+    py_assert!(py, module, "module.__all__ == ['LazyClass', 'AnonClass']");
+    py_assert!(py, module, "module.dynamic_value == 'dynamic_value'");
+    py_expect_exception!(py, module, "module.missing", AttributeError);
+
+    py_assert!(py, module, "module.LazyClass.__name__ == 'LazyClass'");
+    py_assert!(
+        py,
+        module,
+        "module.__dict__['LazyClass'] is module.LazyClass"
+    );
+    py_assert!(py, module, "dir(module).count('LazyClass') == 1");
+}
+
+#[test]
+fn test_add_class_lazy_keeps_module_dict() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let module = wrap_pymodule!(lazy_classes)(py);
+    let dict = module.cast_as::<PyModule>(py).unwrap().dict();
+    let ref_count = dict.get_refcnt();
+
+    // Each lookup falling back to the generated `__getattr__` reads the module dict
+    {
+        let _pool = pyo3::GILPool::new(py);
+        for _ in 0..10 {
+            py_assert!(py, module, "module.dynamic_value == 'dynamic_value'");
+        }
+    }
+    assert_eq!(dict.get_refcnt(), ref_count);
+}
+
+#[pymodule]
+fn constants(_py: Python, module: &PyModule) -> PyResult<()> {
+    #[pyconst]
+    const MAX_SIZE: usize = 1 << 20;
+    #[pyconst("VERSION")]
+    const VERSION_STRING: &str = "1.2";
+    #[pyconst(module)]
+    static PRIMES: [u32; 4] = [2, 3, 5, 7];
+    #[pyconst(module, "ENABLED")]
+    const ENABLED_FLAG: bool = true;
+    // Not added to the module
+    const INTERNAL: i32 = 1;
+
+    module.add("internal", INTERNAL)
+}
+
+#[test]
+fn test_module_constants() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let module = wrap_pymodule!(constants)(py);
+
+    py_assert!(py, module, "module.MAX_SIZE == 1048576");
+    py_assert!(py, module, "module.VERSION == '1.2'");
+    py_assert!(py, module, "module.PRIMES == [2, 3, 5, 7]");
+    py_assert!(py, module, "module.ENABLED is True");
+    py_assert!(py, module, "not hasattr(module, 'INTERNAL')");
+    py_assert!(
+        py,
+        module,
+        "module.__all__ == ['MAX_SIZE', 'VERSION', 'PRIMES', 'ENABLED', 'internal']"
+    );
+}
+
+#[pyfunction]
+fn tree_add(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+#[pyclass]
+struct TreeMatrix {}
+
+fn add_tree_constants(_py: Python, module: &PyModule) -> PyResult<()> {
+    module.add("ANSWER", 42)
+}
+
+#[pymodule]
+fn tree_package(_py: Python, module: &PyModule) -> PyResult<()> {
+    pyo3::module_tree!(module, {
+        "core" => {
+            doc "The core functions";
+            fn tree_add;
+            init add_tree_constants;
+            "linalg" => {
+                class TreeMatrix;
+            }
+        }
+        "io" => {}
+        use "core" => [tree_add, ANSWER];
+        use "core.linalg" => [TreeMatrix];
+    })
+}
+
+#[pymodule]
+fn tree_without_submodules(_py: Python, module: &PyModule) -> PyResult<()> {
+    pyo3::module_tree!(module, {
+        fn tree_add;
+    })
+}
+
+#[test]
+fn test_module_tree() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let package = wrap_pymodule!(tree_package)(py);
+    pyo3::py_run!(
+        py,
+        package,
+        r#"
+        import sys
+        assert package.core.__name__ == "tree_package.core"
+        assert package.core.__doc__ == "The core functions"
+        assert package.core.tree_add(1, 2) == 3
+        assert package.core.ANSWER == 42
+        assert package.core.linalg.__name__ == "tree_package.core.linalg"
+        assert package.core.linalg.TreeMatrix.__name__ == "TreeMatrix"
+        assert sys.modules["tree_package.core.linalg"] is package.core.linalg
+        assert sys.modules["tree_package.io"] is package.io
+
+        # Re-exports
+        assert package.tree_add is package.core.tree_add
+        assert package.TreeMatrix is package.core.linalg.TreeMatrix
+        assert package.ANSWER == 42
+        assert set(package.__all__) == {"core", "io", "tree_add", "ANSWER", "TreeMatrix"}
+    "#
+    );
+
+    let package: &PyModule = package.extract(py).unwrap();
+    package.add_to_sys_modules("tree_package").unwrap();
+    py.run(
+        "from tree_package.core.linalg import TreeMatrix; import tree_package.io",
+        None,
+        None,
+    )
+    .unwrap();
+
+    let module = wrap_pymodule!(tree_without_submodules)(py);
+    py_assert!(py, module, "module.tree_add(2, 2) == 4");
+}
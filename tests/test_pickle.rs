@@ -0,0 +1,127 @@
+#![cfg(feature = "pickle-serde")]
+
+use pyo3::pickle::Pickle;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::{py_run, PyClassShell};
+use serde::{Deserialize, Serialize};
+
+#[pyclass(module = "test_pickle")]
+#[derive(Serialize, Deserialize, Pickle)]
+struct Point {
+    #[pyo3(get)]
+    x: f64,
+    #[pyo3(get)]
+    y: f64,
+    labels: Vec<String>,
+}
+
+#[pymethods]
+impl Point {
+    fn labels(&self) -> Vec<String> {
+        self.labels.clone()
+    }
+}
+
+/// Pickled with hand-written methods, restored by `__new__` and `__setstate__`.
+#[pyclass(module = "test_pickle")]
+struct Counter {
+    #[pyo3(get)]
+    count: u32,
+}
+
+#[pymethods]
+impl Counter {
+    #[new]
+    fn new(count: u32) -> Self {
+        Counter { count }
+    }
+
+    fn __getnewargs__(&self) -> (u32,) {
+        (0,)
+    }
+
+    fn __getstate__(&self) -> u32 {
+        self.count
+    }
+
+    fn __setstate__(&mut self, count: u32) {
+        self.count = count;
+    }
+}
+
+fn test_module(py: Python) -> &PyModule {
+    let module = PyModule::new(py, "test_pickle").unwrap();
+    module.add_class::<Point>().unwrap();
+    module.add_class::<Counter>().unwrap();
+    py.import("sys")
+        .unwrap()
+        .getattr("modules")
+        .unwrap()
+        .set_item("test_pickle", module)
+        .unwrap();
+    module
+}
+
+#[test]
+fn test_derive_pickle() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    test_module(py);
+    let point = PyClassShell::new_ref(
+        py,
+        Point {
+            x: 1.5,
+            y: -2.0,
+            labels: vec!["a".to_string(), "b".to_string()],
+        },
+    )
+    .unwrap();
+    py_run!(
+        py,
+        point,
+        r#"
+        import copy, pickle
+        for protocol in range(pickle.HIGHEST_PROTOCOL + 1):
+            p = pickle.loads(pickle.dumps(point, protocol))
+            assert type(p) is type(point)
+            assert (p.x, p.y, p.labels()) == (1.5, -2.0, ["a", "b"])
+        p = copy.deepcopy(point)
+        assert p is not point and (p.x, p.y, p.labels()) == (1.5, -2.0, ["a", "b"])
+
+        assert point.__getstate__() == {"x": 1.5, "y": -2.0, "labels": ["a", "b"]}
+        p.__setstate__({"x": 3.0, "y": 4.0, "labels": []})
+        assert (p.x, p.y, p.labels()) == (3.0, 4.0, [])
+    "#
+    );
+
+    let globals = PyDict::new(py);
+    globals.set_item("point", point).unwrap();
+    let err = py
+        .run("point.__setstate__({})", Some(globals), None)
+        .unwrap_err();
+    let unpickling_error = py.import("pickle").unwrap().getattr("UnpicklingError");
+    assert!(err.matches(py, unpickling_error.unwrap()));
+    let err = py
+        .run("point.__setstate__(1)", Some(globals), None)
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+}
+
+#[test]
+fn test_pickle_methods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    test_module(py);
+    let counter = PyClassShell::new_ref(py, Counter { count: 7 }).unwrap();
+    py_run!(
+        py,
+        counter,
+        r#"
+        import copy, pickle
+        for protocol in range(2, pickle.HIGHEST_PROTOCOL + 1):
+            assert pickle.loads(pickle.dumps(counter, protocol)).count == 7
+        assert copy.deepcopy(counter).count == 7
+    "#
+    );
+}
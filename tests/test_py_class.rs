@@ -0,0 +1,126 @@
+use pyo3::exceptions::ValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyAny;
+use pyo3::{py_class, py_run, PyClassShell};
+
+mod common;
+
+py_class! {
+    /// A point on the plane
+    #[derive(Clone)]
+    struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    impl Point {
+        #[new]
+        fn new(x: f64, y: f64) -> PyResult<Self> {
+            if x.is_nan() || y.is_nan() {
+                return Err(ValueError::py_err("coordinates can't be NaN"));
+            }
+            Ok(Point { x, y })
+        }
+
+        /// Moves the point by the given offsets
+        fn shift(&mut self, dx: f64, dy: f64) {
+            self.x += dx;
+            self.y += dy;
+        }
+
+        fn coordinates(&self) -> (f64, f64) {
+            (self.x, self.y)
+        }
+
+        fn scaled(&self, factor: f64) -> PyResult<Point> {
+            if factor == 0.0 {
+                return Err(ValueError::py_err("factor can't be zero"));
+            }
+            Ok(Point {
+                x: self.x * factor,
+                y: self.y * factor,
+            })
+        }
+
+        fn call_with(&self, callback: &PyAny) -> PyResult<()> {
+            callback.call0()?;
+            Ok(())
+        }
+    }
+}
+
+py_class! {
+    struct Empty {}
+}
+
+#[test]
+fn py_class_methods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let point = py.get_type::<Point>();
+
+    py_run!(
+        py,
+        point,
+        r#"
+        p = point(1.0, 2.0)
+        assert p.coordinates() == (1.0, 2.0)
+        p.shift(1.0, dy=-1.0)
+        assert p.coordinates() == (2.0, 1.0)
+        assert p.scaled(3).coordinates() == (6.0, 3.0)
+        assert type(p.scaled(3)) is point
+        "#
+    );
+    py_expect_exception!(py, point, "point(1.0)", TypeError);
+    py_expect_exception!(py, point, "point(1.0, float('nan'))", ValueError);
+    py_expect_exception!(py, point, "point(1.0, 2.0).scaled(0)", ValueError);
+    py_expect_exception!(py, point, "point(1.0, 2.0).shift('a', 1.0)", TypeError);
+}
+
+#[test]
+fn py_class_docs() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let point = py.get_type::<Point>();
+    let empty = py.get_type::<Empty>();
+
+    py_assert!(py, point, "point.__name__ == 'Point'");
+    py_assert!(py, point, "point.__doc__ == ' A point on the plane'");
+    py_assert!(
+        py,
+        point,
+        "point.shift.__doc__ == ' Moves the point by the given offsets'"
+    );
+    py_assert!(py, empty, "empty.__doc__ is None");
+}
+
+#[test]
+fn py_class_borrows() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let point = PyClassShell::new_ref(py, Point { x: 0.0, y: 0.0 }).unwrap();
+
+    py_run!(py, point, "point.call_with(point.coordinates)");
+    py_expect_exception!(
+        py,
+        point,
+        "point.call_with(lambda p=point: p.shift(1.0, 1.0))",
+        RuntimeError
+    );
+    assert_eq!(point.borrow().x, 0.0);
+}
+
+#[test]
+fn py_class_conversions() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let obj: PyObject = Point { x: 1.0, y: 2.0 }.into_py(py);
+    let point: Point = obj.extract(py).unwrap();
+    assert_eq!((point.x, point.y), (1.0, 2.0));
+    let point: PyRef<Point> = obj.extract(py).unwrap();
+    assert_eq!((point.x, point.y), (1.0, 2.0));
+
+    let empty: PyObject = Empty {}.into_py(py);
+    assert!(empty.extract::<PyRef<Point>>(py).is_err());
+}
@@ -34,12 +34,12 @@ impl Reader {
         })
     }
     fn get_iter_and_reset(
-        slf: &mut PyClassShell<Self>,
+        slf: &PyClassShell<Self>,
         keys: Py<PyBytes>,
         py: Python,
     ) -> PyResult<Iter> {
-        let reader = Py::new(py, slf.clone())?;
-        slf.inner.clear();
+        let reader = Py::new(py, slf.try_borrow()?.clone())?;
+        slf.try_borrow_mut()?.inner.clear();
         Ok(Iter {
             reader,
             keys,
@@ -57,19 +57,21 @@ struct Iter {
 
 #[pyproto]
 impl PyIterProtocol for Iter {
-    fn __iter__(slf: &mut PyClassShell<Self>) -> PyResult<PyObject> {
+    fn __iter__(slf: &PyClassShell<Self>) -> PyResult<PyObject> {
         let py = unsafe { Python::assume_gil_acquired() };
         Ok(slf.to_object(py))
     }
 
-    fn __next__(slf: &mut PyClassShell<Self>) -> PyResult<Option<PyObject>> {
+    fn __next__(slf: &PyClassShell<Self>) -> PyResult<Option<PyObject>> {
         let py = unsafe { Python::assume_gil_acquired() };
+        let mut slf = slf.try_borrow_mut()?;
         let bytes = slf.keys.as_ref(py).as_bytes();
         match bytes.get(slf.idx) {
             Some(&b) => {
                 let res = slf
                     .reader
                     .as_ref(py)
+                    .try_borrow()?
                     .inner
                     .get(&b)
                     .map(|s| PyString::new(py, s).into());
@@ -119,5 +121,5 @@ fn test_nested_iter_reset() {
         reader,
         "list(reader.get_iter_and_reset(bytes([3, 5, 2]))) == ['c', 'e', 'b']"
     );
-    assert!(reader.inner.is_empty());
+    assert!(reader.borrow().inner.is_empty());
 }
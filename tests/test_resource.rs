@@ -0,0 +1,244 @@
+use pyo3::exceptions::IOError;
+use pyo3::prelude::*;
+use pyo3::py_run;
+use pyo3::resource::PyResource;
+use pyo3::types::PyList;
+use pyo3::{ffi, AsPyPointer, IntoPyPointer};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+#[pyclass(resource)]
+struct Connection {
+    closes: Arc<AtomicUsize>,
+    fail: bool,
+    closed: bool,
+}
+
+impl Connection {
+    fn new(closes: &Arc<AtomicUsize>, fail: bool) -> Self {
+        Connection {
+            closes: closes.clone(),
+            fail,
+            closed: false,
+        }
+    }
+}
+
+impl PyResource for Connection {
+    fn close(&mut self) -> PyResult<()> {
+        self.closed = true;
+        self.closes.fetch_add(1, Ordering::SeqCst);
+        if self.fail {
+            Err(IOError::py_err("connection reset"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn closed(&self) -> bool {
+        self.closed
+    }
+}
+
+#[test]
+fn close_and_closed() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let closes = Arc::new(AtomicUsize::new(0));
+
+    let conn = Py::new(py, Connection::new(&closes, false)).unwrap();
+    py_assert!(py, conn, "not conn.closed");
+    py_run!(py, conn, "conn.close(); conn.close()");
+    py_assert!(py, conn, "conn.closed");
+    assert_eq!(closes.load(Ordering::SeqCst), 1);
+
+    let conn = Py::new(py, Connection::new(&closes, true)).unwrap();
+    py_expect_exception!(py, conn, "conn.close()", IOError);
+    py_assert!(py, conn, "conn.closed");
+    py_run!(py, conn, "conn.close()");
+    assert_eq!(closes.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn context_manager() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let closes = Arc::new(AtomicUsize::new(0));
+
+    let conn = Py::new(py, Connection::new(&closes, false)).unwrap();
+    py_run!(
+        py,
+        conn,
+        r#"
+        with conn as c:
+            assert c is conn and not c.closed
+        assert conn.closed
+        "#
+    );
+    py_expect_exception!(py, conn, "with conn: pass", ValueError);
+
+    // Exceptions raised in the block aren't suppressed, and the object is closed anyway
+    let conn = Py::new(py, Connection::new(&closes, false)).unwrap();
+    py_expect_exception!(py, conn, "with conn: raise KeyError()", KeyError);
+    py_assert!(py, conn, "conn.closed");
+
+    let conn = Py::new(py, Connection::new(&closes, true)).unwrap();
+    py_expect_exception!(py, conn, "with conn: pass", IOError);
+    assert_eq!(closes.load(Ordering::SeqCst), 3);
+}
+
+/// A list holding the only reference to `conn`, so Python code can drop it by popping it
+fn only_reference(py: Python, conn: Connection) -> &PyList {
+    let list = PyList::new(py, &[py.None()]);
+    let conn = Py::new(py, conn).unwrap();
+    unsafe { ffi::PyList_SetItem(list.as_ptr(), 0, conn.into_ptr()) };
+    list
+}
+
+#[test]
+fn unclosed_warning() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let closes = Arc::new(AtomicUsize::new(0));
+
+    let conns = only_reference(py, Connection::new(&closes, false));
+    py_run!(
+        py,
+        conns,
+        r#"
+        import warnings
+        with warnings.catch_warnings(record=True) as caught:
+            warnings.simplefilter("always")
+            conns.pop()
+        assert len(caught) == 1, caught
+        assert caught[0].category is ResourceWarning
+        assert str(caught[0].message) == "unclosed Connection object"
+        "#
+    );
+    assert_eq!(closes.load(Ordering::SeqCst), 1);
+
+    // A closed object doesn't warn
+    let conns = only_reference(py, Connection::new(&closes, false));
+    py_run!(
+        py,
+        conns,
+        r#"
+        import warnings
+        conns[0].close()
+        with warnings.catch_warnings(record=True) as caught:
+            warnings.simplefilter("always")
+            conns.pop()
+        assert caught == []
+        "#
+    );
+    assert_eq!(closes.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn unclosed_error_is_unraisable() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let closes = Arc::new(AtomicUsize::new(0));
+
+    let conns = only_reference(py, Connection::new(&closes, true));
+    py_run!(
+        py,
+        conns,
+        r#"
+        import sys, warnings
+        unraisable = []
+        hook = sys.unraisablehook
+        sys.unraisablehook = unraisable.append
+        try:
+            with warnings.catch_warnings():
+                warnings.simplefilter("ignore")
+                conns.pop()
+        finally:
+            sys.unraisablehook = hook
+        assert len(unraisable) == 1
+        assert isinstance(unraisable[0].exc_value, OSError)
+        "#
+    );
+    assert_eq!(closes.load(Ordering::SeqCst), 1);
+}
+
+#[pyclass(resource)]
+struct Panicking {
+    closed: bool,
+}
+
+impl PyResource for Panicking {
+    fn close(&mut self) -> PyResult<()> {
+        self.closed = true;
+        panic!("close failed");
+    }
+
+    fn closed(&self) -> bool {
+        self.closed
+    }
+}
+
+#[test]
+fn panic_in_close() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let res = Py::new(py, Panicking { closed: false }).unwrap();
+    py_expect_exception!(py, res, "res.close()", RuntimeError);
+    py_assert!(py, res, "res.closed");
+}
+
+#[pyclass(resource, freelist = 2)]
+struct Pooled {
+    events: Arc<Mutex<Vec<&'static str>>>,
+    closed: bool,
+}
+
+impl PyResource for Pooled {
+    fn close(&mut self) -> PyResult<()> {
+        self.closed = true;
+        self.events.lock().unwrap().push("close");
+        Ok(())
+    }
+
+    fn closed(&self) -> bool {
+        self.closed
+    }
+}
+
+impl Drop for Pooled {
+    fn drop(&mut self) {
+        self.events.lock().unwrap().push("drop");
+    }
+}
+
+#[test]
+fn unclosed_with_free_list() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    // The finalizer runs before the object is dropped and put into the free list
+    for _ in 0..3 {
+        let pooled = Pooled {
+            events: events.clone(),
+            closed: false,
+        };
+        let list = PyList::new(py, &[py.None()]);
+        let pooled = Py::new(py, pooled).unwrap();
+        unsafe { ffi::PyList_SetItem(list.as_ptr(), 0, pooled.into_ptr()) };
+        py_run!(
+            py,
+            list,
+            r#"
+            import warnings
+            with warnings.catch_warnings():
+                warnings.simplefilter("ignore")
+                list.pop()
+            "#
+        );
+    }
+    assert_eq!(*events.lock().unwrap(), ["close", "drop"].repeat(3));
+}
@@ -0,0 +1,23 @@
+// Linking serde_json adds `PartialEq` impls which make the inferred `extract()`s of other tests
+// ambiguous, so this is the only test using it.
+#![cfg(feature = "serde_json")]
+
+use pyo3::prelude::*;
+use pyo3::py_run;
+use pyo3::serde::{from_object, to_object};
+
+#[test]
+fn test_json_value() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let value: serde_json::Value =
+        serde_json::from_str(r#"{"a": [1, 2.5, "x", null, true], "b": {"c": -1}}"#).unwrap();
+    let obj = to_object(py, &value).unwrap();
+    py_run!(
+        py,
+        obj,
+        "assert obj == {'a': [1, 2.5, 'x', None, True], 'b': {'c': -1}}"
+    );
+    let back: serde_json::Value = from_object(obj.as_ref(py)).unwrap();
+    assert_eq!(back, value);
+}
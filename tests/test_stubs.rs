@@ -0,0 +1,163 @@
+#![cfg(feature = "stubs")]
+
+use pyo3::create_exception;
+use pyo3::exceptions::Exception;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyType};
+use pyo3::{py_run, wrap_pyfunction, wrap_pymodule};
+use std::collections::HashMap;
+
+mod common;
+
+create_exception!(shapes, ShapeError, Exception);
+
+/// A point on the plane
+#[pyclass]
+struct Point {
+    #[pyo3(get, set)]
+    x: f64,
+    #[pyo3(get)]
+    y: f64,
+}
+
+#[pymethods]
+impl Point {
+    #[classattr]
+    const DIMENSIONS: usize = 2;
+
+    #[new]
+    fn new(x: f64, y: f64) -> Self {
+        Point { x, y }
+    }
+
+    /// The distance to `other`, or to the origin
+    fn distance(&self, other: Option<PyRef<Point>>) -> f64 {
+        let (x, y) = other.map_or((0.0, 0.0), |o| (o.x, o.y));
+        ((self.x - x).powi(2) + (self.y - y).powi(2)).sqrt()
+    }
+
+    #[classmethod]
+    fn origin(_cls: &PyType) -> Self {
+        Point { x: 0.0, y: 0.0 }
+    }
+
+    #[staticmethod]
+    fn parse(text: &str) -> PyResult<Point> {
+        let _ = text;
+        Err(ShapeError::py_err("not implemented"))
+    }
+
+    #[getter]
+    fn norm(&self) -> f64 {
+        self.distance(None)
+    }
+
+    /// Moves the point onto the x axis
+    #[deleter]
+    fn del_y(&mut self) {
+        self.y = 0.0;
+    }
+}
+
+/// Finds the points closest to `target`
+#[pyfunction(limit = 10, "*", verbose = false)]
+fn closest(
+    target: PyRef<Point>,
+    points: Vec<PyRef<Point>>,
+    limit: usize,
+    verbose: bool,
+) -> Vec<(usize, f64)> {
+    let _ = (target, verbose);
+    points
+        .iter()
+        .take(limit)
+        .enumerate()
+        .map(|(i, p)| (i, p.x))
+        .collect()
+}
+
+#[pyfunction(kwargs = "**")]
+fn tally(words: Vec<String>, kwargs: Option<&PyDict>) -> HashMap<String, u32> {
+    let _ = kwargs;
+    let mut counts = HashMap::new();
+    for word in words {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Shapes implemented in Rust
+#[pymodule]
+fn shapes(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Point>()?;
+    m.add_wrapped(wrap_pyfunction!(closest))?;
+    m.add_wrapped(wrap_pyfunction!(tally))?;
+    m.add("ShapeError", py.get_type::<ShapeError>())?;
+    m.add("DIMENSIONS", 2)?;
+    m.add("size", py.eval("len", None, None)?)?;
+    m.add_wrapped(&pyo3::stubs::python_function)?;
+    Ok(())
+}
+
+const EXPECTED: &str = r#""""Shapes implemented in Rust"""
+
+from typing import Any, Dict, Iterator, List, Optional, Set, Tuple
+
+class Point:
+    """A point on the plane"""
+    DIMENSIONS: int
+    def __init__(self, x: float, y: float) -> None: ...
+    def distance(self, other: Optional[Point] = None) -> float:
+        """The distance to `other`, or to the origin"""
+    @property
+    def norm(self) -> float: ...
+    @classmethod
+    def origin(cls) -> Point: ...
+    @staticmethod
+    def parse(text: str) -> Point: ...
+    @property
+    def x(self) -> float: ...
+    @x.setter
+    def x(self, value: float) -> None: ...
+    @property
+    def y(self) -> float: ...
+    @y.deleter
+    def y(self) -> None: ...
+
+def closest(target: Point, points: List[Point], limit: int = 10, *, verbose: bool = False) -> List[Tuple[int, float]]:
+    """Finds the points closest to `target`"""
+
+def tally(words: List[str], **kwargs: Any) -> Dict[str, int]: ...
+
+class ShapeError(Exception): ...
+
+DIMENSIONS: int
+
+def size(obj, /) -> Any:
+    """Return the number of items in a container."""
+
+def generate_stub(module: Any) -> str:
+    """Returns the `.pyi` stub of the module."""
+"#;
+
+#[test]
+fn module_stub() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let module = wrap_pymodule!(shapes)(py);
+    let module: &PyModule = module.extract(py).unwrap();
+
+    let stub = pyo3::stubs::generate(module).unwrap();
+    assert_eq!(stub, EXPECTED);
+
+    py_run!(
+        py,
+        module stub,
+        r#"
+        import ast
+        ast.parse(stub)
+        assert module.generate_stub(module) == stub
+        "#
+    );
+    py_expect_exception!(py, module, "module.generate_stub(1)", TypeError);
+}
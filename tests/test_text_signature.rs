@@ -1,5 +1,7 @@
+use pyo3::exceptions::ValueError;
 use pyo3::prelude::*;
-use pyo3::{types::PyType, wrap_pyfunction, wrap_pymodule, PyClassShell};
+use pyo3::types::{PyCFunction, PyDict, PyTuple, PyType};
+use pyo3::{py_run, wrap_pyfunction, wrap_pymodule, AsPyPointer, PyClassShell};
 
 mod common;
 
@@ -109,6 +111,54 @@ fn test_function() {
     py_assert!(py, f, "f.__text_signature__ == '(a, b=None, *, c=42)'");
 }
 
+#[test]
+fn function_signature_from_arguments() {
+    /// Adds up the arguments
+    #[pyfunction(a, b = "None", "*", c = 42, d = "-1.5", e = "Vec::new()")]
+    fn documented(a: i32, b: Option<i32>, c: i32, d: f64, e: Vec<i32>) {
+        let _ = (a, b, c, d, e);
+    }
+
+    #[pyfunction(args = "*", kwargs = "**")]
+    fn variadic(py: Python, first: bool, args: &PyTuple, kwargs: Option<&PyDict>) {
+        let _ = (py, first, args, kwargs);
+    }
+
+    #[pyfunction]
+    fn optional_before_required(a: Option<i32>, b: i32) {
+        let _ = (a, b);
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let documented = wrap_pyfunction!(documented)(py);
+    let variadic = wrap_pyfunction!(variadic)(py);
+    let invalid = wrap_pyfunction!(optional_before_required)(py);
+
+    py_assert!(
+        py,
+        documented,
+        "documented.__text_signature__ == '(a, b=None, *, c=42, d=-1.5, e=...)'"
+    );
+    py_assert!(
+        py,
+        documented,
+        "documented.__doc__ == 'Adds up the arguments'"
+    );
+    py_run!(
+        py,
+        documented,
+        "import inspect; assert str(inspect.signature(documented)) == '(a, b=None, *, c=42, d=-1.5, e=Ellipsis)'"
+    );
+    py_assert!(
+        py,
+        variadic,
+        "variadic.__text_signature__ == '(first, *args, **kwargs)'"
+    );
+    py_assert!(py, variadic, "variadic.__doc__ is None");
+    py_assert!(py, invalid, "invalid.__text_signature__ is None");
+}
+
 #[test]
 fn test_pyfn() {
     #[pymodule]
@@ -184,3 +234,141 @@ fn test_methods() {
         "typeobj.static_method.__text_signature__ == '(d)'"
     );
 }
+
+#[test]
+fn method_signatures_from_arguments() {
+    #[pyclass]
+    struct MyClass {}
+
+    #[pymethods]
+    impl MyClass {
+        #[args(b = "None")]
+        fn method(&self, a: i32, b: Option<i32>) {
+            let _ = (a, b);
+        }
+        #[name = "renamed"]
+        fn pyself_method(_this: &PyClassShell<Self>, b: i32) {
+            let _ = b;
+        }
+        #[classmethod]
+        fn class_method(_cls: &PyType, c: i32) {
+            let _ = c;
+        }
+        #[staticmethod]
+        fn static_method(d: i32) {
+            let _ = d;
+        }
+        /// documented(e, /)
+        /// --
+        ///
+        /// Has a signature in its docstring
+        #[staticmethod]
+        fn documented(e: i32) {
+            let _ = e;
+        }
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let typeobj = py.get_type::<MyClass>();
+
+    py_assert!(
+        py,
+        typeobj,
+        "typeobj.method.__text_signature__ == '($self, a, b=None)'"
+    );
+    py_assert!(
+        py,
+        typeobj,
+        "typeobj.renamed.__text_signature__ == '($self, b)'"
+    );
+    py_assert!(
+        py,
+        typeobj,
+        "typeobj.class_method.__text_signature__ == '($cls, c)'"
+    );
+    py_assert!(
+        py,
+        typeobj,
+        "typeobj.static_method.__text_signature__ == '(d)'"
+    );
+    py_assert!(
+        py,
+        typeobj,
+        "typeobj.documented.__text_signature__ == '(e, /)'"
+    );
+    py_assert!(
+        py,
+        typeobj,
+        "typeobj.documented.__doc__ == 'Has a signature in its docstring'"
+    );
+    py_run!(
+        py,
+        typeobj,
+        "import inspect; assert str(inspect.signature(typeobj.method)) == '(self, /, a, b=None)'"
+    );
+}
+
+#[test]
+fn function_doc_and_signature_at_runtime() {
+    /// Documented
+    #[pyfunction]
+    fn documented(a: i32) -> i32 {
+        a
+    }
+
+    #[pyfunction]
+    fn undocumented() {}
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let first = wrap_pyfunction!(documented)(py);
+    let second = wrap_pyfunction!(documented)(py);
+    let f: &PyCFunction = first.extract(py).unwrap();
+    py_assert!(py, f, "f.__text_signature__ == '(a)'");
+    f.set_doc("Replaced").unwrap();
+    py_assert!(py, f, "f.__doc__ == 'Replaced'");
+    py_assert!(py, f, "f.__text_signature__ == '(a)'");
+    f.set_text_signature("(a, /)").unwrap();
+    py_assert!(py, f, "f.__doc__ == 'Replaced'");
+    py_assert!(py, f, "f.__text_signature__ == '(a, /)'");
+    py_assert!(py, f, "f(3) == 3");
+    py_run!(
+        py,
+        f,
+        "import inspect; assert str(inspect.signature(f)) == '(a, /)'"
+    );
+    // Functions created from the same definition keep their docs
+    py_assert!(py, second, "second.__doc__ == 'Documented'");
+    // Later changes reuse the method definition copied by the first one
+    let def = method_def(f);
+    f.set_doc("Replaced again").unwrap();
+    assert_eq!(method_def(f), def);
+    py_assert!(py, f, "f.__doc__ == 'Replaced again'");
+    py_assert!(py, f, "f.__text_signature__ == '(a, /)'");
+
+    let err = f.set_text_signature("a").unwrap_err();
+    assert!(err.is_instance::<ValueError>(py));
+    let err = f.set_doc("nul\0").unwrap_err();
+    assert!(err.is_instance::<ValueError>(py));
+
+    let f = wrap_pyfunction!(undocumented)(py);
+    let f: &PyCFunction = f.extract(py).unwrap();
+    f.set_text_signature("()").unwrap();
+    py_assert!(py, f, "f.__text_signature__ == '()'");
+    py_assert!(py, f, "f.__doc__ in (None, '')");
+
+    // Functions created from closures change their own method definition
+    let f = PyCFunction::new_closure(py, "closure", "Documented", |_, _| Ok(())).unwrap();
+    let def = method_def(f);
+    f.set_doc("Replaced").unwrap();
+    f.set_text_signature("()").unwrap();
+    assert_eq!(method_def(f), def);
+    py_assert!(py, f, "f.__doc__ == 'Replaced'");
+    py_assert!(py, f, "f.__text_signature__ == '()'");
+}
+
+fn method_def(f: &PyCFunction) -> *mut pyo3::ffi::PyMethodDef {
+    unsafe { (*(f.as_ptr() as *mut pyo3::ffi::PyCFunctionObject)).m_ml }
+}
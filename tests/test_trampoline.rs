@@ -0,0 +1,70 @@
+use pyo3::callback::{PyObjectCallbackConverter, UnitCallbackConverter};
+use pyo3::prelude::*;
+use pyo3::types::PyAny;
+use pyo3::{ffi, trampoline, AsPyPointer};
+use std::os::raw::c_int;
+
+trampoline! {
+    fn double(value: c_int) -> *mut ffi::PyObject;
+    converter = PyObjectCallbackConverter;
+    |_py| Ok(value * 2)
+}
+
+trampoline! {
+    fn call_with_value(callback: *mut ffi::PyObject, value: c_int) -> c_int;
+    converter = UnitCallbackConverter;
+    |py| {
+        let callback: &PyAny = unsafe { py.from_borrowed_ptr(callback) };
+        callback.call1((value,))?;
+        Ok(())
+    }
+}
+
+trampoline! {
+    fn panicking() -> c_int;
+    converter = UnitCallbackConverter;
+    |_py| { panic!("callback failed") }
+}
+
+#[test]
+fn test_trampoline_without_gil() {
+    // The trampoline acquires the GIL itself
+    let result = double(21);
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let result: PyObject = unsafe { PyObject::from_owned_ptr_or_err(py, result) }.unwrap();
+    assert_eq!(result.extract::<i32>(py).unwrap(), 42);
+}
+
+#[test]
+fn test_trampoline_error() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let values = py.eval("[]", None, None).unwrap();
+    let append = values.getattr("append").unwrap();
+    assert_eq!(call_with_value(append.as_ptr(), 1), 0);
+    assert_eq!(values.extract::<Vec<i32>>().unwrap(), vec![1]);
+
+    let raise = py.eval("lambda value: 1 / 0", None, None).unwrap();
+    assert_eq!(call_with_value(raise.as_ptr(), 1), -1);
+    let err = PyErr::fetch(py);
+    assert!(err.is_instance::<pyo3::exceptions::ZeroDivisionError>(py));
+}
+
+#[test]
+fn test_trampoline_panic() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    assert_eq!(panicking(), -1);
+    let err = PyErr::fetch(py);
+    assert!(err.is_instance::<pyo3::exceptions::RuntimeError>(py));
+    let msg: String = err
+        .to_object(py)
+        .call_method0(py, "__str__")
+        .unwrap()
+        .extract(py)
+        .unwrap();
+    assert!(msg.contains("callback failed"));
+    assert!(msg.contains("panicking"));
+}
@@ -1,7 +1,7 @@
 use pyo3::prelude::*;
 use pyo3::types::IntoPyDict;
 use pyo3::types::{PyDict, PyTuple};
-use pyo3::{py_run, wrap_pyfunction, AsPyRef, PyClassShell};
+use pyo3::{py_run, wrap_pyfunction, AsPyRef, PyClassShell, PyRefMut};
 
 mod common;
 
@@ -15,7 +15,7 @@ impl MutRefArg {
     fn get(&self) -> PyResult<i32> {
         Ok(self.n)
     }
-    fn set_other(&self, other: &mut MutRefArg) -> PyResult<()> {
+    fn set_other(&self, mut other: PyRefMut<MutRefArg>) -> PyResult<()> {
         other.n = 100;
         Ok(())
     }
@@ -31,7 +31,10 @@ fn mut_ref_arg() {
     let d = [("inst1", &inst1), ("inst2", &inst2)].into_py_dict(py);
 
     py.run("inst1.set_other(inst2)", None, Some(d)).unwrap();
-    assert_eq!(inst2.as_ref(py).n, 100);
+    assert_eq!(inst2.as_ref(py).borrow().n, 100);
+
+    // `self` is already borrowed
+    py_expect_exception!(py, inst1, "inst1.set_other(inst1)", RuntimeError);
 }
 
 #[pyclass]
@@ -141,7 +144,7 @@ fn add_module(py: Python, module: &PyModule) -> PyResult<()> {
         .dict()
         .get_item("modules")
         .unwrap()
-        .downcast_mut::<PyDict>()?
+        .downcast_ref::<PyDict>()?
         .set_item(module.name()?, module)
 }
 
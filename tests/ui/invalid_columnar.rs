@@ -0,0 +1,6 @@
+use pyo3::columnar::Columnar;
+
+#[derive(Columnar)]
+struct TupleStruct(i32, String);
+
+fn main() {}
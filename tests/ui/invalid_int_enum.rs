@@ -0,0 +1,14 @@
+use pyo3::int_enum::IntEnum;
+
+#[derive(IntEnum, Clone, Copy)]
+enum MissingRepr {
+    A,
+}
+
+#[derive(IntEnum, Clone, Copy)]
+#[repr(u8)]
+enum WithFields {
+    A(u8),
+}
+
+fn main() {}
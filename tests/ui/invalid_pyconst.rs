@@ -0,0 +1,10 @@
+use pyo3::prelude::*;
+
+#[pymodule]
+fn no_module_argument(_py: Python, _: &PyModule) -> PyResult<()> {
+    #[pyconst]
+    const ANSWER: i32 = 42;
+    Ok(())
+}
+
+fn main() {}
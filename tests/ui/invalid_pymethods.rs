@@ -0,0 +1,12 @@
+use pyo3::prelude::*;
+
+#[pyclass]
+struct MyClass {}
+
+#[pymethods]
+impl MyClass {
+    #[classattr]
+    fn class_attr_with_args(foo: i32) {}
+}
+
+fn main() {}